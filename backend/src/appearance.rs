@@ -0,0 +1,144 @@
+//! # Account Colors and Icons
+//! 
+//! Accounts can carry a label color and an icon so front-ends can tell
+//! entries apart at a glance. Colors are hex RGB values, stored as
+//! lowercase `#rrggbb`. Icons are either a short piece of text, usually
+//! an emoji, or an image kept inside the vault as a `data:image/...` URL.
+//! Remote image URLs are refused, so showing an icon never contacts a site.
+//! 
+//! With the opt-in `favicon` feature, `favicon::fetch_favicon` downloads
+//! a site's favicon so it can be stored as a data URL; nothing else in
+//! this module touches the network.
+
+#[cfg(feature = "favicon")]
+pub mod favicon;
+
+use crate::{PassManError, Result};
+
+/// Characters a text icon may have; enough for emoji sequences like flags and families
+pub const MAX_TEXT_ICON_CHARS: usize = 16;
+
+/// Length of a `data:` icon URL, about a 64 KiB image
+pub const MAX_DATA_URL_LEN: usize = 96 * 1024;
+
+/// Check a label color and put it in canonical form
+/// 
+/// # Arguments
+/// * `color` - `#rgb` or `#rrggbb`, with or without the `#`
+/// 
+/// # Returns
+/// The color as lowercase `#rrggbb`
+/// 
+/// # Errors
+/// Returns an error if the color isn't 3 or 6 hex digits
+pub fn normalize_color(color: &str) -> Result<String> {
+    let trimmed = color.trim();
+    let digits = trimmed.strip_prefix('#').unwrap_or(trimmed);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_color(color));
+    }
+    
+    let digits = digits.to_ascii_lowercase();
+    match digits.len() {
+        3 => Ok(digits.chars().fold(String::from("#"), |mut expanded, c| {
+            expanded.push(c);
+            expanded.push(c);
+            expanded
+        })),
+        6 => Ok(format!("#{}", digits)),
+        _ => Err(invalid_color(color)),
+    }
+}
+
+/// Check an icon
+/// 
+/// # Arguments
+/// * `icon` - A short text icon (e.g. an emoji), or a `data:image/` URL
+/// 
+/// # Returns
+/// The icon without surrounding whitespace
+/// 
+/// # Errors
+/// Returns an error if the icon is empty, too long, a web address, or
+/// contains whitespace or control characters
+pub fn normalize_icon(icon: &str) -> Result<String> {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        return Err(PassManError::InvalidInput("An icon can't be empty".to_string()));
+    }
+    if icon.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(PassManError::InvalidInput("An icon can't contain spaces or control characters".to_string()));
+    }
+    
+    if has_prefix(icon, "http://") || has_prefix(icon, "https://") {
+        return Err(PassManError::InvalidInput(
+            "Icons can't be web addresses, which would be fetched whenever they are shown; use an emoji or the site's favicon".to_string()
+        ));
+    }
+    if has_prefix(icon, "data:") {
+        if !is_icon_url(icon) {
+            return Err(PassManError::InvalidInput("A data URL icon has to be an image".to_string()));
+        }
+        if icon.len() > MAX_DATA_URL_LEN {
+            return Err(PassManError::InvalidInput(format!("An icon URL can be at most {} bytes long", MAX_DATA_URL_LEN)));
+        }
+    } else if icon.chars().count() > MAX_TEXT_ICON_CHARS {
+        return Err(PassManError::InvalidInput(format!(
+            "A text icon can have at most {} characters; use an emoji or an image",
+            MAX_TEXT_ICON_CHARS,
+        )));
+    }
+    Ok(icon.to_string())
+}
+
+/// Check whether an icon is an image (a `data:image/` URL) rather than text
+pub fn is_icon_url(icon: &str) -> bool {
+    has_prefix(icon, "data:image/")
+}
+
+/// Case-insensitive `starts_with` for ASCII prefixes
+fn has_prefix(value: &str, prefix: &str) -> bool {
+    value.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn invalid_color(color: &str) -> PassManError {
+    PassManError::InvalidInput(format!("'{}' is not a color; use hex like #3b82f6 or #38f", color.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_normalize_color() {
+        assert_eq!(normalize_color("#3B82F6").unwrap(), "#3b82f6");
+        assert_eq!(normalize_color(" 3b82f6 ").unwrap(), "#3b82f6");
+        assert_eq!(normalize_color("#38f").unwrap(), "#3388ff");
+        assert!(normalize_color("#3b82f").is_err());
+        assert!(normalize_color("blue").is_err());
+        assert!(normalize_color("#").is_err());
+        assert!(normalize_color("#ééé").is_err());
+    }
+    
+    #[test]
+    fn test_normalize_icon() {
+        assert_eq!(normalize_icon(" 🔑 ").unwrap(), "🔑");
+        assert_eq!(normalize_icon("👨‍👩‍👧‍👦").unwrap(), "👨‍👩‍👧‍👦");
+        assert_eq!(normalize_icon("GH").unwrap(), "GH");
+        assert!(normalize_icon("").is_err());
+        assert!(normalize_icon("two words").is_err());
+        assert!(normalize_icon("averylongtexticonname").is_err());
+        
+        let url = "data:image/png;base64,iVBORw0KGgo=";
+        assert_eq!(normalize_icon(url).unwrap(), url);
+        assert!(is_icon_url(url));
+        assert!(!is_icon_url("🔑"));
+        assert!(normalize_icon("data:text/html;base64,PHNjcmlwdD4=").is_err());
+        assert!(normalize_icon(&format!("data:image/png;base64,{}", "A".repeat(MAX_DATA_URL_LEN))).is_err());
+        
+        // Showing a remote icon would contact its host
+        assert!(!is_icon_url("https://github.com/favicon.ico"));
+        assert!(normalize_icon("https://github.com/favicon.ico").is_err());
+        assert!(normalize_icon("HTTP://a.io").is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! # Favicons
+//! 
+//! Downloads a site's favicon so an account can show it without further
+//! network access: `Favicon::data_url` turns it into an icon that is
+//! stored in the vault. The site's page is read for `<link rel="icon">`
+//! (and `apple-touch-icon`) tags, falling back to `/favicon.ico`.
+//! 
+//! Only the site itself is contacted, and only when a caller asks for
+//! its icon: links and redirects to any other host are ignored, though
+//! `www.` and the bare domain count as the same site.
+
+use crate::{PassManError, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::Read;
+use std::time::Duration;
+use url::Url;
+
+/// Size of the largest icon that is kept
+pub const MAX_ICON_BYTES: u64 = 64 * 1024;
+
+/// How much of a site's page is searched for icon links
+const MAX_PAGE_BYTES: u64 = 512 * 1024;
+
+/// How long one request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Redirects followed per request
+const MAX_REDIRECTS: usize = 5;
+
+/// A downloaded favicon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Favicon {
+    /// Where the icon was downloaded from
+    pub url: String,
+    
+    /// MIME type of the image, e.g. `image/png`
+    pub content_type: String,
+    
+    /// The image itself
+    pub data: Vec<u8>,
+}
+
+impl Favicon {
+    /// The icon as a `data:` URL, for `Account::icon`
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.content_type, STANDARD.encode(&self.data))
+    }
+}
+
+/// Download a site's favicon
+/// 
+/// # Arguments
+/// * `site_url` - The site, e.g. an account's URL; `https://` is assumed without a scheme
+/// 
+/// # Returns
+/// The first icon the site links to that could be downloaded, or its `/favicon.ico`
+/// 
+/// # Errors
+/// Returns an error if the URL is invalid, or the site can't be reached
+/// or has no usable icon
+pub fn fetch_favicon(site_url: &str) -> Result<Favicon> {
+    let site = parse_site_url(site_url)?;
+    // Redirects are followed by `get`, which keeps them on the site
+    let agent = ureq::AgentBuilder::new()
+        .redirects(0)
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("passman/", env!("CARGO_PKG_VERSION")))
+        .build();
+    
+    // Links are relative to the page after redirects
+    let mut candidates = Vec::new();
+    let mut base = site.clone();
+    if let Ok(response) = get(&agent, &site, &site) {
+        if let Ok(page_url) = Url::parse(response.get_url()) {
+            base = page_url;
+        }
+        let mut page = String::new();
+        if response.into_reader().take(MAX_PAGE_BYTES).read_to_string(&mut page).is_ok() {
+            candidates.extend(icon_links(&page).iter()
+                .filter_map(|href| base.join(href).ok())
+                .filter(|url| same_site(&site, url)));
+        }
+    }
+    candidates.extend(base.join("/favicon.ico").ok());
+    
+    let mut last_error = format!("{} has no favicon", site);
+    for candidate in candidates {
+        match fetch_icon(&agent, &site, &candidate) {
+            Ok(icon) => return Ok(icon),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(PassManError::IoError(std::io::Error::other(last_error)))
+}
+
+/// Download one icon, rejecting anything that isn't a small image
+fn fetch_icon(agent: &ureq::Agent, site: &Url, url: &Url) -> std::result::Result<Favicon, String> {
+    let response = get(agent, site, url)?;
+    let content_type = match response.content_type().to_ascii_lowercase() {
+        image if image.starts_with("image/") => image,
+        // Servers often don't know the type of .ico files
+        _ if url.path().to_ascii_lowercase().ends_with(".ico") => "image/x-icon".to_string(),
+        other => return Err(format!("{} is not an image ({})", url, other)),
+    };
+    
+    let mut data = Vec::new();
+    response.into_reader().take(MAX_ICON_BYTES + 1).read_to_end(&mut data)
+        .map_err(|e| format!("Downloading {} failed: {}", url, e))?;
+    if data.is_empty() {
+        return Err(format!("{} is empty", url));
+    }
+    if data.len() as u64 > MAX_ICON_BYTES {
+        return Err(format!("{} is larger than {} KiB", url, MAX_ICON_BYTES / 1024));
+    }
+    Ok(Favicon { url: url.to_string(), content_type, data })
+}
+
+/// Request a URL on the site, following redirects only while they stay on it
+fn get(agent: &ureq::Agent, site: &Url, url: &Url) -> std::result::Result<ureq::Response, String> {
+    let mut url = url.clone();
+    for _ in 0..=MAX_REDIRECTS {
+        if !same_site(site, &url) {
+            return Err(format!("{} is not on {}", url, site.host_str().unwrap_or_default()));
+        }
+        let response = agent.request_url("GET", &url).call()
+            .map_err(|e| format!("Downloading {} failed: {}", url, e))?;
+        if !(300..400).contains(&response.status()) {
+            return Ok(response);
+        }
+        let location = response.header("location")
+            .ok_or_else(|| format!("{} redirects nowhere", url))?;
+        url = url.join(location)
+            .map_err(|e| format!("{} redirects to an invalid address: {}", url, e))?;
+    }
+    Err(format!("{} redirects too often", url))
+}
+
+/// Check that a URL is a web address on the site's host, `www.` or not
+fn same_site(site: &Url, url: &Url) -> bool {
+    let host = |url: &Url| url.host_str().map(|host| host.strip_prefix("www.").unwrap_or(host).to_string());
+    matches!(url.scheme(), "http" | "https") && host(url).is_some() && host(url) == host(site)
+}
+
+/// Parse a site address, assuming `https://` when it has no scheme
+fn parse_site_url(site_url: &str) -> Result<Url> {
+    let site_url = site_url.trim();
+    let with_scheme = if site_url.contains("://") {
+        site_url.to_string()
+    } else {
+        format!("https://{}", site_url)
+    };
+    let url = Url::parse(&with_scheme)
+        .map_err(|e| PassManError::InvalidInput(format!("'{}' is not a valid URL: {}", site_url, e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+        return Err(PassManError::InvalidInput(format!("'{}' is not a web address", site_url)));
+    }
+    Ok(url)
+}
+
+/// Find the icons a page links to, best first
+/// 
+/// `rel="icon"` (including `shortcut icon`) comes before
+/// `apple-touch-icon`, which is usually larger.
+fn icon_links(html: &str) -> Vec<String> {
+    let lower = html.to_ascii_lowercase();
+    let mut icons = Vec::new();
+    let mut touch_icons = Vec::new();
+    let mut rest = 0;
+    while let Some(start) = lower[rest..].find("<link").map(|offset| rest + offset) {
+        let end = lower[start..].find('>').map_or(lower.len(), |offset| start + offset);
+        let attributes = tag_attributes(&html[start + "<link".len()..end]);
+        rest = end;
+        
+        let attribute = |name: &str| attributes.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str());
+        let (Some(rel), Some(href)) = (attribute("rel"), attribute("href")) else {
+            continue;
+        };
+        let href = href.trim().replace("&amp;", "&");
+        if href.is_empty() {
+            continue;
+        }
+        let rel = rel.to_ascii_lowercase();
+        if rel.split_whitespace().any(|token| token == "icon") {
+            icons.push(href);
+        } else if rel.split_whitespace().any(|token| token == "apple-touch-icon") {
+            touch_icons.push(href);
+        }
+    }
+    icons.extend(touch_icons);
+    icons
+}
+
+/// Split the inside of a tag into `name=value` pairs
+fn tag_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut chars = tag.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() || c == '/' {
+            chars.next();
+            continue;
+        }
+        let mut name_end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '=' || c == '/' {
+                break;
+            }
+            name_end = i + c.len_utf8();
+            chars.next();
+        }
+        let name = tag[start..name_end].to_string();
+        while chars.peek().is_some_and(|&(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none_or(|&(_, c)| c != '=') {
+            attributes.push((name, String::new()));
+            continue;
+        }
+        chars.next();
+        while chars.peek().is_some_and(|&(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        
+        let quote = chars.peek().map(|&(_, c)| c).filter(|&c| c == '"' || c == '\'');
+        if quote.is_some() {
+            chars.next();
+        }
+        let mut value = String::new();
+        for (_, c) in chars.by_ref() {
+            if Some(c) == quote || (quote.is_none() && c.is_whitespace()) {
+                break;
+            }
+            value.push(c);
+        }
+        attributes.push((name, value));
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    
+    #[test]
+    fn test_icon_links() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            <LINK REL=apple-touch-icon HREF=/touch.png>
+            <link href='/static/icon.svg?v=1&amp;x=2' rel='shortcut icon' type="image/svg+xml" />
+            <link rel="icon" href="">
+        </head></html>"#;
+        assert_eq!(icon_links(html), ["/static/icon.svg?v=1&x=2", "/touch.png"]);
+        assert!(icon_links("<p>no links</p><link").is_empty());
+    }
+    
+    #[test]
+    fn test_parse_site_url() {
+        assert_eq!(parse_site_url("github.com").unwrap().as_str(), "https://github.com/");
+        assert_eq!(parse_site_url("http://example.com/login").unwrap().as_str(), "http://example.com/login");
+        assert!(parse_site_url("ftp://example.com").is_err());
+        assert!(parse_site_url("https://").is_err());
+    }
+    
+    #[test]
+    fn test_same_site() {
+        let site = Url::parse("https://example.com/login").unwrap();
+        for url in ["https://example.com/favicon.ico", "http://www.example.com/a.png", "https://example.com:8443/b.png"] {
+            assert!(same_site(&site, &Url::parse(url).unwrap()), "{}", url);
+        }
+        for url in ["https://cdn.example.com/a.png", "https://example.com.evil.test/a.png", "ftp://example.com/a.png", "data:image/png;base64,AA=="] {
+            assert!(!same_site(&site, &Url::parse(url).unwrap()), "{}", url);
+        }
+    }
+    
+    /// Answer `count` requests with `respond(path)`, returning the paths asked for
+    fn serve(
+        listener: TcpListener,
+        count: usize,
+        respond: impl Fn(&str) -> (&'static str, String, Vec<u8>) + Send + 'static,
+    ) -> std::thread::JoinHandle<Vec<String>> {
+        std::thread::spawn(move || {
+            let mut paths = Vec::new();
+            for _ in 0..count {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header == "\r\n" {
+                        break;
+                    }
+                }
+                let path = request.split_whitespace().nth(1).unwrap().to_string();
+                let (status, headers, body) = respond(&path);
+                let stream = reader.get_mut();
+                write!(stream, "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n", status, headers, body.len()).unwrap();
+                stream.write_all(&body).unwrap();
+                paths.push(path);
+            }
+            paths
+        })
+    }
+    
+    #[test]
+    fn test_fetch_favicon() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let site = format!("http://{}/login", listener.local_addr().unwrap());
+        // The page, a missing icon, then the touch icon
+        let server = serve(listener, 3, |path| match path {
+            "/login" => ("200 OK", "Content-Type: text/html\r\n".to_string(), br#"<link rel="icon" href="missing.png"><link rel="apple-touch-icon" href="/touch.png">"#.to_vec()),
+            "/touch.png" => ("200 OK", "Content-Type: image/png\r\n".to_string(), b"\x89PNG".to_vec()),
+            _ => ("404 Not Found", "Content-Type: text/plain\r\n".to_string(), b"not found".to_vec()),
+        });
+        
+        let favicon = fetch_favicon(&site).unwrap();
+        assert!(favicon.url.ends_with("/touch.png"));
+        assert_eq!(favicon.content_type, "image/png");
+        assert_eq!(favicon.data_url(), "data:image/png;base64,iVBORw==");
+        assert_eq!(server.join().unwrap(), ["/login", "/missing.png", "/touch.png"]);
+    }
+    
+    #[test]
+    fn test_fetch_favicon_stays_on_site() {
+        // "localhost" is another host than the site's 127.0.0.1
+        let elsewhere = TcpListener::bind("127.0.0.1:0").unwrap();
+        let other = format!("localhost:{}", elsewhere.local_addr().unwrap().port());
+        elsewhere.set_nonblocking(true).unwrap();
+        
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let site = format!("http://{}/", listener.local_addr().unwrap());
+        let page = format!(r#"<link rel="icon" href="http://{0}/abs.png"><link rel="icon" href="//{0}/relative.png">"#, other);
+        let redirect = format!("Location: http://{}/favicon.ico\r\n", other);
+        let server = serve(listener, 2, move |path| match path {
+            "/" => ("200 OK", "Content-Type: text/html\r\n".to_string(), page.clone().into_bytes()),
+            _ => ("302 Found", redirect.clone(), Vec::new()),
+        });
+        
+        assert!(fetch_favicon(&site).is_err());
+        assert_eq!(server.join().unwrap(), ["/", "/favicon.ico"]);
+        assert_eq!(elsewhere.accept().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+    }
+}
@@ -0,0 +1,226 @@
+//! # Async Vault API
+//! 
+//! `PassMan` does its work synchronously: unlocking runs Argon2id and
+//! every change rewrites the vault file. Calling it from an async task
+//! stalls the runtime's worker thread for the duration, so `AsyncPassMan`
+//! wraps a `PassMan` and runs each operation on tokio's blocking thread
+//! pool instead.
+//! 
+//! Available with the `async` feature. The wrapper is cheap to clone;
+//! clones share the same vault, and operations on it run one at a time.
+//! `run` gives access to any `PassMan` method without a dedicated wrapper.
+
+use crate::{
+    PassManError, Result,
+    models::{Account, VaultSettings},
+    storage::BackupInfo,
+    vault::PassMan,
+};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// A `PassMan` whose operations run on the blocking thread pool
+#[derive(Clone)]
+pub struct AsyncPassMan {
+    inner: Arc<Mutex<PassMan>>,
+}
+
+impl AsyncPassMan {
+    /// Create a manager for a vault
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault
+    /// 
+    /// # Returns
+    /// A new AsyncPassMan instance
+    /// 
+    /// # Errors
+    /// Returns an error if storage initialization fails
+    pub async fn new(vault_name: &str) -> Result<Self> {
+        let vault_name = vault_name.to_string();
+        let passman = spawn(move || PassMan::new(&vault_name)).await?;
+        Ok(Self::from_passman(passman))
+    }
+    
+    /// Wrap an existing manager
+    /// 
+    /// # Arguments
+    /// * `passman` - The manager to wrap
+    pub fn from_passman(passman: PassMan) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(passman)),
+        }
+    }
+    
+    /// Take the wrapped manager back
+    /// 
+    /// # Returns
+    /// The manager, or this wrapper again if it still has clones
+    pub fn into_inner(self) -> std::result::Result<PassMan, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|mutex| mutex.into_inner().unwrap_or_else(|e| e.into_inner()))
+            .map_err(|inner| Self { inner })
+    }
+    
+    /// Run a closure against the manager on the blocking thread pool
+    /// 
+    /// # Arguments
+    /// * `f` - Operation to run
+    /// 
+    /// # Returns
+    /// The closure's result
+    /// 
+    /// # Errors
+    /// Returns the closure's error, or an error if the task was cancelled
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut PassMan) -> Result<T> + Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        spawn(move || {
+            let mut passman = inner.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut passman)
+        }).await
+    }
+    
+    /// Initialize a new vault (see `PassMan::init_vault`)
+    pub async fn init_vault(&self, email: String, master_password: &str) -> Result<()> {
+        let master_password = Zeroizing::new(master_password.to_string());
+        self.run(move |passman| passman.init_vault(email, &master_password)).await
+    }
+    
+    /// Open the vault with the master password (see `PassMan::open_vault`)
+    pub async fn open_vault(&self, master_password: &str) -> Result<()> {
+        let master_password = Zeroizing::new(master_password.to_string());
+        self.run(move |passman| passman.open_vault(&master_password)).await
+    }
+    
+    /// Open the vault with the master password and a keyfile
+    /// (see `PassMan::open_vault_with_keyfile`)
+    pub async fn open_vault_with_keyfile(&self, master_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
+        let master_password = Zeroizing::new(master_password.to_string());
+        let keyfile = keyfile.map(|keyfile| Zeroizing::new(keyfile.to_vec()));
+        self.run(move |passman| passman.open_vault_with_keyfile(&master_password, keyfile.as_deref().map(Vec::as_slice))).await
+    }
+    
+    /// Check the master password of the open vault
+    /// (see `PassMan::verify_master_password`)
+    pub async fn verify_master_password(&self, master_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
+        let master_password = Zeroizing::new(master_password.to_string());
+        let keyfile = keyfile.map(|keyfile| Zeroizing::new(keyfile.to_vec()));
+        self.run(move |passman| passman.verify_master_password(&master_password, keyfile.as_deref().map(Vec::as_slice))).await
+    }
+    
+    /// Change the master password (see `PassMan::change_master_password`)
+    pub async fn change_master_password(&self, new_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
+        let new_password = Zeroizing::new(new_password.to_string());
+        let keyfile = keyfile.map(|keyfile| Zeroizing::new(keyfile.to_vec()));
+        self.run(move |passman| passman.change_master_password(&new_password, keyfile.as_deref().map(Vec::as_slice))).await
+    }
+    
+    /// Re-read the vault file (see `PassMan::reload_vault`)
+    pub async fn reload_vault(&self) -> Result<bool> {
+        self.run(|passman| passman.reload_vault()).await
+    }
+    
+    /// Close the vault and clear its keys (see `PassMan::close_vault`)
+    pub async fn close_vault(&self) -> Result<()> {
+        self.run(|passman| {
+            passman.close_vault();
+            Ok(())
+        }).await
+    }
+    
+    /// Check whether the vault is open
+    pub async fn is_vault_open(&self) -> Result<bool> {
+        self.run(|passman| Ok(passman.is_vault_open())).await
+    }
+    
+    /// Add an account and save (see `PassMan::add_account_entry`)
+    pub async fn add_account_entry(&self, account: Account) -> Result<Uuid> {
+        self.run(move |passman| passman.add_account_entry(account)).await
+    }
+    
+    /// Delete an account and save (see `PassMan::delete_account`)
+    pub async fn delete_account(&self, id: Uuid) -> Result<()> {
+        self.run(move |passman| passman.delete_account(id)).await
+    }
+    
+    /// Get a copy of an account (see `PassMan::get_account`)
+    pub async fn get_account(&self, id: Uuid) -> Result<Option<Account>> {
+        self.run(move |passman| Ok(passman.get_account(id).cloned())).await
+    }
+    
+    /// Get copies of all accounts (see `PassMan::get_all_accounts`)
+    pub async fn get_all_accounts(&self) -> Result<Vec<Account>> {
+        self.run(|passman| Ok(passman.get_all_accounts().into_iter().cloned().collect())).await
+    }
+    
+    /// Replace the vault settings and save (see `PassMan::update_settings`)
+    pub async fn update_settings(&self, settings: VaultSettings) -> Result<()> {
+        self.run(move |passman| passman.update_settings(settings)).await
+    }
+    
+    /// Back up the vault file (see `PassMan::create_backup`)
+    pub async fn create_backup(&self) -> Result<BackupInfo> {
+        self.run(|passman| passman.create_backup()).await
+    }
+}
+
+/// Run blocking work on tokio's blocking thread pool
+async fn spawn<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        // Surface panics as if the operation had run on this task
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(PassManError::IoError(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "vault operation was cancelled",
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AccountType;
+    
+    fn test_vault_name() -> String {
+        crate::testing::isolate();
+        format!("test_async_vault_{}", Uuid::new_v4().simple())
+    }
+    
+    #[tokio::test]
+    async fn test_async_vault_operations() {
+        let vault_name = test_vault_name();
+        let passman = AsyncPassMan::new(&vault_name).await.unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").await.unwrap();
+        
+        let id = passman.add_account_entry(Account::new("Mail".to_string(), AccountType::Email, "pw".to_string())).await.unwrap();
+        passman.close_vault().await.unwrap();
+        assert!(!passman.is_vault_open().await.unwrap());
+        
+        assert!(matches!(
+            passman.open_vault("wrong_password").await,
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        passman.open_vault("master_password").await.unwrap();
+        assert_eq!(passman.get_account(id).await.unwrap().unwrap().name, "Mail");
+        
+        let shared = passman.clone();
+        let Err(passman) = passman.into_inner() else {
+            panic!("a shared vault must not be unwrapped");
+        };
+        drop(shared);
+        let passman = passman.into_inner().ok().unwrap();
+        assert_eq!(passman.get_all_accounts().len(), 1);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+}
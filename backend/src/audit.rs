@@ -0,0 +1,376 @@
+//! # Password Health Audit
+//! 
+//! This module checks the accounts of a vault for common password hygiene
+//! problems and groups the findings so front-ends can render a security
+//! overview without reimplementing the rules:
+//! 
+//! - **reused**: the same password on several accounts
+//! - **weak**: a strength score at or below `AuditOptions::weak_score`
+//! - **stale**: not changed for `AuditOptions::stale_after_days`
+//! - **breached**: flagged by a caller-supplied breach check
+//! - **expired**: past the account's expiry date or rotation interval
+//! - **expiring**: due within `AuditOptions::expiring_within_days`
+//! 
+//! Canary accounts (fake credentials for services the user controls) are
+//! listed separately instead of being scored, and a canary whose password
+//! turns up in breach data is reported as tripped. `canaries` describes
+//! them for external monitoring, which alerts when they are ever used.
+//! 
+//! Reports identify accounts by ID and name only; passwords never leave
+//! the vault through an audit, and canary passwords only as SHA-1 hashes.
+
+use crate::generator::PasswordGenerator;
+use crate::models::Account;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Thresholds used by an audit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AuditOptions {
+    /// Strength scores at or below this are weak ("Weak" or worse)
+    pub weak_score: u8,
+    
+    /// Passwords unchanged for this many days are stale (0 = never)
+    pub stale_after_days: u32,
+    
+    /// Passwords due within this many days are reported as expiring
+    pub expiring_within_days: u32,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            weak_score: 40,
+            stale_after_days: 365,
+            expiring_within_days: 14,
+        }
+    }
+}
+
+/// An account named in an audit finding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    /// Account ID
+    pub id: Uuid,
+    
+    /// Account name
+    pub name: String,
+    
+    /// Account username
+    pub username: Option<String>,
+    
+    /// Password strength score (0-100)
+    pub strength: u8,
+    
+    /// Days since the account was last modified
+    pub days_since_change: i64,
+    
+    /// When the password has to be changed (see `Account::password_expiry`)
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Structured result of a password health audit
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HealthReport {
+    /// Number of audited (active) accounts
+    pub total: usize,
+    
+    /// Share of accounts without any finding, 0-100
+    pub score: u8,
+    
+    /// Groups of accounts sharing one password
+    pub reused: Vec<Vec<AuditEntry>>,
+    
+    /// Accounts with a weak password
+    pub weak: Vec<AuditEntry>,
+    
+    /// Accounts whose password hasn't changed in a long time
+    pub stale: Vec<AuditEntry>,
+    
+    /// Accounts whose password appears in a breach
+    pub breached: Vec<AuditEntry>,
+    
+    /// Accounts whose password is past its expiry date
+    #[serde(default)]
+    pub expired: Vec<AuditEntry>,
+    
+    /// Accounts whose password expires soon
+    #[serde(default)]
+    pub expiring: Vec<AuditEntry>,
+    
+    /// Whether passwords were checked against breach data at all
+    pub breach_checked: bool,
+    
+    /// Canary accounts (not audited or counted in `total`)
+    #[serde(default)]
+    pub canaries: Vec<AuditEntry>,
+    
+    /// Canary accounts whose password appears in breach data
+    #[serde(default)]
+    pub tripped_canaries: Vec<AuditEntry>,
+}
+
+impl HealthReport {
+    /// Number of accounts with at least one finding
+    pub fn affected_accounts(&self) -> usize {
+        let mut ids: Vec<&Uuid> = self.reused.iter().flatten()
+            .chain(&self.weak)
+            .chain(&self.stale)
+            .chain(&self.breached)
+            .chain(&self.expired)
+            .map(|entry| &entry.id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids.len()
+    }
+}
+
+/// A canary credential, as handed to external monitoring
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Canary {
+    /// Account ID
+    pub id: Uuid,
+    
+    /// Account name
+    pub name: String,
+    
+    /// Where the credentials are meant to be used
+    pub url: Option<String>,
+    
+    /// Username to watch for
+    pub username: Option<String>,
+    
+    /// Uppercase hex SHA-1 of the password, as in the Pwned Passwords dataset
+    pub password_sha1: String,
+}
+
+/// Describe the canary accounts for external monitoring
+/// 
+/// Archived canaries are left out.
+/// 
+/// # Arguments
+/// * `accounts` - Accounts to search
+/// 
+/// # Returns
+/// The canaries, sorted by name
+pub fn canaries(accounts: &[&Account]) -> Vec<Canary> {
+    let mut canaries: Vec<Canary> = accounts.iter()
+        .filter(|account| account.canary && !account.archived)
+        .map(|account| Canary {
+            id: account.id,
+            name: account.name.clone(),
+            url: account.url.clone(),
+            username: account.username.clone(),
+            password_sha1: Sha1::digest(account.password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect(),
+        })
+        .collect();
+    canaries.sort_by_key(|canary| canary.name.to_lowercase());
+    canaries
+}
+
+/// Audit a set of accounts
+/// 
+/// Archived accounts are skipped, and canaries are only listed.
+/// 
+/// # Arguments
+/// * `accounts` - Accounts to audit
+/// * `options` - Audit thresholds
+/// * `is_breached` - Breach check for a password, if breach data is available
+/// 
+/// # Returns
+/// The findings, grouped by problem, with entries sorted by name
+pub fn audit(
+    accounts: &[&Account],
+    options: &AuditOptions,
+    is_breached: Option<&dyn Fn(&str) -> bool>,
+) -> HealthReport {
+    let generator = PasswordGenerator::new();
+    let now = Utc::now();
+    let (canaries, accounts): (Vec<&Account>, Vec<&Account>) = accounts.iter()
+        .copied()
+        .filter(|account| !account.archived)
+        .partition(|account| account.canary);
+    
+    let entry = |account: &Account| AuditEntry {
+        id: account.id,
+        name: account.name.clone(),
+        username: account.username.clone(),
+        strength: generator.calculate_strength(&account.password),
+        days_since_change: (now - account.updated_at).num_days(),
+        expires_at: account.password_expiry(),
+    };
+    let mut report = HealthReport {
+        total: accounts.len(),
+        breach_checked: is_breached.is_some(),
+        ..HealthReport::default()
+    };
+    
+    for account in canaries {
+        let entry = entry(account);
+        if is_breached.is_some_and(|check| check(&account.password)) {
+            report.tripped_canaries.push(entry.clone());
+        }
+        report.canaries.push(entry);
+    }
+    
+    let mut by_password: HashMap<&str, Vec<&Account>> = HashMap::new();
+    for account in &accounts {
+        if !account.password.is_empty() {
+            by_password.entry(account.password.as_str()).or_default().push(account);
+        }
+        
+        let entry = entry(account);
+        if entry.strength <= options.weak_score && account.uses_password() {
+            report.weak.push(entry.clone());
+        }
+        if options.stale_after_days > 0 && entry.days_since_change >= options.stale_after_days as i64 {
+            report.stale.push(entry.clone());
+        }
+        match entry.expires_at {
+            Some(expiry) if expiry <= now => report.expired.push(entry.clone()),
+            Some(expiry) if expiry <= now + Duration::days(options.expiring_within_days.into()) => report.expiring.push(entry.clone()),
+            _ => {}
+        }
+        if is_breached.is_some_and(|check| check(&account.password)) {
+            report.breached.push(entry);
+        }
+    }
+    
+    report.reused = by_password.into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let mut group: Vec<AuditEntry> = group.into_iter().map(entry).collect();
+            sort_by_name(&mut group);
+            group
+        })
+        .collect();
+    report.reused.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].name.to_lowercase().cmp(&b[0].name.to_lowercase())));
+    sort_by_name(&mut report.weak);
+    sort_by_name(&mut report.stale);
+    sort_by_name(&mut report.breached);
+    report.expired.sort_by_key(|entry| entry.expires_at);
+    report.expiring.sort_by_key(|entry| entry.expires_at);
+    sort_by_name(&mut report.canaries);
+    sort_by_name(&mut report.tripped_canaries);
+    
+    let healthy = report.total - report.affected_accounts();
+    report.score = (100 * healthy).checked_div(report.total).unwrap_or(100) as u8;
+    
+    report
+}
+
+/// Sort entries alphabetically by account name
+fn sort_by_name(entries: &mut [AuditEntry]) {
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AccountType;
+    
+    fn account(name: &str, password: &str) -> Account {
+        Account::new(name.to_string(), AccountType::Personal, password.to_string())
+    }
+    
+    #[test]
+    fn test_audit_groups_findings() {
+        let strong = "x7#Kq!2mZ$9vLp@4Rw";
+        let mut old = account("Old", "Tr0ub4dor&3-correct-horse");
+        old.updated_at = Utc::now() - chrono::Duration::days(400);
+        let mut archived = account("Archived", "password");
+        archived.archived = true;
+        let accounts = [
+            account("GitHub", strong),
+            account("gitlab", strong),
+            account("Forum", "password"),
+            old,
+            archived,
+        ];
+        let refs: Vec<&Account> = accounts.iter().collect();
+        
+        let report = audit(&refs, &AuditOptions::default(), None);
+        assert_eq!(report.total, 4);
+        assert_eq!(report.reused.len(), 1);
+        let reused: Vec<&str> = report.reused[0].iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(reused, ["GitHub", "gitlab"]);
+        assert_eq!(report.weak.len(), 1);
+        assert_eq!(report.weak[0].name, "Forum");
+        assert_eq!(report.stale.len(), 1);
+        assert_eq!(report.stale[0].name, "Old");
+        assert!(!report.breach_checked);
+        assert_eq!(report.score, 0);
+    }
+    
+    #[test]
+    fn test_audit_breach_check_and_score() {
+        let accounts = [
+            account("Bank", "x7#Kq!2mZ$9vLp@4Rw"),
+            account("Shop", "Hunter2-is-leaked!"),
+        ];
+        let refs: Vec<&Account> = accounts.iter().collect();
+        let leaked = |password: &str| password.starts_with("Hunter2");
+        
+        let report = audit(&refs, &AuditOptions::default(), Some(&leaked));
+        assert!(report.breach_checked);
+        assert_eq!(report.breached.len(), 1);
+        assert_eq!(report.breached[0].name, "Shop");
+        assert_eq!(report.score, 50);
+        
+        assert_eq!(audit(&[], &AuditOptions::default(), None).score, 100);
+    }
+    
+    #[test]
+    fn test_audit_expiry() {
+        let strong = "x7#Kq!2mZ$9vLp@4Rw";
+        let mut expired = account("Expired", strong);
+        expired.expires_at = Some(Utc::now() - Duration::days(1));
+        let mut rotate = account("Rotate", "Hunter2-is-leaked!");
+        rotate.rotation_interval_days = Some(7);
+        let mut later = account("Later", "correct-Horse-battery-9");
+        later.expires_at = Some(Utc::now() + Duration::days(60));
+        let accounts = [expired, rotate, later];
+        let refs: Vec<&Account> = accounts.iter().collect();
+        
+        let report = audit(&refs, &AuditOptions::default(), None);
+        assert_eq!(report.expired.len(), 1);
+        assert_eq!(report.expired[0].name, "Expired");
+        assert_eq!(report.expiring.len(), 1);
+        assert_eq!(report.expiring[0].name, "Rotate");
+        assert!(report.expiring[0].expires_at.is_some());
+        // Only expired passwords count against the score
+        assert_eq!(report.score, 66);
+    }
+    
+    #[test]
+    fn test_canaries() {
+        let mut honeypot = account("Honeypot", "password");
+        honeypot.canary = true;
+        honeypot.username = Some("admin".to_string());
+        let mut leaked = account("Leaked honeypot", "Hunter2-is-leaked!");
+        leaked.canary = true;
+        let accounts = [account("Bank", "x7#Kq!2mZ$9vLp@4Rw"), honeypot, leaked];
+        let refs: Vec<&Account> = accounts.iter().collect();
+        let is_leaked = |password: &str| password.starts_with("Hunter2");
+        
+        // Canaries are neither scored nor flagged as weak or breached
+        let report = audit(&refs, &AuditOptions::default(), Some(&is_leaked));
+        assert_eq!(report.total, 1);
+        assert_eq!(report.score, 100);
+        assert!(report.weak.is_empty() && report.breached.is_empty());
+        assert_eq!(report.canaries.len(), 2);
+        assert_eq!(report.tripped_canaries.len(), 1);
+        assert_eq!(report.tripped_canaries[0].name, "Leaked honeypot");
+        
+        let canaries = canaries(&refs);
+        assert_eq!(canaries.len(), 2);
+        assert_eq!(canaries[0].username.as_deref(), Some("admin"));
+        assert_eq!(canaries[0].password_sha1, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+}
@@ -6,6 +6,11 @@
 use std::time::{Duration, Instant};
 use crate::{PassManError, Result, crypto::CryptoManager, models::VaultMetadata};
 
+/// Delay after the second failed attempt; doubles with each further failure
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Longest delay enforced between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
 /// Authentication session information
 #[derive(Debug, Clone)]
 pub struct AuthSession {
@@ -21,6 +26,9 @@ pub struct AuthSession {
     /// Number of failed authentication attempts
     pub failed_attempts: u32,
     
+    /// When the most recent failed attempt happened
+    pub last_failed_attempt: Option<Instant>,
+    
     /// Last activity timestamp
     pub last_activity: Instant,
 }
@@ -42,6 +50,7 @@ impl AuthSession {
             expires_at: now + timeout_duration,
             is_active: true,
             failed_attempts: 0,
+            last_failed_attempt: None,
             last_activity: now,
         }
     }
@@ -71,11 +80,29 @@ impl AuthSession {
     /// Record a failed authentication attempt
     pub fn record_failed_attempt(&mut self) {
         self.failed_attempts += 1;
+        self.last_failed_attempt = Some(Instant::now());
     }
     
     /// Reset failed attempts counter
     pub fn reset_failed_attempts(&mut self) {
         self.failed_attempts = 0;
+        self.last_failed_attempt = None;
+    }
+    
+    /// Get how long to wait before the next attempt is allowed
+    /// 
+    /// A single typo costs nothing; from the second failure on the delay
+    /// starts at one second and doubles with every failure, up to 30 seconds.
+    /// 
+    /// # Returns
+    /// Remaining backoff, or zero if another attempt may be made now
+    pub fn retry_delay(&self) -> Duration {
+        let Some(last) = self.last_failed_attempt.filter(|_| self.failed_attempts >= 2) else {
+            return Duration::ZERO;
+        };
+        let exponent = (self.failed_attempts - 2).min(16);
+        let delay = BASE_RETRY_DELAY.saturating_mul(1 << exponent).min(MAX_RETRY_DELAY);
+        delay.saturating_sub(last.elapsed())
     }
     
     /// Check if too many failed attempts have occurred
@@ -135,14 +162,7 @@ impl AuthManager {
     /// # Errors
     /// Returns an error if authentication fails
     pub fn authenticate(&mut self, master_password: &str, _vault_metadata: &VaultMetadata) -> Result<bool> {
-        // Check if already locked out
-        if let Some(ref session) = self.session {
-            if session.is_locked_out(self.max_failed_attempts) {
-                return Err(PassManError::AuthenticationFailed(
-                    "Too many failed attempts. Please try again later.".to_string()
-                ));
-            }
-        }
+        self.check_attempt_allowed()?;
         
         // Verify the master password
         let password_hash = self.crypto.hash_password(master_password)?;
@@ -158,15 +178,7 @@ impl AuthManager {
             
             Ok(true)
         } else {
-            // Record failed attempt
-            if let Some(ref mut session) = self.session {
-                session.record_failed_attempt();
-            } else {
-                // Create a session just to track failed attempts
-                let mut session = AuthSession::new(self.session_timeout_minutes);
-                session.record_failed_attempt();
-                self.session = Some(session);
-            }
+            self.record_failed_attempt();
             
             Err(PassManError::AuthenticationFailed(
                 "Invalid master password".to_string()
@@ -174,12 +186,75 @@ impl AuthManager {
         }
     }
     
+    /// Check whether another authentication attempt may be made now
+    /// 
+    /// # Returns
+    /// Unit if an attempt is allowed
+    /// 
+    /// # Errors
+    /// Returns an error if the user is locked out or still inside the
+    /// backoff window after a failed attempt
+    pub fn check_attempt_allowed(&self) -> Result<()> {
+        if self.is_locked_out() {
+            return Err(PassManError::LockedOut(
+                "Too many failed attempts. Please try again later.".to_string()
+            ));
+        }
+        
+        let delay = self.retry_delay();
+        if !delay.is_zero() {
+            return Err(PassManError::LockedOut(format!(
+                "Too many failed attempts. Try again in {} second(s).",
+                delay.as_secs_f64().ceil() as u64
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Record a failed authentication attempt
+    /// 
+    /// Failures are tracked on an inactive session so that they never
+    /// count as being authenticated.
+    pub fn record_failed_attempt(&mut self) {
+        let timeout = self.session_timeout_minutes;
+        let session = self.session.get_or_insert_with(|| {
+            let mut session = AuthSession::new(timeout);
+            session.is_active = false;
+            session
+        });
+        session.record_failed_attempt();
+    }
+    
+    /// Forget failed attempts after the user proved their identity
+    pub fn reset_failed_attempts(&mut self) {
+        if let Some(ref mut session) = self.session {
+            session.reset_failed_attempts();
+        }
+    }
+    
+    /// Get how long to wait before the next authentication attempt
+    /// 
+    /// # Returns
+    /// Remaining backoff after recent failures, or zero
+    pub fn retry_delay(&self) -> Duration {
+        self.session.as_ref().map_or(Duration::ZERO, |s| s.retry_delay())
+    }
+    
+    /// Start a new session without checking a password
+    /// 
+    /// Used when the caller already proved knowledge of the vault key,
+    /// e.g. by successfully decrypting the vault with a cached key.
+    pub fn start_session(&mut self) {
+        self.session = Some(AuthSession::new(self.session_timeout_minutes));
+    }
+    
     /// Check if the user is currently authenticated
     /// 
     /// # Returns
     /// True if the user is authenticated and session is valid
     pub fn is_authenticated(&self) -> bool {
-        self.session.as_ref().map_or(false, |s| s.is_valid())
+        self.session.as_ref().is_some_and(|s| s.is_valid())
     }
     
     /// Get the current session (if authenticated)
@@ -226,7 +301,7 @@ impl AuthManager {
     /// # Returns
     /// True if the user is locked out due to too many failed attempts
     pub fn is_locked_out(&self) -> bool {
-        self.session.as_ref().map_or(false, |s| s.is_locked_out(self.max_failed_attempts))
+        self.session.as_ref().is_some_and(|s| s.is_locked_out(self.max_failed_attempts))
     }
     
     /// Get time until session expires
@@ -435,7 +510,6 @@ impl Default for PasswordValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::VaultMetadata;
     
     #[test]
     fn test_auth_session_creation() {
@@ -454,6 +528,24 @@ mod tests {
         assert!(session.is_locked_out(2));
     }
     
+    #[test]
+    fn test_failed_attempt_backoff() {
+        let mut auth = AuthManager::new(3, 15);
+        assert!(auth.check_attempt_allowed().is_ok());
+        
+        auth.record_failed_attempt();
+        assert!(!auth.is_authenticated());
+        assert!(auth.check_attempt_allowed().is_ok());
+        
+        auth.record_failed_attempt();
+        assert!(auth.retry_delay() > Duration::ZERO);
+        assert!(auth.check_attempt_allowed().is_err());
+        
+        auth.record_failed_attempt();
+        assert!(auth.is_locked_out());
+        assert!(auth.check_attempt_allowed().is_err());
+    }
+    
     #[test]
     fn test_password_validator() {
         let validator = PasswordValidator::default();
@@ -474,6 +566,6 @@ mod tests {
         assert!(validator.validate("MyStrong!P@ssword").is_err());
         
         // No special characters
-        assert!(validator.validate("MyStr0ngP@ssw0rd").is_err());
+        assert!(validator.validate("MyStr0ngPassw0rd").is_err());
     }
 }
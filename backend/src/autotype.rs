@@ -0,0 +1,340 @@
+//! # Autotype Sequences
+//! 
+//! Autotype fills a login form by typing an account's credentials into
+//! whatever window has focus. What gets typed is described by a
+//! sequence of literal text and `{...}` placeholders, KeePass style:
+//! 
+//! - **fields**: `{USERNAME}`, `{PASSWORD}`, `{URL}`, `{TITLE}`, `{NOTES}`,
+//!   `{TOTP}` (the current two-factor code), `{S:Name}` (a custom field)
+//! - **keys**: `{TAB}`, `{ENTER}`, `{SPACE}`, `{BACKSPACE}`, `{DELETE}`,
+//!   `{ESC}`, `{UP}`, `{DOWN}`, `{LEFT}`, `{RIGHT}`, `{HOME}`, `{END}`
+//! - **pauses**: `{DELAY 500}` waits 500 milliseconds
+//! - **braces**: `{{}` and `{}}` type a literal `{` and `}`
+//! 
+//! Accounts without their own sequence use `DEFAULT_SEQUENCE`. This
+//! module only turns sequences into actions and picks accounts for a
+//! window; simulating the key presses is left to the front-end.
+
+use crate::models::Account;
+#[cfg(feature = "totp")]
+use crate::totp::Totp;
+use crate::{PassManError, Result};
+use zeroize::Zeroizing;
+
+/// Sequence used by accounts without a custom one
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+
+/// Longest pause a `{DELAY}` may request, in milliseconds
+pub const MAX_DELAY_MS: u64 = 10_000;
+
+/// A special key autotype can press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutotypeKey {
+    Tab,
+    Enter,
+    Space,
+    Backspace,
+    Delete,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+/// One step of an autotype run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutotypeAction {
+    /// Type text (may hold a secret, so it is wiped on drop)
+    Text(Zeroizing<String>),
+    
+    /// Press and release a special key
+    Key(AutotypeKey),
+    
+    /// Wait before the next step, in milliseconds
+    Delay(u64),
+}
+
+/// Account field a placeholder refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Username,
+    Password,
+    Url,
+    Title,
+    Notes,
+    Totp,
+    Custom(String),
+}
+
+/// A parsed, account-independent sequence element
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    Field(Field),
+    Key(AutotypeKey),
+    Delay(u64),
+}
+
+/// Check that a sequence only uses known placeholders
+/// 
+/// # Arguments
+/// * `sequence` - The sequence to check
+/// 
+/// # Errors
+/// Returns an error describing the first invalid placeholder
+pub fn validate_sequence(sequence: &str) -> Result<()> {
+    parse(sequence).map(|_| ())
+}
+
+/// Resolve the actions that type an account into a window
+/// 
+/// # Arguments
+/// * `account` - Account whose fields are typed
+/// 
+/// # Returns
+/// The actions for the account's own sequence, or `DEFAULT_SEQUENCE`
+/// 
+/// # Errors
+/// Returns an error if the sequence or the account's TOTP secret is invalid
+pub fn actions(account: &Account) -> Result<Vec<AutotypeAction>> {
+    let sequence = account.autotype.as_deref()
+        .filter(|sequence| !sequence.trim().is_empty())
+        .unwrap_or(DEFAULT_SEQUENCE);
+    
+    let mut actions: Vec<AutotypeAction> = Vec::new();
+    for token in parse(sequence)? {
+        let text = match token {
+            Token::Text(text) => Zeroizing::new(text),
+            Token::Field(field) => resolve(account, &field)?,
+            Token::Key(key) => {
+                actions.push(AutotypeAction::Key(key));
+                continue;
+            }
+            Token::Delay(ms) => {
+                actions.push(AutotypeAction::Delay(ms));
+                continue;
+            }
+        };
+        if text.is_empty() {
+            continue;
+        }
+        // Merge adjacent text so it is typed in one go
+        match actions.last_mut() {
+            Some(AutotypeAction::Text(previous)) => previous.push_str(&text),
+            _ => actions.push(AutotypeAction::Text(text)),
+        }
+    }
+    
+    Ok(actions)
+}
+
+/// Find the accounts that belong to a window
+/// 
+/// An account matches when the window title contains the host of its
+/// URL (without `www.`) or its name. URL matches rank first, then longer
+/// matches; archived accounts never match.
+/// 
+/// # Arguments
+/// * `window_title` - Title of the focused window
+/// * `accounts` - Accounts to choose from
+/// 
+/// # Returns
+/// Matching accounts, best match first
+pub fn match_window<'a>(window_title: &str, accounts: &[&'a Account]) -> Vec<&'a Account> {
+    let title = window_title.to_lowercase();
+    if title.trim().is_empty() {
+        return Vec::new();
+    }
+    
+    let mut matches: Vec<(usize, &Account)> = accounts.iter()
+        .copied()
+        .filter(|account| !account.archived)
+        .filter_map(|account| {
+            let host_score = account.host()
+                .filter(|host| title.contains(host.as_str()))
+                .map(|host| 1000 + host.len());
+            let name = account.name.trim().to_lowercase();
+            // Very short names ("X", "Me") would match almost any title
+            let name_score = (name.chars().count() >= 3 && title.contains(&name)).then_some(name.len());
+            host_score.or(name_score).map(|score| (score, account))
+        })
+        .collect();
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    
+    matches.into_iter().map(|(_, account)| account).collect()
+}
+
+/// Look up the value of a field placeholder
+fn resolve(account: &Account, field: &Field) -> Result<Zeroizing<String>> {
+    let value = match field {
+        Field::Username => account.username.clone().unwrap_or_default(),
+        Field::Password => account.password.clone(),
+        Field::Url => account.url.clone().unwrap_or_default(),
+        Field::Title => account.name.clone(),
+        Field::Notes => account.notes.clone().unwrap_or_default(),
+        Field::Totp => match account.totp.as_deref() {
+            #[cfg(feature = "totp")]
+            Some(secret) => Totp::parse(secret)?.current_code().0,
+            #[cfg(not(feature = "totp"))]
+            Some(_) => return Err(PassManError::InvalidInput("{TOTP} needs the `totp` feature".to_string())),
+            None => String::new(),
+        },
+        Field::Custom(name) => account.custom_field(name)
+            .map(|field| field.value.clone())
+            .unwrap_or_default(),
+    };
+    Ok(Zeroizing::new(value))
+}
+
+/// Split a sequence into text and placeholders
+fn parse(sequence: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = sequence;
+    
+    while let Some(start) = rest.find(['{', '}']) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        
+        // "{{}" and "{}}" are escaped braces
+        if let Some(after) = rest.strip_prefix("{{}") {
+            text.push('{');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("{}}") {
+            text.push('}');
+            rest = after;
+            continue;
+        }
+        if rest.starts_with('}') {
+            return Err(invalid("unmatched '}' (use {}} for a literal brace)"));
+        }
+        
+        let end = rest.find('}')
+            .ok_or_else(|| invalid("unclosed '{' (use {{} for a literal brace)"))?;
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(parse_placeholder(&rest[1..end])?);
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    
+    Ok(tokens)
+}
+
+/// Parse the inside of one `{...}` placeholder
+fn parse_placeholder(placeholder: &str) -> Result<Token> {
+    if let Some(name) = placeholder.strip_prefix("S:").or_else(|| placeholder.strip_prefix("s:")) {
+        if name.trim().is_empty() {
+            return Err(invalid("{S:} needs a custom field name"));
+        }
+        return Ok(Token::Field(Field::Custom(name.trim().to_string())));
+    }
+    
+    let upper = placeholder.trim().to_uppercase();
+    if let Some(ms) = upper.strip_prefix("DELAY ") {
+        let ms: u64 = ms.trim().parse()
+            .map_err(|_| invalid(&format!("invalid delay '{}'", ms.trim())))?;
+        if ms > MAX_DELAY_MS {
+            return Err(invalid(&format!("delay must be at most {} ms", MAX_DELAY_MS)));
+        }
+        return Ok(Token::Delay(ms));
+    }
+    
+    let token = match upper.as_str() {
+        "USERNAME" => Token::Field(Field::Username),
+        "PASSWORD" => Token::Field(Field::Password),
+        "URL" => Token::Field(Field::Url),
+        "TITLE" => Token::Field(Field::Title),
+        "NOTES" => Token::Field(Field::Notes),
+        "TOTP" => Token::Field(Field::Totp),
+        "TAB" => Token::Key(AutotypeKey::Tab),
+        "ENTER" => Token::Key(AutotypeKey::Enter),
+        "SPACE" => Token::Key(AutotypeKey::Space),
+        "BACKSPACE" | "BS" => Token::Key(AutotypeKey::Backspace),
+        "DELETE" | "DEL" => Token::Key(AutotypeKey::Delete),
+        "ESC" => Token::Key(AutotypeKey::Escape),
+        "UP" => Token::Key(AutotypeKey::Up),
+        "DOWN" => Token::Key(AutotypeKey::Down),
+        "LEFT" => Token::Key(AutotypeKey::Left),
+        "RIGHT" => Token::Key(AutotypeKey::Right),
+        "HOME" => Token::Key(AutotypeKey::Home),
+        "END" => Token::Key(AutotypeKey::End),
+        _ => return Err(invalid(&format!("unknown placeholder {{{}}}", placeholder))),
+    };
+    Ok(token)
+}
+
+/// Build an invalid-sequence error
+fn invalid(reason: &str) -> PassManError {
+    PassManError::InvalidInput(format!("Invalid autotype sequence: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AccountType, CustomField};
+    
+    fn text(value: &str) -> AutotypeAction {
+        AutotypeAction::Text(Zeroizing::new(value.to_string()))
+    }
+    
+    #[test]
+    fn test_actions_for_sequences() {
+        let mut account = Account::new("GitHub".to_string(), AccountType::Work, "s3cret".to_string());
+        account.username = Some("octocat".to_string());
+        assert_eq!(actions(&account).unwrap(), [
+            text("octocat"),
+            AutotypeAction::Key(AutotypeKey::Tab),
+            text("s3cret"),
+            AutotypeAction::Key(AutotypeKey::Enter),
+        ]);
+        
+        account.custom_fields.push(CustomField {
+            name: "PIN".to_string(),
+            value: "1234".to_string(),
+            hidden: true,
+        });
+        account.autotype = Some("{username}@{{}x{}}{DELAY 200}{S:pin}{TOTP}{enter}".to_string());
+        assert_eq!(actions(&account).unwrap(), [
+            text("octocat@{x}"),
+            AutotypeAction::Delay(200),
+            text("1234"),
+            AutotypeAction::Key(AutotypeKey::Enter),
+        ]);
+        
+        for bad in ["{PASS}", "{USERNAME", "user}", "{DELAY x}", "{DELAY 60000}", "{S:}"] {
+            assert!(validate_sequence(bad).is_err(), "{} should be rejected", bad);
+        }
+        assert!(validate_sequence(DEFAULT_SEQUENCE).is_ok());
+    }
+    
+    #[test]
+    fn test_match_window() {
+        let mut github = Account::new("GitHub".to_string(), AccountType::Work, "x".to_string());
+        github.url = Some("https://www.github.com/login".to_string());
+        let mut git = Account::new("Git".to_string(), AccountType::Work, "x".to_string());
+        git.url = Some("https://git-scm.com".to_string());
+        let mut archived = Account::new("GitHub old".to_string(), AccountType::Work, "x".to_string());
+        archived.archived = true;
+        let me = Account::new("Me".to_string(), AccountType::Personal, "x".to_string());
+        let accounts = [&git, &github, &archived, &me];
+        
+        let names = |title: &str| -> Vec<String> {
+            match_window(title, &accounts).into_iter().map(|a| a.name.clone()).collect()
+        };
+        assert_eq!(names("Sign in to GitHub · github.com — Mozilla Firefox"), ["GitHub", "Git"]);
+        assert_eq!(names("Some Game - Steam"), Vec::<String>::new());
+        assert!(names("").is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! # Base32
+//! 
+//! RFC 4648 base32, as used by TOTP secrets, recovery and emergency keys,
+//! and pairing codes.
+
+use crate::{PassManError, Result};
+
+/// Base32 alphabet (RFC 4648)
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Decode an RFC 4648 base32 string, ignoring case, spaces, and padding
+pub(crate) fn decode(input: &str) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u64;
+    let mut bits = 0u32;
+    
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '-' && *c != '=') {
+        let value = ALPHABET.iter()
+            .position(|&b| b == c.to_ascii_uppercase() as u8)
+            .ok_or_else(|| PassManError::InvalidInput(format!("Invalid base32 character: '{}'", c)))?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    
+    if output.is_empty() {
+        return Err(PassManError::InvalidInput("Empty base32 value".to_string()));
+    }
+    
+    Ok(output)
+}
+
+/// Encode bytes as unpadded RFC 4648 base32
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer = 0u64;
+    let mut bits = 0u32;
+    
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_roundtrip() {
+        let data = b"12345678901234567890";
+        let encoded = encode(data);
+        assert_eq!(encoded, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(decode(&encoded.to_lowercase()).unwrap(), data);
+    }
+}
@@ -0,0 +1,382 @@
+//! # Offline Breach Data
+//! 
+//! Checks passwords against the Have I Been Pwned "Pwned Passwords" list
+//! without any network access. PassMan never downloads anything itself:
+//! the user fetches the SHA-1 "ordered by hash" dataset (one
+//! `HASH:COUNT` line per password, e.g. with the official downloader)
+//! and ingests it once. Ingesting distills the dataset into a Bloom
+//! filter in the passman config directory (`breaches.bloom`), a fraction
+//! of the dataset's size; a filter file built elsewhere can be installed
+//! the same way.
+//! 
+//! A Bloom filter has no false negatives, but a small share of passwords
+//! that were never breached are reported as breached (0.1% with the
+//! default `DEFAULT_FALSE_POSITIVE_RATE`). Lookups read only the few bits
+//! a password maps to, so the filter is never loaded into memory.
+//! 
+//! Filter file layout: `PMBLOOM1`, then little-endian `u32` hash count,
+//! `u64` bit count, `u64` entry count, and the bits as `u64` words.
+//! 
+//! With the opt-in `breach-online` feature, `online` checks passwords
+//! against the Have I Been Pwned API instead, sending only hash prefixes
+//! and only with the caller's consent.
+
+#[cfg(feature = "breach-online")]
+pub mod online;
+
+use crate::{PassManError, Result, config::passman_config_dir};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the installed filter inside the passman config directory
+pub const BREACH_FILTER_FILE: &str = "breaches.bloom";
+
+/// Share of unbreached passwords a new filter reports as breached
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.001;
+
+/// Magic bytes at the start of filter files
+const FILTER_MAGIC: &[u8; 8] = b"PMBLOOM1";
+
+/// Length of the header after the magic
+const HEADER_LEN: u64 = 4 + 8 + 8;
+
+/// Shortest dataset line: 40 hex digits, `:`, one count digit, newline
+const MIN_LINE_LEN: u64 = 43;
+
+/// A Bloom filter of breached password hashes, built in memory
+pub struct BreachFilter {
+    words: Vec<u64>,
+    num_bits: u64,
+    hashes: u32,
+    count: u64,
+}
+
+impl BreachFilter {
+    /// Create an empty filter sized for a number of hashes
+    /// 
+    /// # Arguments
+    /// * `expected` - How many hashes will be inserted
+    /// * `false_positive_rate` - Acceptable share of false positives, between 0 and 1
+    /// 
+    /// # Errors
+    /// Returns an error if the rate is out of range
+    pub fn new(expected: u64, false_positive_rate: f64) -> Result<Self> {
+        if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+            return Err(PassManError::InvalidInput("False positive rate must be between 0 and 1".to_string()));
+        }
+        let ln2 = std::f64::consts::LN_2;
+        let expected = expected.max(1) as f64;
+        let num_bits = ((-expected * false_positive_rate.ln() / (ln2 * ln2)).ceil() as u64).max(64);
+        let hashes = ((num_bits as f64 / expected * ln2).round() as u32).clamp(1, 30);
+        
+        Ok(Self {
+            words: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            hashes,
+            count: 0,
+        })
+    }
+    
+    /// Build a filter from a Pwned Passwords dataset
+    /// 
+    /// # Arguments
+    /// * `reader` - The dataset, one `HASH:COUNT` line per password
+    /// * `expected` - Upper bound on the number of lines
+    /// * `false_positive_rate` - Acceptable share of false positives
+    /// 
+    /// # Errors
+    /// Returns an error if the dataset can't be read or a line isn't a SHA-1 hash
+    pub fn ingest(reader: impl BufRead, expected: u64, false_positive_rate: f64) -> Result<Self> {
+        let mut filter = Self::new(expected, false_positive_rate)?;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let hash = line.split(':').next().unwrap_or_default().trim();
+            if hash.is_empty() {
+                continue;
+            }
+            let hash = parse_sha1(hash).ok_or_else(|| PassManError::InvalidInput(format!(
+                "Line {} of the dataset is not a SHA-1 hash; use the SHA-1 dataset",
+                number + 1,
+            )))?;
+            filter.insert_hash(&hash);
+        }
+        Ok(filter)
+    }
+    
+    /// Add a password's SHA-1 hash
+    pub fn insert_hash(&mut self, hash: &[u8; 20]) {
+        for bit in bit_indexes(hash, self.hashes, self.num_bits) {
+            self.words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+    }
+    
+    /// Check whether a password may be in the filter
+    pub fn contains(&self, password: &str) -> bool {
+        bit_indexes(&sha1(password), self.hashes, self.num_bits)
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+    
+    /// Number of hashes inserted
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+    
+    /// Check whether no hashes were inserted
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+    
+    /// Write the filter to a file, replacing it atomically
+    /// 
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("tmp");
+        {
+            let mut file = BufWriter::new(File::create(&temp_path)?);
+            file.write_all(FILTER_MAGIC)?;
+            file.write_all(&self.hashes.to_le_bytes())?;
+            file.write_all(&self.num_bits.to_le_bytes())?;
+            file.write_all(&self.count.to_le_bytes())?;
+            for word in &self.words {
+                file.write_all(&word.to_le_bytes())?;
+            }
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        fs::rename(&temp_path, path)
+            .map_err(|e| PassManError::StorageError(format!("Failed to save breach filter: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// A filter file, queried without loading it
+pub struct BreachFilterFile {
+    file: File,
+    num_bits: u64,
+    hashes: u32,
+    count: u64,
+}
+
+impl BreachFilterFile {
+    /// Open a filter file
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or isn't a breach filter
+    pub fn open(path: &Path) -> Result<Self> {
+        let invalid = || PassManError::InvalidInput(format!("{} is not a PassMan breach filter", path.display()));
+        let mut file = File::open(path)?;
+        let mut header = [0u8; FILTER_MAGIC.len() + HEADER_LEN as usize];
+        file.read_exact(&mut header).map_err(|_| invalid())?;
+        if !header.starts_with(FILTER_MAGIC) {
+            return Err(invalid());
+        }
+        
+        let hashes = u32::from_le_bytes(header[8..12].try_into().expect("4 bytes"));
+        let num_bits = u64::from_le_bytes(header[12..20].try_into().expect("8 bytes"));
+        let count = u64::from_le_bytes(header[20..28].try_into().expect("8 bytes"));
+        let expected_len = header.len() as u64 + num_bits.div_ceil(64) * 8;
+        if hashes == 0 || num_bits == 0 || file.metadata()?.len() != expected_len {
+            return Err(invalid());
+        }
+        
+        Ok(Self { file, num_bits, hashes, count })
+    }
+    
+    /// Check whether a password may be breached
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read
+    pub fn contains(&self, password: &str) -> Result<bool> {
+        let mut file = &self.file;
+        for bit in bit_indexes(&sha1(password), self.hashes, self.num_bits) {
+            let offset = FILTER_MAGIC.len() as u64 + HEADER_LEN + (bit / 64) * 8;
+            let mut word = [0u8; 8];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut word)?;
+            if u64::from_le_bytes(word) & (1 << (bit % 64)) == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+    
+    /// Number of breached passwords in the filter
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+    
+    /// Check whether the filter holds no passwords
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Path of the installed filter
+/// 
+/// # Errors
+/// Returns an error if the config directory cannot be determined
+pub fn filter_path() -> Result<PathBuf> {
+    Ok(passman_config_dir()?.join(BREACH_FILTER_FILE))
+}
+
+/// Open the installed filter, if there is one
+/// 
+/// # Errors
+/// Returns an error if the installed filter can't be read
+pub fn installed_filter() -> Result<Option<BreachFilterFile>> {
+    let path = filter_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    BreachFilterFile::open(&path).map(Some)
+}
+
+/// Install breach data from a file
+/// 
+/// A filter file is copied as is; anything else is read as the SHA-1
+/// Pwned Passwords dataset and distilled into a new filter, which for the
+/// full dataset takes a while.
+/// 
+/// # Arguments
+/// * `source` - The dataset or a filter file
+/// * `false_positive_rate` - Rate for a filter built from a dataset
+/// 
+/// # Returns
+/// Number of breached passwords in the installed filter
+/// 
+/// # Errors
+/// Returns an error if the file can't be read or isn't valid breach data
+pub fn install(source: &Path, false_positive_rate: f64) -> Result<u64> {
+    let target = filter_path()?;
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    
+    let mut magic = [0u8; 8];
+    let is_filter = File::open(source)?.read_exact(&mut magic).is_ok() && &magic == FILTER_MAGIC;
+    if is_filter {
+        let count = BreachFilterFile::open(source)?.len();
+        let temp_path = target.with_extension("tmp");
+        fs::copy(source, &temp_path)?;
+        fs::rename(&temp_path, &target)?;
+        return Ok(count);
+    }
+    
+    let expected = fs::metadata(source)?.len() / MIN_LINE_LEN;
+    let filter = BreachFilter::ingest(BufReader::new(File::open(source)?), expected, false_positive_rate)?;
+    if filter.is_empty() {
+        return Err(PassManError::InvalidInput(format!("{} holds no password hashes", source.display())));
+    }
+    filter.save(&target)?;
+    Ok(filter.len())
+}
+
+/// Delete the installed filter
+/// 
+/// # Returns
+/// True if a filter was installed
+/// 
+/// # Errors
+/// Returns an error if the filter can't be deleted
+pub fn uninstall() -> Result<bool> {
+    let path = filter_path()?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path)?;
+    Ok(true)
+}
+
+/// Bit positions of a hash, by double hashing its first 16 bytes
+fn bit_indexes(hash: &[u8; 20], hashes: u32, num_bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = u64::from_le_bytes(hash[0..8].try_into().expect("8 bytes"));
+    let h2 = u64::from_le_bytes(hash[8..16].try_into().expect("8 bytes")) | 1;
+    (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+}
+
+fn sha1(password: &str) -> [u8; 20] {
+    Sha1::digest(password.as_bytes()).into()
+}
+
+fn parse_sha1(hex: &str) -> Option<[u8; 20]> {
+    if hex.len() != 40 {
+        return None;
+    }
+    let mut hash = [0u8; 20];
+    for (byte, pair) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn dataset(passwords: &[&str]) -> String {
+        let mut lines: Vec<String> = passwords.iter()
+            .map(|password| {
+                let hex: String = sha1(password).iter().map(|b| format!("{:02X}", b)).collect();
+                format!("{}:42\r\n", hex)
+            })
+            .collect();
+        lines.sort();
+        lines.concat()
+    }
+    
+    #[test]
+    fn test_ingest_and_lookup() {
+        let breached = ["password", "123456", "qwerty", "letmein"];
+        let data = dataset(&breached);
+        let filter = BreachFilter::ingest(data.as_bytes(), breached.len() as u64, 0.001).unwrap();
+        assert_eq!(filter.len(), 4);
+        assert!(breached.iter().all(|password| filter.contains(password)));
+        assert!(!filter.contains("x7#Kq!2mZ$9vLp@4Rw"));
+        
+        assert!(BreachFilter::ingest("not a hash:1\n".as_bytes(), 1, 0.001).is_err());
+        assert!(BreachFilter::new(1, 1.5).is_err());
+    }
+    
+    #[test]
+    fn test_filter_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BREACH_FILTER_FILE);
+        let passwords: Vec<String> = (0..1000).map(|i| format!("breached-{}", i)).collect();
+        let mut filter = BreachFilter::new(1000, 0.001).unwrap();
+        for password in &passwords {
+            filter.insert_hash(&sha1(password));
+        }
+        filter.save(&path).unwrap();
+        
+        let file = BreachFilterFile::open(&path).unwrap();
+        assert_eq!(file.len(), 1000);
+        assert!(passwords.iter().all(|password| file.contains(password).unwrap()));
+        let false_positives = (0..1000).filter(|i| file.contains(&format!("safe-{}", i)).unwrap()).count();
+        assert!(false_positives < 10, "{} false positives", false_positives);
+        
+        fs::write(dir.path().join("other"), b"PMBLOOM1 truncated").unwrap();
+        assert!(BreachFilterFile::open(&dir.path().join("other")).is_err());
+    }
+    
+    #[test]
+    fn test_install() {
+        crate::testing::isolate();
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+        fs::write(&source, dataset(&["password", "hunter2"])).unwrap();
+        
+        // Both a filter file and the dataset itself can be installed
+        let filter = dir.path().join("copy.bloom");
+        BreachFilter::ingest(BufReader::new(File::open(&source).unwrap()), 2, 0.001).unwrap().save(&filter).unwrap();
+        assert_eq!(install(&filter, DEFAULT_FALSE_POSITIVE_RATE).unwrap(), 2);
+        assert_eq!(install(&source, DEFAULT_FALSE_POSITIVE_RATE).unwrap(), 2);
+        
+        let installed = installed_filter().unwrap().unwrap();
+        assert!(installed.contains("hunter2").unwrap());
+        assert!(uninstall().unwrap());
+        assert!(installed_filter().unwrap().is_none());
+    }
+}
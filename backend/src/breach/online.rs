@@ -0,0 +1,233 @@
+//! # Online Breach Checks
+//! 
+//! Checks passwords against Have I Been Pwned's Pwned Passwords range API
+//! using its k-anonymity scheme: only the first 5 hex digits of a
+//! password's SHA-1 hash are sent, the API answers with every breached
+//! hash starting with them, and the match is made locally. Responses are
+//! padded with fake entries so their size gives nothing away either.
+//! 
+//! Nothing is sent unless the caller passes a `NetworkConsent`, which
+//! each check consumes, so consent is given for one check at a time.
+
+use super::sha1;
+use crate::{PassManError, Result};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// The range API, followed by a 5-digit hash prefix
+pub const RANGE_API_URL: &str = "https://api.pwnedpasswords.com/range/";
+
+/// Hex digits of the hash sent to the API
+const PREFIX_LEN: usize = 5;
+
+/// How long one range request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The user's go-ahead for one online breach check
+/// 
+/// Created only by `NetworkConsent::granted` and consumed by the check,
+/// so every check that contacts Have I Been Pwned is one the caller
+/// explicitly asked for.
+#[derive(Debug)]
+pub struct NetworkConsent(());
+
+impl NetworkConsent {
+    /// Record that the user agreed to send hash prefixes to Have I Been Pwned
+    pub fn granted() -> Self {
+        Self(())
+    }
+}
+
+/// Client for the Pwned Passwords range API
+pub struct RangeClient {
+    agent: ureq::Agent,
+    base_url: String,
+}
+
+impl Default for RangeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RangeClient {
+    /// Create a client for Have I Been Pwned
+    pub fn new() -> Self {
+        Self::with_base_url(RANGE_API_URL)
+    }
+    
+    /// Create a client for another server speaking the range API, e.g. a mirror
+    /// 
+    /// # Arguments
+    /// * `base_url` - URL the hash prefix is appended to
+    pub fn with_base_url(base_url: &str) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("passman/", env!("CARGO_PKG_VERSION")))
+            .build();
+        Self {
+            agent,
+            base_url: base_url.to_string(),
+        }
+    }
+    
+    /// Count how often a password appears in breaches
+    /// 
+    /// # Arguments
+    /// * `password` - The password to check
+    /// * `consent` - The user's go-ahead for this check
+    /// 
+    /// # Returns
+    /// How many times the password was seen in breaches (0 if never)
+    /// 
+    /// # Errors
+    /// Returns an error if the API can't be reached
+    pub fn pwned_count(&self, password: &str, consent: NetworkConsent) -> Result<u64> {
+        Ok(self.check_passwords([password], consent)?.count(password))
+    }
+    
+    /// Check several passwords, asking for each hash prefix once
+    /// 
+    /// # Arguments
+    /// * `passwords` - The passwords to check
+    /// * `consent` - The user's go-ahead for this check
+    /// 
+    /// # Returns
+    /// The breached passwords among them
+    /// 
+    /// # Errors
+    /// Returns an error if the API can't be reached
+    pub fn check_passwords<'a>(&self, passwords: impl IntoIterator<Item = &'a str>, consent: NetworkConsent) -> Result<BreachedPasswords> {
+        let _ = consent;
+        let hashes: HashSet<String> = passwords.into_iter().map(|password| hex(&sha1(password))).collect();
+        let mut prefixes: Vec<&str> = hashes.iter().map(|hash| &hash[..PREFIX_LEN]).collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+        
+        let mut breached = BreachedPasswords::default();
+        for prefix in prefixes {
+            let range = self.fetch_range(prefix)?;
+            for hash in hashes.iter().filter(|hash| hash.starts_with(prefix)) {
+                if let Some(count) = range_count(&range, &hash[PREFIX_LEN..]) {
+                    breached.counts.insert(hash.clone(), count);
+                }
+            }
+        }
+        Ok(breached)
+    }
+    
+    /// Get the `SUFFIX:COUNT` lines for a hash prefix
+    fn fetch_range(&self, prefix: &str) -> Result<String> {
+        let failed = |e: &dyn std::fmt::Display| {
+            PassManError::IoError(std::io::Error::other(format!("Have I Been Pwned request failed: {}", e)))
+        };
+        self.agent.get(&format!("{}{}", self.base_url, prefix))
+            .set("Add-Padding", "true")
+            .call()
+            .map_err(|e| failed(&e))?
+            .into_string()
+            .map_err(|e| failed(&e))
+    }
+}
+
+/// Passwords found by an online breach check
+#[derive(Debug, Default)]
+pub struct BreachedPasswords {
+    /// Breach count by uppercase hex SHA-1 hash
+    counts: HashMap<String, u64>,
+}
+
+impl BreachedPasswords {
+    /// How many times a checked password was seen in breaches (0 if never)
+    pub fn count(&self, password: &str) -> u64 {
+        self.counts.get(&hex(&sha1(password))).copied().unwrap_or(0)
+    }
+    
+    /// Check whether a checked password was seen in breaches
+    pub fn contains(&self, password: &str) -> bool {
+        self.count(password) > 0
+    }
+    
+    /// Number of distinct breached passwords
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+    
+    /// Check whether none of the passwords were breached
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+/// Find a hash suffix in a range response
+/// 
+/// Padding entries have a count of 0 and don't count as breached.
+fn range_count(range: &str, suffix: &str) -> Option<u64> {
+    range.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .filter(|&count| count > 0)
+}
+
+fn hex(hash: &[u8; 20]) -> String {
+    hash.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    
+    /// SHA-1 of "password"
+    const PASSWORD_SHA1: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+    
+    #[test]
+    fn test_range_count() {
+        let range = "1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n0018A45C4D1DEF81644B54AB7F969B88D65:0\r\n";
+        assert_eq!(range_count(range, &PASSWORD_SHA1[PREFIX_LEN..]), Some(9659365));
+        assert_eq!(range_count(range, "0018a45c4d1def81644b54ab7f969b88d65"), None);
+        assert_eq!(range_count(range, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), None);
+    }
+    
+    #[test]
+    fn test_only_prefixes_are_sent() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/range/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            // "password" and "password" again share one prefix; "hunter2" has another
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut padding = false;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    padding |= header.eq_ignore_ascii_case("add-padding: true\r\n");
+                    if header == "\r\n" {
+                        break;
+                    }
+                }
+                assert!(padding);
+                let body = format!("{}:3861493\r\nAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA:0\r\n", &PASSWORD_SHA1[PREFIX_LEN..]);
+                write!(reader.get_mut(), "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+        
+        let client = RangeClient::with_base_url(&base_url);
+        let breached = client.check_passwords(["password", "hunter2", "password"], NetworkConsent::granted()).unwrap();
+        assert_eq!(breached.count("password"), 3861493);
+        assert!(!breached.contains("hunter2"));
+        assert_eq!(breached.len(), 1);
+        
+        let mut requests = server.join().unwrap();
+        requests.sort();
+        assert_eq!(requests, ["GET /range/5BAA6 HTTP/1.1\r\n", "GET /range/F3BBB HTTP/1.1\r\n"]);
+    }
+}
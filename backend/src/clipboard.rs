@@ -0,0 +1,163 @@
+//! # Clipboard Support
+//! 
+//! This module wraps the system clipboard so front-ends can copy secrets
+//! without ever displaying them (`clipboard` feature).
+//! 
+//! Copied text is marked as sensitive so clipboard managers and history
+//! features skip it where the platform has a convention for that:
+//! 
+//! - Linux: the `x-kde-passwordManagerHint` MIME type (Klipper, GPaste,
+//!   and most other clipboard managers honor it)
+//! - macOS: the `org.nspasteboard.ConcealedType` pasteboard type
+//! - Windows: `ExcludeClipboardContentFromMonitorProcessing` (clipboard
+//!   history and cloud sync) and `CF_CLIPBOARD_VIEWER_IGNORE` (third-party
+//!   clipboard viewers)
+//! 
+//! Clearing is verified by reading the clipboard back, since a clipboard
+//! manager may restore the text right after it is cleared.
+
+use crate::{PassManError, Result};
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// How often clearing is attempted before giving up
+const CLEAR_ATTEMPTS: usize = 3;
+/// Time for clipboard managers to react before a clear is verified
+const CLEAR_SETTLE: Duration = Duration::from_millis(250);
+
+/// System clipboard handle
+pub struct Clipboard {
+    /// Platform clipboard context
+    ctx: arboard::Clipboard,
+}
+
+impl Clipboard {
+    /// Open the system clipboard
+    /// 
+    /// # Returns
+    /// A new Clipboard instance
+    /// 
+    /// # Errors
+    /// Returns an error if no clipboard is available (e.g. headless session)
+    pub fn new() -> Result<Self> {
+        let ctx = arboard::Clipboard::new().map_err(clipboard_error)?;
+        Ok(Self { ctx })
+    }
+    
+    /// Put text on the clipboard, excluded from clipboard history
+    /// 
+    /// # Arguments
+    /// * `text` - The text to copy
+    /// 
+    /// # Returns
+    /// Unit on success
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        exclude_from_history(self.ctx.set()).text(text).map_err(clipboard_error)?;
+        
+        #[cfg(windows)]
+        ignore_in_clipboard_viewers()?;
+        
+        Ok(())
+    }
+    
+    /// Wait for the given timeout, then clear the clipboard
+    /// 
+    /// The clipboard is only cleared if it still holds `text`, so anything
+    /// the user copied in the meantime is left alone.
+    /// 
+    /// # Arguments
+    /// * `text` - The text that was copied
+    /// * `timeout` - How long to wait before clearing
+    /// 
+    /// # Returns
+    /// True if the clipboard was cleared
+    /// 
+    /// # Errors
+    /// Returns an error if the clipboard still holds `text` after clearing it
+    pub fn clear_after(&mut self, text: &str, timeout: Duration) -> Result<bool> {
+        thread::sleep(timeout);
+        
+        if !self.holds(text) {
+            return Ok(false);
+        }
+        
+        for _ in 0..CLEAR_ATTEMPTS {
+            self.ctx.clear().map_err(clipboard_error)?;
+            thread::sleep(CLEAR_SETTLE);
+            if !self.holds(text) {
+                return Ok(true);
+            }
+        }
+        
+        Err(PassManError::IoError(io::Error::other(
+            "The clipboard still holds the copied secret; clear it manually (a clipboard manager may be restoring it)",
+        )))
+    }
+    
+    /// Check whether the clipboard currently holds `text`
+    fn holds(&mut self, text: &str) -> bool {
+        self.ctx.get_text().is_ok_and(|current| current == text)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+    use arboard::SetExtLinux;
+    set.exclude_from_history()
+}
+
+#[cfg(target_os = "macos")]
+fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+    use arboard::SetExtApple;
+    set.exclude_from_history()
+}
+
+#[cfg(windows)]
+fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+    use arboard::SetExtWindows;
+    // Covers both clipboard history and cloud clipboard sync
+    set.exclude_from_monitoring()
+}
+
+/// Add the `CF_CLIPBOARD_VIEWER_IGNORE` format to the current clipboard content
+/// 
+/// Third-party clipboard viewers (Ditto, ClipboardFusion, ...) skip content
+/// with this format; it is added next to the text without replacing it.
+#[cfg(windows)]
+fn ignore_in_clipboard_viewers() -> Result<()> {
+    use windows_sys::Win32::Foundation::GlobalFree;
+    use windows_sys::Win32::System::DataExchange::{
+        CloseClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows_sys::Win32::System::Memory::{GlobalAlloc, GMEM_MOVEABLE, GMEM_ZEROINIT};
+    
+    let name: Vec<u16> = "Clipboard Viewer Ignore".encode_utf16().chain([0]).collect();
+    // SAFETY: `name` is NUL-terminated, and the clipboard is closed on every
+    // path after a successful open. The allocation is owned by the system
+    // once SetClipboardData succeeds and freed here otherwise.
+    let set = unsafe {
+        let format = RegisterClipboardFormatW(name.as_ptr());
+        if format == 0 || OpenClipboard(std::ptr::null_mut()) == 0 {
+            false
+        } else {
+            let data = GlobalAlloc(GMEM_MOVEABLE | GMEM_ZEROINIT, 4);
+            let set = !data.is_null() && !SetClipboardData(format, data).is_null();
+            if !set && !data.is_null() {
+                GlobalFree(data);
+            }
+            CloseClipboard();
+            set
+        }
+    };
+    
+    if !set {
+        return Err(PassManError::IoError(io::Error::other("Failed to hide the copied secret from clipboard viewers")));
+    }
+    Ok(())
+}
+
+/// Convert a clipboard provider error into a PassMan error
+fn clipboard_error(e: arboard::Error) -> PassManError {
+    PassManError::IoError(io::Error::other(format!("Clipboard unavailable: {}", e)))
+}
@@ -0,0 +1,357 @@
+//! # Library Configuration
+//! 
+//! `PassManConfig` holds the settings every front-end shares: where vaults
+//! live, how new vaults derive their keys, and how backups are kept. It is
+//! stored in `config.toml` in PassMan's config directory
+//! (`~/.config/passman/config.toml` on Linux), next to front-end settings
+//! such as the CLI's; saving it keeps keys it doesn't own.
+//! 
+//! Every setting is optional; a missing file means all defaults.
+
+use crate::{PassManError, Result, crypto::KdfParams};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the config file inside the passman config directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Backups kept per vault unless configured otherwise
+pub const DEFAULT_KEEP_BACKUPS: usize = 10;
+
+/// Config directory set by `testing::isolate`, used instead of the platform one
+#[cfg(any(test, feature = "testing"))]
+static CONFIG_DIR_OVERRIDE: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Top-level keys of `config.toml` that belong to `PassManConfig`
+const LIBRARY_KEYS: [&str; 8] = ["vault_dir", "storage", "kdf", "backups", "reminders", "keep_unlocked_on_screen_lock", "locale", "telemetry"];
+
+/// Settings shared by all PassMan front-ends
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PassManConfig {
+    /// Directory holding the vault files (default: `passman/vaults` in
+    /// the config directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_dir: Option<PathBuf>,
+    
+    /// How vaults are kept in the vault directory
+    #[serde(skip_serializing_if = "StorageEngine::is_default")]
+    pub storage: StorageEngine,
+    
+    /// Key derivation parameters for new vaults (default: `KdfParams::default()`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    
+    /// How vault backups are kept
+    #[serde(skip_serializing_if = "BackupPolicy::is_default")]
+    pub backups: BackupPolicy,
+    
+    /// When front-ends remind the user to change old passwords
+    #[serde(skip_serializing_if = "ReminderPolicy::is_default")]
+    pub reminders: ReminderPolicy,
+    
+    /// Keep vaults open when the screen locks or the system sleeps (front-ends
+    /// lock them by default; see `screenlock`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub keep_unlocked_on_screen_lock: bool,
+    
+    /// Language for front-end text, e.g. "de-DE" (default: the system's)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    
+    /// Placeholder: PassMan collects no telemetry, and this is always off
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub telemetry: bool,
+}
+
+/// How vaults are kept in the vault directory
+/// 
+/// Backups are files in the backup directory either way.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageEngine {
+    /// A file per vault, and one per account record
+    #[default]
+    Files,
+    /// All vaults in `vaults.db`, a row per vault file and account record
+    /// (needs the `sqlite` feature); new vaults keep a record per account
+    Sqlite,
+}
+
+impl StorageEngine {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How vault backups are kept
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BackupPolicy {
+    /// Back up the vault file before every save
+    pub automatic: bool,
+    
+    /// Backups kept per vault; older ones are deleted
+    pub keep: usize,
+    
+    /// Directory holding the backups (default: `backups` in the vault directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            automatic: true,
+            keep: DEFAULT_KEEP_BACKUPS,
+            dir: None,
+        }
+    }
+}
+
+impl BackupPolicy {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// When front-ends remind the user to change old passwords
+/// 
+/// See `reminders::ReminderScheduler`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ReminderPolicy {
+    /// Send password change reminders
+    pub enabled: bool,
+    
+    /// Age at which a password is due for a change
+    pub max_age_days: u32,
+    
+    /// Days before the due date to start reminding
+    pub warn_days: u32,
+    
+    /// Days before reminding about the same account again
+    pub repeat_days: u32,
+    
+    /// Hours between checks
+    pub interval_hours: u32,
+}
+
+impl Default for ReminderPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 365,
+            warn_days: 14,
+            repeat_days: 7,
+            interval_hours: 24,
+        }
+    }
+}
+
+impl ReminderPolicy {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl PassManConfig {
+    /// Load the config file, falling back to defaults if it doesn't exist
+    /// 
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed
+    pub fn load() -> Result<Self> {
+        Self::load_from(&config_path()?)
+    }
+    
+    /// Load a config file, falling back to defaults if it doesn't exist
+    /// 
+    /// # Arguments
+    /// * `path` - The config file
+    /// 
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        
+        let contents = std::fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&contents)
+            .map_err(|e| PassManError::InvalidInput(format!("Invalid config file {}: {}", path.display(), e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Write the settings to the config file, keeping other keys in it
+    /// 
+    /// # Errors
+    /// Returns an error if the settings are invalid or the file cannot be
+    /// read or written
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&config_path()?)
+    }
+    
+    /// Write the settings to a config file, keeping other keys in it
+    /// 
+    /// # Arguments
+    /// * `path` - The config file
+    /// 
+    /// # Errors
+    /// Returns an error if the settings are invalid or the file cannot be
+    /// read or written
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        self.validate()?;
+        
+        let mut table: toml::Table = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|e| PassManError::InvalidInput(format!("Invalid config file {}: {}", path.display(), e)))?
+        } else {
+            toml::Table::new()
+        };
+        for key in LIBRARY_KEYS {
+            table.remove(key);
+        }
+        table.extend(toml::Table::try_from(self).map_err(toml_error)?);
+        
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(&table).map_err(toml_error)?)?;
+        Ok(())
+    }
+    
+    /// Directory holding the vault files
+    /// 
+    /// # Errors
+    /// Returns an error if no directory is configured and the config
+    /// directory cannot be determined
+    pub fn vault_dir(&self) -> Result<PathBuf> {
+        match &self.vault_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(passman_config_dir()?.join("vaults")),
+        }
+    }
+    
+    /// Directory holding the vault backups
+    /// 
+    /// # Errors
+    /// Returns an error if the vault directory cannot be determined
+    pub fn backup_dir(&self) -> Result<PathBuf> {
+        match &self.backups.dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(self.vault_dir()?.join("backups")),
+        }
+    }
+    
+    /// Key derivation parameters for new vaults
+    pub fn kdf_params(&self) -> KdfParams {
+        self.kdf.unwrap_or_default()
+    }
+    
+    /// Reject settings the library can't honor
+    fn validate(&self) -> Result<()> {
+        if self.telemetry {
+            return Err(PassManError::InvalidInput("telemetry is not supported and must stay false".to_string()));
+        }
+        if self.backups.keep == 0 {
+            return Err(PassManError::InvalidInput("backups.keep must be at least 1".to_string()));
+        }
+        if self.reminders.max_age_days == 0 || self.reminders.interval_hours == 0 {
+            return Err(PassManError::InvalidInput("reminders.max_age_days and reminders.interval_hours must be at least 1".to_string()));
+        }
+        if self.storage == StorageEngine::Sqlite && !cfg!(feature = "sqlite") {
+            return Err(PassManError::InvalidInput("storage = \"sqlite\" needs PassMan built with the sqlite feature".to_string()));
+        }
+        if let Some(kdf) = &self.kdf {
+            kdf.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// PassMan's directory inside the platform config directory
+/// 
+/// # Errors
+/// Returns an error if the config directory cannot be determined
+pub fn passman_config_dir() -> Result<PathBuf> {
+    #[cfg(any(test, feature = "testing"))]
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
+    
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| PassManError::StorageError("Cannot determine config directory".to_string()))?;
+    Ok(config_dir.join("passman"))
+}
+
+/// Path of the config file
+/// 
+/// # Errors
+/// Returns an error if the config directory cannot be determined
+pub fn config_path() -> Result<PathBuf> {
+    Ok(passman_config_dir()?.join(CONFIG_FILE))
+}
+
+/// Use `dir` as the config directory for the rest of the process
+/// 
+/// Only the first call has an effect.
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn override_config_dir(dir: PathBuf) {
+    let _ = CONFIG_DIR_OVERRIDE.set(dir);
+}
+
+fn toml_error(e: impl std::fmt::Display) -> PassManError {
+    PassManError::StorageError(format!("Failed to serialize config: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_save_keeps_other_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "color = \"never\"\nlocale = \"fr-FR\"\n\n[keyfiles]\nmain = \"/tmp/main.key\"\n").unwrap();
+        
+        let mut config = PassManConfig::load_from(&path).unwrap();
+        assert_eq!(config.locale.as_deref(), Some("fr-FR"));
+        assert!(config.backups.automatic);
+        assert_eq!(config.backups.keep, DEFAULT_KEEP_BACKUPS);
+        
+        config.locale = None;
+        config.backups.keep = 3;
+        config.vault_dir = Some(dir.path().join("vaults"));
+        config.save_to(&path).unwrap();
+        
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("color = \"never\""));
+        assert!(contents.contains("main = \"/tmp/main.key\""));
+        assert!(!contents.contains("locale"));
+        
+        let reloaded = PassManConfig::load_from(&path).unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.backup_dir().unwrap(), dir.path().join("vaults").join("backups"));
+        assert_eq!(reloaded.kdf_params(), KdfParams::default());
+    }
+    
+    #[test]
+    fn test_invalid_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        assert_eq!(PassManConfig::load_from(&path).unwrap(), PassManConfig::default());
+        
+        std::fs::write(&path, "telemetry = true\n").unwrap();
+        assert!(PassManConfig::load_from(&path).is_err());
+        
+        std::fs::write(&path, "storage = \"sqlite\"\n").unwrap();
+        assert_eq!(PassManConfig::load_from(&path).is_ok(), cfg!(feature = "sqlite"));
+        
+        let config = PassManConfig {
+            backups: BackupPolicy { keep: 0, ..BackupPolicy::default() },
+            ..PassManConfig::default()
+        };
+        assert!(config.save_to(&path).is_err());
+    }
+}
@@ -4,9 +4,12 @@
 //! using AES-GCM-256 for vault encryption and Argon2id for key derivation.
 
 use aes_gcm::{Aes256Gcm, Key, Nonce, aead::{Aead, KeyInit}};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::{SaltString, rand_core::OsRng}};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version, password_hash::{SaltString, rand_core::OsRng}};
 use rand::RngCore;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
 use crate::{PassManError, Result};
 
 /// Size of the encryption key in bytes (256 bits)
@@ -15,6 +18,18 @@ const KEY_SIZE: usize = 32;
 const NONCE_SIZE: usize = 12;
 /// Size of the salt in bytes (128 bits)
 const SALT_SIZE: usize = 16;
+/// Memory cost used when calibrating key derivation (64 MiB)
+const CALIBRATION_MEMORY_KIB: u32 = 64 * 1024;
+/// Fewest Argon2 iterations calibration will choose
+const MIN_CALIBRATED_ITERATIONS: u32 = 2;
+/// Most Argon2 iterations calibration will choose
+const MAX_CALIBRATED_ITERATIONS: u32 = 64;
+/// Most memory accepted from parameters in an untrusted file (1 GiB)
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+/// Most iterations accepted from parameters in an untrusted file
+pub const MAX_KDF_ITERATIONS: u32 = 100;
+/// Most lanes accepted from parameters in an untrusted file
+pub const MAX_KDF_PARALLELISM: u32 = 16;
 
 /// Secure key container that zeroizes on drop
 #[derive(ZeroizeOnDrop, Clone)]
@@ -26,6 +41,13 @@ impl SecureKey {
         Self(key)
     }
     
+    /// Generate a new random key
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+    
     /// Get the key bytes (use with caution)
     pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
         &self.0
@@ -55,6 +77,138 @@ impl Salt {
     }
 }
 
+/// Argon2id cost parameters for key derivation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    
+    /// Number of passes over memory
+    pub iterations: u32,
+    
+    /// Degree of parallelism (lanes)
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        // Matches Argon2::default(), which vaults created before
+        // configurable parameters were derived with
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Check that Argon2 accepts these parameters
+    /// 
+    /// # Errors
+    /// Returns an error if a parameter is out of range
+    pub fn validate(&self) -> Result<()> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, Some(KEY_SIZE))
+            .map(drop)
+            .map_err(|e| PassManError::CryptoError(format!("Invalid key derivation parameters: {}", e)))
+    }
+    
+    /// Check parameters read from an untrusted file before deriving with them
+    /// 
+    /// Argon2 allocates `memory_kib` up front and runs for `iterations`
+    /// passes, so a crafted file could otherwise exhaust memory or hang.
+    /// 
+    /// # Errors
+    /// Returns an error if a parameter is out of range or above the limits
+    pub fn check_limits(&self) -> Result<()> {
+        if self.memory_kib > MAX_KDF_MEMORY_KIB || self.iterations > MAX_KDF_ITERATIONS || self.parallelism > MAX_KDF_PARALLELISM {
+            return Err(PassManError::CryptoError(format!(
+                "Key derivation parameters ({} KiB, {} iterations, {} lanes) exceed the limits of {} MiB, {} iterations and {} lanes",
+                self.memory_kib,
+                self.iterations,
+                self.parallelism,
+                MAX_KDF_MEMORY_KIB / 1024,
+                MAX_KDF_ITERATIONS,
+                MAX_KDF_PARALLELISM,
+            )));
+        }
+        self.validate()
+    }
+    
+    /// Derive a key from a secret with these parameters
+    /// 
+    /// # Arguments
+    /// * `secret` - The secret to derive from (usually the master password)
+    /// * `salt` - The salt to use for key derivation
+    /// 
+    /// # Returns
+    /// The derived key
+    /// 
+    /// # Errors
+    /// Returns an error if the parameters are invalid or derivation fails
+    pub fn derive(&self, secret: &[u8], salt: &Salt) -> Result<SecureKey> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(KEY_SIZE))
+            .map_err(|e| PassManError::CryptoError(format!("Invalid key derivation parameters: {}", e)))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        
+        let mut key_bytes = [0u8; KEY_SIZE];
+        argon2
+            .hash_password_into(secret, salt.as_bytes(), &mut key_bytes)
+            .map_err(|e| PassManError::CryptoError(format!("Key derivation failed: {}", e)))?;
+        
+        Ok(SecureKey::new(key_bytes))
+    }
+    
+    /// Pick parameters that take roughly `target` to derive on this machine
+    /// 
+    /// Memory is fixed at 64 MiB and the iteration count is scaled from
+    /// the time a single pass takes, within sane bounds.
+    /// 
+    /// # Arguments
+    /// * `target` - Desired unlock time
+    /// 
+    /// # Returns
+    /// The calibrated parameters
+    /// 
+    /// # Errors
+    /// Returns an error if the trial derivation fails
+    pub fn calibrate(target: Duration) -> Result<Self> {
+        let mut params = Self {
+            memory_kib: CALIBRATION_MEMORY_KIB,
+            iterations: 1,
+            parallelism: 1,
+        };
+        
+        let start = Instant::now();
+        params.derive(b"passman calibration", &Salt::generate())?;
+        let per_iteration = start.elapsed().max(Duration::from_millis(1));
+        
+        let iterations = (target.as_secs_f64() / per_iteration.as_secs_f64()).round() as u32;
+        params.iterations = iterations.clamp(MIN_CALIBRATED_ITERATIONS, MAX_CALIBRATED_ITERATIONS);
+        
+        Ok(params)
+    }
+}
+
+/// Combine a master password and optional keyfile into the KDF input
+/// 
+/// The keyfile is hashed so files of any size contribute a fixed-length
+/// value, and both factors are then required to reproduce the key.
+/// 
+/// # Arguments
+/// * `master_password` - The master password
+/// * `keyfile` - Contents of the keyfile, if the vault uses one
+/// 
+/// # Returns
+/// The secret to feed into key derivation
+pub fn master_secret(master_password: &str, keyfile: Option<&[u8]>) -> Zeroizing<Vec<u8>> {
+    let mut secret = Zeroizing::new(master_password.as_bytes().to_vec());
+    if let Some(keyfile) = keyfile {
+        secret.extend_from_slice(&Sha256::digest(keyfile));
+    }
+    secret
+}
+
 /// Cryptographic operations manager
 pub struct CryptoManager {
     /// The encryption key (will be zeroized on drop)
@@ -84,15 +238,7 @@ impl CryptoManager {
     /// # Errors
     /// Returns an error if key derivation fails
     pub fn derive_key(&mut self, master_password: &str, salt: &Salt) -> Result<SecureKey> {
-        let argon2 = Argon2::default();
-        let mut key_bytes = [0u8; KEY_SIZE];
-        
-        // Use the raw salt bytes directly for key derivation
-        argon2
-            .hash_password_into(master_password.as_bytes(), salt.as_bytes(), &mut key_bytes)
-            .map_err(|e| PassManError::CryptoError(format!("Key derivation failed: {}", e)))?;
-        
-        let key = SecureKey::new(key_bytes);
+        let key = KdfParams::default().derive(master_password.as_bytes(), salt)?;
         self.key = Some(key.clone());
         self.salt = Some(salt.clone());
         
@@ -112,6 +258,24 @@ impl CryptoManager {
         Ok((key, salt))
     }
     
+    /// Install an already-derived key and its salt
+    /// 
+    /// # Arguments
+    /// * `key` - The derived encryption key
+    /// * `salt` - The salt the key was derived with
+    pub fn set_key(&mut self, key: SecureKey, salt: Salt) {
+        self.key = Some(key);
+        self.salt = Some(salt);
+    }
+    
+    /// Get the current encryption key
+    /// 
+    /// # Returns
+    /// The key, or None if no key is set
+    pub fn key(&self) -> Option<&SecureKey> {
+        self.key.as_ref()
+    }
+    
     /// Get the currently stored salt
     /// 
     /// # Returns
@@ -184,7 +348,7 @@ impl CryptoManager {
     /// Encrypted data with nonce prepended
     pub fn encrypt_with_key(&self, data: &[u8], key: &SecureKey) -> Result<Vec<u8>> {
         let key = Key::<Aes256Gcm>::from_slice(key.as_bytes());
-        let cipher = Aes256Gcm::new(&key);
+        let cipher = Aes256Gcm::new(key);
         let nonce_bytes = self.generate_nonce();
         let nonce = Nonce::from_slice(&nonce_bytes);
         
@@ -232,7 +396,7 @@ impl CryptoManager {
         
         let (nonce_bytes, ciphertext) = encrypted_data.split_at(NONCE_SIZE);
         let key = Key::<Aes256Gcm>::from_slice(key.as_bytes());
-        let cipher = Aes256Gcm::new(&key);
+        let cipher = Aes256Gcm::new(key);
         let nonce = Nonce::from_slice(nonce_bytes);
         
         let plaintext = cipher
@@ -268,7 +432,7 @@ impl Default for CryptoManager {
 
 /// Utility functions for secure string handling
 pub mod secure_string {
-    use zeroize::{Zeroize, ZeroizeOnDrop};
+    use zeroize::ZeroizeOnDrop;
     
     /// A secure string that zeroizes on drop
     #[derive(ZeroizeOnDrop)]
@@ -331,11 +495,38 @@ mod tests {
         assert_eq!(key1.as_bytes(), key2.as_bytes());
     }
     
+    #[test]
+    fn test_default_params_match_legacy_derivation() {
+        let salt = Salt::generate();
+        let key = CryptoManager::new().derive_key("test_password_123", &salt).unwrap();
+        
+        let mut legacy = [0u8; KEY_SIZE];
+        Argon2::default()
+            .hash_password_into(b"test_password_123", salt.as_bytes(), &mut legacy)
+            .unwrap();
+        assert_eq!(key.as_bytes(), &legacy);
+    }
+    
+    #[test]
+    fn test_keyfile_changes_secret() {
+        let without = master_secret("password", None);
+        let with = master_secret("password", Some(b"keyfile contents"));
+        assert_ne!(*without, *with);
+        assert_eq!(*with, *master_secret("password", Some(b"keyfile contents")));
+    }
+    
+    #[test]
+    fn test_calibration_bounds() {
+        let params = KdfParams::calibrate(Duration::ZERO).unwrap();
+        assert_eq!(params.iterations, MIN_CALIBRATED_ITERATIONS);
+        assert_eq!(params.memory_kib, CALIBRATION_MEMORY_KIB);
+    }
+    
     #[test]
     fn test_encryption_decryption() {
         let mut crypto = CryptoManager::new();
         let password = "test_password_123";
-        let (key, salt) = crypto.generate_key_and_salt(password).unwrap();
+        let (key, _salt) = crypto.generate_key_and_salt(password).unwrap();
         
         let plaintext = b"Hello, World!";
         let encrypted = crypto.encrypt_with_key(plaintext, &key).unwrap();
@@ -0,0 +1,255 @@
+//! # Vault Diff
+//! 
+//! `diff` compares two versions of a vault, such as a backup and the
+//! current vault, and lists the accounts added, removed, and modified
+//! between them. Modified accounts list each changed field; the values of
+//! secret fields (passwords, TOTP secrets, hidden custom fields, SSH
+//! private keys) are masked, so a diff can be shown or logged safely.
+//! 
+//! Accounts are matched by ID; timestamps alone don't count as a change.
+
+use crate::models::{Account, Vault};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Shown instead of the value of a secret field
+pub const MASK: &str = "••••••••";
+
+/// Differences between two versions of a vault
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct VaultDiff {
+    /// Accounts only in the newer version, by name
+    pub added: Vec<AccountRef>,
+    
+    /// Accounts only in the older version, by name
+    pub removed: Vec<AccountRef>,
+    
+    /// Accounts in both versions whose fields differ, by name
+    pub modified: Vec<AccountDiff>,
+}
+
+/// An account named in a diff
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountRef {
+    /// Account ID
+    pub id: Uuid,
+    
+    /// Account name
+    pub name: String,
+}
+
+/// Changed fields of one account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountDiff {
+    /// Account ID
+    pub id: Uuid,
+    
+    /// Account name in the newer version
+    pub name: String,
+    
+    /// The changed fields, in field order
+    pub changes: Vec<FieldChange>,
+}
+
+/// One changed field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    /// Field name (`password`, `tags`, `custom:<name>`, ...)
+    pub field: String,
+    
+    /// Value in the older version, if it was set (`MASK` for secrets)
+    pub old: Option<String>,
+    
+    /// Value in the newer version, if it is set (`MASK` for secrets)
+    pub new: Option<String>,
+    
+    /// Whether the values are masked
+    pub secret: bool,
+}
+
+impl VaultDiff {
+    /// Check whether the versions hold the same accounts
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Compare two versions of a vault
+/// 
+/// # Arguments
+/// * `old` - The older version, e.g. a backup
+/// * `new` - The newer version
+/// 
+/// # Returns
+/// The accounts added, removed, and modified from `old` to `new`, each
+/// list sorted by name
+pub fn diff(old: &Vault, new: &Vault) -> VaultDiff {
+    let mut result = VaultDiff::default();
+    
+    for account in new.accounts.values() {
+        match old.get_account(&account.id) {
+            None => result.added.push(AccountRef::from(account)),
+            Some(previous) => {
+                let changes = diff_accounts(previous, account);
+                if !changes.is_empty() {
+                    result.modified.push(AccountDiff {
+                        id: account.id,
+                        name: account.name.clone(),
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    result.removed = old.accounts.values()
+        .filter(|account| new.get_account(&account.id).is_none())
+        .map(AccountRef::from)
+        .collect();
+    
+    let by_name = |a: &str, b: &str| a.to_lowercase().cmp(&b.to_lowercase());
+    result.added.sort_by(|a, b| by_name(&a.name, &b.name));
+    result.removed.sort_by(|a, b| by_name(&a.name, &b.name));
+    result.modified.sort_by(|a, b| by_name(&a.name, &b.name));
+    result
+}
+
+/// Compare two versions of an account field by field
+/// 
+/// # Arguments
+/// * `old` - The older version
+/// * `new` - The newer version
+/// 
+/// # Returns
+/// The changed fields, with secret values masked
+pub fn diff_accounts(old: &Account, new: &Account) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, old: Option<String>, new: Option<String>, secret: bool| {
+        if old != new {
+            let mask = |value: Option<String>| if secret { value.map(|_| MASK.to_string()) } else { value };
+            changes.push(FieldChange {
+                field: field.to_string(),
+                old: mask(old),
+                new: mask(new),
+                secret,
+            });
+        }
+    };
+    
+    compare("name", Some(old.name.clone()), Some(new.name.clone()), false);
+    compare(
+        "type",
+        Some(old.account_type.display_name().to_string()),
+        Some(new.account_type.display_name().to_string()),
+        false,
+    );
+    compare("url", old.url.clone(), new.url.clone(), false);
+    compare("username", old.username.clone(), new.username.clone(), false);
+    compare("password", Some(old.password.clone()), Some(new.password.clone()), true);
+    compare("notes", old.notes.clone(), new.notes.clone(), false);
+    compare("tags", join(&old.tags), join(&new.tags), false);
+    compare("folder", old.folder.clone(), new.folder.clone(), false);
+    compare("favorite", Some(old.is_favorite.to_string()), Some(new.is_favorite.to_string()), false);
+    compare("color", old.color.clone(), new.color.clone(), false);
+    compare("icon", old.icon.clone(), new.icon.clone(), false);
+    compare("archived", Some(old.archived.to_string()), Some(new.archived.to_string()), false);
+    compare("canary", Some(old.canary.to_string()), Some(new.canary.to_string()), false);
+    compare("expires_at", old.expires_at.map(|date| date.to_rfc3339()), new.expires_at.map(|date| date.to_rfc3339()), false);
+    compare(
+        "rotation_interval_days",
+        old.rotation_interval_days.map(|days| days.to_string()),
+        new.rotation_interval_days.map(|days| days.to_string()),
+        false,
+    );
+    compare("totp", old.totp.clone(), new.totp.clone(), true);
+    compare("autotype", old.autotype.clone(), new.autotype.clone(), false);
+    compare(
+        "ssh_key",
+        old.ssh_key.as_ref().map(|key| key.private_key.clone()),
+        new.ssh_key.as_ref().map(|key| key.private_key.clone()),
+        true,
+    );
+    let card = |account: &Account| account.card.as_ref().and_then(|card| serde_json::to_string(card).ok());
+    compare("card", card(old), card(new), true);
+    let identity = |account: &Account| account.identity.as_ref().and_then(|identity| serde_json::to_string(identity).ok());
+    compare("identity", identity(old), identity(new), true);
+    let policy = |account: &Account| account.site_policy.as_ref().and_then(|policy| serde_json::to_string(policy).ok());
+    compare("site_policy", policy(old), policy(new), false);
+    
+    // Custom fields are matched by name, in the order they appear
+    let mut names: Vec<&str> = old.custom_fields.iter().map(|field| field.name.as_str()).collect();
+    for field in &new.custom_fields {
+        if !names.iter().any(|name| name.eq_ignore_ascii_case(&field.name)) {
+            names.push(&field.name);
+        }
+    }
+    for name in names {
+        let old_field = old.custom_field(name);
+        let new_field = new.custom_field(name);
+        let secret = old_field.is_some_and(|f| f.hidden) || new_field.is_some_and(|f| f.hidden);
+        compare(
+            &format!("custom:{}", name),
+            old_field.map(|f| f.value.clone()),
+            new_field.map(|f| f.value.clone()),
+            secret,
+        );
+    }
+    
+    changes
+}
+
+impl From<&Account> for AccountRef {
+    fn from(account: &Account) -> Self {
+        Self {
+            id: account.id,
+            name: account.name.clone(),
+        }
+    }
+}
+
+fn join(values: &[String]) -> Option<String> {
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AccountType, CustomField};
+    
+    #[test]
+    fn test_diff() {
+        let mut old = Vault::new("test@example.com".to_string());
+        let kept = Account::new("GitHub".to_string(), AccountType::Work, "old secret".to_string());
+        let gone = Account::new("Forum".to_string(), AccountType::Social, "pw".to_string());
+        old.add_account(kept.clone());
+        old.add_account(gone.clone());
+        
+        let mut new = old.clone();
+        assert!(diff(&old, &new).is_empty());
+        
+        new.remove_account(&gone.id);
+        let added = Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string());
+        new.add_account(added.clone());
+        new.update_account(&kept.id, |account| {
+            account.password = "new secret".to_string();
+            account.username = Some("octocat".to_string());
+            account.custom_fields.push(CustomField { name: "PIN".to_string(), value: "1234".to_string(), hidden: true });
+            account.updated_at = chrono::Utc::now();
+        });
+        
+        let changes = diff(&old, &new);
+        assert_eq!(changes.added, [AccountRef::from(&added)]);
+        assert_eq!(changes.removed, [AccountRef::from(&gone)]);
+        assert_eq!(changes.modified.len(), 1);
+        
+        let fields: Vec<_> = changes.modified[0].changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["username", "password", "custom:PIN"]);
+        let password = &changes.modified[0].changes[1];
+        assert_eq!((password.old.as_deref(), password.new.as_deref()), (Some(MASK), Some(MASK)));
+        let pin = &changes.modified[0].changes[2];
+        assert_eq!((pin.old.as_deref(), pin.new.as_deref()), (None, Some(MASK)));
+        
+        let serialized = serde_json::to_string(&changes).unwrap();
+        assert!(!serialized.contains("old secret") && !serialized.contains("new secret"));
+        assert!(!serialized.contains("1234"));
+    }
+}
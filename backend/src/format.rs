@@ -0,0 +1,915 @@
+//! # Vault File Format
+//! 
+//! Parsing and decryption of vault files, independent of where the bytes
+//! come from. `storage` reads and writes them on disk; without the
+//! `storage` feature (e.g. on wasm32) a vault file loaded some other way
+//! can still be decrypted with `open_vault_bytes`.
+//! 
+//! A versioned vault file is `PASSMAN\0`, a little-endian `u32` header
+//! length, the JSON `VaultHeader`, then the encrypted vault. Legacy files
+//! are a 16-byte salt followed by the encrypted vault.
+//! 
+//! Format versions:
+//! 
+//! 1. Legacy layout; the key is derived from the master password directly
+//! 2. Header with KDF parameters and key slots wrapping a random vault key
+//! 3. The header names the payload cipher
+//! 4. Accounts can be kept in separate records (see `VaultHeader::records`)
+//! 
+//! Older files are upgraded when they are opened (see `migrate_header`
+//! and `upgrade_legacy`); files from a newer version are refused.
+//! 
+//! Portable exports are laid out the same way behind `PMEXPORT\0`, with
+//! an `ExportHeader` instead: the payload is encrypted with a key derived
+//! from an export password, so the file opens on any machine without the
+//! vault it came from.
+//! 
+//! In a vault with `records` set, the payload is a series of frames, each
+//! a little-endian `u32` length and a ciphertext. The first frame is the
+//! encrypted `RecordIndex`: the vault without its accounts, and the
+//! SHA-256 digest of each account's encrypted record. Saving rewrites
+//! only the records of accounts that changed; unchanged records stay as
+//! they are. The vault file itself holds just the index, and `storage`
+//! keeps each record in its own blob named by its digest. A file that
+//! has to stand alone (a backup, or a vault sent to another device) has
+//! the records appended as further frames.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{PassManError, Result, models::{Account, Vault}, crypto::{self, CryptoManager, KdfParams, Salt, SecureKey}};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+#[cfg(feature = "storage")]
+use std::io;
+use zeroize::Zeroizing;
+
+/// Initial size of the buffer a vault is serialized into
+#[cfg(feature = "storage")]
+const SERIALIZE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Magic bytes at the start of versioned vault files
+const VAULT_MAGIC: &[u8; 8] = b"PASSMAN\0";
+/// Current vault file format version
+const FORMAT_VERSION: u32 = 4;
+/// First format version with a header (version 1 is the legacy layout)
+const FIRST_HEADER_VERSION: u32 = 2;
+
+/// Magic bytes at the start of portable exports
+const EXPORT_MAGIC: &[u8; 8] = b"PMEXPORT";
+/// Current portable export format version
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Header upgrades, one per format version after `FIRST_HEADER_VERSION`
+/// 
+/// `MIGRATIONS[i]` turns a version `FIRST_HEADER_VERSION + i` header into
+/// the next version; the version number itself is bumped by `migrate_header`.
+#[cfg(feature = "storage")]
+const MIGRATIONS: [fn(&mut VaultHeader); (FORMAT_VERSION - FIRST_HEADER_VERSION) as usize] = [
+    // 2 -> 3: version 2 always used AES-256-GCM
+    |header| header.cipher = Cipher::Aes256Gcm,
+    // 3 -> 4: version 3 kept every account in the payload
+    |header| header.records = false,
+];
+
+/// Cipher a vault payload is encrypted with
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Cipher {
+    /// AES-256-GCM, with the 96-bit nonce prefixed to the ciphertext
+    #[default]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+}
+
+impl Cipher {
+    /// Human-readable name of the cipher
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cipher::Aes256Gcm => "AES-256-GCM",
+        }
+    }
+}
+
+/// What a key slot's wrapping key is derived from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySlotKind {
+    /// Master password (and keyfile, if the vault uses one)
+    Password,
+    
+    /// Randomly generated recovery key
+    Recovery,
+    
+    /// Random key held in one device's OS keystore (e.g. for biometric unlock)
+    Device,
+    
+    /// Random key given to an emergency contact, usable after a waiting period
+    Emergency,
+}
+
+impl KeySlotKind {
+    /// Human-readable name of the unlock method
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeySlotKind::Password => "master password",
+            KeySlotKind::Recovery => "recovery key",
+            KeySlotKind::Device => "device key",
+            KeySlotKind::Emergency => "emergency access key",
+        }
+    }
+}
+
+/// The vault key, encrypted with a key derived from one unlock method
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeySlot {
+    /// Unlock method this slot belongs to
+    pub kind: KeySlotKind,
+    
+    /// Base64 of the nonce-prefixed encrypted vault key
+    pub wrapped_key: String,
+}
+
+/// Emergency access granted to a contact
+/// 
+/// The contact holds a key that unwraps the `Emergency` key slot. PassMan
+/// only uses it once the contact has requested access and `wait_days`
+/// have passed without the owner denying the request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmergencyAccess {
+    /// Who holds the emergency key (a name or email, for the owner's reference)
+    pub contact: String,
+    
+    /// Days between a request and access
+    pub wait_days: u32,
+    
+    /// When access was granted
+    pub granted_at: DateTime<Utc>,
+}
+
+/// Unencrypted header of a versioned vault file
+/// 
+/// The vault payload is encrypted with a random vault key. Each key slot
+/// holds that key wrapped by one unlock method, so the master password
+/// can change (or a recovery key can be used) without re-encrypting the
+/// payload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VaultHeader {
+    /// File format version
+    pub format_version: u32,
+    
+    /// Key derivation parameters for the password slot
+    pub kdf: KdfParams,
+    
+    /// Cipher of the payload and the wrapped keys
+    #[serde(default)]
+    pub cipher: Cipher,
+    
+    /// Base64 of the key derivation salt
+    pub salt: String,
+    
+    /// Whether unlocking also requires a keyfile
+    pub keyfile: bool,
+    
+    /// Wrapped copies of the vault key
+    pub key_slots: Vec<KeySlot>,
+    
+    /// Master password hint, stored unencrypted so it can be shown before unlocking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    
+    /// Emergency access settings, if a contact holds an emergency key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emergency: Option<EmergencyAccess>,
+    
+    /// Whether each account is encrypted into a record of its own, so
+    /// saves only rewrite the accounts that changed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub records: bool,
+}
+
+impl VaultHeader {
+    /// Create a header with a fresh salt and no key slots
+    /// 
+    /// # Arguments
+    /// * `kdf` - Key derivation parameters
+    /// * `keyfile` - Whether the vault requires a keyfile
+    pub fn new(kdf: KdfParams, keyfile: bool) -> Self {
+        let mut header = Self {
+            format_version: FORMAT_VERSION,
+            kdf,
+            cipher: Cipher::default(),
+            salt: String::new(),
+            keyfile,
+            key_slots: Vec::new(),
+            hint: None,
+            emergency: None,
+            records: false,
+        };
+        header.set_salt(&Salt::generate());
+        header
+    }
+    
+    /// Get the key derivation salt
+    /// 
+    /// # Errors
+    /// Returns an error if the stored salt is malformed
+    pub fn salt(&self) -> Result<Salt> {
+        let bytes = STANDARD.decode(&self.salt)
+            .map_err(|e| PassManError::VaultCorrupted(format!("Invalid salt in vault header: {}", e)))?;
+        let bytes = bytes.try_into()
+            .map_err(|_| PassManError::VaultCorrupted("Invalid salt length in vault header".to_string()))?;
+        Ok(Salt::from_bytes(bytes))
+    }
+    
+    /// Replace the key derivation salt
+    pub fn set_salt(&mut self, salt: &Salt) {
+        self.salt = STANDARD.encode(salt.as_bytes());
+    }
+    
+    /// Derive the password slot's wrapping key
+    /// 
+    /// # Arguments
+    /// * `master_password` - The master password
+    /// * `keyfile` - Keyfile contents, required if the vault uses a keyfile
+    /// 
+    /// # Returns
+    /// The key that wraps the password slot
+    /// 
+    /// # Errors
+    /// Returns an error if a required keyfile is missing or derivation fails
+    pub fn password_key(&self, master_password: &str, keyfile: Option<&[u8]>) -> Result<SecureKey> {
+        if self.keyfile && keyfile.is_none() {
+            return Err(PassManError::AuthenticationFailed("This vault requires a keyfile".to_string()));
+        }
+        let secret = crypto::master_secret(master_password, keyfile);
+        self.kdf.derive(&secret, &self.salt()?)
+    }
+    
+    /// Check whether the header has a slot of the given kind
+    pub fn has_slot(&self, kind: KeySlotKind) -> bool {
+        self.key_slots.iter().any(|slot| slot.kind == kind)
+    }
+    
+    /// Remove the slot of the given kind, if any
+    /// 
+    /// # Returns
+    /// True if a slot was removed
+    pub fn remove_slot(&mut self, kind: KeySlotKind) -> bool {
+        let before = self.key_slots.len();
+        self.key_slots.retain(|slot| slot.kind != kind);
+        self.key_slots.len() != before
+    }
+    
+    /// Store the vault key wrapped with `wrapping_key`, replacing any slot of the same kind
+    /// 
+    /// # Arguments
+    /// * `kind` - Unlock method of the slot
+    /// * `vault_key` - The vault key to wrap
+    /// * `wrapping_key` - Key derived from the unlock method
+    /// 
+    /// # Errors
+    /// Returns an error if encryption fails
+    pub fn set_slot(&mut self, kind: KeySlotKind, vault_key: &SecureKey, wrapping_key: &SecureKey) -> Result<()> {
+        let wrapped = CryptoManager::new().encrypt_with_key(vault_key.as_bytes(), wrapping_key)?;
+        self.key_slots.retain(|slot| slot.kind != kind);
+        self.key_slots.push(KeySlot {
+            kind,
+            wrapped_key: STANDARD.encode(wrapped),
+        });
+        Ok(())
+    }
+    
+    /// Recover the vault key from a slot
+    /// 
+    /// # Arguments
+    /// * `kind` - Unlock method of the slot
+    /// * `wrapping_key` - Key derived from the unlock method
+    /// 
+    /// # Returns
+    /// The vault key
+    /// 
+    /// # Errors
+    /// Returns an authentication error if the slot is missing or the key is wrong
+    pub fn unwrap_slot(&self, kind: KeySlotKind, wrapping_key: &SecureKey) -> Result<SecureKey> {
+        let slot = self.key_slots.iter()
+            .find(|slot| slot.kind == kind)
+            .ok_or_else(|| PassManError::AuthenticationFailed(format!("Vault has no {} key slot", kind.as_str())))?;
+        let wrapped = STANDARD.decode(&slot.wrapped_key)
+            .map_err(|e| PassManError::VaultCorrupted(format!("Invalid key slot in vault header: {}", e)))?;
+        
+        let key_bytes = CryptoManager::new().decrypt_with_key(&wrapped, wrapping_key)
+            .map_err(|_| PassManError::AuthenticationFailed(format!("Invalid {}", kind.as_str())))?;
+        let key_bytes = Zeroizing::new(key_bytes);
+        let key: [u8; 32] = key_bytes.as_slice().try_into()
+            .map_err(|_| PassManError::VaultCorrupted("Invalid key length in vault header".to_string()))?;
+        
+        Ok(SecureKey::new(key))
+    }
+    
+    /// Serialize the header with its magic and length prefix
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        encode_header(VAULT_MAGIC, self)
+    }
+}
+
+/// Unencrypted header of a portable export
+/// 
+/// Unlike a vault, an export has no key slots: its payload is encrypted
+/// directly with the key derived from the export password.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportHeader {
+    /// Export format version
+    pub format_version: u32,
+    
+    /// Key derivation parameters for the export password
+    pub kdf: KdfParams,
+    
+    /// Cipher of the payload
+    pub cipher: Cipher,
+    
+    /// Base64 of the key derivation salt
+    pub salt: String,
+    
+    /// When the export was written
+    pub exported_at: DateTime<Utc>,
+}
+
+impl ExportHeader {
+    /// Derive the payload key from the export password
+    /// 
+    /// The KDF parameters come from the file, so they are checked against
+    /// `KdfParams::check_limits` first.
+    fn key(&self, password: &str) -> Result<SecureKey> {
+        self.kdf.check_limits()?;
+        let bytes = STANDARD.decode(&self.salt)
+            .map_err(|e| PassManError::VaultCorrupted(format!("Invalid salt in export header: {}", e)))?;
+        let bytes = bytes.try_into()
+            .map_err(|_| PassManError::VaultCorrupted("Invalid salt length in export header".to_string()))?;
+        self.kdf.derive(password.as_bytes(), &Salt::from_bytes(bytes))
+    }
+}
+
+/// Serialize a header behind its magic and length prefix
+fn encode_header(magic: &[u8; 8], header: &impl Serialize) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(header).map_err(PassManError::SerializationError)?;
+    let mut bytes = Vec::with_capacity(magic.len() + 4 + json.len());
+    bytes.extend_from_slice(magic);
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&json);
+    Ok(bytes)
+}
+
+/// Split a file behind `magic` into its JSON header and the rest
+/// 
+/// # Returns
+/// None if the file is truncated or the header length doesn't fit in memory
+fn split_header<'a>(magic: &[u8; 8], file_data: &'a [u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let start = magic.len() + 4;
+    let length_bytes: [u8; 4] = file_data.get(magic.len()..start)?.try_into().ok()?;
+    // A u32 length can overflow usize on 32-bit targets such as wasm32
+    let end = usize::try_from(u32::from_le_bytes(length_bytes)).ok()?.checked_add(start)?;
+    Some((file_data.get(start..end)?, &file_data[end..]))
+}
+
+/// Layout of a vault file
+pub(crate) enum VaultLayout {
+    /// Original layout: 16-byte salt followed by the ciphertext
+    Legacy(Salt),
+    
+    /// Magic, length-prefixed JSON header, then the ciphertext
+    Versioned(VaultHeader),
+}
+
+impl VaultLayout {
+    /// Get the vault key for a master password
+    /// 
+    /// For legacy vaults the key is derived directly from the password;
+    /// for versioned vaults it is unwrapped from the password key slot.
+    pub(crate) fn unlock_key(&self, master_password: &str, keyfile: Option<&[u8]>) -> Result<SecureKey> {
+        match self {
+            VaultLayout::Legacy(salt) => CryptoManager::new().derive_key(master_password, salt),
+            VaultLayout::Versioned(header) => {
+                let wrapping_key = header.password_key(master_password, keyfile)?;
+                header.unwrap_slot(KeySlotKind::Password, &wrapping_key)
+            }
+        }
+    }
+}
+
+/// Split vault file contents into layout and encrypted payload
+/// 
+/// # Errors
+/// Returns an error if the file is truncated, corrupted, or from a newer
+/// version of PassMan
+pub(crate) fn parse_vault_file(file_data: &[u8]) -> Result<(VaultLayout, Vec<u8>)> {
+    if file_data.starts_with(VAULT_MAGIC) {
+        return parse_versioned(file_data);
+    }
+    
+    // Extract salt (first 16 bytes) and encrypted data (rest)
+    if file_data.len() < 16 {
+        return Err(PassManError::VaultCorrupted("the file is too small".to_string()));
+    }
+    
+    let salt_bytes: [u8; 16] = file_data[0..16].try_into()
+        .map_err(|_| PassManError::VaultCorrupted("Failed to read salt from vault file".to_string()))?;
+    let encrypted_data = file_data[16..].to_vec();
+    
+    Ok((VaultLayout::Legacy(Salt::from_bytes(salt_bytes)), encrypted_data))
+}
+
+/// Split a versioned vault file into header and encrypted payload
+fn parse_versioned(file_data: &[u8]) -> Result<(VaultLayout, Vec<u8>)> {
+    let (header, encrypted_data) = split_header(VAULT_MAGIC, file_data)
+        .ok_or_else(|| PassManError::VaultCorrupted("truncated header".to_string()))?;
+    let header: VaultHeader = serde_json::from_slice(header)
+        .map_err(|e| PassManError::VaultCorrupted(format!("Invalid vault header: {}", e)))?;
+    if header.format_version < FIRST_HEADER_VERSION {
+        return Err(PassManError::VaultCorrupted(format!("Invalid format version {}", header.format_version)));
+    }
+    if header.format_version > FORMAT_VERSION {
+        return Err(PassManError::StorageError(format!(
+            "Vault format version {} is newer than this version of PassMan supports",
+            header.format_version
+        )));
+    }
+    
+    Ok((VaultLayout::Versioned(header), encrypted_data.to_vec()))
+}
+
+/// Upgrade a header to the current format version
+/// 
+/// # Arguments
+/// * `header` - Header of a versioned vault file, as read
+/// 
+/// # Returns
+/// True if the header was from an older version and has changed
+#[cfg(feature = "storage")]
+pub(crate) fn migrate_header(header: &mut VaultHeader) -> bool {
+    let from = header.format_version;
+    while header.format_version < FORMAT_VERSION {
+        MIGRATIONS[(header.format_version - FIRST_HEADER_VERSION) as usize](header);
+        header.format_version += 1;
+    }
+    header.format_version != from
+}
+
+/// Create a header for a legacy vault
+/// 
+/// The legacy key becomes the vault key, wrapped in a password slot, so
+/// the payload stays readable and only the file prefix changes.
+/// 
+/// # Arguments
+/// * `vault_key` - Key the legacy vault is encrypted with
+/// * `kdf` - Key derivation parameters for the password slot
+/// * `master_password` - The vault's master password
+/// 
+/// # Errors
+/// Returns an error if key derivation or wrapping fails
+#[cfg(feature = "storage")]
+pub(crate) fn upgrade_legacy(vault_key: &SecureKey, kdf: KdfParams, master_password: &str) -> Result<VaultHeader> {
+    let mut header = VaultHeader::new(kdf, false);
+    let password_key = header.password_key(master_password, None)?;
+    header.set_slot(KeySlotKind::Password, vault_key, &password_key)?;
+    Ok(header)
+}
+
+/// Decrypt a vault payload
+/// 
+/// # Errors
+/// Returns an error if the key is wrong or the payload is corrupted
+pub(crate) fn decrypt_payload(encrypted_data: &[u8], key: &SecureKey) -> Result<Vault> {
+    let decrypted_data = Zeroizing::new(CryptoManager::new().decrypt_with_key(encrypted_data, key)?);
+    // The payload decrypted, so the key was right and the contents are damaged
+    serde_json::from_slice(&decrypted_data)
+        .map_err(|e| PassManError::VaultCorrupted(format!("Invalid vault data: {}", e)))
+}
+
+/// Encrypt a vault into a payload, zeroizing the plaintext JSON afterwards
+/// 
+/// # Errors
+/// Returns an error if serialization or encryption fails
+#[cfg(feature = "storage")]
+pub(crate) fn encrypt_payload(vault: &Vault, crypto: &CryptoManager) -> Result<Vec<u8>> {
+    let mut writer = ZeroizingWriter(Zeroizing::new(Vec::with_capacity(SERIALIZE_BUFFER_SIZE)));
+    serde_json::to_writer_pretty(&mut writer, vault).map_err(PassManError::SerializationError)?;
+    crypto.encrypt(&writer.0)
+}
+
+/// Writer into a zeroizing buffer that also zeroizes the buffers it outgrows
+/// 
+/// A growing `Vec` copies itself and frees the old allocation as is, which
+/// would leave pieces of the plaintext behind on the heap.
+#[cfg(feature = "storage")]
+struct ZeroizingWriter(Zeroizing<Vec<u8>>);
+
+#[cfg(feature = "storage")]
+impl io::Write for ZeroizingWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let needed = self.0.len() + data.len();
+        if needed > self.0.capacity() {
+            let mut grown = Zeroizing::new(Vec::with_capacity(needed.max(self.0.capacity() * 2)));
+            grown.extend_from_slice(&self.0);
+            // The old buffer is zeroized as it drops
+            self.0 = grown;
+        }
+        self.0.extend_from_slice(data);
+        Ok(data.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where an account's record is, in the index of a record vault
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RecordRef {
+    /// The account's ID
+    pub id: uuid::Uuid,
+    
+    /// Digest of the encrypted record (see `record_digest`)
+    pub digest: String,
+}
+
+/// First frame of a record vault's payload, once decrypted
+#[derive(Deserialize)]
+struct RecordIndex {
+    /// The vault without its accounts
+    vault: Vault,
+    
+    /// The vault's accounts
+    records: Vec<RecordRef>,
+}
+
+/// `RecordIndex` borrowing what it holds, for writing
+#[cfg(feature = "storage")]
+#[derive(Serialize)]
+struct RecordIndexRef<'a> {
+    vault: &'a Vault,
+    records: &'a [RecordRef],
+}
+
+/// Hex SHA-256 digest of an encrypted record, which also names its blob
+pub(crate) fn record_digest(record: &[u8]) -> String {
+    Sha256::digest(record).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Serialize an account for its record, into a zeroizing buffer
+/// 
+/// # Errors
+/// Returns an error if serialization fails
+#[cfg(feature = "storage")]
+pub(crate) fn record_plaintext(account: &Account) -> Result<Zeroizing<Vec<u8>>> {
+    let mut writer = ZeroizingWriter(Zeroizing::new(Vec::new()));
+    serde_json::to_writer(&mut writer, account).map_err(PassManError::SerializationError)?;
+    Ok(writer.0)
+}
+
+/// Encrypt the index of a record vault into the payload of its vault file
+/// 
+/// # Arguments
+/// * `vault` - The vault; its accounts are left out
+/// * `records` - Where the vault's accounts are
+/// * `crypto` - Crypto manager holding the vault key
+/// 
+/// # Errors
+/// Returns an error if serialization or encryption fails
+#[cfg(feature = "storage")]
+pub(crate) fn encrypt_record_index(vault: &Vault, records: &[RecordRef], crypto: &CryptoManager) -> Result<Vec<u8>> {
+    let vault = vault.without_accounts();
+    let mut writer = ZeroizingWriter(Zeroizing::new(Vec::with_capacity(SERIALIZE_BUFFER_SIZE)));
+    serde_json::to_writer(&mut writer, &RecordIndexRef { vault: &vault, records })
+        .map_err(PassManError::SerializationError)?;
+    let mut payload = Vec::new();
+    push_frame(&mut payload, &crypto.encrypt(&writer.0)?);
+    Ok(payload)
+}
+
+/// Append a length-prefixed frame, e.g. a record to a vault file
+pub(crate) fn push_frame(data: &mut Vec<u8>, frame: &[u8]) {
+    data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+    data.extend_from_slice(frame);
+}
+
+/// Split a record vault payload into the framed index and the records appended to it
+/// 
+/// # Errors
+/// Returns an error if the payload is truncated
+pub(crate) fn split_record_payload(payload: &[u8]) -> Result<(&[u8], Vec<&[u8]>)> {
+    let mut frames = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let frame = rest.get(..4)
+            .map(|length| u32::from_le_bytes(length.try_into().expect("4 bytes")) as usize)
+            .and_then(|length| rest[4..].get(..length))
+            .ok_or_else(|| PassManError::VaultCorrupted("truncated record".to_string()))?;
+        frames.push(frame);
+        rest = &rest[4 + frame.len()..];
+    }
+    
+    let Some((index, records)) = frames.split_first() else {
+        return Err(PassManError::VaultCorrupted("the record index is missing".to_string()));
+    };
+    Ok((&payload[..4 + index.len()], records.to_vec()))
+}
+
+/// Decrypt the payload of a record vault
+/// 
+/// # Arguments
+/// * `payload` - The payload; records appended to it are used first
+/// * `key` - The vault key
+/// * `fetch` - Reads a record that isn't in the payload, by its digest
+/// 
+/// # Returns
+/// The vault, and where each of its accounts came from
+/// 
+/// # Errors
+/// Returns an error if the key is wrong, or a record is missing or
+/// doesn't match the index
+pub(crate) fn decrypt_records(payload: &[u8], key: &SecureKey, fetch: &dyn Fn(&str) -> Result<Option<Vec<u8>>>) -> Result<(Vault, Vec<RecordRef>)> {
+    let (index, appended) = split_record_payload(payload)?;
+    let crypto = CryptoManager::new();
+    let decrypted_index = Zeroizing::new(crypto.decrypt_with_key(&index[4..], key)?);
+    let RecordIndex { mut vault, records } = serde_json::from_slice(&decrypted_index)
+        .map_err(|e| PassManError::VaultCorrupted(format!("Invalid record index: {}", e)))?;
+    let appended: HashMap<String, &[u8]> = appended.into_iter()
+        .map(|record| (record_digest(record), record))
+        .collect();
+    
+    for record in &records {
+        let fetched;
+        let data = match appended.get(&record.digest) {
+            Some(data) => *data,
+            None => {
+                fetched = fetch(&record.digest)?
+                    .ok_or_else(|| PassManError::VaultCorrupted(format!("the record of account {} is missing", record.id)))?;
+                fetched.as_slice()
+            }
+        };
+        // The index only vouches for records by their digest
+        if record_digest(data) != record.digest {
+            return Err(PassManError::VaultCorrupted(format!("the record of account {} doesn't match the index", record.id)));
+        }
+        
+        let plaintext = Zeroizing::new(crypto.decrypt_with_key(data, key)?);
+        let account: Account = serde_json::from_slice(&plaintext)
+            .map_err(|e| PassManError::VaultCorrupted(format!("Invalid record of account {}: {}", record.id, e)))?;
+        if account.id != record.id {
+            return Err(PassManError::VaultCorrupted(format!("the record of account {} holds another account", record.id)));
+        }
+        vault.accounts.insert(account.id, account);
+    }
+    vault.reindex();
+    Ok((vault, records))
+}
+
+/// Decrypt the payload of a vault file in either layout
+/// 
+/// # Arguments
+/// * `layout` - Layout of the file
+/// * `payload` - The encrypted payload
+/// * `key` - The vault key
+/// * `fetch` - Reads records that aren't in the payload (see `decrypt_records`)
+/// 
+/// # Errors
+/// Returns an error if the key is wrong or the payload is corrupted
+pub(crate) fn decrypt_vault(layout: &VaultLayout, payload: &[u8], key: &SecureKey, fetch: &dyn Fn(&str) -> Result<Option<Vec<u8>>>) -> Result<Vault> {
+    match layout {
+        VaultLayout::Versioned(header) if header.records => Ok(decrypt_records(payload, key, fetch)?.0),
+        _ => decrypt_payload(payload, key),
+    }
+}
+
+/// Read the header of a vault file held in memory
+/// 
+/// # Arguments
+/// * `file_data` - Contents of a vault file
+/// 
+/// # Returns
+/// The header, or None for legacy vaults
+/// 
+/// # Errors
+/// Returns an error if the file is corrupted
+pub fn read_header_bytes(file_data: &[u8]) -> Result<Option<VaultHeader>> {
+    match parse_vault_file(file_data)?.0 {
+        VaultLayout::Legacy(_) => Ok(None),
+        VaultLayout::Versioned(header) => Ok(Some(header)),
+    }
+}
+
+/// Decrypt a vault file held in memory
+/// 
+/// # Arguments
+/// * `file_data` - Contents of a vault file
+/// * `master_password` - The master password
+/// * `keyfile` - Keyfile contents, if the vault uses one
+/// 
+/// # Returns
+/// The decrypted vault
+/// 
+/// # Errors
+/// Returns an error if the file is corrupted or the credentials are wrong
+pub fn open_vault_bytes(file_data: &[u8], master_password: &str, keyfile: Option<&[u8]>) -> Result<Vault> {
+    let (layout, encrypted_data) = parse_vault_file(file_data)?;
+    let key = layout.unlock_key(master_password, keyfile)?;
+    decrypt_vault(&layout, &encrypted_data, &key, &|_| Ok(None))
+}
+
+/// Encrypt a vault into a portable export
+/// 
+/// # Arguments
+/// * `vault` - The vault to export
+/// * `password` - The export password, independent of the master password
+/// * `kdf` - Key derivation parameters for the export password
+/// 
+/// # Returns
+/// The contents of the export file
+/// 
+/// # Errors
+/// Returns an error if the parameters are invalid or encryption fails
+pub fn encrypt_portable(vault: &Vault, password: &str, kdf: KdfParams) -> Result<Vec<u8>> {
+    let salt = Salt::generate();
+    let header = ExportHeader {
+        format_version: EXPORT_FORMAT_VERSION,
+        kdf,
+        cipher: Cipher::default(),
+        salt: STANDARD.encode(salt.as_bytes()),
+        exported_at: Utc::now(),
+    };
+    let key = header.key(password)?;
+    
+    let json = Zeroizing::new(serde_json::to_vec(vault).map_err(PassManError::SerializationError)?);
+    let mut file_data = encode_header(EXPORT_MAGIC, &header)?;
+    file_data.extend_from_slice(&CryptoManager::new().encrypt_with_key(&json, &key)?);
+    Ok(file_data)
+}
+
+/// Whether a file is a portable export
+pub fn is_portable_export(file_data: &[u8]) -> bool {
+    file_data.starts_with(EXPORT_MAGIC)
+}
+
+/// Read the header of a portable export
+/// 
+/// # Errors
+/// Returns an error if the file isn't a portable export, is truncated,
+/// or is from a newer version of PassMan
+pub fn read_export_header(file_data: &[u8]) -> Result<ExportHeader> {
+    Ok(parse_portable(file_data)?.0)
+}
+
+/// Decrypt a portable export
+/// 
+/// # Arguments
+/// * `file_data` - Contents of the export file
+/// * `password` - The export password
+/// 
+/// # Returns
+/// The exported vault
+/// 
+/// # Errors
+/// Returns an authentication error if the password is wrong, or another
+/// error if the file isn't a readable portable export
+pub fn open_portable_bytes(file_data: &[u8], password: &str) -> Result<Vault> {
+    let (header, encrypted_data) = parse_portable(file_data)?;
+    let key = header.key(password)?;
+    let json = CryptoManager::new().decrypt_with_key(encrypted_data, &key)
+        .map_err(|_| PassManError::AuthenticationFailed("Wrong password for the export".to_string()))?;
+    let json = Zeroizing::new(json);
+    serde_json::from_slice(&json)
+        .map_err(|e| PassManError::VaultCorrupted(format!("Invalid export data: {}", e)))
+}
+
+/// Split a portable export into header and encrypted payload
+fn parse_portable(file_data: &[u8]) -> Result<(ExportHeader, &[u8])> {
+    if !is_portable_export(file_data) {
+        return Err(PassManError::InvalidInput("Not a PassMan export".to_string()));
+    }
+    let (header, encrypted_data) = split_header(EXPORT_MAGIC, file_data)
+        .ok_or_else(|| PassManError::VaultCorrupted("truncated export header".to_string()))?;
+    let header: ExportHeader = serde_json::from_slice(header)
+        .map_err(|e| PassManError::VaultCorrupted(format!("Invalid export header: {}", e)))?;
+    if header.format_version > EXPORT_FORMAT_VERSION {
+        return Err(PassManError::StorageError(format!(
+            "Export format version {} is newer than this version of PassMan supports",
+            header.format_version
+        )));
+    }
+    Ok((header, encrypted_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    #[cfg(feature = "storage")]
+    fn test_zeroizing_writer() {
+        use std::io::Write;
+        
+        let mut writer = ZeroizingWriter(Zeroizing::new(Vec::with_capacity(4)));
+        for chunk in [&b"abc"[..], b"defgh", &[b'x'; 100]] {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(&writer.0[..8], b"abcdefgh");
+        assert_eq!(writer.0.len(), 108);
+        
+        // Large enough to outgrow the initial buffer
+        let mut vault = Vault::new("test@example.com".to_string());
+        for i in 0..1000 {
+            vault.add_account(crate::models::Account::new(format!("Account {}", i), crate::models::AccountType::Other, "x".repeat(64)));
+        }
+        let mut crypto = CryptoManager::new();
+        let (key, _) = crypto.generate_key_and_salt("password").unwrap();
+        let payload = encrypt_payload(&vault, &crypto).unwrap();
+        assert_eq!(decrypt_payload(&payload, &key).unwrap(), vault);
+    }
+    
+    #[test]
+    fn test_open_vault_bytes() {
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(KdfParams::default(), false);
+        header.hint = Some("hint".to_string());
+        let wrapping_key = header.password_key("test_password", None).unwrap();
+        header.set_slot(KeySlotKind::Password, &vault_key, &wrapping_key).unwrap();
+        
+        let vault = Vault::new("test@example.com".to_string());
+        let payload = CryptoManager::new()
+            .encrypt_with_key(&serde_json::to_vec(&vault).unwrap(), &vault_key)
+            .unwrap();
+        let mut file_data = header.encode().unwrap();
+        file_data.extend_from_slice(&payload);
+        
+        assert_eq!(read_header_bytes(&file_data).unwrap(), Some(header));
+        assert_eq!(open_vault_bytes(&file_data, "test_password", None).unwrap(), vault);
+        assert!(matches!(
+            open_vault_bytes(&file_data, "wrong_password", None),
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        assert!(open_vault_bytes(&file_data[..12], "test_password", None).is_err());
+    }
+    
+    #[test]
+    #[cfg(feature = "storage")]
+    fn test_migrate_header() {
+        let current = VaultHeader::new(KdfParams::default(), false);
+        let mut json = serde_json::to_value(&current).unwrap();
+        json["format_version"] = FIRST_HEADER_VERSION.into();
+        json.as_object_mut().unwrap().remove("cipher");
+        
+        let mut header: VaultHeader = serde_json::from_value(json.clone()).unwrap();
+        assert!(migrate_header(&mut header));
+        assert_eq!(header, current);
+        assert!(!migrate_header(&mut header));
+        
+        // Headers outside the known versions are refused
+        for version in [1, FORMAT_VERSION + 1] {
+            json["format_version"] = version.into();
+            let mut file_data = VAULT_MAGIC.to_vec();
+            let header_json = serde_json::to_vec(&json).unwrap();
+            file_data.extend_from_slice(&(header_json.len() as u32).to_le_bytes());
+            file_data.extend_from_slice(&header_json);
+            assert!(read_header_bytes(&file_data).is_err());
+        }
+    }
+    
+    #[test]
+    fn test_portable_export() {
+        let mut vault = Vault::new("test@example.com".to_string());
+        vault.add_account(crate::models::Account::new("GitHub".to_string(), crate::models::AccountType::Work, "hunter2".to_string()));
+        let kdf = KdfParams { memory_kib: 8 * 1024, iterations: 1, parallelism: 1 };
+        let file_data = encrypt_portable(&vault, "export password", kdf).unwrap();
+        
+        assert!(is_portable_export(&file_data));
+        assert!(!is_portable_export(b"PASSMAN\0"));
+        let header = read_export_header(&file_data).unwrap();
+        assert_eq!((header.format_version, header.kdf), (EXPORT_FORMAT_VERSION, kdf));
+        
+        assert_eq!(open_portable_bytes(&file_data, "export password").unwrap(), vault);
+        assert!(matches!(
+            open_portable_bytes(&file_data, "master password"),
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        assert!(open_portable_bytes(&file_data[..20], "export password").is_err());
+        
+        // Exports from a newer version are refused
+        let mut newer = header.clone();
+        newer.format_version = EXPORT_FORMAT_VERSION + 1;
+        let mut newer_data = encode_header(EXPORT_MAGIC, &newer).unwrap();
+        newer_data.extend_from_slice(split_header(EXPORT_MAGIC, &file_data).unwrap().1);
+        assert!(matches!(read_export_header(&newer_data), Err(PassManError::StorageError(_))));
+        
+        // KDF parameters that would exhaust memory are refused before deriving
+        let mut hostile = header.clone();
+        hostile.kdf.memory_kib = u32::MAX;
+        let mut hostile_data = encode_header(EXPORT_MAGIC, &hostile).unwrap();
+        hostile_data.extend_from_slice(split_header(EXPORT_MAGIC, &file_data).unwrap().1);
+        assert!(matches!(open_portable_bytes(&hostile_data, "export password"), Err(PassManError::CryptoError(_))));
+        
+        // So are header lengths past the end of the file
+        let mut truncated = EXPORT_MAGIC.to_vec();
+        truncated.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(split_header(EXPORT_MAGIC, &truncated).is_none());
+    }
+}
@@ -0,0 +1,352 @@
+//! # Vault Consistency Check
+//! 
+//! `PassMan::fsck` looks for problems a normal unlock doesn't notice: a
+//! damaged header, backups that no longer decrypt or whose signatures don't
+//! match, vault metadata that disagrees with the accounts, and files left
+//! behind next to the vault. This module holds the checks that only need
+//! the header or the decrypted vault; the vault manager adds the ones that
+//! need its storage.
+//! 
+//! Issues that can be fixed without losing data are repaired when asked
+//! to. Repairs are saved like any other change, so the vault is backed up
+//! first.
+
+use crate::format::{KeySlotKind, VaultHeader};
+use crate::models::Vault;
+use crate::sync;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Length of a wrapped vault key: nonce, 32-byte key, and authentication tag
+const WRAPPED_KEY_LEN: usize = 12 + 32 + 16;
+
+/// How serious an issue is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Harmless now, but a sign of an interrupted operation or an old bug
+    Warning,
+    
+    /// Data or an unlock method is damaged
+    Error,
+}
+
+/// One problem found by a check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FsckIssue {
+    /// How serious the issue is
+    pub severity: Severity,
+    
+    /// What is wrong
+    pub message: String,
+    
+    /// Whether `fsck --repair` can fix it
+    pub repairable: bool,
+    
+    /// Whether this run fixed it
+    pub repaired: bool,
+}
+
+/// Result of a consistency check
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FsckReport {
+    /// Problems found, in the order they were checked
+    pub issues: Vec<FsckIssue>,
+    
+    /// Number of accounts checked
+    pub accounts_checked: usize,
+    
+    /// Number of backups checked
+    pub backups_checked: usize,
+}
+
+impl FsckReport {
+    /// Whether no problems were found
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+    
+    /// Problems that are still there after this run
+    pub fn unresolved(&self) -> impl Iterator<Item = &FsckIssue> {
+        self.issues.iter().filter(|issue| !issue.repaired)
+    }
+    
+    /// Record an issue that can't be repaired
+    pub(crate) fn problem(&mut self, severity: Severity, message: String) {
+        self.issues.push(FsckIssue { severity, message, repairable: false, repaired: false });
+    }
+    
+    /// Record an issue that can be repaired, and whether it was
+    pub(crate) fn fixable(&mut self, severity: Severity, message: String, repaired: bool) {
+        self.issues.push(FsckIssue { severity, message, repairable: true, repaired });
+    }
+}
+
+/// Check a vault header
+/// 
+/// # Arguments
+/// * `header` - The header, repaired in place if `repair` is set
+/// * `repair` - Fix what can be fixed
+/// * `report` - Where to record issues
+/// 
+/// # Returns
+/// True if the header was changed and must be saved
+pub(crate) fn check_header(header: &mut VaultHeader, repair: bool, report: &mut FsckReport) -> bool {
+    let mut changed = false;
+    
+    if let Err(e) = header.salt() {
+        report.problem(Severity::Error, format!("Header salt is invalid: {}", e));
+    }
+    if let Err(e) = header.kdf.validate() {
+        report.problem(Severity::Error, format!("Header key derivation settings are invalid: {}", e));
+    }
+    if !header.has_slot(KeySlotKind::Password) {
+        report.problem(Severity::Error, "Header has no master password key slot".to_string());
+    }
+    
+    // Only the first slot of each kind is ever used
+    let mut seen = HashSet::new();
+    let duplicates = header.key_slots.iter().filter(|slot| !seen.insert(slot.kind)).count();
+    if duplicates > 0 {
+        if repair {
+            let mut seen = HashSet::new();
+            header.key_slots.retain(|slot| seen.insert(slot.kind));
+            changed = true;
+        }
+        report.fixable(Severity::Warning, format!("Header has {} duplicate key slot(s)", duplicates), repair);
+    }
+    
+    let damaged: Vec<KeySlotKind> = header.key_slots.iter()
+        .filter(|slot| STANDARD.decode(&slot.wrapped_key).map_or(true, |bytes| bytes.len() != WRAPPED_KEY_LEN))
+        .map(|slot| slot.kind)
+        .collect();
+    for kind in damaged {
+        // Without the password slot the vault can't be unlocked at all, so it stays for recovery tools
+        if kind == KeySlotKind::Password {
+            report.problem(Severity::Error, format!("The {} key slot is damaged", kind.as_str()));
+            continue;
+        }
+        if repair {
+            header.remove_slot(kind);
+            changed = true;
+        }
+        report.fixable(Severity::Error, format!("The {} key slot is damaged and can only be removed", kind.as_str()), repair);
+    }
+    
+    match (header.emergency.as_ref().map(|emergency| emergency.contact.clone()), header.has_slot(KeySlotKind::Emergency)) {
+        (Some(contact), false) => {
+            if repair {
+                header.emergency = None;
+                changed = true;
+            }
+            report.fixable(Severity::Error, format!("Emergency access for {} has no key slot", contact), repair);
+        }
+        (None, true) => {
+            if repair {
+                header.remove_slot(KeySlotKind::Emergency);
+                changed = true;
+            }
+            report.fixable(Severity::Warning, "Emergency key slot without an emergency contact".to_string(), repair);
+        }
+        _ => {}
+    }
+    
+    changed
+}
+
+/// Check a decrypted vault against its own metadata
+/// 
+/// # Arguments
+/// * `vault` - The vault, repaired in place if `repair` is set
+/// * `repair` - Fix what can be fixed
+/// * `report` - Where to record issues
+/// 
+/// # Returns
+/// True if the vault was changed and must be saved
+pub(crate) fn check_vault(vault: &mut Vault, repair: bool, report: &mut FsckReport) -> bool {
+    let mut changed = false;
+    report.accounts_checked = vault.accounts.len();
+    
+    // Accounts stored under another account's ID
+    let misfiled: Vec<_> = vault.accounts.iter()
+        .filter(|(key, account)| **key != account.id)
+        .map(|(key, account)| (*key, account.id))
+        .collect();
+    for (key, id) in misfiled {
+        let name = vault.accounts[&key].name.clone();
+        if vault.accounts.contains_key(&id) {
+            report.problem(Severity::Error, format!("Account '{}' is stored under ID {} and its own ID {} is taken", name, key, id));
+            continue;
+        }
+        if repair {
+            let account = vault.accounts.remove(&key).expect("listed above");
+            vault.accounts.insert(id, account);
+            changed = true;
+        }
+        report.fixable(Severity::Error, format!("Account '{}' is stored under ID {} instead of {}", name, key, id), repair);
+    }
+    
+    if vault.metadata.account_count != vault.accounts.len() {
+        report.fixable(
+            Severity::Warning,
+            format!("Metadata counts {} account(s), the vault has {}", vault.metadata.account_count, vault.accounts.len()),
+            repair,
+        );
+        if repair {
+            vault.metadata.account_count = vault.accounts.len();
+            changed = true;
+        }
+    }
+    
+    let revived = vault.deleted.keys().filter(|id| vault.accounts.contains_key(id)).count();
+    if revived > 0 {
+        if repair {
+            let accounts = &vault.accounts;
+            vault.deleted.retain(|id, _| !accounts.contains_key(id));
+            changed = true;
+        }
+        report.fixable(Severity::Warning, format!("{} account(s) are also recorded as deleted", revived), repair);
+    }
+    
+    // An account that is back in the vault must not also be purgeable from the trash
+    let restored = vault.trash.keys().filter(|id| vault.accounts.contains_key(id)).count();
+    if restored > 0 {
+        if repair {
+            let accounts = &vault.accounts;
+            vault.trash.retain(|id, _| !accounts.contains_key(id));
+            changed = true;
+        }
+        report.fixable(Severity::Warning, format!("{} account(s) are also in the trash", restored), repair);
+    }
+    
+    let mut unknown_times = 0;
+    for account in vault.accounts.values_mut() {
+        let before = account.field_times.len();
+        let mut times = account.field_times.clone();
+        times.retain(|field, _| sync::FIELDS.contains(&field.as_str()));
+        if times.len() != before {
+            unknown_times += before - times.len();
+            if repair {
+                account.field_times = times;
+                changed = true;
+            }
+        }
+    }
+    if unknown_times > 0 {
+        report.fixable(Severity::Warning, format!("{} change time(s) recorded for unknown fields", unknown_times), repair);
+    }
+    
+    // The vault's tag list should be exactly the tags its accounts use
+    let used: BTreeSet<&String> = vault.accounts.values().flat_map(|account| &account.tags).collect();
+    let listed: BTreeSet<&String> = vault.tags.iter().collect();
+    if used != listed {
+        let missing = used.difference(&listed).count();
+        let unused = listed.difference(&used).count();
+        let duplicates = vault.tags.len() - listed.len();
+        let tags: Vec<String> = used.into_iter().cloned().collect();
+        report.fixable(
+            Severity::Warning,
+            format!("Tag list is out of date ({} missing, {} unused, {} duplicate)", missing, unused, duplicates),
+            repair,
+        );
+        if repair {
+            vault.tags = tags;
+            changed = true;
+        }
+    } else if vault.tags.len() != listed.len() {
+        report.fixable(Severity::Warning, format!("Tag list has {} duplicate(s)", vault.tags.len() - listed.len()), repair);
+        if repair {
+            vault.tags = listed.into_iter().cloned().collect();
+            changed = true;
+        }
+    }
+    
+    if changed {
+        vault.reindex();
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{KdfParams, SecureKey};
+    use crate::format::EmergencyAccess;
+    use crate::models::{Account, AccountType, TrashedAccount};
+    use chrono::Utc;
+    
+    fn account(name: &str, tags: &[&str]) -> Account {
+        let mut account = Account::new(name.to_string(), AccountType::Email, "pw".to_string());
+        account.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        account
+    }
+    
+    #[test]
+    fn test_check_vault() {
+        let mut vault = Vault::new("test@example.com".to_string());
+        let mail = account("Mail", &["work"]);
+        let mail_id = mail.id;
+        vault.add_account(mail);
+        vault.add_account(account("Bank", &["money", "work"]));
+        vault.tags = vec!["work".to_string(), "money".to_string()];
+        let mut report = FsckReport::default();
+        assert!(!check_vault(&mut vault, false, &mut report));
+        assert!(report.is_clean());
+        assert_eq!(report.accounts_checked, 2);
+        
+        let misfiled = account("Shop", &["old"]);
+        let shop_id = misfiled.id;
+        vault.accounts.insert(uuid::Uuid::new_v4(), misfiled);
+        vault.deleted.insert(mail_id, Utc::now());
+        vault.trash.insert(mail_id, TrashedAccount { account: account("Mail", &[]), deleted_at: Utc::now() });
+        vault.accounts.values_mut().next().unwrap().field_times.insert("colour".to_string(), Utc::now());
+        
+        let mut report = FsckReport::default();
+        assert!(!check_vault(&mut vault, false, &mut report));
+        assert_eq!(report.issues.len(), 6);
+        assert!(report.issues.iter().all(|issue| issue.repairable && !issue.repaired));
+        
+        let mut report = FsckReport::default();
+        assert!(check_vault(&mut vault, true, &mut report));
+        assert_eq!(report.unresolved().count(), 0);
+        assert!(vault.accounts.contains_key(&shop_id));
+        assert_eq!(vault.metadata.account_count, 3);
+        assert!(vault.deleted.is_empty() && vault.trash.is_empty());
+        assert_eq!(vault.tags, ["money", "old", "work"]);
+        assert_eq!(vault.get_accounts_by_tag("old").len(), 1);
+        
+        let mut report = FsckReport::default();
+        assert!(!check_vault(&mut vault, false, &mut report));
+        assert!(report.is_clean());
+    }
+    
+    #[test]
+    fn test_check_header() {
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(KdfParams::default(), false);
+        header.set_slot(KeySlotKind::Password, &vault_key, &SecureKey::generate()).unwrap();
+        header.set_slot(KeySlotKind::Recovery, &vault_key, &SecureKey::generate()).unwrap();
+        let mut report = FsckReport::default();
+        assert!(!check_header(&mut header, false, &mut report));
+        assert!(report.is_clean());
+        
+        header.key_slots[1].wrapped_key = "not base64!".to_string();
+        header.emergency = Some(EmergencyAccess {
+            contact: "friend@example.com".to_string(),
+            wait_days: 7,
+            granted_at: Utc::now(),
+        });
+        let mut report = FsckReport::default();
+        assert!(check_header(&mut header, true, &mut report));
+        assert_eq!(report.issues.len(), 2);
+        assert!(!header.has_slot(KeySlotKind::Recovery));
+        assert!(header.emergency.is_none());
+        assert!(header.has_slot(KeySlotKind::Password));
+        
+        header.key_slots.clear();
+        let mut report = FsckReport::default();
+        assert!(!check_header(&mut header, true, &mut report));
+        assert_eq!(report.unresolved().count(), 1);
+    }
+}
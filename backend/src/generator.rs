@@ -3,8 +3,10 @@
 //! This module provides secure password generation functionality with
 //! customizable options for length, character sets, and exclusions.
 
-use rand::{Rng, thread_rng};
-use crate::{PassManError, Result, models::PasswordOptions};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::Serialize;
+use crate::{PassManError, Result, models::{PassphraseOptions, PassphraseWordList, PasswordOptions, UsernameOptions}, policy::SitePolicy, strength::{self, StrengthEstimate}};
+use std::path::Path;
 
 /// Character sets for password generation
 const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
@@ -17,17 +19,125 @@ const SIMILAR_CHARS: &str = "0OIl1|";
 /// Characters that are ambiguous in certain contexts
 const AMBIGUOUS_CHARS: &str = "{}[]()\\/~,;.<>";
 
+/// Built-in passphrase word list, one word per line
+#[cfg(feature = "wordlists")]
+const DEFAULT_WORDLIST: &str = include_str!("wordlist.txt");
+/// The EFF long word list in its original diceware layout (`11111\tabacus`),
+/// from https://www.eff.org/dice under CC BY 3.0 US
+#[cfg(feature = "wordlists")]
+const EFF_LONG_WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+/// Smallest custom word list accepted for passphrases
+const MIN_WORDLIST_SIZE: usize = 100;
+/// Shortest and longest PINs generated
+const PIN_LENGTHS: std::ops::RangeInclusive<usize> = 4..=20;
+/// PINs people pick most often, beyond repeats and runs
+const COMMON_PINS: &[&str] = &[
+    "1212", "1004", "2580", "0852", "1122", "1313", "6969", "1010", "2468", "1357",
+    "7410", "0147", "1001", "2001", "5683", "0007", "1998", "2525", "4200", "8520",
+    "112233", "121212", "123654", "159753", "147258", "258369", "147852", "696969",
+];
+/// Most passwords generated in one batch
+const MAX_BATCH_SIZE: usize = 100;
+/// Most digits in a generated username's number
+const MAX_USERNAME_DIGITS: usize = 6;
+/// First words of generated usernames
+const ADJECTIVES: &[&str] = &[
+    "amber", "ancient", "autumn", "bold", "brave", "bright", "brisk", "calm",
+    "clever", "cosmic", "crimson", "curious", "dapper", "daring", "dusty", "eager",
+    "electric", "fancy", "fierce", "frosty", "gentle", "gilded", "golden", "grand",
+    "happy", "hidden", "humble", "icy", "jolly", "keen", "lively", "lucky",
+    "lunar", "mellow", "merry", "misty", "mossy", "nimble", "noble", "olive",
+    "plucky", "polar", "proud", "quick", "quiet", "rapid", "rustic", "sandy",
+    "scarlet", "silent", "silver", "sleepy", "snowy", "solar", "spry", "steady",
+    "stormy", "sunny", "swift", "tidy", "velvet", "vivid", "wandering", "witty",
+];
+/// Second words of generated usernames
+const NOUNS: &[&str] = &[
+    "badger", "beacon", "bison", "canyon", "cedar", "comet", "condor", "coral",
+    "cricket", "dolphin", "ember", "falcon", "fern", "fjord", "fox", "gecko",
+    "glacier", "harbor", "hawk", "heron", "island", "jaguar", "kestrel", "koala",
+    "lantern", "lemur", "lynx", "maple", "meadow", "meteor", "moose", "nebula",
+    "otter", "owl", "panda", "pebble", "pelican", "penguin", "pine", "puffin",
+    "quasar", "raven", "reef", "river", "robin", "rocket", "sparrow", "spruce",
+    "squirrel", "summit", "thistle", "tiger", "tundra", "turtle", "valley", "voyager",
+    "walrus", "willow", "wolf", "wombat", "yak", "zebra", "zephyr", "orchid",
+];
+
+/// A generated password with its strength, offered to pick from
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PasswordCandidate {
+    /// The generated password
+    pub password: String,
+    
+    /// Strength score from 0 to 100 (see `PasswordGenerator::calculate_strength`)
+    pub strength: u8,
+}
+
+/// Characters of one class a password may use
+struct CharacterClass {
+    /// Name for error messages
+    name: &'static str,
+    
+    /// Characters left after exclusions
+    chars: Vec<char>,
+    
+    /// How many the password needs
+    required: usize,
+}
+
 /// Password generator with configurable options
 pub struct PasswordGenerator {
-    /// Random number generator
-    rng: rand::rngs::ThreadRng,
+    /// Random number generator (a CSPRNG seeded from the OS; unlike
+    /// `ThreadRng` it is `Send`, so a `PassMan` can be shared across threads)
+    rng: StdRng,
+    
+    /// Custom passphrase words (see `with_wordlist`); None uses the built-in list
+    word_list: Option<Vec<String>>,
 }
 
 impl PasswordGenerator {
     /// Create a new password generator
     pub fn new() -> Self {
         Self {
-            rng: thread_rng(),
+            rng: StdRng::from_entropy(),
+            word_list: None,
+        }
+    }
+    
+    /// Create a generator that picks passphrase words from a word list file
+    /// 
+    /// The file is read with `parse_word_list`, so it needs at least 100
+    /// distinct words; duplicates are dropped. `generate_passphrase_from_list`
+    /// then uses it instead of the built-in list.
+    /// 
+    /// # Arguments
+    /// * `path` - The word list file
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or has too few distinct words
+    pub fn with_wordlist(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| PassManError::InvalidInput(format!("Failed to read word list {}: {}", path.display(), e)))?;
+        let words = parse_word_list(&contents)?.into_iter().map(str::to_string).collect();
+        Ok(Self {
+            word_list: Some(words),
+            ..Self::new()
+        })
+    }
+    
+    /// Create a generator with a fixed seed, for reproducible tests
+    /// 
+    /// Never use this for real passwords: anyone who knows the seed can
+    /// recreate them.
+    /// 
+    /// # Arguments
+    /// * `seed` - Seed for the random number generator
+    #[cfg(any(test, feature = "testing"))]
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            word_list: None,
         }
     }
     
@@ -44,25 +154,15 @@ impl PasswordGenerator {
     pub fn generate(&mut self, options: &PasswordOptions) -> Result<String> {
         self.validate_options(options)?;
         
-        let charset = self.build_charset(options);
-        if charset.is_empty() {
-            return Err(PassManError::InvalidInput("No character set available".to_string()));
-        }
-        
-        let mut password = String::with_capacity(options.length);
+        let classes = self.character_classes(options);
+        let charset: Vec<char> = classes.iter().flat_map(|class| class.chars.iter().copied()).collect();
         
-        // Ensure at least one character from each required set
-        if options.include_uppercase {
-            password.push(self.random_char_from(UPPERCASE));
-        }
-        if options.include_lowercase {
-            password.push(self.random_char_from(LOWERCASE));
-        }
-        if options.include_numbers {
-            password.push(self.random_char_from(NUMBERS));
-        }
-        if options.include_special {
-            password.push(self.random_char_from(SPECIAL));
+        // Ensure the required characters from each set
+        let mut password = Vec::with_capacity(options.length);
+        for class in &classes {
+            for _ in 0..class.required {
+                password.push(self.random_char_from(&class.chars));
+            }
         }
         
         // Fill the rest with random characters from the full charset
@@ -70,10 +170,54 @@ impl PasswordGenerator {
             password.push(self.random_char_from(&charset));
         }
         
-        // Shuffle the password to avoid predictable patterns
-        self.shuffle_string(&mut password);
+        // Shuffle the password so the required characters can be anywhere
+        password.shuffle(&mut self.rng);
+        
+        Ok(password.into_iter().collect())
+    }
+    
+    /// Generate several passwords to choose from
+    /// 
+    /// # Arguments
+    /// * `options` - Configuration options for password generation
+    /// * `count` - Number of passwords, from 1 to 100
+    /// 
+    /// # Returns
+    /// The passwords with their strength scores
+    /// 
+    /// # Errors
+    /// Returns an error if the options or count are invalid
+    pub fn generate_batch(&mut self, options: &PasswordOptions, count: usize) -> Result<Vec<PasswordCandidate>> {
+        if !(1..=MAX_BATCH_SIZE).contains(&count) {
+            return Err(PassManError::InvalidInput(format!(
+                "Between 1 and {} passwords can be generated at once",
+                MAX_BATCH_SIZE
+            )));
+        }
         
-        Ok(password)
+        (0..count)
+            .map(|_| {
+                let password = self.generate(options)?;
+                let strength = self.calculate_strength(&password);
+                Ok(PasswordCandidate { password, strength })
+            })
+            .collect()
+    }
+    
+    /// Generate a password a website accepts
+    /// 
+    /// # Arguments
+    /// * `options` - The options the user chose
+    /// * `policy` - The site's policy; the options are adapted to it (see
+    ///   `SitePolicy::password_options`)
+    /// 
+    /// # Returns
+    /// A generated password string
+    /// 
+    /// # Errors
+    /// Returns an error if the policy can't be met or the options are invalid
+    pub fn generate_for_site(&mut self, options: &PasswordOptions, policy: &SitePolicy) -> Result<String> {
+        self.generate(&policy.password_options(options)?)
     }
     
     /// Generate a simple password with default options
@@ -100,6 +244,76 @@ impl PasswordGenerator {
         self.generate(&options)
     }
     
+    /// Generate a numeric PIN
+    /// 
+    /// PINs are drawn uniformly from those `is_weak_pin` accepts, so there
+    /// are no repeated digits like `0000`, runs like `1234`, or common PINs.
+    /// 
+    /// # Arguments
+    /// * `length` - Number of digits, from 4 to 20
+    /// 
+    /// # Returns
+    /// A generated PIN
+    /// 
+    /// # Errors
+    /// Returns an error if the length is out of range
+    pub fn generate_pin(&mut self, length: usize) -> Result<String> {
+        if !PIN_LENGTHS.contains(&length) {
+            return Err(PassManError::InvalidInput(format!(
+                "PIN length must be between {} and {}",
+                PIN_LENGTHS.start(),
+                PIN_LENGTHS.end()
+            )));
+        }
+        
+        let digits: Vec<char> = NUMBERS.chars().collect();
+        loop {
+            let pin: String = (0..length).map(|_| self.random_char_from(&digits)).collect();
+            if !is_weak_pin(&pin) {
+                return Ok(pin);
+            }
+        }
+    }
+    
+    /// Generate a username that says nothing about its owner
+    /// 
+    /// Usernames are an adjective, a noun, and a random number, such as
+    /// `quiet_otter_42` or `QuietOtter42`.
+    /// 
+    /// # Arguments
+    /// * `options` - Separator, capitalization, and number of digits
+    /// 
+    /// # Returns
+    /// A generated username
+    /// 
+    /// # Errors
+    /// Returns an error if the number has too many digits
+    pub fn generate_username(&mut self, options: &UsernameOptions) -> Result<String> {
+        if options.digits > MAX_USERNAME_DIGITS {
+            return Err(PassManError::InvalidInput(format!(
+                "Usernames end in at most {} digits",
+                MAX_USERNAME_DIGITS
+            )));
+        }
+        
+        let mut parts: Vec<String> = [ADJECTIVES, NOUNS].iter()
+            .map(|words| {
+                let word = *words.choose(&mut self.rng).expect("word list is not empty");
+                if options.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect();
+        if options.digits > 0 {
+            let digits: Vec<char> = NUMBERS.chars().collect();
+            parts.push((0..options.digits).map(|_| self.random_char_from(&digits)).collect());
+        }
+        
+        Ok(parts.join(&options.separator))
+    }
+    
     /// Generate a passphrase using common words
     /// 
     /// # Arguments
@@ -108,78 +322,143 @@ impl PasswordGenerator {
     /// 
     /// # Returns
     /// A generated passphrase
+    #[cfg(feature = "wordlists")]
     pub fn generate_passphrase(&mut self, word_count: usize, separator: Option<char>) -> Result<String> {
-        if word_count == 0 {
+        let options = PassphraseOptions {
+            word_count,
+            separator: separator.unwrap_or(' ').to_string(),
+            ..Default::default()
+        };
+        self.generate_passphrase_with(&options, &default_word_list())
+    }
+    
+    /// Generate a passphrase with the given options and word list
+    /// 
+    /// # Arguments
+    /// * `options` - Configuration options for passphrase generation
+    /// * `word_list` - Words to choose from (see `default_word_list` and `parse_word_list`)
+    /// 
+    /// # Returns
+    /// A generated passphrase
+    /// 
+    /// # Errors
+    /// Returns an error if the word count is zero or the word list is empty
+    pub fn generate_passphrase_with(&mut self, options: &PassphraseOptions, word_list: &[&str]) -> Result<String> {
+        if options.word_count == 0 {
             return Err(PassManError::InvalidInput("Word count must be greater than 0".to_string()));
         }
-        
-        let words = include_str!("wordlist.txt");
-        let word_list: Vec<&str> = words.lines().collect();
-        
         if word_list.is_empty() {
             return Err(PassManError::InvalidInput("Word list is empty".to_string()));
         }
         
-        let sep = separator.unwrap_or(' ');
-        let mut passphrase = String::new();
+        let mut words: Vec<String> = (0..options.word_count)
+            .map(|_| {
+                let word = *word_list.choose(&mut self.rng).expect("word list is not empty");
+                if options.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect();
+        
+        if options.include_number {
+            let index = self.rng.gen_range(0..words.len());
+            let digit = self.random_char_from(&NUMBERS.chars().collect::<Vec<_>>());
+            words[index].push(digit);
+        }
+        
+        if options.include_symbol {
+            let index = self.rng.gen_range(0..words.len());
+            let symbol = self.random_char_from(&SPECIAL.chars().collect::<Vec<_>>());
+            words[index].push(symbol);
+        }
         
-        for i in 0..word_count {
-            if i > 0 {
-                passphrase.push(sep);
+        Ok(words.join(&options.separator))
+    }
+    
+    /// Generate a passphrase from the generator's word list
+    /// 
+    /// Uses the list given to `with_wordlist`, or the built-in list picked by
+    /// `options.word_list`.
+    /// 
+    /// # Arguments
+    /// * `options` - Configuration options for passphrase generation
+    /// 
+    /// # Returns
+    /// A generated passphrase
+    /// 
+    /// # Errors
+    /// Returns an error if the word count is zero, or there is no custom
+    /// list and the `wordlists` feature is off
+    pub fn generate_passphrase_from_list(&mut self, options: &PassphraseOptions) -> Result<String> {
+        let custom = self.word_list.take();
+        let result = match custom {
+            Some(ref words) => {
+                let words: Vec<&str> = words.iter().map(String::as_str).collect();
+                self.generate_passphrase_with(options, &words)
             }
-            let word = word_list[self.rng.gen_range(0..word_list.len())];
-            passphrase.push_str(word);
+            None => self.generate_passphrase_with(options, &builtin_word_list(options.word_list)),
+        };
+        self.word_list = custom;
+        result
+    }
+    
+    /// Number of words passphrases are picked from (for `passphrase_entropy`)
+    pub fn word_list_len(&self, options: &PassphraseOptions) -> usize {
+        match self.word_list {
+            Some(ref words) => words.len(),
+            None => builtin_word_list(options.word_list).len(),
+        }
+    }
+    
+    /// Calculate the entropy of passphrases generated with the given options
+    /// 
+    /// # Arguments
+    /// * `options` - Passphrase options
+    /// * `word_list_len` - Number of distinct words in the list
+    /// 
+    /// # Returns
+    /// Entropy in bits
+    pub fn passphrase_entropy(options: &PassphraseOptions, word_list_len: usize) -> f64 {
+        if options.word_count == 0 || word_list_len == 0 {
+            return 0.0;
         }
         
-        Ok(passphrase)
+        let mut bits = options.word_count as f64 * (word_list_len as f64).log2();
+        if options.include_number {
+            // Which digit, and which word it was appended to
+            bits += (NUMBERS.len() as f64 * options.word_count as f64).log2();
+        }
+        if options.include_symbol {
+            bits += (SPECIAL.len() as f64 * options.word_count as f64).log2();
+        }
+        bits
     }
     
     /// Calculate password strength score (0-100)
     /// 
+    /// The score comes from the guesses an attacker would need (see
+    /// `strength`); use `estimate_strength` for the crack time as well.
+    /// 
     /// # Arguments
     /// * `password` - The password to analyze
     /// 
     /// # Returns
     /// A strength score from 0 (weak) to 100 (very strong)
     pub fn calculate_strength(&self, password: &str) -> u8 {
-        if password.is_empty() {
-            return 0;
-        }
-        
-        let mut score = 0u8;
-        
-        // Length bonus
-        score += match password.len() {
-            1..=7 => 10,
-            8..=11 => 20,
-            12..=15 => 30,
-            16..=19 => 40,
-            20..=23 => 50,
-            _ => 60,
-        };
-        
-        // Character variety bonus
-        let has_upper = password.chars().any(|c| c.is_uppercase());
-        let has_lower = password.chars().any(|c| c.is_lowercase());
-        let has_digit = password.chars().any(|c| c.is_ascii_digit());
-        let has_special = password.chars().any(|c| "!@#$%^&*()_+-=[]{}|;:,.<>?".contains(c));
-        
-        if has_upper { score += 10; }
-        if has_lower { score += 10; }
-        if has_digit { score += 10; }
-        if has_special { score += 10; }
-        
-        // Pattern penalty
-        if self.has_repeating_patterns(password) {
-            score = score.saturating_sub(20);
-        }
-        
-        // Common password penalty
-        if self.is_common_password(password) {
-            score = score.saturating_sub(30);
-        }
-        
-        score.min(100)
+        strength::estimate(password).score
+    }
+    
+    /// Estimate password strength with the crack time and weaknesses found
+    /// 
+    /// # Arguments
+    /// * `password` - The password to analyze
+    /// 
+    /// # Returns
+    /// The strength score, estimated crack time, and patterns found
+    pub fn estimate_strength(&self, password: &str) -> StrengthEstimate {
+        strength::estimate(password)
     }
     
     /// Get strength description based on score
@@ -202,7 +481,7 @@ impl PasswordGenerator {
     }
     
     /// Validate password generation options
-    fn validate_options(&self, options: &PasswordOptions) -> Result<()> {
+    pub(crate) fn validate_options(&self, options: &PasswordOptions) -> Result<()> {
         if options.length == 0 {
             return Err(PassManError::InvalidInput("Password length must be greater than 0".to_string()));
         }
@@ -216,98 +495,164 @@ impl PasswordGenerator {
             return Err(PassManError::InvalidInput("At least one character type must be enabled".to_string()));
         }
         
-        Ok(())
-    }
-    
-    /// Build character set based on options
-    fn build_charset(&self, options: &PasswordOptions) -> String {
-        let mut charset = String::new();
-        
-        if options.include_uppercase {
-            charset.push_str(UPPERCASE);
-        }
-        if options.include_lowercase {
-            charset.push_str(LOWERCASE);
-        }
-        if options.include_numbers {
-            charset.push_str(NUMBERS);
-        }
-        if options.include_special {
-            charset.push_str(SPECIAL);
+        let minimums = [
+            ("uppercase letters", options.include_uppercase, options.min_uppercase),
+            ("numbers", options.include_numbers, options.min_numbers),
+            ("special characters", options.include_special, options.min_special),
+        ];
+        if let Some((name, ..)) = minimums.iter().find(|(_, included, minimum)| *minimum > 0 && !included) {
+            return Err(PassManError::InvalidInput(format!("A minimum number of {} is set, but they are not included", name)));
         }
         
-        // Remove similar characters if requested
-        if options.exclude_similar {
-            charset = charset.chars()
-                .filter(|c| !SIMILAR_CHARS.contains(*c))
-                .collect();
+        let classes = self.character_classes(options);
+        if let Some(class) = classes.iter().find(|class| class.chars.is_empty()) {
+            return Err(PassManError::InvalidInput(format!("All {} are excluded", class.name)));
         }
-        
-        // Remove ambiguous characters if requested
-        if options.exclude_ambiguous {
-            charset = charset.chars()
-                .filter(|c| !AMBIGUOUS_CHARS.contains(*c))
-                .collect();
+        let required: usize = classes.iter().map(|class| class.required).sum();
+        if required > options.length {
+            return Err(PassManError::InvalidInput(format!(
+                "Password length {} is too short for the {} required characters",
+                options.length,
+                required
+            )));
         }
         
-        charset
+        Ok(())
     }
     
-    /// Get a random character from the given character set
-    fn random_char_from(&mut self, charset: &str) -> char {
-        let index = self.rng.gen_range(0..charset.len());
-        charset.chars().nth(index).unwrap()
+    /// Characters of each enabled class, without excluded characters
+    fn character_classes(&self, options: &PasswordOptions) -> Vec<CharacterClass> {
+        let excluded = |c: &char| {
+            (options.exclude_similar && SIMILAR_CHARS.contains(*c))
+                || (options.exclude_ambiguous && AMBIGUOUS_CHARS.contains(*c))
+                || options.exclude_chars.contains(*c)
+        };
+        [
+            (options.include_uppercase, "uppercase letters", UPPERCASE, options.min_uppercase),
+            (options.include_lowercase, "lowercase letters", LOWERCASE, 0),
+            (options.include_numbers, "numbers", NUMBERS, options.min_numbers),
+            (options.include_special, "special characters", SPECIAL, options.min_special),
+        ]
+        .into_iter()
+        .filter(|(enabled, ..)| *enabled)
+        .map(|(_, name, chars, minimum)| CharacterClass {
+            name,
+            chars: chars.chars().filter(|c| !excluded(c)).collect(),
+            required: minimum.max(1),
+        })
+        .collect()
     }
     
-    /// Shuffle a string in place using Fisher-Yates algorithm
-    fn shuffle_string(&mut self, s: &mut String) {
-        let mut chars: Vec<char> = s.chars().collect();
-        for i in (1..chars.len()).rev() {
-            let j = self.rng.gen_range(0..=i);
-            chars.swap(i, j);
-        }
-        *s = chars.into_iter().collect();
+    /// Pick a character uniformly at random
+    fn random_char_from(&mut self, charset: &[char]) -> char {
+        *charset.choose(&mut self.rng).expect("character set is not empty")
     }
     
-    /// Check if password has repeating patterns
-    fn has_repeating_patterns(&self, password: &str) -> bool {
-        // Check for repeated characters
-        let chars: Vec<char> = password.chars().collect();
-        for i in 0..chars.len().saturating_sub(2) {
-            if chars[i] == chars[i + 1] && chars[i + 1] == chars[i + 2] {
-                return true;
-            }
-        }
-        
-        // Check for keyboard patterns
-        let keyboard_rows = [
-            "qwertyuiop",
-            "asdfghjkl",
-            "zxcvbnm",
-            "1234567890",
-        ];
-        
-        for row in &keyboard_rows {
-            for i in 0..password.len().saturating_sub(2) {
-                let substr = &password[i..i + 3];
-                if row.contains(substr) {
-                    return true;
-                }
-            }
-        }
-        
-        false
+    /// Check if password is in common password list
+    pub(crate) fn is_common_password(&self, password: &str) -> bool {
+        strength::is_common_password(password)
+    }
+}
+
+/// Whether a PIN is among the first an attacker would try
+/// 
+/// That is a repeated digit or block (`0000`, `1212`, `123123`), a run up or
+/// down (`1234`, `9876`, `2468`), a year, a common PIN, or fewer than four
+/// digits. Anything other than digits isn't a PIN and isn't judged here.
+pub fn is_weak_pin(pin: &str) -> bool {
+    let digits: Vec<i32> = pin.chars().filter_map(|c| c.to_digit(10)).map(|d| d as i32).collect();
+    if digits.len() != pin.chars().count() {
+        return false;
+    }
+    if digits.len() < *PIN_LENGTHS.start() {
+        return true;
     }
     
-    /// Check if password is in common password list
-    fn is_common_password(&self, password: &str) -> bool {
-        let common_passwords = [
-            "password", "123456", "123456789", "qwerty", "abc123",
-            "password123", "admin", "letmein", "welcome", "monkey",
-            "1234567890", "password1", "qwerty123", "dragon", "master",
-        ];
-        
-        common_passwords.contains(&password.to_lowercase().as_str())
+    let repeated = (1..=digits.len() / 2).any(|block| {
+        digits.len().is_multiple_of(block) && digits.chunks(block).all(|chunk| chunk == &digits[..block])
+    });
+    let step = digits[1] - digits[0];
+    let run = (1..=2).contains(&step.abs()) && digits.windows(2).all(|pair| pair[1] - pair[0] == step);
+    let year = digits.len() == 4 && (pin.starts_with("19") || pin.starts_with("20"));
+    
+    repeated || run || year || COMMON_PINS.contains(&pin)
+}
+
+/// The built-in passphrase word list
+#[cfg(feature = "wordlists")]
+pub fn default_word_list() -> Vec<&'static str> {
+    distinct_words(DEFAULT_WORDLIST)
+}
+
+/// The EFF long word list
+#[cfg(feature = "wordlists")]
+pub fn eff_long_word_list() -> Vec<&'static str> {
+    distinct_words(EFF_LONG_WORDLIST)
+}
+
+/// A built-in word list, or none without the `wordlists` feature
+fn builtin_word_list(kind: PassphraseWordList) -> Vec<&'static str> {
+    #[cfg(feature = "wordlists")]
+    return match kind {
+        PassphraseWordList::Default => default_word_list(),
+        PassphraseWordList::EffLong => eff_long_word_list(),
+    };
+    #[cfg(not(feature = "wordlists"))]
+    {
+        let _ = kind;
+        Vec::new()
+    }
+}
+
+/// Parse a custom word list
+/// 
+/// Accepts one word per line, ignoring blank lines and `#` comments. Diceware
+/// lists (`11111 word`) are supported by taking the last field of each line.
+/// Duplicates are removed so entropy estimates stay honest.
+/// 
+/// # Arguments
+/// * `contents` - The word list file contents
+/// 
+/// # Returns
+/// The distinct words
+/// 
+/// # Errors
+/// Returns an error if the list has too few distinct words
+pub fn parse_word_list(contents: &str) -> Result<Vec<&str>> {
+    let words = distinct_words(contents);
+    
+    if words.len() < MIN_WORDLIST_SIZE {
+        return Err(PassManError::InvalidInput(format!(
+            "Word list has {} distinct words; at least {} are required",
+            words.len(),
+            MIN_WORDLIST_SIZE
+        )));
+    }
+    
+    Ok(words)
+}
+
+/// The distinct words of a word list, in order
+/// 
+/// Skips blank lines and `#` comments, and takes the last field of each line
+/// so diceware numbering is dropped. Entropy is computed from the length of
+/// this list, so duplicates must not count twice.
+fn distinct_words(contents: &str) -> Vec<&str> {
+    let mut seen = std::collections::HashSet::new();
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|word| seen.insert(*word))
+        .collect()
+}
+
+/// Uppercase the first letter of a word
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
 }
 
@@ -344,13 +689,238 @@ mod tests {
         assert_eq!(password.len(), 16);
     }
     
+    #[test]
+    #[cfg(feature = "wordlists")]
+    fn test_passphrase_options() {
+        let mut generator = PasswordGenerator::new();
+        let options = PassphraseOptions {
+            word_count: 4,
+            separator: ".".to_string(),
+            capitalize: true,
+            include_number: true,
+            include_symbol: false,
+            word_list: PassphraseWordList::Default,
+        };
+        
+        let passphrase = generator.generate_passphrase_with(&options, &default_word_list()).unwrap();
+        let words: Vec<&str> = passphrase.split('.').collect();
+        assert_eq!(words.len(), 4);
+        assert!(words.iter().all(|w| w.starts_with(|c: char| c.is_uppercase())));
+        assert_eq!(passphrase.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+        
+        let options = PassphraseOptions { separator: " ".to_string(), include_symbol: true, ..options };
+        let passphrase = generator.generate_passphrase_with(&options, &default_word_list()).unwrap();
+        assert_eq!(passphrase.split(' ').count(), 4);
+        assert_eq!(passphrase.chars().filter(|c| SPECIAL.contains(*c)).count(), 1);
+    }
+    
+    #[test]
+    #[cfg(feature = "wordlists")]
+    fn test_builtin_word_lists() {
+        let contents = DEFAULT_WORDLIST.lines().filter(|line| !line.is_empty()).count();
+        assert_eq!(default_word_list().len(), contents, "built-in word list has duplicates");
+        
+        let eff = eff_long_word_list();
+        assert_eq!(eff.len(), 7776);
+        assert_eq!(eff.first(), Some(&"abacus"));
+        assert_eq!(eff.last(), Some(&"zoom"));
+        
+        let mut generator = PasswordGenerator::new();
+        let options = PassphraseOptions { word_list: PassphraseWordList::EffLong, ..Default::default() };
+        assert_eq!(generator.word_list_len(&options), 7776);
+        let passphrase = generator.generate_passphrase_from_list(&options).unwrap();
+        assert!(passphrase.split('-').all(|word| eff.contains(&word)));
+        assert!((PasswordGenerator::passphrase_entropy(&options, 7776) - 77.5).abs() < 0.1);
+    }
+    
+    #[test]
+    fn test_passphrase_entropy() {
+        let options = PassphraseOptions { word_count: 4, ..Default::default() };
+        assert_eq!(PasswordGenerator::passphrase_entropy(&options, 1024), 40.0);
+        
+        let with_number = PassphraseOptions { include_number: true, ..options.clone() };
+        assert!(PasswordGenerator::passphrase_entropy(&with_number, 1024) > 45.0);
+        
+        let with_symbol = PassphraseOptions { include_symbol: true, ..with_number.clone() };
+        assert!(PasswordGenerator::passphrase_entropy(&with_symbol, 1024) > PasswordGenerator::passphrase_entropy(&with_number, 1024) + 6.0);
+    }
+    
+    #[test]
+    fn test_with_wordlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        let contents: String = (0..120).map(|i| format!("mot{}\n", i)).collect();
+        std::fs::write(&path, format!("{}mot0\n", contents)).unwrap();
+        
+        let mut generator = PasswordGenerator::with_wordlist(&path).unwrap();
+        let options = PassphraseOptions { word_count: 5, ..Default::default() };
+        assert_eq!(generator.word_list_len(&options), 120);
+        let passphrase = generator.generate_passphrase_from_list(&options).unwrap();
+        assert!(passphrase.split('-').all(|word| word.starts_with("mot")));
+        assert_eq!(passphrase.split('-').count(), 5);
+        
+        std::fs::write(&path, "too\nfew\nwords\n").unwrap();
+        assert!(matches!(PasswordGenerator::with_wordlist(&path), Err(PassManError::InvalidInput(_))));
+        assert!(matches!(PasswordGenerator::with_wordlist(dir.path().join("missing.txt")), Err(PassManError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_parse_word_list() {
+        let diceware: String = (0..150).map(|i| format!("{:05} word{}\n", i, i)).collect();
+        let contents = format!("# comment\n\n{}word1\n", diceware);
+        let words = parse_word_list(&contents).unwrap();
+        assert_eq!(words.len(), 150);
+        assert_eq!(words[0], "word0");
+        
+        assert!(parse_word_list("too\nfew\nwords").is_err());
+    }
+    
+    /// Pearson's chi-squared statistic of observed counts against a uniform distribution
+    fn chi_squared(counts: &[usize]) -> f64 {
+        let expected = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+        counts.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum()
+    }
+    
+    #[test]
+    fn test_uniform_characters() {
+        let mut generator = PasswordGenerator::from_seed(7);
+        let options = PasswordOptions {
+            length: 26,
+            include_uppercase: false,
+            include_numbers: false,
+            include_special: false,
+            exclude_similar: false,
+            ..Default::default()
+        };
+        let mut counts = [0; 26];
+        for _ in 0..1000 {
+            for c in generator.generate(&options).unwrap().chars() {
+                counts[(c as u8 - b'a') as usize] += 1;
+            }
+        }
+        
+        // 25 degrees of freedom: 52.6 is the 99.9th percentile
+        assert!(chi_squared(&counts) < 52.6, "chi-squared {}", chi_squared(&counts));
+    }
+    
+    #[test]
+    fn test_uniform_positions() {
+        // With one character of each class, the uppercase one may be anywhere
+        let mut generator = PasswordGenerator::from_seed(11);
+        let options = PasswordOptions { length: 4, exclude_similar: false, ..PasswordOptions::strong(4) };
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let password = generator.generate(&options).unwrap();
+            counts[password.chars().position(|c| c.is_ascii_uppercase()).unwrap()] += 1;
+        }
+        
+        // 3 degrees of freedom: 16.3 is the 99.9th percentile
+        assert!(chi_squared(&counts) < 16.3, "chi-squared {}", chi_squared(&counts));
+    }
+    
+    #[test]
+    fn test_excluded_characters() {
+        let mut generator = PasswordGenerator::from_seed(3);
+        for _ in 0..200 {
+            let password = generator.generate(&PasswordOptions::simple(8)).unwrap();
+            assert!(!password.chars().any(|c| SIMILAR_CHARS.contains(c) || AMBIGUOUS_CHARS.contains(c)));
+        }
+    }
+    
+    #[test]
+    fn test_minimums_and_exclusions() {
+        let mut generator = PasswordGenerator::from_seed(5);
+        let options = PasswordOptions {
+            length: 12,
+            exclude_chars: "aeiouAEIOU".to_string(),
+            min_uppercase: 3,
+            min_numbers: 4,
+            min_special: 2,
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let password = generator.generate(&options).unwrap();
+            assert_eq!(password.len(), 12);
+            assert!(!password.chars().any(|c| options.exclude_chars.contains(c)));
+            assert!(password.chars().filter(char::is_ascii_uppercase).count() >= 3);
+            assert!(password.chars().filter(char::is_ascii_digit).count() >= 4);
+            assert!(password.chars().filter(|c| SPECIAL.contains(*c)).count() >= 2);
+        }
+        
+        // Constraints no password can meet
+        let too_short = PasswordOptions { length: 9, ..options.clone() };
+        let not_included = PasswordOptions { include_special: false, ..options.clone() };
+        let all_excluded = PasswordOptions { exclude_chars: NUMBERS.to_string(), ..options.clone() };
+        let one_per_class = PasswordOptions::strong(3);
+        for options in [too_short, not_included, all_excluded, one_per_class] {
+            assert!(matches!(generator.generate(&options), Err(PassManError::InvalidInput(_))));
+        }
+    }
+    
+    #[test]
+    fn test_pin() {
+        let mut generator = PasswordGenerator::from_seed(9);
+        for length in [4, 6, 20] {
+            for _ in 0..200 {
+                let pin = generator.generate_pin(length).unwrap();
+                assert_eq!(pin.len(), length);
+                assert!(pin.chars().all(|c| c.is_ascii_digit()));
+                assert!(!is_weak_pin(&pin));
+            }
+        }
+        assert!(generator.generate_pin(3).is_err());
+        assert!(generator.generate_pin(21).is_err());
+        
+        for weak in ["0000", "1234", "9876", "13579", "1212", "123123", "1987", "2580", "147258"] {
+            assert!(is_weak_pin(weak), "{} should be weak", weak);
+        }
+        for fine in ["7391", "4062", "1233", "830519"] {
+            assert!(!is_weak_pin(fine), "{} should not be weak", fine);
+        }
+    }
+    
+    #[test]
+    fn test_username() {
+        let mut generator = PasswordGenerator::from_seed(4);
+        let username = generator.generate_username(&UsernameOptions::default()).unwrap();
+        let parts: Vec<&str> = username.split('_').collect();
+        assert_eq!(parts.len(), 3);
+        assert!(ADJECTIVES.contains(&parts[0]) && NOUNS.contains(&parts[1]));
+        assert!(parts[2].len() == 2 && parts[2].chars().all(|c| c.is_ascii_digit()));
+        
+        let options = UsernameOptions { separator: String::new(), capitalize: true, digits: 0 };
+        let username = generator.generate_username(&options).unwrap();
+        assert!(username.chars().all(char::is_alphabetic));
+        assert_eq!(username.chars().filter(char::is_ascii_uppercase).count(), 2);
+        
+        let options = UsernameOptions { digits: 7, ..Default::default() };
+        assert!(matches!(generator.generate_username(&options), Err(PassManError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_generate_batch() {
+        let mut generator = PasswordGenerator::from_seed(8);
+        let candidates = generator.generate_batch(&PasswordOptions::default(), 5).unwrap();
+        assert_eq!(candidates.len(), 5);
+        for candidate in &candidates {
+            assert_eq!(candidate.password.len(), 16);
+            assert_eq!(candidate.strength, generator.calculate_strength(&candidate.password));
+        }
+        assert!(candidates.windows(2).all(|pair| pair[0].password != pair[1].password));
+        
+        assert!(generator.generate_batch(&PasswordOptions::default(), 0).is_err());
+        assert!(generator.generate_batch(&PasswordOptions::default(), 101).is_err());
+        assert!(generator.generate_batch(&PasswordOptions { length: 0, ..Default::default() }, 3).is_err());
+    }
+    
     #[test]
     fn test_strength_calculation() {
         let generator = PasswordGenerator::new();
         
         assert_eq!(generator.calculate_strength(""), 0);
         assert!(generator.calculate_strength("password") < 50);
-        assert!(generator.calculate_strength("MyStr0ng!P@ssw0rd") > 80);
+        assert!(generator.calculate_strength("MyStr0ng!P@ssw0rd2024") > 80);
+        assert!(generator.calculate_strength("ÄÖÜäöü1!") > 0);
     }
     
     #[test]
@@ -0,0 +1,53 @@
+//! # Importers
+//! 
+//! Readers for other password managers' files. Each importer can be
+//! called directly (e.g. `import_kdbx`), through `PassMan::import_from`
+//! with an `Importer`, or as an `ImportPlugin` registered by
+//! `PluginRegistry::with_builtins`, so front-ends offer it as
+//! `passman import --format <name>`.
+
+pub mod bitwarden;
+pub mod csv;
+pub mod kdbx;
+pub mod lastpass;
+pub mod onepassword;
+
+pub use bitwarden::{BITWARDEN_FORMAT, BitwardenImporter, import_bitwarden, read_bitwarden};
+pub use self::csv::{CSV_FORMAT, ColumnMapping, CsvExporter, CsvField, CsvImporter, read_csv, write_csv};
+pub use kdbx::{KDBX_FORMAT, KdbxImporter, import_kdbx, read_kdbx};
+pub use lastpass::{LASTPASS_FORMAT, LastPassImporter, import_lastpass, read_lastpass};
+pub use onepassword::{ONEPUX_FORMAT, OnePasswordImporter, import_1pux, read_1pux};
+
+use crate::plugins::ImportPlugin;
+
+/// The password managers the backend imports from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Importer {
+    /// CSV with a header row, such as browser password exports
+    Csv,
+    
+    /// KeePass 2 / KeePassXC databases (always password-protected)
+    Kdbx,
+    
+    /// Bitwarden JSON exports, plain or password-protected
+    Bitwarden,
+    
+    /// LastPass CSV exports, including secure notes
+    LastPass,
+    
+    /// 1Password unencrypted exports (`.1pux`)
+    OnePassword,
+}
+
+impl Importer {
+    /// The importer as an import plugin
+    pub fn plugin(self) -> Box<dyn ImportPlugin> {
+        match self {
+            Importer::Csv => Box::new(CsvImporter::default()),
+            Importer::Kdbx => Box::new(KdbxImporter),
+            Importer::Bitwarden => Box::new(BitwardenImporter),
+            Importer::LastPass => Box::new(LastPassImporter),
+            Importer::OnePassword => Box::new(OnePasswordImporter),
+        }
+    }
+}
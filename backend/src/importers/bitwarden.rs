@@ -0,0 +1,458 @@
+//! # Bitwarden Import
+//! 
+//! Reads Bitwarden's JSON exports, both plain ("File format: .json") and
+//! password-protected ("Encrypted JSON" with "Password protected"). Exports
+//! encrypted with the account's own key can't be read outside Bitwarden.
+//! 
+//! Each item becomes an account:
+//! 
+//! - Its folder (and, in organization exports, its collections) become
+//!   tags.
+//! - Item types become account types: cards are `Card`, identities
+//!   `Identity`, and logins, secure notes, and SSH keys `Other`.
+//! - Card and identity details carry over as the account's `card` and
+//!   `identity`.
+//! - A login's first URI is the URL; more URIs and custom fields become
+//!   custom fields, with secrets hidden.
+//! - TOTP secrets, favorites, notes, SSH keys, and dates carry over.
+//! 
+//! Items in the trash are skipped.
+
+use crate::models::{Account, AccountType, CustomField, Identity, PaymentCard, SshKey};
+use crate::plugins::{AccountRecord, ImportPlugin, ImportedAccounts};
+use crate::{PassManError, Result};
+use aes::Aes256;
+use aes::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Name of the Bitwarden import format
+pub const BITWARDEN_FORMAT: &str = "bitwarden";
+
+/// Bitwarden JSON exports as an import format
+pub struct BitwardenImporter;
+
+impl ImportPlugin for BitwardenImporter {
+    fn name(&self) -> &str {
+        BITWARDEN_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "Bitwarden JSON export, plain or password-protected"
+    }
+    
+    fn is_encrypted(&self, data: &[u8]) -> bool {
+        serde_json::from_slice::<Export>(data).is_ok_and(|export| export.password_protected)
+    }
+    
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+        read_bitwarden(&read_all(input)?, None)
+    }
+    
+    fn import_encrypted(&self, input: &mut dyn Read, password: &str) -> Result<ImportedAccounts> {
+        read_bitwarden(&read_all(input)?, Some(password))
+    }
+}
+
+/// Import the items of a Bitwarden JSON export file
+/// 
+/// # Arguments
+/// * `path` - The exported `.json` file
+/// * `password` - The export's password, if it is password-protected
+/// 
+/// # Returns
+/// The accounts read, plus messages for items that were skipped
+/// 
+/// # Errors
+/// Returns an error if the file can't be read, is password-protected and
+/// the password is missing or wrong, or is encrypted with an account key
+pub fn import_bitwarden(path: &Path, password: Option<&str>) -> Result<ImportedAccounts> {
+    let data = std::fs::read(path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+    read_bitwarden(&data, password)
+}
+
+/// Import the items of a Bitwarden JSON export already in memory
+/// 
+/// # Arguments
+/// * `data` - Contents of the exported file
+/// * `password` - The export's password, if it is password-protected
+/// 
+/// # Returns
+/// The accounts read, plus messages for items that were skipped
+/// 
+/// # Errors
+/// Same as `import_bitwarden`
+pub fn read_bitwarden(data: &[u8], password: Option<&str>) -> Result<ImportedAccounts> {
+    let export: Export = serde_json::from_slice(data).map_err(invalid)?;
+    if !export.encrypted {
+        return Ok(read_items(export));
+    }
+    if !export.password_protected {
+        return Err(PassManError::InvalidInput(
+            "The Bitwarden export is encrypted with the account's key; export it again as plain or password-protected JSON".to_string(),
+        ));
+    }
+    let password = password.ok_or_else(|| PassManError::InvalidInput("The Bitwarden export is password-protected".to_string()))?;
+    
+    let keys = export.keys(password)?;
+    let validation = export.enc_key_validation.as_deref().ok_or_else(damaged)?;
+    decrypt(validation, &keys).map_err(|_| PassManError::AuthenticationFailed("Wrong password for the Bitwarden export".to_string()))?;
+    let json = decrypt(export.data.as_deref().ok_or_else(damaged)?, &keys)?;
+    let inner: Export = serde_json::from_slice(&json).map_err(invalid)?;
+    Ok(read_items(inner))
+}
+
+/// A Bitwarden export; encrypted ones carry the plain export in `data`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    password_protected: bool,
+    salt: Option<String>,
+    #[serde(default)]
+    kdf_type: u32,
+    kdf_iterations: Option<u32>,
+    kdf_memory: Option<u32>,
+    kdf_parallelism: Option<u32>,
+    #[serde(rename = "encKeyValidation_DO_NOT_EDIT")]
+    enc_key_validation: Option<String>,
+    data: Option<String>,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    collections: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+/// A folder or collection
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Item {
+    #[serde(rename = "type")]
+    item_type: u32,
+    name: String,
+    notes: Option<String>,
+    favorite: bool,
+    folder_id: Option<String>,
+    collection_ids: Option<Vec<String>>,
+    fields: Option<Vec<Field>>,
+    login: Option<Login>,
+    card: Option<HashMap<String, Option<String>>>,
+    identity: Option<HashMap<String, Option<String>>>,
+    ssh_key: Option<SshKeyItem>,
+    creation_date: Option<DateTime<Utc>>,
+    revision_date: Option<DateTime<Utc>>,
+    deleted_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "type")]
+    field_type: u32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Login {
+    uris: Option<Vec<Uri>>,
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SshKeyItem {
+    private_key: String,
+    public_key: String,
+}
+
+/// Item type numbers
+const LOGIN: u32 = 1;
+const CARD: u32 = 3;
+const IDENTITY: u32 = 4;
+
+/// Custom field type numbers
+const FIELD_HIDDEN: u32 = 1;
+const FIELD_LINKED: u32 = 3;
+
+/// Encryption and MAC keys of a password-protected export
+struct Keys {
+    encryption: Zeroizing<[u8; 32]>,
+    mac: Zeroizing<[u8; 32]>,
+}
+
+impl Export {
+    /// Derive the export's keys from its password
+    fn keys(&self, password: &str) -> Result<Keys> {
+        let salt = self.salt.as_deref().ok_or_else(damaged)?;
+        let iterations = self.kdf_iterations.ok_or_else(damaged)?;
+        let mut master = Zeroizing::new([0u8; 32]);
+        match self.kdf_type {
+            0 => pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, master.as_mut()),
+            1 => {
+                let memory_mib = self.kdf_memory.ok_or_else(damaged)?;
+                let params = Params::new(memory_mib.saturating_mul(1024), iterations, self.kdf_parallelism.ok_or_else(damaged)?, Some(32))
+                    .map_err(|e| PassManError::InvalidInput(format!("Unsupported Argon2 parameters in the Bitwarden export: {}", e)))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), &Sha256::digest(salt.as_bytes()), master.as_mut())
+                    .map_err(|e| PassManError::CryptoError(format!("Key derivation failed: {}", e)))?;
+            }
+            other => return Err(PassManError::InvalidInput(format!("Unsupported key derivation in the Bitwarden export: {}", other))),
+        }
+        
+        // HKDF-Expand of the master key, one block per key
+        let expand = |info: &[u8]| -> Result<Zeroizing<[u8; 32]>> {
+            let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(master.as_ref()).map_err(|_| damaged())?;
+            mac.update(info);
+            mac.update(&[1]);
+            Ok(Zeroizing::new(mac.finalize().into_bytes().into()))
+        };
+        Ok(Keys { encryption: expand(b"enc")?, mac: expand(b"mac")? })
+    }
+}
+
+/// Decrypt a Bitwarden "encrypted string" (`2.<iv>|<data>|<mac>`, AES-256-CBC with HMAC-SHA256)
+fn decrypt(value: &str, keys: &Keys) -> Result<Zeroizing<Vec<u8>>> {
+    let parts = value.strip_prefix("2.").ok_or_else(damaged)?;
+    let parts: Vec<Vec<u8>> = parts.split('|')
+        .map(|part| STANDARD.decode(part).map_err(|_| damaged()))
+        .collect::<Result<_>>()?;
+    let [iv, data, tag] = parts.as_slice() else {
+        return Err(damaged());
+    };
+    
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(keys.mac.as_ref()).map_err(|_| damaged())?;
+    mac.update(iv);
+    mac.update(data);
+    mac.verify_slice(tag).map_err(|_| damaged())?;
+    
+    cbc::Decryptor::<Aes256>::new_from_slices(keys.encryption.as_ref(), iv)
+        .map_err(|_| damaged())?
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map(Zeroizing::new)
+        .map_err(|_| damaged())
+}
+
+/// Turn the items of a plain export into accounts
+fn read_items(export: Export) -> ImportedAccounts {
+    let names: HashMap<&str, &str> = export.folders.iter().chain(&export.collections)
+        .map(|folder| (folder.id.as_str(), folder.name.as_str()))
+        .collect();
+    
+    let mut imported = ImportedAccounts::default();
+    for (index, item) in export.items.iter().filter(|item| item.deleted_date.is_none()).enumerate() {
+        let tags = item.folder_id.iter()
+            .chain(item.collection_ids.iter().flatten())
+            .filter_map(|id| names.get(id.as_str()))
+            .map(|name| name.to_string())
+            .collect();
+        match read_item(item, tags) {
+            Ok(account) => imported.accounts.push(account),
+            Err(e) => imported.errors.push(format!("item {}: {}", index + 1, e)),
+        }
+    }
+    imported
+}
+
+/// Turn one item into an account
+fn read_item(item: &Item, tags: Vec<String>) -> Result<Account> {
+    let account_type = match item.item_type {
+        CARD => AccountType::Card,
+        IDENTITY => AccountType::Identity,
+        _ => AccountType::Other,
+    };
+    let mut record = AccountRecord {
+        name: item.name.clone(),
+        account_type: Some(account_type),
+        notes: item.notes.clone(),
+        tags,
+        ..AccountRecord::default()
+    };
+    
+    let mut fields = Vec::new();
+    if let Some(login) = item.login.as_ref().filter(|_| item.item_type == LOGIN) {
+        let mut uris = login.uris.iter().flatten().filter_map(|uri| uri.uri.clone());
+        record.url = uris.next();
+        for (n, uri) in uris.enumerate() {
+            fields.push(CustomField::new(format!("URL {}", n + 2), uri, false));
+        }
+        record.username = login.username.clone();
+        record.password = login.password.clone().unwrap_or_default();
+        record.totp = login.totp.clone();
+    }
+    let card = item.card.as_ref().filter(|_| item.item_type == CARD).map(|card| {
+        let detail = |key: &str| card.get(key).cloned().flatten().filter(|value| !value.is_empty());
+        let mut details = PaymentCard::new(&detail("number").unwrap_or_default());
+        details.cardholder = detail("cardholderName");
+        details.brand = detail("brand").or(details.brand);
+        details.expiry_month = detail("expMonth").and_then(|month| month.parse().ok());
+        details.expiry_year = detail("expYear").and_then(|year| year.parse().ok())
+            .map(|year: u16| if year < 100 { 2000 + year } else { year });
+        details.cvv = detail("code");
+        details
+    });
+    let identity = item.identity.as_ref().filter(|_| item.item_type == IDENTITY).map(|identity| {
+        let detail = |key: &str| identity.get(key).cloned().flatten().filter(|value| !value.is_empty());
+        record.username = detail("username");
+        let address: Vec<String> = ["address1", "address2", "address3"].into_iter().filter_map(detail).collect();
+        Identity {
+            title: detail("title"),
+            first_name: detail("firstName"),
+            middle_name: detail("middleName"),
+            last_name: detail("lastName"),
+            company: detail("company"),
+            email: detail("email"),
+            phone: detail("phone"),
+            address: (!address.is_empty()).then(|| address.join("\n")),
+            city: detail("city"),
+            state: detail("state"),
+            postal_code: detail("postalCode"),
+            country: detail("country"),
+            national_id: detail("ssn"),
+            passport_number: detail("passportNumber"),
+            license_number: detail("licenseNumber"),
+        }
+    });
+    for field in item.fields.iter().flatten().filter(|field| field.field_type != FIELD_LINKED) {
+        if let (Some(name), Some(value)) = (&field.name, &field.value) {
+            fields.push(CustomField::new(name.as_str(), value.as_str(), field.field_type == FIELD_HIDDEN));
+        }
+    }
+    record.custom_fields = fields;
+    
+    let mut account = record.into_account()?;
+    account.is_favorite = item.favorite;
+    account.card = card;
+    account.identity = identity;
+    account.ssh_key = item.ssh_key.as_ref().map(|key| SshKey {
+        private_key: key.private_key.clone(),
+        public_key: key.public_key.clone(),
+    });
+    if let Some(created) = item.creation_date {
+        account.created_at = created;
+    }
+    if let Some(modified) = item.revision_date {
+        account.updated_at = modified;
+    }
+    Ok(account)
+}
+
+fn read_all(input: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn invalid(e: serde_json::Error) -> PassManError {
+    PassManError::InvalidInput(format!("Not a Bitwarden JSON export: {}", e))
+}
+
+fn damaged() -> PassManError {
+    PassManError::InvalidInput("The Bitwarden export is damaged".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Password of the password-protected test exports
+    const PASSWORD: &str = "correct horse";
+    
+    fn check_import(imported: ImportedAccounts) {
+        assert_eq!(imported.errors, ["item 5: Invalid input: missing name"]);
+        let names: Vec<&str> = imported.accounts.iter().map(|account| account.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "Visa", "Wifi", "Me"]);
+        
+        let github = &imported.accounts[0];
+        assert_eq!(github.account_type, AccountType::Other);
+        assert_eq!(github.tags, ["Work"]);
+        assert_eq!(github.username.as_deref(), Some("octocat"));
+        assert_eq!(github.password, "hunter2");
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.totp.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(github.notes.as_deref(), Some("recovery codes in the safe"));
+        assert!(github.is_favorite);
+        assert_eq!(github.custom_fields, [
+            CustomField::new("URL 2", "https://gist.github.com", false),
+            CustomField::new("Plan", "Pro", false),
+            CustomField::new("PIN", "4321", true),
+            CustomField::new("Verified", "true", false),
+        ]);
+        assert_eq!(github.created_at.to_rfc3339(), "2020-01-02T03:04:05+00:00");
+        assert_eq!(github.updated_at.to_rfc3339(), "2021-06-07T08:09:10+00:00");
+        
+        let visa = &imported.accounts[1];
+        assert_eq!(visa.account_type, AccountType::Card);
+        assert_eq!(visa.tags, ["Finance/Cards"]);
+        assert!(visa.password.is_empty() && visa.custom_fields.is_empty());
+        let card = visa.card.as_ref().unwrap();
+        assert_eq!((card.number.as_str(), card.cvv.as_deref()), ("4111111111111111", Some("123")));
+        assert_eq!((card.cardholder.as_deref(), card.brand.as_deref()), (Some("Jane Doe"), Some("Visa")));
+        assert_eq!(card.expiry().as_deref(), Some("04/2030"));
+        assert!(card.validate().is_ok());
+        
+        let note = &imported.accounts[2];
+        assert_eq!(note.notes.as_deref(), Some("password: abc"));
+        assert!(note.tags.is_empty());
+        
+        let me = &imported.accounts[3];
+        assert_eq!(me.account_type, AccountType::Identity);
+        assert_eq!(me.username.as_deref(), Some("jdoe"));
+        let identity = me.identity.as_ref().unwrap();
+        assert_eq!(identity.full_name().as_deref(), Some("Ms Jane Doe"));
+        assert_eq!(identity.email.as_deref(), Some("jane@example.com"));
+        assert_eq!((identity.address.as_deref(), identity.postal_code.as_deref()), (Some("1 Main St"), Some("12345")));
+        assert_eq!(identity.documents(), [("National ID", "123-45-6789")]);
+    }
+    
+    #[test]
+    fn test_plain_export() {
+        let data = include_bytes!("testdata/bitwarden.json");
+        assert!(!BitwardenImporter.is_encrypted(data));
+        check_import(read_bitwarden(data, None).unwrap());
+    }
+    
+    #[test]
+    fn test_password_protected_export() {
+        for data in [&include_bytes!("testdata/bitwarden_pbkdf2.json")[..], &include_bytes!("testdata/bitwarden_argon2.json")[..]] {
+            assert!(BitwardenImporter.is_encrypted(data));
+            check_import(read_bitwarden(data, Some(PASSWORD)).unwrap());
+            assert!(matches!(read_bitwarden(data, Some("wrong")), Err(PassManError::AuthenticationFailed(_))));
+            assert!(matches!(read_bitwarden(data, None), Err(PassManError::InvalidInput(_))));
+        }
+    }
+    
+    #[test]
+    fn test_account_key_export() {
+        let data = br#"{"encrypted": true, "encKeyValidation_DO_NOT_EDIT": "2.a|b|c", "data": "2.a|b|c"}"#;
+        let error = read_bitwarden(data, Some(PASSWORD)).unwrap_err();
+        assert!(error.to_string().contains("account's key"));
+        assert!(read_bitwarden(b"[]", None).is_err());
+    }
+}
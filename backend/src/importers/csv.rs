@@ -0,0 +1,415 @@
+//! # CSV Import and Export
+//! 
+//! Reads CSV files with a header row, such as the password exports of
+//! Chrome, Edge, Firefox, and Safari, or the columns `passman add --batch`
+//! reads. Each account field is filled from the first column whose name
+//! (ignoring case) is one of the field's usual names:
+//! 
+//! | Field      | Columns                                   |
+//! |------------|-------------------------------------------|
+//! | `name`     | name, title                               |
+//! | `username` | username, user name, login, user          |
+//! | `password` | password                                  |
+//! | `url`      | url, website, web site, uri, login_uri    |
+//! | `notes`    | notes, note, comments, extra              |
+//! | `tags`     | tags (separated by `;` or `,`)            |
+//! | `type`     | type                                      |
+//! | `folder`   | folder, group, grouping                   |
+//! | `totp`     | totp, otp, otpauth                        |
+//! 
+//! A `ColumnMapping` names other columns, e.g. `name=Site` for a file
+//! whose names are in a "Site" column. Rows without a name (Firefox
+//! exports have none) are named after their URL's host.
+//! 
+//! Exports use the layout browsers import, `name,url,username,password,note`
+//! (Chrome's); other fields are left out.
+
+use crate::models::{Account, AccountType, url_host};
+use crate::plugins::{AccountRecord, ExportPlugin, ImportPlugin, ImportedAccounts};
+use crate::{PassManError, Result};
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// Name of the CSV import and export format
+pub const CSV_FORMAT: &str = "csv";
+
+/// Header of exported CSV files
+const EXPORT_HEADER: [&str; 5] = ["name", "url", "username", "password", "note"];
+
+/// An account field a CSV column can fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    /// Account name
+    Name,
+    
+    /// Username or email
+    Username,
+    
+    /// Password
+    Password,
+    
+    /// Website URL
+    Url,
+    
+    /// Notes
+    Notes,
+    
+    /// Tags, separated by `;` or `,`
+    Tags,
+    
+    /// Account type, e.g. `work`
+    Type,
+    
+    /// Folder path
+    Folder,
+    
+    /// TOTP secret or `otpauth://` URI
+    Totp,
+}
+
+impl CsvField {
+    /// Every field, in the order of the table above
+    pub const ALL: [CsvField; 9] = [
+        CsvField::Name,
+        CsvField::Username,
+        CsvField::Password,
+        CsvField::Url,
+        CsvField::Notes,
+        CsvField::Tags,
+        CsvField::Type,
+        CsvField::Folder,
+        CsvField::Totp,
+    ];
+    
+    /// The field's name, as used in mappings
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CsvField::Name => "name",
+            CsvField::Username => "username",
+            CsvField::Password => "password",
+            CsvField::Url => "url",
+            CsvField::Notes => "notes",
+            CsvField::Tags => "tags",
+            CsvField::Type => "type",
+            CsvField::Folder => "folder",
+            CsvField::Totp => "totp",
+        }
+    }
+    
+    /// Column names recognized without a mapping, lowercase
+    fn default_columns(self) -> &'static [&'static str] {
+        match self {
+            CsvField::Name => &["name", "title"],
+            CsvField::Username => &["username", "user name", "login", "user"],
+            CsvField::Password => &["password"],
+            CsvField::Url => &["url", "website", "web site", "uri", "login_uri"],
+            CsvField::Notes => &["notes", "note", "comments", "extra"],
+            CsvField::Tags => &["tags"],
+            CsvField::Type => &["type"],
+            CsvField::Folder => &["folder", "group", "grouping"],
+            CsvField::Totp => &["totp", "otp", "otpauth"],
+        }
+    }
+}
+
+impl FromStr for CsvField {
+    type Err = PassManError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        CsvField::ALL.into_iter()
+            .find(|field| field.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| PassManError::InvalidInput(format!(
+                "Unknown field '{}' (fields: {})",
+                s,
+                CsvField::ALL.map(CsvField::as_str).join(", "),
+            )))
+    }
+}
+
+/// Columns chosen by hand for some fields; other fields use their usual names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnMapping {
+    columns: Vec<(CsvField, String)>,
+}
+
+impl ColumnMapping {
+    /// Create a mapping that only uses the usual column names
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Read a field from a column
+    /// 
+    /// # Arguments
+    /// * `field` - The account field
+    /// * `column` - Name of the column in the header row (case-insensitive)
+    pub fn map(mut self, field: CsvField, column: impl Into<String>) -> Self {
+        self.columns.retain(|(mapped, _)| *mapped != field);
+        self.columns.push((field, column.into()));
+        self
+    }
+    
+    /// Parse mappings written as `field=column`, e.g. `name=Site`
+    /// 
+    /// # Errors
+    /// Returns an error if a mapping has no `=` or names an unknown field
+    pub fn parse<S: AsRef<str>>(mappings: &[S]) -> Result<Self> {
+        mappings.iter().try_fold(Self::new(), |mapping, entry| {
+            let (field, column) = entry.as_ref().split_once('=')
+                .ok_or_else(|| PassManError::InvalidInput(format!("Expected FIELD=COLUMN, got '{}'", entry.as_ref())))?;
+            Ok(mapping.map(field.parse()?, column.trim()))
+        })
+    }
+    
+    /// Index of the column a field comes from
+    /// 
+    /// # Errors
+    /// Returns an error if the field is mapped to a column the header lacks
+    fn position(&self, field: CsvField, headers: &[String]) -> Result<Option<usize>> {
+        if let Some((_, column)) = self.columns.iter().find(|(mapped, _)| *mapped == field) {
+            return headers.iter()
+                .position(|header| header.eq_ignore_ascii_case(column))
+                .map(Some)
+                .ok_or_else(|| PassManError::InvalidInput(format!("The CSV header has no '{}' column", column)));
+        }
+        Ok(field.default_columns().iter().find_map(|name| headers.iter().position(|header| header == name)))
+    }
+}
+
+/// CSV files as an import format
+#[derive(Debug, Clone, Default)]
+pub struct CsvImporter {
+    mapping: ColumnMapping,
+}
+
+impl CsvImporter {
+    /// Create a CSV importer
+    /// 
+    /// # Arguments
+    /// * `mapping` - Columns chosen by hand (see `ColumnMapping`)
+    pub fn new(mapping: ColumnMapping) -> Self {
+        Self { mapping }
+    }
+}
+
+impl ImportPlugin for CsvImporter {
+    fn name(&self) -> &str {
+        CSV_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "CSV with a header row (browser exports, `passman add --batch` columns)"
+    }
+    
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+        read_csv(input, &self.mapping)
+    }
+}
+
+/// Accounts as browser-compatible CSV
+pub struct CsvExporter;
+
+impl ExportPlugin for CsvExporter {
+    fn name(&self) -> &str {
+        CSV_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "CSV as browsers import it (name, url, username, password, note)"
+    }
+    
+    fn export(&self, accounts: &[Account], output: &mut dyn Write) -> Result<()> {
+        write_csv(accounts, output)
+    }
+}
+
+/// Write accounts as browser-compatible CSV
+/// 
+/// # Arguments
+/// * `accounts` - The accounts to write
+/// * `output` - Where the CSV is written
+/// 
+/// # Errors
+/// Returns an error if the output can't be written
+pub fn write_csv(accounts: &[Account], output: &mut dyn Write) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(output);
+    let failed = |e: ::csv::Error| PassManError::StorageError(format!("Failed to write CSV: {}", e));
+    writer.write_record(EXPORT_HEADER).map_err(failed)?;
+    for account in accounts {
+        writer.write_record([
+            account.name.as_str(),
+            account.url.as_deref().unwrap_or_default(),
+            account.username.as_deref().unwrap_or_default(),
+            account.password.as_str(),
+            account.notes.as_deref().unwrap_or_default(),
+        ]).map_err(failed)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Import the rows of a CSV file
+/// 
+/// # Arguments
+/// * `input` - CSV with a header row
+/// * `mapping` - Columns chosen by hand
+/// 
+/// # Returns
+/// The accounts read, plus messages for rows that were skipped, as
+/// "line N: reason"
+/// 
+/// # Errors
+/// Returns an error if the header can't be read, a mapped column is
+/// missing, or neither a name nor a URL column can be found
+pub fn read_csv(input: &mut dyn Read, mapping: &ColumnMapping) -> Result<ImportedAccounts> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(input);
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to read CSV header: {}", e)))?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+    
+    let mut columns = Vec::new();
+    for field in CsvField::ALL {
+        columns.push(mapping.position(field, &headers)?);
+    }
+    if columns[CsvField::Name as usize].is_none() && columns[CsvField::Url as usize].is_none() {
+        return Err(PassManError::InvalidInput("The CSV header has neither a name nor a URL column (see --map)".to_string()));
+    }
+    
+    let mut imported = ImportedAccounts::default();
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 2;
+        let result = record
+            .map_err(|e| format!("malformed row: {}", e))
+            .and_then(|record| {
+                let value = |field: CsvField| {
+                    columns[field as usize]
+                        .and_then(|i| record.get(i))
+                        .filter(|value| !value.is_empty())
+                        .map(str::to_string)
+                };
+                read_row(&value)
+            });
+        match result {
+            Ok(account) => imported.accounts.push(account),
+            Err(reason) => imported.errors.push(format!("line {}: {}", line, reason)),
+        }
+    }
+    Ok(imported)
+}
+
+/// Build an account from the values of one row
+fn read_row(value: &dyn Fn(CsvField) -> Option<String>) -> std::result::Result<crate::models::Account, String> {
+    let url = value(CsvField::Url);
+    let name = value(CsvField::Name)
+        .or_else(|| url.as_deref().and_then(url_host))
+        .ok_or("missing name")?;
+    
+    let account_type = match value(CsvField::Type) {
+        Some(name) => name.parse().map_err(|e: crate::PassManError| e.to_string())?,
+        None => AccountType::Personal,
+    };
+    
+    let totp = value(CsvField::Totp);
+    #[cfg(feature = "totp")]
+    if let Some(secret) = &totp {
+        crate::totp::Totp::parse(secret).map_err(|e| e.to_string())?;
+    }
+    
+    let record = AccountRecord {
+        name,
+        account_type: Some(account_type),
+        username: value(CsvField::Username),
+        password: value(CsvField::Password).unwrap_or_default(),
+        url,
+        notes: value(CsvField::Notes),
+        tags: value(CsvField::Tags)
+            .map(|tags| {
+                tags.split([';', ','])
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        folder: value(CsvField::Folder),
+        totp,
+        custom_fields: Vec::new(),
+    };
+    record.into_account().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn import(csv: &str, mapping: &ColumnMapping) -> Result<ImportedAccounts> {
+        read_csv(&mut csv.as_bytes(), mapping)
+    }
+    
+    #[test]
+    fn test_browser_exports() {
+        let chrome = "name,url,username,password,note\n\
+            github.com,https://github.com/login,octocat,hunter2,\n\
+            ,https://example.com,me,secret,work account\n\
+            ,,nobody,orphan,\n";
+        let imported = import(chrome, &ColumnMapping::new()).unwrap();
+        assert_eq!(imported.errors, ["line 4: missing name"]);
+        assert_eq!(imported.accounts.len(), 2);
+        assert_eq!(imported.accounts[0].name, "github.com");
+        assert_eq!(imported.accounts[0].username.as_deref(), Some("octocat"));
+        assert_eq!(imported.accounts[1].name, "example.com");
+        assert_eq!(imported.accounts[1].notes.as_deref(), Some("work account"));
+        
+        let firefox = "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\"\n\
+            \"https://www.mozilla.org\",\"fox\",\"p@ss\",,\"https://www.mozilla.org\",\"{1}\"\n";
+        let imported = import(firefox, &ColumnMapping::new()).unwrap();
+        assert_eq!(imported.accounts[0].name, "mozilla.org");
+        assert_eq!(imported.accounts[0].password, "p@ss");
+        
+        let safari = "Title,URL,Username,Password,Notes,OTPAuth\nBank,https://bank.example,me,pw,,\n";
+        assert_eq!(import(safari, &ColumnMapping::new()).unwrap().accounts[0].name, "Bank");
+    }
+    
+    #[test]
+    fn test_column_mapping() {
+        let csv = "Site,Login,Secret,Type\nGitHub,octocat,hunter2,work\nBank,me,pw,spaceship\n";
+        let mapping = ColumnMapping::parse(&["name=site", "password = Secret"]).unwrap();
+        let imported = import(csv, &mapping).unwrap();
+        assert_eq!(imported.accounts.len(), 2);
+        assert_eq!(imported.accounts[0].name, "GitHub");
+        assert_eq!(imported.accounts[0].username.as_deref(), Some("octocat"));
+        assert_eq!(imported.accounts[0].password, "hunter2");
+        assert_eq!(imported.accounts[0].account_type, AccountType::Work);
+        assert_eq!(imported.accounts[1].account_type, AccountType::Custom("spaceship".to_string()));
+        assert!(imported.errors.is_empty());
+        
+        assert!(import(csv, &ColumnMapping::new()).is_err());
+        assert!(import(csv, &ColumnMapping::new().map(CsvField::Name, "Title")).is_err());
+        assert!(ColumnMapping::parse(&["name"]).is_err());
+        assert!(ColumnMapping::parse(&["color=Site"]).is_err());
+    }
+    
+    #[test]
+    fn test_export_round_trip() {
+        let mut github = Account::new("GitHub".to_string(), AccountType::Work, "p,w\"1".to_string());
+        github.url = Some("https://github.com".to_string());
+        github.username = Some("octocat".to_string());
+        github.notes = Some("line one\nline two".to_string());
+        let mail = Account::new("Mail".to_string(), AccountType::Email, "pw".to_string());
+        
+        let mut output = Vec::new();
+        CsvExporter.export(&[github, mail], &mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        assert!(csv.starts_with("name,url,username,password,note\nGitHub,https://github.com,octocat,\"p,w\"\"1\",\"line one\nline two\"\n"));
+        
+        let imported = import(&csv, &ColumnMapping::new()).unwrap();
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.accounts[0].password, "p,w\"1");
+        assert_eq!(imported.accounts[0].notes.as_deref(), Some("line one\nline two"));
+        assert_eq!((imported.accounts[1].name.as_str(), imported.accounts[1].url.as_deref()), ("Mail", None));
+    }
+}
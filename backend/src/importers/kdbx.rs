@@ -0,0 +1,895 @@
+//! # KeePass Import
+//! 
+//! Reads KeePass databases (KDBX 3.1 and 4, as saved by KeePass 2 and
+//! KeePassXC) that are locked with a master password alone; key files and
+//! hardware keys aren't supported. AES and ChaCha20 databases can be read,
+//! with either the AES or the Argon2 key derivation.
+//! 
+//! Each entry becomes an account:
+//! 
+//! - Its groups become the folder, e.g. `Internet/Shopping` (the root
+//!   group is left out), and its KeePass tags become tags.
+//! - Title, user name, password, URL, and notes map to the same fields.
+//! - TOTP seeds are kept from the `otp` field (KeePassXC), the
+//!   `TimeOtp-*` fields (KeePass 2), and `TOTP Seed` (KeeTrayTOTP).
+//! - Other fields become custom fields, hidden if KeePass protects them.
+//! 
+//! Entry history and the recycle bin are not imported.
+
+use crate::models::{Account, CustomField};
+use crate::plugins::{AccountRecord, ImportPlugin, ImportedAccounts};
+use crate::crypto::{MAX_KDF_ITERATIONS, MAX_KDF_MEMORY_KIB};
+use crate::{PassManError, Result};
+use aes::Aes256;
+use aes::cipher::{BlockDecryptMut, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher, block_padding::Pkcs7, generic_array::GenericArray};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20::ChaCha20;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use salsa20::Salsa20;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Name of the KeePass import format
+pub const KDBX_FORMAT: &str = "kdbx";
+
+/// First signature of every KeePass database
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+
+/// Second signature of KeePass 2 (KDBX) databases
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+
+/// Second signature of KeePass 1 (KDB) databases
+const SIGNATURE_2_KDB: u32 = 0xB54B_FB65;
+
+const CIPHER_AES256: [u8; 16] = hex16("31c1f2e6bf714350be5805216afc5aff");
+const CIPHER_CHACHA20: [u8; 16] = hex16("d6038a2b8b6f4cb5a524339a31dbb59a");
+const KDF_AES: [u8; 16] = hex16("c9d9f39a628a4460bf740d08c18a4fea");
+const KDF_AES_KDBX4: [u8; 16] = hex16("7c02bb8279a74ac0927d114a00648238");
+const KDF_ARGON2D: [u8; 16] = hex16("ef636ddf8c29444b91f7a9a403e30a0c");
+const KDF_ARGON2ID: [u8; 16] = hex16("9e298b1956db4773b23dfc3ec6f0a1e6");
+
+/// Most AES-KDF rounds accepted; KeePass's one-second setting is far below this
+const MAX_AES_ROUNDS: u64 = 200_000_000;
+
+/// Nonce of the Salsa20 stream protecting values in KDBX 3.1 files
+const SALSA20_NONCE: [u8; 8] = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+
+/// KeePass databases as an import format
+/// 
+/// The master password is passed to `import_encrypted`; `import` alone
+/// fails.
+pub struct KdbxImporter;
+
+impl ImportPlugin for KdbxImporter {
+    fn name(&self) -> &str {
+        KDBX_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "KeePass 2 / KeePassXC database (.kdbx)"
+    }
+    
+    fn is_encrypted(&self, _data: &[u8]) -> bool {
+        true
+    }
+    
+    fn import(&self, _input: &mut dyn Read) -> Result<ImportedAccounts> {
+        Err(PassManError::InvalidInput("KeePass databases can only be imported with their master password".to_string()))
+    }
+    
+    fn import_encrypted(&self, input: &mut dyn Read, password: &str) -> Result<ImportedAccounts> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        read_kdbx(&data, password)
+    }
+}
+
+/// Import the entries of a KeePass database file
+/// 
+/// # Arguments
+/// * `path` - The `.kdbx` file
+/// * `password` - The database's master password
+/// 
+/// # Returns
+/// The accounts read, plus messages for entries that were skipped
+/// 
+/// # Errors
+/// Returns an error if the file can't be read, the password is wrong, or
+/// the database uses a format or cipher that isn't supported
+pub fn import_kdbx(path: &Path, password: &str) -> Result<ImportedAccounts> {
+    let data = std::fs::read(path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+    read_kdbx(&data, password)
+}
+
+/// Import the entries of a KeePass database already in memory
+/// 
+/// # Arguments
+/// * `data` - Contents of a `.kdbx` file
+/// * `password` - The database's master password
+/// 
+/// # Returns
+/// The accounts read, plus messages for entries that were skipped
+/// 
+/// # Errors
+/// Same as `import_kdbx`
+pub fn read_kdbx(data: &[u8], password: &str) -> Result<ImportedAccounts> {
+    let header = Header::parse(data)?;
+    let composite = Zeroizing::new(sha256(&[&sha256(&[password.as_bytes()])]));
+    let transformed = header.kdf.transform(&composite)?;
+    
+    let (xml, mut stream) = if header.major_version >= 4 {
+        decrypt_kdbx4(data, &header, &transformed)?
+    } else {
+        decrypt_kdbx3(data, &header, &transformed)?
+    };
+    let xml = std::str::from_utf8(&xml).map_err(|_| damaged())?;
+    let document = parse_xml(xml, &mut stream)?;
+    Ok(read_entries(&document))
+}
+
+/// Cipher the database payload is encrypted with
+#[derive(Clone, Copy)]
+enum Cipher {
+    Aes256,
+    ChaCha20,
+}
+
+/// How the master password is turned into a key
+enum Kdf {
+    Aes { seed: Vec<u8>, rounds: u64 },
+    Argon2 { algorithm: Algorithm, version: Version, salt: Vec<u8>, memory_kib: u32, iterations: u32, lanes: u32 },
+}
+
+/// The unencrypted header at the start of a database
+struct Header {
+    major_version: u16,
+    
+    /// Length of the header in bytes
+    length: usize,
+    
+    cipher: Cipher,
+    gzip: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    kdf: Kdf,
+    
+    /// KDBX 3.1 only: first bytes of the decrypted payload, to detect a wrong password
+    stream_start: Vec<u8>,
+    
+    /// KDBX 3.1 only: cipher and key of protected values
+    inner_stream: Option<(u32, Vec<u8>)>,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut input = Cursor::new(data);
+        if input.u32()? != SIGNATURE_1 {
+            return Err(PassManError::InvalidInput("Not a KeePass database".to_string()));
+        }
+        match input.u32()? {
+            SIGNATURE_2 => {}
+            SIGNATURE_2_KDB => {
+                return Err(PassManError::InvalidInput(
+                    "KeePass 1 databases (.kdb) aren't supported; save the database as .kdbx first".to_string(),
+                ));
+            }
+            _ => return Err(PassManError::InvalidInput("Not a KeePass database".to_string())),
+        }
+        let minor_version = input.u16()?;
+        let major_version = input.u16()?;
+        if !(3..=4).contains(&major_version) {
+            return Err(PassManError::InvalidInput(format!(
+                "KDBX {}.{} databases aren't supported",
+                major_version, minor_version,
+            )));
+        }
+        
+        let mut cipher = None;
+        let mut gzip = false;
+        let mut master_seed = Vec::new();
+        let mut iv = Vec::new();
+        let mut transform_seed = Vec::new();
+        let mut rounds = 0;
+        let mut kdf = None;
+        let mut stream_start = Vec::new();
+        let mut stream_key = None;
+        let mut stream_id = None;
+        loop {
+            let id = input.u8()?;
+            let size = if major_version >= 4 { input.u32()? as usize } else { input.u16()? as usize };
+            let value = input.take(size)?;
+            match id {
+                0 => break,
+                2 => {
+                    cipher = Some(match value {
+                        v if v == CIPHER_AES256 => Cipher::Aes256,
+                        v if v == CIPHER_CHACHA20 => Cipher::ChaCha20,
+                        _ => {
+                            return Err(PassManError::InvalidInput(
+                                "The KeePass database uses a cipher that isn't supported (only AES and ChaCha20 are)".to_string(),
+                            ));
+                        }
+                    });
+                }
+                3 => gzip = Cursor::new(value).u32()? == 1,
+                4 => master_seed = value.to_vec(),
+                5 => transform_seed = value.to_vec(),
+                6 => rounds = Cursor::new(value).u64()?,
+                7 => iv = value.to_vec(),
+                8 => stream_key = Some(value.to_vec()),
+                9 => stream_start = value.to_vec(),
+                10 => stream_id = Some(Cursor::new(value).u32()?),
+                11 => kdf = Some(parse_kdf_parameters(value)?),
+                _ => {}
+            }
+        }
+        
+        let kdf = match kdf {
+            Some(kdf) => kdf,
+            None if major_version < 4 => Kdf::Aes { seed: transform_seed, rounds },
+            None => return Err(damaged()),
+        };
+        let inner_stream = match (stream_id, stream_key) {
+            (Some(id), Some(key)) => Some((id, key)),
+            _ => None,
+        };
+        if master_seed.len() != 32 || (major_version < 4 && (inner_stream.is_none() || stream_start.is_empty())) {
+            return Err(damaged());
+        }
+        
+        Ok(Self {
+            major_version,
+            length: input.position(),
+            cipher: cipher.ok_or_else(damaged)?,
+            gzip,
+            master_seed,
+            iv,
+            kdf,
+            stream_start,
+            inner_stream,
+        })
+    }
+    
+    /// Key the payload is encrypted with
+    fn payload_key(&self, transformed: &[u8]) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(sha256(&[&self.master_seed, transformed]))
+    }
+    
+    fn decrypt(&self, key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+        match self.cipher {
+            Cipher::Aes256 => cbc::Decryptor::<Aes256>::new_from_slices(key, &self.iv)
+                .map_err(|_| damaged())?
+                .decrypt_padded_vec_mut::<Pkcs7>(data)
+                .map_err(|_| damaged()),
+            Cipher::ChaCha20 => {
+                let mut cipher = ChaCha20::new_from_slices(key, &self.iv).map_err(|_| damaged())?;
+                let mut plain = data.to_vec();
+                cipher.apply_keystream(&mut plain);
+                Ok(plain)
+            }
+        }
+    }
+}
+
+impl Kdf {
+    /// Derive the transformed key from the composite key
+    /// 
+    /// The parameters come from the file, so ones that would run for hours
+    /// or exhaust memory are refused first.
+    fn transform(&self, composite: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>> {
+        self.check_limits()?;
+        let mut transformed = Zeroizing::new([0u8; 32]);
+        match self {
+            Kdf::Aes { seed, rounds } => {
+                let cipher = Aes256::new_from_slice(seed).map_err(|_| damaged())?;
+                let mut blocks = [
+                    GenericArray::clone_from_slice(&composite[..16]),
+                    GenericArray::clone_from_slice(&composite[16..]),
+                ];
+                for _ in 0..*rounds {
+                    cipher.encrypt_blocks(&mut blocks);
+                }
+                *transformed = sha256(&[&blocks[0], &blocks[1]]);
+            }
+            Kdf::Argon2 { algorithm, version, salt, memory_kib, iterations, lanes } => {
+                let params = Params::new(*memory_kib, *iterations, *lanes, Some(32))
+                    .map_err(|e| PassManError::InvalidInput(format!("Unsupported Argon2 parameters in the KeePass database: {}", e)))?;
+                Argon2::new(*algorithm, *version, params)
+                    .hash_password_into(composite, salt, transformed.as_mut())
+                    .map_err(|e| PassManError::CryptoError(format!("Key derivation failed: {}", e)))?;
+            }
+        }
+        Ok(transformed)
+    }
+    
+    fn check_limits(&self) -> Result<()> {
+        let sane = match self {
+            Kdf::Aes { rounds, .. } => *rounds <= MAX_AES_ROUNDS,
+            Kdf::Argon2 { memory_kib, iterations, .. } => *memory_kib <= MAX_KDF_MEMORY_KIB && *iterations <= MAX_KDF_ITERATIONS,
+        };
+        if !sane {
+            return Err(damaged());
+        }
+        Ok(())
+    }
+}
+
+/// Read the KDF parameters of a KDBX 4 header (a KeePass "variant dictionary")
+fn parse_kdf_parameters(data: &[u8]) -> Result<Kdf> {
+    let mut input = Cursor::new(data);
+    if input.u16()? >> 8 != 1 {
+        return Err(damaged());
+    }
+    let mut values = HashMap::new();
+    loop {
+        if input.u8()? == 0 {
+            break;
+        }
+        let name_length = input.u32()? as usize;
+        let name = String::from_utf8_lossy(input.take(name_length)?).into_owned();
+        let value_length = input.u32()? as usize;
+        values.insert(name, input.take(value_length)?);
+    }
+    
+    let value = |name: &str| values.get(name).copied().ok_or_else(damaged);
+    let number = |name: &str| -> Result<u64> {
+        match value(name)? {
+            bytes if bytes.len() == 4 => Ok(Cursor::new(bytes).u32()? as u64),
+            bytes => Cursor::new(bytes).u64(),
+        }
+    };
+    let uuid = value("$UUID")?;
+    if uuid == KDF_AES || uuid == KDF_AES_KDBX4 {
+        return Ok(Kdf::Aes { seed: value("S")?.to_vec(), rounds: number("R")? });
+    }
+    let algorithm = match uuid {
+        u if u == KDF_ARGON2D => Algorithm::Argon2d,
+        u if u == KDF_ARGON2ID => Algorithm::Argon2id,
+        _ => return Err(PassManError::InvalidInput("The KeePass database uses a key derivation that isn't supported".to_string())),
+    };
+    let version = match number("V")? {
+        0x10 => Version::V0x10,
+        0x13 => Version::V0x13,
+        _ => return Err(damaged()),
+    };
+    let small = |n: u64| u32::try_from(n).map_err(|_| damaged());
+    Ok(Kdf::Argon2 {
+        algorithm,
+        version,
+        salt: value("S")?.to_vec(),
+        memory_kib: small(number("M")? / 1024)?,
+        iterations: small(number("I")?)?,
+        lanes: small(number("P")?)?,
+    })
+}
+
+/// Decrypt a KDBX 4 payload
+/// 
+/// # Returns
+/// The XML document and the stream protected values are decrypted with
+fn decrypt_kdbx4(data: &[u8], header: &Header, transformed: &[u8; 32]) -> Result<(Vec<u8>, InnerStream)> {
+    let header_data = &data[..header.length];
+    let mut input = Cursor::new(data);
+    input.take(header.length)?;
+    if input.take(32)? != Sha256::digest(header_data).as_slice() {
+        return Err(damaged());
+    }
+    
+    // Every block has its own MAC key; the header's is block u64::MAX
+    let hmac_base = sha512(&[&header.master_seed, transformed, &[1]]);
+    let block_mac = |index: u64| -> Result<Hmac<Sha256>> {
+        let key = sha512(&[&index.to_le_bytes(), hmac_base.as_ref()]);
+        <Hmac<Sha256> as Mac>::new_from_slice(key.as_ref()).map_err(|_| damaged())
+    };
+    
+    // The header's MAC is the first place a wrong password shows
+    let mut mac = block_mac(u64::MAX)?;
+    mac.update(header_data);
+    mac.verify_slice(input.take(32)?).map_err(|_| wrong_password())?;
+    
+    let mut ciphertext = Vec::new();
+    for index in 0u64.. {
+        let expected = input.take(32)?;
+        let size = input.u32()?;
+        let block = input.take(size as usize)?;
+        let mut mac = block_mac(index)?;
+        mac.update(&index.to_le_bytes());
+        mac.update(&size.to_le_bytes());
+        mac.update(block);
+        mac.verify_slice(expected).map_err(|_| damaged())?;
+        if size == 0 {
+            break;
+        }
+        ciphertext.extend_from_slice(block);
+    }
+    
+    let payload = Zeroizing::new(header.decrypt(&header.payload_key(transformed), &ciphertext)?);
+    let payload = if header.gzip { Zeroizing::new(gunzip(&payload)?) } else { payload };
+    
+    // The inner header, then the XML document
+    let mut input = Cursor::new(&payload);
+    let mut stream_id = None;
+    let mut stream_key = None;
+    loop {
+        let id = input.u8()?;
+        let size = input.u32()? as usize;
+        let value = input.take(size)?;
+        match id {
+            0 => break,
+            1 => stream_id = Some(Cursor::new(value).u32()?),
+            2 => stream_key = Some(Zeroizing::new(value.to_vec())),
+            _ => {}
+        }
+    }
+    let stream = InnerStream::new(stream_id.ok_or_else(damaged)?, &stream_key.ok_or_else(damaged)?)?;
+    Ok((payload[input.position()..].to_vec(), stream))
+}
+
+/// Decrypt a KDBX 3.1 payload
+/// 
+/// # Returns
+/// The XML document and the stream protected values are decrypted with
+fn decrypt_kdbx3(data: &[u8], header: &Header, transformed: &[u8; 32]) -> Result<(Vec<u8>, InnerStream)> {
+    // Bad padding or unexpected start bytes both mean a wrong password
+    let payload = Zeroizing::new(
+        header.decrypt(&header.payload_key(transformed), &data[header.length..]).map_err(|_| wrong_password())?,
+    );
+    if !payload.starts_with(&header.stream_start) {
+        return Err(wrong_password());
+    }
+    
+    let mut input = Cursor::new(&payload[header.stream_start.len()..]);
+    let mut blocks = Zeroizing::new(Vec::new());
+    loop {
+        input.u32()?;
+        let hash = input.take(32)?;
+        let size = input.u32()? as usize;
+        if size == 0 {
+            break;
+        }
+        let block = input.take(size)?;
+        if Sha256::digest(block).as_slice() != hash {
+            return Err(damaged());
+        }
+        blocks.extend_from_slice(block);
+    }
+    
+    let xml = if header.gzip { gunzip(&blocks)? } else { blocks.to_vec() };
+    let (stream_id, stream_key) = header.inner_stream.as_ref().ok_or_else(damaged)?;
+    Ok((xml, InnerStream::new(*stream_id, stream_key)?))
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    flate2::read::GzDecoder::new(data).read_to_end(&mut output).map_err(|_| damaged())?;
+    Ok(output)
+}
+
+/// Stream cipher protecting `Protected="True"` values inside the XML
+/// 
+/// Values are decrypted in document order from one continuous keystream.
+enum InnerStream {
+    None,
+    Salsa20(Box<Salsa20>),
+    ChaCha20(Box<ChaCha20>),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Self> {
+        match id {
+            0 => Ok(InnerStream::None),
+            2 => {
+                let key = Zeroizing::new(sha256(&[key]));
+                Ok(InnerStream::Salsa20(Box::new(Salsa20::new(key.as_ref().into(), &SALSA20_NONCE.into()))))
+            }
+            3 => {
+                let hash = sha512(&[key]);
+                let cipher = ChaCha20::new_from_slices(&hash[..32], &hash[32..44]).map_err(|_| damaged())?;
+                Ok(InnerStream::ChaCha20(Box::new(cipher)))
+            }
+            _ => Err(PassManError::InvalidInput("The KeePass database protects values with a cipher that isn't supported".to_string())),
+        }
+    }
+    
+    /// Decrypt the next protected value (base64 in the XML)
+    fn reveal(&mut self, value: &str) -> Result<String> {
+        let mut data = Zeroizing::new(STANDARD.decode(value.trim()).map_err(|_| damaged())?);
+        match self {
+            InnerStream::None => {}
+            InnerStream::Salsa20(cipher) => cipher.apply_keystream(&mut data),
+            InnerStream::ChaCha20(cipher) => cipher.apply_keystream(&mut data),
+        }
+        String::from_utf8(data.to_vec()).map_err(|_| damaged())
+    }
+}
+
+/// An element of the decrypted XML document
+#[derive(Default)]
+struct Element {
+    name: String,
+    
+    /// Whether this is a `Value Protected="True"`, now decrypted
+    protected: bool,
+    
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn open(start: &BytesStart) -> Result<Self> {
+        let protected = start.try_get_attribute("Protected")
+            .map_err(|_| damaged())?
+            .is_some_and(|attribute| attribute.value.eq_ignore_ascii_case("true"));
+        Ok(Self {
+            name: start.name().as_ref().to_string(),
+            protected,
+            ..Self::default()
+        })
+    }
+    
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+    
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+    
+    /// Text of a child element, if present
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.as_str())
+    }
+}
+
+/// Read the XML document into a tree, decrypting protected values
+fn parse_xml(xml: &str, stream: &mut InnerStream) -> Result<Element> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event().map_err(|_| damaged())? {
+            Event::Start(start) => stack.push(Element::open(&start)?),
+            Event::Empty(start) => {
+                let element = Element::open(&start)?;
+                stack.last_mut().ok_or_else(damaged)?.children.push(element);
+            }
+            Event::End(_) => {
+                let mut element = stack.pop().ok_or_else(damaged)?;
+                if element.protected {
+                    element.text = stream.reveal(&element.text)?;
+                }
+                stack.last_mut().ok_or_else(damaged)?.children.push(element);
+            }
+            Event::Text(text) => stack.last_mut().ok_or_else(damaged)?.text.push_str(&text.xml10_content()),
+            Event::CData(data) => stack.last_mut().ok_or_else(damaged)?.text.push_str(&data),
+            Event::GeneralRef(reference) => {
+                let resolved = match reference.resolve_char_ref().map_err(|_| damaged())? {
+                    Some(c) => c.to_string(),
+                    None => quick_xml::escape::resolve_xml_entity(&reference).ok_or_else(damaged)?.to_string(),
+                };
+                stack.last_mut().ok_or_else(damaged)?.text.push_str(&resolved);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    
+    match stack.len() {
+        1 => stack.pop().ok_or_else(damaged),
+        _ => Err(damaged()),
+    }
+}
+
+/// Turn the entries of the document into accounts
+fn read_entries(document: &Element) -> ImportedAccounts {
+    let mut imported = ImportedAccounts::default();
+    let file = document.child("KeePassFile");
+    let recycle_bin = file
+        .and_then(|file| file.child("Meta"))
+        .filter(|meta| meta.child_text("RecycleBinEnabled").is_some_and(|enabled| enabled.eq_ignore_ascii_case("true")))
+        .and_then(|meta| meta.child_text("RecycleBinUUID"));
+    let Some(root) = file.and_then(|file| file.child("Root")).and_then(|root| root.child("Group")) else {
+        return imported;
+    };
+    
+    let mut entries = Vec::new();
+    collect_entries(root, &mut Vec::new(), recycle_bin, &mut entries);
+    for (index, (folder, entry)) in entries.into_iter().enumerate() {
+        match read_entry(entry, folder) {
+            Ok(account) => imported.accounts.push(account),
+            Err(e) => imported.errors.push(format!("entry {}: {}", index + 1, e)),
+        }
+    }
+    imported
+}
+
+/// Collect the entries of a group and its subgroups with their folder paths
+fn collect_entries<'a>(group: &'a Element, path: &mut Vec<&'a str>, recycle_bin: Option<&str>, entries: &mut Vec<(String, &'a Element)>) {
+    for entry in group.children("Entry") {
+        entries.push((path.join("/"), entry));
+    }
+    for subgroup in group.children("Group") {
+        if recycle_bin.is_some() && subgroup.child_text("UUID") == recycle_bin {
+            continue;
+        }
+        path.push(subgroup.child_text("Name").unwrap_or_default());
+        collect_entries(subgroup, path, recycle_bin, entries);
+        path.pop();
+    }
+}
+
+/// Turn one entry into an account
+fn read_entry(entry: &Element, folder: String) -> Result<Account> {
+    let mut record = AccountRecord {
+        folder: Some(folder),
+        tags: entry.child_text("Tags").unwrap_or_default()
+            .split([';', ','])
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        ..AccountRecord::default()
+    };
+    
+    let mut others = Vec::new();
+    for field in entry.children("String") {
+        let key = field.child_text("Key").unwrap_or_default();
+        let value = field.child("Value");
+        let text = value.map(|value| value.text.clone()).unwrap_or_default();
+        match key {
+            "Title" => record.name = text,
+            "UserName" => record.username = Some(text),
+            "Password" => record.password = text,
+            "URL" => record.url = Some(text),
+            "Notes" => record.notes = Some(text),
+            _ => others.push(CustomField::new(key, text, value.is_some_and(|value| value.protected))),
+        }
+    }
+    record.totp = take_totp(&mut others);
+    record.custom_fields = others.into_iter().filter(|field| !field.value.is_empty()).collect();
+    
+    let mut account = record.into_account()?;
+    if let Some(times) = entry.child("Times") {
+        if let Some(created) = times.child_text("CreationTime").and_then(parse_time) {
+            account.created_at = created;
+        }
+        if let Some(modified) = times.child_text("LastModificationTime").and_then(parse_time) {
+            account.updated_at = modified;
+        }
+    }
+    Ok(account)
+}
+
+/// Remove the fields plugins store TOTP seeds in and build the account's TOTP secret
+fn take_totp(fields: &mut Vec<CustomField>) -> Option<String> {
+    let mut take = |name: &str| {
+        let index = fields.iter().position(|field| field.name == name)?;
+        Some(fields.remove(index).value.trim().to_string()).filter(|value| !value.is_empty())
+    };
+    let otp = take("otp");
+    let secret = take("TimeOtp-Secret-Base32");
+    let length = take("TimeOtp-Length");
+    let period = take("TimeOtp-Period");
+    let algorithm = take("TimeOtp-Algorithm");
+    let seed = take("TOTP Seed");
+    let settings = take("TOTP Settings");
+    if otp.is_some() {
+        return otp;
+    }
+    
+    if let Some(secret) = secret {
+        let algorithm = algorithm.map(|algorithm| algorithm.trim_start_matches("HMAC-").replace('-', ""));
+        return Some(otpauth_uri(&secret, &[("period", period), ("digits", length), ("algorithm", algorithm)]));
+    }
+    
+    // KeeTrayTOTP keeps "<period>;<digits>" beside the seed
+    let seed = seed?;
+    let mut settings = settings.as_deref().unwrap_or_default().split(';').map(str::trim);
+    let number = |value: Option<&str>| value.filter(|value| value.parse::<u32>().is_ok()).map(str::to_string);
+    let period = number(settings.next());
+    let digits = number(settings.next());
+    Some(otpauth_uri(&seed, &[("period", period), ("digits", digits)]))
+}
+
+/// A bare secret, or an `otpauth://` URI if it has settings
+fn otpauth_uri(secret: &str, parameters: &[(&str, Option<String>)]) -> String {
+    let secret: String = secret.chars().filter(|c| !c.is_whitespace()).collect();
+    let parameters: Vec<String> = parameters.iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("&{}={}", name, value)))
+        .collect();
+    if parameters.is_empty() {
+        return secret;
+    }
+    format!("otpauth://totp/KeePass?secret={}{}", secret, parameters.concat())
+}
+
+/// Parse a KeePass time: base64 seconds since year 1 (KDBX 4) or ISO 8601 (KDBX 3.1)
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text.trim()) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let bytes: [u8; 8] = STANDARD.decode(text.trim()).ok()?.try_into().ok()?;
+    let epoch = NaiveDate::from_ymd_opt(1, 1, 1)?.and_hms_opt(0, 0, 0)?.and_utc();
+    epoch.checked_add_signed(Duration::try_seconds(i64::from_le_bytes(bytes))?)
+}
+
+/// SHA-256 of byte strings in a row
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// SHA-512 of byte strings in a row
+fn sha512(parts: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut hash = Zeroizing::new([0u8; 64]);
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+fn damaged() -> PassManError {
+    PassManError::InvalidInput("The KeePass database is damaged or not a KeePass database".to_string())
+}
+
+fn wrong_password() -> PassManError {
+    PassManError::AuthenticationFailed("Wrong password for the KeePass database".to_string())
+}
+
+/// A 16-byte UUID from hex, at compile time
+const fn hex16(hex: &str) -> [u8; 16] {
+    const fn digit(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+    let hex = hex.as_bytes();
+    let mut bytes = [0u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        bytes[i] = digit(hex[2 * i]) << 4 | digit(hex[2 * i + 1]);
+        i += 1;
+    }
+    bytes
+}
+
+/// Little-endian reader over a byte slice
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+    
+    fn position(&self) -> usize {
+        self.position
+    }
+    
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        let end = self.position.checked_add(length).filter(|end| *end <= self.data.len()).ok_or_else(damaged)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+    
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+    
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().map_err(|_| damaged())?))
+    }
+    
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().map_err(|_| damaged())?))
+    }
+    
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().map_err(|_| damaged())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Password of both test databases
+    const PASSWORD: &str = "correct horse";
+    
+    fn check_import(data: &[u8]) {
+        let imported = read_kdbx(data, PASSWORD).unwrap();
+        assert_eq!(imported.errors, ["entry 2: Invalid input: missing name"]);
+        assert_eq!(imported.accounts.len(), 2);
+        
+        let github = &imported.accounts[0];
+        assert_eq!(github.name, "GitHub");
+        assert_eq!(github.username.as_deref(), Some("octocat"));
+        assert_eq!(github.password, "hunter2 & more");
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.notes.as_deref(), Some("line one\nline <two>"));
+        assert_eq!(github.tags, ["dev", "work"]);
+        assert_eq!(github.folder, None);
+        assert_eq!(github.totp.as_deref(), Some("otpauth://totp/GitHub:octocat?secret=JBSWY3DPEHPK3PXP&issuer=GitHub"));
+        assert_eq!(github.custom_fields, [
+            CustomField::new("Recovery code", "1234-5678", true),
+            CustomField::new("Plan", "Pro", false),
+        ]);
+        assert_eq!(github.created_at.to_rfc3339(), "2020-01-02T03:04:05+00:00");
+        assert_eq!(github.updated_at.to_rfc3339(), "2021-06-07T08:09:10+00:00");
+        
+        let store = &imported.accounts[1];
+        assert_eq!(store.name, "Store");
+        assert_eq!(store.folder.as_deref(), Some("Internet/Shopping"));
+        assert_eq!(store.username, None);
+        assert!(store.password.is_empty());
+        assert_eq!(store.totp.as_deref(), Some("otpauth://totp/KeePass?secret=GEZDGNBVGY3TQOJQ&period=60&algorithm=SHA256"));
+        assert!(store.custom_fields.is_empty());
+        
+        assert!(matches!(read_kdbx(data, "wrong"), Err(PassManError::AuthenticationFailed(_))));
+    }
+    
+    #[test]
+    fn test_kdbx4() {
+        check_import(include_bytes!("testdata/kdbx4.kdbx"));
+    }
+    
+    #[test]
+    fn test_kdbx3() {
+        check_import(include_bytes!("testdata/kdbx3.kdbx"));
+    }
+    
+    #[test]
+    fn test_not_a_database() {
+        assert!(matches!(read_kdbx(b"not a database", PASSWORD), Err(PassManError::InvalidInput(_))));
+        
+        let mut kdb = include_bytes!("testdata/kdbx4.kdbx").to_vec();
+        kdb[4..8].copy_from_slice(&SIGNATURE_2_KDB.to_le_bytes());
+        let error = read_kdbx(&kdb, PASSWORD).unwrap_err();
+        assert!(error.to_string().contains("KeePass 1"));
+        
+        let mut truncated = include_bytes!("testdata/kdbx4.kdbx").to_vec();
+        truncated.truncate(truncated.len() - 10);
+        assert!(matches!(read_kdbx(&truncated, PASSWORD), Err(PassManError::InvalidInput(_))));
+        
+        // Key derivation that would hang or exhaust memory is refused up front
+        let composite = [0u8; 32];
+        let aes = Kdf::Aes { seed: vec![0; 32], rounds: u64::MAX };
+        assert!(matches!(aes.transform(&composite), Err(PassManError::InvalidInput(_))));
+        let argon2 = Kdf::Argon2 {
+            algorithm: Algorithm::Argon2id,
+            version: Version::V0x13,
+            salt: vec![0; 32],
+            memory_kib: u32::MAX,
+            iterations: 1,
+            lanes: 1,
+        };
+        assert!(matches!(argon2.transform(&composite), Err(PassManError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_importer_plugin() {
+        let registry = crate::plugins::PluginRegistry::with_builtins();
+        let plugin = registry.importer("KDBX").unwrap();
+        let data = include_bytes!("testdata/kdbx4.kdbx");
+        assert!(plugin.is_encrypted(data));
+        assert!(plugin.import(&mut &data[..]).is_err());
+        assert_eq!(plugin.import_encrypted(&mut &data[..], PASSWORD).unwrap().accounts.len(), 2);
+    }
+}
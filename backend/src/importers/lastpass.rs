@@ -0,0 +1,242 @@
+//! # LastPass Import
+//! 
+//! Reads LastPass CSV exports (`url,username,password,totp,extra,name,
+//! grouping,fav`). LastPass folders become folders, favorites stay
+//! favorites, and the `extra` column becomes the notes.
+//! 
+//! Secure notes (URL `http://sn`) are read by note type: a credit card or
+//! bank account becomes a `Banking` account, an email account `Email`,
+//! an address, passport, license, or insurance `Personal`, and anything
+//! else `Other`. Their details become custom fields (numbers and codes
+//! hidden), except a username or password, which fill those fields.
+//! 
+//! Rows that can't be read are reported one by one; the others are still
+//! imported.
+
+use crate::models::{Account, AccountType, CustomField, url_host};
+use crate::plugins::{AccountRecord, ImportPlugin, ImportedAccounts};
+use crate::{PassManError, Result};
+use std::io::Read;
+use std::path::Path;
+
+/// Name of the LastPass import format
+pub const LASTPASS_FORMAT: &str = "lastpass";
+
+/// URL LastPass gives secure notes
+const SECURE_NOTE_URL: &str = "http://sn";
+
+/// Details of secure notes that are secrets, lowercase
+const HIDDEN_DETAILS: [&str; 8] = [
+    "number",
+    "security code",
+    "pin",
+    "account number",
+    "routing number",
+    "private key",
+    "passphrase",
+    "license key",
+];
+
+/// LastPass CSV exports as an import format
+pub struct LastPassImporter;
+
+impl ImportPlugin for LastPassImporter {
+    fn name(&self) -> &str {
+        LASTPASS_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "LastPass CSV export, including secure notes"
+    }
+    
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+        read_lastpass(input)
+    }
+}
+
+/// Import the rows of a LastPass CSV export file
+/// 
+/// # Arguments
+/// * `path` - The exported `.csv` file
+/// 
+/// # Returns
+/// The accounts read, plus messages for rows that were skipped, as
+/// "line N: reason"
+/// 
+/// # Errors
+/// Returns an error if the file can't be read or its header lacks the
+/// `name` column
+pub fn import_lastpass(path: &Path) -> Result<ImportedAccounts> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+    read_lastpass(&mut std::io::BufReader::new(file))
+}
+
+/// Import the rows of a LastPass CSV export
+/// 
+/// # Arguments
+/// * `input` - The exported CSV
+/// 
+/// # Returns
+/// The accounts read, plus messages for rows that were skipped
+/// 
+/// # Errors
+/// Same as `import_lastpass`
+pub fn read_lastpass(input: &mut dyn Read) -> Result<ImportedAccounts> {
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to read CSV header: {}", e)))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    if !headers.iter().any(|header| header == "name") {
+        return Err(PassManError::InvalidInput("Not a LastPass export: the header has no 'name' column".to_string()));
+    }
+    
+    let mut imported = ImportedAccounts::default();
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 2;
+        let result = record
+            .map_err(|e| format!("malformed row: {}", e))
+            .and_then(|record| {
+                let value = |name: &str| {
+                    headers.iter()
+                        .position(|header| header == name)
+                        .and_then(|i| record.get(i))
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty())
+                        .map(str::to_string)
+                };
+                read_row(&value).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(account) => imported.accounts.push(account),
+            Err(reason) => imported.errors.push(format!("line {}: {}", line, reason)),
+        }
+    }
+    Ok(imported)
+}
+
+/// Build an account from one row
+fn read_row(value: &dyn Fn(&str) -> Option<String>) -> Result<Account> {
+    let url = value("url");
+    let mut record = AccountRecord {
+        name: value("name")
+            .or_else(|| url.as_deref().filter(|url| *url != SECURE_NOTE_URL).and_then(url_host))
+            .unwrap_or_default(),
+        account_type: Some(AccountType::Other),
+        username: value("username"),
+        password: value("password").unwrap_or_default(),
+        notes: value("extra"),
+        folder: value("grouping").map(|folder| folder.replace('\\', "/")),
+        totp: value("totp"),
+        ..AccountRecord::default()
+    };
+    if url.as_deref() == Some(SECURE_NOTE_URL) {
+        read_secure_note(&mut record);
+    } else {
+        record.url = url;
+    }
+    
+    let mut account = record.into_account()?;
+    account.is_favorite = value("fav").as_deref() == Some("1");
+    Ok(account)
+}
+
+/// Turn a secure note's `NoteType:...` lines into the record's type and fields
+fn read_secure_note(record: &mut AccountRecord) {
+    let Some(extra) = record.notes.take() else {
+        return;
+    };
+    let Some(note_type) = extra.strip_prefix("NoteType:").and_then(|rest| rest.lines().next()) else {
+        // A plain note
+        record.notes = Some(extra);
+        return;
+    };
+    record.account_type = Some(match note_type.trim() {
+        "Credit Card" | "Bank Account" => AccountType::Banking,
+        "Email Account" => AccountType::Email,
+        "Address" | "Driver's License" | "Passport" | "Social Security" | "Insurance" | "Health Insurance" | "Membership" => {
+            AccountType::Personal
+        }
+        _ => AccountType::Other,
+    });
+    
+    // "Key:value" lines, then "Notes:" and the rest of the text
+    let mut lines = extra.lines();
+    for line in lines.by_ref() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Notes" => {
+                let rest: Vec<&str> = std::iter::once(value).chain(lines.by_ref()).collect();
+                record.notes = Some(rest.join("\n").trim().to_string());
+                break;
+            }
+            "NoteType" | "Language" => {}
+            _ if value.is_empty() => {}
+            "Username" => record.username = Some(value.to_string()),
+            "Password" => record.password = value.to_string(),
+            key => {
+                let hidden = HIDDEN_DETAILS.contains(&key.to_lowercase().as_str());
+                record.custom_fields.push(CustomField::new(key, value, hidden));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_lastpass_export() {
+        let csv = "url,username,password,totp,extra,name,grouping,fav\n\
+            https://github.com,octocat,hunter2,JBSWY3DPEHPK3PXP,recovery codes,GitHub,Work\\Dev,1\n\
+            http://sn,,,,\"NoteType:Credit Card\nLanguage:en-US\nName on Card:Jane Doe\nType:Visa\nNumber:4111111111111111\nSecurity Code:123\nStart Date:\nExpiration Date:April,2030\nNotes:first line\nsecond line\",Visa,,0\n\
+            http://sn,,,,just some text,Wifi,,0\n\
+            http://sn,,,,\"NoteType:Email Account\nUsername:jane\nPassword:mailpw\nServer:imap.example.com\nNotes:\",Mail,,0\n\
+            https://example.com,me,pw,,,,,0\n\
+            ,me,pw,,,,,0\n";
+        let csv = [csv.as_bytes(), b"https://example.org,\xff,pw,,,Bad,,0\n"].concat();
+        let imported = read_lastpass(&mut csv.as_slice()).unwrap();
+        assert_eq!(imported.errors.len(), 2);
+        assert_eq!(imported.errors[0], "line 7: Invalid input: missing name");
+        assert!(imported.errors[1].starts_with("line 8: malformed row"));
+        
+        let names: Vec<&str> = imported.accounts.iter().map(|account| account.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "Visa", "Wifi", "Mail", "example.com"]);
+        
+        let github = &imported.accounts[0];
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.folder.as_deref(), Some("Work/Dev"));
+        assert_eq!(github.totp.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(github.notes.as_deref(), Some("recovery codes"));
+        assert!(github.is_favorite);
+        
+        let visa = &imported.accounts[1];
+        assert_eq!(visa.account_type, AccountType::Banking);
+        assert_eq!(visa.url, None);
+        assert_eq!(visa.notes.as_deref(), Some("first line\nsecond line"));
+        assert_eq!(visa.custom_fields, [
+            CustomField::new("Name on Card", "Jane Doe", false),
+            CustomField::new("Type", "Visa", false),
+            CustomField::new("Number", "4111111111111111", true),
+            CustomField::new("Security Code", "123", true),
+            CustomField::new("Expiration Date", "April,2030", false),
+        ]);
+        
+        let wifi = &imported.accounts[2];
+        assert_eq!((wifi.account_type.clone(), wifi.notes.as_deref()), (AccountType::Other, Some("just some text")));
+        
+        let mail = &imported.accounts[3];
+        assert_eq!(mail.account_type, AccountType::Email);
+        assert_eq!((mail.username.as_deref(), mail.password.as_str()), (Some("jane"), "mailpw"));
+        assert_eq!(mail.notes, None);
+        assert_eq!(mail.custom_fields, [CustomField::new("Server", "imap.example.com", false)]);
+        
+        assert!(read_lastpass(&mut "a,b\n1,2\n".as_bytes()).is_err());
+    }
+}
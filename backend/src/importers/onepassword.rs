@@ -0,0 +1,500 @@
+//! # 1Password Import
+//! 
+//! Reads 1Password's unencrypted export (`.1pux`), a zip archive whose
+//! `export.data` holds every vault as JSON. The `export.data` file on its
+//! own is accepted too.
+//! 
+//! Each vault's name becomes the folder of its items and 1Password tags
+//! stay tags. Credit cards become `Card` accounts with their details in
+//! `card`; identities, passports, driver licenses, and social security
+//! numbers become `Identity` accounts with their details in `identity`.
+//! Bank accounts and crypto wallets become `Banking` accounts, email
+//! accounts `Email`, other documents such as memberships `Personal`, and
+//! everything else, secure notes included, `Other`. Remaining section
+//! fields become custom fields (concealed ones hidden); the first one-time
+//! password becomes the account's TOTP.
+//! 
+//! Items that can't be read are reported one by one; the others are
+//! still imported.
+
+use crate::models::{Account, AccountType, CustomField, Identity, PaymentCard, SshKey};
+use crate::plugins::{AccountRecord, ImportPlugin, ImportedAccounts};
+use crate::{PassManError, Result};
+use chrono::DateTime;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+
+/// Name of the 1Password import format
+pub const ONEPUX_FORMAT: &str = "1pux";
+
+/// The archive member holding the export
+const EXPORT_DATA: &str = "export.data";
+
+/// 1Password exports (`.1pux`) as an import format
+pub struct OnePasswordImporter;
+
+impl ImportPlugin for OnePasswordImporter {
+    fn name(&self) -> &str {
+        ONEPUX_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "1Password unencrypted export (.1pux)"
+    }
+    
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        read_1pux(&data)
+    }
+}
+
+/// Import the items of a 1Password export file
+/// 
+/// # Arguments
+/// * `path` - The `.1pux` file, or the `export.data` inside it
+/// 
+/// # Returns
+/// The accounts read, plus messages for items that were skipped, as
+/// "item N: reason"
+/// 
+/// # Errors
+/// Returns an error if the file can't be read or isn't a 1Password export
+pub fn import_1pux(path: &Path) -> Result<ImportedAccounts> {
+    let data = std::fs::read(path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+    read_1pux(&data)
+}
+
+/// Import the items of a 1Password export
+/// 
+/// # Arguments
+/// * `data` - The `.1pux` archive, or the `export.data` inside it
+/// 
+/// # Returns
+/// The accounts read, plus messages for items that were skipped
+/// 
+/// # Errors
+/// Same as `import_1pux`
+pub fn read_1pux(data: &[u8]) -> Result<ImportedAccounts> {
+    let json = if data.starts_with(b"PK") {
+        zip_member(data, EXPORT_DATA)?
+    } else {
+        data.to_vec()
+    };
+    let export: Export = serde_json::from_slice(&json)
+        .map_err(|e| PassManError::InvalidInput(format!("Not a 1Password export: {}", e)))?;
+    
+    let mut imported = ImportedAccounts::default();
+    let items = export.accounts.into_iter()
+        .flat_map(|account| account.vaults)
+        .flat_map(|vault| {
+            let name = vault.attrs.name;
+            vault.items.into_iter().map(move |item| (name.clone(), item))
+        });
+    for (index, (vault, item)) in items.enumerate() {
+        let result = serde_json::from_value::<Item>(item)
+            .map_err(|e| PassManError::InvalidInput(e.to_string()))
+            .and_then(|item| read_item(item, &vault));
+        match result {
+            Ok(Some(account)) => imported.accounts.push(account),
+            Ok(None) => {}
+            Err(e) => imported.errors.push(format!("item {}: {}", index + 1, e)),
+        }
+    }
+    Ok(imported)
+}
+
+#[derive(Deserialize)]
+struct Export {
+    accounts: Vec<ExportAccount>,
+}
+
+/// A 1Password account, holding vaults
+#[derive(Deserialize)]
+struct ExportAccount {
+    #[serde(default)]
+    vaults: Vec<Vault>,
+}
+
+#[derive(Deserialize)]
+struct Vault {
+    attrs: VaultAttrs,
+    /// Read one by one, so a malformed item only skips itself
+    #[serde(default)]
+    items: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct VaultAttrs {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(default)]
+    category_uuid: String,
+    #[serde(default)]
+    fav_index: i64,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    #[serde(default)]
+    state: String,
+    details: Details,
+    overview: Overview,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Details {
+    login_fields: Vec<LoginField>,
+    notes_plain: Option<String>,
+    sections: Vec<Section>,
+    /// The password of "Password" items
+    password: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LoginField {
+    value: String,
+    designation: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Section {
+    fields: Vec<Field>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Field {
+    title: String,
+    id: String,
+    /// An object with one key naming the kind of value, e.g. `{"concealed": "..."}`
+    value: serde_json::Map<String, Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Overview {
+    title: String,
+    url: Option<String>,
+    urls: Vec<ItemUrl>,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ItemUrl {
+    url: String,
+}
+
+/// Turn one item into an account; `None` for items in the trash
+fn read_item(item: Item, vault: &str) -> Result<Option<Account>> {
+    if item.state == "deleted" {
+        return Ok(None);
+    }
+    let category = item.category_uuid.as_str();
+    let account_type = match category {
+        "002" => AccountType::Card,
+        // Bank account, crypto wallet
+        "101" | "115" => AccountType::Banking,
+        "111" => AccountType::Email,
+        // Identity, driver license, passport, social security number
+        "004" | "103" | "106" | "108" => AccountType::Identity,
+        // Outdoor license, membership, medical record
+        "104" | "105" | "113" => AccountType::Personal,
+        _ => AccountType::Other,
+    };
+    let mut card = (account_type == AccountType::Card).then(PaymentCard::default);
+    let mut identity = (account_type == AccountType::Identity).then(Identity::default);
+    let details = item.details;
+    let mut record = AccountRecord {
+        name: item.overview.title,
+        account_type: Some(account_type),
+        password: details.password.unwrap_or_default(),
+        notes: details.notes_plain,
+        tags: item.overview.tags,
+        folder: Some(vault.to_string()),
+        ..AccountRecord::default()
+    };
+    
+    for field in details.login_fields {
+        match field.designation.as_deref() {
+            Some("username") => record.username = Some(field.value),
+            Some("password") => record.password = field.value,
+            _ => {}
+        }
+    }
+    
+    let mut urls = item.overview.urls.into_iter().map(|url| url.url).filter(|url| !url.is_empty());
+    record.url = urls.next().or(item.overview.url);
+    for (n, url) in urls.enumerate() {
+        record.custom_fields.push(CustomField::new(format!("URL {}", n + 2), url, false));
+    }
+    
+    let mut ssh_key = None;
+    for field in details.sections.into_iter().flat_map(|section| section.fields) {
+        let Some((kind, value)) = field.value.into_iter().next() else {
+            continue;
+        };
+        let label = if field.title.is_empty() { field.id.clone() } else { field.title };
+        let role = field.id.to_lowercase();
+        if let (Some(identity), "address", Value::Object(address)) = (identity.as_mut(), kind.as_str(), &value) {
+            let part = |key: &str| address.get(key).and_then(Value::as_str).filter(|part| !part.is_empty()).map(str::to_string);
+            identity.address = part("street");
+            identity.city = part("city");
+            identity.state = part("state");
+            identity.postal_code = part("zip");
+            identity.country = part("country");
+            continue;
+        }
+        match (kind.as_str(), value) {
+            ("totp", Value::String(totp)) if record.totp.is_none() && !totp.is_empty() => record.totp = Some(totp),
+            ("sshKey", Value::Object(key)) => {
+                let text = |value: Option<&Value>| value.and_then(Value::as_str).unwrap_or_default().to_string();
+                ssh_key = Some(SshKey {
+                    private_key: text(key.get("privateKey")),
+                    public_key: text(key.get("metadata").and_then(|metadata| metadata.get("publicKey"))),
+                });
+            }
+            (kind, value) => {
+                let Some(text) = field_text(kind, value).filter(|text| !text.is_empty()) else {
+                    continue;
+                };
+                if card.as_mut().is_some_and(|card| card_field(card, kind, &role, &text))
+                    || identity.as_mut().is_some_and(|identity| identity_field(identity, category, &role, &text)) {
+                    continue;
+                }
+                if (role == "username" || role.ends_with("_username")) && record.username.is_none() {
+                    record.username = Some(text);
+                } else if (role == "password" || role.ends_with("_password")) && record.password.is_empty() {
+                    record.password = text;
+                } else {
+                    let hidden = matches!(kind, "concealed" | "totp");
+                    record.custom_fields.push(CustomField::new(label, text, hidden));
+                }
+            }
+        }
+    }
+    
+    let mut account = record.into_account()?;
+    account.is_favorite = item.fav_index > 0;
+    account.archived = item.state == "archived";
+    account.ssh_key = ssh_key;
+    account.card = card.filter(|card| *card != PaymentCard::default());
+    account.identity = identity.filter(|identity| !identity.is_empty());
+    if let Some(created) = item.created_at.and_then(|secs| DateTime::from_timestamp(secs, 0)) {
+        account.created_at = created;
+    }
+    if let Some(modified) = item.updated_at.and_then(|secs| DateTime::from_timestamp(secs, 0)) {
+        account.updated_at = modified;
+    }
+    Ok(Some(account))
+}
+
+/// Take a credit card field into the card's details
+/// 
+/// # Returns
+/// Whether the field was one of the card's details
+fn card_field(card: &mut PaymentCard, kind: &str, role: &str, text: &str) -> bool {
+    match (kind, role) {
+        ("creditCardNumber", _) => {
+            let number = PaymentCard::new(text);
+            card.brand = card.brand.take().or(number.brand);
+            card.number = number.number;
+        }
+        ("creditCardType", _) => card.brand = Some(text.to_string()),
+        ("monthYear", "expiry" | "expiry date") => match PaymentCard::parse_expiry(text) {
+            Ok((month, year)) => (card.expiry_month, card.expiry_year) = (Some(month), Some(year)),
+            Err(_) => return false,
+        },
+        (_, "cardholder" | "cardholder name") => card.cardholder = Some(text.to_string()),
+        (_, "cvv" | "verification number") => card.cvv = Some(text.to_string()),
+        _ => return false,
+    }
+    true
+}
+
+/// Take an identity or document field into the identity's details
+/// 
+/// # Returns
+/// Whether the field was one of the identity's details
+fn identity_field(identity: &mut Identity, category: &str, role: &str, text: &str) -> bool {
+    let detail = match (category, role) {
+        (_, "firstname" | "first name") => &mut identity.first_name,
+        (_, "initial" | "middle name") => &mut identity.middle_name,
+        (_, "lastname" | "last name") => &mut identity.last_name,
+        (_, "company") => &mut identity.company,
+        (_, "email") => &mut identity.email,
+        (_, "defphone" | "cellphone" | "homephone" | "busphone") => &mut identity.phone,
+        ("103", "number") => &mut identity.license_number,
+        ("106", "number") => &mut identity.passport_number,
+        ("108", "number") => &mut identity.national_id,
+        _ => return false,
+    };
+    if detail.is_some() {
+        return false;
+    }
+    *detail = Some(text.to_string());
+    true
+}
+
+/// A section field's value as text
+fn field_text(kind: &str, value: Value) -> Option<String> {
+    match (kind, value) {
+        // Unix seconds
+        ("date", Value::Number(secs)) => {
+            DateTime::from_timestamp(secs.as_i64()?, 0).map(|date| date.format("%Y-%m-%d").to_string())
+        }
+        // YYYYMM
+        ("monthYear", Value::Number(month_year)) => {
+            let month_year = month_year.as_u64()?;
+            Some(format!("{:02}/{}", month_year % 100, month_year / 100))
+        }
+        ("email", Value::Object(email)) => email.get("email_address").and_then(Value::as_str).map(str::to_string),
+        ("address", Value::Object(address)) => {
+            let parts: Vec<&str> = ["street", "city", "state", "zip", "country"].iter()
+                .filter_map(|key| address.get(*key).and_then(Value::as_str))
+                .filter(|part| !part.is_empty())
+                .collect();
+            Some(parts.join(", "))
+        }
+        (_, Value::String(text)) => Some(text),
+        (_, Value::Number(number)) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Read a member of a zip archive, stored or deflated
+fn zip_member(data: &[u8], name: &str) -> Result<Vec<u8>> {
+    let damaged = || PassManError::InvalidInput("The 1Password export is damaged".to_string());
+    let u16_at = |offset: usize| data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |offset: usize| data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    
+    // The end of central directory record is last, before an optional comment
+    let end = (0..data.len().saturating_sub(21)).rev()
+        .find(|&offset| data[offset..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(damaged)?;
+    let count = u16_at(end + 10).ok_or_else(damaged)?;
+    let mut offset = u32_at(end + 16).ok_or_else(damaged)?;
+    
+    for _ in 0..count {
+        if !data.get(offset..).is_some_and(|rest| rest.starts_with(b"PK\x01\x02")) {
+            return Err(damaged());
+        }
+        let method = u16_at(offset + 10).ok_or_else(damaged)?;
+        let size = u32_at(offset + 20).ok_or_else(damaged)?;
+        let name_len = u16_at(offset + 28).ok_or_else(damaged)?;
+        let extra_len = u16_at(offset + 30).ok_or_else(damaged)?;
+        let comment_len = u16_at(offset + 32).ok_or_else(damaged)?;
+        let local = u32_at(offset + 42).ok_or_else(damaged)?;
+        let member = data.get(offset + 46..offset + 46 + name_len).ok_or_else(damaged)?;
+        offset += 46 + name_len + extra_len + comment_len;
+        if member != name.as_bytes() {
+            continue;
+        }
+        
+        let start = local + 30 + u16_at(local + 26).ok_or_else(damaged)? + u16_at(local + 28).ok_or_else(damaged)?;
+        let contents = data.get(start..start + size).ok_or_else(damaged)?;
+        return match method {
+            0 => Ok(contents.to_vec()),
+            8 => {
+                let mut inflated = Vec::new();
+                flate2::read::DeflateDecoder::new(contents).read_to_end(&mut inflated).map_err(|_| damaged())?;
+                Ok(inflated)
+            }
+            _ => Err(PassManError::InvalidInput(format!("Unsupported compression method {} in the 1Password export", method))),
+        };
+    }
+    Err(PassManError::InvalidInput(format!("Not a 1Password export: the archive has no {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::PluginRegistry;
+    
+    #[test]
+    fn test_1pux_export() {
+        let imported = read_1pux(include_bytes!("testdata/export.1pux")).unwrap();
+        assert_eq!(imported.errors.len(), 2);
+        assert_eq!(imported.errors[0], "item 4: Invalid input: missing name");
+        assert!(imported.errors[1].starts_with("item 5: Invalid input: invalid type"));
+        
+        let names: Vec<&str> = imported.accounts.iter().map(|account| account.name.as_str()).collect();
+        assert_eq!(names, ["GitHub", "Visa", "Wifi", "Work mail", "Me", "Passport"]);
+        assert!(imported.accounts.iter().all(|account| account.folder.as_deref() == Some("Personal")));
+        
+        let github = &imported.accounts[0];
+        assert_eq!((github.username.as_deref(), github.password.as_str()), (Some("octocat"), "hunter2"));
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.totp.as_deref(), Some("otpauth://totp/GitHub?secret=JBSWY3DPEHPK3PXP"));
+        assert_eq!(github.notes.as_deref(), Some("recovery codes in the safe"));
+        assert_eq!(github.tags, ["dev", "work"]);
+        assert_eq!(github.custom_fields, [
+            CustomField::new("URL 2", "https://gist.github.com", false),
+            CustomField::new("Recovery PIN", "4321", true),
+            CustomField::new("Plan", "Pro", false),
+        ]);
+        assert!(github.is_favorite && !github.archived);
+        assert_eq!(github.created_at.to_rfc3339(), "2020-01-02T03:04:05+00:00");
+        assert_eq!(github.updated_at.to_rfc3339(), "2021-06-07T08:09:10+00:00");
+        
+        let visa = &imported.accounts[1];
+        assert_eq!(visa.account_type, AccountType::Card);
+        assert!(visa.custom_fields.is_empty());
+        assert_eq!(visa.card, Some(PaymentCard {
+            cardholder: Some("Jane Doe".to_string()),
+            brand: Some("visa".to_string()),
+            number: "4111111111111111".to_string(),
+            expiry_month: Some(4),
+            expiry_year: Some(2030),
+            cvv: Some("123".to_string()),
+        }));
+        
+        let wifi = &imported.accounts[2];
+        assert_eq!(wifi.account_type, AccountType::Other);
+        assert_eq!(wifi.notes.as_deref(), Some("wifi password: abc"));
+        assert!(wifi.archived);
+        
+        let mail = &imported.accounts[3];
+        assert_eq!(mail.account_type, AccountType::Email);
+        assert_eq!((mail.username.as_deref(), mail.password.as_str()), (Some("jane"), "mailpw"));
+        assert_eq!(mail.custom_fields, [
+            CustomField::new("server", "imap.example.com", false),
+            CustomField::new("birthday", "2000-01-01", false),
+        ]);
+        
+        let me = &imported.accounts[4];
+        assert_eq!(me.account_type, AccountType::Identity);
+        let identity = me.identity.as_ref().unwrap();
+        assert_eq!(identity.full_name().as_deref(), Some("Jane Doe"));
+        assert_eq!((identity.address.as_deref(), identity.city.as_deref(), identity.state.as_deref()), (Some("1 Main St"), Some("Springfield"), None));
+        assert_eq!(identity.phone.as_deref(), Some("555-0100"));
+        assert_eq!(me.custom_fields, [CustomField::new("birth date", "2000-01-01", false)]);
+        
+        let passport = &imported.accounts[5];
+        assert_eq!(passport.identity.as_ref().unwrap().documents(), [("Passport", "X1234567")]);
+    }
+    
+    #[test]
+    fn test_bare_export_data() {
+        let json = r#"{"accounts": [{"vaults": [{"attrs": {"name": "Shared"}, "items": [
+            {"categoryUuid": "005", "details": {"password": "s3cret"}, "overview": {"title": "Router"}}
+        ]}]}]}"#;
+        let imported = OnePasswordImporter.import(&mut json.as_bytes()).unwrap();
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.accounts[0].password, "s3cret");
+        assert_eq!(imported.accounts[0].folder.as_deref(), Some("Shared"));
+        
+        assert!(read_1pux(b"PK\x03\x04 not really a zip").is_err());
+        assert!(PluginRegistry::with_builtins().importer(ONEPUX_FORMAT).is_some());
+    }
+}
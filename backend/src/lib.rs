@@ -7,30 +7,122 @@
 //! - Secure vault encryption using AES-GCM-256
 //! - Argon2id key derivation for master passwords
 //! - Local-only storage with no cloud dependencies
+//! - Pluggable storage backends: files, memory, or your own (`StorageBackend`)
+//! - Shared configuration for all front-ends (`PassManConfig`)
 //! - Password generation with customizable options
+//! - Strength estimates that spot dictionary words, dates, sequences, and keyboard walks, with crack times
+//! - Vault password policies enforced when passwords are added or changed
+//! - TOTP two-factor code generation (`totp` feature)
+//! - Password health audits (reused, weak, stale, breached)
+//! - Reminders to change old passwords, with deep links to the accounts
+//! - Offline breach checks against the Pwned Passwords dataset (`breach-check` feature)
+//! - Online breach checks against Have I Been Pwned, sending only hash prefixes (`breach-online` feature, off by default)
+//! - Autotype sequences for filling login forms
+//! - Account label colors and icons, with favicon downloads (`favicon` feature, off by default)
+//! - SSH keys and the ssh-agent protocol (`ssh` feature)
+//! - Signed backups and exports (minisign)
+//! - Import/export formats as plugins, including external executables (`importers` feature)
+//! - Import from KeePass databases (KDBX 3.1 and 4) and Bitwarden, LastPass, and 1Password exports (`importers` feature)
 //! - Account management (CRUD operations)
+//! - Encrypted cache of account names, URLs, and tags for fast listing
+//! - Diffs between vault versions, with secrets masked
+//! - Deep consistency checks of vault files and backups, with repairs
+//! - Field-level merging of vault copies edited on different devices (`sync` feature)
+//! - Emergency access for a trusted contact after a waiting period
+//! - Vault transfer between devices on the local network, paired by QR code (`sync` feature)
+//! - Passphrases from a built-in word list (`wordlists` feature)
+//! - Clipboard access that keeps secrets out of clipboard history (`clipboard` feature)
+//! - Notice of screen locks and system sleep, to lock open vaults (`screen-lock` feature)
+//! - Async API for use from async runtimes (`async` feature)
+//! - Test fixtures for downstream apps (`testing` feature)
+//! - Builds for wasm32 without the default `storage` feature, with a
+//!   read-only vault viewer for browsers (`viewer`; JavaScript bindings
+//!   with the `wasm` feature)
 //! - Memory-safe handling of sensitive data
+//! 
+//! Every feature except `storage` can be turned off for a minimal core of
+//! crypto, models, and (with `storage`) vault files: `default-features =
+//! false, features = ["storage"]`.
 
+#[cfg(feature = "async")]
+pub mod async_vault;
+pub mod appearance;
+pub mod audit;
+pub mod autotype;
 pub mod auth;
+#[cfg(any(feature = "storage", feature = "totp"))]
+mod base32;
+#[cfg(feature = "breach-check")]
+pub mod breach;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+#[cfg(feature = "storage")]
+pub mod config;
 pub mod crypto;
+pub mod diff;
+pub mod format;
+#[cfg(feature = "storage")]
+pub mod fsck;
 pub mod generator;
+#[cfg(feature = "importers")]
+pub mod importers;
 pub mod models;
+#[cfg(all(feature = "storage", feature = "sync"))]
+pub mod pairing;
+#[cfg(feature = "importers")]
+pub mod plugins;
+pub mod policy;
+#[cfg(feature = "storage")]
+pub mod reminders;
+#[cfg(feature = "storage")]
+pub mod search_cache;
+pub mod signing;
+#[cfg(feature = "screen-lock")]
+pub mod screenlock;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "storage")]
 pub mod storage;
+pub mod storage_backend;
+pub mod strength;
+pub mod sync;
+#[cfg(feature = "totp")]
+pub mod totp;
+#[cfg(all(feature = "storage", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "storage")]
 pub mod vault;
+pub mod viewer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 // Re-export main types for easy access
 pub use models::*;
-pub use vault::PassMan;
+#[cfg(feature = "storage")]
+pub use vault::{PassMan, VaultInitOptions};
+#[cfg(feature = "storage")]
+pub use config::PassManConfig;
+#[cfg(feature = "async")]
+pub use async_vault::AsyncPassMan;
 
 /// Result type alias for PassMan operations
 pub type Result<T> = std::result::Result<T, PassManError>;
 
 /// Main error type for PassMan operations
+/// 
+/// Every variant has a stable numeric code and kind name (see `code` and
+/// `kind`), so front-ends can branch on the error without parsing its
+/// message; `info` bundles them for serialization.
 #[derive(thiserror::Error, Debug)]
 pub enum PassManError {
+    /// Wrong master password, keyfile, or other credential
     #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
     
+    /// Too many failed unlock attempts; retry later
+    #[error("Locked out: {0}")]
+    LockedOut(String),
+    
     #[error("Encryption error: {0}")]
     EncryptionError(String),
     
@@ -40,6 +132,17 @@ pub enum PassManError {
     #[error("Vault not found: {0}")]
     VaultNotFound(String),
     
+    /// A vault with the name is already there
+    #[error("Vault already exists: {0}")]
+    VaultExists(String),
+    
+    /// The vault file can't be parsed (as opposed to a wrong password)
+    #[error("Vault is corrupted: {0}")]
+    VaultCorrupted(String),
+    
+    #[error("Vault locked: {0}")]
+    VaultLocked(String),
+    
     #[error("Account not found: {0}")]
     AccountNotFound(String),
     
@@ -54,4 +157,87 @@ pub enum PassManError {
     
     #[error("Crypto error: {0}")]
     CryptoError(String),
+    
+    #[error("Password doesn't meet the vault's password policy: {}", policy::describe(.0))]
+    PolicyViolation(Vec<policy::PolicyViolation>),
+}
+
+impl PassManError {
+    /// Stable numeric code of the error kind
+    /// 
+    /// Codes are never reused or renumbered; new kinds get new codes.
+    pub fn code(&self) -> u16 {
+        match self {
+            PassManError::AuthenticationFailed(_) => 1,
+            PassManError::LockedOut(_) => 2,
+            PassManError::VaultLocked(_) => 3,
+            PassManError::VaultNotFound(_) => 4,
+            PassManError::VaultExists(_) => 5,
+            PassManError::VaultCorrupted(_) => 6,
+            PassManError::AccountNotFound(_) => 7,
+            PassManError::InvalidInput(_) => 8,
+            PassManError::PolicyViolation(_) => 9,
+            PassManError::StorageError(_) => 10,
+            PassManError::IoError(_) => 11,
+            PassManError::SerializationError(_) => 12,
+            PassManError::EncryptionError(_) => 13,
+            PassManError::CryptoError(_) => 14,
+        }
+    }
+    
+    /// Stable name of the error kind, e.g. `authentication_failed`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PassManError::AuthenticationFailed(_) => "authentication_failed",
+            PassManError::LockedOut(_) => "locked_out",
+            PassManError::VaultLocked(_) => "vault_locked",
+            PassManError::VaultNotFound(_) => "vault_not_found",
+            PassManError::VaultExists(_) => "vault_exists",
+            PassManError::VaultCorrupted(_) => "vault_corrupted",
+            PassManError::AccountNotFound(_) => "account_not_found",
+            PassManError::InvalidInput(_) => "invalid_input",
+            PassManError::PolicyViolation(_) => "policy_violation",
+            PassManError::StorageError(_) => "storage_error",
+            PassManError::IoError(_) => "io_error",
+            PassManError::SerializationError(_) => "serialization_error",
+            PassManError::EncryptionError(_) => "encryption_error",
+            PassManError::CryptoError(_) => "crypto_error",
+        }
+    }
+    
+    /// Serializable description of the error
+    pub fn info(&self) -> ErrorInfo {
+        ErrorInfo {
+            code: self.code(),
+            kind: self.kind().to_string(),
+            message: self.to_string(),
+            violations: match self {
+                PassManError::PolicyViolation(violations) => violations.clone(),
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// An error as sent to front-ends (Tauri commands, the local API)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct ErrorInfo {
+    /// Stable numeric code (see `PassManError::code`)
+    pub code: u16,
+    
+    /// Stable name of the error kind (see `PassManError::kind`)
+    pub kind: String,
+    
+    /// Human-readable message
+    pub message: String,
+    
+    /// The broken rules of a `policy_violation`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<policy::PolicyViolation>,
+}
+
+impl From<PassManError> for ErrorInfo {
+    fn from(error: PassManError) -> Self {
+        error.info()
+    }
 }
\ No newline at end of file
@@ -4,10 +4,12 @@
 //! including accounts, vault metadata, and configuration options.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::OnceLock;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use zeroize::Zeroize;
+use crate::policy::{PasswordPolicy, SitePolicy};
 
 /// Represents a password account entry in the vault
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -36,6 +38,73 @@ pub struct Account {
     /// Tags for organizing accounts
     pub tags: Vec<String>,
     
+    /// Folder the account is filed under
+    #[serde(default)]
+    pub folder: Option<String>,
+    
+    /// Whether the account is marked as a favorite
+    #[serde(default)]
+    pub is_favorite: bool,
+    
+    /// Label color as lowercase `#rrggbb` (see `appearance::normalize_color`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    
+    /// Icon: a short text like an emoji, or an image URL (see `appearance::normalize_icon`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    
+    /// Whether the account is archived (hidden from normal listings)
+    #[serde(default)]
+    pub archived: bool,
+    
+    /// Whether the account is a canary: fake credentials for a service the
+    /// user controls, watched for use by external monitoring (see `audit::canaries`)
+    #[serde(default)]
+    pub canary: bool,
+    
+    /// When the password expires and has to be changed
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    
+    /// Days a password may be kept before it should be rotated
+    #[serde(default)]
+    pub rotation_interval_days: Option<u32>,
+    
+    /// TOTP secret (base32 or otpauth URI) for two-factor codes
+    #[serde(default)]
+    pub totp: Option<String>,
+    
+    /// Additional user-defined fields (PINs, security questions, etc.)
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    
+    /// Custom autotype sequence (see `autotype`); None uses the default
+    #[serde(default)]
+    pub autotype: Option<String>,
+    
+    /// SSH key pair served by `passman ssh-agent`
+    #[serde(default)]
+    pub ssh_key: Option<SshKey>,
+    
+    /// Payment card details, for `Card` accounts
+    #[serde(default)]
+    pub card: Option<PaymentCard>,
+    
+    /// Personal details, for `Identity` accounts
+    #[serde(default)]
+    pub identity: Option<Identity>,
+    
+    /// What the site accepts as a password, used when one is generated
+    /// for the account (see `Vault::site_policy`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_policy: Option<SitePolicy>,
+    
+    /// When each field last changed, keyed by `sync::FIELDS` names; used
+    /// to merge copies edited on different devices
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_times: BTreeMap<String, DateTime<Utc>>,
+    
     /// When this account was created
     pub created_at: DateTime<Utc>,
     
@@ -44,6 +113,10 @@ pub struct Account {
     
     /// When this account was last accessed
     pub last_accessed: Option<DateTime<Utc>>,
+    
+    /// How many times the account has been accessed (see `mark_accessed`)
+    #[serde(default)]
+    pub access_count: u32,
 }
 
 impl Account {
@@ -67,15 +140,63 @@ impl Account {
             password,
             notes: None,
             tags: Vec::new(),
+            folder: None,
+            is_favorite: false,
+            color: None,
+            icon: None,
+            archived: false,
+            canary: false,
+            expires_at: None,
+            rotation_interval_days: None,
+            totp: None,
+            custom_fields: Vec::new(),
+            autotype: None,
+            ssh_key: None,
+            card: None,
+            identity: None,
+            site_policy: None,
+            field_times: BTreeMap::new(),
             created_at: now,
             updated_at: now,
             last_accessed: None,
+            access_count: 0,
         }
     }
     
-    /// Update the last accessed timestamp
+    /// Update the last accessed timestamp and count the access
     pub fn mark_accessed(&mut self) {
         self.last_accessed = Some(Utc::now());
+        self.access_count = self.access_count.saturating_add(1);
+    }
+    
+    /// When the password has to be changed: at `expires_at`, or
+    /// `rotation_interval_days` after it last changed, whichever is earlier
+    /// 
+    /// # Returns
+    /// The date, or None if the account has neither
+    pub fn password_expiry(&self) -> Option<DateTime<Utc>> {
+        let rotation_due = self.rotation_interval_days
+            .map(|days| crate::sync::field_time(self, "password") + chrono::Duration::days(days.into()));
+        [self.expires_at, rotation_due].into_iter().flatten().min()
+    }
+    
+    /// Whether the account is used with a password; cards, identities, and
+    /// SSH keys without one aren't checked against password policies or audited
+    pub fn uses_password(&self) -> bool {
+        !self.password.is_empty() || (self.card.is_none() && self.identity.is_none() && self.ssh_key.is_none())
+    }
+    
+    /// Find a custom field by name (case-insensitive)
+    pub fn custom_field(&self, name: &str) -> Option<&CustomField> {
+        self.custom_fields.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+    
+    /// Get the lowercase host of the account's URL, without `www.`
+    /// 
+    /// # Returns
+    /// The host, or None if the account has no usable URL
+    pub fn host(&self) -> Option<String> {
+        url_host(self.url.as_deref()?)
     }
     
     /// Update the account with new data
@@ -87,8 +208,577 @@ impl Account {
     }
 }
 
+impl Zeroize for Account {
+    /// Overwrite every string of the account; IDs, flags, and times are kept
+    fn zeroize(&mut self) {
+        self.name.zeroize();
+        self.url.zeroize();
+        self.username.zeroize();
+        self.password.zeroize();
+        self.notes.zeroize();
+        self.tags.zeroize();
+        self.folder.zeroize();
+        self.color.zeroize();
+        self.icon.zeroize();
+        self.totp.zeroize();
+        self.custom_fields.zeroize();
+        self.autotype.zeroize();
+        self.ssh_key.zeroize();
+        self.card.zeroize();
+        self.identity.zeroize();
+        if let AccountType::Custom(name) = &mut self.account_type {
+            name.zeroize();
+        }
+    }
+}
+
+/// Builder for an `Account`, field by field
+/// 
+/// Accounts start as type `Other` with an empty password.
+/// 
+/// ```
+/// use passman_backend::{AccountBuilder, AccountType};
+/// 
+/// let account = AccountBuilder::new("GitHub")
+///     .account_type(AccountType::Work)
+///     .password("correct horse")
+///     .username("octocat")
+///     .tag("dev")
+///     .build();
+/// assert_eq!(account.username.as_deref(), Some("octocat"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccountBuilder {
+    account: Account,
+}
+
+impl AccountBuilder {
+    /// Start an account with the given name
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            account: Account::new(name.into(), AccountType::Other, String::new()),
+        }
+    }
+    
+    /// Set the account type
+    pub fn account_type(mut self, account_type: AccountType) -> Self {
+        self.account.account_type = account_type;
+        self
+    }
+    
+    /// Set the password
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.account.password = password.into();
+        self
+    }
+    
+    /// Set the username
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.account.username = Some(username.into());
+        self
+    }
+    
+    /// Set the URL
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.account.url = Some(url.into());
+        self
+    }
+    
+    /// Set the notes
+    pub fn notes(mut self, notes: impl Into<String>) -> Self {
+        self.account.notes = Some(notes.into());
+        self
+    }
+    
+    /// Add a tag
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.account.tags.push(tag.into());
+        self
+    }
+    
+    /// Set the folder
+    pub fn folder(mut self, folder: impl Into<String>) -> Self {
+        self.account.folder = Some(folder.into());
+        self
+    }
+    
+    /// Set the TOTP secret
+    pub fn totp(mut self, secret: impl Into<String>) -> Self {
+        self.account.totp = Some(secret.into());
+        self
+    }
+    
+    /// Add a custom field
+    pub fn custom_field(mut self, field: CustomField) -> Self {
+        self.account.custom_fields.push(field);
+        self
+    }
+    
+    /// Set the label color (`#rrggbb`)
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.account.color = Some(color.into());
+        self
+    }
+    
+    /// Set the icon
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.account.icon = Some(icon.into());
+        self
+    }
+    
+    /// Set the custom autotype sequence
+    pub fn autotype(mut self, sequence: impl Into<String>) -> Self {
+        self.account.autotype = Some(sequence.into());
+        self
+    }
+    
+    /// Attach an SSH key
+    pub fn ssh_key(mut self, key: SshKey) -> Self {
+        self.account.ssh_key = Some(key);
+        self
+    }
+    
+    /// Attach payment card details
+    pub fn card(mut self, card: PaymentCard) -> Self {
+        self.account.card = Some(card);
+        self
+    }
+    
+    /// Attach personal details
+    pub fn identity(mut self, identity: Identity) -> Self {
+        self.account.identity = Some(identity);
+        self
+    }
+    
+    /// Mark the account as a favorite
+    pub fn favorite(mut self) -> Self {
+        self.account.is_favorite = true;
+        self
+    }
+    
+    /// Archive the account
+    pub fn archived(mut self) -> Self {
+        self.account.archived = true;
+        self
+    }
+    
+    /// Mark the account as a canary
+    pub fn canary(mut self) -> Self {
+        self.account.canary = true;
+        self
+    }
+    
+    /// Set the date the password expires
+    pub fn expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.account.expires_at = Some(expires_at);
+        self
+    }
+    
+    /// Set the number of days after which the password should be rotated
+    pub fn rotation_interval_days(mut self, days: u32) -> Self {
+        self.account.rotation_interval_days = Some(days);
+        self
+    }
+    
+    /// Finish the account
+    pub fn build(self) -> Account {
+        self.account
+    }
+}
+
+/// Changes to some fields of an account (see `PassMan::patch_account`)
+/// 
+/// Fields left as None are not changed. Optional fields take `Some(None)`
+/// to clear them; in JSON a missing key keeps the value and `null` clears it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AccountPatch {
+    /// New display name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    
+    /// New type/category
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<AccountType>,
+    
+    /// New password
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    
+    /// New or cleared website URL
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub url: Option<Option<String>>,
+    
+    /// New or cleared username
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub username: Option<Option<String>>,
+    
+    /// New or cleared notes
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub notes: Option<Option<String>>,
+    
+    /// New tags, replacing the old ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    
+    /// New or cleared folder
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub folder: Option<Option<String>>,
+    
+    /// Whether the account is a favorite
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_favorite: Option<bool>,
+    
+    /// New or cleared label color
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub color: Option<Option<String>>,
+    
+    /// New or cleared icon
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Option<String>>,
+    
+    /// New or cleared TOTP secret
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub totp: Option<Option<String>>,
+    
+    /// New custom fields, replacing the old ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<Vec<CustomField>>,
+    
+    /// New or cleared autotype sequence
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub autotype: Option<Option<String>>,
+    
+    /// New or cleared expiry date
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+    
+    /// New or cleared rotation interval, in days
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub rotation_interval_days: Option<Option<u32>>,
+    
+    /// New or cleared payment card details
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub card: Option<Option<PaymentCard>>,
+    
+    /// New or cleared personal details
+    #[serde(deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Option<Identity>>,
+}
+
+impl AccountPatch {
+    /// Whether the patch changes nothing
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Apply the changes to an account (`updated_at` is left to the caller)
+    pub fn apply(self, account: &mut Account) {
+        fn set<T>(field: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        set(&mut account.name, self.name);
+        set(&mut account.account_type, self.account_type);
+        set(&mut account.password, self.password);
+        set(&mut account.url, self.url);
+        set(&mut account.username, self.username);
+        set(&mut account.notes, self.notes);
+        set(&mut account.tags, self.tags);
+        set(&mut account.folder, self.folder);
+        set(&mut account.is_favorite, self.is_favorite);
+        set(&mut account.color, self.color);
+        set(&mut account.icon, self.icon);
+        set(&mut account.totp, self.totp);
+        set(&mut account.custom_fields, self.custom_fields);
+        set(&mut account.autotype, self.autotype);
+        set(&mut account.expires_at, self.expires_at);
+        set(&mut account.rotation_interval_days, self.rotation_interval_days);
+        set(&mut account.card, self.card);
+        set(&mut account.identity, self.identity);
+    }
+}
+
+impl Zeroize for AccountPatch {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.notes.zeroize();
+        self.totp.zeroize();
+        self.custom_fields.zeroize();
+        self.card.zeroize();
+        self.identity.zeroize();
+    }
+}
+
+/// Deserialize a present value, even `null`, as `Some`, so that a missing
+/// field (None through `#[serde(default)]`) differs from a cleared one
+fn double_option<'de, T, D>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A user-defined field attached to an account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Zeroize)]
+pub struct CustomField {
+    /// Field label (e.g., "PIN", "Security question")
+    pub name: String,
+    
+    /// Field value
+    pub value: String,
+    
+    /// Whether the value is secret and should be masked when displayed
+    pub hidden: bool,
+}
+
+impl CustomField {
+    /// Create a custom field
+    /// 
+    /// # Arguments
+    /// * `name` - Field label
+    /// * `value` - Field value
+    /// * `hidden` - Whether to mask the value when displayed
+    pub fn new(name: impl Into<String>, value: impl Into<String>, hidden: bool) -> Self {
+        Self { name: name.into(), value: value.into(), hidden }
+    }
+}
+
+/// An SSH key pair attached to an account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Zeroize)]
+pub struct SshKey {
+    /// Private key in OpenSSH format, without a passphrase (the vault
+    /// itself is encrypted)
+    pub private_key: String,
+    
+    /// Public key in `authorized_keys` format
+    pub public_key: String,
+}
+
+/// A payment card attached to an account
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Zeroize)]
+#[serde(default)]
+pub struct PaymentCard {
+    /// Name printed on the card
+    pub cardholder: Option<String>,
+    
+    /// Card network (e.g., "Visa"), detected from the number if not given
+    pub brand: Option<String>,
+    
+    /// Card number, digits only
+    pub number: String,
+    
+    /// Expiry month (1-12)
+    pub expiry_month: Option<u8>,
+    
+    /// Expiry year, four digits
+    pub expiry_year: Option<u16>,
+    
+    /// Security code (CVV/CVC)
+    pub cvv: Option<String>,
+}
+
+impl PaymentCard {
+    /// Create a card from a number, which may contain spaces or dashes
+    pub fn new(number: &str) -> Self {
+        let number: String = number.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+        Self {
+            brand: Self::detect_brand(&number).map(str::to_string),
+            number,
+            ..Self::default()
+        }
+    }
+    
+    /// Check the card's details
+    /// 
+    /// # Errors
+    /// Returns an error if the number isn't 12-19 digits or fails the Luhn
+    /// check, the expiry month isn't 1-12, or the security code isn't 3-4 digits
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |message: &str| Err(crate::PassManError::InvalidInput(message.to_string()));
+        if !(12..=19).contains(&self.number.len()) || !self.number.bytes().all(|b| b.is_ascii_digit()) {
+            return invalid("A card number has 12 to 19 digits");
+        }
+        if !luhn_valid(&self.number) {
+            return invalid("The card number is mistyped (its check digit doesn't match)");
+        }
+        if self.expiry_month.is_some_and(|month| !(1..=12).contains(&month)) {
+            return invalid("The expiry month must be 1-12");
+        }
+        if let Some(ref cvv) = self.cvv {
+            if !(3..=4).contains(&cvv.len()) || !cvv.bytes().all(|b| b.is_ascii_digit()) {
+                return invalid("A security code has 3 or 4 digits");
+            }
+        }
+        Ok(())
+    }
+    
+    /// The number with all but the last four digits masked
+    pub fn masked_number(&self) -> String {
+        let last_four = &self.number[self.number.len().saturating_sub(4)..];
+        format!("•••• {}", last_four)
+    }
+    
+    /// The expiry date as `MM/YYYY`, if both parts are known
+    pub fn expiry(&self) -> Option<String> {
+        Some(format!("{:02}/{}", self.expiry_month?, self.expiry_year?))
+    }
+    
+    /// Whether the card expired before the given month
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        use chrono::Datelike;
+        match (self.expiry_month, self.expiry_year) {
+            (Some(month), Some(year)) => (year as i32, month as u32) < (now.year(), now.month()),
+            _ => false,
+        }
+    }
+    
+    /// Parse an expiry date written `MM/YY` or `MM/YYYY`
+    /// 
+    /// # Returns
+    /// The month and four-digit year
+    /// 
+    /// # Errors
+    /// Returns an error if the date isn't in one of those forms
+    pub fn parse_expiry(value: &str) -> crate::Result<(u8, u16)> {
+        let invalid = || crate::PassManError::InvalidInput(format!("'{}' is not an expiry date (expected MM/YY)", value));
+        let (month, year) = value.trim().split_once('/').ok_or_else(invalid)?;
+        let month: u8 = month.trim().parse().map_err(|_| invalid())?;
+        let year = year.trim();
+        let year: u16 = match year.len() {
+            2 => 2000 + year.parse::<u16>().map_err(|_| invalid())?,
+            4 => year.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+        if !(1..=12).contains(&month) {
+            return Err(invalid());
+        }
+        Ok((month, year))
+    }
+    
+    /// Guess the card network from the number's leading digits
+    pub fn detect_brand(number: &str) -> Option<&'static str> {
+        let prefix = |digits: usize| number.get(..digits).and_then(|prefix| prefix.parse::<u32>().ok());
+        match (prefix(1)?, prefix(2)?, prefix(4).unwrap_or(0)) {
+            (4, _, _) => Some("Visa"),
+            (_, 51..=55, _) | (_, _, 2221..=2720) => Some("Mastercard"),
+            (_, 34 | 37, _) => Some("American Express"),
+            (_, 65, _) | (_, _, 6011) => Some("Discover"),
+            (_, 35, _) => Some("JCB"),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a string of digits passes the Luhn checksum used by card numbers
+pub fn luhn_valid(digits: &str) -> bool {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let sum: u32 = digits.bytes().rev().enumerate()
+        .map(|(i, b)| {
+            let digit = u32::from(b - b'0');
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Personal details attached to an account
+/// 
+/// Document numbers are secrets and masked like passwords when shown.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Zeroize)]
+#[serde(default)]
+pub struct Identity {
+    /// Title (e.g., "Dr")
+    pub title: Option<String>,
+    
+    /// First name
+    pub first_name: Option<String>,
+    
+    /// Middle name
+    pub middle_name: Option<String>,
+    
+    /// Last name
+    pub last_name: Option<String>,
+    
+    /// Company
+    pub company: Option<String>,
+    
+    /// Email address
+    pub email: Option<String>,
+    
+    /// Phone number
+    pub phone: Option<String>,
+    
+    /// Street address, one line per line
+    pub address: Option<String>,
+    
+    /// City
+    pub city: Option<String>,
+    
+    /// State or province
+    pub state: Option<String>,
+    
+    /// Postal code
+    pub postal_code: Option<String>,
+    
+    /// Country
+    pub country: Option<String>,
+    
+    /// Social security or national ID number
+    pub national_id: Option<String>,
+    
+    /// Passport number
+    pub passport_number: Option<String>,
+    
+    /// Driver's license number
+    pub license_number: Option<String>,
+}
+
+impl Identity {
+    /// The name parts that are set, joined with spaces
+    pub fn full_name(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.title, &self.first_name, &self.middle_name, &self.last_name].into_iter()
+            .filter_map(|part| part.as_deref())
+            .filter(|part| !part.is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+    
+    /// The document numbers that are set, as (label, number) pairs
+    pub fn documents(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("National ID", &self.national_id),
+            ("Passport", &self.passport_number),
+            ("Driver's license", &self.license_number),
+        ]
+        .into_iter()
+        .filter_map(|(label, number)| Some((label, number.as_deref()?)))
+        .collect()
+    }
+    
+    /// Whether no detail is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Categories for organizing accounts
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, clap::ValueEnum)]
+/// 
+/// Besides the built-in types, users can define categories of their own
+/// (`Custom`), which the vault keeps in `Vault::categories`. Types are
+/// serialized as plain strings: the built-in ones by variant name
+/// (`"SshKey"`), custom ones by their name. Custom names compare
+/// case-insensitively.
+#[derive(Debug, Clone)]
 pub enum AccountType {
     /// Social media accounts (Twitter, Facebook, etc.)
     Social,
@@ -111,8 +801,20 @@ pub enum AccountType {
     /// Gaming accounts
     Gaming,
     
+    /// Payment cards (see `PaymentCard`)
+    Card,
+    
+    /// Personal details and documents (see `Identity`)
+    Identity,
+    
+    /// SSH key pairs (see `SshKey`)
+    SshKey,
+    
     /// Other category
     Other,
+    
+    /// A category defined by the user
+    Custom(String),
 }
 
 impl AccountType {
@@ -126,10 +828,36 @@ impl AccountType {
             AccountType::Email => "Email",
             AccountType::Shopping => "Shopping",
             AccountType::Gaming => "Gaming",
+            AccountType::Card => "Card",
+            AccountType::Identity => "Identity",
+            AccountType::SshKey => "SSH Key",
             AccountType::Other => "Other",
+            AccountType::Custom(name) => name,
+        }
+    }
+    
+    /// Name used in serialized vaults and on the command line
+    pub fn key(&self) -> &str {
+        match self {
+            AccountType::SshKey => "SshKey",
+            other => other.display_name(),
         }
     }
     
+    /// Whether this is a category defined by the user
+    pub fn is_custom(&self) -> bool {
+        matches!(self, AccountType::Custom(_))
+    }
+    
+    /// Find the built-in type with a name, ignoring case, spaces, dashes,
+    /// and underscores (`ssh-key`, `SSH Key`, and `SshKey` all match)
+    pub fn builtin(name: &str) -> Option<AccountType> {
+        let wanted = fold_type_name(name);
+        Self::all_types().into_iter()
+            .chain([AccountType::Other])
+            .find(|account_type| fold_type_name(account_type.key()) == wanted)
+    }
+    
     /// Get all available account types
     pub fn all_types() -> Vec<AccountType> {
         vec![
@@ -140,10 +868,210 @@ impl AccountType {
             AccountType::Email,
             AccountType::Shopping,
             AccountType::Gaming,
+            AccountType::Card,
+            AccountType::Identity,
+            AccountType::SshKey,
         ]
     }
 }
 
+impl PartialEq for AccountType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (AccountType::Custom(a), AccountType::Custom(b)) => a.to_lowercase() == b.to_lowercase(),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for AccountType {}
+
+impl std::str::FromStr for AccountType {
+    type Err = crate::PassManError;
+    
+    /// Parse a built-in type (see `AccountType::builtin`), or else a custom category
+    fn from_str(value: &str) -> crate::Result<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(crate::PassManError::InvalidInput("The account type is empty".to_string()));
+        }
+        Ok(Self::builtin(value).unwrap_or_else(|| AccountType::Custom(value.to_string())))
+    }
+}
+
+impl std::fmt::Display for AccountType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
+impl Serialize for AccountType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Spell a custom category as the vault does, adding it if it's new
+fn register_category(categories: &mut Vec<String>, account_type: &mut AccountType) {
+    if let AccountType::Custom(name) = account_type {
+        let lowercase = name.to_lowercase();
+        match categories.iter().find(|category| category.to_lowercase() == lowercase) {
+            Some(known) => name.clone_from(known),
+            None => categories.push(name.clone()),
+        }
+    }
+}
+
+/// Lowercase a type name and drop spaces, dashes, and underscores
+fn fold_type_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Sort order for account listings
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortOrder {
+    /// Alphabetical by name
+    #[default]
+    Name,
+    
+    /// Newest first
+    Created,
+    
+    /// Most recently modified first
+    Updated,
+    
+    /// Most recently accessed first
+    LastUsed,
+    
+    /// Most often accessed first
+    MostUsed,
+    
+    /// Grouped by account type, then by name
+    Type,
+}
+
+/// Filter, sort, and pagination options for listing accounts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AccountQuery {
+    /// Case-insensitive substring match on the account name
+    pub search: Option<String>,
+    
+    /// Only accounts of this type
+    pub account_type: Option<AccountType>,
+    
+    /// Only accounts carrying this tag
+    pub tag: Option<String>,
+    
+    /// Only accounts in this folder
+    pub folder: Option<String>,
+    
+    /// Only favorite accounts
+    pub favorites_only: bool,
+    
+    /// List favorites ahead of the other accounts, each group in `sort` order
+    pub favorites_first: bool,
+    
+    /// List archived accounts instead of active ones
+    pub archived: bool,
+    
+    /// Leave out canary accounts (e.g. for exports shared with others)
+    pub exclude_canaries: bool,
+    
+    /// Sort order of the results
+    pub sort: SortOrder,
+    
+    /// Number of results to skip
+    pub offset: usize,
+    
+    /// Maximum number of results (None = unlimited)
+    pub limit: Option<usize>,
+}
+
+impl AccountQuery {
+    /// Check whether an account passes this query's filters
+    pub fn matches(&self, account: &Account) -> bool {
+        if account.archived != self.archived {
+            return false;
+        }
+        if self.favorites_only && !account.is_favorite {
+            return false;
+        }
+        if self.exclude_canaries && account.canary {
+            return false;
+        }
+        if let Some(ref search) = self.search {
+            if !account.name.to_lowercase().contains(&search.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(ref account_type) = self.account_type {
+            if &account.account_type != account_type {
+                return false;
+            }
+        }
+        if let Some(ref tag) = self.tag {
+            if !account.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+        if let Some(ref folder) = self.folder {
+            if !account.folder.as_deref().is_some_and(|f| f.eq_ignore_ascii_case(folder)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// How an imported account that duplicates an existing one is handled
+/// 
+/// Accounts are duplicates when their names and usernames match,
+/// ignoring case.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupePolicy {
+    /// Keep the existing account and drop the imported one
+    #[default]
+    Skip,
+    
+    /// Replace the existing account's fields with the imported ones
+    Overwrite,
+    
+    /// Keep the existing account, filling in its empty fields from the
+    /// imported one and adding the imported tags and custom fields
+    Merge,
+    
+    /// Add the imported account alongside the existing one
+    KeepBoth,
+}
+
+/// Outcome of merging imported accounts into a vault
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ImportSummary {
+    /// Accounts added as new entries
+    pub added: usize,
+    
+    /// Existing accounts replaced by an imported duplicate
+    pub overwritten: usize,
+    
+    /// Existing accounts that took fields from an imported duplicate
+    #[serde(default)]
+    pub merged: usize,
+    
+    /// Imported duplicates that were dropped
+    pub skipped: usize,
+}
+
 /// Options for password generation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PasswordOptions {
@@ -167,6 +1095,22 @@ pub struct PasswordOptions {
     
     /// Exclude ambiguous characters ({}[]()\/~,;.<>)
     pub exclude_ambiguous: bool,
+    
+    /// Other characters never to use
+    #[serde(default)]
+    pub exclude_chars: String,
+    
+    /// Fewest uppercase letters (at least one when they are included)
+    #[serde(default)]
+    pub min_uppercase: usize,
+    
+    /// Fewest numbers (at least one when they are included)
+    #[serde(default)]
+    pub min_numbers: usize,
+    
+    /// Fewest special characters (at least one when they are included)
+    #[serde(default)]
+    pub min_special: usize,
 }
 
 impl Default for PasswordOptions {
@@ -179,6 +1123,10 @@ impl Default for PasswordOptions {
             include_special: true,
             exclude_similar: true,
             exclude_ambiguous: false,
+            exclude_chars: String::new(),
+            min_uppercase: 0,
+            min_numbers: 0,
+            min_special: 0,
         }
     }
 }
@@ -202,6 +1150,7 @@ impl PasswordOptions {
             include_special: false,
             exclude_similar: true,
             exclude_ambiguous: true,
+            ..Default::default()
         }
     }
     
@@ -215,6 +1164,79 @@ impl PasswordOptions {
             include_special: true,
             exclude_similar: true,
             exclude_ambiguous: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// Options for passphrase generation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PassphraseOptions {
+    /// Number of words in the passphrase
+    pub word_count: usize,
+    
+    /// Text placed between words
+    pub separator: String,
+    
+    /// Capitalize the first letter of each word
+    pub capitalize: bool,
+    
+    /// Append a random digit to one randomly chosen word
+    pub include_number: bool,
+    
+    /// Append a random symbol to one randomly chosen word
+    #[serde(default)]
+    pub include_symbol: bool,
+    
+    /// Built-in word list the words are picked from
+    #[serde(default)]
+    pub word_list: PassphraseWordList,
+}
+
+/// Built-in passphrase word lists
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PassphraseWordList {
+    /// Passman's own list of short, common words
+    #[default]
+    Default,
+    /// The EFF long word list (7,776 words, about 12.9 bits each)
+    EffLong,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            word_count: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            include_number: false,
+            include_symbol: false,
+            word_list: PassphraseWordList::Default,
+        }
+    }
+}
+
+/// Options for username generation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct UsernameOptions {
+    /// Text placed between the adjective, noun, and number
+    pub separator: String,
+    
+    /// Capitalize the first letter of each word
+    pub capitalize: bool,
+    
+    /// Digits in the number at the end; 0 for none
+    pub digits: usize,
+}
+
+impl Default for UsernameOptions {
+    fn default() -> Self {
+        Self {
+            separator: "_".to_string(),
+            capitalize: false,
+            digits: 2,
         }
     }
 }
@@ -261,6 +1283,50 @@ pub struct VaultSettings {
     
     /// Default password generation options
     pub default_password_options: PasswordOptions,
+    
+    /// Fetch site icons for accounts (contacts each account's website)
+    #[serde(default)]
+    pub fetch_icons: bool,
+    
+    /// Keep recently generated passwords in the (encrypted) vault
+    #[serde(default)]
+    pub keep_generation_history: bool,
+    
+    /// How `passman ssh-agent` approves each signature
+    #[serde(default)]
+    pub ssh_agent_confirmation: SshConfirmation,
+    
+    /// Rules for passwords added or changed in this vault (see `policy`)
+    #[serde(default)]
+    pub password_policy: Option<PasswordPolicy>,
+    
+    /// Minutes the encrypted search cache stays valid after a save (0 =
+    /// no cache; see `search_cache`)
+    #[serde(default)]
+    pub search_cache_minutes: u32,
+    
+    /// Days deleted accounts stay in the trash before they are purged (0 =
+    /// until purged by hand)
+    #[serde(default)]
+    pub trash_retention_days: u32,
+    
+    /// What websites accept as passwords, by domain (see `Vault::site_policy`)
+    #[serde(default)]
+    pub site_policies: BTreeMap<String, SitePolicy>,
+}
+
+/// Approval required before the SSH agent signs with a stored key
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SshConfirmation {
+    /// Sign without asking
+    Never,
+    
+    /// Ask for a yes/no confirmation
+    #[default]
+    Confirm,
+    
+    /// Ask for the master password
+    Reauthenticate,
 }
 
 impl Default for VaultSettings {
@@ -272,10 +1338,27 @@ impl Default for VaultSettings {
             clipboard_timeout: 30, // 30 seconds
             show_strength_indicators: true,
             default_password_options: PasswordOptions::default(),
+            fetch_icons: false,
+            keep_generation_history: false,
+            ssh_agent_confirmation: SshConfirmation::default(),
+            password_policy: None,
+            search_cache_minutes: 0,
+            trash_retention_days: 30,
+            site_policies: BTreeMap::new(),
         }
     }
 }
 
+/// A deleted account kept in the trash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashedAccount {
+    /// The account as it was when deleted
+    pub account: Account,
+    
+    /// When the account was deleted
+    pub deleted_at: DateTime<Utc>,
+}
+
 /// Complete vault structure containing all accounts and metadata
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Vault {
@@ -283,10 +1366,192 @@ pub struct Vault {
     pub metadata: VaultMetadata,
     
     /// All accounts in the vault
+    /// 
+    /// Change accounts through the `Vault` methods, which keep the lookup
+    /// indexes in sync; after changing the map directly, call `reindex`.
     pub accounts: HashMap<Uuid, Account>,
     
     /// Vault-specific tags for organizing accounts
     pub tags: Vec<String>,
+    
+    /// User-defined account categories (see `AccountType::Custom`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    
+    /// Recently generated passwords, newest first
+    #[serde(default)]
+    pub generated_passwords: Vec<GeneratedPassword>,
+    
+    /// Key pair that signs this vault's backups and exports
+    #[serde(default)]
+    pub signing_key: Option<SigningKey>,
+    
+    /// When accounts were deleted, so merging an older copy doesn't bring
+    /// them back (see `sync`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub deleted: BTreeMap<Uuid, DateTime<Utc>>,
+    
+    /// Deleted accounts that can still be restored, by ID
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub trash: HashMap<Uuid, TrashedAccount>,
+    
+    /// Lookup indexes over `accounts`, built on first use
+    #[serde(skip)]
+    index: IndexCell,
+}
+
+/// Account IDs by lowercase name, URL host, and tag
+#[derive(Debug, Clone, Default)]
+struct AccountIndex {
+    /// Lowercase name to IDs; sorted so searches return accounts by name
+    by_name: BTreeMap<String, BTreeSet<Uuid>>,
+    
+    /// URL host (see `Account::host`) to IDs
+    by_host: HashMap<String, BTreeSet<Uuid>>,
+    
+    /// ASCII-lowercase tag to IDs
+    by_tag: HashMap<String, BTreeSet<Uuid>>,
+}
+
+impl AccountIndex {
+    fn build(accounts: &HashMap<Uuid, Account>) -> Self {
+        let mut index = Self::default();
+        for account in accounts.values() {
+            index.insert(account);
+        }
+        index
+    }
+    
+    fn insert(&mut self, account: &Account) {
+        self.by_name.entry(account.name.to_lowercase()).or_default().insert(account.id);
+        if let Some(host) = account.host() {
+            self.by_host.entry(host).or_default().insert(account.id);
+        }
+        for tag in &account.tags {
+            self.by_tag.entry(tag.to_ascii_lowercase()).or_default().insert(account.id);
+        }
+    }
+    
+    fn remove(&mut self, account: &Account) {
+        let name = account.name.to_lowercase();
+        if let Some(ids) = self.by_name.get_mut(&name) {
+            ids.remove(&account.id);
+            if ids.is_empty() {
+                self.by_name.remove(&name);
+            }
+        }
+        if let Some(host) = account.host() {
+            remove_id(&mut self.by_host, host, account.id);
+        }
+        for tag in &account.tags {
+            remove_id(&mut self.by_tag, tag.to_ascii_lowercase(), account.id);
+        }
+    }
+}
+
+impl Zeroize for AccountIndex {
+    /// Overwrite the names, hosts, and tags used as keys
+    fn zeroize(&mut self) {
+        for (mut name, _) in std::mem::take(&mut self.by_name) {
+            name.zeroize();
+        }
+        for (mut key, _) in std::mem::take(&mut self.by_host).into_iter().chain(std::mem::take(&mut self.by_tag)) {
+            key.zeroize();
+        }
+    }
+}
+
+/// Get the lowercase host of a URL, without `www.`
+/// 
+/// # Arguments
+/// * `url` - The URL; the scheme is optional
+/// 
+/// # Returns
+/// The host, or None if the URL has none
+pub fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Remove an ID from an index entry, dropping the entry once it is empty
+fn remove_id(map: &mut HashMap<String, BTreeSet<Uuid>>, key: String, id: Uuid) {
+    if let Some(ids) = map.get_mut(&key) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            map.remove(&key);
+        }
+    }
+}
+
+/// Lazily built `AccountIndex`; ignored when comparing vaults
+#[derive(Debug, Clone, Default)]
+struct IndexCell(OnceLock<AccountIndex>);
+
+impl PartialEq for IndexCell {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A minisign key pair for signing backups and exports (see `signing`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Zeroize)]
+pub struct SigningKey {
+    /// Secret key (base64, not password-protected; the vault is encrypted)
+    pub secret_key: String,
+    
+    /// Public key (base64, as passed to `minisign -P`)
+    pub public_key: String,
+    
+    /// When the key pair was generated
+    #[zeroize(skip)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A password from the generator, kept so it can be recovered if it was
+/// never saved to an account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Zeroize)]
+pub struct GeneratedPassword {
+    /// The generated password
+    pub password: String,
+    
+    /// When it was generated
+    #[zeroize(skip)]
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Zeroize for Vault {
+    /// Overwrite the accounts and every other secret or personal string
+    fn zeroize(&mut self) {
+        self.metadata.email.zeroize();
+        for account in self.accounts.values_mut() {
+            account.zeroize();
+        }
+        self.accounts.clear();
+        for trashed in self.trash.values_mut() {
+            trashed.account.zeroize();
+        }
+        self.trash.clear();
+        self.tags.zeroize();
+        self.categories.zeroize();
+        self.generated_passwords.zeroize();
+        self.signing_key.zeroize();
+        if let Some(index) = self.index.0.get_mut() {
+            index.zeroize();
+        }
+    }
+}
+
+/// A vault is zeroized when it drops: when it is locked, replaced by a
+/// newer copy, or was only a temporary copy (e.g. of a backup)
+impl Drop for Vault {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
 }
 
 impl Vault {
@@ -304,11 +1569,29 @@ impl Vault {
             },
             accounts: HashMap::new(),
             tags: Vec::new(),
+            categories: Vec::new(),
+            generated_passwords: Vec::new(),
+            signing_key: None,
+            deleted: BTreeMap::new(),
+            trash: HashMap::new(),
+            index: IndexCell::default(),
         }
     }
     
     /// Add an account to the vault
-    pub fn add_account(&mut self, account: Account) {
+    /// 
+    /// A custom category the vault doesn't know yet is added to `categories`.
+    pub fn add_account(&mut self, mut account: Account) {
+        register_category(&mut self.categories, &mut account.account_type);
+        if let Some(previous) = self.accounts.get(&account.id) {
+            if let Some(index) = self.index.0.get_mut() {
+                index.remove(previous);
+            }
+        }
+        if let Some(index) = self.index.0.get_mut() {
+            index.insert(&account);
+        }
+        self.deleted.remove(&account.id);
         self.accounts.insert(account.id, account);
         self.metadata.account_count = self.accounts.len();
         self.metadata.last_modified = Utc::now();
@@ -317,20 +1600,131 @@ impl Vault {
     /// Remove an account from the vault
     pub fn remove_account(&mut self, id: &Uuid) -> Option<Account> {
         let account = self.accounts.remove(id);
-        if account.is_some() {
+        if let Some(ref account) = account {
+            if let Some(index) = self.index.0.get_mut() {
+                index.remove(account);
+            }
+            self.deleted.insert(account.id, Utc::now());
             self.metadata.account_count = self.accounts.len();
             self.metadata.last_modified = Utc::now();
         }
         account
     }
     
+    /// Move an account to the trash
+    /// 
+    /// Like `remove_account`, but the account can be brought back with
+    /// `restore_account` until the trash is purged.
+    /// 
+    /// # Returns
+    /// True if there was such an account
+    pub fn trash_account(&mut self, id: &Uuid) -> bool {
+        let Some(account) = self.remove_account(id) else {
+            return false;
+        };
+        let deleted_at = self.deleted.get(id).copied().unwrap_or_else(Utc::now);
+        self.trash.insert(*id, TrashedAccount { account, deleted_at });
+        true
+    }
+    
+    /// Move an account from the trash back into the vault
+    /// 
+    /// The account counts as changed now, so merging a copy that still has
+    /// the deletion keeps it (see `sync`).
+    /// 
+    /// # Returns
+    /// The restored account, or None if it isn't in the trash
+    pub fn restore_account(&mut self, id: &Uuid) -> Option<&Account> {
+        let TrashedAccount { mut account, .. } = self.trash.remove(id)?;
+        account.updated_at = Utc::now();
+        self.add_account(account);
+        self.accounts.get(id)
+    }
+    
+    /// Permanently delete accounts in the trash
+    /// 
+    /// # Arguments
+    /// * `deleted_before` - Only purge accounts deleted before this time; all if None
+    /// 
+    /// # Returns
+    /// Number of accounts purged
+    pub fn purge_trash(&mut self, deleted_before: Option<DateTime<Utc>>) -> usize {
+        let purged: Vec<Uuid> = self.trash.iter()
+            .filter(|(_, trashed)| deleted_before.is_none_or(|before| trashed.deleted_at < before))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &purged {
+            if let Some(mut trashed) = self.trash.remove(id) {
+                trashed.account.zeroize();
+            }
+        }
+        if !purged.is_empty() {
+            self.metadata.last_modified = Utc::now();
+        }
+        purged.len()
+    }
+    
+    /// Change an account, keeping the lookup indexes in sync
+    /// 
+    /// Fields that changed are stamped in `Account::field_times`.
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `change` - Applied to the account
+    /// 
+    /// # Returns
+    /// What `change` returned, or None if there is no such account
+    pub fn update_account<R>(&mut self, id: &Uuid, change: impl FnOnce(&mut Account) -> R) -> Option<R> {
+        let account = self.accounts.get_mut(id)?;
+        if let Some(index) = self.index.0.get_mut() {
+            index.remove(account);
+        }
+        let before = account.clone();
+        let result = change(account);
+        register_category(&mut self.categories, &mut account.account_type);
+        crate::sync::stamp_changes(&before, account, Utc::now());
+        if let Some(index) = self.index.0.get_mut() {
+            index.insert(account);
+        }
+        self.metadata.last_modified = Utc::now();
+        Some(result)
+    }
+    
+    /// A copy of the vault without its accounts
+    #[cfg(feature = "storage")]
+    pub(crate) fn without_accounts(&self) -> Self {
+        Self {
+            metadata: self.metadata.clone(),
+            accounts: HashMap::new(),
+            tags: self.tags.clone(),
+            categories: self.categories.clone(),
+            generated_passwords: self.generated_passwords.clone(),
+            signing_key: self.signing_key.clone(),
+            deleted: self.deleted.clone(),
+            trash: self.trash.clone(),
+            index: IndexCell::default(),
+        }
+    }
+    
+    /// Rebuild the lookup indexes after `accounts` was changed directly
+    pub fn reindex(&mut self) {
+        if let Some(index) = self.index.0.get_mut() {
+            index.zeroize();
+        }
+        self.index = IndexCell::default();
+    }
+    
     /// Get an account by ID
     pub fn get_account(&self, id: &Uuid) -> Option<&Account> {
         self.accounts.get(id)
     }
     
     /// Get an account by ID (mutable)
+    /// 
+    /// The lookup indexes are rebuilt on next use; prefer `update_account`,
+    /// which updates them in place.
     pub fn get_account_mut(&mut self, id: &Uuid) -> Option<&mut Account> {
+        self.reindex();
         self.accounts.get_mut(id)
     }
     
@@ -339,13 +1733,76 @@ impl Vault {
         self.accounts.values().collect()
     }
     
-    /// Search accounts by name (case-insensitive)
+    /// Search accounts by name (case-insensitive), sorted by name
     pub fn search_accounts(&self, query: &str) -> Vec<&Account> {
         let query_lower = query.to_lowercase();
-        self.accounts
-            .values()
-            .filter(|account| account.name.to_lowercase().contains(&query_lower))
-            .collect()
+        self.resolve(self.index().by_name
+            .iter()
+            .filter(|(name, _)| name.contains(&query_lower))
+            .flat_map(|(_, ids)| ids))
+    }
+    
+    /// Get accounts with exactly this name (case-insensitive)
+    pub fn get_accounts_by_name(&self, name: &str) -> Vec<&Account> {
+        self.resolve(self.index().by_name.get(&name.to_lowercase()).into_iter().flatten())
+    }
+    
+    /// Get accounts whose URL belongs to a site
+    /// 
+    /// An account matches when its host (see `Account::host`) is the
+    /// URL's host or a parent domain of it, so an account for
+    /// `github.com` matches `https://gist.github.com/`.
+    /// 
+    /// # Arguments
+    /// * `url` - URL or host of the site
+    /// 
+    /// # Returns
+    /// Matching accounts, most specific host first
+    pub fn get_accounts_for_url(&self, url: &str) -> Vec<&Account> {
+        let Some(host) = url_host(url) else {
+            return Vec::new();
+        };
+        
+        let index = self.index();
+        let mut domain = host.as_str();
+        let mut ids = Vec::new();
+        loop {
+            ids.extend(index.by_host.get(domain).into_iter().flatten());
+            match domain.split_once('.') {
+                // Stop before the bare top-level domain
+                Some((_, parent)) if parent.contains('.') => domain = parent,
+                _ => break,
+            }
+        }
+        self.resolve(ids.into_iter())
+    }
+    
+    /// The site policy for an account's passwords
+    /// 
+    /// The account's own policy wins. Otherwise the policy stored in
+    /// `VaultSettings::site_policies` for the account's host or its
+    /// nearest parent domain applies, so a policy for `example.com`
+    /// covers `login.example.com`.
+    /// 
+    /// # Arguments
+    /// * `account` - The account
+    /// 
+    /// # Returns
+    /// The policy, or None if the site has none
+    pub fn site_policy<'a>(&'a self, account: &'a Account) -> Option<&'a SitePolicy> {
+        if account.site_policy.is_some() {
+            return account.site_policy.as_ref();
+        }
+        
+        let host = account.host()?;
+        let policies = &self.metadata.settings.site_policies;
+        let mut domain = host.as_str();
+        loop {
+            if let Some(policy) = policies.get(domain) {
+                return Some(policy);
+            }
+            domain = domain.split_once('.')?.1;
+        }
     }
     
     /// Get accounts by type
@@ -356,11 +1813,392 @@ impl Vault {
             .collect()
     }
     
+    /// Find a custom category by name (case-insensitive)
+    pub fn category(&self, name: &str) -> Option<&str> {
+        let name = name.trim().to_lowercase();
+        self.categories.iter().find(|category| category.to_lowercase() == name).map(String::as_str)
+    }
+    
+    /// Define a custom category
+    /// 
+    /// # Errors
+    /// Returns an error if the name is empty, names a built-in type, or is
+    /// already taken
+    pub fn add_category(&mut self, name: &str) -> crate::Result<()> {
+        let name = self.check_category_name(name, None)?;
+        self.categories.push(name);
+        self.metadata.last_modified = Utc::now();
+        Ok(())
+    }
+    
+    /// Rename a custom category, moving its accounts along
+    /// 
+    /// # Returns
+    /// Number of accounts moved
+    /// 
+    /// # Errors
+    /// Returns an error if there is no such category or the new name is
+    /// empty, names a built-in type, or is taken by another category
+    pub fn rename_category(&mut self, name: &str, new_name: &str) -> crate::Result<usize> {
+        let old = self.category(name).map(str::to_string)
+            .ok_or_else(|| crate::PassManError::InvalidInput(format!("No category named '{}'", name.trim())))?;
+        let new_name = self.check_category_name(new_name, Some(&old))?;
+        if let Some(category) = self.categories.iter_mut().find(|category| **category == old) {
+            category.clone_from(&new_name);
+        }
+        Ok(self.move_category(&old, AccountType::Custom(new_name)))
+    }
+    
+    /// Delete a custom category; its accounts become `Other`
+    /// 
+    /// # Returns
+    /// Number of accounts moved to `Other`
+    /// 
+    /// # Errors
+    /// Returns an error if there is no such category
+    pub fn remove_category(&mut self, name: &str) -> crate::Result<usize> {
+        let old = self.category(name).map(str::to_string)
+            .ok_or_else(|| crate::PassManError::InvalidInput(format!("No category named '{}'", name.trim())))?;
+        self.categories.retain(|category| *category != old);
+        Ok(self.move_category(&old, AccountType::Other))
+    }
+    
+    /// Validate a new category name, allowing a different spelling of `current`
+    fn check_category_name(&self, name: &str, current: Option<&str>) -> crate::Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(crate::PassManError::InvalidInput("The category name is empty".to_string()));
+        }
+        if let Some(builtin) = AccountType::builtin(name) {
+            return Err(crate::PassManError::InvalidInput(format!("'{}' is a built-in account type", builtin.display_name())));
+        }
+        match self.category(name) {
+            Some(taken) if current.is_none_or(|current| taken != current) => {
+                Err(crate::PassManError::InvalidInput(format!("Category '{}' already exists", taken)))
+            }
+            _ => Ok(name.to_string()),
+        }
+    }
+    
+    /// Give every account of a custom category another type
+    fn move_category(&mut self, category: &str, account_type: AccountType) -> usize {
+        let old = AccountType::Custom(category.to_string());
+        let ids: Vec<Uuid> = self.accounts.values()
+            .filter(|account| account.account_type == old)
+            .map(|account| account.id)
+            .collect();
+        for id in &ids {
+            self.update_account(id, |account| account.account_type = account_type.clone());
+        }
+        self.metadata.last_modified = Utc::now();
+        ids.len()
+    }
+    
     /// Get accounts by tag
     pub fn get_accounts_by_tag(&self, tag: &str) -> Vec<&Account> {
-        self.accounts
-            .values()
-            .filter(|account| account.tags.contains(&tag.to_string()))
+        self.resolve(self.index().by_tag.get(&tag.to_ascii_lowercase()).into_iter().flatten())
+            .into_iter()
+            .filter(|account| account.tags.iter().any(|t| t == tag))
             .collect()
     }
+    
+    /// Get the most recently accessed accounts, newest first
+    /// 
+    /// Archived accounts and accounts that were never accessed are left out.
+    pub fn get_recently_used(&self, limit: usize) -> Vec<&Account> {
+        self.query_accounts(&AccountQuery {
+            sort: SortOrder::LastUsed,
+            limit: Some(limit),
+            ..Default::default()
+        })
+        .into_iter()
+        .take_while(|account| account.last_accessed.is_some())
+        .collect()
+    }
+    
+    /// Get the most often accessed accounts, most used first
+    /// 
+    /// Archived accounts and accounts that were never accessed are left out.
+    pub fn get_most_used(&self, limit: usize) -> Vec<&Account> {
+        self.query_accounts(&AccountQuery {
+            sort: SortOrder::MostUsed,
+            limit: Some(limit),
+            ..Default::default()
+        })
+        .into_iter()
+        .take_while(|account| account.access_count > 0)
+        .collect()
+    }
+    
+    /// Get the accounts whose password expires within some days, soonest first
+    /// 
+    /// Accounts that already expired come first; archived accounts are left
+    /// out. See `Account::password_expiry`.
+    pub fn get_expiring_accounts(&self, within_days: u32) -> Vec<&Account> {
+        let cutoff = Utc::now() + chrono::Duration::days(within_days.into());
+        let mut accounts: Vec<(DateTime<Utc>, &Account)> = self.accounts.values()
+            .filter(|account| !account.archived)
+            .filter_map(|account| account.password_expiry().map(|expiry| (expiry, account)))
+            .filter(|(expiry, _)| *expiry <= cutoff)
+            .collect();
+        accounts.sort_by_key(|(expiry, _)| *expiry);
+        accounts.into_iter().map(|(_, account)| account).collect()
+    }
+    
+    /// List accounts matching a query, sorted and paginated
+    pub fn query_accounts(&self, query: &AccountQuery) -> Vec<&Account> {
+        // Narrow the candidates with an index when the query allows it
+        let candidates = match (&query.tag, &query.search) {
+            (Some(tag), _) => self.resolve(self.index().by_tag.get(&tag.to_ascii_lowercase()).into_iter().flatten()),
+            (None, Some(search)) => self.search_accounts(search),
+            (None, None) => self.get_all_accounts(),
+        };
+        let mut accounts: Vec<&Account> = candidates
+            .into_iter()
+            .filter(|account| query.matches(account))
+            .collect();
+        
+        let by_name = |a: &&Account, b: &&Account| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        match query.sort {
+            SortOrder::Name => accounts.sort_by(by_name),
+            SortOrder::Created => accounts.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| by_name(a, b))),
+            SortOrder::Updated => accounts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| by_name(a, b))),
+            SortOrder::LastUsed => accounts.sort_by(|a, b| b.last_accessed.cmp(&a.last_accessed).then_with(|| by_name(a, b))),
+            SortOrder::MostUsed => accounts.sort_by(|a, b| {
+                b.access_count.cmp(&a.access_count)
+                    .then_with(|| b.last_accessed.cmp(&a.last_accessed))
+                    .then_with(|| by_name(a, b))
+            }),
+            SortOrder::Type => accounts.sort_by(|a, b| {
+                a.account_type.display_name().cmp(b.account_type.display_name()).then_with(|| by_name(a, b))
+            }),
+        }
+        if query.favorites_first {
+            // A stable sort keeps each group in the order above
+            accounts.sort_by_key(|account| !account.is_favorite);
+        }
+        
+        accounts
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+    
+    /// The lookup indexes, built on first use
+    fn index(&self) -> &AccountIndex {
+        self.index.0.get_or_init(|| AccountIndex::build(&self.accounts))
+    }
+    
+    /// Look up indexed IDs, in order
+    fn resolve<'a>(&'a self, ids: impl Iterator<Item = &'a Uuid>) -> Vec<&'a Account> {
+        ids.filter_map(|id| self.accounts.get(id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample_vault() -> Vault {
+        let mut vault = Vault::new("test@example.com".to_string());
+        for (name, account_type, tag) in [
+            ("GitHub", AccountType::Work, "dev"),
+            ("bank", AccountType::Banking, "money"),
+            ("Gmail", AccountType::Email, "dev"),
+        ] {
+            let mut account = Account::new(name.to_string(), account_type, "secret".to_string());
+            account.tags = vec![tag.to_string()];
+            vault.add_account(account);
+        }
+        vault
+    }
+    
+    #[test]
+    fn test_zeroize() {
+        let mut vault = sample_vault();
+        vault.generated_passwords.push(GeneratedPassword { password: "generated".to_string(), generated_at: Utc::now() });
+        let mut account = vault.get_all_accounts()[0].clone();
+        account.custom_fields.push(CustomField { name: "PIN".to_string(), value: "1234".to_string(), hidden: true });
+        account.zeroize();
+        assert!(account.name.is_empty() && account.password.is_empty() && account.tags.is_empty());
+        assert!(account.custom_fields.is_empty());
+        
+        assert_eq!(vault.get_accounts_by_tag("dev").len(), 2);
+        vault.zeroize();
+        assert!(vault.accounts.is_empty() && vault.generated_passwords.is_empty());
+        assert!(vault.metadata.email.is_empty());
+        assert!(vault.get_accounts_by_tag("dev").is_empty());
+    }
+    
+    #[test]
+    fn test_account_type_names() {
+        assert_eq!("ssh-key".parse::<AccountType>().unwrap(), AccountType::SshKey);
+        assert_eq!("SSH Key".parse::<AccountType>().unwrap(), AccountType::SshKey);
+        assert_eq!("work".parse::<AccountType>().unwrap(), AccountType::Work);
+        assert_eq!("Travel".parse::<AccountType>().unwrap(), AccountType::Custom("travel".to_string()));
+        assert!("  ".parse::<AccountType>().is_err());
+        assert_ne!(AccountType::Custom("Other".to_string()), AccountType::Other);
+        
+        // Built-in types keep their serialized names; custom ones are plain strings
+        let types = vec![AccountType::SshKey, AccountType::Other, AccountType::Custom("Travel".to_string())];
+        let json = serde_json::to_string(&types).unwrap();
+        assert_eq!(json, r#"["SshKey","Other","Travel"]"#);
+        assert_eq!(serde_json::from_str::<Vec<AccountType>>(&json).unwrap(), types);
+    }
+    
+    #[test]
+    fn test_query_sorting_and_pagination() {
+        let mut vault = sample_vault();
+        
+        let names: Vec<_> = vault.query_accounts(&AccountQuery::default())
+            .iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["bank", "GitHub", "Gmail"]);
+        
+        let query = AccountQuery { offset: 1, limit: Some(1), ..Default::default() };
+        let names: Vec<_> = vault.query_accounts(&query).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["GitHub"]);
+        
+        let id = vault.get_accounts_by_name("gmail")[0].id;
+        vault.get_account_mut(&id).unwrap().is_favorite = true;
+        let query = AccountQuery { favorites_first: true, ..Default::default() };
+        let names: Vec<_> = vault.query_accounts(&query).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Gmail", "bank", "GitHub"]);
+    }
+    
+    #[test]
+    fn test_usage_ranking() {
+        let mut vault = sample_vault();
+        assert!(vault.get_recently_used(10).is_empty());
+        assert!(vault.get_most_used(10).is_empty());
+        
+        let id = |vault: &Vault, name: &str| vault.get_accounts_by_name(name)[0].id;
+        let (github, bank, gmail) = (id(&vault, "GitHub"), id(&vault, "bank"), id(&vault, "Gmail"));
+        for _ in 0..3 {
+            vault.update_account(&bank, Account::mark_accessed);
+        }
+        vault.update_account(&github, Account::mark_accessed);
+        vault.update_account(&gmail, Account::mark_accessed);
+        vault.get_account_mut(&gmail).unwrap().last_accessed = Some(Utc::now() + chrono::Duration::minutes(1));
+        assert_eq!(vault.get_account(&bank).unwrap().access_count, 3);
+        
+        let names = |accounts: Vec<&Account>| accounts.iter().map(|a| a.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(vault.get_most_used(2)), ["bank", "Gmail"]);
+        assert_eq!(names(vault.get_recently_used(1)), ["Gmail"]);
+        
+        vault.get_account_mut(&bank).unwrap().archived = true;
+        assert_eq!(names(vault.get_most_used(10)), ["Gmail", "GitHub"]);
+        
+        let query = AccountQuery { sort: SortOrder::MostUsed, archived: true, ..Default::default() };
+        assert_eq!(names(vault.query_accounts(&query)), ["bank"]);
+    }
+    
+    #[test]
+    fn test_payment_card() {
+        let mut card = PaymentCard::new("3782-822463-10005");
+        assert_eq!(card.number, "378282246310005");
+        assert_eq!(card.brand.as_deref(), Some("American Express"));
+        assert_eq!(card.masked_number(), "•••• 0005");
+        assert!(card.validate().is_ok());
+        
+        assert!(luhn_valid("79927398713") && !luhn_valid("79927398710") && !luhn_valid("7992x398713"));
+        assert!(PaymentCard::new("4111111111111112").validate().is_err());
+        assert!(PaymentCard::new("4111").validate().is_err());
+        card.cvv = Some("12".to_string());
+        assert!(card.validate().is_err());
+        
+        assert_eq!(PaymentCard::parse_expiry("4/30").unwrap(), (4, 2030));
+        assert_eq!(PaymentCard::parse_expiry("12/2031").unwrap(), (12, 2031));
+        assert!(PaymentCard::parse_expiry("13/30").is_err() && PaymentCard::parse_expiry("0430").is_err());
+        (card.expiry_month, card.expiry_year) = (Some(4), Some(2020));
+        assert_eq!(card.expiry().as_deref(), Some("04/2020"));
+        assert!(card.is_expired(Utc::now()));
+    }
+    
+    #[test]
+    fn test_expiring_accounts() {
+        let mut vault = sample_vault();
+        assert!(vault.get_expiring_accounts(365).is_empty());
+        
+        let id = |vault: &Vault, name: &str| vault.get_accounts_by_name(name)[0].id;
+        let (github, bank, gmail) = (id(&vault, "GitHub"), id(&vault, "bank"), id(&vault, "Gmail"));
+        let now = Utc::now();
+        vault.update_account(&github, |account| account.expires_at = Some(now + chrono::Duration::days(20)));
+        vault.update_account(&bank, |account| account.expires_at = Some(now - chrono::Duration::days(1)));
+        vault.update_account(&gmail, |account| account.rotation_interval_days = Some(90));
+        
+        // The rotation interval counts from the last password change
+        let gmail_expiry = vault.get_account(&gmail).unwrap().password_expiry().unwrap();
+        assert_eq!((gmail_expiry - now).num_days(), 89);
+        vault.update_account(&gmail, |account| account.expires_at = Some(now + chrono::Duration::days(10)));
+        assert_eq!(vault.get_account(&gmail).unwrap().password_expiry(), Some(now + chrono::Duration::days(10)));
+        
+        let names = |accounts: Vec<&Account>| accounts.iter().map(|a| a.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(vault.get_expiring_accounts(0)), ["bank"]);
+        assert_eq!(names(vault.get_expiring_accounts(30)), ["bank", "Gmail", "GitHub"]);
+        
+        vault.get_account_mut(&bank).unwrap().archived = true;
+        assert_eq!(names(vault.get_expiring_accounts(30)), ["Gmail", "GitHub"]);
+    }
+    
+    #[test]
+    fn test_query_filters() {
+        let mut vault = sample_vault();
+        
+        let query = AccountQuery { tag: Some("DEV".to_string()), ..Default::default() };
+        assert_eq!(vault.query_accounts(&query).len(), 2);
+        
+        let id = vault.search_accounts("gmail")[0].id;
+        vault.get_account_mut(&id).unwrap().archived = true;
+        assert_eq!(vault.query_accounts(&query).len(), 1);
+        
+        let query = AccountQuery { archived: true, ..Default::default() };
+        assert_eq!(vault.query_accounts(&query)[0].name, "Gmail");
+        
+        let query = AccountQuery { favorites_only: true, ..Default::default() };
+        assert!(vault.query_accounts(&query).is_empty());
+    }
+    
+    #[test]
+    fn test_indexes_follow_changes() {
+        let mut vault = sample_vault();
+        let id = vault.get_accounts_by_name("GITHUB")[0].id;
+        assert_eq!(vault.get_accounts_by_tag("dev").len(), 2);
+        
+        vault.update_account(&id, |account| {
+            account.name = "GitHub Enterprise".to_string();
+            account.url = Some("https://github.com/login".to_string());
+            account.tags = vec!["work".to_string()];
+        });
+        assert!(vault.get_accounts_by_name("github").is_empty());
+        assert_eq!(vault.get_accounts_by_name("github enterprise")[0].id, id);
+        assert_eq!(vault.get_accounts_by_tag("dev").len(), 1);
+        assert_eq!(vault.get_accounts_by_tag("work")[0].id, id);
+        assert_eq!(vault.get_accounts_for_url("gist.github.com")[0].id, id);
+        assert!(vault.get_accounts_for_url("https://notgithub.com").is_empty());
+        
+        let names: Vec<_> = vault.search_accounts("G").iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["GitHub Enterprise", "Gmail"]);
+        
+        // Direct changes are picked up once the index is rebuilt
+        vault.get_account_mut(&id).unwrap().name = "Forge".to_string();
+        assert_eq!(vault.search_accounts("forge").len(), 1);
+        vault.accounts.get_mut(&id).unwrap().tags.clear();
+        vault.reindex();
+        assert!(vault.get_accounts_by_tag("work").is_empty());
+        
+        vault.remove_account(&id);
+        assert!(vault.search_accounts("forge").is_empty());
+        assert!(vault.get_accounts_for_url("github.com").is_empty());
+    }
+    
+    #[test]
+    fn test_account_host() {
+        let mut account = Account::new("Site".to_string(), AccountType::Other, "pw".to_string());
+        assert_eq!(account.host(), None);
+        account.url = Some("https://user@WWW.Example.com:8080/login?next=/".to_string());
+        assert_eq!(account.host().as_deref(), Some("example.com"));
+        account.url = Some("intranet.local/wiki".to_string());
+        assert_eq!(account.host().as_deref(), Some("intranet.local"));
+    }
 }
@@ -0,0 +1,321 @@
+//! # Device Pairing
+//! 
+//! Moves a vault file to another device over the local network, without
+//! any server in between. The sending device listens on a random port
+//! and shows a pairing code, as text and as a QR code:
+//! 
+//! ```text
+//! passman-pair:192.168.1.20:41873/MZXW6YTBOI...
+//! ```
+//! 
+//! The code holds the address and a random one-time secret. The receiving
+//! device connects and proves it knows the secret; only then is the vault
+//! file sent. Every message is encrypted and authenticated with AES-GCM
+//! under keys derived from the secret (one per direction), so nobody on
+//! the network can read, change, or receive the vault without the code.
+//! The vault file itself stays encrypted with the master password.
+//! 
+//! A listener sends one vault, then stops. Connections that fail the
+//! handshake are dropped and it keeps waiting until its timeout.
+
+use crate::{
+    PassManError, Result,
+    crypto::{CryptoManager, SecureKey},
+    base32,
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Prefix of pairing codes
+pub const PAIRING_SCHEME: &str = "passman-pair:";
+
+/// How long each step of a connection may take
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest message accepted (vault files are far smaller)
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// Where to send a vault, and the one-time secret that protects it
+pub struct PairingCode {
+    /// Address the sending device listens on
+    pub address: SocketAddr,
+    
+    /// One-time secret shared through the code
+    secret: SecureKey,
+}
+
+/// A vault file received from another device
+pub struct ReceivedVault {
+    /// Name of the vault on the sending device
+    pub name: String,
+    
+    /// The vault file, still encrypted with its master password
+    pub data: Vec<u8>,
+}
+
+/// Messages of the pairing protocol
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    /// Receiver to sender: a fresh challenge, proving the receiver knows the secret
+    Hello { challenge: String },
+    
+    /// Sender to receiver: the vault, bound to the receiver's challenge
+    Vault { challenge: String, name: String, data: String },
+    
+    /// Receiver to sender: the vault arrived intact
+    Received,
+}
+
+impl PairingCode {
+    /// Parse a pairing code
+    /// 
+    /// # Arguments
+    /// * `code` - The code, as shown by the sending device
+    /// 
+    /// # Errors
+    /// Returns an error if it isn't a pairing code
+    pub fn parse(code: &str) -> Result<Self> {
+        let invalid = || PassManError::InvalidInput("Not a pairing code".to_string());
+        let rest = code.trim().strip_prefix(PAIRING_SCHEME).ok_or_else(invalid)?;
+        let (address, secret) = rest.rsplit_once('/').ok_or_else(invalid)?;
+        let address = address.parse().map_err(|_| invalid())?;
+        let secret: [u8; 32] = base32::decode(secret)
+            .map_err(|_| invalid())?
+            .try_into()
+            .map_err(|_| invalid())?;
+        Ok(Self { address, secret: SecureKey::new(secret) })
+    }
+    
+    /// Key for messages in one direction
+    fn key(&self, direction: &str) -> SecureKey {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(b"passman pairing ");
+        mac.update(direction.as_bytes());
+        SecureKey::new(mac.finalize().into_bytes().into())
+    }
+}
+
+impl fmt::Display for PairingCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", PAIRING_SCHEME, self.address, base32::encode(self.secret.as_bytes()))
+    }
+}
+
+/// The sending side of a pairing, waiting for the other device
+pub struct PairingListener {
+    listener: TcpListener,
+    code: PairingCode,
+}
+
+impl PairingListener {
+    /// Listen on a random port for the receiving device
+    /// 
+    /// # Arguments
+    /// * `address` - Address to listen on and put in the code (default:
+    ///   this device's address on the local network)
+    /// 
+    /// # Errors
+    /// Returns an error if no local address is found or binding fails
+    pub fn bind(address: Option<IpAddr>) -> Result<Self> {
+        let ip = match address {
+            Some(ip) => ip,
+            None => local_address()?,
+        };
+        let listener = TcpListener::bind((ip, 0))
+            .map_err(|e| PassManError::StorageError(format!("Cannot listen on {}: {}", ip, e)))?;
+        
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let code = PairingCode {
+            address: listener.local_addr()?,
+            secret: SecureKey::new(secret),
+        };
+        Ok(Self { listener, code })
+    }
+    
+    /// The code to show to the receiving device
+    pub fn code(&self) -> &PairingCode {
+        &self.code
+    }
+    
+    /// Wait for the receiving device and send it a vault file
+    /// 
+    /// # Arguments
+    /// * `name` - Name of the vault
+    /// * `data` - The vault file
+    /// * `timeout` - How long to wait for the other device
+    /// 
+    /// # Returns
+    /// Address of the device that received the vault
+    /// 
+    /// # Errors
+    /// Returns an error if no device completes the handshake in time
+    pub fn send(self, name: &str, data: &[u8], timeout: Duration) -> Result<SocketAddr> {
+        let deadline = Instant::now() + timeout;
+        self.listener.set_nonblocking(true)?;
+        
+        loop {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    // A stranger's connection doesn't end the pairing
+                    if self.serve(stream, name, data).is_ok() {
+                        return Ok(peer);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(PassManError::StorageError("No device paired before the timeout".to_string()));
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    
+    fn serve(&self, mut stream: TcpStream, name: &str, data: &[u8]) -> Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let incoming = self.code.key("to sender");
+        let outgoing = self.code.key("to receiver");
+        
+        let Message::Hello { challenge } = read_message(&mut stream, &incoming)? else {
+            return Err(protocol_error());
+        };
+        let vault = Message::Vault { challenge, name: name.to_string(), data: BASE64.encode(data) };
+        write_message(&mut stream, &outgoing, &vault)?;
+        match read_message(&mut stream, &incoming)? {
+            Message::Received => Ok(()),
+            _ => Err(protocol_error()),
+        }
+    }
+}
+
+/// Receive a vault file from the device showing a pairing code
+/// 
+/// # Arguments
+/// * `code` - The pairing code
+/// 
+/// # Errors
+/// Returns an error if the device can't be reached or the code is wrong
+pub fn receive(code: &PairingCode) -> Result<ReceivedVault> {
+    let mut stream = TcpStream::connect_timeout(&code.address, IO_TIMEOUT)
+        .map_err(|e| PassManError::StorageError(format!("Cannot reach {}: {}", code.address, e)))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let outgoing = code.key("to sender");
+    let incoming = code.key("to receiver");
+    
+    let mut challenge = [0u8; 16];
+    OsRng.fill_bytes(&mut challenge);
+    let challenge = BASE64.encode(challenge);
+    write_message(&mut stream, &outgoing, &Message::Hello { challenge: challenge.clone() })?;
+    
+    let Message::Vault { challenge: echoed, name, data } = read_message(&mut stream, &incoming)? else {
+        return Err(protocol_error());
+    };
+    if echoed != challenge {
+        return Err(protocol_error());
+    }
+    let data = BASE64.decode(data).map_err(|_| protocol_error())?;
+    write_message(&mut stream, &outgoing, &Message::Received)?;
+    
+    Ok(ReceivedVault { name, data })
+}
+
+/// This device's address on the local network
+/// 
+/// Connecting a UDP socket sends nothing; it only picks the interface a
+/// packet to that address would leave from.
+fn local_address() -> Result<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect((Ipv4Addr::new(192, 168, 0, 1), 9))
+        .map_err(|_| PassManError::StorageError("No local network found; pass an address to listen on".to_string()))?;
+    Ok(socket.local_addr()?.ip())
+}
+
+fn write_message(stream: &mut TcpStream, key: &SecureKey, message: &Message) -> Result<()> {
+    let encrypted = CryptoManager::new().encrypt_with_key(&serde_json::to_vec(message)?, key)?;
+    stream.write_all(&(encrypted.len() as u32).to_be_bytes())?;
+    stream.write_all(&encrypted)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_message(stream: &mut TcpStream, key: &SecureKey) -> Result<Message> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_MESSAGE {
+        return Err(protocol_error());
+    }
+    let mut encrypted = vec![0u8; length];
+    stream.read_exact(&mut encrypted)?;
+    
+    let plaintext = CryptoManager::new().decrypt_with_key(&encrypted, key)
+        .map_err(|_| PassManError::AuthenticationFailed("The other device doesn't have this pairing code".to_string()))?;
+    serde_json::from_slice(&plaintext).map_err(|_| protocol_error())
+}
+
+fn protocol_error() -> PassManError {
+    PassManError::InvalidInput("Unexpected message from the other device".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PassMan;
+    use crate::storage::VaultStorage;
+    use crate::testing::{AccountBuilder, TestVault, isolate};
+    
+    #[test]
+    fn test_code_round_trip() {
+        let listener = PairingListener::bind(Some(Ipv4Addr::LOCALHOST.into())).unwrap();
+        let code = listener.code().to_string();
+        assert!(code.starts_with("passman-pair:127.0.0.1:"));
+        
+        let parsed = PairingCode::parse(&code).unwrap();
+        assert_eq!(parsed.to_string(), code);
+        assert!(PairingCode::parse("passman-pair:127.0.0.1:1/AAAA").is_err());
+        assert!(PairingCode::parse("https://example.com").is_err());
+    }
+    
+    #[test]
+    fn test_transfer() {
+        let vault = TestVault::builder().account(AccountBuilder::new("GitHub").build()).build().unwrap();
+        let file = std::fs::read(vault.dir().join("test.vault")).unwrap();
+        let listener = PairingListener::bind(Some(Ipv4Addr::LOCALHOST.into())).unwrap();
+        let code = PairingCode::parse(&listener.code().to_string()).unwrap();
+        let sender = std::thread::spawn(move || listener.send("main", &file, Duration::from_secs(10)));
+        
+        // A device with the wrong secret gets nothing
+        let mut wrong = PairingCode::parse(&code.to_string()).unwrap();
+        wrong.secret = SecureKey::generate();
+        assert!(receive(&wrong).is_err());
+        
+        let received = receive(&code).unwrap();
+        assert_eq!(received.name, "main");
+        assert!(sender.join().unwrap().is_ok());
+        
+        // The received file is the same vault, under the same master password
+        isolate();
+        let name = format!("paired_{}", uuid::Uuid::new_v4().simple());
+        let storage = VaultStorage::new(&name).unwrap();
+        storage.install_vault_file(&received.data).unwrap();
+        assert!(storage.install_vault_file(&received.data).is_err());
+        let mut passman = PassMan::new(&name).unwrap();
+        passman.open_vault(vault.password()).unwrap();
+        assert_eq!(passman.get_all_accounts().len(), 1);
+        VaultStorage::delete_vault(&name).unwrap();
+    }
+}
@@ -0,0 +1,649 @@
+//! # Import and Export Plugins
+//! 
+//! Import and export formats are plugins looked up by name in a
+//! `PluginRegistry`. The backend registers its own formats
+//! (`PluginRegistry::with_builtins`); other crates add theirs by
+//! implementing `ImportPlugin` or `ExportPlugin` and registering them.
+//! 
+//! Formats can also come from executables, found by name like git
+//! subcommands (`PluginRegistry::discover`):
+//! 
+//! - `passman-import-<format>` reads the file to import on stdin and
+//!   writes `{"accounts": [<record>...], "errors": ["..."]}` to stdout.
+//! - `passman-export-<format>` reads `{"accounts": [<record>...]}` on
+//!   stdin and writes the exported file to stdout.
+//! 
+//! A record is an `AccountRecord` as JSON. A plugin fails by exiting
+//! with a non-zero status; its stderr becomes the error message.
+
+use crate::{
+    PassManError, Result,
+    models::{Account, AccountType, CustomField},
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Name of the built-in JSON format (the backend's account records)
+pub const JSON_FORMAT: &str = "json";
+
+/// Name of the portable export format (`format::encrypt_portable`)
+pub const PORTABLE_FORMAT: &str = "passman";
+
+/// A format accounts can be imported from
+pub trait ImportPlugin: Send + Sync {
+    /// Format name, as passed to `passman import --format`
+    fn name(&self) -> &str;
+    
+    /// One-line description for format listings
+    fn description(&self) -> &str;
+    
+    /// Read accounts from a file in this format
+    /// 
+    /// # Arguments
+    /// * `input` - The file's contents
+    /// 
+    /// # Returns
+    /// The accounts read, plus messages for entries that were skipped
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read as this format at all
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts>;
+    
+    /// Whether a file is locked with a password, which front-ends ask for
+    /// and pass to `import_encrypted`
+    /// 
+    /// # Arguments
+    /// * `data` - The file's contents
+    fn is_encrypted(&self, data: &[u8]) -> bool {
+        let _ = data;
+        false
+    }
+    
+    /// Read accounts from a password-protected file in this format
+    /// 
+    /// Formats that aren't encrypted ignore the password.
+    /// 
+    /// # Arguments
+    /// * `input` - The file's contents
+    /// * `password` - The file's password
+    /// 
+    /// # Errors
+    /// Returns an error if the password is wrong or the file can't be read
+    /// as this format at all
+    fn import_encrypted(&self, input: &mut dyn Read, password: &str) -> Result<ImportedAccounts> {
+        let _ = password;
+        self.import(input)
+    }
+}
+
+/// A format accounts can be exported to
+pub trait ExportPlugin: Send + Sync {
+    /// Format name, as passed to `passman export --format`
+    fn name(&self) -> &str;
+    
+    /// One-line description for format listings
+    fn description(&self) -> &str;
+    
+    /// Write accounts in this format
+    /// 
+    /// # Arguments
+    /// * `accounts` - The accounts to export
+    /// * `output` - Where the exported file is written
+    /// 
+    /// # Errors
+    /// Returns an error if the accounts can't be written
+    fn export(&self, accounts: &[Account], output: &mut dyn Write) -> Result<()>;
+}
+
+/// Accounts read by an import plugin
+#[derive(Debug, Default)]
+pub struct ImportedAccounts {
+    /// Accounts ready to merge into a vault
+    pub accounts: Vec<Account>,
+    
+    /// Entries that couldn't be read, e.g. "line 4: missing name"
+    pub errors: Vec<String>,
+}
+
+/// An account as exchanged with executable plugins
+/// 
+/// Every field but `name` may be left out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AccountRecord {
+    /// Account name
+    pub name: String,
+    
+    /// Account type (`Other` if missing)
+    #[serde(rename = "type")]
+    pub account_type: Option<AccountType>,
+    
+    /// Username or email
+    pub username: Option<String>,
+    
+    /// Password
+    pub password: String,
+    
+    /// Website URL
+    pub url: Option<String>,
+    
+    /// Notes
+    pub notes: Option<String>,
+    
+    /// Tags
+    pub tags: Vec<String>,
+    
+    /// Folder path
+    pub folder: Option<String>,
+    
+    /// TOTP secret or `otpauth://` URI
+    pub totp: Option<String>,
+    
+    /// User-defined fields
+    pub custom_fields: Vec<CustomField>,
+}
+
+impl From<&Account> for AccountRecord {
+    fn from(account: &Account) -> Self {
+        Self {
+            name: account.name.clone(),
+            account_type: Some(account.account_type.clone()),
+            username: account.username.clone(),
+            password: account.password.clone(),
+            url: account.url.clone(),
+            notes: account.notes.clone(),
+            tags: account.tags.clone(),
+            folder: account.folder.clone(),
+            totp: account.totp.clone(),
+            custom_fields: account.custom_fields.clone(),
+        }
+    }
+}
+
+impl AccountRecord {
+    /// Turn the record into a new account
+    /// 
+    /// # Errors
+    /// Returns an error if the name is empty
+    pub fn into_account(self) -> Result<Account> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(PassManError::InvalidInput("missing name".to_string()));
+        }
+        
+        let mut account = Account::new(name.to_string(), self.account_type.unwrap_or(AccountType::Other), self.password);
+        account.username = self.username.filter(|username| !username.is_empty());
+        account.url = self.url.filter(|url| !url.is_empty());
+        account.notes = self.notes.filter(|notes| !notes.is_empty());
+        account.tags = self.tags;
+        account.folder = self.folder.filter(|folder| !folder.is_empty());
+        account.totp = self.totp.filter(|totp| !totp.is_empty());
+        account.custom_fields = self.custom_fields;
+        Ok(account)
+    }
+}
+
+/// Import and export formats by name
+pub struct PluginRegistry {
+    importers: Vec<Box<dyn ImportPlugin>>,
+    exporters: Vec<Box<dyn ExportPlugin>>,
+}
+
+impl PluginRegistry {
+    /// Create a registry without any formats
+    pub fn new() -> Self {
+        Self {
+            importers: Vec::new(),
+            exporters: Vec::new(),
+        }
+    }
+    
+    /// Create a registry with the backend's own formats
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.importers.push(Box::new(JsonFormat));
+        registry.importers.push(Box::new(PortableFormat));
+        registry.importers.push(Box::new(crate::importers::CsvImporter::default()));
+        registry.importers.push(Box::new(crate::importers::KdbxImporter));
+        registry.importers.push(Box::new(crate::importers::BitwardenImporter));
+        registry.importers.push(Box::new(crate::importers::LastPassImporter));
+        registry.importers.push(Box::new(crate::importers::OnePasswordImporter));
+        registry.exporters.push(Box::new(JsonFormat));
+        registry.exporters.push(Box::new(crate::importers::CsvExporter));
+        registry
+    }
+    
+    /// Add an import format
+    /// 
+    /// # Errors
+    /// Returns an error if a format with the same name is registered
+    pub fn register_importer(&mut self, plugin: Box<dyn ImportPlugin>) -> Result<()> {
+        if self.importer(plugin.name()).is_some() {
+            return Err(PassManError::InvalidInput(format!("Import format '{}' is already registered", plugin.name())));
+        }
+        self.importers.push(plugin);
+        Ok(())
+    }
+    
+    /// Add an export format
+    /// 
+    /// # Errors
+    /// Returns an error if a format with the same name is registered
+    pub fn register_exporter(&mut self, plugin: Box<dyn ExportPlugin>) -> Result<()> {
+        if self.exporter(plugin.name()).is_some() {
+            return Err(PassManError::InvalidInput(format!("Export format '{}' is already registered", plugin.name())));
+        }
+        self.exporters.push(plugin);
+        Ok(())
+    }
+    
+    /// Find an import format by name (case-insensitive)
+    pub fn importer(&self, name: &str) -> Option<&dyn ImportPlugin> {
+        self.importers.iter()
+            .find(|plugin| plugin.name().eq_ignore_ascii_case(name))
+            .map(|plugin| plugin.as_ref())
+    }
+    
+    /// Find an export format by name (case-insensitive)
+    pub fn exporter(&self, name: &str) -> Option<&dyn ExportPlugin> {
+        self.exporters.iter()
+            .find(|plugin| plugin.name().eq_ignore_ascii_case(name))
+            .map(|plugin| plugin.as_ref())
+    }
+    
+    /// Import formats, in registration order
+    pub fn importers(&self) -> impl Iterator<Item = &dyn ImportPlugin> {
+        self.importers.iter().map(|plugin| plugin.as_ref())
+    }
+    
+    /// Export formats, in registration order
+    pub fn exporters(&self) -> impl Iterator<Item = &dyn ExportPlugin> {
+        self.exporters.iter().map(|plugin| plugin.as_ref())
+    }
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+/// The backend's account records as a JSON array
+struct JsonFormat;
+
+impl ImportPlugin for JsonFormat {
+    fn name(&self) -> &str {
+        JSON_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "PassMan JSON export"
+    }
+    
+    fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+        let accounts: Vec<Account> = serde_json::from_reader(input)?;
+        Ok(ImportedAccounts { accounts, errors: Vec::new() })
+    }
+}
+
+impl ExportPlugin for JsonFormat {
+    fn name(&self) -> &str {
+        JSON_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "PassMan JSON export"
+    }
+    
+    fn export(&self, accounts: &[Account], output: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(&mut *output, accounts)?;
+        output.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Accounts of a portable export
+/// 
+/// Exports are written by `PassMan::export_portable`; this reads their
+/// accounts so they can be merged into another vault.
+struct PortableFormat;
+
+impl ImportPlugin for PortableFormat {
+    fn name(&self) -> &str {
+        PORTABLE_FORMAT
+    }
+    
+    fn description(&self) -> &str {
+        "PassMan portable export, encrypted with an export password"
+    }
+    
+    fn is_encrypted(&self, _data: &[u8]) -> bool {
+        true
+    }
+    
+    fn import(&self, _input: &mut dyn Read) -> Result<ImportedAccounts> {
+        Err(PassManError::InvalidInput("PassMan exports can only be imported with their export password".to_string()))
+    }
+    
+    fn import_encrypted(&self, input: &mut dyn Read, password: &str) -> Result<ImportedAccounts> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let mut vault = crate::format::open_portable_bytes(&data, password)?;
+        let mut accounts: Vec<Account> = std::mem::take(&mut vault.accounts).into_values().collect();
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(ImportedAccounts { accounts, errors: Vec::new() })
+    }
+}
+
+#[cfg(feature = "storage")]
+pub use executable::{ExecutablePlugin, plugin_dirs};
+
+#[cfg(feature = "storage")]
+mod executable {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    
+    /// File name prefix of executable import plugins
+    const IMPORT_PREFIX: &str = "passman-import-";
+    
+    /// File name prefix of executable export plugins
+    const EXPORT_PREFIX: &str = "passman-export-";
+    
+    /// What an import plugin writes to stdout
+    #[derive(Deserialize)]
+    struct ImportOutput {
+        accounts: Vec<AccountRecord>,
+        #[serde(default)]
+        errors: Vec<String>,
+    }
+    
+    /// What an export plugin reads on stdin
+    #[derive(Serialize)]
+    struct ExportInput {
+        accounts: Vec<AccountRecord>,
+    }
+    
+    /// An import or export format provided by an executable
+    pub struct ExecutablePlugin {
+        name: String,
+        description: String,
+        path: PathBuf,
+    }
+    
+    impl ExecutablePlugin {
+        /// Wrap an executable speaking the plugin protocol
+        /// 
+        /// # Arguments
+        /// * `name` - Format name
+        /// * `path` - The executable
+        pub fn new(name: &str, path: PathBuf) -> Self {
+            Self {
+                name: name.to_string(),
+                description: format!("plugin {}", path.display()),
+                path,
+            }
+        }
+        
+        /// Path of the executable
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+        
+        /// Run the executable with `input` on stdin and collect its stdout
+        fn run(&self, input: &mut dyn Read) -> Result<Vec<u8>> {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            
+            let mut child = Command::new(&self.path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| PassManError::StorageError(format!("Cannot run {}: {}", self.path.display(), e)))?;
+            
+            // Feed stdin from another thread so a plugin that writes while
+            // it reads can't deadlock against us
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            let (fed, output) = std::thread::scope(|scope| {
+                let feeder = scope.spawn(move || stdin.write_all(&data));
+                let output = child.wait_with_output();
+                (feeder.join().expect("stdin feeder panicked"), output)
+            });
+            let output = output?;
+            
+            if !output.status.success() {
+                let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                return Err(PassManError::InvalidInput(if message.is_empty() {
+                    format!("Plugin '{}' failed ({})", self.name, output.status)
+                } else {
+                    format!("Plugin '{}' failed: {}", self.name, message)
+                }));
+            }
+            // The plugin may exit without reading all of its input
+            if let Err(e) = fed {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    return Err(e.into());
+                }
+            }
+            Ok(output.stdout)
+        }
+    }
+    
+    impl ImportPlugin for ExecutablePlugin {
+        fn name(&self) -> &str {
+            &self.name
+        }
+        
+        fn description(&self) -> &str {
+            &self.description
+        }
+        
+        fn import(&self, input: &mut dyn Read) -> Result<ImportedAccounts> {
+            let stdout = self.run(input)?;
+            let output: ImportOutput = serde_json::from_slice(&stdout)
+                .map_err(|e| PassManError::InvalidInput(format!("Plugin '{}' wrote invalid output: {}", self.name, e)))?;
+            
+            let mut imported = ImportedAccounts { accounts: Vec::new(), errors: output.errors };
+            for (index, record) in output.accounts.into_iter().enumerate() {
+                match record.into_account() {
+                    Ok(account) => imported.accounts.push(account),
+                    Err(e) => imported.errors.push(format!("record {}: {}", index + 1, e)),
+                }
+            }
+            Ok(imported)
+        }
+    }
+    
+    impl ExportPlugin for ExecutablePlugin {
+        fn name(&self) -> &str {
+            &self.name
+        }
+        
+        fn description(&self) -> &str {
+            &self.description
+        }
+        
+        fn export(&self, accounts: &[Account], output: &mut dyn Write) -> Result<()> {
+            let input = serde_json::to_vec(&ExportInput {
+                accounts: accounts.iter().map(AccountRecord::from).collect(),
+            })?;
+            let stdout = self.run(&mut input.as_slice())?;
+            output.write_all(&stdout)?;
+            Ok(())
+        }
+    }
+    
+    /// Directories searched for executable plugins: `passman/plugins` in
+    /// the config directory, then `PATH`
+    pub fn plugin_dirs() -> Vec<PathBuf> {
+        let mut dirs_found: Vec<PathBuf> = crate::config::passman_config_dir()
+            .map(|dir| dir.join("plugins"))
+            .into_iter()
+            .collect();
+        if let Some(path) = std::env::var_os("PATH") {
+            dirs_found.extend(std::env::split_paths(&path));
+        }
+        dirs_found
+    }
+    
+    impl PluginRegistry {
+        /// Register executable plugins found in `dirs`
+        /// 
+        /// Names already taken (by built-in formats, registered plugins, or
+        /// an executable found earlier) are skipped. Unreadable directories
+        /// are ignored.
+        /// 
+        /// # Arguments
+        /// * `dirs` - Directories to search, in order of preference
+        /// 
+        /// # Returns
+        /// The number of plugins registered
+        pub fn discover(&mut self, dirs: &[PathBuf]) -> usize {
+            let mut registered = 0;
+            for dir in dirs {
+                let Ok(entries) = std::fs::read_dir(dir) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                        continue;
+                    };
+                    // Windows executables carry an extension
+                    let stem = file_name.strip_suffix(std::env::consts::EXE_SUFFIX).unwrap_or(file_name);
+                    if !is_executable(&path) {
+                        continue;
+                    }
+                    
+                    if let Some(name) = stem.strip_prefix(IMPORT_PREFIX).filter(|name| !name.is_empty()) {
+                        let plugin = ExecutablePlugin::new(name, path.clone());
+                        if self.register_importer(Box::new(plugin)).is_ok() {
+                            registered += 1;
+                        }
+                    } else if let Some(name) = stem.strip_prefix(EXPORT_PREFIX).filter(|name| !name.is_empty()) {
+                        let plugin = ExecutablePlugin::new(name, path.clone());
+                        if self.register_exporter(Box::new(plugin)).is_ok() {
+                            registered += 1;
+                        }
+                    }
+                }
+            }
+            registered
+        }
+    }
+    
+    #[cfg(unix)]
+    fn is_executable(path: &Path) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    
+    #[cfg(not(unix))]
+    fn is_executable(path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    struct UpperCase;
+    
+    impl ExportPlugin for UpperCase {
+        fn name(&self) -> &str {
+            "upper"
+        }
+        
+        fn description(&self) -> &str {
+            "Account names in capitals"
+        }
+        
+        fn export(&self, accounts: &[Account], output: &mut dyn Write) -> Result<()> {
+            for account in accounts {
+                writeln!(output, "{}", account.name.to_uppercase())?;
+            }
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_registry() {
+        let mut registry = PluginRegistry::with_builtins();
+        assert!(registry.importer("JSON").is_some());
+        assert!(registry.exporter("upper").is_none());
+        
+        registry.register_exporter(Box::new(UpperCase)).unwrap();
+        assert!(registry.register_exporter(Box::new(UpperCase)).is_err());
+        assert_eq!(registry.exporters().map(|plugin| plugin.name()).collect::<Vec<_>>(), ["json", "csv", "upper"]);
+        
+        let accounts = vec![Account::new("Mail".to_string(), AccountType::Email, "pw".to_string())];
+        let mut output = Vec::new();
+        registry.exporter("upper").unwrap().export(&accounts, &mut output).unwrap();
+        assert_eq!(output, b"MAIL\n");
+    }
+    
+    #[test]
+    fn test_json_round_trip() {
+        let registry = PluginRegistry::with_builtins();
+        let mut account = Account::new("Mail".to_string(), AccountType::Email, "pw".to_string());
+        account.username = Some("me".to_string());
+        
+        let mut output = Vec::new();
+        registry.exporter(JSON_FORMAT).unwrap().export(std::slice::from_ref(&account), &mut output).unwrap();
+        let imported = registry.importer(JSON_FORMAT).unwrap().import(&mut output.as_slice()).unwrap();
+        assert_eq!(imported.accounts.len(), 1);
+        assert_eq!(imported.accounts[0].username.as_deref(), Some("me"));
+        
+        let record: AccountRecord = serde_json::from_str(r#"{"name": " Bank ", "type": "Banking", "url": ""}"#).unwrap();
+        let account = record.into_account().unwrap();
+        assert_eq!(account.name, "Bank");
+        assert_eq!(account.account_type, AccountType::Banking);
+        assert!(account.url.is_none());
+        assert!(AccountRecord::default().into_account().is_err());
+    }
+    
+    #[test]
+    fn test_portable_import() {
+        let mut vault = crate::models::Vault::new("test@example.com".to_string());
+        vault.add_account(Account::new("Mail".to_string(), AccountType::Email, "pw".to_string()));
+        vault.add_account(Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string()));
+        let kdf = crate::crypto::KdfParams { memory_kib: 8 * 1024, iterations: 1, parallelism: 1 };
+        let data = crate::format::encrypt_portable(&vault, "export password", kdf).unwrap();
+        
+        let registry = PluginRegistry::with_builtins();
+        let plugin = registry.importer(PORTABLE_FORMAT).unwrap();
+        assert!(plugin.is_encrypted(&data));
+        assert!(plugin.import(&mut data.as_slice()).is_err());
+        assert!(plugin.import_encrypted(&mut data.as_slice(), "wrong").is_err());
+        let imported = plugin.import_encrypted(&mut data.as_slice(), "export password").unwrap();
+        assert_eq!(imported.accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["Bank", "Mail"]);
+    }
+    
+    #[test]
+    #[cfg(all(feature = "storage", unix))]
+    fn test_executable_plugins() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("passman-import-lines");
+        std::fs::write(&script, "#!/bin/sh\nprintf '{\"accounts\": ['\nsep=\nwhile read -r name; do printf '%s{\"name\": \"%s\"}' \"$sep\" \"$name\"; sep=,; done\nprintf ']}'\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let failing = dir.path().join("passman-export-broken");
+        std::fs::write(&failing, "#!/bin/sh\necho 'no such format' >&2\nexit 3\n").unwrap();
+        std::fs::set_permissions(&failing, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(dir.path().join("passman-import-notexecutable"), "").unwrap();
+        
+        let mut registry = PluginRegistry::with_builtins();
+        assert_eq!(registry.discover(&[dir.path().to_path_buf()]), 2);
+        assert!(registry.importer("notexecutable").is_none());
+        
+        let imported = registry.importer("lines").unwrap().import(&mut b"GitHub\n\nMail\n".as_slice()).unwrap();
+        assert_eq!(imported.accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["GitHub", "Mail"]);
+        assert_eq!(imported.errors, ["record 2: Invalid input: missing name"]);
+        
+        let error = registry.exporter("broken").unwrap().export(&[], &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("no such format"));
+    }
+}
@@ -0,0 +1,373 @@
+//! # Password Policy
+//! 
+//! A vault can carry a password policy (`VaultSettings::password_policy`)
+//! that every password added or changed through the vault manager must
+//! meet. Passwords that fail are rejected with
+//! `PassManError::PolicyViolation`, which lists each rule that was broken
+//! so front-ends can show them next to the password field.
+//! 
+//! Imports and merges are not checked: they bring in passwords that
+//! already exist elsewhere, and the audit reports the weak ones.
+//! 
+//! A `SitePolicy` is the other side: what one website accepts (no
+//! symbols, at most 12 characters, ...). It is stored on an account or
+//! for a domain (see `Vault::site_policy`), and passwords generated for
+//! that account are adapted to it.
+
+use crate::generator::PasswordGenerator;
+use crate::models::PasswordOptions;
+use crate::{PassManError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Rules a vault's passwords must follow
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// Minimum length in characters (0 = any)
+    pub min_length: usize,
+    
+    /// Require an uppercase letter
+    pub require_uppercase: bool,
+    
+    /// Require a lowercase letter
+    pub require_lowercase: bool,
+    
+    /// Require a digit
+    pub require_digit: bool,
+    
+    /// Require a character that is neither a letter nor a digit
+    pub require_special: bool,
+    
+    /// Minimum strength score, 0-100 (see `PasswordGenerator::calculate_strength`)
+    pub min_strength: u8,
+    
+    /// Reject well-known common passwords
+    pub deny_common: bool,
+}
+
+/// One rule of a policy that a password breaks
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PolicyViolation {
+    /// Shorter than `min_length`
+    TooShort { min: usize, actual: usize },
+    
+    /// No uppercase letter
+    MissingUppercase,
+    
+    /// No lowercase letter
+    MissingLowercase,
+    
+    /// No digit
+    MissingDigit,
+    
+    /// No special character
+    MissingSpecial,
+    
+    /// Strength score below `min_strength`
+    TooWeak { min: u8, score: u8 },
+    
+    /// A well-known common password
+    Common,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::TooShort { min, actual } => {
+                write!(f, "must be at least {} characters long (has {})", min, actual)
+            }
+            PolicyViolation::MissingUppercase => write!(f, "must contain an uppercase letter"),
+            PolicyViolation::MissingLowercase => write!(f, "must contain a lowercase letter"),
+            PolicyViolation::MissingDigit => write!(f, "must contain a digit"),
+            PolicyViolation::MissingSpecial => write!(f, "must contain a special character"),
+            PolicyViolation::TooWeak { min, score } => {
+                write!(f, "must have a strength score of at least {} (scores {})", min, score)
+            }
+            PolicyViolation::Common => write!(f, "must not be a common password"),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Whether the policy has no rules at all
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// List the rules a password breaks
+    /// 
+    /// # Arguments
+    /// * `password` - The password to check
+    /// 
+    /// # Returns
+    /// The broken rules, in the order they are declared; empty if the
+    /// password meets the policy
+    pub fn violations(&self, password: &str) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(PolicyViolation::TooShort { min: self.min_length, actual: length });
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(PolicyViolation::MissingUppercase);
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(PolicyViolation::MissingLowercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PolicyViolation::MissingDigit);
+        }
+        if self.require_special && password.chars().all(char::is_alphanumeric) {
+            violations.push(PolicyViolation::MissingSpecial);
+        }
+        if self.min_strength > 0 || self.deny_common {
+            let generator = PasswordGenerator::new();
+            let score = generator.calculate_strength(password);
+            if score < self.min_strength {
+                violations.push(PolicyViolation::TooWeak { min: self.min_strength, score });
+            }
+            if self.deny_common && generator.is_common_password(password) {
+                violations.push(PolicyViolation::Common);
+            }
+        }
+        violations
+    }
+    
+    /// Check a password against the policy
+    /// 
+    /// # Arguments
+    /// * `password` - The password to check
+    /// 
+    /// # Returns
+    /// Unit if the password meets the policy
+    /// 
+    /// # Errors
+    /// Returns `PassManError::PolicyViolation` listing the broken rules
+    pub fn check(&self, password: &str) -> Result<()> {
+        let violations = self.violations(password);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(PassManError::PolicyViolation(violations))
+        }
+    }
+}
+
+/// What a website accepts as a password
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SitePolicy {
+    /// Minimum length in characters (0 = any)
+    pub min_length: usize,
+    
+    /// Maximum length in characters (0 = no limit)
+    pub max_length: usize,
+    
+    /// Character classes the site accepts
+    pub allow_uppercase: bool,
+    pub allow_lowercase: bool,
+    pub allow_digits: bool,
+    pub allow_special: bool,
+    
+    /// Character classes the site insists on
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+}
+
+impl Default for SitePolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 0,
+            max_length: 0,
+            allow_uppercase: true,
+            allow_lowercase: true,
+            allow_digits: true,
+            allow_special: true,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_special: false,
+        }
+    }
+}
+
+impl SitePolicy {
+    /// Whether the policy accepts anything
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Check that some password can meet the policy
+    /// 
+    /// # Errors
+    /// Returns an error if the minimum length is above the maximum, a
+    /// required class isn't allowed, or no class is allowed
+    pub fn validate(&self) -> Result<()> {
+        if self.max_length > 0 && self.min_length > self.max_length {
+            return Err(PassManError::InvalidInput(format!(
+                "Minimum length {} is above the maximum {}", self.min_length, self.max_length
+            )));
+        }
+        let classes = [
+            ("uppercase letters", self.allow_uppercase, self.require_uppercase),
+            ("lowercase letters", self.allow_lowercase, self.require_lowercase),
+            ("digits", self.allow_digits, self.require_digit),
+            ("special characters", self.allow_special, self.require_special),
+        ];
+        if let Some((name, _, _)) = classes.iter().find(|(_, allow, require)| *require && !*allow) {
+            return Err(PassManError::InvalidInput(format!("The site can't both require and forbid {}", name)));
+        }
+        if classes.iter().all(|(_, allow, _)| !*allow) {
+            return Err(PassManError::InvalidInput("The site must allow at least one character class".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Adapt password generator options to the site
+    /// 
+    /// The length is moved into the site's range, classes the site doesn't
+    /// accept are dropped along with their minimums, and classes it requires
+    /// are added. If none of the chosen classes are left, every class the
+    /// site accepts is used.
+    /// 
+    /// # Arguments
+    /// * `options` - The options the user chose
+    /// 
+    /// # Returns
+    /// Options whose passwords the site accepts
+    /// 
+    /// # Errors
+    /// Returns an error if no password can meet the policy (see `validate`)
+    pub fn password_options(&self, options: &PasswordOptions) -> Result<PasswordOptions> {
+        self.validate()?;
+        
+        let mut length = options.length.max(self.min_length);
+        if self.max_length > 0 {
+            length = length.min(self.max_length);
+        }
+        let mut adapted = PasswordOptions {
+            length,
+            include_uppercase: self.allow_uppercase && (options.include_uppercase || self.require_uppercase),
+            include_lowercase: self.allow_lowercase && (options.include_lowercase || self.require_lowercase),
+            include_numbers: self.allow_digits && (options.include_numbers || self.require_digit),
+            include_special: self.allow_special && (options.include_special || self.require_special),
+            min_uppercase: if self.allow_uppercase { options.min_uppercase } else { 0 },
+            min_numbers: if self.allow_digits { options.min_numbers } else { 0 },
+            min_special: if self.allow_special { options.min_special } else { 0 },
+            ..options.clone()
+        };
+        if !(adapted.include_uppercase || adapted.include_lowercase || adapted.include_numbers || adapted.include_special) {
+            adapted.include_uppercase = self.allow_uppercase;
+            adapted.include_lowercase = self.allow_lowercase;
+            adapted.include_numbers = self.allow_digits;
+            adapted.include_special = self.allow_special;
+        }
+        Ok(adapted)
+    }
+}
+
+/// Join violations into one sentence, for error messages
+pub(crate) fn describe(violations: &[PolicyViolation]) -> String {
+    violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_violations() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.is_empty());
+        assert!(policy.violations("").is_empty());
+        
+        let policy = PasswordPolicy {
+            min_length: 12,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            min_strength: 60,
+            deny_common: true,
+        };
+        assert!(policy.violations("Correct-Horse-9-Battery").is_empty());
+        assert_eq!(policy.violations("password"), [
+            PolicyViolation::TooShort { min: 12, actual: 8 },
+            PolicyViolation::MissingUppercase,
+            PolicyViolation::MissingDigit,
+            PolicyViolation::MissingSpecial,
+            PolicyViolation::TooWeak { min: 60, score: 3 },
+            PolicyViolation::Common,
+        ]);
+        
+        let error = policy.check("ÄÖÜäöü1!").unwrap_err();
+        let PassManError::PolicyViolation(violations) = &error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert_eq!(violations[0], PolicyViolation::TooShort { min: 12, actual: 8 });
+        assert!(error.to_string().contains("at least 12 characters"));
+    }
+    
+    #[test]
+    fn test_site_policy_options() {
+        let options = PasswordOptions::strong(20);
+        assert_eq!(SitePolicy::default().password_options(&options).unwrap(), options);
+        
+        let no_symbols = SitePolicy {
+            max_length: 12,
+            allow_special: false,
+            ..SitePolicy::default()
+        };
+        let adapted = no_symbols.password_options(&options).unwrap();
+        assert_eq!(adapted.length, 12);
+        assert!(!adapted.include_special);
+        assert!(adapted.include_uppercase && adapted.include_lowercase && adapted.include_numbers);
+        
+        // Required classes are added, and a length below the minimum is raised
+        let digits_only = SitePolicy {
+            min_length: 6,
+            allow_uppercase: false,
+            allow_lowercase: false,
+            allow_special: false,
+            require_digit: true,
+            ..SitePolicy::default()
+        };
+        let adapted = digits_only.password_options(&PasswordOptions { length: 4, ..PasswordOptions::simple(4) }).unwrap();
+        assert_eq!(adapted.length, 6);
+        assert!(adapted.include_numbers && !adapted.include_uppercase && !adapted.include_lowercase);
+        
+        // Nothing chosen is left, so the site's classes are used
+        let letters = SitePolicy { allow_digits: false, allow_special: false, ..SitePolicy::default() };
+        let only_digits = PasswordOptions { include_uppercase: false, include_lowercase: false, include_special: false, ..options.clone() };
+        let adapted = letters.password_options(&only_digits).unwrap();
+        assert!(adapted.include_uppercase && adapted.include_lowercase && !adapted.include_numbers);
+    }
+    
+    #[test]
+    fn test_site_policy_validate() {
+        assert!(SitePolicy::default().validate().is_ok());
+        for policy in [
+            SitePolicy { min_length: 16, max_length: 12, ..SitePolicy::default() },
+            SitePolicy { allow_special: false, require_special: true, ..SitePolicy::default() },
+            SitePolicy { allow_uppercase: false, allow_lowercase: false, allow_digits: false, allow_special: false, ..SitePolicy::default() },
+        ] {
+            assert!(matches!(policy.validate(), Err(PassManError::InvalidInput(_))), "{:?}", policy);
+        }
+    }
+    
+    #[test]
+    fn test_violation_serialization() {
+        let json = serde_json::to_string(&PolicyViolation::TooWeak { min: 60, score: 20 }).unwrap();
+        assert_eq!(json, r#"{"rule":"too_weak","min":60,"score":20}"#);
+        let json = serde_json::to_string(&PolicyViolation::Common).unwrap();
+        assert_eq!(json, r#"{"rule":"common"}"#);
+        
+        let info = PassManError::PolicyViolation(vec![PolicyViolation::Common]).info();
+        assert_eq!((info.code, info.kind.as_str()), (9, "policy_violation"));
+        assert_eq!(serde_json::to_value(&info).unwrap()["violations"][0]["rule"], "common");
+    }
+}
@@ -0,0 +1,260 @@
+//! # Password Change Reminders
+//! 
+//! `ReminderScheduler` decides when front-ends should remind the user to
+//! change old passwords: the desktop app runs it from a background task,
+//! and `passman notify` runs it from cron. A password is due for a change
+//! `ReminderPolicy::max_age_days` after it was last changed, or after the
+//! account's own `rotation_interval_days`, and at its `expires_at` date at
+//! the latest; reminders start `warn_days` earlier and repeat every
+//! `repeat_days` per account.
+//! 
+//! The scheduler's state (when it last ran, and when each account was last
+//! reminded about) lives in `reminders/<vault>.json` in the passman config
+//! directory. It also keeps each account's due date, so a check while the
+//! vault is locked can still say how many passwords need attention; only
+//! account IDs are stored, never names.
+//! 
+//! Each reminder carries a deep link, `passman://<vault>/accounts/<id>`,
+//! which front-ends use to open the affected entry.
+
+use crate::{
+    Result,
+    config::{ReminderPolicy, passman_config_dir},
+    models::Account,
+    sync,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Scheme of account deep links
+pub const LINK_SCHEME: &str = "passman://";
+
+/// A password that needs changing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reminder {
+    /// Account ID
+    pub account_id: Uuid,
+    
+    /// Account name (unknown when the check ran with the vault locked)
+    pub account_name: Option<String>,
+    
+    /// When the password is or was due for a change
+    pub due: DateTime<Utc>,
+    
+    /// Deep link to the account
+    pub link: String,
+}
+
+impl Reminder {
+    /// Whether the password is already past its due date
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.due <= now
+    }
+    
+    /// One-line description for a notification
+    pub fn message(&self, now: DateTime<Utc>) -> String {
+        let name = self.account_name.as_deref().unwrap_or("A password");
+        let days = (self.due - now).num_days();
+        if self.is_overdue(now) {
+            format!("{}: the password is due for a change", name)
+        } else {
+            format!("{}: the password is due for a change in {} day(s)", name, days.max(1))
+        }
+    }
+}
+
+/// Persistent state of a scheduler
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct SchedulerState {
+    /// When reminders were last evaluated
+    last_run: Option<DateTime<Utc>>,
+    
+    /// When each account was last reminded about
+    notified: BTreeMap<Uuid, DateTime<Utc>>,
+    
+    /// Due date of each account's password, as of the last unlocked run
+    due: BTreeMap<Uuid, DateTime<Utc>>,
+}
+
+/// Decides which password reminders to send, and when
+pub struct ReminderScheduler {
+    vault_name: String,
+    policy: ReminderPolicy,
+    state: SchedulerState,
+}
+
+impl ReminderScheduler {
+    /// Load the scheduler of a vault
+    /// 
+    /// # Arguments
+    /// * `vault_name` - The vault
+    /// * `policy` - Reminder settings, usually `PassManConfig::reminders`
+    /// 
+    /// # Errors
+    /// Returns an error if the state file exists but can't be read
+    pub fn load(vault_name: &str, policy: ReminderPolicy) -> Result<Self> {
+        let path = state_path(vault_name)?;
+        let state = if path.exists() {
+            // A corrupt state file only means some reminders come again
+            serde_json::from_slice(&std::fs::read(&path)?).unwrap_or_default()
+        } else {
+            SchedulerState::default()
+        };
+        Ok(Self { vault_name: vault_name.to_string(), policy, state })
+    }
+    
+    /// When the next check is due
+    pub fn next_run(&self) -> DateTime<Utc> {
+        match self.state.last_run {
+            Some(last_run) => last_run + Duration::hours(self.policy.interval_hours.into()),
+            None => DateTime::<Utc>::MIN_UTC,
+        }
+    }
+    
+    /// Whether a check is due
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.policy.enabled && now >= self.next_run()
+    }
+    
+    /// Evaluate reminders
+    /// 
+    /// Returns the accounts to remind about now and records them, so they
+    /// aren't reminded about again for `repeat_days`. Call `save` afterwards.
+    /// 
+    /// # Arguments
+    /// * `accounts` - All accounts of the open vault, or None to use the due
+    ///   dates of the last run with the vault open
+    /// * `now` - Current time
+    /// 
+    /// # Returns
+    /// The reminders, most urgent first
+    pub fn run(&mut self, accounts: Option<&[&Account]>, now: DateTime<Utc>) -> Vec<Reminder> {
+        self.state.last_run = Some(now);
+        if !self.policy.enabled {
+            return Vec::new();
+        }
+        
+        let mut names = BTreeMap::new();
+        if let Some(accounts) = accounts {
+            self.state.due.clear();
+            for account in accounts {
+                if let Some(due) = password_due(account, &self.policy) {
+                    self.state.due.insert(account.id, due);
+                    names.insert(account.id, account.name.clone());
+                }
+            }
+            // Forget accounts that were deleted or archived
+            let due = &self.state.due;
+            self.state.notified.retain(|id, _| due.contains_key(id));
+        }
+        
+        let warn_from = now + Duration::days(self.policy.warn_days.into());
+        let repeat = Duration::days(self.policy.repeat_days.max(1).into());
+        let mut reminders: Vec<Reminder> = self.state.due.iter()
+            .filter(|(_, due)| **due <= warn_from)
+            .filter(|(id, _)| self.state.notified.get(id).is_none_or(|last| now - *last >= repeat))
+            .map(|(id, due)| Reminder {
+                account_id: *id,
+                account_name: names.get(id).cloned(),
+                due: *due,
+                link: account_link(&self.vault_name, id),
+            })
+            .collect();
+        reminders.sort_by_key(|reminder| reminder.due);
+        
+        for reminder in &reminders {
+            self.state.notified.insert(reminder.account_id, now);
+        }
+        reminders
+    }
+    
+    /// Save the scheduler's state
+    /// 
+    /// # Errors
+    /// Returns an error if the state file can't be written
+    pub fn save(&self) -> Result<()> {
+        let path = state_path(&self.vault_name)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_vec_pretty(&self.state)?)?;
+        Ok(())
+    }
+}
+
+/// When an account's password is due for a change
+/// 
+/// # Returns
+/// The due date, or None for archived accounts and accounts without a password
+pub fn password_due(account: &Account, policy: &ReminderPolicy) -> Option<DateTime<Utc>> {
+    if account.archived || account.password.is_empty() {
+        return None;
+    }
+    // The account's own rotation interval replaces the policy's maximum age
+    let max_age_days = account.rotation_interval_days.unwrap_or(policy.max_age_days);
+    let due = sync::field_time(account, "password") + Duration::days(max_age_days.into());
+    Some(account.expires_at.map_or(due, |expires_at| expires_at.min(due)))
+}
+
+/// Deep link to an account
+pub fn account_link(vault_name: &str, id: &Uuid) -> String {
+    format!("{}{}/accounts/{}", LINK_SCHEME, vault_name, id)
+}
+
+/// Parse a deep link into vault name and account ID
+pub fn parse_account_link(link: &str) -> Option<(String, Uuid)> {
+    let (vault, id) = link.strip_prefix(LINK_SCHEME)?.split_once("/accounts/")?;
+    Some((vault.to_string(), id.trim_end_matches('/').parse().ok()?))
+}
+
+fn state_path(vault_name: &str) -> Result<PathBuf> {
+    Ok(passman_config_dir()?.join("reminders").join(format!("{}.json", vault_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AccountType, Vault};
+    
+    #[test]
+    fn test_scheduler() {
+        crate::testing::isolate();
+        let vault_name = format!("reminders_{}", Uuid::new_v4().simple());
+        let now = Utc::now();
+        let mut vault = Vault::new("test@example.com".to_string());
+        let mut old = Account::new("Old".to_string(), AccountType::Email, "pw".to_string());
+        old.updated_at = now - Duration::days(400);
+        let mut soon = Account::new("Soon".to_string(), AccountType::Email, "pw".to_string());
+        soon.updated_at = now - Duration::days(360);
+        let fresh = Account::new("Fresh".to_string(), AccountType::Email, "pw".to_string());
+        for account in [old.clone(), soon.clone(), fresh] {
+            vault.add_account(account);
+        }
+        
+        let mut scheduler = ReminderScheduler::load(&vault_name, ReminderPolicy::default()).unwrap();
+        assert!(scheduler.is_due(now));
+        let reminders = scheduler.run(Some(&vault.get_all_accounts()), now);
+        let names: Vec<_> = reminders.iter().map(|r| r.account_name.as_deref().unwrap()).collect();
+        assert_eq!(names, ["Old", "Soon"]);
+        assert!(reminders[0].is_overdue(now) && !reminders[1].is_overdue(now));
+        assert_eq!(parse_account_link(&reminders[0].link), Some((vault_name.clone(), old.id)));
+        assert!(!scheduler.is_due(now + Duration::hours(1)));
+        scheduler.save().unwrap();
+        
+        // Nothing repeats within `repeat_days`; a locked check still knows the due dates
+        let mut scheduler = ReminderScheduler::load(&vault_name, ReminderPolicy::default()).unwrap();
+        assert!(scheduler.run(None, now + Duration::days(1)).is_empty());
+        let reminders = scheduler.run(None, now + Duration::days(8));
+        assert_eq!(reminders.len(), 2);
+        assert!(reminders.iter().all(|r| r.account_name.is_none()));
+        
+        // Changing the password ends the reminders
+        vault.update_account(&old.id, |account| account.password = "new".to_string());
+        let reminders = scheduler.run(Some(&vault.get_all_accounts()), now + Duration::days(16));
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].account_id, soon.id);
+    }
+}
@@ -0,0 +1,291 @@
+//! # Screen Lock Watcher
+//! 
+//! This module tells front-ends when the user's session locks or the
+//! machine is about to sleep, so they can lock open vaults right away
+//! instead of waiting for the auto-lock timeout (`screen-lock` feature).
+//! 
+//! - Linux: logind's `Session.Lock` signal and `LockedHint` property for
+//!   the current session, and `Manager.PrepareForSleep`, read from the
+//!   system bus through `gdbus monitor`
+//! - macOS: the `com.apple.screenIsLocked` distributed notification and
+//!   NSWorkspace's sleep and session switch notifications
+//! - Windows: `WM_WTSSESSION_CHANGE` lock messages and the
+//!   `WM_POWERBROADCAST` suspend message
+//! 
+//! Watching runs on a background thread for the rest of the process.
+
+use crate::{PassManError, Result};
+use std::sync::Arc;
+
+/// Why open vaults should be locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockEvent {
+    /// The user's session locked (screen lock, or switching users)
+    ScreenLocked,
+    
+    /// The machine is about to sleep or hibernate
+    Suspending,
+}
+
+impl LockEvent {
+    /// Short name, e.g. for logs and front-end events
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockEvent::ScreenLocked => "screen-lock",
+            LockEvent::Suspending => "sleep",
+        }
+    }
+}
+
+/// Callback receiving lock events on the watcher thread
+type Handler = Arc<dyn Fn(LockEvent) + Send + Sync>;
+
+/// Start watching for screen locks and suspends
+/// 
+/// # Arguments
+/// * `on_event` - Called on a background thread for every event
+/// 
+/// # Returns
+/// Unit once watching has started
+/// 
+/// # Errors
+/// Returns an error if the platform doesn't report these events, or the
+/// watcher can't be started (e.g. `gdbus` is missing on Linux)
+pub fn watch(on_event: impl Fn(LockEvent) + Send + Sync + 'static) -> Result<()> {
+    start(Arc::new(on_event))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn start(handler: Handler) -> Result<()> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    
+    let session = std::env::var("XDG_SESSION_ID").ok().map(|id| logind_session_path(&id));
+    let mut child = Command::new("gdbus")
+        .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| watch_error(&format!("can't run gdbus: {}", e)))?;
+    let stdout = child.stdout.take()
+        .ok_or_else(|| watch_error("gdbus has no output"))?;
+    
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(std::result::Result::ok) {
+            if let Some(event) = parse_logind_signal(&line, session.as_deref()) {
+                handler(event);
+            }
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Object path of a logind session (see `sd_bus_path_encode`)
+#[cfg(all(unix, not(target_os = "macos")))]
+fn logind_session_path(id: &str) -> String {
+    let mut path = String::from("/org/freedesktop/login1/session/");
+    if id.is_empty() {
+        path.push('_');
+    }
+    for (i, byte) in id.bytes().enumerate() {
+        if byte.is_ascii_alphabetic() || (byte.is_ascii_digit() && i > 0) {
+            path.push(byte as char);
+        } else {
+            path.push_str(&format!("_{:02x}", byte));
+        }
+    }
+    path
+}
+
+/// Event for one line of `gdbus monitor` output, if it is one
+/// 
+/// Lines look like `<object path>: <interface>.<member> (<arguments>)`.
+/// Lock signals of other sessions are ignored when the session is known.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn parse_logind_signal(line: &str, session: Option<&str>) -> Option<LockEvent> {
+    let (path, signal) = line.split_once(": ")?;
+    if signal.starts_with("org.freedesktop.login1.Manager.PrepareForSleep (true") {
+        return Some(LockEvent::Suspending);
+    }
+    if session.is_some_and(|session| session != path) {
+        return None;
+    }
+    let locked = signal.starts_with("org.freedesktop.login1.Session.Lock ")
+        || (signal.starts_with("org.freedesktop.DBus.Properties.PropertiesChanged ('org.freedesktop.login1.Session'")
+            && signal.contains("'LockedHint': <true>"));
+    locked.then_some(LockEvent::ScreenLocked)
+}
+
+#[cfg(target_os = "macos")]
+fn start(handler: Handler) -> Result<()> {
+    use block2::RcBlock;
+    use objc2_app_kit::{
+        NSWorkspace, NSWorkspaceSessionDidResignActiveNotification, NSWorkspaceWillSleepNotification,
+    };
+    use objc2_foundation::{NSDistributedNotificationCenter, NSNotification, NSRunLoop, NSString};
+    use std::ptr::NonNull;
+    
+    std::thread::spawn(move || {
+        let workspace = NSWorkspace::sharedWorkspace().notificationCenter();
+        let distributed = NSDistributedNotificationCenter::defaultCenter();
+        let screen_locked = NSString::from_str("com.apple.screenIsLocked");
+        
+        let observe = |event: LockEvent| {
+            let handler = Arc::clone(&handler);
+            RcBlock::new(move |_: NonNull<NSNotification>| handler(event))
+        };
+        let on_lock = observe(LockEvent::ScreenLocked);
+        let on_sleep = observe(LockEvent::Suspending);
+        
+        // Observers stay registered while the run loop below runs, which is forever
+        let _observers = unsafe {
+            [
+                distributed.addObserverForName_object_queue_usingBlock(Some(&screen_locked), None, None, &on_lock),
+                workspace.addObserverForName_object_queue_usingBlock(Some(NSWorkspaceSessionDidResignActiveNotification), None, None, &on_lock),
+                workspace.addObserverForName_object_queue_usingBlock(Some(NSWorkspaceWillSleepNotification), None, None, &on_sleep),
+            ]
+        };
+        NSRunLoop::currentRunLoop().run();
+    });
+    Ok(())
+}
+
+#[cfg(windows)]
+fn start(handler: Handler) -> Result<()> {
+    use std::sync::mpsc;
+    
+    let (started, result) = mpsc::channel();
+    std::thread::spawn(move || windows::run(handler, started));
+    result.recv().map_err(|_| watch_error("the watcher thread stopped"))?
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::System::RemoteDesktop::WTSRegisterSessionNotification;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, TranslateMessage, MSG, WNDCLASSW,
+    };
+    
+    // From WinUser.h and WtsApi32.h
+    const WM_POWERBROADCAST: u32 = 0x0218;
+    const WM_WTSSESSION_CHANGE: u32 = 0x02B1;
+    const PBT_APMSUSPEND: usize = 0x0004;
+    const WTS_SESSION_LOCK: usize = 0x0007;
+    const NOTIFY_FOR_THIS_SESSION: u32 = 0;
+    
+    thread_local! {
+        /// Handler of the watcher thread, called from its window procedure
+        static HANDLER: RefCell<Option<Handler>> = const { RefCell::new(None) };
+    }
+    
+    /// Create a hidden window for the messages and pump them forever
+    /// 
+    /// Message-only windows don't receive `WM_POWERBROADCAST`, so this is
+    /// an ordinary top-level window that is never shown.
+    pub(super) fn run(handler: Handler, started: Sender<Result<()>>) {
+        HANDLER.with(|cell| *cell.borrow_mut() = Some(handler));
+        let class_name: Vec<u16> = "PassManScreenLockWatcher\0".encode_utf16().collect();
+        
+        let hwnd = unsafe {
+            let instance = GetModuleHandleW(std::ptr::null());
+            let mut class: WNDCLASSW = std::mem::zeroed();
+            class.lpfnWndProc = Some(window_proc);
+            class.hInstance = instance;
+            class.lpszClassName = class_name.as_ptr();
+            if RegisterClassW(&class) == 0 {
+                let _ = started.send(Err(watch_error("can't register the window class")));
+                return;
+            }
+            CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                class_name.as_ptr(),
+                0,
+                0, 0, 0, 0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                instance,
+                std::ptr::null(),
+            )
+        };
+        if hwnd.is_null() {
+            let _ = started.send(Err(watch_error("can't create the message window")));
+            return;
+        }
+        if unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) } == 0 {
+            let _ = started.send(Err(watch_error("can't register for session notifications")));
+            return;
+        }
+        let _ = started.send(Ok(()));
+        
+        unsafe {
+            let mut message: MSG = std::mem::zeroed();
+            while GetMessageW(&mut message, std::ptr::null_mut(), 0, 0) > 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+        }
+    }
+    
+    unsafe extern "system" fn window_proc(hwnd: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        let event = match (message, wparam) {
+            (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => Some(LockEvent::ScreenLocked),
+            (WM_POWERBROADCAST, PBT_APMSUSPEND) => Some(LockEvent::Suspending),
+            _ => None,
+        };
+        if let Some(event) = event {
+            HANDLER.with(|cell| {
+                if let Some(handler) = cell.borrow().as_ref() {
+                    handler(event);
+                }
+            });
+        }
+        DefWindowProcW(hwnd, message, wparam, lparam)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn start(_handler: Handler) -> Result<()> {
+    Err(watch_error("not supported on this platform"))
+}
+
+fn watch_error(reason: &str) -> PassManError {
+    PassManError::InvalidInput(format!("Can't watch for screen locks: {}", reason))
+}
+
+#[cfg(test)]
+#[cfg(all(unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_logind_signal() {
+        let session = logind_session_path("2");
+        assert_eq!(session, "/org/freedesktop/login1/session/_32");
+        assert_eq!(logind_session_path("c1"), "/org/freedesktop/login1/session/c1");
+        
+        let lock = format!("{}: org.freedesktop.login1.Session.Lock ()", session);
+        assert_eq!(parse_logind_signal(&lock, Some(&session)), Some(LockEvent::ScreenLocked));
+        assert_eq!(parse_logind_signal(&lock, None), Some(LockEvent::ScreenLocked));
+        let other = "/org/freedesktop/login1/session/_33: org.freedesktop.login1.Session.Lock ()";
+        assert_eq!(parse_logind_signal(other, Some(&session)), None);
+        
+        let hint = format!(
+            "{}: org.freedesktop.DBus.Properties.PropertiesChanged ('org.freedesktop.login1.Session', {{'LockedHint': <true>}}, @as [])",
+            session,
+        );
+        assert_eq!(parse_logind_signal(&hint, Some(&session)), Some(LockEvent::ScreenLocked));
+        assert_eq!(parse_logind_signal(&hint.replace("<true>", "<false>"), Some(&session)), None);
+        
+        let sleep = "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)";
+        assert_eq!(parse_logind_signal(sleep, Some(&session)), Some(LockEvent::Suspending));
+        assert_eq!(parse_logind_signal(&sleep.replace("true", "false"), Some(&session)), None);
+    }
+}
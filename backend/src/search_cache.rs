@@ -0,0 +1,242 @@
+//! # Search Cache
+//! 
+//! Listing or searching a vault normally decrypts all of it, secrets
+//! included. When a vault's `search_cache_minutes` setting is on, every
+//! save also writes `<vault>.cache` beside the vault file: the accounts
+//! with their secrets removed (names, URLs, usernames, tags, folders,
+//! usage counts), encrypted with a key derived from the vault key.
+//! Front-ends that hold a session key (see `PassMan::session_key`) list
+//! and search from it instead of decrypting the whole vault.
+//! 
+//! A cache is only used until it expires, and only for the vault file it
+//! was built from: it records a digest of that file, so a save by an
+//! older version, a restored backup, or a synced copy makes it stale.
+
+use crate::crypto::{CryptoManager, SecureKey};
+use crate::models::{Account, AccountQuery, Vault};
+use crate::{PassManError, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Extension of the search cache file kept next to a vault file
+pub(crate) const CACHE_EXTENSION: &str = "cache";
+
+/// Label mixed into the vault key to get the cache key
+const KEY_CONTEXT: &[u8] = b"passman search cache v1";
+
+/// Accounts of a vault without their secrets, for listing and searching
+pub struct SearchCache {
+    /// The cached accounts, in a vault of their own for querying
+    vault: Vault,
+    
+    /// Digest of the vault file the cache was built from
+    vault_digest: String,
+    
+    /// When the cache was written
+    built_at: DateTime<Utc>,
+    
+    /// When the cache stops being used
+    expires_at: DateTime<Utc>,
+}
+
+/// Contents of a cache file before encryption
+#[derive(Serialize, Deserialize)]
+struct CacheRecord {
+    vault_digest: String,
+    built_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    accounts: Vec<Account>,
+}
+
+impl SearchCache {
+    /// List cached accounts matching a query (see `Vault::query_accounts`)
+    /// 
+    /// The accounts have no passwords, notes, TOTP secrets, custom
+    /// fields, autotype sequences, or SSH keys.
+    pub fn query_accounts(&self, query: &AccountQuery) -> Vec<&Account> {
+        self.vault.query_accounts(query)
+    }
+    
+    /// Number of cached accounts
+    pub fn len(&self) -> usize {
+        self.vault.accounts.len()
+    }
+    
+    /// Whether the cache holds no accounts
+    pub fn is_empty(&self) -> bool {
+        self.vault.accounts.is_empty()
+    }
+    
+    /// When the cache was written
+    pub fn built_at(&self) -> DateTime<Utc> {
+        self.built_at
+    }
+    
+    /// When the cache stops being used
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+    
+    /// Whether the cache was built from a vault file with this digest
+    pub(crate) fn is_for(&self, vault_digest: &str) -> bool {
+        self.vault_digest == vault_digest
+    }
+    
+    /// Whether the cache can still be used, i.e. is current and unexpired
+    pub(crate) fn is_valid_for(&self, vault_digest: &str) -> bool {
+        self.is_for(vault_digest) && self.expires_at > Utc::now()
+    }
+}
+
+/// Digest identifying the contents of a vault file
+/// 
+/// # Arguments
+/// * `parts` - The file's contents, possibly in pieces
+pub(crate) fn file_digest(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Build and encrypt the cache of a vault
+/// 
+/// # Arguments
+/// * `vault` - The vault as just saved
+/// * `vault_digest` - Digest of the saved vault file (see `file_digest`)
+/// * `key` - The vault key
+/// * `ttl_minutes` - How long the cache stays valid
+/// 
+/// # Returns
+/// The encrypted cache file
+/// 
+/// # Errors
+/// Returns an error if serialization or encryption fails
+pub(crate) fn encode(vault: &Vault, vault_digest: &str, key: &SecureKey, ttl_minutes: u32) -> Result<Vec<u8>> {
+    let built_at = Utc::now();
+    let record = CacheRecord {
+        vault_digest: vault_digest.to_string(),
+        built_at,
+        expires_at: built_at + Duration::minutes(ttl_minutes as i64),
+        accounts: vault.accounts.values().map(without_secrets).collect(),
+    };
+    let json = Zeroizing::new(serde_json::to_vec(&record).map_err(PassManError::SerializationError)?);
+    CryptoManager::new().encrypt_with_key(&json, &cache_key(key))
+}
+
+/// Decrypt a cache file
+/// 
+/// # Arguments
+/// * `data` - The encrypted cache file
+/// * `key` - The vault key
+/// 
+/// # Returns
+/// The cache, whether or not it is still valid
+/// 
+/// # Errors
+/// Returns an error if the key is wrong or the file is damaged
+pub(crate) fn decode(data: &[u8], key: &SecureKey) -> Result<SearchCache> {
+    let json = Zeroizing::new(CryptoManager::new().decrypt_with_key(data, &cache_key(key))?);
+    let record: CacheRecord = serde_json::from_slice(&json)
+        .map_err(|e| PassManError::StorageError(format!("Invalid search cache: {}", e)))?;
+    
+    let mut vault = Vault::new(String::new());
+    for account in record.accounts {
+        vault.add_account(account);
+    }
+    Ok(SearchCache {
+        vault,
+        vault_digest: record.vault_digest,
+        built_at: record.built_at,
+        expires_at: record.expires_at,
+    })
+}
+
+/// Key the cache is encrypted with, so the vault key itself encrypts nothing else
+fn cache_key(key: &SecureKey) -> SecureKey {
+    let mut hasher = Sha256::new();
+    hasher.update(KEY_CONTEXT);
+    hasher.update(key.as_bytes());
+    SecureKey::new(hasher.finalize().into())
+}
+
+/// Copy of an account with only the fields listing and searching use
+/// 
+/// Fields are listed one by one (rather than cloning and clearing) so the
+/// secrets are never copied, and a new field has to be placed here.
+fn without_secrets(account: &Account) -> Account {
+    Account {
+        id: account.id,
+        name: account.name.clone(),
+        account_type: account.account_type.clone(),
+        url: account.url.clone(),
+        username: account.username.clone(),
+        password: String::new(),
+        notes: None,
+        tags: account.tags.clone(),
+        folder: account.folder.clone(),
+        is_favorite: account.is_favorite,
+        color: account.color.clone(),
+        icon: account.icon.clone(),
+        archived: account.archived,
+        canary: account.canary,
+        expires_at: account.expires_at,
+        rotation_interval_days: account.rotation_interval_days,
+        totp: None,
+        custom_fields: Vec::new(),
+        autotype: None,
+        ssh_key: None,
+        card: None,
+        identity: None,
+        site_policy: None,
+        field_times: Default::default(),
+        created_at: account.created_at,
+        updated_at: account.updated_at,
+        last_accessed: account.last_accessed,
+        access_count: account.access_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AccountType;
+    
+    #[test]
+    fn test_cache_roundtrip() {
+        let mut vault = Vault::new("test@example.com".to_string());
+        let mut account = Account::new("GitHub".to_string(), AccountType::Work, "hunter2".to_string());
+        account.url = Some("https://github.com".to_string());
+        account.notes = Some("recovery codes".to_string());
+        account.tags = vec!["dev".to_string()];
+        vault.add_account(account);
+        vault.add_account(Account::new("Bank".to_string(), AccountType::Banking, "secret".to_string()));
+        
+        let key = SecureKey::generate();
+        let digest = file_digest(&[b"header", b"payload"]);
+        assert_eq!(digest, file_digest(&[b"headerpayload"]));
+        let data = encode(&vault, &digest, &key, 10).unwrap();
+        
+        let cache = decode(&data, &key).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.is_valid_for(&digest));
+        assert!(!cache.is_valid_for(&file_digest(&[b"other"])));
+        assert!(cache.expires_at() > cache.built_at());
+        
+        let query = AccountQuery { search: Some("git".to_string()), ..AccountQuery::default() };
+        let found = cache.query_accounts(&query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].url.as_deref(), Some("https://github.com"));
+        assert_eq!(found[0].tags, ["dev"]);
+        assert!(found[0].password.is_empty() && found[0].notes.is_none());
+        
+        assert!(decode(&data, &SecureKey::generate()).is_err());
+        
+        let expired = decode(&encode(&vault, &digest, &key, 0).unwrap(), &key).unwrap();
+        assert!(expired.is_for(&digest));
+        assert!(!expired.is_valid_for(&digest));
+    }
+}
@@ -0,0 +1,246 @@
+//! # Backup and Export Signatures
+//! 
+//! A vault can hold a minisign key pair (`Vault::signing_key`). While it
+//! does, backups and exports get a detached signature next to them
+//! (`<file>.minisig`), and restores and imports check it. A backup that
+//! was changed while kept on untrusted storage is caught before it
+//! replaces the vault.
+//! 
+//! Signatures use the minisign format, so they can also be checked
+//! without PassMan: `minisign -Vm <file> -P <public key>`.
+
+use crate::{PassManError, Result, models::SigningKey};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use minisign::{KeyPair, PublicKey, SecretKey, SignatureBox};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+use zeroize::Zeroizing;
+
+/// File extension of detached signatures
+pub const SIGNATURE_EXTENSION: &str = "minisig";
+
+/// Untrusted comment written into signatures
+const UNTRUSTED_COMMENT: &str = "signature from passman";
+
+/// Outcome of checking a file's detached signature
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Signed with the expected key and unchanged since
+    Valid,
+    
+    /// The file changed after signing, or another key signed it
+    Invalid,
+    
+    /// The file has no signature
+    Unsigned,
+    
+    /// No public key to check against (the vault has no signing key)
+    NoKey,
+}
+
+/// Generate a new signing key pair
+/// 
+/// # Errors
+/// Returns an error if the system random number generator fails
+pub fn generate_key() -> Result<SigningKey> {
+    let pair = KeyPair::generate_unencrypted_keypair().map_err(minisign_error)?;
+    let secret_key = Zeroizing::new(pair.sk.to_bytes());
+    Ok(SigningKey {
+        secret_key: BASE64.encode(secret_key.as_slice()),
+        public_key: pair.pk.to_base64(),
+        created_at: chrono::Utc::now(),
+    })
+}
+
+/// Sign data
+/// 
+/// # Arguments
+/// * `key` - Key pair to sign with
+/// * `data` - Data to sign
+/// * `trusted_comment` - Signed comment stored in the signature
+/// 
+/// # Returns
+/// The signature, in minisign's text format
+/// 
+/// # Errors
+/// Returns an error if the key pair is damaged or reading fails
+pub fn sign(key: &SigningKey, data: impl Read, trusted_comment: &str) -> Result<String> {
+    let secret_key = Zeroizing::new(BASE64.decode(&key.secret_key)
+        .map_err(|_| PassManError::CryptoError("Damaged signing key".to_string()))?);
+    let secret_key = SecretKey::from_bytes(&secret_key).map_err(minisign_error)?;
+    let public_key = parse_public_key(&key.public_key)?;
+    
+    let signature = minisign::sign(Some(&public_key), &secret_key, data, Some(trusted_comment), Some(UNTRUSTED_COMMENT))
+        .map_err(minisign_error)?;
+    Ok(signature.into_string())
+}
+
+/// Check a signature
+/// 
+/// # Arguments
+/// * `public_key` - Public key (base64) the data should be signed with
+/// * `data` - The signed data
+/// * `signature` - Signature in minisign's text format
+/// 
+/// # Returns
+/// True if the signature is valid for this data and key
+/// 
+/// # Errors
+/// Returns an error if the public key is malformed
+pub fn verify(public_key: &str, data: impl Read + Seek, signature: &str) -> Result<bool> {
+    let public_key = parse_public_key(public_key)?;
+    let Ok(signature) = SignatureBox::from_string(signature) else {
+        return Ok(false);
+    };
+    Ok(minisign::verify(&public_key, &signature, data, true, false, false).is_ok())
+}
+
+/// Make a detached signature for the contents of a file
+/// 
+/// # Arguments
+/// * `key` - Key pair to sign with
+/// * `file_name` - Name of the file, recorded in the signed comment
+/// * `data` - The file's contents
+/// 
+/// # Returns
+/// The signature, in minisign's text format
+/// 
+/// # Errors
+/// Returns an error if the key pair is damaged
+pub fn sign_contents(key: &SigningKey, file_name: &str, data: &[u8]) -> Result<String> {
+    let trusted_comment = format!("timestamp:{}\tfile:{}", chrono::Utc::now().timestamp(), file_name);
+    sign(key, data, &trusted_comment)
+}
+
+/// Check the contents of a file against its detached signature
+/// 
+/// # Arguments
+/// * `public_key` - Public key (base64) to expect, or None if there is none
+/// * `data` - The file's contents
+/// * `signature` - The detached signature, or None if the file has none
+/// 
+/// # Errors
+/// Returns an error if the key is malformed
+pub fn check_contents(public_key: Option<&str>, data: &[u8], signature: Option<&str>) -> Result<SignatureStatus> {
+    let Some(signature) = signature else {
+        return Ok(SignatureStatus::Unsigned);
+    };
+    let Some(public_key) = public_key else {
+        return Ok(SignatureStatus::NoKey);
+    };
+    Ok(if verify(public_key, std::io::Cursor::new(data), signature)? {
+        SignatureStatus::Valid
+    } else {
+        SignatureStatus::Invalid
+    })
+}
+
+/// Parse a base64 minisign public key
+/// 
+/// # Errors
+/// Returns an error if it isn't a minisign public key
+pub fn parse_public_key(public_key: &str) -> Result<PublicKey> {
+    PublicKey::from_base64(public_key.trim())
+        .map_err(|_| PassManError::InvalidInput("Not a minisign public key".to_string()))
+}
+
+fn minisign_error(e: minisign::PError) -> PassManError {
+    PassManError::CryptoError(format!("Signing failed: {}", e))
+}
+
+#[cfg(feature = "storage")]
+pub use files::{check_file, sign_file, signature_path};
+
+#[cfg(feature = "storage")]
+mod files {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    
+    /// Path of a file's detached signature (`<file>.minisig`)
+    pub fn signature_path(path: &Path) -> PathBuf {
+        let mut signature_path = path.as_os_str().to_owned();
+        signature_path.push(".");
+        signature_path.push(SIGNATURE_EXTENSION);
+        PathBuf::from(signature_path)
+    }
+    
+    /// Write a detached signature next to a file
+    /// 
+    /// # Returns
+    /// Path of the signature
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or the signature written
+    pub fn sign_file(key: &SigningKey, path: &Path) -> Result<PathBuf> {
+        let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let data = fs::read(path)
+            .map_err(|e| PassManError::StorageError(format!("Failed to read {}: {}", path.display(), e)))?;
+        let signature = sign_contents(key, &file_name, &data)?;
+        
+        let signature_path = signature_path(path);
+        fs::write(&signature_path, signature)
+            .map_err(|e| PassManError::StorageError(format!("Failed to write signature: {}", e)))?;
+        Ok(signature_path)
+    }
+    
+    /// Check the detached signature of a file
+    /// 
+    /// # Arguments
+    /// * `public_key` - Public key (base64) to expect, or None if there is none
+    /// * `path` - The signed file
+    /// 
+    /// # Errors
+    /// Returns an error if the files can't be read or the key is malformed
+    pub fn check_file(public_key: Option<&str>, path: &Path) -> Result<SignatureStatus> {
+        let signature = match fs::read_to_string(signature_path(path)) {
+            Ok(signature) => signature,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SignatureStatus::Unsigned),
+            Err(e) => return Err(PassManError::StorageError(format!("Failed to read signature: {}", e))),
+        };
+        if public_key.is_none() {
+            return Ok(SignatureStatus::NoKey);
+        }
+        
+        let data = fs::read(path)
+            .map_err(|e| PassManError::StorageError(format!("Failed to read {}: {}", path.display(), e)))?;
+        check_contents(public_key, &data, Some(&signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    
+    #[test]
+    fn test_sign_and_verify() {
+        let key = generate_key().unwrap();
+        let other = generate_key().unwrap();
+        
+        let signature = sign(&key, b"backup data".as_slice(), "file:backup").unwrap();
+        assert!(verify(&key.public_key, Cursor::new(b"backup data"), &signature).unwrap());
+        assert!(!verify(&key.public_key, Cursor::new(b"backup dat4"), &signature).unwrap());
+        assert!(!verify(&other.public_key, Cursor::new(b"backup data"), &signature).unwrap());
+        assert!(!verify(&key.public_key, Cursor::new(b"backup data"), "garbage").unwrap());
+        assert!(verify("not a key", Cursor::new(b"backup data"), &signature).is_err());
+    }
+    
+    #[test]
+    #[cfg(feature = "storage")]
+    fn test_file_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.json");
+        std::fs::write(&path, b"[]").unwrap();
+        let key = generate_key().unwrap();
+        
+        assert_eq!(check_file(Some(&key.public_key), &path).unwrap(), SignatureStatus::Unsigned);
+        let signature_path = sign_file(&key, &path).unwrap();
+        assert_eq!(signature_path, dir.path().join("export.json.minisig"));
+        assert_eq!(check_file(Some(&key.public_key), &path).unwrap(), SignatureStatus::Valid);
+        assert_eq!(check_file(None, &path).unwrap(), SignatureStatus::NoKey);
+        
+        std::fs::write(&path, b"[{}]").unwrap();
+        assert_eq!(check_file(Some(&key.public_key), &path).unwrap(), SignatureStatus::Invalid);
+    }
+}
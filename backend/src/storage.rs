@@ -1,26 +1,95 @@
 //! # Vault Storage Management
 //! 
-//! This module handles the secure storage and retrieval of vault data
-//! from the local filesystem with proper file permissions and atomic operations.
+//! This module handles the secure storage and retrieval of vault data.
+//! Vault files, backups, and the files kept beside them are stored in a
+//! `StorageBackend`: the local filesystem by default, with proper file
+//! permissions and atomic writes, a SQLite database (`storage = "sqlite"`
+//! in the config file), or any other backend.
+//! 
+//! Vaults whose header has `records` set keep each account in a record
+//! blob of its own (see `format`), so a save writes only the records of
+//! accounts that changed, plus the vault file with the record index.
+//! Backups, and vault files handed to other devices, carry the records
+//! with them.
 
-use std::fs::{self, File};
+use std::fs::File;
 use std::io::{Read, Write};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use dirs;
+use std::sync::{Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
 use serde_json;
-use crate::{PassManError, Result, models::Vault, crypto::CryptoManager};
+use crate::{PassManError, Result, config::{BackupPolicy, PassManConfig}, models::{SigningKey, Vault}, crypto::{CryptoManager, KdfParams, Salt, SecureKey}, format::{self, RecordRef, VaultLayout}, search_cache::{self, SearchCache}, signing::{self, SignatureStatus}, storage_backend::{self, BACKUPS, record_blob, record_blobs, vault_blob}};
+
+pub use crate::format::{Cipher, EmergencyAccess, KeySlot, KeySlotKind, VaultHeader};
+pub use crate::storage_backend::{FileBackend, MemoryBackend, StorageBackend};
+#[cfg(feature = "sqlite")]
+pub use crate::storage_backend::SqliteBackend;
+
+/// Filename prefix of vault backups
+const BACKUP_PREFIX: &str = "vault_backup_";
+/// Length of the `%Y%m%d_%H%M%S` timestamp in backup filenames
+const BACKUP_TIMESTAMP_LEN: usize = 15;
+
+/// Extension of the emergency request file kept next to a vault file
+const EMERGENCY_REQUEST_EXTENSION: &str = "emergency";
+
+/// An emergency contact's request for access to a vault
+/// 
+/// Kept in `<vault>.emergency` beside the vault file, so the contact can
+/// create it without unlocking the vault and the owner can deny it by
+/// deleting it. It isn't authenticated: whoever can write it can also
+/// backdate it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmergencyRequest {
+    /// When access was requested
+    pub requested_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A backup copy of a vault file, taken before each save or on request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackupInfo {
+    /// Timestamp identifying the backup (`YYYYmmdd_HHMMSS`, UTC)
+    pub timestamp: String,
+    
+    /// When the backup was taken
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    
+    /// Size of the backup file in bytes
+    pub size: u64,
+    
+    /// Whether the backup has a detached signature (see `signing`)
+    #[serde(default)]
+    pub signed: bool,
+}
 
 /// Vault storage manager
 pub struct VaultStorage {
-    /// Path to the vault file
-    vault_path: PathBuf,
-    /// Backup directory for vault files
-    backup_dir: PathBuf,
+    /// Name of the vault
+    vault_name: String,
+    /// Where the vault file and its backups are kept
+    backend: Box<dyn StorageBackend>,
+    /// Path to the vault file, if the backend keeps it in a local file
+    vault_path: Option<PathBuf>,
+    /// When backups are taken and how many are kept
+    backups: BackupPolicy,
+    /// Records of a record vault as last read or written, by account
+    records: Mutex<HashMap<uuid::Uuid, StoredRecord>>,
+}
+
+/// An account record that is in the storage backend
+struct StoredRecord {
+    /// Digest of the account's plaintext, to tell whether it has changed
+    plaintext: String,
+    /// Digest of the encrypted record, which names its blob
+    digest: String,
 }
 
 impl VaultStorage {
     /// Create a new vault storage manager
     /// 
+    /// Paths and the backup policy come from the config file.
+    /// 
     /// # Arguments
     /// * `vault_name` - Name of the vault (used for filename)
     /// 
@@ -28,38 +97,53 @@ impl VaultStorage {
     /// A new VaultStorage instance
     /// 
     /// # Errors
-    /// Returns an error if the vault directory cannot be created
+    /// Returns an error if the config file is invalid or the vault
+    /// directory cannot be created
     pub fn new(vault_name: &str) -> Result<Self> {
-        let vault_dir = Self::get_vault_directory()?;
-        let backup_dir = vault_dir.join("backups");
-        
-        // Create directories if they don't exist
-        fs::create_dir_all(&vault_dir)
-            .map_err(|e| PassManError::StorageError(format!("Failed to create vault directory: {}", e)))?;
-        
-        fs::create_dir_all(&backup_dir)
-            .map_err(|e| PassManError::StorageError(format!("Failed to create backup directory: {}", e)))?;
-        
-        let vault_path = vault_dir.join(format!("{}.vault", vault_name));
-        
-        Ok(Self {
-            vault_path,
-            backup_dir,
-        })
+        Self::with_config(vault_name, &PassManConfig::load()?)
     }
     
-    /// Get the default vault directory for the current platform
+    /// Create a new vault storage manager with the given configuration
+    /// 
+    /// The config's `storage` picks the backend.
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault (used for filename)
+    /// * `config` - Paths and backup policy to use
     /// 
     /// # Returns
-    /// Path to the vault directory
+    /// A new VaultStorage instance
     /// 
     /// # Errors
-    /// Returns an error if the directory cannot be determined
-    fn get_vault_directory() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| PassManError::StorageError("Cannot determine config directory".to_string()))?;
-        
-        Ok(config_dir.join("passman").join("vaults"))
+    /// Returns an error if the vault directory cannot be created, or the
+    /// configured backend isn't built in
+    pub fn with_config(vault_name: &str, config: &PassManConfig) -> Result<Self> {
+        let backend = storage_backend::configured_backend(config, true)?;
+        Ok(Self::with_boxed_backend(vault_name, backend, config.backups.clone()))
+    }
+    
+    /// Create a new vault storage manager on any storage backend
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault (used for blob names)
+    /// * `backend` - Where to keep the vault file and its backups
+    /// * `backups` - When backups are taken and how many are kept
+    /// 
+    /// # Returns
+    /// A new VaultStorage instance
+    pub fn with_backend(vault_name: &str, backend: impl StorageBackend + 'static, backups: BackupPolicy) -> Self {
+        Self::with_boxed_backend(vault_name, Box::new(backend), backups)
+    }
+    
+    fn with_boxed_backend(vault_name: &str, backend: Box<dyn StorageBackend>, backups: BackupPolicy) -> Self {
+        let vault_path = backend.local_path(&vault_blob(vault_name));
+        Self {
+            vault_name: vault_name.to_string(),
+            backend,
+            vault_path,
+            backups,
+            records: Mutex::default(),
+        }
     }
     
     /// Check if a vault exists
@@ -67,7 +151,24 @@ impl VaultStorage {
     /// # Returns
     /// True if the vault file exists, false otherwise
     pub fn vault_exists(&self) -> bool {
-        self.vault_path.exists()
+        self.backend.exists(&self.vault_blob()).unwrap_or(false)
+    }
+    
+    /// Create the vault from a vault file made elsewhere
+    /// 
+    /// Used to install a vault received from another device. The file
+    /// stays encrypted with the master password it was made with.
+    /// 
+    /// # Arguments
+    /// * `data` - The vault file
+    /// 
+    /// # Errors
+    /// Returns an error if the vault already exists or the data isn't a vault file
+    pub fn install_vault_file(&self, data: &[u8]) -> Result<()> {
+        if self.vault_exists() {
+            return Err(PassManError::VaultExists(format!("'{}'", self.vault_name)));
+        }
+        self.write_vault_bundle(data)
     }
     
     /// Save a vault to disk with encryption
@@ -83,49 +184,157 @@ impl VaultStorage {
     /// Returns an error if saving or encryption fails
     pub fn save_vault(&self, vault: &Vault, crypto: &CryptoManager) -> Result<()> {
         // Create backup before saving
-        if self.vault_exists() {
-            self.create_backup()?;
+        if self.backups.automatic && self.vault_exists() {
+            self.create_backup(vault.signing_key.as_ref())?;
         }
         
-        // Serialize vault to JSON
-        let vault_json = serde_json::to_string_pretty(vault)
-            .map_err(|e| PassManError::SerializationError(e))?;
+        // Keep the existing header; legacy vaults keep the bare salt prefix
+        match self.read_header()? {
+            Some(header) => self.write_vault(vault, crypto, &header.encode()?, header.records),
+            None => {
+                let salt = crypto.get_salt()
+                    .ok_or_else(|| PassManError::StorageError("No salt available for storage".to_string()))?;
+                self.write_vault(vault, crypto, salt.as_bytes(), false)
+            }
+        }
+    }
+    
+    /// Save a vault with a new header
+    /// 
+    /// Used when creating a vault or when its key slots change.
+    /// 
+    /// # Arguments
+    /// * `vault` - The vault to save
+    /// * `header` - The header to write
+    /// * `crypto` - Crypto manager holding the vault key
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if encryption or file operations fail
+    pub fn save_vault_with_header(&self, vault: &Vault, header: &VaultHeader, crypto: &CryptoManager) -> Result<()> {
+        if self.backups.automatic && self.vault_exists() {
+            self.create_backup(vault.signing_key.as_ref())?;
+        }
         
-        // Encrypt the vault data
-        let encrypted_data = crypto.encrypt(vault_json.as_bytes())?;
+        self.write_vault(vault, crypto, &header.encode()?, header.records)
+    }
+    
+    /// Encrypt a vault and write it behind a header or salt
+    /// 
+    /// Record vaults only write the records of accounts that changed;
+    /// records the vault file no longer refers to are deleted after it is
+    /// written.
+    fn write_vault(&self, vault: &Vault, crypto: &CryptoManager, prefix: &[u8], records: bool) -> Result<()> {
+        let (encrypted_data, unused_records) = if records {
+            self.write_records(vault, crypto)?
+        } else {
+            // Serialize and encrypt; the plaintext JSON is zeroized
+            let encrypted_data = format::encrypt_payload(vault, crypto)?;
+            self.stored_records().clear();
+            (encrypted_data, record_blobs(self.backend.as_ref(), &self.vault_name)?)
+        };
         
-        // Get the salt used for encryption
-        let salt = crypto.get_salt()
-            .ok_or_else(|| PassManError::StorageError("No salt available for storage".to_string()))?;
+        self.write_vault_file(prefix, &encrypted_data)?;
+        for name in unused_records {
+            let _ = self.backend.delete(&name);
+        }
+        self.update_search_cache(vault, crypto, prefix, &encrypted_data);
+        Ok(())
+    }
+    
+    /// Write the records of accounts that changed since they were last read or written
+    /// 
+    /// # Returns
+    /// The payload of the vault file, and the record blobs it doesn't refer to
+    fn write_records(&self, vault: &Vault, crypto: &CryptoManager) -> Result<(Vec<u8>, Vec<String>)> {
+        // Another program may have saved the vault and deleted records since
+        let mut unused: HashSet<String> = record_blobs(self.backend.as_ref(), &self.vault_name)?.into_iter().collect();
+        let mut stored = self.stored_records();
+        let mut written = HashMap::with_capacity(vault.accounts.len());
+        let mut index = Vec::with_capacity(vault.accounts.len());
         
-        // Write to temporary file first (atomic operation)
-        let temp_path = self.vault_path.with_extension("tmp");
-        {
-            let mut file = File::create(&temp_path)
-                .map_err(|e| PassManError::StorageError(format!("Failed to create temp file: {}", e)))?;
-            
-            // Write salt first (16 bytes)
-            file.write_all(salt.as_bytes())
-                .map_err(|e| PassManError::StorageError(format!("Failed to write salt: {}", e)))?;
-            
-            // Then write encrypted data
-            file.write_all(&encrypted_data)
-                .map_err(|e| PassManError::StorageError(format!("Failed to write vault data: {}", e)))?;
-            
-            file.sync_all()
-                .map_err(|e| PassManError::StorageError(format!("Failed to sync vault data: {}", e)))?;
+        for (id, account) in &vault.accounts {
+            let plaintext = format::record_plaintext(account)?;
+            let fingerprint = format::record_digest(&plaintext);
+            let record = match stored.remove(id) {
+                Some(record) if record.plaintext == fingerprint && unused.remove(&record_blob(&self.vault_name, &record.digest)) => record,
+                _ => {
+                    let data = crypto.encrypt(&plaintext)?;
+                    let digest = format::record_digest(&data);
+                    self.backend.write(&record_blob(&self.vault_name, &digest), &data)
+                        .map_err(|e| PassManError::StorageError(format!("Failed to write account record: {}", e)))?;
+                    StoredRecord { plaintext: fingerprint, digest }
+                }
+            };
+            index.push(RecordRef { id: *id, digest: record.digest.clone() });
+            written.insert(*id, record);
         }
+        *stored = written;
         
-        // Atomically move temp file to final location
-        fs::rename(&temp_path, &self.vault_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to move vault file: {}", e)))?;
+        let mut unused: Vec<String> = unused.into_iter().collect();
+        unused.sort();
+        Ok((format::encrypt_record_index(vault, &index, crypto)?, unused))
+    }
+    
+    /// Replace the vault with a vault file as `read_vault_file_data` returns it
+    /// 
+    /// The records appended to a record vault's file go into their own blobs.
+    fn write_vault_bundle(&self, file_data: &[u8]) -> Result<()> {
+        let (layout, encrypted_data) = format::parse_vault_file(file_data)?;
+        let existing = record_blobs(self.backend.as_ref(), &self.vault_name)?;
+        self.stored_records().clear();
+        if !matches!(&layout, VaultLayout::Versioned(header) if header.records) {
+            self.write_vault_file(&[], file_data)?;
+            for name in existing {
+                let _ = self.backend.delete(&name);
+            }
+            return Ok(());
+        }
         
-        // Set secure file permissions (owner read/write only)
-        self.set_secure_permissions(&self.vault_path)?;
+        let (index, records) = format::split_record_payload(&encrypted_data)?;
+        let mut kept = HashSet::with_capacity(records.len());
+        for record in records {
+            let name = record_blob(&self.vault_name, &format::record_digest(record));
+            if !self.backend.exists(&name)? {
+                self.backend.write(&name, record)
+                    .map_err(|e| PassManError::StorageError(format!("Failed to write account record: {}", e)))?;
+            }
+            kept.insert(name);
+        }
         
+        let prefix = &file_data[..file_data.len() - encrypted_data.len()];
+        self.write_vault_file(prefix, index)?;
+        for name in existing.into_iter().filter(|name| !kept.contains(name)) {
+            let _ = self.backend.delete(&name);
+        }
         Ok(())
     }
     
+    fn stored_records(&self) -> MutexGuard<'_, HashMap<uuid::Uuid, StoredRecord>> {
+        // Losing track of records only means rewriting them
+        self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
+    /// Decrypt a vault payload, reading records from the backend
+    fn decrypt(&self, layout: &VaultLayout, encrypted_data: &[u8], key: &SecureKey) -> Result<Vault> {
+        format::decrypt_vault(layout, encrypted_data, key, &|digest| self.read_record(digest))
+    }
+    
+    fn read_record(&self, digest: &str) -> Result<Option<Vec<u8>>> {
+        self.backend.read(&record_blob(&self.vault_name, digest))
+    }
+    
+    /// Atomically replace the vault file with a prefix and ciphertext
+    fn write_vault_file(&self, prefix: &[u8], encrypted_data: &[u8]) -> Result<()> {
+        // The salt or header comes first, then the encrypted data
+        let mut file_data = Vec::with_capacity(prefix.len() + encrypted_data.len());
+        file_data.extend_from_slice(prefix);
+        file_data.extend_from_slice(encrypted_data);
+        self.backend.write(&self.vault_blob(), &file_data)
+    }
+    
     /// Load a vault from disk with decryption
     /// 
     /// # Arguments
@@ -137,70 +346,454 @@ impl VaultStorage {
     /// # Errors
     /// Returns an error if loading or decryption fails
     pub fn load_vault(&self, master_password: &str) -> Result<Vault> {
-        if !self.vault_exists() {
-            return Err(PassManError::VaultNotFound(format!("Vault not found at: {}", self.vault_path.display())));
+        let key = self.unlock_key(master_password, None)?;
+        self.load_vault_with_key(&key)
+    }
+    
+    /// Get the vault key for a master password
+    /// 
+    /// For legacy vaults the key is derived directly from the password;
+    /// for versioned vaults it is unwrapped from the password key slot.
+    /// 
+    /// # Arguments
+    /// * `master_password` - The master password
+    /// * `keyfile` - Keyfile contents, if the vault uses one
+    /// 
+    /// # Returns
+    /// The vault key
+    /// 
+    /// # Errors
+    /// Returns an error if the vault cannot be read or the credentials are wrong
+    pub fn unlock_key(&self, master_password: &str, keyfile: Option<&[u8]>) -> Result<SecureKey> {
+        self.read_vault_file()?.0.unlock_key(master_password, keyfile)
+    }
+    
+    /// Load a vault from disk with an already-derived key
+    /// 
+    /// # Arguments
+    /// * `key` - Key derived from the master password and the vault's salt
+    /// 
+    /// # Returns
+    /// The loaded vault
+    /// 
+    /// # Errors
+    /// Returns an error if loading or decryption fails
+    pub fn load_vault_with_key(&self, key: &SecureKey) -> Result<Vault> {
+        let (layout, encrypted_data) = self.read_vault_file()?;
+        if !matches!(&layout, VaultLayout::Versioned(header) if header.records) {
+            return format::decrypt_payload(&encrypted_data, key);
         }
         
-        // Read data from file
-        let mut file = File::open(&self.vault_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to open vault file: {}", e)))?;
-        
-        let mut file_data = Vec::new();
-        file.read_to_end(&mut file_data)
-            .map_err(|e| PassManError::StorageError(format!("Failed to read vault file: {}", e)))?;
-        
-        // Extract salt (first 16 bytes) and encrypted data (rest)
-        if file_data.len() < 16 {
-            return Err(PassManError::StorageError("Vault file is corrupted: too small".to_string()));
+        // Remember the records, so the next save only writes those that change
+        let (vault, index) = format::decrypt_records(&encrypted_data, key, &|digest| self.read_record(digest))?;
+        let mut stored = HashMap::with_capacity(index.len());
+        for RecordRef { id, digest } in index {
+            let plaintext = format::record_digest(&format::record_plaintext(&vault.accounts[&id])?);
+            stored.insert(id, StoredRecord { plaintext, digest });
         }
-        
-        let salt_bytes: [u8; 16] = file_data[0..16].try_into()
-            .map_err(|_| PassManError::StorageError("Failed to read salt from vault file".to_string()))?;
-        let encrypted_data = &file_data[16..];
-        
-        // Create crypto manager and derive key from password and stored salt
-        let mut crypto = crate::crypto::CryptoManager::new();
-        let salt = crate::crypto::Salt::from_bytes(salt_bytes);
-        let key = crypto.derive_key(master_password, &salt)?;
-        
-        // Decrypt the vault data
-        let decrypted_data = crypto.decrypt_with_key(encrypted_data, &key)?;
-        
-        // Deserialize vault from JSON
-        let vault: Vault = serde_json::from_slice(&decrypted_data)
-            .map_err(|e| PassManError::SerializationError(e))?;
-        
+        *self.stored_records() = stored;
         Ok(vault)
     }
     
+    /// Read the key derivation salt stored in the vault file
+    /// 
+    /// # Returns
+    /// The vault's salt
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file is missing or corrupted
+    pub fn read_salt(&self) -> Result<Salt> {
+        match self.read_vault_file()?.0 {
+            VaultLayout::Legacy(salt) => Ok(salt),
+            VaultLayout::Versioned(header) => header.salt(),
+        }
+    }
+    
+    /// Read the header of a versioned vault file
+    /// 
+    /// # Returns
+    /// The header, or None for legacy vaults and vaults not yet created
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file cannot be read or is corrupted
+    pub fn read_header(&self) -> Result<Option<VaultHeader>> {
+        if !self.vault_exists() {
+            return Ok(None);
+        }
+        match self.read_vault_file()?.0 {
+            VaultLayout::Legacy(_) => Ok(None),
+            VaultLayout::Versioned(header) => Ok(Some(header)),
+        }
+    }
+    
+    /// Read the pending emergency access request, if any
+    /// 
+    /// # Errors
+    /// Returns an error if the request file cannot be read
+    pub fn read_emergency_request(&self) -> Result<Option<EmergencyRequest>> {
+        let Some(data) = self.backend.read(&self.emergency_request_blob())? else {
+            return Ok(None);
+        };
+        let request = serde_json::from_slice(&data)
+            .map_err(PassManError::SerializationError)?;
+        Ok(Some(request))
+    }
+    
+    /// Record an emergency access request next to the vault file
+    /// 
+    /// # Errors
+    /// Returns an error if the request file cannot be written
+    pub fn write_emergency_request(&self, request: &EmergencyRequest) -> Result<()> {
+        let data = serde_json::to_vec_pretty(request).map_err(PassManError::SerializationError)?;
+        self.backend.write(&self.emergency_request_blob(), &data)
+            .map_err(|e| PassManError::StorageError(format!("Failed to write emergency request: {}", e)))
+    }
+    
+    /// Delete the pending emergency access request
+    /// 
+    /// # Returns
+    /// True if there was a request
+    /// 
+    /// # Errors
+    /// Returns an error if the request file cannot be deleted
+    pub fn remove_emergency_request(&self) -> Result<bool> {
+        self.backend.delete(&self.emergency_request_blob())
+            .map_err(|e| PassManError::StorageError(format!("Failed to delete emergency request: {}", e)))
+    }
+    
+    fn emergency_request_blob(&self) -> String {
+        format!("{}.{}", self.vault_name, EMERGENCY_REQUEST_EXTENSION)
+    }
+    
+    /// Rewrite the search cache after a save, or remove it if it is turned off
+    /// 
+    /// Best effort: the vault file is already saved, and a cache that
+    /// couldn't be rewritten no longer matches it, so it isn't used.
+    fn update_search_cache(&self, vault: &Vault, crypto: &CryptoManager, prefix: &[u8], encrypted_data: &[u8]) {
+        if let Some(key) = crypto.key() {
+            let _ = self.write_search_cache(vault, key, &search_cache::file_digest(&[prefix, encrypted_data]));
+        }
+    }
+    
+    /// Rebuild the search cache from the vault file as it is on disk
+    /// 
+    /// The file is read once for both the accounts and the digest, so the
+    /// cache matches it even if another program saved in the meantime.
+    /// Removes the cache if the vault's setting is off.
+    /// 
+    /// # Arguments
+    /// * `key` - The vault key
+    /// 
+    /// # Errors
+    /// Returns an error if the vault can't be read or decrypted, or the
+    /// cache can't be written
+    pub fn rebuild_search_cache(&self, key: &SecureKey) -> Result<()> {
+        let file_data = self.read_vault_blob()?;
+        let (layout, encrypted_data) = format::parse_vault_file(&file_data)?;
+        let vault = self.decrypt(&layout, &encrypted_data, key)?;
+        self.write_search_cache(&vault, key, &search_cache::file_digest(&[&file_data]))
+    }
+    
+    /// Write the search cache for the vault file with the given digest
+    fn write_search_cache(&self, vault: &Vault, key: &SecureKey, vault_digest: &str) -> Result<()> {
+        let minutes = vault.metadata.settings.search_cache_minutes;
+        if minutes == 0 {
+            self.remove_search_cache()?;
+            return Ok(());
+        }
+        let data = search_cache::encode(vault, vault_digest, key, minutes)?;
+        self.backend.write(&self.search_cache_blob(), &data)
+            .map_err(|e| PassManError::StorageError(format!("Failed to write search cache: {}", e)))
+    }
+    
+    /// Read and decrypt the search cache, valid or not
+    /// 
+    /// # Arguments
+    /// * `key` - The vault key
+    /// 
+    /// # Returns
+    /// The cache, or None if there is no cache file
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or decrypted
+    pub fn read_search_cache(&self, key: &SecureKey) -> Result<Option<SearchCache>> {
+        match self.backend.read(&self.search_cache_blob())? {
+            Some(data) => search_cache::decode(&data, key).map(Some),
+            None => Ok(None),
+        }
+    }
+    
+    /// Delete the search cache
+    /// 
+    /// # Returns
+    /// True if there was a cache
+    /// 
+    /// # Errors
+    /// Returns an error if the cache file cannot be deleted
+    pub fn remove_search_cache(&self) -> Result<bool> {
+        self.backend.delete(&self.search_cache_blob())
+            .map_err(|e| PassManError::StorageError(format!("Failed to delete search cache: {}", e)))
+    }
+    
+    /// Digest of the vault file as it is stored (see `search_cache::file_digest`)
+    /// 
+    /// The index in a record vault's file holds the digests of its
+    /// records, so this covers them too.
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file can't be read
+    pub fn vault_digest(&self) -> Result<String> {
+        Ok(search_cache::file_digest(&[&self.read_vault_blob()?]))
+    }
+    
+    fn search_cache_blob(&self) -> String {
+        format!("{}.{}", self.vault_name, search_cache::CACHE_EXTENSION)
+    }
+    
+    /// Read the vault file as it is stored, still encrypted
+    /// 
+    /// A record vault's records are appended, so the file stands on its
+    /// own, e.g. as a backup or on another device.
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist or can't be read
+    pub fn read_vault_file_data(&self) -> Result<Vec<u8>> {
+        let mut file_data = self.read_vault_blob()?;
+        if format::read_header_bytes(&file_data)?.is_some_and(|header| header.records) {
+            for name in record_blobs(self.backend.as_ref(), &self.vault_name)? {
+                let record = self.backend.read(&name)?
+                    .ok_or_else(|| PassManError::StorageError(format!("{} was deleted while it was read", name)))?;
+                format::push_frame(&mut file_data, &record);
+            }
+        }
+        Ok(file_data)
+    }
+    
+    /// Read the vault file blob, without the records of a record vault
+    fn read_vault_blob(&self) -> Result<Vec<u8>> {
+        self.backend.read(&self.vault_blob())
+            .map_err(|e| PassManError::StorageError(format!("Failed to read vault file: {}", e)))?
+            .ok_or_else(|| PassManError::VaultNotFound(format!("Vault '{}' does not exist", self.vault_name)))
+    }
+    
+    /// Read the vault file and split it into salt and encrypted payload
+    fn read_vault_file(&self) -> Result<(VaultLayout, Vec<u8>)> {
+        format::parse_vault_file(&self.read_vault_blob()?)
+    }
+    
+    /// Load a backup with the key of the vault
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// * `key` - The vault key
+    /// 
+    /// # Returns
+    /// The vault as it was when the backup was made
+    /// 
+    /// # Errors
+    /// Returns an error if the backup doesn't exist or can't be decrypted
+    /// with this key
+    pub fn load_backup_with_key(&self, timestamp: &str, key: &SecureKey) -> Result<Vault> {
+        let (layout, encrypted_data) = format::parse_vault_file(&self.read_backup(timestamp)?)?;
+        self.decrypt(&layout, &encrypted_data, key)
+    }
+    
+    /// Load another copy of the vault file with the key of the vault
+    /// 
+    /// # Arguments
+    /// * `path` - The copy (a backup, or a sync tool's conflict copy)
+    /// * `key` - The vault key
+    /// 
+    /// # Returns
+    /// The vault stored in the copy
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or decrypted with this key
+    pub fn load_copy_with_key(&self, path: &Path, key: &SecureKey) -> Result<Vault> {
+        let (layout, encrypted_data) = read_vault_file(path)?;
+        self.decrypt(&layout, &encrypted_data, key)
+    }
+    
     /// Create a backup of the current vault
     /// 
+    /// # Arguments
+    /// * `signing_key` - Key to sign the backup with, if any
+    /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
     /// Returns an error if backup creation fails
-    pub fn create_backup(&self) -> Result<()> {
+    pub fn create_backup(&self, signing_key: Option<&SigningKey>) -> Result<()> {
         if !self.vault_exists() {
             return Ok(()); // Nothing to backup
         }
+        let data = self.read_vault_file_data()?;
         
-        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-        let backup_filename = format!("vault_backup_{}.vault", timestamp);
-        let backup_path = self.backup_dir.join(backup_filename);
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let backup_blob = self.backup_blob(&timestamp);
         
-        fs::copy(&self.vault_path, &backup_path)
+        self.backend.write(&backup_blob, &data)
             .map_err(|e| PassManError::StorageError(format!("Failed to create backup: {}", e)))?;
         
-        // Set secure permissions on backup
-        self.set_secure_permissions(&backup_path)?;
+        match signing_key {
+            Some(key) => {
+                let file_name = backup_blob.strip_prefix(BACKUPS).unwrap_or(&backup_blob);
+                let signature = signing::sign_contents(key, file_name, &data)?;
+                self.backend.write(&signature_blob(&backup_blob), signature.as_bytes())
+                    .map_err(|e| PassManError::StorageError(format!("Failed to write signature: {}", e)))?;
+            }
+            // Don't leave the signature of an older backup from the same second
+            None => {
+                let _ = self.backend.delete(&signature_blob(&backup_blob));
+            }
+        }
         
-        // Clean up old backups (keep only last 10)
         self.cleanup_old_backups()?;
         
         Ok(())
     }
     
+    /// List the backups of this vault
+    /// 
+    /// # Returns
+    /// The backups, newest first
+    /// 
+    /// # Errors
+    /// Returns an error if the backups can't be listed
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        let mut backups = Vec::new();
+        for name in self.backup_blobs()? {
+            let Some(timestamp) = backup_timestamp(&name) else {
+                continue;
+            };
+            let (Some(created_at), Some(size)) = (parse_backup_timestamp(timestamp), self.backend.size(&name)?) else {
+                continue;
+            };
+            backups.push(BackupInfo {
+                timestamp: timestamp.to_string(),
+                created_at,
+                size,
+                signed: self.backend.exists(&signature_blob(&name))?,
+            });
+        }
+        backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        
+        Ok(backups)
+    }
+    
+    /// Check the signature of a backup
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// * `public_key` - Public key the backup should be signed with, if any
+    /// 
+    /// # Returns
+    /// The signature status
+    /// 
+    /// # Errors
+    /// Returns an error if the backup doesn't exist or can't be read
+    pub fn check_backup_signature(&self, timestamp: &str, public_key: Option<&str>) -> Result<SignatureStatus> {
+        let data = self.read_backup(timestamp)?;
+        let signature = self.backend.read(&signature_blob(&self.backup_blob(timestamp)))?
+            .map(|signature| String::from_utf8_lossy(&signature).into_owned());
+        signing::check_contents(public_key, &data, signature.as_deref())
+    }
+    
+    /// List files left behind by interrupted or older operations
+    /// 
+    /// These are the temporary file of a save that didn't finish and
+    /// signatures whose backup has been deleted. Neither is ever read, so
+    /// they are safe to delete with `remove_stray_file`.
+    /// 
+    /// # Returns
+    /// Names of the stray files in the storage backend
+    /// 
+    /// # Errors
+    /// Returns an error if the backups can't be listed
+    pub fn stray_files(&self) -> Result<Vec<String>> {
+        let mut stray = Vec::new();
+        let temp_blob = format!("{}.tmp", self.vault_name);
+        if self.backend.exists(&temp_blob)? {
+            stray.push(temp_blob);
+        }
+        
+        for name in self.backend.list(&format!("{}{}", BACKUPS, BACKUP_PREFIX))? {
+            let Some(backup_blob) = name.strip_suffix(&format!(".{}", signing::SIGNATURE_EXTENSION)) else {
+                continue;
+            };
+            let ours = backup_vault_name(backup_blob.strip_prefix(BACKUPS).unwrap_or(backup_blob))
+                .is_some_and(|name| name == self.vault_name);
+            if ours && !self.backend.exists(backup_blob)? {
+                stray.push(name);
+            }
+        }
+        
+        Ok(stray)
+    }
+    
+    /// Delete a file listed by `stray_files`
+    /// 
+    /// # Errors
+    /// Returns an error if the file isn't stray or can't be deleted
+    pub fn remove_stray_file(&self, name: &str) -> Result<()> {
+        if !self.stray_files()?.iter().any(|stray| stray == name) {
+            return Err(PassManError::InvalidInput(format!("{} is not a stray file", name)));
+        }
+        self.backend.delete(name)?;
+        Ok(())
+    }
+    
+    /// Replace the vault file with one of its backups
+    /// 
+    /// The current vault file is backed up first, so a restore can itself
+    /// be undone.
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// * `signing_key` - Key to sign the backup of the current vault with
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the backup doesn't exist or file operations fail
+    pub fn restore_backup(&self, timestamp: &str, signing_key: Option<&SigningKey>) -> Result<()> {
+        let data = self.read_backup(timestamp)?;
+        
+        self.create_backup(signing_key)?;
+        self.write_vault_bundle(&data)
+    }
+    
+    /// Blob name of the vault file
+    fn vault_blob(&self) -> String {
+        vault_blob(&self.vault_name)
+    }
+    
+    /// Blob name of a backup
+    fn backup_blob(&self, timestamp: &str) -> String {
+        format!("{}{}{}_{}.vault", BACKUPS, BACKUP_PREFIX, timestamp, self.vault_name)
+    }
+    
+    /// Names of this vault's backups, oldest first
+    fn backup_blobs(&self) -> Result<Vec<String>> {
+        Ok(self.backend.list(&format!("{}{}", BACKUPS, BACKUP_PREFIX))?
+            .into_iter()
+            .filter(|name| backup_vault_name(&name[BACKUPS.len()..]) == Some(self.vault_name.as_str()))
+            .collect())
+    }
+    
+    /// Read a backup that must exist
+    fn read_backup(&self, timestamp: &str) -> Result<Vec<u8>> {
+        // Also rejects anything that could escape the backup directory
+        if parse_backup_timestamp(timestamp).is_none() {
+            return Err(PassManError::InvalidInput(format!("Invalid backup timestamp: {}", timestamp)));
+        }
+        self.backend.read(&self.backup_blob(timestamp))
+            .map_err(|e| PassManError::StorageError(format!("Failed to read backup: {}", e)))?
+            .ok_or_else(|| PassManError::StorageError(format!("No backup from {}", timestamp)))
+    }
+    
     /// Export vault to a file (for backup/transfer)
     /// 
     /// # Arguments
@@ -214,27 +807,27 @@ impl VaultStorage {
     /// # Errors
     /// Returns an error if export fails
     pub fn export_vault(&self, vault: &Vault, crypto: &CryptoManager, export_path: &Path) -> Result<()> {
-        // Serialize vault to JSON
-        let vault_json = serde_json::to_string_pretty(vault)
-            .map_err(|e| PassManError::SerializationError(e))?;
-        
-        // Encrypt the vault data
-        let encrypted_data = crypto.encrypt(vault_json.as_bytes())?;
-        
-        // Write to export file
-        let mut file = File::create(export_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to create export file: {}", e)))?;
-        
-        file.write_all(&encrypted_data)
-            .map_err(|e| PassManError::StorageError(format!("Failed to write export data: {}", e)))?;
-        
-        file.sync_all()
-            .map_err(|e| PassManError::StorageError(format!("Failed to sync export data: {}", e)))?;
-        
-        // Set secure permissions
-        self.set_secure_permissions(export_path)?;
-        
-        Ok(())
+        // Serialize and encrypt; the plaintext JSON is zeroized
+        let encrypted_data = format::encrypt_payload(vault, crypto)?;
+        write_export_file(export_path, &encrypted_data)
+    }
+    
+    /// Export vault to a portable file, encrypted with its own password
+    /// 
+    /// # Arguments
+    /// * `vault` - The vault to export
+    /// * `password` - The export password
+    /// * `kdf` - Key derivation parameters for the export password
+    /// * `export_path` - Path where to save the export
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if export fails
+    pub fn export_portable(&self, vault: &Vault, password: &str, kdf: KdfParams, export_path: &Path) -> Result<()> {
+        let file_data = format::encrypt_portable(vault, password, kdf)?;
+        write_export_file(export_path, &file_data)
     }
     
     /// Import vault from a file
@@ -249,99 +842,81 @@ impl VaultStorage {
     /// # Errors
     /// Returns an error if import fails
     pub fn import_vault(&self, crypto: &CryptoManager, import_path: &Path) -> Result<Vault> {
-        if !import_path.exists() {
-            return Err(PassManError::StorageError(format!("Import file not found: {}", import_path.display())));
-        }
+        let encrypted_data = read_import_file(import_path)?;
         
-        // Read encrypted data from import file
-        let mut file = File::open(import_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to open import file: {}", e)))?;
-        
-        let mut encrypted_data = Vec::new();
-        file.read_to_end(&mut encrypted_data)
-            .map_err(|e| PassManError::StorageError(format!("Failed to read import file: {}", e)))?;
-        
-        // Decrypt the vault data
-        let decrypted_data = crypto.decrypt(&encrypted_data)?;
+        // Decrypt the vault data; the plaintext JSON is zeroized
+        let decrypted_data = zeroize::Zeroizing::new(crypto.decrypt(&encrypted_data)?);
         
         // Deserialize vault from JSON
         let vault: Vault = serde_json::from_slice(&decrypted_data)
-            .map_err(|e| PassManError::SerializationError(e))?;
+            .map_err(PassManError::SerializationError)?;
         
         Ok(vault)
     }
     
+    /// Import vault from a portable export
+    /// 
+    /// # Arguments
+    /// * `password` - The export password
+    /// * `import_path` - Path to the export
+    /// 
+    /// # Returns
+    /// The exported vault
+    /// 
+    /// # Errors
+    /// Returns an authentication error if the password is wrong, or
+    /// another error if the file can't be read as a portable export
+    pub fn import_portable(&self, password: &str, import_path: &Path) -> Result<Vault> {
+        format::open_portable_bytes(&read_import_file(import_path)?, password)
+    }
+    
     /// Get the vault file path
-    pub fn vault_path(&self) -> &Path {
-        &self.vault_path
+    /// 
+    /// # Returns
+    /// The path, or None if the storage backend doesn't keep the vault in
+    /// a local file
+    pub fn vault_path(&self) -> Option<&Path> {
+        self.vault_path.as_deref()
     }
     
     /// Get vault file size in bytes
     /// 
     /// # Returns
-    /// File size in bytes, or 0 if file doesn't exist
+    /// File size in bytes, with the records of a record vault, or 0 if
+    /// file doesn't exist
     pub fn vault_size(&self) -> Result<u64> {
-        if !self.vault_exists() {
-            return Ok(0);
+        let mut size = 0;
+        for name in std::iter::once(self.vault_blob()).chain(record_blobs(self.backend.as_ref(), &self.vault_name)?) {
+            size += self.backend.size(&name)
+                .map_err(|e| PassManError::StorageError(format!("Failed to get vault metadata: {}", e)))?
+                .unwrap_or(0);
         }
-        
-        let metadata = fs::metadata(&self.vault_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to get vault metadata: {}", e)))?;
-        
-        Ok(metadata.len())
+        Ok(size)
     }
     
     /// Get vault file modification time
     /// 
     /// # Returns
-    /// Modification time, or None if file doesn't exist
+    /// Modification time, or None if file doesn't exist or the storage
+    /// backend doesn't record it
     pub fn vault_modified(&self) -> Result<Option<std::time::SystemTime>> {
-        if !self.vault_exists() {
-            return Ok(None);
-        }
-        
-        let metadata = fs::metadata(&self.vault_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to get vault metadata: {}", e)))?;
-        
-        Ok(metadata.modified().ok())
+        self.backend.modified(&self.vault_blob())
+            .map_err(|e| PassManError::StorageError(format!("Failed to get vault metadata: {}", e)))
     }
     
-    /// Set secure file permissions (owner read/write only)
-    fn set_secure_permissions(&self, path: &Path) -> Result<()> {
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(path)?.permissions();
-            perms.set_mode(0o600); // rw-------
-            fs::set_permissions(path, perms)?;
-        }
-        
-        // On Windows, we rely on the file system's default behavior
-        // and the fact that we're writing to a user-specific directory
-        
-        Ok(())
+    /// Get the storage backend
+    pub fn backend(&self) -> &dyn StorageBackend {
+        self.backend.as_ref()
     }
     
-    /// Clean up old backup files (keep only last 10)
+    /// Delete the oldest backups beyond the policy's `keep` count
     fn cleanup_old_backups(&self) -> Result<()> {
-        let mut backup_files: Vec<_> = fs::read_dir(&self.backup_dir)?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().extension().map_or(false, |ext| ext == "vault")
-            })
-            .collect();
-        
-        // Sort by modification time (newest first)
-        backup_files.sort_by_key(|entry| {
-            entry.metadata()
-                .and_then(|meta| meta.modified())
-                .unwrap_or(std::time::UNIX_EPOCH)
-        });
-        backup_files.reverse();
-        
-        // Remove old backups (keep only last 10)
-        for entry in backup_files.into_iter().skip(10) {
-            let _ = fs::remove_file(entry.path());
+        // Backup names sort by timestamp, oldest first
+        let backups = self.backup_blobs()?;
+        let excess = backups.len().saturating_sub(self.backups.keep);
+        for name in &backups[..excess] {
+            let _ = self.backend.delete(name);
+            let _ = self.backend.delete(&signature_blob(name));
         }
         
         Ok(())
@@ -352,26 +927,19 @@ impl VaultStorage {
     /// # Returns
     /// Vector of vault names
     pub fn list_vaults() -> Result<Vec<String>> {
-        let vault_dir = Self::get_vault_directory()?;
-        
-        if !vault_dir.exists() {
-            return Ok(Vec::new());
-        }
-        
-        let vault_files: Vec<String> = fs::read_dir(&vault_dir)?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().extension().map_or(false, |ext| ext == "vault")
-            })
-            .filter_map(|entry| {
-                entry.path()
-                    .file_stem()
-                    .and_then(|stem| stem.to_str())
-                    .map(|s| s.to_string())
-            })
-            .collect();
-        
-        Ok(vault_files)
+        Self::list_vaults_in(&*Self::configured_backend()?)
+    }
+    
+    /// List the vaults in a storage backend
+    /// 
+    /// # Returns
+    /// Vector of vault names, sorted
+    pub fn list_vaults_in(backend: &dyn StorageBackend) -> Result<Vec<String>> {
+        Ok(backend.list("")?
+            .into_iter()
+            .filter(|name| !name.contains('/'))
+            .filter_map(|name| name.strip_suffix(".vault").map(str::to_string))
+            .collect())
     }
     
     /// Delete a vault and all its backups
@@ -385,54 +953,247 @@ impl VaultStorage {
     /// # Errors
     /// Returns an error if deletion fails
     pub fn delete_vault(vault_name: &str) -> Result<()> {
-        let vault_dir = Self::get_vault_directory()?;
-        let vault_path = vault_dir.join(format!("{}.vault", vault_name));
-        let backup_dir = vault_dir.join("backups");
-        
+        Self::delete_vault_in(&*Self::configured_backend()?, vault_name)
+    }
+    
+    /// Delete a vault and all its backups from a storage backend
+    /// 
+    /// # Arguments
+    /// * `backend` - The storage backend holding the vault
+    /// * `vault_name` - Name of the vault to delete
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if deletion fails
+    pub fn delete_vault_in(backend: &dyn StorageBackend, vault_name: &str) -> Result<()> {
         // Delete main vault file
-        if vault_path.exists() {
-            fs::remove_file(&vault_path)
-                .map_err(|e| PassManError::StorageError(format!("Failed to delete vault: {}", e)))?;
+        backend.delete(&vault_blob(vault_name))
+            .map_err(|e| PassManError::StorageError(format!("Failed to delete vault: {}", e)))?;
+        let _ = backend.delete(&format!("{}.{}", vault_name, EMERGENCY_REQUEST_EXTENSION));
+        let _ = backend.delete(&format!("{}.{}", vault_name, search_cache::CACHE_EXTENSION));
+        for name in record_blobs(backend, vault_name)? {
+            let _ = backend.delete(&name);
         }
         
         // Delete all backups for this vault
-        if backup_dir.exists() {
-            let _backup_pattern = format!("vault_backup_*_{}.vault", vault_name);
-            for entry in fs::read_dir(&backup_dir)? {
-                let entry = entry?;
-                if let Some(filename) = entry.file_name().to_str() {
-                    if filename.ends_with(&format!("_{}.vault", vault_name)) {
-                        let _ = fs::remove_file(entry.path());
-                    }
-                }
+        for name in backend.list(&format!("{}{}", BACKUPS, BACKUP_PREFIX))? {
+            if backup_vault_name(&name[BACKUPS.len()..]) == Some(vault_name) {
+                let _ = backend.delete(&name);
+                let _ = backend.delete(&signature_blob(&name));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Rename a vault and its backups
+    /// 
+    /// # Arguments
+    /// * `old_name` - Current name of the vault
+    /// * `new_name` - New name for the vault
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist, the new name is taken
+    /// or invalid, or the files cannot be renamed
+    pub fn rename_vault(old_name: &str, new_name: &str) -> Result<()> {
+        Self::rename_vault_in(&*Self::configured_backend()?, old_name, new_name)
+    }
+    
+    /// Rename a vault and its backups in a storage backend
+    /// 
+    /// # Arguments
+    /// * `backend` - The storage backend holding the vault
+    /// * `old_name` - Current name of the vault
+    /// * `new_name` - New name for the vault
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist, the new name is taken
+    /// or invalid, or the files cannot be renamed
+    pub fn rename_vault_in(backend: &dyn StorageBackend, old_name: &str, new_name: &str) -> Result<()> {
+        validate_vault_name(new_name)?;
+        
+        if !backend.exists(&vault_blob(old_name))? {
+            return Err(PassManError::VaultNotFound(format!("Vault '{}' does not exist", old_name)));
+        }
+        if backend.exists(&vault_blob(new_name))? {
+            return Err(PassManError::VaultExists(format!("'{}'", new_name)));
+        }
+        
+        backend.rename(&vault_blob(old_name), &vault_blob(new_name))
+            .map_err(|e| PassManError::StorageError(format!("Failed to rename vault: {}", e)))?;
+        for extension in [EMERGENCY_REQUEST_EXTENSION, search_cache::CACHE_EXTENSION] {
+            let _ = backend.rename(&format!("{}.{}", old_name, extension), &format!("{}.{}", new_name, extension));
+        }
+        for name in record_blobs(backend, old_name)? {
+            let digest = &name[name.len() - 64..];
+            backend.rename(&name, &record_blob(new_name, digest))
+                .map_err(|e| PassManError::StorageError(format!("Failed to rename account record: {}", e)))?;
+        }
+        
+        // Backups follow the vault
+        for name in backend.list(&format!("{}{}", BACKUPS, BACKUP_PREFIX))? {
+            if backup_vault_name(&name[BACKUPS.len()..]) != Some(old_name) {
+                continue;
+            }
+            if let Some(timestamp) = backup_timestamp(&name) {
+                let renamed = format!("{}{}{}_{}.vault", BACKUPS, BACKUP_PREFIX, timestamp, new_name);
+                let _ = backend.rename(&signature_blob(&name), &signature_blob(&renamed));
+                let _ = backend.rename(&name, &renamed);
             }
         }
         
         Ok(())
     }
+    
+    /// Backend for the vault and backup directories of the config file
+    /// 
+    /// Doesn't create the directories, so listing vaults doesn't either.
+    fn configured_backend() -> Result<Box<dyn StorageBackend>> {
+        storage_backend::configured_backend(&PassManConfig::load()?, false)
+    }
+}
+
+/// Check that a vault name is usable as a file name
+/// 
+/// # Errors
+/// Returns an error if the name is empty, has path separators, or starts with a dot
+pub fn validate_vault_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(PassManError::InvalidInput("Vault name cannot be empty".to_string()));
+    }
+    if name.starts_with('.') || name.contains(['/', '\\', ':']) {
+        return Err(PassManError::InvalidInput(format!("Invalid vault name: '{}'", name)));
+    }
+    Ok(())
+}
+
+/// Blob name of the detached signature of a blob
+fn signature_blob(name: &str) -> String {
+    format!("{}.{}", name, signing::SIGNATURE_EXTENSION)
+}
+
+/// Timestamp in the name of a backup blob
+fn backup_timestamp(name: &str) -> Option<&str> {
+    name.strip_prefix(BACKUPS)?.strip_prefix(BACKUP_PREFIX)?.get(..BACKUP_TIMESTAMP_LEN)
+}
+
+/// Read a vault file and split it into salt and encrypted payload
+fn read_vault_file(path: &Path) -> Result<(VaultLayout, Vec<u8>)> {
+    let mut file = File::open(path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to open vault file: {}", e)))?;
+    
+    let mut file_data = Vec::new();
+    file.read_to_end(&mut file_data)
+        .map_err(|e| PassManError::StorageError(format!("Failed to read vault file: {}", e)))?;
+    
+    format::parse_vault_file(&file_data)
+}
+
+/// Write an export file readable only by the current user
+fn write_export_file(export_path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = File::create(export_path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to create export file: {}", e)))?;
+    
+    file.write_all(data)
+        .map_err(|e| PassManError::StorageError(format!("Failed to write export data: {}", e)))?;
+    
+    file.sync_all()
+        .map_err(|e| PassManError::StorageError(format!("Failed to sync export data: {}", e)))?;
+    
+    // Set secure permissions
+    storage_backend::set_secure_permissions(export_path)
+}
+
+/// Read an export file
+fn read_import_file(import_path: &Path) -> Result<Vec<u8>> {
+    if !import_path.exists() {
+        return Err(PassManError::StorageError(format!("Import file not found: {}", import_path.display())));
+    }
+    
+    let mut file = File::open(import_path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to open import file: {}", e)))?;
+    
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|e| PassManError::StorageError(format!("Failed to read import file: {}", e)))?;
+    Ok(data)
+}
+
+/// Parse the `YYYYmmdd_HHMMSS` timestamp of a backup filename
+fn parse_backup_timestamp(timestamp: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if timestamp.len() != BACKUP_TIMESTAMP_LEN {
+        return None;
+    }
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// Extract the vault name from a backup filename
+/// 
+/// Backups are named `vault_backup_<YYYYmmdd_HHMMSS>_<vault>.vault`.
+fn backup_vault_name(filename: &str) -> Option<&str> {
+    let rest = filename.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".vault")?;
+    rest.get(BACKUP_TIMESTAMP_LEN..)?.strip_prefix('_')
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::TempDir;
-    use crate::crypto::CryptoManager;
+    use crate::crypto::{CryptoManager, KdfParams};
     use crate::models::{Vault, Account, AccountType};
     
+    /// Unique vault name so parallel tests don't share a vault file
+    fn test_vault_name() -> String {
+        crate::testing::isolate();
+        format!("test_vault_{}", uuid::Uuid::new_v4().simple())
+    }
+    
     #[test]
     fn test_vault_storage_creation() {
-        let temp_dir = TempDir::new().unwrap();
-        let vault_storage = VaultStorage::new("test_vault").unwrap();
+        let vault_storage = VaultStorage::new(&test_vault_name()).unwrap();
         assert!(!vault_storage.vault_exists());
     }
     
+    #[test]
+    fn test_configured_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PassManConfig {
+            vault_dir: Some(dir.path().join("vaults")),
+            backups: BackupPolicy { automatic: false, ..BackupPolicy::default() },
+            ..PassManConfig::default()
+        };
+        let mut crypto = CryptoManager::new();
+        crypto.generate_key_and_salt("test_password").unwrap();
+        
+        let storage = VaultStorage::with_config("main", &config).unwrap();
+        assert_eq!(storage.vault_path(), Some(dir.path().join("vaults").join("main.vault").as_path()));
+        let vault = Vault::new("test@example.com".to_string());
+        storage.save_vault(&vault, &crypto).unwrap();
+        storage.save_vault(&vault, &crypto).unwrap();
+        assert!(storage.list_backups().unwrap().is_empty());
+        
+        storage.create_backup(None).unwrap();
+        assert_eq!(storage.list_backups().unwrap().len(), 1);
+        assert!(dir.path().join("vaults").join("backups").is_dir());
+    }
+    
     #[test]
     fn test_vault_save_and_load() {
         let mut crypto = CryptoManager::new();
         let password = "test_password";
-        let (_, salt) = crypto.generate_key_and_salt(password).unwrap();
+        crypto.generate_key_and_salt(password).unwrap();
         
-        let vault_storage = VaultStorage::new("test_vault").unwrap();
+        let vault_name = test_vault_name();
+        let vault_storage = VaultStorage::new(&vault_name).unwrap();
         let mut vault = Vault::new("test@example.com".to_string());
         
         let account = Account::new(
@@ -445,8 +1206,142 @@ mod tests {
         vault_storage.save_vault(&vault, &crypto).unwrap();
         assert!(vault_storage.vault_exists());
         
-        let loaded_vault = vault_storage.load_vault(&crypto).unwrap();
+        let loaded_vault = vault_storage.load_vault(password).unwrap();
         assert_eq!(vault.metadata.email, loaded_vault.metadata.email);
         assert_eq!(vault.accounts.len(), loaded_vault.accounts.len());
+        
+        VaultStorage::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_backup_vault_name() {
+        assert_eq!(backup_vault_name("vault_backup_20240101_120000_main.vault"), Some("main"));
+        assert_eq!(backup_vault_name("vault_backup_20240101_120000_my_vault.vault"), Some("my_vault"));
+        assert_eq!(backup_vault_name("vault_backup_20240101_120000.vault"), None);
+        assert_eq!(backup_vault_name("main.vault"), None);
+    }
+    
+    #[test]
+    fn test_list_and_restore_backups() {
+        let mut crypto = CryptoManager::new();
+        crypto.generate_key_and_salt("test_password").unwrap();
+        
+        let vault_name = test_vault_name();
+        let vault_storage = VaultStorage::new(&vault_name).unwrap();
+        let original = Vault::new("test@example.com".to_string());
+        vault_storage.save_vault(&original, &crypto).unwrap();
+        assert!(vault_storage.list_backups().unwrap().is_empty());
+        
+        let mut changed = original.clone();
+        changed.add_account(Account::new("Mail".to_string(), AccountType::Email, "pw".to_string()));
+        vault_storage.save_vault(&changed, &crypto).unwrap();
+        
+        let backups = vault_storage.list_backups().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert!(!backups[0].signed);
+        assert!(vault_storage.restore_backup("../../etc/passwd", None).is_err());
+        assert!(vault_storage.restore_backup("19990101_000000", None).is_err());
+        vault_storage.restore_backup(&backups[0].timestamp, None).unwrap();
+        assert_eq!(vault_storage.load_vault("test_password").unwrap().accounts.len(), 0);
+        
+        VaultStorage::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_rename_vault() {
+        let mut crypto = CryptoManager::new();
+        crypto.generate_key_and_salt("test_password").unwrap();
+        
+        let old_name = test_vault_name();
+        let new_name = test_vault_name();
+        let vault_storage = VaultStorage::new(&old_name).unwrap();
+        vault_storage.save_vault(&Vault::new("test@example.com".to_string()), &crypto).unwrap();
+        
+        assert!(VaultStorage::rename_vault(&old_name, "../escape").is_err());
+        VaultStorage::rename_vault(&old_name, &new_name).unwrap();
+        assert!(!vault_storage.vault_exists());
+        assert!(VaultStorage::new(&new_name).unwrap().load_vault("test_password").is_ok());
+        
+        VaultStorage::delete_vault(&new_name).unwrap();
+    }
+    
+    #[test]
+    fn test_versioned_vault_roundtrip() {
+        let vault_name = test_vault_name();
+        let vault_storage = VaultStorage::new(&vault_name).unwrap();
+        let vault = Vault::new("test@example.com".to_string());
+        
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(KdfParams::default(), false);
+        let wrapping_key = header.password_key("test_password", None).unwrap();
+        header.set_slot(KeySlotKind::Password, &vault_key, &wrapping_key).unwrap();
+        
+        let mut crypto = CryptoManager::new();
+        crypto.set_key(vault_key.clone(), header.salt().unwrap());
+        vault_storage.save_vault_with_header(&vault, &header, &crypto).unwrap();
+        
+        // Plain saves keep the header
+        vault_storage.save_vault(&vault, &crypto).unwrap();
+        assert_eq!(vault_storage.read_header().unwrap(), Some(header));
+        
+        let key = vault_storage.unlock_key("test_password", None).unwrap();
+        assert_eq!(key.as_bytes(), vault_key.as_bytes());
+        assert!(matches!(
+            vault_storage.unlock_key("wrong_password", None),
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        assert_eq!(vault_storage.load_vault("test_password").unwrap().metadata.email, "test@example.com");
+        
+        VaultStorage::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_record_vault() {
+        let backend = MemoryBackend::new();
+        let storage = VaultStorage::with_backend("main", backend.clone(), BackupPolicy::default());
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(KdfParams::default(), false);
+        header.records = true;
+        let mut crypto = CryptoManager::new();
+        crypto.set_key(vault_key.clone(), header.salt().unwrap());
+        
+        let mut vault = Vault::new("test@example.com".to_string());
+        for name in ["Mail", "Bank", "Shop"] {
+            vault.add_account(Account::new(name.to_string(), AccountType::Other, "pw".to_string()));
+        }
+        storage.save_vault_with_header(&vault, &header, &crypto).unwrap();
+        let records = record_blobs(&backend, "main").unwrap();
+        assert_eq!(records.len(), 3);
+        
+        // A save only replaces the record of the account that changed
+        let id = *vault.accounts.keys().next().unwrap();
+        vault.get_account_mut(&id).unwrap().password = "changed".to_string();
+        storage.save_vault(&vault, &crypto).unwrap();
+        let changed = record_blobs(&backend, "main").unwrap();
+        assert_eq!(changed.len(), 3);
+        assert_eq!(changed.iter().filter(|name| records.contains(name)).count(), 2);
+        
+        // Another storage manager reads the records back
+        let reopened = VaultStorage::with_backend("main", backend.clone(), BackupPolicy::default());
+        assert_eq!(reopened.load_vault_with_key(&vault_key).unwrap().accounts, vault.accounts);
+        
+        // The vault file carries its records to another backend, and to backups
+        let copy = VaultStorage::with_backend("main", MemoryBackend::new(), BackupPolicy::default());
+        copy.install_vault_file(&storage.read_vault_file_data().unwrap()).unwrap();
+        assert_eq!(copy.load_vault_with_key(&vault_key).unwrap().accounts, vault.accounts);
+        let timestamp = storage.list_backups().unwrap()[0].timestamp.clone();
+        assert_eq!(storage.load_backup_with_key(&timestamp, &vault_key).unwrap().accounts.len(), 3);
+        
+        // Records that are missing or don't match the index are refused
+        backend.write(&changed[0], b"swapped").unwrap();
+        assert!(matches!(reopened.load_vault_with_key(&vault_key), Err(PassManError::VaultCorrupted(_))));
+        backend.delete(&changed[0]).unwrap();
+        assert!(matches!(reopened.load_vault_with_key(&vault_key), Err(PassManError::VaultCorrupted(_))));
+        
+        // Going back to a single file removes the records
+        header.records = false;
+        storage.save_vault_with_header(&vault, &header, &crypto).unwrap();
+        assert!(record_blobs(&backend, "main").unwrap().is_empty());
+        assert_eq!(storage.load_vault_with_key(&vault_key).unwrap().accounts, vault.accounts);
     }
 }
@@ -0,0 +1,712 @@
+//! # Storage Backends
+//! 
+//! `VaultStorage` keeps everything it stores (the vault file, its
+//! backups and their signatures, the search cache, emergency access
+//! requests) as named blobs in a `StorageBackend`. `FileBackend` keeps
+//! them in the configured vault and backup directories; `SqliteBackend`
+//! (with the `sqlite` feature) keeps them in rows of one database in the
+//! vault directory; `MemoryBackend` keeps them in memory, for tests and
+//! for front-ends that bring their own persistence. Without the `storage` feature (e.g. on wasm32) only
+//! the trait and `MemoryBackend` are built; a browser front-end fills a
+//! `MemoryBackend` from IndexedDB or a file picker and reads it back with
+//! `viewer::VaultViewer`.
+//! 
+//! Blob names are file names: `<vault>.vault`, `<vault>.cache`, and so
+//! on. Backups live under the `backups/` prefix (see `BACKUPS`). Vaults
+//! that keep accounts in records (see `format`) have a
+//! `<vault>.record-<digest>` blob per account.
+
+use crate::Result;
+#[cfg(feature = "storage")]
+use crate::{PassManError, config::{PassManConfig, StorageEngine}};
+use std::collections::BTreeMap;
+#[cfg(feature = "storage")]
+use std::fs::{self, File};
+#[cfg(feature = "storage")]
+use std::io::Write;
+#[cfg(feature = "storage")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Prefix of the names of backup blobs
+pub const BACKUPS: &str = "backups/";
+
+/// File name of the `SqliteBackend` database in the vault directory
+#[cfg(feature = "sqlite")]
+pub const SQLITE_DATABASE: &str = "vaults.db";
+
+/// Blob name of a vault file
+pub fn vault_blob(vault_name: &str) -> String {
+    format!("{}.vault", vault_name)
+}
+
+/// Blob name of an account record, by the digest of its contents
+pub fn record_blob(vault_name: &str, digest: &str) -> String {
+    format!("{}{}", record_prefix(vault_name), digest)
+}
+
+/// Names of a vault's record blobs, sorted
+/// 
+/// # Errors
+/// Returns an error if the blobs can't be listed
+pub fn record_blobs(backend: &dyn StorageBackend, vault_name: &str) -> Result<Vec<String>> {
+    let prefix = record_prefix(vault_name);
+    Ok(backend.list(&prefix)?
+        .into_iter()
+        // Not the files of a vault named like one of our records
+        .filter(|name| {
+            let digest = &name[prefix.len()..];
+            digest.len() == 64 && digest.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        })
+        .collect())
+}
+
+fn record_prefix(vault_name: &str) -> String {
+    format!("{}.record-", vault_name)
+}
+
+/// Where vault files and their backups are kept
+/// 
+/// Only `read`, `write`, `list`, and `delete` have to be implemented; the
+/// other methods are built on them, and backends with cheaper ways to
+/// answer can override them.
+pub trait StorageBackend: Send + Sync {
+    /// Read a blob
+    /// 
+    /// # Returns
+    /// The blob, or None if there is none with this name
+    /// 
+    /// # Errors
+    /// Returns an error if the blob exists but can't be read
+    fn read(&self, name: &str) -> Result<Option<Vec<u8>>>;
+    
+    /// Create or replace a blob
+    /// 
+    /// Replacing must be atomic: a reader sees the old or the new blob,
+    /// never part of one.
+    /// 
+    /// # Errors
+    /// Returns an error if the blob can't be written
+    fn write(&self, name: &str, data: &[u8]) -> Result<()>;
+    
+    /// List blobs by name
+    /// 
+    /// # Arguments
+    /// * `prefix` - Start of the names to list (empty for all)
+    /// 
+    /// # Returns
+    /// Names of the blobs starting with `prefix`, sorted
+    /// 
+    /// # Errors
+    /// Returns an error if the blobs can't be listed
+    fn list(&self, prefix: &str) -> Result<Vec<String>>;
+    
+    /// Delete a blob
+    /// 
+    /// # Returns
+    /// True if there was a blob with this name
+    /// 
+    /// # Errors
+    /// Returns an error if the blob exists but can't be deleted
+    fn delete(&self, name: &str) -> Result<bool>;
+    
+    /// Whether a blob exists
+    fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.read(name)?.is_some())
+    }
+    
+    /// Size of a blob in bytes, or None if it doesn't exist
+    fn size(&self, name: &str) -> Result<Option<u64>> {
+        Ok(self.read(name)?.map(|data| data.len() as u64))
+    }
+    
+    /// When a blob was last written, if the backend knows
+    fn modified(&self, _name: &str) -> Result<Option<SystemTime>> {
+        Ok(None)
+    }
+    
+    /// Rename a blob, replacing any blob with the new name
+    /// 
+    /// # Returns
+    /// True if there was a blob with the old name
+    fn rename(&self, from: &str, to: &str) -> Result<bool> {
+        let Some(data) = self.read(from)? else {
+            return Ok(false);
+        };
+        self.write(to, &data)?;
+        self.delete(from)
+    }
+    
+    /// Path of a blob on the local filesystem, for backends that keep
+    /// blobs in files
+    fn local_path(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// The backend `config.storage` asks for
+/// 
+/// # Arguments
+/// * `config` - The library configuration
+/// * `create` - Create the vault and backup directories now
+/// 
+/// # Errors
+/// Returns an error if a directory can't be determined or created, or
+/// SQLite storage is configured without the `sqlite` feature
+#[cfg(feature = "storage")]
+pub fn configured_backend(config: &PassManConfig, create: bool) -> Result<Box<dyn StorageBackend>> {
+    match config.storage {
+        StorageEngine::Files if create => Ok(Box::new(FileBackend::from_config(config)?)),
+        StorageEngine::Files => Ok(Box::new(FileBackend::new(config.vault_dir()?, config.backup_dir()?))),
+        #[cfg(feature = "sqlite")]
+        StorageEngine::Sqlite => {
+            if create {
+                FileBackend::from_config(config)?;
+            }
+            Ok(Box::new(SqliteBackend::new(config.vault_dir()?.join(SQLITE_DATABASE), config.backup_dir()?)))
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StorageEngine::Sqlite => Err(PassManError::InvalidInput(
+            "SQLite storage needs PassMan built with the sqlite feature".to_string()
+        )),
+    }
+}
+
+/// Blobs as files in a vault directory and a backup directory
+/// 
+/// Files are written atomically (to a `.tmp` file beside them, then
+/// renamed) and readable by their owner only.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    /// Directory holding vault files
+    vault_dir: PathBuf,
+    
+    /// Directory holding the `backups/` blobs
+    backup_dir: PathBuf,
+}
+
+#[cfg(feature = "storage")]
+impl FileBackend {
+    /// Create a file backend for two directories
+    /// 
+    /// The directories are created when the first blob is written.
+    /// 
+    /// # Arguments
+    /// * `vault_dir` - Directory for vault files
+    /// * `backup_dir` - Directory for backups
+    pub fn new(vault_dir: impl Into<PathBuf>, backup_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            vault_dir: vault_dir.into(),
+            backup_dir: backup_dir.into(),
+        }
+    }
+    
+    /// Create a file backend for the configured directories, creating them
+    /// 
+    /// # Errors
+    /// Returns an error if a directory can't be determined or created
+    pub fn from_config(config: &PassManConfig) -> Result<Self> {
+        let backend = Self::new(config.vault_dir()?, config.backup_dir()?);
+        fs::create_dir_all(&backend.vault_dir)
+            .map_err(|e| PassManError::StorageError(format!("Failed to create vault directory: {}", e)))?;
+        fs::create_dir_all(&backend.backup_dir)
+            .map_err(|e| PassManError::StorageError(format!("Failed to create backup directory: {}", e)))?;
+        Ok(backend)
+    }
+    
+    /// Path of the file holding a blob
+    /// 
+    /// # Errors
+    /// Returns an error for names that would leave the two directories
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let (dir, file_name) = match name.strip_prefix(BACKUPS) {
+            Some(file_name) => (&self.backup_dir, file_name),
+            None => (&self.vault_dir, name),
+        };
+        if file_name.is_empty() || file_name.starts_with('.') || file_name.contains(['/', '\\']) {
+            return Err(PassManError::InvalidInput(format!("Invalid storage name: '{}'", name)));
+        }
+        Ok(dir.join(file_name))
+    }
+    
+    /// Names of the files in a directory that start with a prefix
+    fn list_dir(dir: &Path, name_prefix: &str, file_prefix: &str) -> Result<Vec<String>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|file_name| file_name.starts_with(file_prefix))
+            .map(|file_name| format!("{}{}", name_prefix, file_name))
+            .collect())
+    }
+}
+
+#[cfg(feature = "storage")]
+impl StorageBackend for FileBackend {
+    fn read(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(name)?) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(PassManError::StorageError(format!("Failed to read {}: {}", name, e))),
+        }
+    }
+    
+    fn write(&self, name: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| PassManError::StorageError(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        
+        // Write to a temporary file first, then move it into place
+        let temp_path = path.with_extension("tmp");
+        {
+            let mut file = File::create(&temp_path)
+                .map_err(|e| PassManError::StorageError(format!("Failed to create temp file: {}", e)))?;
+            file.write_all(data)
+                .map_err(|e| PassManError::StorageError(format!("Failed to write {}: {}", name, e)))?;
+            file.sync_all()
+                .map_err(|e| PassManError::StorageError(format!("Failed to sync {}: {}", name, e)))?;
+        }
+        fs::rename(&temp_path, &path)
+            .map_err(|e| PassManError::StorageError(format!("Failed to move {} into place: {}", name, e)))?;
+        
+        set_secure_permissions(&path)
+    }
+    
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut names = match prefix.strip_prefix(BACKUPS) {
+            Some(file_prefix) => Self::list_dir(&self.backup_dir, BACKUPS, file_prefix)?,
+            None => {
+                let mut names = Self::list_dir(&self.vault_dir, "", prefix)?;
+                if BACKUPS.starts_with(prefix) {
+                    names.extend(Self::list_dir(&self.backup_dir, BACKUPS, "")?);
+                }
+                names
+            }
+        };
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+    
+    fn delete(&self, name: &str) -> Result<bool> {
+        match fs::remove_file(self.path(name)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(PassManError::StorageError(format!("Failed to delete {}: {}", name, e))),
+        }
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.path(name)?.is_file())
+    }
+    
+    fn size(&self, name: &str) -> Result<Option<u64>> {
+        match fs::metadata(self.path(name)?) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(PassManError::StorageError(format!("Failed to get metadata of {}: {}", name, e))),
+        }
+    }
+    
+    fn modified(&self, name: &str) -> Result<Option<SystemTime>> {
+        match fs::metadata(self.path(name)?) {
+            Ok(metadata) => Ok(metadata.modified().ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(PassManError::StorageError(format!("Failed to get metadata of {}: {}", name, e))),
+        }
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<bool> {
+        match fs::rename(self.path(from)?, self.path(to)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(PassManError::StorageError(format!("Failed to rename {}: {}", from, e))),
+        }
+    }
+    
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        self.path(name).ok()
+    }
+}
+
+/// Set secure file permissions (owner read/write only)
+#[cfg(feature = "storage")]
+pub(crate) fn set_secure_permissions(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path)?.permissions();
+        perms.set_mode(0o600); // rw-------
+        fs::set_permissions(path, perms)?;
+    }
+    
+    // On Windows, we rely on the file system's default behavior
+    // and the fact that we're writing to a user-specific directory
+    
+    Ok(())
+}
+
+/// Blobs as rows of a SQLite database, with backups as files
+/// 
+/// All vaults in the vault directory share one database with a row per
+/// blob. A record vault (see `format`) keeps each encrypted account in a
+/// row of its own, so a save replaces only the rows of accounts that
+/// changed. Backups are files in the backup directory, as with
+/// `FileBackend`, so a damaged database doesn't take them along.
+/// 
+/// The database is created with the first write and is readable by its
+/// owner only. Other processes may use it at the same time.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteBackend {
+    /// The database file
+    path: PathBuf,
+    
+    /// Connection to the database, opened on first use
+    connection: Mutex<Option<rusqlite::Connection>>,
+    
+    /// Holds the `backups/` blobs
+    backups: FileBackend,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteBackend {
+    /// Create a SQLite backend for a database file and a backup directory
+    /// 
+    /// Nothing is created until the first blob is written.
+    /// 
+    /// # Arguments
+    /// * `path` - The database file
+    /// * `backup_dir` - Directory for backups
+    pub fn new(path: impl Into<PathBuf>, backup_dir: impl Into<PathBuf>) -> Self {
+        let backup_dir = backup_dir.into();
+        Self {
+            path: path.into(),
+            connection: Mutex::default(),
+            // Only `backups/` names are handed to it, so its vault directory is never used
+            backups: FileBackend::new(backup_dir.clone(), backup_dir),
+        }
+    }
+    
+    /// Run statements on the database
+    /// 
+    /// # Arguments
+    /// * `create` - Create the database if it doesn't exist yet
+    /// * `statements` - What to run
+    /// 
+    /// # Returns
+    /// What the statements returned, or None if there is no database and
+    /// `create` is false
+    fn run<T>(&self, create: bool, statements: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T>) -> Result<Option<T>> {
+        // The database rolls back statements cut short by a panic, so the connection stays usable
+        let mut connection = self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if connection.is_none() {
+            if !create && !self.path.exists() {
+                return Ok(None);
+            }
+            *connection = Some(self.open()?);
+        }
+        let connection = connection.as_ref().expect("connection was opened above");
+        statements(connection)
+            .map(Some)
+            .map_err(|e| PassManError::StorageError(format!("Vault database {}: {}", self.path.display(), e)))
+    }
+    
+    /// Open the database, creating it and its table if needed
+    fn open(&self) -> Result<rusqlite::Connection> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| PassManError::StorageError(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        let open_error = |e: rusqlite::Error| PassManError::StorageError(format!("Failed to open {}: {}", self.path.display(), e));
+        let connection = rusqlite::Connection::open(&self.path).map_err(open_error)?;
+        set_secure_permissions(&self.path)?;
+        
+        // Wait for other processes' writes instead of failing at once
+        connection.busy_timeout(std::time::Duration::from_secs(5)).map_err(open_error)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = FULL;
+             CREATE TABLE IF NOT EXISTS blobs (
+                 name TEXT PRIMARY KEY NOT NULL,
+                 data BLOB NOT NULL,
+                 modified INTEGER NOT NULL
+             ) WITHOUT ROWID;",
+        ).map_err(open_error)?;
+        Ok(connection)
+    }
+    
+    /// Copy a blob between the database and the backup directory
+    fn copy_across(&self, from: &str, to: &str) -> Result<bool> {
+        let Some(data) = self.read(from)? else {
+            return Ok(false);
+        };
+        self.write(to, &data)?;
+        self.delete(from)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StorageBackend for SqliteBackend {
+    fn read(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.read(name);
+        }
+        use rusqlite::OptionalExtension;
+        Ok(self.run(false, |connection| {
+            connection.query_row("SELECT data FROM blobs WHERE name = ?1", [name], |row| row.get(0)).optional()
+        })?.flatten())
+    }
+    
+    fn write(&self, name: &str, data: &[u8]) -> Result<()> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.write(name, data);
+        }
+        let modified = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |since| since.as_millis() as i64);
+        self.run(true, |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO blobs (name, data, modified) VALUES (?1, ?2, ?3)",
+                rusqlite::params![name, data, modified],
+            )
+        })?;
+        Ok(())
+    }
+    
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        if prefix.starts_with(BACKUPS) {
+            return self.backups.list(prefix);
+        }
+        let mut names = self.run(false, |connection| {
+            connection
+                .prepare("SELECT name FROM blobs WHERE substr(name, 1, length(?1)) = ?1")?
+                .query_map([prefix], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })?.unwrap_or_default();
+        if BACKUPS.starts_with(prefix) {
+            names.extend(self.backups.list(BACKUPS)?);
+        }
+        names.sort();
+        Ok(names)
+    }
+    
+    fn delete(&self, name: &str) -> Result<bool> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.delete(name);
+        }
+        let deleted = self.run(false, |connection| connection.execute("DELETE FROM blobs WHERE name = ?1", [name]))?;
+        Ok(deleted.is_some_and(|rows| rows > 0))
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.size(name)?.is_some())
+    }
+    
+    fn size(&self, name: &str) -> Result<Option<u64>> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.size(name);
+        }
+        use rusqlite::OptionalExtension;
+        let size: Option<i64> = self.run(false, |connection| {
+            connection.query_row("SELECT length(data) FROM blobs WHERE name = ?1", [name], |row| row.get(0)).optional()
+        })?.flatten();
+        Ok(size.map(|size| size as u64))
+    }
+    
+    fn modified(&self, name: &str) -> Result<Option<SystemTime>> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.modified(name);
+        }
+        use rusqlite::OptionalExtension;
+        let modified: Option<i64> = self.run(false, |connection| {
+            connection.query_row("SELECT modified FROM blobs WHERE name = ?1", [name], |row| row.get(0)).optional()
+        })?.flatten();
+        Ok(modified.map(|millis| SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(millis as u64)))
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<bool> {
+        match (from.starts_with(BACKUPS), to.starts_with(BACKUPS)) {
+            (true, true) => return self.backups.rename(from, to),
+            (false, false) => {}
+            _ => return self.copy_across(from, to),
+        }
+        if from == to {
+            return self.exists(from);
+        }
+        let renamed = self.run(false, |connection| {
+            let transaction = connection.unchecked_transaction()?;
+            transaction.execute("DELETE FROM blobs WHERE name = ?2 AND EXISTS (SELECT 1 FROM blobs WHERE name = ?1)", [from, to])?;
+            let renamed = transaction.execute("UPDATE blobs SET name = ?2 WHERE name = ?1", [from, to])?;
+            transaction.commit()?;
+            Ok(renamed > 0)
+        })?;
+        Ok(renamed.unwrap_or(false))
+    }
+    
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        if name.starts_with(BACKUPS) {
+            return self.backups.local_path(name);
+        }
+        None
+    }
+}
+
+/// Blobs by name, with when they were written
+type Blobs = BTreeMap<String, (Vec<u8>, SystemTime)>;
+
+/// Blobs in memory
+/// 
+/// Clones share their blobs, so a test can open the same vault twice.
+/// Everything is lost when the last clone is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    blobs: Arc<Mutex<Blobs>>,
+}
+
+impl MemoryBackend {
+    /// Create an empty in-memory backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    fn blobs(&self) -> std::sync::MutexGuard<'_, Blobs> {
+        // The map is consistent after every statement, so a panic elsewhere can't corrupt it
+        self.blobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn read(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.blobs().get(name).map(|(data, _)| data.clone()))
+    }
+    
+    fn write(&self, name: &str, data: &[u8]) -> Result<()> {
+        // SystemTime::now panics on wasm32; chrono asks the browser instead
+        self.blobs().insert(name.to_string(), (data.to_vec(), chrono::Utc::now().into()));
+        Ok(())
+    }
+    
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self.blobs().keys().filter(|name| name.starts_with(prefix)).cloned().collect())
+    }
+    
+    fn delete(&self, name: &str) -> Result<bool> {
+        Ok(self.blobs().remove(name).is_some())
+    }
+    
+    fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.blobs().contains_key(name))
+    }
+    
+    fn size(&self, name: &str) -> Result<Option<u64>> {
+        Ok(self.blobs().get(name).map(|(data, _)| data.len() as u64))
+    }
+    
+    fn modified(&self, name: &str) -> Result<Option<SystemTime>> {
+        Ok(self.blobs().get(name).map(|(_, modified)| *modified))
+    }
+    
+    fn rename(&self, from: &str, to: &str) -> Result<bool> {
+        let mut blobs = self.blobs();
+        let Some(blob) = blobs.remove(from) else {
+            return Ok(false);
+        };
+        blobs.insert(to.to_string(), blob);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn check_backend(backend: &dyn StorageBackend) {
+        assert_eq!(backend.read("main.vault").unwrap(), None);
+        assert!(!backend.exists("main.vault").unwrap());
+        
+        backend.write("main.vault", b"first").unwrap();
+        backend.write("main.vault", b"second").unwrap();
+        backend.write("work.vault", b"work").unwrap();
+        backend.write("backups/vault_backup_20240101_120000_main.vault", b"first").unwrap();
+        assert_eq!(backend.read("main.vault").unwrap().as_deref(), Some(b"second".as_slice()));
+        assert_eq!(backend.size("main.vault").unwrap(), Some(6));
+        assert!(backend.modified("main.vault").unwrap().is_some());
+        
+        assert_eq!(backend.list("").unwrap(), [
+            "backups/vault_backup_20240101_120000_main.vault",
+            "main.vault",
+            "work.vault",
+        ]);
+        assert_eq!(backend.list("backups/").unwrap(), ["backups/vault_backup_20240101_120000_main.vault"]);
+        assert_eq!(backend.list("w").unwrap(), ["work.vault"]);
+        
+        assert!(backend.rename("work.vault", "home.vault").unwrap());
+        assert!(!backend.rename("work.vault", "home.vault").unwrap());
+        assert_eq!(backend.read("home.vault").unwrap().as_deref(), Some(b"work".as_slice()));
+        
+        assert!(backend.delete("home.vault").unwrap());
+        assert!(!backend.delete("home.vault").unwrap());
+        assert_eq!(backend.list("").unwrap().len(), 2);
+    }
+    
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_file_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = FileBackend::new(dir.path().join("vaults"), dir.path().join("vaults").join("backups"));
+        check_backend(&backend);
+        
+        assert_eq!(backend.local_path("main.vault"), Some(dir.path().join("vaults").join("main.vault")));
+        assert!(dir.path().join("vaults").join("backups").join("vault_backup_20240101_120000_main.vault").is_file());
+        assert!(!dir.path().join("vaults").join("main.tmp").exists());
+        for name in ["../escape", "backups/../escape", ".hidden", "backups/"] {
+            assert!(backend.read(name).is_err(), "{}", name);
+        }
+    }
+    
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vaults").join(SQLITE_DATABASE);
+        let backend = SqliteBackend::new(&path, dir.path().join("backups"));
+        assert!(backend.list("").unwrap().is_empty());
+        assert!(!path.exists());
+        check_backend(&backend);
+        
+        assert_eq!(backend.local_path("main.vault"), None);
+        assert!(dir.path().join("backups").join("vault_backup_20240101_120000_main.vault").is_file());
+        assert!(!dir.path().join("vaults").join("main.vault").exists());
+        
+        // Blobs move between the database and the backup directory
+        assert!(backend.rename("main.vault", "backups/main.vault").unwrap());
+        assert!(!backend.exists("main.vault").unwrap());
+        assert!(backend.rename("backups/main.vault", "main.vault").unwrap());
+        assert!(backend.rename("main.vault", "main.vault").unwrap());
+        
+        let reopened = SqliteBackend::new(&path, dir.path().join("backups"));
+        assert_eq!(reopened.read("main.vault").unwrap().as_deref(), Some(b"second".as_slice()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+    
+    #[test]
+    fn test_memory_backend() {
+        let backend = MemoryBackend::new();
+        check_backend(&backend);
+        
+        let clone = backend.clone();
+        assert_eq!(clone.read("main.vault").unwrap().as_deref(), Some(b"second".as_slice()));
+        assert_eq!(clone.local_path("main.vault"), None);
+    }
+}
@@ -0,0 +1,637 @@
+//! # Password Strength Estimation
+//! 
+//! A zxcvbn-style estimator. Rather than rewarding length and character
+//! classes, it looks for the patterns attackers try first:
+//! 
+//! - **common passwords** and **dictionary words**, also capitalized,
+//!   reversed, or with l33t substitutions (`p@ssw0rd`)
+//! - **dates** and recent **years** (`1990`, `12/05/87`)
+//! - **sequences** (`abcd`, `9753`)
+//! - **repeats** (`aaaa`, `abcabc`)
+//! - **keyboard walks** (`qwerty`, `1qaz2wsx`, `zxcvbn`)
+//! 
+//! Each match gets an estimated number of guesses, and the password's
+//! guesses are those of the cheapest way to cover it with matches, brute
+//! forcing whatever no pattern explains. The estimate becomes a 0-100 score
+//! and a crack time against an offline attack on a slow hash.
+
+use serde::Serialize;
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Guesses per second assumed for crack times (offline attack, slow hash)
+pub const GUESSES_PER_SECOND: f64 = 1e4;
+
+/// Only the start of longer passwords is analyzed, to bound the work
+const MAX_ANALYZED_CHARS: usize = 100;
+
+/// Guesses per character no pattern explains
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// Fewest guesses credited to a match that covers only part of the password
+const MIN_SUBMATCH_GUESSES: f64 = 50.0;
+
+/// Fewest years an attacker is assumed to try around the current one
+const MIN_YEAR_SPACE: i32 = 20;
+
+/// Shortest dictionary, sequence, and keyboard matches
+const MIN_MATCH_LEN: usize = 3;
+
+/// Keyboard rows, unshifted and shifted, each offset half a key from the one above
+const KEYBOARD_ROWS: [(&str, &str); 4] = [
+    ("1234567890-=", "!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// Keys a keyboard walk can start on (each key, shifted or not)
+const KEYBOARD_STARTING_POSITIONS: f64 = 94.0;
+
+/// Average number of neighbors of a key
+const KEYBOARD_AVERAGE_DEGREE: f64 = 4.6;
+
+/// Common l33t substitutions
+const L33T: [(char, char); 18] = [
+    ('4', 'a'), ('@', 'a'), ('8', 'b'), ('(', 'c'), ('{', 'c'), ('3', 'e'),
+    ('6', 'g'), ('9', 'g'), ('1', 'i'), ('!', 'i'), ('|', 'i'), ('0', 'o'),
+    ('$', 's'), ('5', 's'), ('7', 't'), ('+', 't'), ('%', 'x'), ('2', 'z'),
+];
+
+/// Characters that stand for either `i` or `l`
+const L33T_I_OR_L: &str = "1|";
+
+/// Frequently used passwords, most common first
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456", "password", "123456789", "12345678", "12345", "qwerty",
+    "1234567", "111111", "1234567890", "123123", "abc123", "1234",
+    "password1", "iloveyou", "1q2w3e4r", "000000", "qwerty123", "zaq12wsx",
+    "dragon", "sunshine", "princess", "letmein", "654321", "monkey",
+    "1qaz2wsx", "123321", "qwertyuiop", "superman", "asdfghjkl", "trustno1",
+    "football", "baseball", "welcome", "admin", "master", "hello",
+    "freedom", "whatever", "qazwsx", "shadow", "michael", "jennifer",
+    "computer", "mustang", "starwars", "login", "passw0rd", "pokemon",
+    "charlie", "donald", "jordan", "harley", "ranger", "hunter",
+    "buster", "soccer", "hockey", "killer", "george", "andrew",
+    "thomas", "robert", "daniel", "pepper", "ginger", "summer",
+    "flower", "cookie", "cheese", "chelsea", "liverpool", "arsenal",
+    "secret", "access", "batman", "666666", "121212", "696969",
+    "112233", "7777777", "987654321", "changeme", "password123", "admin123",
+    "welcome1", "qwerty1", "test", "guest", "root", "default",
+    "love", "angel", "family", "pass", "matrix", "maggie",
+    "tigger", "jessica", "ashley", "bailey", "nicole", "abc",
+];
+
+/// A weakness found in a password
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// A frequently used password
+    CommonPassword,
+    /// A dictionary word, possibly capitalized, reversed, or in l33t
+    DictionaryWord,
+    /// A date or recent year
+    Date,
+    /// A sequence such as `abc` or `7531`
+    Sequence,
+    /// Repeated characters or substrings
+    Repeat,
+    /// Adjacent keys such as `qwerty` or `1qaz`
+    KeyboardWalk,
+}
+
+impl Pattern {
+    /// Advice for a password containing this pattern
+    pub fn warning(&self) -> &'static str {
+        match self {
+            Pattern::CommonPassword => "Contains a very common password",
+            Pattern::DictionaryWord => "Contains a dictionary word; substitutions like '@' for 'a' don't help much",
+            Pattern::Date => "Contains a date or year, which are easy to guess",
+            Pattern::Sequence => "Contains a sequence like 'abc' or '6543'",
+            Pattern::Repeat => "Contains repeats like 'aaa' or 'abcabc'",
+            Pattern::KeyboardWalk => "Contains a run of adjacent keyboard keys",
+        }
+    }
+}
+
+/// How hard a password is to guess
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StrengthEstimate {
+    /// Strength score from 0 (weak) to 100 (very strong)
+    pub score: u8,
+    
+    /// Base-10 logarithm of the estimated guesses needed
+    pub guesses_log10: f64,
+    
+    /// Estimated seconds to crack at `GUESSES_PER_SECOND`
+    pub crack_time_seconds: f64,
+    
+    /// The crack time for display, e.g. "3 hours" or "centuries"
+    pub crack_time: String,
+    
+    /// Weaknesses found, in the order they appear in the password
+    pub patterns: Vec<Pattern>,
+}
+
+/// A part of the password explained by a pattern
+struct Match {
+    start: usize,
+    end: usize,
+    guesses_log10: f64,
+    pattern: Pattern,
+}
+
+/// Estimate how hard a password is to guess
+/// 
+/// # Arguments
+/// * `password` - The password to analyze
+/// 
+/// # Returns
+/// The score, estimated guesses and crack time, and weaknesses found
+pub fn estimate(password: &str) -> StrengthEstimate {
+    let chars: Vec<char> = password.chars().collect();
+    let analyzed = &chars[..chars.len().min(MAX_ANALYZED_CHARS)];
+    let (guesses_log10, patterns) = cheapest_cover(analyzed);
+    let guesses_log10 = guesses_log10 + (chars.len() - analyzed.len()) as f64 * BRUTEFORCE_CARDINALITY.log10();
+    
+    let crack_time_seconds = 10f64.powf(guesses_log10) / GUESSES_PER_SECOND;
+    StrengthEstimate {
+        score: score(guesses_log10),
+        guesses_log10,
+        crack_time_seconds,
+        crack_time: display_time(crack_time_seconds),
+        patterns,
+    }
+}
+
+/// Whether a password is one of the most common passwords (case-insensitive)
+pub fn is_common_password(password: &str) -> bool {
+    COMMON_PASSWORDS.contains(&password.to_lowercase().as_str())
+}
+
+/// Guesses for the cheapest way to cover the password with matches and
+/// brute force, and the patterns used
+fn cheapest_cover(chars: &[char]) -> (f64, Vec<Pattern>) {
+    let matches = find_matches(chars);
+    let min_submatch = MIN_SUBMATCH_GUESSES.log10();
+    let bruteforce = BRUTEFORCE_CARDINALITY.log10();
+    
+    // best[i]: cheapest cover of the first i characters, and the match ending it
+    let mut best: Vec<(f64, Option<usize>)> = vec![(0.0, None); chars.len() + 1];
+    for end in 1..=chars.len() {
+        best[end] = (best[end - 1].0 + bruteforce, None);
+        for (index, m) in matches.iter().enumerate().filter(|(_, m)| m.end == end) {
+            let whole = m.start == 0 && m.end == chars.len();
+            let guesses = if whole { m.guesses_log10 } else { m.guesses_log10.max(min_submatch) };
+            let total = best[m.start].0 + guesses;
+            if total < best[end].0 {
+                best[end] = (total, Some(index));
+            }
+        }
+    }
+    
+    let mut patterns = Vec::new();
+    let mut end = chars.len();
+    while end > 0 {
+        match best[end].1 {
+            Some(index) => {
+                let m = &matches[index];
+                if !patterns.contains(&m.pattern) {
+                    patterns.push(m.pattern);
+                }
+                end = m.start;
+            }
+            None => end -= 1,
+        }
+    }
+    patterns.reverse();
+    
+    (best[chars.len()].0, patterns)
+}
+
+fn find_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &mut matches);
+    date_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    repeat_matches(chars, &mut matches);
+    keyboard_matches(chars, &mut matches);
+    matches
+}
+
+/// Ranked dictionary words with the pattern they count as
+fn dictionary() -> &'static HashMap<String, (usize, Pattern)> {
+    static DICTIONARY: OnceLock<HashMap<String, (usize, Pattern)>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        let mut dictionary = HashMap::new();
+        for (rank, password) in COMMON_PASSWORDS.iter().enumerate() {
+            dictionary.insert(password.to_string(), (rank + 1, Pattern::CommonPassword));
+        }
+        
+        // The word list isn't ordered by frequency, so every word ranks alike
+        #[cfg(feature = "wordlists")]
+        {
+            let words = crate::generator::default_word_list();
+            let rank = words.len();
+            for word in words {
+                dictionary.entry(word.to_lowercase()).or_insert((rank, Pattern::DictionaryWord));
+            }
+        }
+        dictionary
+    })
+}
+
+fn dictionary_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let dictionary = dictionary();
+    let max_len = dictionary.keys().map(|word| word.chars().count()).max().unwrap_or(0);
+    
+    for start in 0..chars.len() {
+        for end in (start + MIN_MATCH_LEN)..=chars.len().min(start + max_len) {
+            let token = &chars[start..end];
+            let lower: String = token.iter().flat_map(|c| c.to_lowercase()).collect();
+            let case = case_variations(token).log10();
+            let mut add = |(rank, pattern): (usize, Pattern), extra: f64| matches.push(Match {
+                start,
+                end,
+                guesses_log10: (rank as f64).log10() + case + extra,
+                pattern,
+            });
+            
+            if let Some(&entry) = dictionary.get(&lower) {
+                add(entry, 0.0);
+            }
+            let reversed: String = lower.chars().rev().collect();
+            if let Some(&entry) = dictionary.get(&reversed) {
+                add(entry, 2f64.log10());
+            }
+            for alternate in [false, true] {
+                if let Some((word, substitutions)) = unl33t(&lower, alternate) {
+                    if let Some(&entry) = dictionary.get(&word) {
+                        add(entry, substitutions as f64 * 2f64.log10());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Undo l33t substitutions, reading `1` and `|` as `l` if `alternate`
+/// 
+/// Returns the word and number of substitutions, or None if there were none
+fn unl33t(token: &str, alternate: bool) -> Option<(String, usize)> {
+    let mut substitutions = 0;
+    let word: String = token.chars()
+        .map(|c| {
+            let letter = if alternate && L33T_I_OR_L.contains(c) {
+                Some('l')
+            } else {
+                L33T.iter().find(|(from, _)| *from == c).map(|(_, to)| *to)
+            };
+            if letter.is_some() {
+                substitutions += 1;
+            }
+            letter.unwrap_or(c)
+        })
+        .collect();
+    (substitutions > 0).then_some((word, substitutions))
+}
+
+/// Ways to capitalize a word that an attacker would try
+fn case_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    
+    // All caps, or only the first or last letter capitalized
+    let first_only = token[0].is_uppercase() && upper == 1;
+    let last_only = token[token.len() - 1].is_uppercase() && upper == 1;
+    if lower == 0 || first_only || last_only {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|i| binomial(upper + lower, i)).sum()
+}
+
+fn date_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let current_year = current_year();
+    for start in 0..chars.len() {
+        for end in (start + 4)..=chars.len().min(start + 10) {
+            let token: String = chars[start..end].iter().collect();
+            let year = if token.len() == 4 {
+                token.parse().ok().filter(|year| (1900..=current_year + 20).contains(year))
+            } else {
+                parse_date(&token)
+            };
+            if let Some(year) = year {
+                let years = (year - current_year).abs().max(MIN_YEAR_SPACE) as f64;
+                let mut guesses = years;
+                if token.len() > 4 {
+                    guesses *= 365.0;
+                    if !token.chars().all(|c| c.is_ascii_digit()) {
+                        guesses *= 4.0;
+                    }
+                }
+                matches.push(Match { start, end, guesses_log10: guesses.log10(), pattern: Pattern::Date });
+            }
+        }
+    }
+}
+
+/// The year of a day-month-year, month-day-year, or year-month-day date,
+/// written without separators or with one kind of separator
+fn parse_date(token: &str) -> Option<i32> {
+    let separator = token.chars().find(|c| !c.is_ascii_digit());
+    let parts: Vec<&str> = match separator {
+        Some(separator) if "/-. _\\".contains(separator) => token.split(separator).collect(),
+        Some(_) => return None,
+        None => match token.len() {
+            6 => vec![&token[..2], &token[2..4], &token[4..]],
+            8 if token.starts_with("19") || token.starts_with("20") => vec![&token[..4], &token[4..6], &token[6..]],
+            8 => vec![&token[..2], &token[2..4], &token[4..]],
+            _ => return None,
+        },
+    };
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty() || part.len() > 4) {
+        return None;
+    }
+    let numbers: Vec<i32> = parts.iter().map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    
+    let valid = |day: i32, month: i32| (1..=31).contains(&day) && (1..=12).contains(&month);
+    let year = |part: &str, value: i32| match part.len() {
+        2 if value < 50 => Some(2000 + value),
+        2 => Some(1900 + value),
+        4 if (1000..=2099).contains(&value) => Some(value),
+        _ => None,
+    };
+    let (first, second, third) = (numbers[0], numbers[1], numbers[2]);
+    if parts[0].len() == 4 && valid(third, second) {
+        return year(parts[0], first);
+    }
+    if parts[0].len() <= 2 && parts[1].len() <= 2 && (valid(first, second) || valid(second, first)) {
+        return year(parts[2], third);
+    }
+    None
+}
+
+fn current_year() -> i32 {
+    use chrono::Datelike;
+    chrono::Utc::now().year()
+}
+
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let class = |c: char| if c.is_ascii_lowercase() { 1 } else if c.is_ascii_uppercase() { 2 } else if c.is_ascii_digit() { 3 } else { 0 };
+    let mut start = 0;
+    while start + MIN_MATCH_LEN <= chars.len() {
+        let delta = chars[start + 1] as i32 - chars[start] as i32;
+        let mut end = start + 1;
+        while end < chars.len()
+            && chars[end] as i32 - chars[end - 1] as i32 == delta
+            && class(chars[end]) == class(chars[start])
+        {
+            end += 1;
+        }
+        
+        if end - start >= MIN_MATCH_LEN && class(chars[start]) != 0 && (1..=5).contains(&delta.abs()) {
+            let first = chars[start];
+            let mut base: f64 = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if delta < 0 {
+                base *= 2.0;
+            }
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: (base * (end - start) as f64).log10(),
+                pattern: Pattern::Sequence,
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+    for start in 0..chars.len() {
+        for base_len in 1..=(chars.len() - start) / 2 {
+            let base = &chars[start..start + base_len];
+            let count = chars[start..].chunks_exact(base_len)
+                .take_while(|chunk| *chunk == base)
+                .count();
+            if count < 2 || (base_len == 1 && count < MIN_MATCH_LEN) {
+                continue;
+            }
+            
+            let base_guesses = if base_len == 1 {
+                character_class_size(base[0]).log10()
+            } else {
+                cheapest_cover(base).0
+            };
+            matches.push(Match {
+                start,
+                end: start + base_len * count,
+                guesses_log10: base_guesses + (count as f64).log10(),
+                pattern: Pattern::Repeat,
+            });
+        }
+    }
+}
+
+fn character_class_size(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_alphabetic() {
+        26.0
+    } else {
+        33.0
+    }
+}
+
+/// Key positions: row, column in half keys, and whether shift is held
+fn keyboard() -> &'static HashMap<char, (i32, i32, bool)> {
+    static KEYBOARD: OnceLock<HashMap<char, (i32, i32, bool)>> = OnceLock::new();
+    KEYBOARD.get_or_init(|| {
+        let mut keyboard = HashMap::new();
+        for (row, (unshifted, shifted)) in KEYBOARD_ROWS.iter().enumerate() {
+            let row = row as i32;
+            for (column, (key, shifted_key)) in unshifted.chars().zip(shifted.chars()).enumerate() {
+                let x = 2 * column as i32 + row;
+                keyboard.insert(key, (row, x, false));
+                keyboard.insert(shifted_key, (row, x, true));
+            }
+        }
+        keyboard
+    })
+}
+
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let keyboard = keyboard();
+    
+    // Direction from one key to a neighboring key, if they are neighbors
+    let step = |from: char, to: char| {
+        let (&(row, x, _), &(next_row, next_x, _)) = (keyboard.get(&from)?, keyboard.get(&to)?);
+        let (rows, columns) = (next_row - row, next_x - x);
+        let adjacent = (rows == 0 && columns.abs() == 2) || (rows.abs() == 1 && columns.abs() == 1);
+        adjacent.then_some((rows, columns))
+    };
+    
+    let mut start = 0;
+    while start + MIN_MATCH_LEN <= chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut direction = None;
+        while end < chars.len() {
+            let Some(next) = step(chars[end - 1], chars[end]) else { break };
+            if direction != Some(next) {
+                turns += 1;
+                direction = Some(next);
+            }
+            end += 1;
+        }
+        
+        if end - start >= MIN_MATCH_LEN {
+            let shifted = chars[start..end].iter().filter(|c| keyboard[c].2).count();
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: keyboard_guesses(end - start, turns, shifted).log10(),
+                pattern: Pattern::KeyboardWalk,
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+/// Walks of this length with at most this many turns, times the ways to
+/// shift some of the keys
+fn keyboard_guesses(length: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        for j in 1..=turns.min(i - 1) {
+            guesses += binomial(i - 1, j - 1) * KEYBOARD_STARTING_POSITIONS * KEYBOARD_AVERAGE_DEGREE.powi(j as i32);
+        }
+    }
+    
+    let unshifted = length - shifted;
+    if shifted == 0 {
+        guesses
+    } else if unshifted == 0 {
+        guesses * 2.0
+    } else {
+        guesses * (1..=shifted.min(unshifted)).map(|i| binomial(length, i)).sum::<f64>()
+    }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |product, i| product * (n + 1 - i) as f64 / i as f64)
+}
+
+/// Score bands: guesses (log10) at which each description's range ends
+const SCORE_BANDS: [(f64, f64); 7] = [
+    (0.0, 0.0),
+    (3.0, 20.0),
+    (6.0, 40.0),
+    (8.0, 60.0),
+    (10.0, 80.0),
+    (12.0, 90.0),
+    (16.0, 100.0),
+];
+
+/// Map guesses onto 0-100, so that `get_strength_description` reads
+/// "Strong" from 10^10 guesses up
+fn score(guesses_log10: f64) -> u8 {
+    for window in SCORE_BANDS.windows(2) {
+        let ((low, low_score), (high, high_score)) = (window[0], window[1]);
+        if guesses_log10 <= high {
+            let fraction = (guesses_log10 - low).max(0.0) / (high - low);
+            return (low_score + fraction * (high_score - low_score)).ceil() as u8;
+        }
+    }
+    100
+}
+
+/// A crack time in words, e.g. "less than a second" or "3 hours"
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = DAY * 365.0;
+    const CENTURY: f64 = YEAR * 100.0;
+    
+    let (amount, unit) = match seconds {
+        s if s < 1.0 => return "less than a second".to_string(),
+        s if s < MINUTE => (s, "second"),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s if s < CENTURY => (s / YEAR, "year"),
+        _ => return "centuries".to_string(),
+    };
+    let amount = amount.round() as u64;
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_common_passwords() {
+        let estimate = estimate("password");
+        assert_eq!(estimate.patterns, [Pattern::CommonPassword]);
+        assert!(estimate.score <= 20);
+        assert_eq!(estimate.crack_time, "less than a second");
+        
+        // Capitalized and l33t variants are barely stronger
+        assert!(super::estimate("P@ssw0rd").score <= 20);
+        assert!(super::estimate("drowssap").patterns.contains(&Pattern::CommonPassword));
+        assert!(is_common_password("QWERTY") && !is_common_password("qwerty-not"));
+    }
+    
+    #[test]
+    fn test_patterns() {
+        assert_eq!(estimate("abcdefgh").patterns, [Pattern::Sequence]);
+        assert_eq!(estimate("zzzzzzzz").patterns, [Pattern::Repeat]);
+        assert_eq!(estimate("qazxswedc").patterns, [Pattern::KeyboardWalk]);
+        assert_eq!(estimate("xjq19870312").patterns, [Pattern::Date]);
+        assert_eq!(estimate("06/12/1987").patterns, [Pattern::Date]);
+        assert!(estimate("kx7vbqm2024").patterns.contains(&Pattern::Date));
+        
+        for weak in ["abcdefgh", "zzzzzzzz", "qwertyuiop", "asdfgh123456"] {
+            assert!(estimate(weak).score <= 40, "{} scored {}", weak, estimate(weak).score);
+        }
+    }
+    
+    #[test]
+    fn test_random_passwords() {
+        // Brute force: ten guesses per character
+        let estimate = estimate("k#8Tz!qW");
+        assert!(estimate.patterns.is_empty());
+        assert!((estimate.guesses_log10 - 8.0).abs() < 1e-9);
+        assert_eq!(estimate.crack_time, "3 hours");
+        
+        assert!(super::estimate("r7$Kp2!vXm9@Lq4w").score > 90);
+        assert_eq!(super::estimate(&"k#8Tz!qW".repeat(20)).crack_time, "centuries");
+        assert_eq!(super::estimate("").score, 0);
+    }
+    
+    #[test]
+    fn test_score_bands() {
+        assert_eq!(score(0.0), 0);
+        assert_eq!(score(3.0), 20);
+        assert_eq!(score(10.0), 80);
+        assert!(score(10.1) > 80);
+        assert_eq!(score(40.0), 100);
+        assert_eq!(display_time(1.0), "1 second");
+        assert_eq!(display_time(7200.0), "2 hours");
+    }
+}
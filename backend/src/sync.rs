@@ -0,0 +1,355 @@
+//! # Sync Merge
+//! 
+//! When a vault file is synced between devices (Syncthing, Dropbox, a
+//! network share), two devices can change the vault before either sees
+//! the other's copy. `merge` combines the two copies without one side's
+//! version of an account replacing the other's wholesale.
+//! 
+//! Every account field is a last-writer-wins register: `Account::field_times`
+//! records when each field last changed, and the merge keeps, field by
+//! field, the value written last. Two devices editing different fields of
+//! the same account therefore both keep their edits. Deleted accounts
+//! leave a tombstone (`Vault::deleted`), so a deletion isn't undone by the
+//! other copy, unless that copy edited the account after the deletion.
+//! 
+//! Accounts last saved before field timestamps existed fall back to their
+//! `updated_at` for every field, i.e. the newer version of the whole
+//! account wins.
+//! 
+//! Field timestamps are always recorded; merging itself needs the `sync`
+//! feature.
+
+use crate::models::Account;
+#[cfg(feature = "sync")]
+use crate::models::Vault;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "sync")]
+use serde::{Deserialize, Serialize};
+
+/// Account fields merged independently, as keys of `Account::field_times`
+pub const FIELDS: [&str; 22] = [
+    "name",
+    "type",
+    "url",
+    "username",
+    "password",
+    "notes",
+    "tags",
+    "folder",
+    "favorite",
+    "color",
+    "icon",
+    "archived",
+    "canary",
+    "expires_at",
+    "rotation_interval_days",
+    "totp",
+    "custom_fields",
+    "autotype",
+    "ssh_key",
+    "card",
+    "identity",
+    "site_policy",
+];
+
+/// What merging another copy changed
+#[cfg(feature = "sync")]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Accounts only the other copy had
+    pub added: usize,
+    
+    /// Accounts the other copy deleted
+    pub deleted: usize,
+    
+    /// Accounts that took some fields from the other copy
+    pub updated: usize,
+}
+
+#[cfg(feature = "sync")]
+impl MergeSummary {
+    /// Check whether the merge changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.added + self.deleted + self.updated == 0
+    }
+}
+
+/// Merge another copy of a vault into this one
+/// 
+/// Vault-wide data (settings, signing key) is taken from the copy modified
+/// last; tags and the generation history are combined.
+/// 
+/// # Arguments
+/// * `ours` - This copy
+/// * `theirs` - The other copy
+/// 
+/// # Returns
+/// The merged vault, and what it took from `theirs`
+#[cfg(feature = "sync")]
+pub fn merge(ours: &Vault, theirs: &Vault) -> (Vault, MergeSummary) {
+    let mut merged = ours.clone();
+    let mut summary = MergeSummary::default();
+    
+    for (id, deleted_at) in &theirs.deleted {
+        let entry = merged.deleted.entry(*id).or_insert(*deleted_at);
+        *entry = (*entry).max(*deleted_at);
+    }
+    
+    for account in theirs.accounts.values() {
+        match merged.get_account(&account.id) {
+            None => {
+                let deleted_here = merged.deleted.get(&account.id)
+                    .is_some_and(|deleted_at| *deleted_at >= last_change(account));
+                if !deleted_here {
+                    merged.add_account(account.clone());
+                    summary.added += 1;
+                }
+            }
+            Some(current) => {
+                let combined = merge_account(current, account);
+                if combined != *current {
+                    // Not `update_account`: the merged fields keep their own times
+                    merged.add_account(combined);
+                    summary.updated += 1;
+                }
+            }
+        }
+    }
+    
+    let deletions: Vec<_> = merged.deleted.iter().map(|(id, deleted_at)| (*id, *deleted_at)).collect();
+    for (id, deleted_at) in deletions {
+        let Some(account) = merged.get_account(&id) else {
+            continue;
+        };
+        if deleted_at >= last_change(account) {
+            merged.remove_account(&id);
+            merged.deleted.insert(id, deleted_at);
+            summary.deleted += 1;
+        } else {
+            // Edited after the deletion: the edit wins
+            merged.deleted.remove(&id);
+        }
+    }
+    
+    // Either copy's trash, minus accounts that are back in the vault
+    for (id, trashed) in &theirs.trash {
+        if !merged.accounts.contains_key(id) {
+            merged.trash.entry(*id).or_insert_with(|| trashed.clone());
+        }
+    }
+    merged.trash.retain(|id, _| !merged.accounts.contains_key(id));
+    
+    for tag in &theirs.tags {
+        if !merged.tags.contains(tag) {
+            merged.tags.push(tag.clone());
+        }
+    }
+    for category in &theirs.categories {
+        if merged.category(category).is_none() {
+            merged.categories.push(category.clone());
+        }
+    }
+    let history_len = ours.generated_passwords.len().max(theirs.generated_passwords.len());
+    for generated in &theirs.generated_passwords {
+        if !merged.generated_passwords.contains(generated) {
+            merged.generated_passwords.push(generated.clone());
+        }
+    }
+    merged.generated_passwords.sort_by_key(|generated| std::cmp::Reverse(generated.generated_at));
+    merged.generated_passwords.truncate(history_len);
+    
+    if theirs.metadata.last_modified > ours.metadata.last_modified {
+        merged.metadata.settings = theirs.metadata.settings.clone();
+        merged.signing_key = theirs.signing_key.clone();
+    }
+    merged.metadata.last_modified = ours.metadata.last_modified.max(theirs.metadata.last_modified);
+    
+    (merged, summary)
+}
+
+/// Merge two versions of an account field by field
+/// 
+/// # Arguments
+/// * `ours` - This version
+/// * `theirs` - The other version; wins fields it changed later
+/// 
+/// # Returns
+/// The merged account
+#[cfg(feature = "sync")]
+pub fn merge_account(ours: &Account, theirs: &Account) -> Account {
+    let mut merged = ours.clone();
+    for field in FIELDS {
+        let their_time = field_time(theirs, field);
+        if their_time > field_time(ours, field) {
+            copy_field(&mut merged, theirs, field);
+            merged.field_times.insert(field.to_string(), their_time);
+        }
+    }
+    merged.created_at = ours.created_at.min(theirs.created_at);
+    merged.updated_at = ours.updated_at.max(theirs.updated_at);
+    merged.last_accessed = ours.last_accessed.max(theirs.last_accessed);
+    merged.access_count = ours.access_count.max(theirs.access_count);
+    merged
+}
+
+/// Record the time of every field that differs between two versions
+/// 
+/// # Arguments
+/// * `before` - The account before the change
+/// * `after` - The changed account, whose `field_times` are updated
+/// * `now` - Time of the change
+pub fn stamp_changes(before: &Account, after: &mut Account, now: DateTime<Utc>) {
+    // Fields an older version never stamped changed at its `updated_at` at the latest
+    if after.field_times.is_empty() {
+        after.field_times = FIELDS.iter()
+            .map(|field| (field.to_string(), field_time(before, field)))
+            .collect();
+    }
+    for field in FIELDS {
+        if !same_field(before, after, field) {
+            after.field_times.insert(field.to_string(), now);
+        }
+    }
+}
+
+/// When a field of an account last changed
+pub fn field_time(account: &Account, field: &str) -> DateTime<Utc> {
+    account.field_times.get(field).copied().unwrap_or(account.updated_at)
+}
+
+/// When any field of an account last changed
+#[cfg(feature = "sync")]
+fn last_change(account: &Account) -> DateTime<Utc> {
+    account.field_times.values().copied().fold(account.updated_at, DateTime::max)
+}
+
+fn same_field(a: &Account, b: &Account, field: &str) -> bool {
+    match field {
+        "name" => a.name == b.name,
+        "type" => a.account_type == b.account_type,
+        "url" => a.url == b.url,
+        "username" => a.username == b.username,
+        "password" => a.password == b.password,
+        "notes" => a.notes == b.notes,
+        "tags" => a.tags == b.tags,
+        "folder" => a.folder == b.folder,
+        "favorite" => a.is_favorite == b.is_favorite,
+        "color" => a.color == b.color,
+        "icon" => a.icon == b.icon,
+        "archived" => a.archived == b.archived,
+        "canary" => a.canary == b.canary,
+        "expires_at" => a.expires_at == b.expires_at,
+        "rotation_interval_days" => a.rotation_interval_days == b.rotation_interval_days,
+        "totp" => a.totp == b.totp,
+        "custom_fields" => a.custom_fields == b.custom_fields,
+        "autotype" => a.autotype == b.autotype,
+        "ssh_key" => a.ssh_key == b.ssh_key,
+        "card" => a.card == b.card,
+        "identity" => a.identity == b.identity,
+        "site_policy" => a.site_policy == b.site_policy,
+        _ => true,
+    }
+}
+
+#[cfg(feature = "sync")]
+fn copy_field(target: &mut Account, source: &Account, field: &str) {
+    match field {
+        "name" => target.name = source.name.clone(),
+        "type" => target.account_type = source.account_type.clone(),
+        "url" => target.url = source.url.clone(),
+        "username" => target.username = source.username.clone(),
+        "password" => target.password = source.password.clone(),
+        "notes" => target.notes = source.notes.clone(),
+        "tags" => target.tags = source.tags.clone(),
+        "folder" => target.folder = source.folder.clone(),
+        "favorite" => target.is_favorite = source.is_favorite,
+        "color" => target.color = source.color.clone(),
+        "icon" => target.icon = source.icon.clone(),
+        "archived" => target.archived = source.archived,
+        "canary" => target.canary = source.canary,
+        "expires_at" => target.expires_at = source.expires_at,
+        "rotation_interval_days" => target.rotation_interval_days = source.rotation_interval_days,
+        "totp" => target.totp = source.totp.clone(),
+        "custom_fields" => target.custom_fields = source.custom_fields.clone(),
+        "autotype" => target.autotype = source.autotype.clone(),
+        "ssh_key" => target.ssh_key = source.ssh_key.clone(),
+        "card" => target.card = source.card.clone(),
+        "identity" => target.identity = source.identity.clone(),
+        "site_policy" => target.site_policy = source.site_policy.clone(),
+        _ => {}
+    }
+}
+
+#[cfg(all(test, feature = "sync"))]
+mod tests {
+    use super::*;
+    use crate::models::AccountType;
+    
+    fn edit(vault: &mut Vault, id: &uuid::Uuid, change: impl FnOnce(&mut Account)) {
+        vault.update_account(id, change).unwrap();
+    }
+    
+    #[test]
+    fn test_edits_to_different_fields_both_survive() {
+        let mut base = Vault::new("test@example.com".to_string());
+        let account = Account::new("GitHub".to_string(), AccountType::Work, "old".to_string());
+        let id = account.id;
+        base.add_account(account);
+        
+        let mut laptop = base.clone();
+        let mut phone = base.clone();
+        edit(&mut laptop, &id, |account| account.password = "from laptop".to_string());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        edit(&mut phone, &id, |account| account.username = Some("octocat".to_string()));
+        
+        for (ours, theirs) in [(&laptop, &phone), (&phone, &laptop)] {
+            let (merged, summary) = merge(ours, theirs);
+            let account = merged.get_account(&id).unwrap();
+            assert_eq!(account.password, "from laptop");
+            assert_eq!(account.username.as_deref(), Some("octocat"));
+            assert_eq!(summary.deleted, 0);
+        }
+        
+        // Later edits to the same field win
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        edit(&mut laptop, &id, |account| account.username = Some("hubber".to_string()));
+        let (merged, _) = merge(&phone, &laptop);
+        assert_eq!(merged.get_account(&id).unwrap().username.as_deref(), Some("hubber"));
+    }
+    
+    #[test]
+    fn test_additions_and_deletions() {
+        let mut base = Vault::new("test@example.com".to_string());
+        let kept = Account::new("Mail".to_string(), AccountType::Email, "pw".to_string());
+        let deleted = Account::new("Forum".to_string(), AccountType::Social, "pw".to_string());
+        base.add_account(kept.clone());
+        base.add_account(deleted.clone());
+        
+        let mut laptop = base.clone();
+        let mut phone = base.clone();
+        laptop.trash_account(&deleted.id);
+        let added = Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string());
+        phone.add_account(added.clone());
+        
+        let (merged, summary) = merge(&phone, &laptop);
+        assert_eq!(summary, MergeSummary { added: 0, deleted: 1, updated: 0 });
+        assert!(merged.get_account(&added.id).is_some());
+        assert!(merged.get_account(&deleted.id).is_none());
+        assert!(merged.trash.contains_key(&deleted.id));
+        
+        let (merged, summary) = merge(&laptop, &phone);
+        assert_eq!(summary, MergeSummary { added: 1, deleted: 0, updated: 0 });
+        assert!(merged.get_account(&deleted.id).is_none());
+        
+        // An edit made after the deletion brings the account back
+        let mut phone = base.clone();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        edit(&mut phone, &deleted.id, |account| account.notes = Some("still needed".to_string()));
+        for (ours, theirs) in [(&laptop, &phone), (&phone, &laptop)] {
+            let (merged, _) = merge(ours, theirs);
+            assert!(merged.get_account(&deleted.id).is_some());
+            assert!(!merged.deleted.contains_key(&deleted.id));
+            assert!(merged.trash.is_empty());
+        }
+    }
+}
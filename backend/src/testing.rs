@@ -0,0 +1,273 @@
+//! # Test Fixtures
+//! 
+//! Helpers for tests of code built on PassMan, enabled by the `testing`
+//! feature (add it under `[dev-dependencies]` only):
+//! 
+//! - `TestVault` creates a vault in its own temporary directory, removed
+//!   when the fixture is dropped, with fast key derivation so tests don't
+//!   spend a second per unlock.
+//! - `AccountBuilder` (re-exported from `models`) builds accounts field by field.
+//! - `TestVaultBuilder::seed` (and `PassMan::seed_generator`) make
+//!   generated passwords reproducible.
+//! - `isolate` points the whole process at a temporary config directory,
+//!   for code that opens vaults by name with `PassMan::new`.
+//! 
+//! Vaults are still real files: PassMan has no pluggable storage backend
+//! yet, so a temporary directory stands in for an in-memory one.
+
+pub use crate::models::AccountBuilder;
+
+use crate::{
+    Account, PassMan, PassManConfig, Result, VaultInitOptions, VaultSettings,
+    config::{self, CONFIG_FILE},
+    crypto::KdfParams,
+};
+use std::path::Path;
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+/// Cheapest valid Argon2id parameters; only for vaults that protect nothing
+pub const FAST_KDF: KdfParams = KdfParams {
+    memory_kib: 8,
+    iterations: 1,
+    parallelism: 1,
+};
+
+/// Name of the vault a `TestVault` creates
+pub const TEST_VAULT_NAME: &str = "test";
+
+/// Email of a `TestVault` unless set otherwise
+pub const TEST_EMAIL: &str = "test@example.com";
+
+/// Master password of a `TestVault` unless set otherwise
+pub const TEST_PASSWORD: &str = "master_password";
+
+/// Point this process at a temporary config directory
+/// 
+/// Afterwards `PassMan::new`, `PassManConfig::load`, and the vault listing
+/// and deletion functions use a new directory in the system temp directory
+/// instead of the user's. It lives as long as the process (statics are
+/// never dropped, so it is left behind), and its config uses `FAST_KDF`
+/// for new vaults. Call it at the start of every test that opens vaults by
+/// name; later calls return the same directory.
+/// 
+/// # Returns
+/// The temporary config directory
+/// 
+/// # Panics
+/// Panics if the directory cannot be created
+pub fn isolate() -> &'static Path {
+    static DIR: OnceLock<TempDir> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = tempfile::Builder::new()
+            .prefix("passman-test-")
+            .tempdir()
+            .expect("failed to create a temporary config directory");
+        let config = PassManConfig {
+            kdf: Some(FAST_KDF),
+            ..PassManConfig::default()
+        };
+        config.save_to(&dir.path().join(CONFIG_FILE))
+            .expect("failed to write the test config");
+        config::override_config_dir(dir.path().to_path_buf());
+        dir
+    }).path()
+}
+
+/// A vault in a temporary directory, deleted when dropped
+pub struct TestVault {
+    dir: TempDir,
+    password: String,
+    seed: Option<u64>,
+    config: PassManConfig,
+}
+
+impl TestVault {
+    /// Create an empty vault with the default email and password
+    /// 
+    /// # Errors
+    /// Returns an error if the vault cannot be created
+    pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+    
+    /// Start building a vault
+    pub fn builder() -> TestVaultBuilder {
+        TestVaultBuilder::default()
+    }
+    
+    /// Unlock a new `PassMan` instance for the vault
+    /// 
+    /// # Errors
+    /// Returns an error if the vault cannot be opened
+    pub fn open(&self) -> Result<PassMan> {
+        let mut passman = self.passman()?;
+        passman.open_vault(&self.password)?;
+        Ok(passman)
+    }
+    
+    /// A new `PassMan` instance for the vault, still locked
+    /// 
+    /// # Errors
+    /// Returns an error if vault storage cannot be initialized
+    pub fn passman(&self) -> Result<PassMan> {
+        let mut passman = PassMan::with_config(TEST_VAULT_NAME, self.config.clone())?;
+        if let Some(seed) = self.seed {
+            passman.seed_generator(seed);
+        }
+        Ok(passman)
+    }
+    
+    /// Name of the vault
+    pub fn name(&self) -> &str {
+        TEST_VAULT_NAME
+    }
+    
+    /// Master password of the vault
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+    
+    /// Configuration pointing at the temporary directory
+    pub fn config(&self) -> &PassManConfig {
+        &self.config
+    }
+    
+    /// The temporary directory holding the vault and its backups
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Builder for a `TestVault`
+pub struct TestVaultBuilder {
+    email: String,
+    password: String,
+    accounts: Vec<Account>,
+    settings: VaultSettings,
+    seed: Option<u64>,
+}
+
+impl Default for TestVaultBuilder {
+    fn default() -> Self {
+        Self {
+            email: TEST_EMAIL.to_string(),
+            password: TEST_PASSWORD.to_string(),
+            accounts: Vec::new(),
+            settings: VaultSettings::default(),
+            seed: None,
+        }
+    }
+}
+
+impl TestVaultBuilder {
+    /// Set the vault's email
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = email.to_string();
+        self
+    }
+    
+    /// Set the master password
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = password.to_string();
+        self
+    }
+    
+    /// Add an account to the vault
+    pub fn account(mut self, account: Account) -> Self {
+        self.accounts.push(account);
+        self
+    }
+    
+    /// Add several accounts to the vault
+    pub fn accounts(mut self, accounts: impl IntoIterator<Item = Account>) -> Self {
+        self.accounts.extend(accounts);
+        self
+    }
+    
+    /// Set the vault settings
+    pub fn settings(mut self, settings: VaultSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+    
+    /// Seed the password generator of every instance the vault opens
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    
+    /// Create the vault
+    /// 
+    /// # Errors
+    /// Returns an error if the directory or vault cannot be created
+    pub fn build(self) -> Result<TestVault> {
+        let dir = tempfile::Builder::new().prefix("passman-vault-").tempdir()?;
+        let config = PassManConfig {
+            vault_dir: Some(dir.path().to_path_buf()),
+            kdf: Some(FAST_KDF),
+            ..PassManConfig::default()
+        };
+        
+        let mut passman = PassMan::with_config(TEST_VAULT_NAME, config.clone())?;
+        let options = VaultInitOptions {
+            settings: self.settings,
+            ..VaultInitOptions::from_config(&config)
+        };
+        passman.init_vault_with_options(self.email, &self.password, options)?;
+        if !self.accounts.is_empty() {
+            passman.add_accounts(self.accounts)?;
+        }
+        
+        Ok(TestVault {
+            dir,
+            password: self.password,
+            seed: self.seed,
+            config,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PasswordOptions;
+    
+    #[test]
+    fn test_vault_fixture() {
+        let vault = TestVault::builder()
+            .account(AccountBuilder::new("GitHub").username("octocat").tag("dev").build())
+            .account(AccountBuilder::new("Old").archived().build())
+            .build()
+            .unwrap();
+        
+        let passman = vault.open().unwrap();
+        assert_eq!(passman.get_all_accounts().len(), 2);
+        assert!(vault.dir().join("test.vault").exists());
+        
+        let mut wrong = vault.passman().unwrap();
+        assert!(wrong.open_vault("wrong").is_err());
+        
+        let dir = vault.dir().to_path_buf();
+        drop(passman);
+        drop(vault);
+        assert!(!dir.exists());
+    }
+    
+    #[test]
+    fn test_seeded_generator() {
+        let vault = TestVault::builder().seed(42).build().unwrap();
+        let options = PasswordOptions::default();
+        let first = vault.open().unwrap().generate_password(&options).unwrap();
+        let second = vault.open().unwrap().generate_password(&options).unwrap();
+        assert_eq!(first, second);
+    }
+    
+    #[test]
+    fn test_isolate() {
+        let dir = isolate();
+        assert_eq!(isolate(), dir);
+        assert_eq!(config::passman_config_dir().unwrap(), dir);
+        assert_eq!(PassManConfig::load().unwrap().kdf_params(), FAST_KDF);
+    }
+}
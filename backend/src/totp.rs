@@ -0,0 +1,275 @@
+//! # Time-based One-Time Passwords
+//! 
+//! This module implements TOTP (RFC 6238) code generation for accounts
+//! that store a two-factor secret, either as a raw base32 secret or as
+//! an `otpauth://totp/...` URI.
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::{Zeroize, Zeroizing};
+use crate::{PassManError, Result, base32};
+
+/// Default number of digits in a TOTP code
+const DEFAULT_DIGITS: u32 = 6;
+/// Default TOTP time step in seconds
+const DEFAULT_PERIOD: u64 = 30;
+
+/// HMAC algorithm used to derive TOTP codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    /// HMAC-SHA1 (the default used by almost every service)
+    Sha1,
+    
+    /// HMAC-SHA256
+    Sha256,
+    
+    /// HMAC-SHA512
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    /// Name of the algorithm as used in otpauth URIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TotpAlgorithm::Sha1 => "SHA1",
+            TotpAlgorithm::Sha256 => "SHA256",
+            TotpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+}
+
+/// A parsed TOTP configuration
+pub struct Totp {
+    /// Decoded shared secret, cleared on drop
+    secret: Zeroizing<Vec<u8>>,
+    
+    /// Number of digits in generated codes
+    pub digits: u32,
+    
+    /// Time step in seconds
+    pub period: u64,
+    
+    /// HMAC algorithm
+    pub algorithm: TotpAlgorithm,
+    
+    /// Issuer from the otpauth URI, if any
+    pub issuer: Option<String>,
+}
+
+impl Totp {
+    /// Parse a TOTP secret
+    /// 
+    /// # Arguments
+    /// * `input` - A base32 secret or an `otpauth://totp/...` URI
+    /// 
+    /// # Returns
+    /// The parsed TOTP configuration
+    /// 
+    /// # Errors
+    /// Returns an error if the secret or URI is malformed, or the secret is empty
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        if input.starts_with("otpauth://") {
+            Self::parse_uri(input)
+        } else {
+            let secret = Zeroizing::new(base32::decode(input)?);
+            if secret.is_empty() {
+                return Err(PassManError::InvalidInput("TOTP secret cannot be empty".to_string()));
+            }
+            Ok(Self {
+                secret,
+                digits: DEFAULT_DIGITS,
+                period: DEFAULT_PERIOD,
+                algorithm: TotpAlgorithm::Sha1,
+                issuer: None,
+            })
+        }
+    }
+    
+    /// Parse an `otpauth://totp/Label?secret=...` URI
+    fn parse_uri(uri: &str) -> Result<Self> {
+        let rest = uri.strip_prefix("otpauth://totp/")
+            .ok_or_else(|| PassManError::InvalidInput("Only otpauth://totp URIs are supported".to_string()))?;
+        let query = rest.split_once('?').map(|(_, q)| q).unwrap_or("");
+        
+        let mut totp = Self {
+            secret: Zeroizing::new(Vec::new()),
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+            algorithm: TotpAlgorithm::Sha1,
+            issuer: None,
+        };
+        
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => totp.secret = Zeroizing::new(base32::decode(&value)?),
+                "digits" => {
+                    totp.digits = value.parse()
+                        .map_err(|_| PassManError::InvalidInput(format!("Invalid TOTP digits: {}", value)))?;
+                }
+                "period" => {
+                    totp.period = value.parse()
+                        .map_err(|_| PassManError::InvalidInput(format!("Invalid TOTP period: {}", value)))?;
+                }
+                "algorithm" => {
+                    totp.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => TotpAlgorithm::Sha1,
+                        "SHA256" => TotpAlgorithm::Sha256,
+                        "SHA512" => TotpAlgorithm::Sha512,
+                        other => return Err(PassManError::InvalidInput(format!("Unsupported TOTP algorithm: {}", other))),
+                    };
+                }
+                "issuer" => totp.issuer = Some(value),
+                _ => {}
+            }
+        }
+        
+        if totp.secret.is_empty() {
+            return Err(PassManError::InvalidInput("otpauth URI has no secret".to_string()));
+        }
+        if !(6..=8).contains(&totp.digits) {
+            return Err(PassManError::InvalidInput("TOTP digits must be between 6 and 8".to_string()));
+        }
+        if totp.period == 0 {
+            return Err(PassManError::InvalidInput("TOTP period must be greater than 0".to_string()));
+        }
+        
+        Ok(totp)
+    }
+    
+    /// Generate the code for a given Unix timestamp
+    /// 
+    /// # Arguments
+    /// * `unix_time` - Seconds since the Unix epoch
+    /// 
+    /// # Returns
+    /// The zero-padded TOTP code
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = (unix_time / self.period).to_be_bytes();
+        let mut digest = match self.algorithm {
+            TotpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&self.secret, &counter),
+            TotpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&self.secret, &counter),
+            TotpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+        
+        // Dynamic truncation (RFC 4226 section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        digest.zeroize();
+        
+        let code = binary % 10u32.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+    
+    /// Generate the code for the current time
+    /// 
+    /// # Returns
+    /// The current code and the number of seconds it remains valid
+    pub fn current_code(&self) -> (String, u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        (self.code_at(now), self.period - now % self.period)
+    }
+    
+    /// Build an otpauth URI for this configuration
+    /// 
+    /// # Arguments
+    /// * `label` - Account label shown by authenticator apps
+    /// 
+    /// # Returns
+    /// An `otpauth://totp/...` URI
+    pub fn to_uri(&self, label: &str) -> String {
+        let mut uri = format!(
+            "otpauth://totp/{}?secret={}&digits={}&period={}&algorithm={}",
+            percent_encode(label),
+            base32::encode(&self.secret),
+            self.digits,
+            self.period,
+            self.algorithm.as_str(),
+        );
+        if let Some(ref issuer) = self.issuer {
+            uri.push_str("&issuer=");
+            uri.push_str(&percent_encode(issuer));
+        }
+        uri
+    }
+}
+
+/// Compute an HMAC digest with the given MAC type
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Decode %XX escapes in a URI component
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                output.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        output.push(if bytes[i] == b'+' { b' ' } else { bytes[i] });
+        i += 1;
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Escape characters that aren't allowed unencoded in a URI component
+fn percent_encode(input: &str) -> String {
+    input.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rfc6238_vectors() {
+        // RFC 6238 appendix B, SHA1 secret "12345678901234567890"
+        let totp = Totp::parse("otpauth://totp/test?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8").unwrap();
+        assert_eq!(totp.code_at(59), "94287082");
+        assert_eq!(totp.code_at(1111111109), "07081804");
+        assert_eq!(totp.code_at(20000000000), "65353130");
+    }
+    
+    #[test]
+    fn test_uri_roundtrip() {
+        let totp = Totp::parse("otpauth://totp/Example%3Aalice?secret=JBSWY3DPEHPK3PXP&issuer=Example").unwrap();
+        assert_eq!(totp.issuer.as_deref(), Some("Example"));
+        
+        let reparsed = Totp::parse(&totp.to_uri("Example:alice")).unwrap();
+        assert_eq!(reparsed.code_at(1_700_000_000), totp.code_at(1_700_000_000));
+    }
+    
+    #[test]
+    fn test_invalid_secret() {
+        assert!(Totp::parse("not base32!").is_err());
+        assert!(matches!(Totp::parse(""), Err(PassManError::InvalidInput(_))));
+        assert!(matches!(Totp::parse("  "), Err(PassManError::InvalidInput(_))));
+        assert!(Totp::parse("otpauth://totp/x?digits=6").is_err());
+    }
+}
@@ -3,15 +3,79 @@
 //! This is the main vault module that provides the high-level API
 //! for password management operations.
 
+use std::collections::HashMap;
 use uuid::Uuid;
+use zeroize::Zeroize;
 use crate::{
     PassManError, Result,
-    models::{Vault, Account, AccountType, PasswordOptions, VaultMetadata},
-    storage::VaultStorage,
+    config::{PassManConfig, StorageEngine},
+    models::{Vault, Account, AccountPatch, CustomField, SigningKey, AccountQuery, AccountType, DedupePolicy, GeneratedPassword, ImportSummary, PassphraseOptions, PasswordOptions, TrashedAccount, UsernameOptions, VaultMetadata, VaultSettings, url_host},
+    storage::{BackupInfo, EmergencyAccess, EmergencyRequest, KeySlotKind, StorageBackend, VaultHeader, VaultStorage},
+    search_cache::SearchCache,
+    audit::{self, AuditOptions, Canary, HealthReport},
+    appearance,
+    autotype,
     auth::AuthManager,
-    generator::PasswordGenerator,
+    generator::{PasswordCandidate, PasswordGenerator},
+    policy::SitePolicy,
+    signing::{self, SignatureStatus},
+    strength::StrengthEstimate,
+    crypto::{KdfParams, Salt, SecureKey},
+    format,
+    fsck::{self, FsckReport, Severity},
+    base32,
 };
 
+#[cfg(feature = "importers")]
+use crate::importers::Importer;
+#[cfg(feature = "sync")]
+use crate::sync::{self, MergeSummary};
+
+pub use crate::diff::{VaultDiff, diff};
+
+/// Characters per dash-separated group in a formatted recovery key
+const RECOVERY_KEY_GROUP: usize = 4;
+
+/// Most generated passwords kept in a generation history
+pub const GENERATION_HISTORY_LIMIT: usize = 20;
+
+/// Options for creating a new vault
+#[derive(Debug, Clone, Default)]
+pub struct VaultInitOptions {
+    /// Key derivation parameters for the master password
+    pub kdf: KdfParams,
+    
+    /// Keyfile contents required alongside the master password
+    pub keyfile: Option<Vec<u8>>,
+    
+    /// Generate a recovery key that can unlock the vault on its own
+    pub recovery_key: bool,
+    
+    /// Initial vault settings
+    pub settings: VaultSettings,
+    
+    /// Master password hint, stored unencrypted in the vault header
+    pub hint: Option<String>,
+    
+    /// Keep each account in an encrypted record of its own (see `set_record_storage`)
+    pub records: bool,
+}
+
+impl VaultInitOptions {
+    /// Default options, with the key derivation parameters configured for
+    /// new vaults, and records when vaults are kept in SQLite
+    /// 
+    /// # Arguments
+    /// * `config` - The library configuration
+    pub fn from_config(config: &PassManConfig) -> Self {
+        Self {
+            kdf: config.kdf_params(),
+            records: config.storage == StorageEngine::Sqlite,
+            ..Self::default()
+        }
+    }
+}
+
 /// Main PassMan vault manager
 pub struct PassMan {
     /// Vault storage manager
@@ -28,6 +92,9 @@ pub struct PassMan {
     
     /// Vault name
     vault_name: String,
+    
+    /// Library configuration the instance was created with
+    config: PassManConfig,
 }
 
 impl PassMan {
@@ -40,17 +107,68 @@ impl PassMan {
     /// A new PassMan instance
     /// 
     /// # Errors
-    /// Returns an error if vault storage cannot be initialized
+    /// Returns an error if the config file is invalid or vault storage
+    /// cannot be initialized
     pub fn new(vault_name: &str) -> Result<Self> {
-        let storage = VaultStorage::new(vault_name)?;
-        
-        Ok(Self {
+        Self::with_config(vault_name, PassManConfig::load()?)
+    }
+    
+    /// Create a new PassMan instance with the given configuration
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault to manage
+    /// * `config` - Vault location, backup policy, and defaults to use
+    /// 
+    /// # Returns
+    /// A new PassMan instance
+    /// 
+    /// # Errors
+    /// Returns an error if vault storage cannot be initialized
+    pub fn with_config(vault_name: &str, config: PassManConfig) -> Result<Self> {
+        let storage = VaultStorage::with_config(vault_name, &config)?;
+        Ok(Self::with_storage(vault_name, storage, config))
+    }
+    
+    /// Create a new PassMan instance that keeps its vault in any storage backend
+    /// 
+    /// The configured vault and backup directories are not used; the
+    /// rest of the configuration is.
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault to manage
+    /// * `backend` - Where to keep the vault file and its backups
+    /// * `config` - Backup policy and defaults to use
+    /// 
+    /// # Returns
+    /// A new PassMan instance
+    pub fn new_with_backend(vault_name: &str, backend: impl StorageBackend + 'static, config: PassManConfig) -> Self {
+        let storage = VaultStorage::with_backend(vault_name, backend, config.backups.clone());
+        Self::with_storage(vault_name, storage, config)
+    }
+    
+    fn with_storage(vault_name: &str, storage: VaultStorage, config: PassManConfig) -> Self {
+        Self {
             storage,
             auth: AuthManager::default(),
             generator: PasswordGenerator::new(),
             vault: None,
             vault_name: vault_name.to_string(),
-        })
+            config,
+        }
+    }
+    
+    /// Get the configuration this instance was created with
+    pub fn config(&self) -> &PassManConfig {
+        &self.config
+    }
+    
+    /// Make password generation reproducible by seeding the generator
+    /// 
+    /// # Arguments
+    /// * `seed` - Seed for the random number generator
+    #[cfg(any(test, feature = "testing"))]
+    pub fn seed_generator(&mut self, seed: u64) {
+        self.generator = PasswordGenerator::from_seed(seed);
     }
     
     /// Initialize a new vault with email and master password
@@ -65,25 +183,61 @@ impl PassMan {
     /// # Errors
     /// Returns an error if vault already exists or initialization fails
     pub fn init_vault(&mut self, email: String, master_password: &str) -> Result<()> {
+        self.init_vault_with_options(email, master_password, VaultInitOptions::from_config(&self.config))
+            .map(|_| ())
+    }
+    
+    /// Initialize a new vault with custom key derivation, keyfile, and settings
+    /// 
+    /// # Arguments
+    /// * `email` - Email address for the vault
+    /// * `master_password` - Master password for encryption
+    /// * `options` - Creation options
+    /// 
+    /// # Returns
+    /// The formatted recovery key if one was requested; it is not stored
+    /// anywhere and must be shown to the user now
+    /// 
+    /// # Errors
+    /// Returns an error if vault already exists or initialization fails
+    pub fn init_vault_with_options(&mut self, email: String, master_password: &str, options: VaultInitOptions) -> Result<Option<String>> {
         if self.storage.vault_exists() {
-            return Err(PassManError::VaultNotFound(
-                "Vault already exists. Use open_vault() to access it.".to_string()
-            ));
+            return Err(PassManError::VaultExists(format!(
+                "'{}'; use open_vault() to access it", self.vault_name
+            )));
         }
         
         // Create new vault
-        let vault = Vault::new(email);
+        let mut vault = Vault::new(email);
+        vault.metadata.settings = options.settings;
         
-        // Set up crypto with master password
-        let (_, _salt) = self.auth.get_crypto_mut_for_init().generate_key_and_salt(master_password)?;
+        // The payload is encrypted with a random key, wrapped per unlock method
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(options.kdf, options.keyfile.is_some());
+        header.hint = options.hint;
+        header.records = options.records;
+        let password_key = header.password_key(master_password, options.keyfile.as_deref())?;
+        header.set_slot(KeySlotKind::Password, &vault_key, &password_key)?;
+        
+        let recovery_key = if options.recovery_key {
+            let recovery_key = SecureKey::generate();
+            header.set_slot(KeySlotKind::Recovery, &vault_key, &recovery_key)?;
+            Some(format_recovery_key(&recovery_key))
+        } else {
+            None
+        };
         
         // Save the vault
-        self.storage.save_vault(&vault, self.auth.get_crypto_for_init())?;
+        self.auth.get_crypto_mut_for_init().set_key(vault_key, header.salt()?);
+        self.storage.save_vault_with_header(&vault, &header, self.auth.get_crypto_for_init())?;
+        
+        // Start an authenticated session for the new vault
+        self.auth.authenticate(master_password, &vault.metadata)?;
         
         // Load the vault for immediate use
         self.vault = Some(vault);
         
-        Ok(())
+        Ok(recovery_key)
     }
     
     /// Open an existing vault with master password
@@ -97,432 +251,3638 @@ impl PassMan {
     /// # Errors
     /// Returns an error if vault doesn't exist or authentication fails
     pub fn open_vault(&mut self, master_password: &str) -> Result<()> {
+        self.open_vault_with_keyfile(master_password, None)
+    }
+    
+    /// Open an existing vault with master password and keyfile
+    /// 
+    /// # Arguments
+    /// * `master_password` - Master password for decryption
+    /// * `keyfile` - Keyfile contents, required if the vault was created with one
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if vault doesn't exist or authentication fails
+    pub fn open_vault_with_keyfile(&mut self, master_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
         if !self.storage.vault_exists() {
             return Err(PassManError::VaultNotFound(
                 "Vault not found. Use init_vault() to create a new vault.".to_string()
             ));
         }
         
-        // Load vault using the master password (salt will be read from file)
-        let vault = self.storage.load_vault(master_password)?;
-        let metadata = &vault.metadata;
+        // Refuse attempts while locked out or backing off after failures
+        self.auth.check_attempt_allowed()?;
         
-        // Authenticate with master password
-        self.auth.authenticate(master_password, metadata)?;
+        // A missing keyfile is a usage error, not a wrong guess
+        if keyfile.is_none() && self.requires_keyfile()? {
+            return Err(PassManError::AuthenticationFailed("This vault requires a keyfile".to_string()));
+        }
         
-        // Set up crypto key in AuthManager for future operations
-        // We need to derive the key using the same salt that was used to create the vault
-        let vault_file_path = format!("{}/.config/passman/vaults/main.vault", 
-            std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
-        let file_data = std::fs::read(&vault_file_path)
-            .map_err(|e| PassManError::StorageError(format!("Failed to read vault file: {}", e)))?;
+        // Derive (legacy) or unwrap (versioned) the vault key
+        let salt = self.storage.read_salt()?;
+        let unlocked = self.storage.unlock_key(master_password, keyfile)
+            .and_then(|key| Ok((self.storage.load_vault_with_key(&key)?, key)));
         
-        if file_data.len() >= 16 {
-            let salt_bytes: [u8; 16] = file_data[0..16].try_into()
-                .map_err(|_| PassManError::StorageError("Failed to read salt from vault file".to_string()))?;
-            let salt = crate::crypto::Salt::from_bytes(salt_bytes);
-            let _key = self.auth.get_crypto_mut_for_init().derive_key(master_password, &salt)?;
-        }
+        // Decrypting the vault proves the password is correct
+        let (vault, key) = match unlocked {
+            Ok(unlocked) => unlocked,
+            Err(PassManError::CryptoError(_)) | Err(PassManError::AuthenticationFailed(_)) => {
+                self.auth.record_failed_attempt();
+                return Err(PassManError::AuthenticationFailed("Invalid master password".to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+        
+        // Authenticate with master password
+        self.auth.authenticate(master_password, &vault.metadata)?;
         
         // Load the full vault
+        self.auth.get_crypto_mut_for_init().set_key(key, salt);
         self.vault = Some(vault);
         
+        self.expire_trash();
+        // The vault is open either way; a failed upgrade is retried next time
+        let _ = self.upgrade_vault_file(Some(master_password));
+        
         Ok(())
     }
     
-    /// Close the current vault
-    pub fn close_vault(&mut self) {
-        self.vault = None;
-        self.auth.logout();
+    /// Re-check the master password of the open vault
+    /// 
+    /// Used to gate sensitive operations (e.g. revealing a password) when
+    /// the vault's `require_confirmation` setting is on. Failures count
+    /// towards the same lockout and backoff as unlock attempts.
+    /// 
+    /// # Arguments
+    /// * `master_password` - The master password to check
+    /// * `keyfile` - Keyfile contents, required if the vault uses one
+    /// 
+    /// # Returns
+    /// Unit if the password is correct
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or the password is wrong
+    pub fn verify_master_password(&mut self, master_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
+        let current = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        self.auth.check_attempt_allowed()?;
+        
+        let matches = match self.storage.unlock_key(master_password, keyfile) {
+            Ok(key) => {
+                // Compare without short-circuiting on the first differing byte
+                key.as_bytes().iter()
+                    .zip(current.as_bytes())
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+            }
+            Err(PassManError::CryptoError(_)) | Err(PassManError::AuthenticationFailed(_)) => false,
+            Err(e) => return Err(e),
+        };
+        
+        if !matches {
+            self.auth.record_failed_attempt();
+            return Err(PassManError::AuthenticationFailed("Invalid master password".to_string()));
+        }
+        
+        self.auth.reset_failed_attempts();
+        Ok(())
     }
     
-    /// Check if a vault is currently open
+    /// Get the number of failed unlock attempts since the last success
+    pub fn failed_unlock_attempts(&self) -> u32 {
+        self.auth.failed_attempts()
+    }
+    
+    /// Get how long to wait before the next unlock attempt is accepted
     /// 
     /// # Returns
-    /// True if a vault is open and authenticated
-    pub fn is_vault_open(&self) -> bool {
-        self.vault.is_some() && self.auth.is_authenticated()
+    /// Remaining backoff after failed attempts, or zero
+    pub fn unlock_retry_delay(&self) -> std::time::Duration {
+        self.auth.retry_delay()
     }
     
-    /// Get vault metadata
+    /// Check whether too many failed attempts have locked out unlocking
+    pub fn is_unlock_locked_out(&self) -> bool {
+        self.auth.is_locked_out()
+    }
+    
+    /// Get the master password hint
+    /// 
+    /// Readable without unlocking, since it is stored in the vault header.
     /// 
     /// # Returns
-    /// Vault metadata or None if not open
-    pub fn get_vault_metadata(&self) -> Option<&VaultMetadata> {
-        self.vault.as_ref().map(|v| &v.metadata)
+    /// The hint, or None if none is set (always None for legacy vaults)
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file cannot be read
+    pub fn password_hint(&self) -> Result<Option<String>> {
+        Ok(self.storage.read_header()?.and_then(|header| header.hint))
     }
     
-    /// Add a new account to the vault
+    /// Set or clear the master password hint
     /// 
     /// # Arguments
-    /// * `name` - Account name
-    /// * `account_type` - Type of account
-    /// * `password` - Account password
-    /// * `url` - Optional URL
-    /// * `username` - Optional username
-    /// * `notes` - Optional notes
-    /// * `tags` - Optional tags
+    /// * `hint` - New hint, or None to remove it
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if vault is not open or save fails
-    pub fn add_account(
-        &mut self,
-        name: String,
-        account_type: AccountType,
-        password: String,
-        url: Option<String>,
-        username: Option<String>,
-        notes: Option<String>,
-        tags: Vec<String>,
-    ) -> Result<()> {
-        let vault = self.vault.as_mut()
-            .ok_or_else(|| PassManError::AuthenticationFailed("Vault not open".to_string()))?;
-        
-        let mut account = Account::new(name, account_type, password);
-        account.url = url;
-        account.username = username;
-        account.notes = notes;
-        account.tags = tags;
-        
-        vault.add_account(account);
-        
-        // Save vault
-        self.save_vault()?;
+    /// Returns an error if the vault is not open or uses the legacy format
+    pub fn set_password_hint(&mut self, hint: Option<String>) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let mut header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::InvalidInput(
+                "Legacy vaults can't store a password hint; unlock it with the master password to upgrade it".to_string()
+            ))?;
         
-        Ok(())
+        header.hint = hint;
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())
     }
     
-    /// Update an existing account
+    /// Keep each account in an encrypted record of its own, or all of them in the vault file
+    /// 
+    /// With records, a save only encrypts and writes the accounts that
+    /// changed, which keeps saves fast in vaults with many accounts.
+    /// Switching rewrites the whole vault once.
     /// 
     /// # Arguments
-    /// * `id` - Account ID
-    /// * `name` - New account name
-    /// * `account_type` - New account type
-    /// * `password` - New password
-    /// * `url` - New URL
-    /// * `username` - New username
-    /// * `notes` - New notes
-    /// * `tags` - New tags
+    /// * `records` - True for a record per account, false for a single file
     /// 
     /// # Returns
-    /// Unit on success
+    /// True if the layout changed
     /// 
     /// # Errors
-    /// Returns an error if account not found or vault not open
-    pub fn update_account(
-        &mut self,
-        id: Uuid,
-        name: String,
-        account_type: AccountType,
-        password: String,
-        url: Option<String>,
-        username: Option<String>,
-        notes: Option<String>,
-        tags: Vec<String>,
-    ) -> Result<()> {
-        let vault = self.vault.as_mut()
-            .ok_or_else(|| PassManError::AuthenticationFailed("Vault not open".to_string()))?;
-        
-        let account = vault.get_account_mut(&id)
-            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
-        
-        account.name = name;
-        account.account_type = account_type;
-        account.password = password;
-        account.url = url;
-        account.username = username;
-        account.notes = notes;
-        account.tags = tags;
-        account.updated_at = chrono::Utc::now();
-        
-        // Save vault
-        self.save_vault()?;
+    /// Returns an error if the vault is not open, uses the legacy format, or save fails
+    pub fn set_record_storage(&mut self, records: bool) -> Result<bool> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let mut header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::InvalidInput(
+                "Legacy vaults keep everything in one file; unlock it with the master password to upgrade it".to_string()
+            ))?;
+        if header.records == records {
+            return Ok(false);
+        }
         
-        Ok(())
+        header.records = records;
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        Ok(true)
     }
     
-    /// Delete an account from the vault
+    /// Open an existing vault with its recovery key
     /// 
     /// # Arguments
-    /// * `id` - Account ID to delete
+    /// * `recovery_key` - Recovery key as shown when the vault was created
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if account not found or vault not open
-    pub fn delete_account(&mut self, id: Uuid) -> Result<()> {
-        let vault = self.vault.as_mut()
-            .ok_or_else(|| PassManError::AuthenticationFailed("Vault not open".to_string()))?;
-        
-        vault.remove_account(&id)
-            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
-        
-        // Save vault
-        self.save_vault()?;
+    /// Returns an error if the vault has no recovery key or the key is wrong
+    pub fn open_vault_with_recovery_key(&mut self, recovery_key: &str) -> Result<()> {
+        let header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::AuthenticationFailed("Vault has no recovery key".to_string()))?;
+        let key = header.unwrap_slot(KeySlotKind::Recovery, &parse_slot_key(recovery_key, KeySlotKind::Recovery)?)?;
         
-        Ok(())
+        self.open_vault_with_key(key)
     }
     
-    /// Get an account by ID
+    /// Add a device key slot to the open vault
     /// 
-    /// # Arguments
-    /// * `id` - Account ID
+    /// The returned key unlocks the vault on its own, so the caller must
+    /// keep it in the OS keystore behind a user-presence check (e.g.
+    /// biometrics). Enrolling again replaces the previous device key.
     /// 
     /// # Returns
-    /// Account reference or None if not found
-    pub fn get_account(&self, id: Uuid) -> Option<&Account> {
-        self.vault.as_ref()?.get_account(&id)
+    /// The new device key
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or uses the legacy format
+    pub fn enroll_device_key(&mut self) -> Result<SecureKey> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault_key = self.auth.get_crypto_for_init().key().cloned()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let mut header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::InvalidInput(
+                "Legacy vaults can't use device unlock; unlock it with the master password to upgrade it".to_string()
+            ))?;
+        
+        let device_key = SecureKey::generate();
+        header.set_slot(KeySlotKind::Device, &vault_key, &device_key)?;
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        
+        Ok(device_key)
     }
     
-    /// Get all accounts in the vault
+    /// Remove the device key slot from the open vault
     /// 
     /// # Returns
-    /// Vector of account references
-    pub fn get_all_accounts(&self) -> Vec<&Account> {
-        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_all_accounts())
+    /// Unit on success (also when no device key was enrolled)
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or save fails
+    pub fn remove_device_key(&mut self) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let Some(mut header) = self.storage.read_header()? else {
+            return Ok(());
+        };
+        
+        if header.remove_slot(KeySlotKind::Device) {
+            self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        }
+        Ok(())
     }
     
-    /// Search accounts by name
-    /// 
-    /// # Arguments
-    /// * `query` - Search query
+    /// Check whether the vault has a device key slot
     /// 
-    /// # Returns
-    /// Vector of matching account references
-    pub fn search_accounts(&self, query: &str) -> Vec<&Account> {
-        self.vault.as_ref().map_or_else(Vec::new, |v| v.search_accounts(query))
+    /// # Errors
+    /// Returns an error if the vault file cannot be read
+    pub fn has_device_key(&self) -> Result<bool> {
+        Ok(self.storage.read_header()?.is_some_and(|header| header.has_slot(KeySlotKind::Device)))
     }
     
-    /// Get accounts by type
+    /// Open an existing vault with a device key
     /// 
     /// # Arguments
-    /// * `account_type` - Account type to filter by
+    /// * `device_key` - Key returned by `enroll_device_key()`
     /// 
     /// # Returns
-    /// Vector of matching account references
-    pub fn get_accounts_by_type(&self, account_type: &AccountType) -> Vec<&Account> {
-        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_by_type(account_type))
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault has no device key or the key is wrong
+    pub fn open_vault_with_device_key(&mut self, device_key: &SecureKey) -> Result<()> {
+        let header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::AuthenticationFailed("Vault has no device key".to_string()))?;
+        let key = header.unwrap_slot(KeySlotKind::Device, device_key)?;
+        
+        self.open_vault_with_key(key)
     }
     
-    /// Get accounts by tag
+    /// Give an emergency contact a key to the open vault
+    /// 
+    /// PassMan lets the contact use the key only after requesting access
+    /// with `request_emergency_access` and waiting `wait_days` without the
+    /// owner denying the request. The wait is advisory: the key alone
+    /// unwraps its key slot, and neither the request time nor `wait_days`
+    /// is authenticated, so a contact who can edit the files beside the
+    /// vault or runs modified software can skip it. Grant access only to
+    /// someone trusted with the vault today. Granting again replaces the
+    /// previous key and cancels any pending request.
     /// 
     /// # Arguments
-    /// * `tag` - Tag to filter by
+    /// * `contact` - Who receives the key, for the owner's reference
+    /// * `wait_days` - Days between a request and access
     /// 
     /// # Returns
-    /// Vector of matching account references
-    pub fn get_accounts_by_tag(&self, tag: &str) -> Vec<&Account> {
-        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_by_tag(tag))
+    /// The formatted emergency key, to hand to the contact; it is not stored
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or uses the legacy format
+    pub fn grant_emergency_access(&mut self, contact: &str, wait_days: u32) -> Result<String> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault_key = self.auth.get_crypto_for_init().key().cloned()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if contact.trim().is_empty() {
+            return Err(PassManError::InvalidInput("Emergency contact cannot be empty".to_string()));
+        }
+        let mut header = self.storage.read_header()?
+            .ok_or_else(|| PassManError::InvalidInput(
+                "Legacy vaults can't grant emergency access; unlock it with the master password to upgrade it".to_string()
+            ))?;
+        
+        let emergency_key = SecureKey::generate();
+        header.set_slot(KeySlotKind::Emergency, &vault_key, &emergency_key)?;
+        header.emergency = Some(EmergencyAccess {
+            contact: contact.trim().to_string(),
+            wait_days,
+            granted_at: chrono::Utc::now(),
+        });
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        self.storage.remove_emergency_request()?;
+        
+        Ok(format_recovery_key(&emergency_key))
     }
     
-    /// Generate a new password
-    /// 
-    /// # Arguments
-    /// * `options` - Password generation options
+    /// Revoke emergency access, invalidating the contact's key
     /// 
     /// # Returns
-    /// Generated password string
+    /// Unit on success (also when no access was granted)
     /// 
     /// # Errors
-    /// Returns an error if generation fails
-    pub fn generate_password(&mut self, options: &PasswordOptions) -> Result<String> {
-        self.generator.generate(options)
+    /// Returns an error if the vault is not open or save fails
+    pub fn revoke_emergency_access(&mut self) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let Some(mut header) = self.storage.read_header()? else {
+            return Ok(());
+        };
+        
+        let removed = header.remove_slot(KeySlotKind::Emergency);
+        if header.emergency.take().is_some() || removed {
+            self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        }
+        self.storage.remove_emergency_request()?;
+        Ok(())
     }
     
-    /// Generate a simple password
-    /// 
-    /// # Arguments
-    /// * `length` - Password length
+    /// Get the vault's emergency access settings
     /// 
     /// # Returns
-    /// Generated password string
-    pub fn generate_simple_password(&mut self, length: usize) -> Result<String> {
-        self.generator.generate_simple(length)
+    /// The settings, or None if no contact holds an emergency key
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file cannot be read
+    pub fn emergency_access(&self) -> Result<Option<EmergencyAccess>> {
+        Ok(self.storage.read_header()?
+            .filter(|header| header.has_slot(KeySlotKind::Emergency))
+            .and_then(|header| header.emergency))
     }
     
-    /// Generate a strong password
+    /// Get the pending emergency access request
+    /// 
+    /// Front-ends should warn the owner about it after unlocking, so they
+    /// can deny it in time.
+    /// 
+    /// # Errors
+    /// Returns an error if the request file cannot be read
+    pub fn emergency_request(&self) -> Result<Option<EmergencyRequest>> {
+        self.storage.read_emergency_request()
+    }
+    
+    /// Request emergency access as the contact
+    /// 
+    /// Starts the waiting period. Requesting again keeps the original
+    /// request time.
     /// 
     /// # Arguments
-    /// * `length` - Password length
+    /// * `emergency_key` - Key returned by `grant_emergency_access()`
     /// 
     /// # Returns
-    /// Generated password string
-    pub fn generate_strong_password(&mut self, length: usize) -> Result<String> {
-        self.generator.generate_strong(length)
+    /// When the key can be used
+    /// 
+    /// # Errors
+    /// Returns an error if the vault grants no emergency access or the key is wrong
+    pub fn request_emergency_access(&self, emergency_key: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+        let (header, access) = self.emergency_header()?;
+        header.unwrap_slot(KeySlotKind::Emergency, &parse_slot_key(emergency_key, KeySlotKind::Emergency)?)?;
+        
+        let request = match self.storage.read_emergency_request()? {
+            Some(request) => request,
+            None => {
+                let request = EmergencyRequest { requested_at: chrono::Utc::now() };
+                self.storage.write_emergency_request(&request)?;
+                request
+            }
+        };
+        Ok(request.requested_at + chrono::Duration::days(access.wait_days.into()))
     }
     
-    /// Calculate password strength
+    /// Deny the pending emergency access request as the owner
     /// 
-    /// # Arguments
-    /// * `password` - Password to analyze
+    /// The contact keeps their key and can request access again.
     /// 
     /// # Returns
-    /// Strength score (0-100)
-    pub fn calculate_password_strength(&self, password: &str) -> u8 {
-        self.generator.calculate_strength(password)
+    /// True if there was a request to deny
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or the request file cannot be deleted
+    pub fn deny_emergency_request(&mut self) -> Result<bool> {
+        if !self.is_vault_open() {
+            return Err(PassManError::VaultLocked("vault is not open".to_string()));
+        }
+        self.storage.remove_emergency_request()
     }
     
-    /// Get password strength description
+    /// Open the vault with an emergency key once the waiting period is over
+    /// 
+    /// The waiting period is checked against the unauthenticated request
+    /// file and header, so it only holds for contacts who don't tamper
+    /// with them (see `grant_emergency_access`).
     /// 
     /// # Arguments
-    /// * `score` - Strength score
+    /// * `emergency_key` - Key returned by `grant_emergency_access()`
     /// 
     /// # Returns
-    /// Human-readable strength description
-    pub fn get_password_strength_description(&self, score: u8) -> &'static str {
-        self.generator.get_strength_description(score)
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if access wasn't requested, the waiting period
+    /// hasn't passed, or the key is wrong
+    pub fn open_vault_with_emergency_key(&mut self, emergency_key: &str) -> Result<()> {
+        let (header, access) = self.emergency_header()?;
+        let request = self.storage.read_emergency_request()?
+            .ok_or_else(|| PassManError::AuthenticationFailed(
+                "Emergency access has not been requested, or the owner denied the request".to_string()
+            ))?;
+        let available_at = request.requested_at + chrono::Duration::days(access.wait_days.into());
+        if chrono::Utc::now() < available_at {
+            return Err(PassManError::AuthenticationFailed(format!(
+                "Emergency access is available from {}",
+                available_at.format("%Y-%m-%d %H:%M UTC"),
+            )));
+        }
+        
+        let key = header.unwrap_slot(KeySlotKind::Emergency, &parse_slot_key(emergency_key, KeySlotKind::Emergency)?)?;
+        self.open_vault_with_key(key)
     }
     
-    /// Export vault to a file
+    /// Read the header of a vault that grants emergency access
+    fn emergency_header(&self) -> Result<(VaultHeader, EmergencyAccess)> {
+        let no_access = || PassManError::AuthenticationFailed("Vault grants no emergency access".to_string());
+        let header = self.storage.read_header()?.ok_or_else(no_access)?;
+        let access = header.emergency.clone()
+            .filter(|_| header.has_slot(KeySlotKind::Emergency))
+            .ok_or_else(no_access)?;
+        Ok((header, access))
+    }
+    
+    /// Check whether unlocking the vault requires a keyfile
+    /// 
+    /// # Returns
+    /// True if a keyfile is needed alongside the master password
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file cannot be read
+    pub fn requires_keyfile(&self) -> Result<bool> {
+        Ok(self.storage.read_header()?.is_some_and(|header| header.keyfile))
+    }
+    
+    /// Set a new master password (and keyfile) for the open vault
+    /// 
+    /// Only the password key slot is rewritten; a recovery key stays valid.
+    /// Legacy vaults are upgraded to the versioned format on the way.
     /// 
     /// # Arguments
-    /// * `export_path` - Path where to save the exported vault
+    /// * `new_password` - The new master password
+    /// * `keyfile` - Keyfile contents to require from now on, if any
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if export fails
-    pub fn export_vault(&self, export_path: &std::path::Path) -> Result<()> {
+    /// Returns an error if vault is not open or save fails
+    pub fn change_master_password(&mut self, new_password: &str, keyfile: Option<&[u8]>) -> Result<()> {
         let vault = self.vault.as_ref()
-            .ok_or_else(|| PassManError::AuthenticationFailed("Vault not open".to_string()))?;
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault_key = self.auth.get_crypto_for_init().key().cloned()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let mut header = self.storage.read_header()?
+            .unwrap_or_else(|| VaultHeader::new(self.config.kdf_params(), false));
+        let salt = Salt::generate();
+        header.set_salt(&salt);
+        header.keyfile = keyfile.is_some();
+        let password_key = header.password_key(new_password, keyfile)?;
+        header.set_slot(KeySlotKind::Password, &vault_key, &password_key)?;
         
-        self.storage.export_vault(vault, self.auth.get_crypto()?, export_path)
+        self.auth.get_crypto_mut_for_init().set_key(vault_key, salt);
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())
     }
     
-    /// Import vault from a file
+    /// Open an existing vault with an already-derived key
+    /// 
+    /// Lets front-ends that cache the session key (keyring, agent) reopen
+    /// the vault without running key derivation again.
     /// 
     /// # Arguments
-    /// * `import_path` - Path to the vault file to import
+    /// * `key` - Key previously obtained from `session_key()`
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if import fails
-    pub fn import_vault(&mut self, import_path: &std::path::Path) -> Result<()> {
-        let vault = self.storage.import_vault(self.auth.get_crypto()?, import_path)?;
+    /// Returns an error if the vault doesn't exist or the key is wrong
+    pub fn open_vault_with_key(&mut self, key: SecureKey) -> Result<()> {
+        let salt = self.storage.read_salt()?;
+        let vault = self.storage.load_vault_with_key(&key)?;
+        
+        self.auth.get_crypto_mut_for_init().set_key(key, salt);
+        self.auth.start_session();
         self.vault = Some(vault);
-        self.save_vault()?;
+        
+        self.expire_trash();
+        let _ = self.upgrade_vault_file(None);
+        
         Ok(())
     }
     
-    /// Get vault file information
+    /// Purge accounts that have been in the trash longer than the vault's
+    /// `trash_retention_days`
+    /// 
+    /// Only the open copy changes; the purge is saved with the next change.
+    fn expire_trash(&mut self) {
+        let Some(vault) = self.vault.as_mut() else {
+            return;
+        };
+        let days = vault.metadata.settings.trash_retention_days;
+        if days > 0 {
+            vault.purge_trash(Some(chrono::Utc::now() - chrono::Duration::days(days.into())));
+        }
+    }
+    
+    /// Rewrite the open vault's file in the current format, if it is older
+    /// 
+    /// Legacy vaults get a password key slot, so they are only upgraded
+    /// when opened with the master password.
+    /// 
+    /// # Arguments
+    /// * `master_password` - The master password the vault was opened with, if any
     /// 
     /// # Returns
-    /// Vault file size and modification time
-    pub fn get_vault_info(&self) -> Result<(u64, Option<std::time::SystemTime>)> {
-        let size = self.storage.vault_size()?;
-        let modified = self.storage.vault_modified()?;
-        Ok((size, modified))
+    /// True if the file was upgraded
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or the file can't be saved
+    fn upgrade_vault_file(&mut self, master_password: Option<&str>) -> Result<bool> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault_key = self.auth.get_crypto_for_init().key().cloned()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let header = match (self.storage.read_header()?, master_password) {
+            (Some(mut header), _) => {
+                if !format::migrate_header(&mut header) {
+                    return Ok(false);
+                }
+                header
+            }
+            (None, Some(master_password)) => format::upgrade_legacy(&vault_key, self.config.kdf_params(), master_password)?,
+            (None, None) => return Ok(false),
+        };
+        
+        // Legacy saves write the salt held here, so it changes only once the header is on disk
+        self.storage.save_vault_with_header(vault, &header, self.auth.get_crypto_for_init())?;
+        self.auth.get_crypto_mut_for_init().set_key(vault_key, header.salt()?);
+        Ok(true)
     }
     
-    /// List all available vaults
+    /// Re-read the open vault from disk
+    /// 
+    /// Picks up changes other programs (the CLI, a sync tool) saved while
+    /// this instance had the vault open, so a later save doesn't overwrite
+    /// them.
     /// 
     /// # Returns
-    /// Vector of vault names
-    pub fn list_vaults() -> Result<Vec<String>> {
-        VaultStorage::list_vaults()
+    /// True if the vault on disk differed from the one in memory
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open or the file no longer
+    /// opens with the session key
+    pub fn reload_vault(&mut self) -> Result<bool> {
+        let key = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault = self.storage.load_vault_with_key(&key)?;
+        
+        let changed = self.vault.as_ref() != Some(&vault);
+        self.vault = Some(vault);
+        Ok(changed)
     }
     
-    /// Delete a vault
+    /// Get the derived key of the open vault
+    /// 
+    /// The key unlocks the vault without the master password, so callers
+    /// must keep it in protected storage and drop it on lock.
+    /// 
+    /// # Returns
+    /// A copy of the session key, or None if the vault is not open
+    pub fn session_key(&self) -> Option<SecureKey> {
+        if !self.is_vault_open() {
+            return None;
+        }
+        self.auth.get_crypto().ok()?.key().cloned()
+    }
+    
+    /// Read the search cache without opening the vault
+    /// 
+    /// Lets front-ends with a cached session key list and search accounts
+    /// without decrypting the whole vault (see `search_cache`).
     /// 
     /// # Arguments
-    /// * `vault_name` - Name of the vault to delete
+    /// * `key` - The vault key, e.g. from a cached session
+    /// 
+    /// # Returns
+    /// The cache, or None if there is none, it expired, it was built from
+    /// another version of the vault file, or the key doesn't open it
+    pub fn load_search_cache(&self, key: &SecureKey) -> Option<SearchCache> {
+        let cache = self.storage.read_search_cache(key).ok()??;
+        let digest = self.storage.vault_digest().ok()?;
+        cache.is_valid_for(&digest).then_some(cache)
+    }
+    
+    /// Rebuild the search cache of the open vault
+    /// 
+    /// Every save rewrites the cache on its own; this is for a cache that
+    /// was missing, expired, or stale when the vault was opened. Removes
+    /// the cache if the vault's `search_cache_minutes` setting is 0.
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if deletion fails
-    pub fn delete_vault(vault_name: &str) -> Result<()> {
-        VaultStorage::delete_vault(vault_name)
+    /// Returns an error if the vault is not open or the cache can't be written
+    pub fn refresh_search_cache(&self) -> Result<()> {
+        let key = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        self.storage.rebuild_search_cache(&key)
     }
     
-    /// Check if the current session is still valid
+    /// Close the current vault
+    /// 
+    /// The decrypted vault is zeroized as it drops, and so is the vault key.
+    pub fn close_vault(&mut self) {
+        self.vault = None;
+        self.auth.logout();
+    }
+    
+    /// Check if a vault is currently open
     /// 
     /// # Returns
-    /// True if the session is valid
-    pub fn is_session_valid(&self) -> bool {
-        self.auth.is_authenticated()
+    /// True if a vault is open and authenticated
+    pub fn is_vault_open(&self) -> bool {
+        self.vault.is_some() && self.auth.is_authenticated()
     }
     
-    /// Update session activity
-    pub fn update_activity(&mut self) {
-        self.auth.update_activity();
+    /// Get the name of the managed vault
+    pub fn vault_name(&self) -> &str {
+        &self.vault_name
     }
     
-    /// Get session information
+    /// Get vault metadata
     /// 
     /// # Returns
-    /// Session information or None if not authenticated
-    pub fn get_session_info(&self) -> Option<&crate::auth::AuthSession> {
-        self.auth.get_session()
+    /// Vault metadata or None if not open
+    pub fn get_vault_metadata(&self) -> Option<&VaultMetadata> {
+        self.vault.as_ref().map(|v| &v.metadata)
     }
     
-    /// Save the current vault to disk
+    /// Replace the settings of the open vault
+    /// 
+    /// # Arguments
+    /// * `settings` - The new settings
     /// 
     /// # Returns
     /// Unit on success
     /// 
     /// # Errors
-    /// Returns an error if save fails
-    fn save_vault(&self) -> Result<()> {
-        let vault = self.vault.as_ref()
-            .ok_or_else(|| PassManError::AuthenticationFailed("Vault not open".to_string()))?;
+    /// Returns an error if vault is not open, the settings are invalid, or
+    /// save fails
+    pub fn update_settings(&mut self, settings: VaultSettings) -> Result<()> {
+        if settings.auto_clear_clipboard && settings.clipboard_timeout == 0 {
+            return Err(PassManError::InvalidInput("Clipboard timeout must be greater than 0".to_string()));
+        }
+        self.generator.validate_options(&settings.default_password_options)?;
+        if settings.password_policy.as_ref().is_some_and(|policy| policy.min_strength > 100) {
+            return Err(PassManError::InvalidInput("Minimum strength score must be between 0 and 100".to_string()));
+        }
         
-        self.storage.save_vault(vault, self.auth.get_crypto_for_init())
-    }
-}
-
-impl Drop for PassMan {
-    fn drop(&mut self) {
-        // Clear sensitive data on drop
-        self.close_vault();
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let previous = std::mem::replace(&mut vault.metadata.settings, settings);
+        // Turning the history off also forgets what it already kept
+        let previous_history = if vault.metadata.settings.keep_generation_history {
+            None
+        } else {
+            Some(std::mem::take(&mut vault.generated_passwords))
+        };
+        
+        if let Err(e) = self.save_vault() {
+            if let Some(vault) = self.vault.as_mut() {
+                vault.metadata.settings = previous;
+                if let Some(history) = previous_history {
+                    vault.generated_passwords = history;
+                }
+            }
+            return Err(e);
+        }
+        
+        Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
     
-    #[test]
-    fn test_passman_creation() {
-        let passman = PassMan::new("test_vault").unwrap();
-        assert!(!passman.is_vault_open());
+    /// Check a password against the open vault's password policy
+    /// 
+    /// Front-ends can call this while the user types; `add_account`,
+    /// `add_account_entry`, and `update_account` run the same check.
+    /// 
+    /// # Arguments
+    /// * `password` - The password to check
+    /// 
+    /// # Returns
+    /// Unit if the vault has no policy or the password meets it
+    /// 
+    /// # Errors
+    /// Returns `PassManError::PolicyViolation` listing the broken rules, or
+    /// an error if vault is not open
+    pub fn check_password_policy(&self, password: &str) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        match vault.metadata.settings.password_policy {
+            Some(ref policy) => policy.check(password),
+            None => Ok(()),
+        }
     }
     
-    #[test]
-    fn test_vault_initialization() {
-        let mut passman = PassMan::new("test_vault").unwrap();
-        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
-        assert!(passman.is_vault_open());
+    /// Add a new account to the vault
+    /// 
+    /// # Arguments
+    /// * `name` - Account name
+    /// * `account_type` - Type of account
+    /// * `password` - Account password
+    /// * `url` - Optional URL
+    /// * `username` - Optional username
+    /// * `notes` - Optional notes
+    /// * `tags` - Optional tags
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open, the password breaks the
+    /// vault's password policy, or save fails
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_account(
+        &mut self,
+        name: String,
+        account_type: AccountType,
+        password: String,
+        url: Option<String>,
+        username: Option<String>,
+        notes: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        self.check_password_policy(&password)?;
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let mut account = Account::new(name, account_type, password);
+        account.url = url;
+        account.username = username;
+        account.notes = notes;
+        account.tags = tags;
+        
+        vault.add_account(account);
+        
+        // Save vault
+        self.save_vault()?;
+        
+        Ok(())
     }
     
-    #[test]
-    fn test_account_operations() {
-        let mut passman = PassMan::new("test_vault").unwrap();
-        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+    /// Add a fully constructed account to the vault
+    /// 
+    /// Useful when the account carries fields beyond what `add_account`
+    /// accepts (TOTP secret, custom fields); build it with `AccountBuilder`.
+    /// 
+    /// # Arguments
+    /// * `account` - The account to add
+    /// 
+    /// # Returns
+    /// The ID of the added account
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open, the password breaks the
+    /// vault's password policy, the card details, color, or icon are
+    /// invalid, or save fails
+    pub fn add_account_entry(&mut self, mut account: Account) -> Result<Uuid> {
+        if account.uses_password() {
+            self.check_password_policy(&account.password)?;
+        }
+        validate_custom_fields(&account.custom_fields)?;
+        if let Some(ref card) = account.card {
+            card.validate()?;
+        }
+        account.color = normalize_appearance(account.color.take(), appearance::normalize_color)?;
+        account.icon = normalize_appearance(account.icon.take(), appearance::normalize_icon)?;
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
         
-        // Add account
-        passman.add_account(
-            "Test Account".to_string(),
-            AccountType::Personal,
-            "test_password".to_string(),
-            Some("https://example.com".to_string()),
-            Some("testuser".to_string()),
-            Some("Test notes".to_string()),
-            vec!["test".to_string()],
-        ).unwrap();
+        let id = account.id;
+        vault.add_account(account);
         
-        let accounts = passman.get_all_accounts();
-        assert_eq!(accounts.len(), 1);
-        assert_eq!(accounts[0].name, "Test Account");
+        // Save vault
+        self.save_vault()?;
+        
+        Ok(id)
+    }
+    
+    /// Add several accounts with a single vault save
+    /// 
+    /// # Arguments
+    /// * `accounts` - The accounts to add
+    /// 
+    /// # Returns
+    /// The IDs of the added accounts, in order
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails; in that case
+    /// none of the accounts are kept
+    pub fn add_accounts(&mut self, accounts: Vec<Account>) -> Result<Vec<Uuid>> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let ids: Vec<Uuid> = accounts.iter().map(|a| a.id).collect();
+        for account in accounts {
+            vault.add_account(account);
+        }
+        
+        // Save vault once for the whole batch
+        if let Err(e) = self.save_vault() {
+            if let Some(vault) = self.vault.as_mut() {
+                for id in &ids {
+                    vault.remove_account(id);
+                    vault.deleted.remove(id);
+                }
+            }
+            return Err(e);
+        }
+        
+        Ok(ids)
+    }
+    
+    /// Merge imported accounts into the vault
+    /// 
+    /// Each account is checked against the vault (and the accounts merged
+    /// before it) for a duplicate with the same name and username, which
+    /// `policy` then resolves. The vault is saved once at the end.
+    /// 
+    /// # Arguments
+    /// * `accounts` - The imported accounts
+    /// * `policy` - How duplicates are handled
+    /// * `dry_run` - Compute the summary without changing the vault
+    /// * `progress` - Called with (processed, total) after each account
+    /// 
+    /// # Returns
+    /// Counts of added, overwritten, merged, and skipped accounts
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails; in that case
+    /// the vault is left unchanged
+    pub fn merge_accounts(
+        &mut self,
+        accounts: Vec<Account>,
+        policy: DedupePolicy,
+        dry_run: bool,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<ImportSummary> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        // Work on a copy so a failed save or a dry run leaves the vault as it was
+        let mut merged = vault.clone();
+        let dedupe_key = |account: &Account| {
+            (account.name.to_lowercase(), account.username.as_deref().unwrap_or_default().to_lowercase())
+        };
+        let mut summary = ImportSummary::default();
+        let total = accounts.len();
+        for (index, mut account) in accounts.into_iter().enumerate() {
+            // The name index also covers accounts added earlier in the batch
+            let key = dedupe_key(&account);
+            let duplicate = merged.get_accounts_by_name(&account.name)
+                .into_iter()
+                .find(|existing| dedupe_key(existing) == key)
+                .map(|existing| existing.id);
+            match (duplicate, policy) {
+                (Some(_), DedupePolicy::Skip) => summary.skipped += 1,
+                (Some(id), DedupePolicy::Overwrite) => {
+                    merged.update_account(&id, |current| {
+                        account.id = current.id;
+                        account.created_at = current.created_at;
+                        account.updated_at = chrono::Utc::now();
+                        *current = account;
+                    });
+                    summary.overwritten += 1;
+                }
+                (Some(id), DedupePolicy::Merge) => {
+                    merged.update_account(&id, |current| merge_fields(current, account));
+                    summary.merged += 1;
+                }
+                _ => {
+                    // Re-importing an export of this vault reuses its IDs
+                    if merged.accounts.contains_key(&account.id) {
+                        account.id = Uuid::new_v4();
+                    }
+                    merged.add_account(account);
+                    summary.added += 1;
+                }
+            }
+            progress(index + 1, total);
+        }
+        
+        if dry_run || summary.added + summary.overwritten + summary.merged == 0 {
+            return Ok(summary);
+        }
+        
+        merged.metadata.last_modified = chrono::Utc::now();
+        let previous = self.vault.replace(merged);
+        if let Err(e) = self.save_vault() {
+            self.vault = previous;
+            return Err(e);
+        }
+        
+        Ok(summary)
+    }
+    
+    /// Find accounts that look like duplicates of each other
+    /// 
+    /// Accounts are grouped by the host of their URL (or their name, if
+    /// they have no URL) and their username, ignoring case. Canaries are
+    /// left out.
+    /// 
+    /// # Returns
+    /// Groups of two or more accounts, each most recently updated first;
+    /// empty if the vault is locked
+    pub fn find_duplicates(&self) -> Vec<Vec<&Account>> {
+        let Some(vault) = self.vault.as_ref() else {
+            return Vec::new();
+        };
+        let mut groups: HashMap<(String, String), Vec<&Account>> = HashMap::new();
+        for account in vault.accounts.values().filter(|account| !account.canary) {
+            let site = account.url.as_deref()
+                .and_then(url_host)
+                .unwrap_or_else(|| format!("name:{}", account.name.trim().to_lowercase()));
+            let username = account.username.as_deref().unwrap_or_default().trim().to_lowercase();
+            groups.entry((site, username)).or_default().push(account);
+        }
+        
+        let mut duplicates: Vec<Vec<&Account>> = groups.into_values().filter(|group| group.len() > 1).collect();
+        for group in &mut duplicates {
+            group.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.name.cmp(&b.name)));
+        }
+        duplicates.sort_by_cached_key(|group| group[0].name.to_lowercase());
+        duplicates
+    }
+    
+    /// Merge duplicate accounts into one
+    /// 
+    /// `keep` keeps its values and gains what the others add: their tags
+    /// and custom fields, their notes, fields it has empty, and their
+    /// passwords if different (as hidden "Previous password" fields). It
+    /// takes the earliest creation time and the combined access history.
+    /// The others move to the trash.
+    /// 
+    /// # Arguments
+    /// * `keep` - ID of the account to keep
+    /// * `others` - IDs of its duplicates
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, an account isn't found,
+    /// `keep` is among `others`, or save fails; in that case the vault is
+    /// left unchanged
+    pub fn merge_duplicates(&mut self, keep: Uuid, others: &[Uuid]) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if others.contains(&keep) {
+            return Err(PassManError::InvalidInput("Can't merge an account into itself".to_string()));
+        }
+        let not_found = |id: &Uuid| PassManError::AccountNotFound(format!("Account with ID {} not found", id));
+        
+        // Work on a copy so a failed save leaves the vault as it was
+        let mut merged = vault.clone();
+        merged.get_account(&keep).ok_or_else(|| not_found(&keep))?;
+        for id in others {
+            let duplicate = merged.get_account(id).cloned().ok_or_else(|| not_found(id))?;
+            merged.update_account(&keep, |current| merge_duplicate(current, duplicate));
+            merged.trash_account(id);
+        }
+        
+        let previous = self.vault.replace(merged);
+        if let Err(e) = self.save_vault() {
+            self.vault = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+    
+    /// Import another password manager's file into the vault
+    /// 
+    /// Accounts with the same name and username as an existing one are
+    /// skipped. A file whose signature doesn't match the vault's signing
+    /// key is refused.
+    /// 
+    /// # Arguments
+    /// * `importer` - The file's format
+    /// * `path` - The file
+    /// 
+    /// # Returns
+    /// Counts of added and skipped accounts, and messages for entries that
+    /// couldn't be read
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, the file can't be read
+    /// (or is password-protected; see `import_encrypted_from`), or the
+    /// vault can't be saved
+    #[cfg(feature = "importers")]
+    pub fn import_from(&mut self, importer: Importer, path: &std::path::Path) -> Result<(ImportSummary, Vec<String>)> {
+        self.import_file_with(importer, path, None)
+    }
+    
+    /// Import another password manager's password-protected file into the vault
+    /// 
+    /// Same as `import_from`, with the file's password.
+    /// 
+    /// # Errors
+    /// Same as `import_from`, plus an authentication error if the password
+    /// is wrong
+    #[cfg(feature = "importers")]
+    pub fn import_encrypted_from(&mut self, importer: Importer, path: &std::path::Path, password: &str) -> Result<(ImportSummary, Vec<String>)> {
+        self.import_file_with(importer, path, Some(password))
+    }
+    
+    #[cfg(feature = "importers")]
+    fn import_file_with(&mut self, importer: Importer, path: &std::path::Path, password: Option<&str>) -> Result<(ImportSummary, Vec<String>)> {
+        if self.vault.is_none() {
+            return Err(PassManError::VaultLocked("vault is not open".to_string()));
+        }
+        self.require_valid_signature(path)?;
+        
+        let data = std::fs::read(path)
+            .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", path.display(), e)))?;
+        let plugin = importer.plugin();
+        let imported = match password {
+            Some(password) => plugin.import_encrypted(&mut data.as_slice(), password)?,
+            None if plugin.is_encrypted(&data) => {
+                return Err(PassManError::InvalidInput(format!("{} is password-protected", path.display())));
+            }
+            None => plugin.import(&mut data.as_slice())?,
+        };
+        
+        let summary = self.merge_accounts(imported.accounts, DedupePolicy::Skip, false, &mut |_, _| {})?;
+        Ok((summary, imported.errors))
+    }
+    
+    /// Update an existing account, replacing its main fields
+    /// 
+    /// To change only some fields, use `patch_account`.
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `name` - New account name
+    /// * `account_type` - New account type
+    /// * `password` - New password
+    /// * `url` - New URL
+    /// * `username` - New username
+    /// * `notes` - New notes
+    /// * `tags` - New tags
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if account not found, vault not open, or a changed
+    /// password breaks the vault's password policy
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_account(
+        &mut self,
+        id: Uuid,
+        name: String,
+        account_type: AccountType,
+        password: String,
+        url: Option<String>,
+        username: Option<String>,
+        notes: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        // Unchanged passwords stay valid when the policy is tightened later
+        if self.get_account(id).is_some_and(|account| account.password != password) {
+            self.check_password_policy(&password)?;
+        }
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        vault.update_account(&id, |account| {
+            account.name = name;
+            account.account_type = account_type;
+            account.password = password;
+            account.url = url;
+            account.username = username;
+            account.notes = notes;
+            account.tags = tags;
+            account.updated_at = chrono::Utc::now();
+        }).ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        // Save vault
+        self.save_vault()?;
+        
+        Ok(())
+    }
+    
+    /// Change some fields of an account
+    /// 
+    /// Only the fields set in the patch change, so callers don't need to
+    /// resend the whole account.
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `patch` - The fields to change
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if a changed password breaks the vault's password
+    /// policy, the custom fields, card details, autotype sequence, color,
+    /// or icon are invalid, the account is not found, the vault is not open, or save fails
+    pub fn patch_account(&mut self, id: Uuid, mut patch: AccountPatch) -> Result<()> {
+        let account = self.get_account(id)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        if let Some(ref password) = patch.password {
+            // Unchanged passwords stay valid when the policy is tightened later
+            if account.password != *password {
+                self.check_password_policy(password)?;
+            }
+        }
+        if let Some(ref fields) = patch.custom_fields {
+            validate_custom_fields(fields)?;
+        }
+        if let Some(Some(ref card)) = patch.card {
+            card.validate()?;
+        }
+        if let Some(ref mut sequence) = patch.autotype {
+            *sequence = sequence.take().filter(|sequence| !sequence.trim().is_empty());
+            if let Some(ref sequence) = sequence {
+                autotype::validate_sequence(sequence)?;
+            }
+        }
+        if let Some(ref mut color) = patch.color {
+            *color = normalize_appearance(color.take(), appearance::normalize_color)?;
+        }
+        if let Some(ref mut icon) = patch.icon {
+            *icon = normalize_appearance(icon.take(), appearance::normalize_icon)?;
+        }
+        if patch.is_empty() {
+            return Ok(());
+        }
+        
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, |account| {
+            patch.apply(account);
+            account.updated_at = chrono::Utc::now();
+        });
+        
+        self.save_vault()
+    }
+    
+    /// Mark an account as a favorite, or unmark it if it already is one
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// 
+    /// # Returns
+    /// Whether the account is now a favorite
+    /// 
+    /// # Errors
+    /// Returns an error if the account is not found, vault not open, or
+    /// save fails
+    pub fn toggle_favorite(&mut self, id: Uuid) -> Result<bool> {
+        let is_favorite = !self.get_account(id)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?
+            .is_favorite;
+        let patch = AccountPatch { is_favorite: Some(is_favorite), ..AccountPatch::default() };
+        self.patch_account(id, patch)?;
+        Ok(is_favorite)
+    }
+    
+    /// Set or clear the custom autotype sequence of an account
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `sequence` - The sequence, or None to use the default
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the sequence is invalid, the account is not
+    /// found, or vault not open
+    pub fn set_autotype_sequence(&mut self, id: Uuid, sequence: Option<String>) -> Result<()> {
+        let sequence = sequence.filter(|sequence| !sequence.trim().is_empty());
+        if let Some(ref sequence) = sequence {
+            autotype::validate_sequence(sequence)?;
+        }
+        
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, |account| {
+            account.autotype = sequence;
+            account.updated_at = chrono::Utc::now();
+        }).ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Replace the custom fields of an account
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `fields` - The account's new custom fields, in display order
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if a field has no name, two fields share a name
+    /// (ignoring case), the account is not found, the vault is not open,
+    /// or save fails
+    pub fn set_custom_fields(&mut self, id: Uuid, fields: Vec<CustomField>) -> Result<()> {
+        validate_custom_fields(&fields)?;
+        
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, |account| {
+            let mut previous = std::mem::replace(&mut account.custom_fields, fields);
+            previous.zeroize();
+            account.updated_at = chrono::Utc::now();
+        }).ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Record that an account's secrets were used (copied, shown, or typed)
+    /// 
+    /// Bumps the account's access count and last access time, which rank
+    /// `get_recently_used`, `get_most_used`, and the `LastUsed` and
+    /// `MostUsed` sort orders. The account's `updated_at` is left alone.
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the account is not found, vault not open, or
+    /// save fails
+    pub fn record_access(&mut self, id: Uuid) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, Account::mark_accessed)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Mark or unmark an account as a canary (see `audit::canaries`)
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `canary` - Whether the account is a canary
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if account not found or vault not open
+    pub fn set_canary(&mut self, id: Uuid, canary: bool) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, |account| {
+            account.canary = canary;
+            account.updated_at = chrono::Utc::now();
+        }).ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Set or clear what an account's website accepts as a password
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `policy` - The site's policy, or None to fall back to the policy
+    ///   of the account's domain
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the policy can't be met, the account is not
+    /// found, or vault not open
+    pub fn set_site_policy(&mut self, id: Uuid, policy: Option<SitePolicy>) -> Result<()> {
+        if let Some(ref policy) = policy {
+            policy.validate()?;
+        }
+        
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.update_account(&id, |account| {
+            account.site_policy = policy;
+            account.updated_at = chrono::Utc::now();
+        }).ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Set or clear what a website accepts as a password, for every
+    /// account on the domain and its subdomains
+    /// 
+    /// # Arguments
+    /// * `domain` - Domain or URL of the site
+    /// * `policy` - The site's policy, or None to remove it
+    /// 
+    /// # Returns
+    /// The domain the policy is stored under (lowercase, without `www.`)
+    /// 
+    /// # Errors
+    /// Returns an error if the domain is invalid, the policy can't be met,
+    /// the vault is not open, or save fails
+    pub fn set_domain_policy(&mut self, domain: &str, policy: Option<SitePolicy>) -> Result<String> {
+        let domain = url_host(domain)
+            .ok_or_else(|| PassManError::InvalidInput(format!("Invalid domain: '{}'", domain)))?;
+        if let Some(ref policy) = policy {
+            policy.validate()?;
+        }
+        
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let policies = &mut vault.metadata.settings.site_policies;
+        match policy {
+            Some(policy) => {
+                policies.insert(domain.clone(), policy);
+            }
+            None => {
+                policies.remove(&domain);
+            }
+        }
+        
+        self.save_vault()?;
+        Ok(domain)
+    }
+    
+    /// Get the site policy that applies to an account (see `Vault::site_policy`)
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// 
+    /// # Returns
+    /// The policy, or None if the site has none
+    /// 
+    /// # Errors
+    /// Returns an error if the account is not found or vault not open
+    pub fn site_policy(&self, id: Uuid) -> Result<Option<&SitePolicy>> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let account = vault.get_account(&id)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        Ok(vault.site_policy(account))
+    }
+    
+    /// Generate a new password for an account, following its site policy
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// * `options` - The options the user chose; adapted to the site's
+    ///   policy if it has one
+    /// 
+    /// # Returns
+    /// The generated password (the account isn't changed)
+    /// 
+    /// # Errors
+    /// Returns an error if the account is not found, the vault is not
+    /// open, or the policy can't be met
+    pub fn generate_account_password(&mut self, id: Uuid, options: &PasswordOptions) -> Result<String> {
+        match self.site_policy(id)?.cloned() {
+            Some(policy) => self.generator.generate_for_site(options, &policy),
+            None => self.generator.generate(options),
+        }
+    }
+    
+    /// Delete an account from the vault
+    /// 
+    /// The account moves to the trash, from where `restore_account` brings
+    /// it back; it is gone for good once the trash is purged.
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID to delete
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if account not found or vault not open
+    pub fn delete_account(&mut self, id: Uuid) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        if !vault.trash_account(&id) {
+            return Err(PassManError::AccountNotFound(format!("Account with ID {} not found", id)));
+        }
+        
+        // Save vault
+        self.save_vault()?;
+        
+        Ok(())
+    }
+    
+    /// List the accounts in the trash
+    /// 
+    /// # Returns
+    /// The deleted accounts, most recently deleted first
+    pub fn list_trash(&self) -> Vec<&TrashedAccount> {
+        let Some(vault) = self.vault.as_ref() else {
+            return Vec::new();
+        };
+        let mut trashed: Vec<&TrashedAccount> = vault.trash.values().collect();
+        trashed.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted_at));
+        trashed
+    }
+    
+    /// Move an account from the trash back into the vault
+    /// 
+    /// # Arguments
+    /// * `id` - ID of the deleted account
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, the account isn't in the
+    /// trash, or save fails
+    pub fn restore_account(&mut self, id: Uuid) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        vault.restore_account(&id)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("No account with ID {} in the trash", id)))?;
+        
+        self.save_vault()
+    }
+    
+    /// Permanently delete accounts in the trash
+    /// 
+    /// # Arguments
+    /// * `id` - The one account to purge; the whole trash if None
+    /// 
+    /// # Returns
+    /// Number of accounts purged
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, the account isn't in the
+    /// trash, or save fails
+    pub fn purge_trash(&mut self, id: Option<Uuid>) -> Result<usize> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let purged = match id {
+            Some(id) => {
+                let mut trashed = vault.trash.remove(&id)
+                    .ok_or_else(|| PassManError::AccountNotFound(format!("No account with ID {} in the trash", id)))?;
+                trashed.account.zeroize();
+                vault.metadata.last_modified = chrono::Utc::now();
+                1
+            }
+            None => vault.purge_trash(None),
+        };
+        
+        if purged > 0 {
+            self.save_vault()?;
+        }
+        Ok(purged)
+    }
+    
+    /// Get an account by ID
+    /// 
+    /// # Arguments
+    /// * `id` - Account ID
+    /// 
+    /// # Returns
+    /// Account reference or None if not found
+    pub fn get_account(&self, id: Uuid) -> Option<&Account> {
+        self.vault.as_ref()?.get_account(&id)
+    }
+    
+    /// Get all accounts in the vault
+    /// 
+    /// # Returns
+    /// Vector of account references
+    pub fn get_all_accounts(&self) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_all_accounts())
+    }
+    
+    /// Search accounts by name
+    /// 
+    /// # Arguments
+    /// * `query` - Search query
+    /// 
+    /// # Returns
+    /// Vector of matching account references
+    pub fn search_accounts(&self, query: &str) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.search_accounts(query))
+    }
+    
+    /// Get the most recently used accounts
+    /// 
+    /// # Arguments
+    /// * `limit` - Maximum number of accounts
+    /// 
+    /// # Returns
+    /// Accessed, unarchived accounts, most recently used first
+    pub fn get_recently_used(&self, limit: usize) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_recently_used(limit))
+    }
+    
+    /// Get the most often used accounts
+    /// 
+    /// # Arguments
+    /// * `limit` - Maximum number of accounts
+    /// 
+    /// # Returns
+    /// Accessed, unarchived accounts, most used first
+    pub fn get_most_used(&self, limit: usize) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_most_used(limit))
+    }
+    
+    /// Get the accounts whose password expires soon or already expired
+    /// 
+    /// # Arguments
+    /// * `within_days` - How many days ahead to look
+    /// 
+    /// # Returns
+    /// Unarchived accounts with an expiry date or rotation interval that
+    /// falls due within `within_days`, soonest first
+    pub fn get_expiring_accounts(&self, within_days: u32) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_expiring_accounts(within_days))
+    }
+    
+    /// Get accounts with exactly this name (case-insensitive)
+    /// 
+    /// # Arguments
+    /// * `name` - Account name
+    /// 
+    /// # Returns
+    /// Vector of matching account references
+    pub fn get_accounts_by_name(&self, name: &str) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_by_name(name))
+    }
+    
+    /// Get accounts whose URL belongs to a site
+    /// 
+    /// # Arguments
+    /// * `url` - URL or host of the site; accounts for a parent domain match too
+    /// 
+    /// # Returns
+    /// Vector of matching account references, most specific host first
+    pub fn get_accounts_for_url(&self, url: &str) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_for_url(url))
+    }
+    
+    /// Get accounts by type
+    /// 
+    /// # Arguments
+    /// * `account_type` - Account type to filter by
+    /// 
+    /// # Returns
+    /// Vector of matching account references
+    pub fn get_accounts_by_type(&self, account_type: &AccountType) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_by_type(account_type))
+    }
+    
+    /// Get the custom categories defined in the vault
+    /// 
+    /// # Returns
+    /// Category names in the order they were added
+    pub fn get_categories(&self) -> &[String] {
+        self.vault.as_ref().map_or(&[], |v| v.categories.as_slice())
+    }
+    
+    /// Define a custom category for accounts (`AccountType::Custom`)
+    /// 
+    /// Adding an account with a new custom category defines it as well.
+    /// 
+    /// # Arguments
+    /// * `name` - Category name
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, the name is empty, names
+    /// a built-in type, or is taken, or save fails
+    pub fn add_category(&mut self, name: &str) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        vault.add_category(name)?;
+        self.save_vault()
+    }
+    
+    /// Rename a custom category, moving its accounts along
+    /// 
+    /// # Arguments
+    /// * `name` - Current name (case-insensitive)
+    /// * `new_name` - New name
+    /// 
+    /// # Returns
+    /// Number of accounts moved
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, there is no such
+    /// category, the new name is not available, or save fails
+    pub fn rename_category(&mut self, name: &str, new_name: &str) -> Result<usize> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let moved = vault.rename_category(name, new_name)?;
+        self.save_vault()?;
+        Ok(moved)
+    }
+    
+    /// Delete a custom category; its accounts become `AccountType::Other`
+    /// 
+    /// # Arguments
+    /// * `name` - Category name (case-insensitive)
+    /// 
+    /// # Returns
+    /// Number of accounts moved to `Other`
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, there is no such
+    /// category, or save fails
+    pub fn remove_category(&mut self, name: &str) -> Result<usize> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let moved = vault.remove_category(name)?;
+        self.save_vault()?;
+        Ok(moved)
+    }
+    
+    /// Get accounts by tag
+    /// 
+    /// # Arguments
+    /// * `tag` - Tag to filter by
+    /// 
+    /// # Returns
+    /// Vector of matching account references
+    pub fn get_accounts_by_tag(&self, tag: &str) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.get_accounts_by_tag(tag))
+    }
+    
+    /// List accounts with filtering, sorting, and pagination
+    /// 
+    /// # Arguments
+    /// * `query` - Filter, sort, and pagination options
+    /// 
+    /// # Returns
+    /// Vector of matching account references
+    pub fn query_accounts(&self, query: &AccountQuery) -> Vec<&Account> {
+        self.vault.as_ref().map_or_else(Vec::new, |v| v.query_accounts(query))
+    }
+    
+    /// Audit the open vault's passwords
+    /// 
+    /// # Arguments
+    /// * `options` - Audit thresholds
+    /// * `is_breached` - Breach check for a password, if breach data is available
+    /// 
+    /// # Returns
+    /// Reused, weak, stale, and breached accounts plus an overall score
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open
+    pub fn health_report(&self, options: &AuditOptions, is_breached: Option<&dyn Fn(&str) -> bool>) -> Result<HealthReport> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        Ok(audit::audit(&vault.get_all_accounts(), options, is_breached))
+    }
+    
+    /// Describe the open vault's canary accounts for external monitoring
+    /// 
+    /// # Returns
+    /// The canaries, with their passwords as SHA-1 hashes
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open
+    pub fn canaries(&self) -> Result<Vec<Canary>> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        Ok(audit::canaries(&vault.get_all_accounts()))
+    }
+    
+    /// Generate a new password
+    /// 
+    /// # Arguments
+    /// * `options` - Password generation options
+    /// 
+    /// # Returns
+    /// Generated password string
+    /// 
+    /// # Errors
+    /// Returns an error if generation fails
+    pub fn generate_password(&mut self, options: &PasswordOptions) -> Result<String> {
+        self.generator.generate(options)
+    }
+    
+    /// Generate several passwords to choose from
+    /// 
+    /// # Arguments
+    /// * `options` - Password generation options
+    /// * `count` - Number of passwords, from 1 to 100
+    /// 
+    /// # Returns
+    /// The passwords with their strength scores
+    /// 
+    /// # Errors
+    /// Returns an error if the options or count are invalid
+    pub fn generate_password_batch(&mut self, options: &PasswordOptions, count: usize) -> Result<Vec<PasswordCandidate>> {
+        self.generator.generate_batch(options, count)
+    }
+    
+    /// Generate a passphrase
+    /// 
+    /// # Arguments
+    /// * `options` - Passphrase generation options
+    /// * `word_list` - Words to choose from
+    /// 
+    /// # Returns
+    /// Generated passphrase
+    /// 
+    /// # Errors
+    /// Returns an error if the options or word list are invalid
+    pub fn generate_passphrase(&mut self, options: &PassphraseOptions, word_list: &[&str]) -> Result<String> {
+        self.generator.generate_passphrase_with(options, word_list)
+    }
+    
+    /// Generate a username that says nothing about its owner
+    /// 
+    /// # Arguments
+    /// * `options` - Username generation options
+    /// 
+    /// # Returns
+    /// Generated username, such as `quiet_otter_42`
+    /// 
+    /// # Errors
+    /// Returns an error if the options are invalid
+    pub fn generate_username(&mut self, options: &UsernameOptions) -> Result<String> {
+        self.generator.generate_username(options)
+    }
+    
+    /// Generate a simple password
+    /// 
+    /// # Arguments
+    /// * `length` - Password length
+    /// 
+    /// # Returns
+    /// Generated password string
+    pub fn generate_simple_password(&mut self, length: usize) -> Result<String> {
+        self.generator.generate_simple(length)
+    }
+    
+    /// Generate a strong password
+    /// 
+    /// # Arguments
+    /// * `length` - Password length
+    /// 
+    /// # Returns
+    /// Generated password string
+    pub fn generate_strong_password(&mut self, length: usize) -> Result<String> {
+        self.generator.generate_strong(length)
+    }
+    
+    /// Remember a generated password in the vault's generation history
+    /// 
+    /// Does nothing unless the vault's `keep_generation_history` setting is
+    /// on. Only the newest `GENERATION_HISTORY_LIMIT` passwords are kept.
+    /// 
+    /// # Arguments
+    /// * `password` - The generated password
+    /// 
+    /// # Returns
+    /// Whether the password was recorded
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails
+    pub fn record_generated_password(&mut self, password: &str) -> Result<bool> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if !vault.metadata.settings.keep_generation_history {
+            return Ok(false);
+        }
+        
+        vault.generated_passwords.insert(0, GeneratedPassword {
+            password: password.to_string(),
+            generated_at: chrono::Utc::now(),
+        });
+        vault.generated_passwords.truncate(GENERATION_HISTORY_LIMIT);
+        
+        self.save_vault()?;
+        Ok(true)
+    }
+    
+    /// Get the generation history stored in the vault
+    /// 
+    /// # Returns
+    /// Recently generated passwords, newest first
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open
+    pub fn generation_history(&self) -> Result<&[GeneratedPassword]> {
+        self.vault.as_ref()
+            .map(|vault| vault.generated_passwords.as_slice())
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))
+    }
+    
+    /// Forget the generation history stored in the vault
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails
+    pub fn clear_generation_history(&mut self) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if vault.generated_passwords.is_empty() {
+            return Ok(());
+        }
+        vault.generated_passwords.clear();
+        
+        self.save_vault()
+    }
+    
+    /// Calculate password strength
+    /// 
+    /// # Arguments
+    /// * `password` - Password to analyze
+    /// 
+    /// # Returns
+    /// Strength score (0-100)
+    pub fn calculate_password_strength(&self, password: &str) -> u8 {
+        self.generator.calculate_strength(password)
+    }
+    
+    /// Estimate password strength with the crack time and weaknesses found
+    /// 
+    /// # Arguments
+    /// * `password` - Password to analyze
+    /// 
+    /// # Returns
+    /// Strength score, estimated crack time, and patterns found
+    pub fn estimate_password_strength(&self, password: &str) -> StrengthEstimate {
+        self.generator.estimate_strength(password)
+    }
+    
+    /// Get password strength description
+    /// 
+    /// # Arguments
+    /// * `score` - Strength score
+    /// 
+    /// # Returns
+    /// Human-readable strength description
+    pub fn get_password_strength_description(&self, score: u8) -> &'static str {
+        self.generator.get_strength_description(score)
+    }
+    
+    /// Export vault to a file
+    /// 
+    /// The export is signed when the vault has a signing key.
+    /// 
+    /// # Arguments
+    /// * `export_path` - Path where to save the exported vault
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if export fails
+    pub fn export_vault(&self, export_path: &std::path::Path) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        self.storage.export_vault(vault, self.auth.get_crypto()?, export_path)?;
+        self.sign_file(export_path)?;
+        Ok(())
+    }
+    
+    /// Import vault from a file
+    /// 
+    /// # Arguments
+    /// * `import_path` - Path to the vault file to import
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if import fails or the file's signature doesn't
+    /// match the vault's signing key
+    pub fn import_vault(&mut self, import_path: &std::path::Path) -> Result<()> {
+        self.require_valid_signature(import_path)?;
+        let vault = self.storage.import_vault(self.auth.get_crypto()?, import_path)?;
+        self.vault = Some(vault);
+        self.save_vault()?;
+        Ok(())
+    }
+    
+    /// Export the vault to a portable file with its own password
+    /// 
+    /// Unlike `export_vault`, the file doesn't depend on this vault's key:
+    /// it carries its own salt and key derivation parameters (this vault's)
+    /// and opens anywhere with `import_portable` and the export password.
+    /// The export is signed when the vault has a signing key.
+    /// 
+    /// # Arguments
+    /// * `export_path` - Path where to save the export
+    /// * `password` - The export password, independent of the master password
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is locked, the password is empty, or
+    /// the export fails
+    pub fn export_portable(&self, export_path: &std::path::Path, password: &str) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if password.is_empty() {
+            return Err(PassManError::InvalidInput("The export password can't be empty".to_string()));
+        }
+        
+        let kdf = self.storage.read_header()?.map(|header| header.kdf).unwrap_or_default();
+        self.storage.export_portable(vault, password, kdf, export_path)?;
+        self.sign_file(export_path)?;
+        Ok(())
+    }
+    
+    /// Replace the vault with a portable export
+    /// 
+    /// The export's own encryption authenticates it, so its signature isn't
+    /// checked: it was usually made by another vault's signing key.
+    /// 
+    /// # Arguments
+    /// * `import_path` - Path to the export
+    /// * `password` - The export password
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an authentication error if the password is wrong, or another
+    /// error if the vault is locked or the file can't be read as an export
+    pub fn import_portable(&mut self, import_path: &std::path::Path, password: &str) -> Result<()> {
+        if self.vault.is_none() {
+            return Err(PassManError::VaultLocked("vault is not open".to_string()));
+        }
+        let vault = self.storage.import_portable(password, import_path)?;
+        self.vault = Some(vault);
+        self.save_vault()?;
+        Ok(())
+    }
+    
+    /// Back up the vault file now
+    /// 
+    /// # Returns
+    /// The new backup
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist or the copy fails
+    pub fn create_backup(&self) -> Result<BackupInfo> {
+        if !self.storage.vault_exists() {
+            return Err(PassManError::VaultNotFound(self.vault_name.clone()));
+        }
+        self.storage.create_backup(self.signing_key())?;
+        self.storage.list_backups()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| PassManError::StorageError("Backup was not created".to_string()))
+    }
+    
+    /// List the backups of the vault
+    /// 
+    /// # Returns
+    /// The backups, newest first
+    /// 
+    /// # Errors
+    /// Returns an error if the backup directory can't be read
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        self.storage.list_backups()
+    }
+    
+    /// Replace the vault with one of its backups
+    /// 
+    /// An open vault is reloaded from the restored file. If the backup
+    /// predates a master password change it won't open with the current
+    /// session, so the vault is closed and must be unlocked again.
+    /// 
+    /// A backup whose signature doesn't match the vault's signing key is
+    /// refused; unsigned backups are restored (see `verify_backup`).
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// 
+    /// # Returns
+    /// True if the vault is still open after the restore
+    /// 
+    /// # Errors
+    /// Returns an error if the backup doesn't exist, has an invalid
+    /// signature, or can't be restored
+    pub fn restore_backup(&mut self, timestamp: &str) -> Result<bool> {
+        if self.verify_backup(timestamp)? == SignatureStatus::Invalid {
+            return Err(tampered_error(&format!("backup from {}", timestamp)));
+        }
+        self.storage.restore_backup(timestamp, self.signing_key())?;
+        if !self.is_vault_open() {
+            return Ok(false);
+        }
+        if self.reload_vault().is_err() {
+            self.close_vault();
+            return Ok(false);
+        }
+        Ok(true)
+    }
+    
+    /// Compare a backup with the open vault
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// 
+    /// # Returns
+    /// What changed from the backup to the open vault, secrets masked
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open, or the backup doesn't exist,
+    /// has an invalid signature, or predates a change of the vault key
+    pub fn diff_backup(&self, timestamp: &str) -> Result<VaultDiff> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let key = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        if self.verify_backup(timestamp)? == SignatureStatus::Invalid {
+            return Err(tampered_error(&format!("backup from {}", timestamp)));
+        }
+        
+        let backup = self.storage.load_backup_with_key(timestamp, &key).map_err(|_| {
+            PassManError::AuthenticationFailed(format!(
+                "The backup from {} uses an older vault key and can't be opened with this session",
+                timestamp,
+            ))
+        })?;
+        Ok(diff(&backup, vault))
+    }
+    
+    /// Merge another copy of this vault into the open vault
+    /// 
+    /// Use this for the conflict copies sync tools leave behind (such as
+    /// Syncthing's `<name>.sync-conflict-<date>.vault`): edits to different
+    /// fields of an account are both kept, and for the same field the
+    /// later edit wins (see `sync`). The copy must be encrypted with this
+    /// vault's key, as copies of the same vault are.
+    /// 
+    /// # Arguments
+    /// * `path` - The other copy
+    /// * `dry_run` - Report what would change without saving
+    /// 
+    /// # Returns
+    /// What the merge took from the other copy
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open, the copy can't be read or
+    /// decrypted with this vault's key, or save fails
+    #[cfg(feature = "sync")]
+    pub fn merge_vault_file(&mut self, path: &std::path::Path, dry_run: bool) -> Result<MergeSummary> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let key = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        let theirs = self.storage.load_copy_with_key(path, &key).map_err(|e| match e {
+            PassManError::StorageError(_) | PassManError::VaultCorrupted(_) => e,
+            _ => PassManError::AuthenticationFailed(format!(
+                "{} is not a copy of this vault, or uses an older vault key",
+                path.display(),
+            )),
+        })?;
+        let (merged, summary) = sync::merge(vault, &theirs);
+        if dry_run || summary.is_empty() {
+            return Ok(summary);
+        }
+        
+        let previous = self.vault.replace(merged);
+        if let Err(e) = self.save_vault() {
+            self.vault = previous;
+            return Err(e);
+        }
+        Ok(summary)
+    }
+    
+    /// Check the signature of a backup against the vault's signing key
+    /// 
+    /// # Arguments
+    /// * `timestamp` - Timestamp of the backup, as listed by `list_backups`
+    /// 
+    /// # Returns
+    /// The signature status (`NoKey` while the vault is locked or has no
+    /// signing key)
+    /// 
+    /// # Errors
+    /// Returns an error if the backup doesn't exist or can't be read
+    pub fn verify_backup(&self, timestamp: &str) -> Result<SignatureStatus> {
+        self.storage.check_backup_signature(timestamp, self.signing_public_key())
+    }
+    
+    /// Check the vault file, its backups, and the files next to it
+    /// 
+    /// See `fsck` for what is checked. With `repair`, fixable issues are
+    /// fixed and the vault is saved (which backs it up first).
+    /// 
+    /// # Arguments
+    /// * `repair` - Fix what can be fixed
+    /// 
+    /// # Returns
+    /// The issues found, with those that were repaired marked
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is not open, files can't be read, or
+    /// the repaired vault can't be saved
+    pub fn fsck(&mut self, repair: bool) -> Result<FsckReport> {
+        let key = self.session_key()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let mut report = FsckReport::default();
+        
+        let mut header = self.storage.read_header()?;
+        let header_changed = match header.as_mut() {
+            Some(header) => fsck::check_header(header, repair, &mut report),
+            None => false,
+        };
+        let has_emergency = header.as_ref().is_some_and(|header| header.emergency.is_some());
+        if !has_emergency && self.storage.read_emergency_request().is_ok_and(|request| request.is_some()) {
+            if repair {
+                self.storage.remove_emergency_request()?;
+            }
+            report.fixable(Severity::Warning, "Emergency access request for a vault without emergency access".to_string(), repair);
+        }
+        
+        let public_key = self.signing_public_key().map(str::to_string);
+        for backup in self.storage.list_backups()? {
+            report.backups_checked += 1;
+            if self.storage.load_backup_with_key(&backup.timestamp, &key).is_err() {
+                report.problem(
+                    Severity::Warning,
+                    format!("Backup {} can't be decrypted with the vault key (damaged, or from before a key change)", backup.timestamp),
+                );
+            }
+            if self.storage.check_backup_signature(&backup.timestamp, public_key.as_deref())? == SignatureStatus::Invalid {
+                report.problem(Severity::Error, format!("Signature of backup {} doesn't match", backup.timestamp));
+            }
+        }
+        
+        let cache_enabled = self.get_vault_metadata().is_some_and(|metadata| metadata.settings.search_cache_minutes > 0);
+        let cache_current = match self.storage.read_search_cache(&key) {
+            Ok(Some(cache)) => Some(cache_enabled && cache.is_for(&self.storage.vault_digest()?)),
+            Ok(None) => None,
+            Err(_) => Some(false),
+        };
+        if cache_current == Some(false) {
+            if repair {
+                self.storage.remove_search_cache()?;
+            }
+            report.fixable(Severity::Warning, "Search cache is unreadable, out of date, or turned off".to_string(), repair);
+        }
+        
+        for name in self.storage.stray_files()? {
+            if repair {
+                self.storage.remove_stray_file(&name)?;
+            }
+            report.fixable(Severity::Warning, format!("Stray file {}", name), repair);
+        }
+        
+        let mut vault = self.vault.clone()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let vault_changed = fsck::check_vault(&mut vault, repair, &mut report);
+        if header_changed || vault_changed {
+            let previous = self.vault.replace(vault);
+            let saved = match &header {
+                Some(header) if header_changed => {
+                    self.storage.save_vault_with_header(self.vault.as_ref().expect("just set"), header, self.auth.get_crypto_for_init())
+                }
+                _ => self.save_vault(),
+            };
+            if let Err(e) = saved {
+                self.vault = previous;
+                return Err(e);
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Generate a new key pair for signing backups and exports
+    /// 
+    /// Replaces any previous key; files it signed then show as invalid.
+    /// 
+    /// # Returns
+    /// The new public key (base64)
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails
+    pub fn generate_signing_key(&mut self) -> Result<String> {
+        let key = signing::generate_key()?;
+        let public_key = key.public_key.clone();
+        self.set_signing_key(Some(key))?;
+        Ok(public_key)
+    }
+    
+    /// Stop signing backups and exports
+    /// 
+    /// # Errors
+    /// Returns an error if vault is not open or save fails
+    pub fn remove_signing_key(&mut self) -> Result<()> {
+        self.set_signing_key(None)
+    }
+    
+    /// Get the public key that signs this vault's backups and exports
+    /// 
+    /// # Returns
+    /// The public key (base64), or None if the vault is locked or has no
+    /// signing key
+    pub fn signing_public_key(&self) -> Option<&str> {
+        self.signing_key().map(|key| key.public_key.as_str())
+    }
+    
+    /// Write a detached signature next to a file
+    /// 
+    /// # Arguments
+    /// * `path` - File to sign
+    /// 
+    /// # Returns
+    /// Path of the signature, or None if the vault has no signing key
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or the signature written
+    pub fn sign_file(&self, path: &std::path::Path) -> Result<Option<std::path::PathBuf>> {
+        self.signing_key()
+            .map(|key| signing::sign_file(key, path))
+            .transpose()
+    }
+    
+    /// Check a file's detached signature against the vault's signing key
+    /// 
+    /// # Arguments
+    /// * `path` - The signed file
+    /// 
+    /// # Returns
+    /// The signature status
+    /// 
+    /// # Errors
+    /// Returns an error if the files can't be read
+    pub fn verify_file(&self, path: &std::path::Path) -> Result<SignatureStatus> {
+        signing::check_file(self.signing_public_key(), path)
+    }
+    
+    /// Refuse a file whose signature doesn't match the vault's signing key
+    fn require_valid_signature(&self, path: &std::path::Path) -> Result<()> {
+        match self.verify_file(path)? {
+            SignatureStatus::Invalid => Err(tampered_error(&path.display().to_string())),
+            _ => Ok(()),
+        }
+    }
+    
+    fn signing_key(&self) -> Option<&SigningKey> {
+        self.vault.as_ref()?.signing_key.as_ref()
+    }
+    
+    fn set_signing_key(&mut self, key: Option<SigningKey>) -> Result<()> {
+        let vault = self.vault.as_mut()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        let previous = std::mem::replace(&mut vault.signing_key, key);
+        
+        if let Err(e) = self.save_vault() {
+            if let Some(vault) = self.vault.as_mut() {
+                vault.signing_key = previous;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+    
+    /// Get vault file information
+    /// 
+    /// # Returns
+    /// Vault file size and modification time
+    pub fn get_vault_info(&self) -> Result<(u64, Option<std::time::SystemTime>)> {
+        let size = self.storage.vault_size()?;
+        let modified = self.storage.vault_modified()?;
+        Ok((size, modified))
+    }
+    
+    /// Get the path of the vault file
+    /// 
+    /// # Returns
+    /// The path, or None if the storage backend doesn't keep the vault in
+    /// a local file
+    pub fn vault_path(&self) -> Option<&std::path::Path> {
+        self.storage.vault_path()
+    }
+    
+    /// Read the vault file as it is stored, still encrypted
+    /// 
+    /// Used to hand the vault to another device, which opens it with the
+    /// same master password.
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist or can't be read
+    pub fn read_vault_file(&self) -> Result<Vec<u8>> {
+        self.storage.read_vault_file_data()
+    }
+    
+    /// Read the unencrypted header of the vault file
+    /// 
+    /// # Returns
+    /// The header, or None for legacy vaults
+    /// 
+    /// # Errors
+    /// Returns an error if the vault file cannot be read
+    pub fn vault_header(&self) -> Result<Option<VaultHeader>> {
+        self.storage.read_header()
+    }
+    
+    /// List all available vaults
+    /// 
+    /// # Returns
+    /// Vector of vault names
+    pub fn list_vaults() -> Result<Vec<String>> {
+        VaultStorage::list_vaults()
+    }
+    
+    /// Rename a vault
+    /// 
+    /// # Arguments
+    /// * `old_name` - Current name of the vault
+    /// * `new_name` - New name for the vault
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist or the new name is taken or invalid
+    pub fn rename_vault(old_name: &str, new_name: &str) -> Result<()> {
+        VaultStorage::rename_vault(old_name, new_name)
+    }
+    
+    /// Delete a vault
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Name of the vault to delete
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if deletion fails
+    pub fn delete_vault(vault_name: &str) -> Result<()> {
+        VaultStorage::delete_vault(vault_name)
+    }
+    
+    /// Check if the current session is still valid
+    /// 
+    /// # Returns
+    /// True if the session is valid
+    pub fn is_session_valid(&self) -> bool {
+        self.auth.is_authenticated()
+    }
+    
+    /// Update session activity
+    pub fn update_activity(&mut self) {
+        self.auth.update_activity();
+    }
+    
+    /// Get session information
+    /// 
+    /// # Returns
+    /// Session information or None if not authenticated
+    pub fn get_session_info(&self) -> Option<&crate::auth::AuthSession> {
+        self.auth.get_session()
+    }
+    
+    /// Save the current vault to disk
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if save fails
+    fn save_vault(&self) -> Result<()> {
+        let vault = self.vault.as_ref()
+            .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))?;
+        
+        self.storage.save_vault(vault, self.auth.get_crypto_for_init())
+    }
+}
+
+/// Error for a file whose signature doesn't match
+fn tampered_error(what: &str) -> PassManError {
+    PassManError::CryptoError(format!("Signature of the {} doesn't match; it may have been tampered with", what))
+}
+
+/// Check that custom fields have names and that no two share one
+/// 
+/// Names are compared ignoring case, as `Account::custom_field` looks them up.
+fn validate_custom_fields(fields: &[CustomField]) -> Result<()> {
+    let mut seen = std::collections::HashSet::new();
+    for field in fields {
+        let name = field.name.trim();
+        if name.is_empty() {
+            return Err(PassManError::InvalidInput("Custom fields need a name".to_string()));
+        }
+        if !seen.insert(name.to_lowercase()) {
+            return Err(PassManError::InvalidInput(format!("There is more than one custom field named '{}'", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Check an account's color or icon; blank values count as none
+fn normalize_appearance(value: Option<String>, normalize: fn(&str) -> Result<String>) -> Result<Option<String>> {
+    value.filter(|value| !value.trim().is_empty())
+        .map(|value| normalize(&value))
+        .transpose()
+}
+
+/// Fill in an account's empty fields from an imported duplicate (`DedupePolicy::Merge`)
+/// 
+/// Tags and custom fields the account lacks are added; fields it already
+/// has keep their values.
+fn merge_fields(current: &mut Account, imported: Account) {
+    fn fill<T>(field: &mut Option<T>, value: Option<T>) {
+        if field.is_none() {
+            *field = value;
+        }
+    }
+    
+    if current.password.is_empty() {
+        current.password = imported.password;
+    }
+    fill(&mut current.username, imported.username);
+    fill(&mut current.url, imported.url);
+    fill(&mut current.notes, imported.notes);
+    fill(&mut current.folder, imported.folder);
+    fill(&mut current.color, imported.color);
+    fill(&mut current.icon, imported.icon);
+    fill(&mut current.totp, imported.totp);
+    fill(&mut current.autotype, imported.autotype);
+    fill(&mut current.ssh_key, imported.ssh_key);
+    fill(&mut current.card, imported.card);
+    fill(&mut current.identity, imported.identity);
+    for tag in imported.tags {
+        if !current.tags.iter().any(|existing| existing.eq_ignore_ascii_case(&tag)) {
+            current.tags.push(tag);
+        }
+    }
+    for field in imported.custom_fields {
+        if current.custom_field(&field.name).is_none() {
+            current.custom_fields.push(field);
+        }
+    }
+    current.updated_at = chrono::Utc::now();
+}
+
+/// Fold a duplicate into the account kept in its place (`PassMan::merge_duplicates`)
+fn merge_duplicate(current: &mut Account, mut duplicate: Account) {
+    if !duplicate.password.is_empty() && duplicate.password != current.password && !current.password.is_empty() {
+        let taken = |name: &str| current.custom_field(name).is_some() || duplicate.custom_field(name).is_some();
+        let name = (1..)
+            .map(|n| if n == 1 { "Previous password".to_string() } else { format!("Previous password {}", n) })
+            .find(|name| !taken(name))
+            .expect("some name is free");
+        current.custom_fields.push(CustomField::new(name, std::mem::take(&mut duplicate.password), true));
+    }
+    match (&mut current.notes, duplicate.notes.take()) {
+        (Some(notes), Some(other)) if !notes.contains(other.as_str()) => {
+            notes.push_str("\n\n");
+            notes.push_str(&other);
+        }
+        (notes, other) => {
+            if notes.is_none() {
+                *notes = other;
+            }
+        }
+    }
+    
+    current.is_favorite |= duplicate.is_favorite;
+    current.created_at = current.created_at.min(duplicate.created_at);
+    current.last_accessed = current.last_accessed.max(duplicate.last_accessed);
+    current.access_count = current.access_count.saturating_add(duplicate.access_count);
+    merge_fields(current, duplicate);
+}
+
+/// Format a recovery or emergency key as dash-separated base32 groups
+fn format_recovery_key(key: &SecureKey) -> String {
+    let encoded = base32::encode(key.as_bytes());
+    encoded.as_bytes()
+        .chunks(RECOVERY_KEY_GROUP)
+        .map(|group| String::from_utf8_lossy(group).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Parse a recovery or emergency key, ignoring case, dashes, and whitespace
+fn parse_slot_key(input: &str, kind: KeySlotKind) -> Result<SecureKey> {
+    let invalid = || PassManError::AuthenticationFailed(format!("Invalid {}", kind.as_str()));
+    let bytes = zeroize::Zeroizing::new(base32::decode(input).map_err(|_| invalid())?);
+    let key: [u8; 32] = bytes.as_slice().try_into().map_err(|_| invalid())?;
+    Ok(SecureKey::new(key))
+}
+
+impl Drop for PassMan {
+    fn drop(&mut self) {
+        // Clear sensitive data on drop
+        self.close_vault();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::{AccountBuilder, Identity, PaymentCard}, policy::{PasswordPolicy, PolicyViolation}};
+    
+    /// Unique vault name so parallel tests don't share a vault file
+    fn test_vault_name() -> String {
+        crate::testing::isolate();
+        format!("test_vault_{}", Uuid::new_v4().simple())
+    }
+    
+    #[test]
+    fn test_passman_creation() {
+        let passman = PassMan::new("test_vault").unwrap();
+        assert!(!passman.is_vault_open());
+    }
+    
+    #[test]
+    fn test_vault_initialization() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(passman.is_vault_open());
+        
+        let mut again = PassMan::new(&vault_name).unwrap();
+        let error = again.init_vault("test@example.com".to_string(), "master_password").unwrap_err();
+        assert_eq!((error.code(), error.kind()), (5, "vault_exists"));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+        assert!(matches!(again.open_vault("master_password"), Err(PassManError::VaultNotFound(_))));
+    }
+    
+    #[test]
+    fn test_memory_backend() {
+        let backend = crate::storage::MemoryBackend::new();
+        let mut passman = PassMan::new_with_backend("main", backend.clone(), PassManConfig::default());
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec![]).unwrap();
+        assert!(passman.vault_path().is_none());
+        assert_eq!(passman.list_backups().unwrap().len(), 1);
+        assert!(passman.get_vault_info().unwrap().0 > 0);
+        
+        let mut reopened = PassMan::new_with_backend("main", backend.clone(), PassManConfig::default());
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_all_accounts().len(), 1);
+        assert_eq!(reopened.read_vault_file().unwrap(), backend.read("main.vault").unwrap().unwrap());
+        
+        VaultStorage::rename_vault_in(&backend, "main", "work").unwrap();
+        assert_eq!(VaultStorage::list_vaults_in(&backend).unwrap(), ["work"]);
+        assert_eq!(backend.list("backups/").unwrap().len(), 1);
+        VaultStorage::delete_vault_in(&backend, "work").unwrap();
+        assert!(backend.list("").unwrap().is_empty());
+    }
+    
+    #[cfg(feature = "importers")]
+    #[test]
+    fn test_import_from() {
+        let testdata = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/importers/testdata");
+        let mut passman = PassMan::new_with_backend("main", crate::storage::MemoryBackend::new(), PassManConfig::default());
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        let (summary, errors) = passman.import_from(Importer::Bitwarden, &testdata.join("bitwarden.json")).unwrap();
+        assert_eq!((summary.added, summary.skipped, errors.len()), (4, 0, 1));
+        assert_eq!(passman.get_all_accounts().len(), 4);
+        
+        let protected = testdata.join("bitwarden_pbkdf2.json");
+        assert!(passman.import_from(Importer::Bitwarden, &protected).is_err());
+        let (summary, _) = passman.import_encrypted_from(Importer::Bitwarden, &protected, "correct horse").unwrap();
+        assert_eq!((summary.added, summary.skipped), (0, 4));
+        
+        // GitHub/octocat is in both files
+        let (summary, _) = passman.import_encrypted_from(Importer::Kdbx, &testdata.join("kdbx4.kdbx"), "correct horse").unwrap();
+        assert_eq!((summary.added, summary.skipped), (1, 1));
+    }
+    
+    #[test]
+    fn test_corrupted_vault() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let path = passman.vault_path().unwrap().to_path_buf();
+        
+        let mut data = std::fs::read(&path).unwrap();
+        data.truncate(12);
+        std::fs::write(&path, &data).unwrap();
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        let error = reopened.open_vault("master_password").unwrap_err();
+        assert!(matches!(error, PassManError::VaultCorrupted(_)), "{:?}", error);
+        assert_eq!(error.info().kind, "vault_corrupted");
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_account_operations() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        // Add account
+        passman.add_account(
+            "Test Account".to_string(),
+            AccountType::Personal,
+            "test_password".to_string(),
+            Some("https://example.com".to_string()),
+            Some("testuser".to_string()),
+            Some("Test notes".to_string()),
+            vec!["test".to_string()],
+        ).unwrap();
+        
+        let accounts = passman.get_all_accounts();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "Test Account");
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_add_accounts_batch() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        let accounts = vec![
+            Account::new("One".to_string(), AccountType::Personal, "pw1".to_string()),
+            Account::new("Two".to_string(), AccountType::Work, "pw2".to_string()),
+        ];
+        let ids = passman.add_accounts(accounts).unwrap();
+        assert_eq!(ids.len(), 2);
+        passman.close_vault();
+        
+        // Both accounts were persisted by the single save
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_all_accounts().len(), 2);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_reopen_with_session_key() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let key = passman.session_key().unwrap();
+        passman.close_vault();
+        assert!(passman.session_key().is_none());
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        assert!(matches!(
+            reopened.open_vault("wrong_password"),
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        reopened.open_vault_with_key(key).unwrap();
+        assert!(reopened.is_vault_open());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_keyfile_and_recovery_key() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        let options = VaultInitOptions {
+            keyfile: Some(b"keyfile contents".to_vec()),
+            recovery_key: true,
+            ..VaultInitOptions::default()
+        };
+        let recovery_key = passman
+            .init_vault_with_options("test@example.com".to_string(), "master_password", options)
+            .unwrap()
+            .unwrap();
+        passman.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        assert!(reopened.requires_keyfile().unwrap());
+        assert!(reopened.open_vault("master_password").is_err());
+        assert!(reopened.open_vault_with_keyfile("master_password", Some(b"other file")).is_err());
+        reopened.open_vault_with_keyfile("master_password", Some(b"keyfile contents")).unwrap();
+        reopened.close_vault();
+        
+        // The recovery key alone opens the vault and can reset the password
+        let mut recovered = PassMan::new(&vault_name).unwrap();
+        recovered.open_vault_with_recovery_key(&recovery_key.to_lowercase()).unwrap();
+        recovered.change_master_password("new_password", None).unwrap();
+        recovered.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        assert!(!reopened.requires_keyfile().unwrap());
+        reopened.open_vault("new_password").unwrap();
+        assert!(reopened.is_vault_open());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_emergency_access() {
+        let vault_name = test_vault_name();
+        let mut owner = PassMan::new(&vault_name).unwrap();
+        owner.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(owner.emergency_access().unwrap().is_none());
+        
+        let emergency_key = owner.grant_emergency_access("Alice", 7).unwrap();
+        assert_eq!(owner.emergency_access().unwrap().unwrap().wait_days, 7);
+        
+        // Nothing works before a request, or during the waiting period
+        let mut contact = PassMan::new(&vault_name).unwrap();
+        assert!(contact.open_vault_with_emergency_key(&emergency_key).is_err());
+        assert!(contact.request_emergency_access("AAAA-BBBB").is_err());
+        assert!(contact.emergency_request().unwrap().is_none());
+        let available_at = contact.request_emergency_access(&emergency_key).unwrap();
+        assert!(available_at > chrono::Utc::now() + chrono::Duration::days(6));
+        assert!(contact.open_vault_with_emergency_key(&emergency_key).is_err());
+        
+        // The owner can deny the request
+        assert!(owner.emergency_request().unwrap().is_some());
+        assert!(owner.deny_emergency_request().unwrap());
+        assert!(contact.open_vault_with_emergency_key(&emergency_key).is_err());
+        
+        // After the waiting period the key opens the vault
+        let requested_at = chrono::Utc::now() - chrono::Duration::days(8);
+        owner.storage.write_emergency_request(&EmergencyRequest { requested_at }).unwrap();
+        contact.open_vault_with_emergency_key(&emergency_key).unwrap();
+        assert!(contact.is_vault_open());
+        contact.close_vault();
+        
+        // Revoking invalidates the key
+        owner.revoke_emergency_access().unwrap();
+        assert!(owner.emergency_access().unwrap().is_none());
+        assert!(owner.emergency_request().unwrap().is_none());
+        assert!(contact.request_emergency_access(&emergency_key).is_err());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_record_storage() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec![]).unwrap();
+        
+        assert!(passman.set_record_storage(true).unwrap());
+        assert!(!passman.set_record_storage(true).unwrap());
+        assert!(passman.vault_header().unwrap().unwrap().records);
+        passman.add_account("Bank".to_string(), AccountType::Banking, "pw".to_string(), None, None, None, vec![]).unwrap();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_all_accounts().len(), 2);
+        assert!(reopened.set_record_storage(false).unwrap());
+        reopened.close_vault();
+        
+        passman.close_vault();
+        passman.open_vault("master_password").unwrap();
+        assert_eq!(passman.get_all_accounts().len(), 2);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_storage() {
+        use crate::storage_backend::{SQLITE_DATABASE, SqliteBackend, record_blobs};
+        
+        let dir = tempfile::tempdir().unwrap();
+        let config = PassManConfig {
+            vault_dir: Some(dir.path().to_path_buf()),
+            storage: StorageEngine::Sqlite,
+            ..PassManConfig::default()
+        };
+        let mut passman = PassMan::with_config("main", config.clone()).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(passman.vault_header().unwrap().unwrap().records);
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec![]).unwrap();
+        
+        // Adding an account adds its row and leaves the others alone
+        let backend = SqliteBackend::new(dir.path().join(SQLITE_DATABASE), dir.path().join("backups"));
+        let records = record_blobs(&backend, "main").unwrap();
+        assert_eq!(records.len(), 1);
+        passman.add_account("Bank".to_string(), AccountType::Banking, "pw".to_string(), None, None, None, vec![]).unwrap();
+        let after = record_blobs(&backend, "main").unwrap();
+        assert_eq!(after.len(), 2);
+        assert!(after.contains(&records[0]));
+        assert!(!dir.path().join("main.vault").exists());
+        
+        let mut reopened = PassMan::with_config("main", config).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_all_accounts().len(), 2);
+        assert!(!backend.list("backups/").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_unlock_backoff_and_hint() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        let options = VaultInitOptions {
+            hint: Some("first pet".to_string()),
+            ..VaultInitOptions::default()
+        };
+        passman.init_vault_with_options("test@example.com".to_string(), "master_password", options).unwrap();
+        passman.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        assert_eq!(reopened.password_hint().unwrap().as_deref(), Some("first pet"));
+        assert!(matches!(reopened.open_vault("wrong"), Err(PassManError::AuthenticationFailed(_))));
+        assert!(reopened.open_vault("wrong").is_err());
+        assert_eq!(reopened.failed_unlock_attempts(), 2);
+        assert!(!reopened.unlock_retry_delay().is_zero());
+        
+        // Even the right password is refused during the backoff window
+        assert!(matches!(reopened.open_vault("master_password"), Err(PassManError::LockedOut(_))));
+        std::thread::sleep(reopened.unlock_retry_delay());
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.failed_unlock_attempts(), 0);
+        
+        reopened.set_password_hint(None).unwrap();
+        assert_eq!(reopened.password_hint().unwrap(), None);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_verify_master_password() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        assert!(passman.verify_master_password("master_password", None).is_err());
+        
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.verify_master_password("master_password", None).unwrap();
+        assert!(passman.verify_master_password("wrong", None).is_err());
+        assert_eq!(passman.failed_unlock_attempts(), 1);
+        passman.verify_master_password("master_password", None).unwrap();
+        assert_eq!(passman.failed_unlock_attempts(), 0);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_merge_accounts() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.add_account(
+            "GitHub".to_string(),
+            AccountType::Work,
+            "old_password".to_string(),
+            None,
+            Some("octocat".to_string()),
+            None,
+            Vec::new(),
+        ).unwrap();
+        
+        let imported = || {
+            let mut duplicate = Account::new("github".to_string(), AccountType::Work, "new_password".to_string());
+            duplicate.username = Some("OctoCat".to_string());
+            vec![duplicate, Account::new("Gmail".to_string(), AccountType::Email, "mail".to_string())]
+        };
+        let mut calls = 0;
+        
+        // A dry run reports what would happen without touching the vault
+        let summary = passman.merge_accounts(imported(), DedupePolicy::Skip, true, &mut |_, _| calls += 1).unwrap();
+        assert_eq!((summary.added, summary.skipped, summary.overwritten), (1, 1, 0));
+        assert_eq!(calls, 2);
+        assert_eq!(passman.get_all_accounts().len(), 1);
+        
+        let summary = passman.merge_accounts(imported(), DedupePolicy::Overwrite, false, &mut |_, _| {}).unwrap();
+        assert_eq!((summary.added, summary.skipped, summary.overwritten), (1, 0, 1));
+        assert_eq!(passman.get_all_accounts().len(), 2);
+        assert_eq!(passman.search_accounts("github")[0].password, "new_password");
+        
+        let mut notes = imported();
+        notes[0].notes = Some("recovery codes".to_string());
+        notes[0].tags = vec!["dev".to_string()];
+        let summary = passman.merge_accounts(notes, DedupePolicy::Merge, false, &mut |_, _| {}).unwrap();
+        assert_eq!((summary.merged, summary.added), (2, 0));
+        let github = passman.search_accounts("github")[0];
+        assert_eq!((github.password.as_str(), github.notes.as_deref()), ("new_password", Some("recovery codes")));
+        assert_eq!(github.tags, ["dev"]);
+        
+        let summary = passman.merge_accounts(imported(), DedupePolicy::KeepBoth, false, &mut |_, _| {}).unwrap();
+        assert_eq!(summary.added, 2);
+        assert_eq!(passman.get_all_accounts().len(), 4);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_merge_duplicates() {
+        use crate::testing::{AccountBuilder, TestVault};
+        let vault = TestVault::builder()
+            .account(AccountBuilder::new("GitHub").url("https://github.com/login").username("octocat").password("current").tag("work").build())
+            .account(AccountBuilder::new("github.com").url("https://GitHub.com").username("OctoCat ").password("old").notes("recovery codes").tag("dev").favorite().build())
+            .account(AccountBuilder::new("GitHub").url("https://github.com").username("octocat").password("current").build())
+            .account(AccountBuilder::new("GitHub").url("https://github.com").username("someone-else").build())
+            .account(AccountBuilder::new("Bank").password("a").build())
+            .account(AccountBuilder::new("bank").password("b").build())
+            .account(AccountBuilder::new("Decoy").url("https://github.com").username("octocat").canary().build())
+            .build()
+            .unwrap();
+        let mut passman = vault.open().unwrap();
+        
+        // Bank and bank (no URL), then the three octocat logins
+        let sizes: Vec<usize> = passman.find_duplicates().iter().map(Vec::len).collect();
+        assert_eq!(sizes, [2, 3]);
+        
+        let github = passman.find_duplicates()[1].iter().map(|account| account.id).collect::<Vec<_>>();
+        let keep = *github.iter().find(|id| passman.get_account(**id).unwrap().tags == ["work"]).unwrap();
+        let others: Vec<Uuid> = github.iter().copied().filter(|id| *id != keep).collect();
+        assert!(matches!(passman.merge_duplicates(keep, &[keep]), Err(PassManError::InvalidInput(_))));
+        assert!(matches!(passman.merge_duplicates(keep, &[Uuid::new_v4()]), Err(PassManError::AccountNotFound(_))));
+        passman.merge_duplicates(keep, &others).unwrap();
+        
+        let merged = passman.get_account(keep).unwrap();
+        assert_eq!((merged.password.as_str(), merged.notes.as_deref()), ("current", Some("recovery codes")));
+        assert_eq!(merged.tags, ["work", "dev"]);
+        assert_eq!(merged.custom_fields, [CustomField::new("Previous password", "old", true)]);
+        assert!(merged.is_favorite);
+        assert_eq!(passman.list_trash().len(), 2);
+        assert_eq!(passman.find_duplicates().len(), 1);
+        
+        let reopened = vault.open().unwrap();
+        assert_eq!(reopened.get_account(keep).unwrap().tags, ["work", "dev"]);
+    }
+    
+    #[test]
+    fn test_device_key_unlock() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(!passman.has_device_key().unwrap());
+        
+        let device_key = passman.enroll_device_key().unwrap();
+        assert!(passman.has_device_key().unwrap());
+        passman.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        assert!(reopened.open_vault_with_device_key(&SecureKey::generate()).is_err());
+        reopened.open_vault_with_device_key(&device_key).unwrap();
+        assert!(reopened.is_vault_open());
+        
+        // The device key survives a password change but not removal
+        reopened.change_master_password("new_password", None).unwrap();
+        reopened.remove_device_key().unwrap();
+        reopened.close_vault();
+        assert!(reopened.open_vault_with_device_key(&device_key).is_err());
+        reopened.open_vault("new_password").unwrap();
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_update_settings() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        let mut settings = passman.get_vault_metadata().unwrap().settings.clone();
+        settings.auto_lock_timeout = 5;
+        settings.default_password_options.length = 24;
+        passman.update_settings(settings.clone()).unwrap();
+        
+        let mut invalid = settings.clone();
+        invalid.default_password_options.length = 0;
+        assert!(passman.update_settings(invalid).is_err());
+        passman.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_vault_metadata().unwrap().settings, settings);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_password_policy() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let weak = passman.add_account_entry(Account::new("Old".to_string(), AccountType::Email, "pw".to_string())).unwrap();
+        
+        let mut settings = passman.get_vault_metadata().unwrap().settings.clone();
+        settings.password_policy = Some(PasswordPolicy { min_strength: 101, ..Default::default() });
+        assert!(passman.update_settings(settings.clone()).is_err());
+        settings.password_policy = Some(PasswordPolicy { min_length: 12, require_digit: true, ..Default::default() });
+        passman.update_settings(settings).unwrap();
+        
+        let result = passman.add_account("Bank".to_string(), AccountType::Banking, "short".to_string(), None, None, None, vec![]);
+        match result {
+            Err(PassManError::PolicyViolation(violations)) => assert_eq!(violations, [
+                PolicyViolation::TooShort { min: 12, actual: 5 },
+                PolicyViolation::MissingDigit,
+            ]),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "short".to_string())).is_err());
+        assert_eq!(passman.get_all_accounts().len(), 1);
+        passman.add_account("Bank".to_string(), AccountType::Banking, "long enough 42".to_string(), None, None, None, vec![]).unwrap();
+        
+        // Editing other fields keeps an old password, changing it is checked
+        passman.update_account(weak, "Renamed".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec![]).unwrap();
+        assert!(passman.update_account(weak, "Renamed".to_string(), AccountType::Email, "pw2".to_string(), None, None, None, vec![]).is_err());
+        assert!(passman.check_password_policy("twelve chars 1").is_ok());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_site_policies() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let mut bank = Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string());
+        bank.url = Some("https://login.bank.example/".to_string());
+        let bank = passman.add_account_entry(bank).unwrap();
+        let other = passman.add_account_entry(Account::new("Other".to_string(), AccountType::Other, "pw".to_string())).unwrap();
+        assert_eq!(passman.site_policy(bank).unwrap(), None);
+        
+        // A domain policy covers subdomains
+        let short = SitePolicy { max_length: 12, allow_special: false, ..SitePolicy::default() };
+        assert_eq!(passman.set_domain_policy("https://www.Bank.example", Some(short.clone())).unwrap(), "bank.example");
+        assert_eq!(passman.site_policy(bank).unwrap(), Some(&short));
+        assert_eq!(passman.site_policy(other).unwrap(), None);
+        let password = passman.generate_account_password(bank, &PasswordOptions::strong(24)).unwrap();
+        assert_eq!(password.len(), 12);
+        assert!(password.chars().all(char::is_alphanumeric));
+        assert_eq!(passman.generate_account_password(other, &PasswordOptions::strong(24)).unwrap().len(), 24);
+        
+        // The account's own policy wins, and both are kept in the vault
+        let pin = SitePolicy { min_length: 6, max_length: 6, allow_uppercase: false, allow_lowercase: false, allow_special: false, ..SitePolicy::default() };
+        assert!(passman.set_site_policy(bank, Some(SitePolicy { min_length: 8, max_length: 4, ..SitePolicy::default() })).is_err());
+        passman.set_site_policy(bank, Some(pin.clone())).unwrap();
+        let password = passman.generate_account_password(bank, &PasswordOptions::strong(24)).unwrap();
+        assert!(password.len() == 6 && password.chars().all(|c| c.is_ascii_digit()));
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.site_policy(bank).unwrap(), Some(&pin));
+        reopened.set_site_policy(bank, None).unwrap();
+        assert_eq!(reopened.site_policy(bank).unwrap(), Some(&short));
+        reopened.set_domain_policy("bank.example", None).unwrap();
+        assert_eq!(reopened.site_policy(bank).unwrap(), None);
+        assert!(reopened.set_domain_policy("", Some(short)).is_err());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_patch_account() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let account = AccountBuilder::new("Bank")
+            .account_type(AccountType::Banking)
+            .password("pw")
+            .url("https://bank.example")
+            .username("me")
+            .tag("money")
+            .build();
+        let id = passman.add_account_entry(account).unwrap();
+        
+        let patch = AccountPatch {
+            password: Some("new-pw".to_string()),
+            url: Some(None),
+            is_favorite: Some(true),
+            ..Default::default()
+        };
+        passman.patch_account(id, patch).unwrap();
+        let account = passman.get_account(id).unwrap();
+        assert_eq!((account.password.as_str(), account.url.as_deref(), account.is_favorite), ("new-pw", None, true));
+        assert_eq!((account.name.as_str(), account.username.as_deref()), ("Bank", Some("me")));
+        assert_eq!(account.tags, ["money"]);
+        
+        // In JSON a missing field is kept and null clears it
+        let patch: AccountPatch = serde_json::from_str(r#"{"name": "My bank", "username": null}"#).unwrap();
+        passman.patch_account(id, patch).unwrap();
+        let account = passman.get_account(id).unwrap();
+        assert_eq!((account.name.as_str(), account.username.as_deref(), account.password.as_str()), ("My bank", None, "new-pw"));
+        
+        let bad = AccountPatch { autotype: Some(Some("{BOGUS}".to_string())), ..Default::default() };
+        assert!(passman.patch_account(id, bad).is_err());
+        assert!(matches!(passman.patch_account(Uuid::new_v4(), AccountPatch::default()), Err(PassManError::AccountNotFound(_))));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_account_color_and_icon() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let account = AccountBuilder::new("GitHub").password("pw").color("#38F").icon(" 🐙 ").build();
+        let id = passman.add_account_entry(account).unwrap();
+        let account = passman.get_account(id).unwrap();
+        assert_eq!((account.color.as_deref(), account.icon.as_deref()), (Some("#3388ff"), Some("🐙")));
+        
+        let bad = AccountBuilder::new("Bad").password("pw").color("teal").build();
+        assert!(matches!(passman.add_account_entry(bad), Err(PassManError::InvalidInput(_))));
+        let bad = AccountPatch { icon: Some(Some("data:text/html,<b>".to_string())), ..Default::default() };
+        assert!(matches!(passman.patch_account(id, bad), Err(PassManError::InvalidInput(_))));
+        
+        // Remote icons would be fetched whenever they are shown
+        let bad = AccountPatch { icon: Some(Some("https://github.com/favicon.ico".to_string())), ..Default::default() };
+        assert!(matches!(passman.patch_account(id, bad), Err(PassManError::InvalidInput(_))));
+        
+        // Blank values clear, like null
+        let patch: AccountPatch = serde_json::from_str(r##"{"color": "", "icon": "data:image/png;base64,iVBORw0KGgo="}"##).unwrap();
+        passman.patch_account(id, patch).unwrap();
+        let account = passman.get_account(id).unwrap();
+        assert_eq!((account.color.as_deref(), account.icon.as_deref()), (None, Some("data:image/png;base64,iVBORw0KGgo=")));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_toggle_favorite() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let id = passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string())).unwrap();
+        
+        assert!(passman.toggle_favorite(id).unwrap());
+        assert!(passman.get_account(id).unwrap().is_favorite);
+        assert!(!passman.toggle_favorite(id).unwrap());
+        assert!(!passman.get_account(id).unwrap().is_favorite);
+        assert!(matches!(passman.toggle_favorite(Uuid::new_v4()), Err(PassManError::AccountNotFound(_))));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_card_and_identity_accounts() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let mut settings = passman.get_vault_metadata().unwrap().settings.clone();
+        settings.password_policy = Some(PasswordPolicy { min_length: 12, ..Default::default() });
+        passman.update_settings(settings).unwrap();
+        
+        // Cards and identities need no password to pass the policy
+        let card = PaymentCard::new("4111 1111 1111 1111");
+        let id = passman.add_account_entry(AccountBuilder::new("Visa").account_type(AccountType::Card).card(card).build()).unwrap();
+        let identity = Identity { first_name: Some("Jane".to_string()), ..Identity::default() };
+        passman.add_account_entry(AccountBuilder::new("Me").account_type(AccountType::Identity).identity(identity).build()).unwrap();
+        assert!(passman.add_account_entry(AccountBuilder::new("Login").build()).is_err());
+        
+        let mistyped = PaymentCard::new("4111 1111 1111 1112");
+        let account = AccountBuilder::new("Mistyped").account_type(AccountType::Card).card(mistyped.clone()).build();
+        assert!(matches!(passman.add_account_entry(account), Err(PassManError::InvalidInput(_))));
+        let patch = AccountPatch { card: Some(Some(mistyped)), ..AccountPatch::default() };
+        assert!(matches!(passman.patch_account(id, patch), Err(PassManError::InvalidInput(_))));
+        assert_eq!(passman.get_account(id).unwrap().card.as_ref().unwrap().brand.as_deref(), Some("Visa"));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_custom_categories() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        passman.add_category("Travel").unwrap();
+        assert!(matches!(passman.add_category("travel"), Err(PassManError::InvalidInput(_))));
+        assert!(matches!(passman.add_category("ssh-key"), Err(PassManError::InvalidInput(_))));
+        
+        // New categories are defined by use, known ones keep their spelling
+        let custom = |name: &str| AccountType::Custom(name.to_string());
+        let flight = passman.add_account_entry(Account::new("Airline".to_string(), custom("TRAVEL"), "pw".to_string())).unwrap();
+        passman.add_account_entry(Account::new("Gym".to_string(), custom("Health"), "pw".to_string())).unwrap();
+        passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string())).unwrap();
+        assert_eq!(passman.get_categories(), ["Travel", "Health"]);
+        assert!(matches!(&passman.get_account(flight).unwrap().account_type, AccountType::Custom(name) if name == "Travel"));
+        
+        let query = AccountQuery { account_type: Some(custom("travel")), ..AccountQuery::default() };
+        let names: Vec<&str> = passman.query_accounts(&query).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Airline"]);
+        
+        assert_eq!(passman.rename_category("travel", "Trips").unwrap(), 1);
+        assert!(matches!(passman.rename_category("Trips", "Health"), Err(PassManError::InvalidInput(_))));
+        assert_eq!(passman.get_account(flight).unwrap().account_type, custom("Trips"));
+        assert_eq!(passman.remove_category("Trips").unwrap(), 1);
+        assert_eq!(passman.get_account(flight).unwrap().account_type, AccountType::Other);
+        assert_eq!(passman.get_categories(), ["Health"]);
+        assert!(passman.remove_category("Trips").is_err());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_set_autotype_sequence() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let id = passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string())).unwrap();
+        
+        assert!(passman.set_autotype_sequence(id, Some("{USERNAME}{BOGUS}".to_string())).is_err());
+        passman.set_autotype_sequence(id, Some("{PASSWORD}{ENTER}".to_string())).unwrap();
+        assert_eq!(passman.get_account(id).unwrap().autotype.as_deref(), Some("{PASSWORD}{ENTER}"));
+        passman.set_autotype_sequence(id, Some("  ".to_string())).unwrap();
+        assert_eq!(passman.get_account(id).unwrap().autotype, None);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_record_access() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let id = passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "pw".to_string())).unwrap();
+        let updated_at = passman.get_account(id).unwrap().updated_at;
+        assert!(passman.get_most_used(5).is_empty());
+        
+        passman.record_access(id).unwrap();
+        passman.record_access(id).unwrap();
+        assert!(passman.record_access(Uuid::new_v4()).is_err());
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        let account = reopened.get_account(id).unwrap();
+        assert_eq!(account.access_count, 2);
+        assert!(account.last_accessed.is_some());
+        assert_eq!(account.updated_at, updated_at);
+        assert_eq!(reopened.get_recently_used(5).len(), 1);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_reload_vault() {
+        let vault_name = test_vault_name();
+        let mut desktop = PassMan::new(&vault_name).unwrap();
+        desktop.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(!desktop.reload_vault().unwrap());
+        
+        let mut cli = PassMan::new(&vault_name).unwrap();
+        cli.open_vault("master_password").unwrap();
+        cli.add_account_entry(Account::new("Mail".to_string(), AccountType::Email, "pw".to_string())).unwrap();
+        
+        assert!(desktop.reload_vault().unwrap());
+        assert_eq!(desktop.get_all_accounts().len(), 1);
+        assert!(!desktop.reload_vault().unwrap());
+        
+        desktop.close_vault();
+        assert!(desktop.reload_vault().is_err());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_portable_export() {
+        let source_name = test_vault_name();
+        let mut source = PassMan::new(&source_name).unwrap();
+        source.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        source.add_account("GitHub".to_string(), AccountType::Work, "hunter2".to_string(), None, None, None, Vec::new()).unwrap();
+        
+        let dir = tempfile::tempdir().unwrap();
+        let export_path = dir.path().join("export.pmx");
+        assert!(source.export_portable(&export_path, "").is_err());
+        source.export_portable(&export_path, "export_password").unwrap();
+        let header = crate::format::read_export_header(&std::fs::read(&export_path).unwrap()).unwrap();
+        assert_eq!(header.kdf, source.storage.read_header().unwrap().unwrap().kdf);
+        
+        // Another vault, with another master password, reads it with the export password
+        let target_name = test_vault_name();
+        let mut target = PassMan::new(&target_name).unwrap();
+        target.init_vault("other@example.com".to_string(), "other_password").unwrap();
+        assert!(matches!(
+            target.import_portable(&export_path, "master_password"),
+            Err(PassManError::AuthenticationFailed(_))
+        ));
+        assert!(target.import_vault(&export_path).is_err());
+        target.import_portable(&export_path, "export_password").unwrap();
+        assert_eq!(target.get_all_accounts().len(), 1);
+        
+        target.close_vault();
+        target.open_vault("other_password").unwrap();
+        assert_eq!(target.get_all_accounts()[0].name, "GitHub");
+        
+        PassMan::delete_vault(&source_name).unwrap();
+        PassMan::delete_vault(&target_name).unwrap();
+    }
+    
+    #[test]
+    fn test_signed_backups_and_exports() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        assert!(passman.signing_public_key().is_none());
+        
+        let public_key = passman.generate_signing_key().unwrap();
+        assert_eq!(passman.signing_public_key(), Some(public_key.as_str()));
+        
+        let backup = passman.create_backup().unwrap();
+        assert!(backup.signed);
+        assert_eq!(passman.verify_backup(&backup.timestamp).unwrap(), SignatureStatus::Valid);
+        
+        let dir = tempfile::tempdir().unwrap();
+        let export_path = dir.path().join("export.vault");
+        passman.export_vault(&export_path).unwrap();
+        assert_eq!(passman.verify_file(&export_path).unwrap(), SignatureStatus::Valid);
+        
+        let mut data = std::fs::read(&export_path).unwrap();
+        data.push(b' ');
+        std::fs::write(&export_path, data).unwrap();
+        assert_eq!(passman.verify_file(&export_path).unwrap(), SignatureStatus::Invalid);
+        assert!(matches!(passman.import_vault(&export_path), Err(PassManError::CryptoError(_))));
+        
+        passman.remove_signing_key().unwrap();
+        assert_eq!(passman.verify_file(&export_path).unwrap(), SignatureStatus::NoKey);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_fsck() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec!["work".to_string()]).unwrap();
+        let report = passman.fsck(false).unwrap();
+        assert_eq!(report.accounts_checked, 1);
+        let clean = report.issues.len();
+        
+        let temp_path = passman.vault_path().unwrap().with_extension("tmp");
+        std::fs::write(&temp_path, b"partial save").unwrap();
+        passman.vault.as_mut().unwrap().metadata.account_count = 5;
+        let report = passman.fsck(false).unwrap();
+        assert_eq!(report.issues.len(), clean + 2);
+        assert!(report.issues.iter().all(|issue| issue.repairable && !issue.repaired));
+        assert!(temp_path.exists());
+        
+        let report = passman.fsck(true).unwrap();
+        assert_eq!(report.unresolved().count(), 0);
+        assert!(!temp_path.exists());
+        
+        // The repairs were saved
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert!(reopened.fsck(false).unwrap().is_clean());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_custom_fields() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        let mut account = Account::new("Bank".to_string(), AccountType::Banking, "secret".to_string());
+        account.custom_fields = vec![CustomField::new("PIN", "1234", true), CustomField::new("pin", "5678", true)];
+        assert!(matches!(passman.add_account_entry(account.clone()), Err(PassManError::InvalidInput(_))));
+        account.custom_fields.pop();
+        let id = passman.add_account_entry(account).unwrap();
+        assert_eq!(passman.get_account(id).unwrap().custom_field("pin").unwrap().value, "1234");
+        
+        let fields = vec![
+            CustomField::new("Security question", "First pet?", false),
+            CustomField::new("Answer", "Rex", true),
+        ];
+        passman.set_custom_fields(id, fields.clone()).unwrap();
+        assert!(passman.set_custom_fields(id, vec![CustomField::new(" ", "x", false)]).is_err());
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        let account = reopened.get_account(id).unwrap();
+        assert_eq!(account.custom_fields, fields);
+        assert!(account.custom_field("PIN").is_none());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_trash() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let mail = passman.add_account_entry(Account::new("Mail".to_string(), AccountType::Email, "pw".to_string())).unwrap();
+        let bank = passman.add_account_entry(Account::new("Bank".to_string(), AccountType::Banking, "secret".to_string())).unwrap();
+        
+        passman.delete_account(mail).unwrap();
+        passman.delete_account(bank).unwrap();
+        assert!(passman.get_account(mail).is_none());
+        assert!(matches!(passman.delete_account(mail), Err(PassManError::AccountNotFound(_))));
+        let trashed: Vec<&str> = passman.list_trash().iter().map(|entry| entry.account.name.as_str()).collect();
+        assert_eq!(trashed, ["Bank", "Mail"]);
+        
+        passman.restore_account(mail).unwrap();
+        assert_eq!(passman.get_account(mail).unwrap().password, "pw");
+        assert!(!passman.vault.as_ref().unwrap().deleted.contains_key(&mail));
+        assert!(matches!(passman.restore_account(mail), Err(PassManError::AccountNotFound(_))));
+        
+        // Old enough to be purged when the vault is next opened
+        let vault = passman.vault.as_mut().unwrap();
+        vault.trash.get_mut(&bank).unwrap().deleted_at -= chrono::Duration::days(31);
+        passman.save_vault().unwrap();
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert!(reopened.list_trash().is_empty());
+        assert!(reopened.get_account(mail).is_some());
+        
+        reopened.delete_account(mail).unwrap();
+        assert_eq!(reopened.purge_trash(None).unwrap(), 1);
+        assert_eq!(reopened.purge_trash(None).unwrap(), 0);
+        assert!(matches!(reopened.purge_trash(Some(mail)), Err(PassManError::AccountNotFound(_))));
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_legacy_upgrade() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec![]).unwrap();
+        let vault = passman.vault.clone().unwrap();
+        passman.close_vault();
+        
+        // Rewrite the vault in the legacy layout: salt, then the payload under the derived key
+        let salt = Salt::generate();
+        let mut crypto = crate::crypto::CryptoManager::new();
+        let key = crypto.derive_key("master_password", &salt).unwrap();
+        crypto.set_key(key.clone(), salt.clone());
+        let mut file_data = salt.as_bytes().to_vec();
+        file_data.extend_from_slice(&format::encrypt_payload(&vault, &crypto).unwrap());
+        std::fs::write(passman.vault_path().unwrap(), &file_data).unwrap();
+        
+        // A session key opens it, but can't add a password slot
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.open_vault_with_key(key).unwrap();
+        assert!(passman.vault_header().unwrap().is_none());
+        passman.close_vault();
+        
+        passman.open_vault("master_password").unwrap();
+        let header = passman.vault_header().unwrap().unwrap();
+        assert_eq!(header.format_version, 4);
+        assert!(header.has_slot(KeySlotKind::Password));
+        
+        // Saves after the upgrade keep the new header
+        passman.add_account("Bank".to_string(), AccountType::Banking, "secret".to_string(), None, None, None, vec![]).unwrap();
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        assert_eq!(reopened.get_all_accounts().len(), 2);
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_search_cache() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        let key = passman.session_key().unwrap();
+        passman.add_account("Mail".to_string(), AccountType::Email, "pw".to_string(), None, None, None, vec!["work".to_string()]).unwrap();
+        assert!(passman.load_search_cache(&key).is_none());
+        
+        let mut settings = passman.get_vault_metadata().unwrap().settings.clone();
+        settings.search_cache_minutes = 10;
+        passman.update_settings(settings.clone()).unwrap();
+        passman.add_account("Bank".to_string(), AccountType::Banking, "secret".to_string(), None, None, None, vec![]).unwrap();
+        
+        // Readable with the key alone, and without secrets
+        let reader = PassMan::new(&vault_name).unwrap();
+        let cache = reader.load_search_cache(&key).unwrap();
+        assert_eq!(cache.len(), 2);
+        let query = AccountQuery { tag: Some("work".to_string()), ..AccountQuery::default() };
+        let found = cache.query_accounts(&query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "Mail");
+        assert!(found[0].password.is_empty());
+        assert!(reader.load_search_cache(&SecureKey::generate()).is_none());
+        let clean = passman.fsck(false).unwrap().issues.len();
+        
+        // Another save of the vault file makes the old cache stale
+        let vault = passman.vault.as_ref().unwrap().clone();
+        let crypto = passman.auth.get_crypto().unwrap();
+        let cache_path = passman.vault_path().unwrap().with_extension("cache");
+        let cache_file = std::fs::read(&cache_path).unwrap();
+        passman.storage.save_vault(&vault, crypto).unwrap();
+        std::fs::write(&cache_path, cache_file).unwrap();
+        assert!(reader.load_search_cache(&key).is_none());
+        assert_eq!(passman.fsck(false).unwrap().issues.len(), clean + 1);
+        
+        passman.refresh_search_cache().unwrap();
+        assert!(reader.load_search_cache(&key).is_some());
+        assert_eq!(passman.fsck(false).unwrap().issues.len(), clean);
+        
+        settings.search_cache_minutes = 0;
+        passman.update_settings(settings).unwrap();
+        assert!(!cache_path.exists());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_diff_backup() {
+        let vault = crate::testing::TestVault::builder()
+            .account(crate::testing::AccountBuilder::new("GitHub").username("octocat").build())
+            .build()
+            .unwrap();
+        // Automatic backups within the same second would replace this one
+        let mut config = vault.config().clone();
+        config.backups.automatic = false;
+        let mut passman = PassMan::with_config(vault.name(), config).unwrap();
+        passman.open_vault(vault.password()).unwrap();
+        let backup = passman.create_backup().unwrap();
+        assert!(passman.diff_backup(&backup.timestamp).unwrap().is_empty());
+        
+        let id = passman.get_accounts_by_name("github")[0].id;
+        passman.update_account(id, "GitHub".to_string(), AccountType::Work, "changed".to_string(), None, Some("octocat".to_string()), None, vec![]).unwrap();
+        passman.add_account("Bank".to_string(), AccountType::Banking, "pw".to_string(), None, None, None, vec![]).unwrap();
+        
+        let changes = passman.diff_backup(&backup.timestamp).unwrap();
+        assert_eq!(changes.added[0].name, "Bank");
+        assert!(changes.removed.is_empty());
+        let fields: Vec<_> = changes.modified[0].changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["type", "password"]);
+        
+        assert!(passman.diff_backup("20000101_000000").is_err());
+    }
+    
+    #[test]
+    #[cfg(feature = "sync")]
+    fn test_merge_vault_file() {
+        let vault = crate::testing::TestVault::builder()
+            .account(crate::testing::AccountBuilder::new("GitHub").password("password").build())
+            .build()
+            .unwrap();
+        let mut laptop = vault.open().unwrap();
+        let id = laptop.get_accounts_by_name("github")[0].id;
+        
+        // The phone edits a copy of the vault file
+        let copy = vault.dir().join("test.sync-conflict.vault");
+        std::fs::copy(laptop.vault_path().unwrap(), &copy).unwrap();
+        let phone_config = PassManConfig { vault_dir: Some(vault.dir().join("phone")), ..vault.config().clone() };
+        std::fs::create_dir_all(vault.dir().join("phone")).unwrap();
+        std::fs::copy(&copy, vault.dir().join("phone").join("test.vault")).unwrap();
+        let mut phone = PassMan::with_config("test", phone_config).unwrap();
+        phone.open_vault(vault.password()).unwrap();
+        phone.update_account(id, "GitHub".to_string(), AccountType::Other, "from phone".to_string(), None, None, None, vec![]).unwrap();
+        phone.add_account("Bank".to_string(), AccountType::Banking, "pw".to_string(), None, None, None, vec![]).unwrap();
+        std::fs::copy(phone.vault_path().unwrap(), &copy).unwrap();
+        
+        laptop.update_account(id, "GitHub".to_string(), AccountType::Other, "password".to_string(), None, Some("octocat".to_string()), None, vec![]).unwrap();
+        let summary = laptop.merge_vault_file(&copy, true).unwrap();
+        assert_eq!(summary, MergeSummary { added: 1, deleted: 0, updated: 1 });
+        assert_eq!(laptop.get_all_accounts().len(), 1);
+        
+        laptop.merge_vault_file(&copy, false).unwrap();
+        let account = laptop.get_account(id).unwrap();
+        assert_eq!(account.password, "from phone");
+        assert_eq!(account.username.as_deref(), Some("octocat"));
+        assert_eq!(vault.open().unwrap().get_all_accounts().len(), 2);
+        assert!(laptop.merge_vault_file(&copy, false).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_generation_history() {
+        let vault_name = test_vault_name();
+        let mut passman = PassMan::new(&vault_name).unwrap();
+        passman.init_vault("test@example.com".to_string(), "master_password").unwrap();
+        
+        assert!(!passman.record_generated_password("ignored").unwrap());
+        assert!(passman.generation_history().unwrap().is_empty());
+        
+        let mut settings = passman.get_vault_metadata().unwrap().settings.clone();
+        settings.keep_generation_history = true;
+        passman.update_settings(settings.clone()).unwrap();
+        for i in 0..GENERATION_HISTORY_LIMIT + 2 {
+            assert!(passman.record_generated_password(&format!("pw{}", i)).unwrap());
+        }
+        passman.close_vault();
+        
+        let mut reopened = PassMan::new(&vault_name).unwrap();
+        reopened.open_vault("master_password").unwrap();
+        let history = reopened.generation_history().unwrap();
+        assert_eq!(history.len(), GENERATION_HISTORY_LIMIT);
+        assert_eq!(history[0].password, format!("pw{}", GENERATION_HISTORY_LIMIT + 1));
+        
+        reopened.clear_generation_history().unwrap();
+        assert!(reopened.generation_history().unwrap().is_empty());
+        
+        reopened.record_generated_password("kept").unwrap();
+        settings.keep_generation_history = false;
+        reopened.update_settings(settings).unwrap();
+        assert!(reopened.generation_history().unwrap().is_empty());
+        
+        PassMan::delete_vault(&vault_name).unwrap();
     }
     
     #[test]
@@ -0,0 +1,205 @@
+//! # Read-Only Vault Viewer
+//! 
+//! A decrypted vault for front-ends that display it but never write it
+//! back, such as the browser viewer built for wasm32 (see `wasm`). It
+//! needs no filesystem: the vault file comes in as bytes or from a
+//! `StorageBackend`, and nothing is saved, so access times and counts
+//! aren't updated either.
+//! 
+//! Listings hand out `AccountSummary` values without passwords, notes,
+//! or other secrets; the full account is only returned by `account`.
+
+use crate::{PassManError, Result, format, models::{Account, AccountType, Vault, VaultMetadata}, storage_backend::{self, StorageBackend}};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An account as shown in listings, without its secrets
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AccountSummary {
+    /// Account ID
+    pub id: Uuid,
+    
+    /// Display name
+    pub name: String,
+    
+    /// Type/category
+    pub account_type: AccountType,
+    
+    /// Website URL
+    pub url: Option<String>,
+    
+    /// Username or email
+    pub username: Option<String>,
+    
+    /// Tags
+    pub tags: Vec<String>,
+    
+    /// Folder the account is filed under
+    pub folder: Option<String>,
+    
+    /// Whether the account is a favorite
+    pub is_favorite: bool,
+    
+    /// Label color (`#rrggbb`)
+    pub color: Option<String>,
+    
+    /// Icon: a short text like an emoji, or an image URL
+    pub icon: Option<String>,
+}
+
+impl From<&Account> for AccountSummary {
+    fn from(account: &Account) -> Self {
+        Self {
+            id: account.id,
+            name: account.name.clone(),
+            account_type: account.account_type.clone(),
+            url: account.url.clone(),
+            username: account.username.clone(),
+            tags: account.tags.clone(),
+            folder: account.folder.clone(),
+            is_favorite: account.is_favorite,
+            color: account.color.clone(),
+            icon: account.icon.clone(),
+        }
+    }
+}
+
+/// A vault opened for reading only
+pub struct VaultViewer {
+    vault: Vault,
+}
+
+impl VaultViewer {
+    /// Decrypt a vault file held in memory
+    /// 
+    /// # Arguments
+    /// * `file_data` - Contents of a vault file
+    /// * `master_password` - The master password
+    /// * `keyfile` - Keyfile contents, if the vault uses one
+    /// 
+    /// # Returns
+    /// A viewer for the vault
+    /// 
+    /// # Errors
+    /// Returns an error if the file is corrupted or the credentials are wrong
+    pub fn open(file_data: &[u8], master_password: &str, keyfile: Option<&[u8]>) -> Result<Self> {
+        let vault = format::open_vault_bytes(file_data, master_password, keyfile)?;
+        Ok(Self { vault })
+    }
+    
+    /// Decrypt a vault kept in a storage backend
+    /// 
+    /// # Arguments
+    /// * `backend` - Where the vault file is kept
+    /// * `vault_name` - Name of the vault
+    /// * `master_password` - The master password
+    /// * `keyfile` - Keyfile contents, if the vault uses one
+    /// 
+    /// # Returns
+    /// A viewer for the vault
+    /// 
+    /// # Errors
+    /// Returns an error if the vault doesn't exist, is corrupted, or the
+    /// credentials are wrong
+    pub fn open_from(backend: &dyn StorageBackend, vault_name: &str, master_password: &str, keyfile: Option<&[u8]>) -> Result<Self> {
+        let mut file_data = backend.read(&storage_backend::vault_blob(vault_name))?
+            .ok_or_else(|| PassManError::VaultNotFound(vault_name.to_string()))?;
+        // Accounts kept in records of their own come along
+        for name in storage_backend::record_blobs(backend, vault_name)? {
+            if let Some(record) = backend.read(&name)? {
+                format::push_frame(&mut file_data, &record);
+            }
+        }
+        Self::open(&file_data, master_password, keyfile)
+    }
+    
+    /// Vault metadata (email, creation time, settings)
+    pub fn metadata(&self) -> &VaultMetadata {
+        &self.vault.metadata
+    }
+    
+    /// Unarchived accounts, sorted by name
+    pub fn accounts(&self) -> Vec<AccountSummary> {
+        let mut accounts: Vec<&Account> = self.vault.get_all_accounts()
+            .into_iter()
+            .filter(|account| !account.archived)
+            .collect();
+        accounts.sort_by_cached_key(|account| account.name.to_lowercase());
+        accounts.into_iter().map(AccountSummary::from).collect()
+    }
+    
+    /// Search unarchived accounts by name (case-insensitive), sorted by name
+    pub fn search(&self, query: &str) -> Vec<AccountSummary> {
+        self.vault.search_accounts(query)
+            .into_iter()
+            .filter(|account| !account.archived)
+            .map(AccountSummary::from)
+            .collect()
+    }
+    
+    /// Get an account with its secrets
+    /// 
+    /// # Errors
+    /// Returns an error if there is no account with this ID
+    pub fn account(&self, id: &Uuid) -> Result<&Account> {
+        self.vault.get_account(id)
+            .ok_or_else(|| PassManError::AccountNotFound(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::{CryptoManager, KdfParams, SecureKey}, format::{KeySlotKind, VaultHeader}, storage_backend::MemoryBackend};
+    
+    fn vault_file() -> (Vec<u8>, Uuid) {
+        let mut vault = Vault::new("test@example.com".to_string());
+        let mut github = Account::new("GitHub".to_string(), AccountType::Work, "gh-secret".to_string());
+        github.username = Some("octocat".to_string());
+        let id = github.id;
+        vault.add_account(github);
+        vault.add_account(Account::new("bank".to_string(), AccountType::Banking, "bank-secret".to_string()));
+        let mut old = Account::new("Old mail".to_string(), AccountType::Email, "old-secret".to_string());
+        old.archived = true;
+        vault.add_account(old);
+        
+        let vault_key = SecureKey::generate();
+        let mut header = VaultHeader::new(KdfParams { memory_kib: 8, iterations: 1, parallelism: 1 }, false);
+        let wrapping_key = header.password_key("master_password", None).unwrap();
+        header.set_slot(KeySlotKind::Password, &vault_key, &wrapping_key).unwrap();
+        let mut file_data = header.encode().unwrap();
+        file_data.extend_from_slice(&CryptoManager::new()
+            .encrypt_with_key(&serde_json::to_vec(&vault).unwrap(), &vault_key)
+            .unwrap());
+        (file_data, id)
+    }
+    
+    #[test]
+    fn test_viewer() {
+        let (file_data, id) = vault_file();
+        let viewer = VaultViewer::open(&file_data, "master_password", None).unwrap();
+        assert_eq!(viewer.metadata().email, "test@example.com");
+        
+        let names: Vec<String> = viewer.accounts().into_iter().map(|account| account.name).collect();
+        assert_eq!(names, ["bank", "GitHub"]);
+        let found = viewer.search("git");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].username.as_deref(), Some("octocat"));
+        assert!(viewer.search("mail").is_empty());
+        
+        assert_eq!(viewer.account(&id).unwrap().password, "gh-secret");
+        assert!(matches!(viewer.account(&Uuid::new_v4()), Err(PassManError::AccountNotFound(_))));
+        assert!(matches!(VaultViewer::open(&file_data, "wrong", None), Err(PassManError::AuthenticationFailed(_))));
+    }
+    
+    #[test]
+    fn test_viewer_from_backend() {
+        let (file_data, _) = vault_file();
+        let backend = MemoryBackend::new();
+        backend.write(&storage_backend::vault_blob("main"), &file_data).unwrap();
+        
+        let viewer = VaultViewer::open_from(&backend, "main", "master_password", None).unwrap();
+        assert_eq!(viewer.accounts().len(), 2);
+        assert!(matches!(VaultViewer::open_from(&backend, "work", "master_password", None), Err(PassManError::VaultNotFound(_))));
+    }
+}
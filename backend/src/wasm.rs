@@ -0,0 +1,111 @@
+//! # JavaScript Bindings
+//! 
+//! wasm-bindgen exports for a browser vault viewer (`wasm` feature):
+//! `VaultViewer` opens a vault file the page loaded (from IndexedDB, a
+//! file picker, or a download) and `generatePassword` runs the generator.
+//! Build with `--no-default-features --features wasm` for
+//! `wasm32-unknown-unknown`.
+//! 
+//! Accounts cross the boundary as JSON strings in the vault's own format;
+//! errors are thrown as JavaScript `Error`s carrying the error message.
+
+use crate::{PassManError, Result, generator::PasswordGenerator, models::PasswordOptions, viewer};
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+
+/// A vault opened for reading only
+#[wasm_bindgen]
+pub struct VaultViewer {
+    viewer: viewer::VaultViewer,
+}
+
+#[wasm_bindgen]
+impl VaultViewer {
+    /// Decrypt a vault file
+    /// 
+    /// # Arguments
+    /// * `file_data` - Contents of a vault file
+    /// * `master_password` - The master password
+    /// * `keyfile` - Keyfile contents, if the vault uses one
+    /// 
+    /// # Errors
+    /// Throws if the file is corrupted or the credentials are wrong
+    #[wasm_bindgen(constructor)]
+    pub fn open(file_data: &[u8], master_password: &str, keyfile: Option<Vec<u8>>) -> std::result::Result<VaultViewer, JsError> {
+        let viewer = viewer::VaultViewer::open(file_data, master_password, keyfile.as_deref()).map_err(js_error)?;
+        Ok(Self { viewer })
+    }
+    
+    /// Email the vault was created with
+    #[wasm_bindgen(getter)]
+    pub fn email(&self) -> String {
+        self.viewer.metadata().email.clone()
+    }
+    
+    /// Unarchived accounts without their secrets, as a JSON array sorted by name
+    pub fn accounts(&self) -> std::result::Result<String, JsError> {
+        to_json(&self.viewer.accounts()).map_err(js_error)
+    }
+    
+    /// Accounts whose name contains `query`, as a JSON array like `accounts`
+    pub fn search(&self, query: &str) -> std::result::Result<String, JsError> {
+        to_json(&self.viewer.search(query)).map_err(js_error)
+    }
+    
+    /// An account with its secrets, as a JSON object
+    /// 
+    /// # Errors
+    /// Throws if the ID is invalid or there is no account with it
+    pub fn account(&self, id: &str) -> std::result::Result<String, JsError> {
+        account_json(&self.viewer, id).map_err(js_error)
+    }
+}
+
+/// Generate a password
+/// 
+/// # Arguments
+/// * `options` - `PasswordOptions` as JSON; empty for the defaults
+/// 
+/// # Errors
+/// Throws if the options are invalid
+#[wasm_bindgen(js_name = generatePassword)]
+pub fn generate_password(options: &str) -> std::result::Result<String, JsError> {
+    generate(options).map_err(js_error)
+}
+
+fn account_json(viewer: &viewer::VaultViewer, id: &str) -> Result<String> {
+    let id = Uuid::parse_str(id)
+        .map_err(|e| PassManError::InvalidInput(format!("Invalid account ID '{}': {}", id, e)))?;
+    to_json(viewer.account(&id)?)
+}
+
+fn generate(options: &str) -> Result<String> {
+    let options = if options.trim().is_empty() {
+        PasswordOptions::default()
+    } else {
+        serde_json::from_str(options)
+            .map_err(|e| PassManError::InvalidInput(format!("Invalid password options: {}", e)))?
+    };
+    PasswordGenerator::new().generate(&options)
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String> {
+    serde_json::to_string(value).map_err(PassManError::SerializationError)
+}
+
+fn js_error(error: PassManError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_generate() {
+        assert_eq!(generate("").unwrap().chars().count(), PasswordOptions::default().length);
+        let options = serde_json::to_string(&PasswordOptions::simple(24)).unwrap();
+        assert_eq!(generate(&options).unwrap().len(), 24);
+        assert!(matches!(generate("{"), Err(PassManError::InvalidInput(_))));
+    }
+}
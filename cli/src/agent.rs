@@ -0,0 +1,455 @@
+//! # Session Agent
+//! 
+//! This module implements `passman agent`, a background process that
+//! holds derived vault keys so consecutive commands don't ask for the
+//! master password, the way ssh-agent and gpg-agent do. While it runs,
+//! the session cache (see `session`) lives in the agent instead of the OS
+//! keyring: keys are kept in locked memory that is never swapped out or
+//! written to a core dump, and are forgotten when their vault's auto-lock
+//! timeout passes, when the screen locks (unless
+//! `keep_unlocked_on_screen_lock` is set), or on `passman lock`.
+//! 
+//! ```bash
+//! passman agent start              # runs in the background
+//! passman agent start --ttl 15     # keep keys at most 15 minutes
+//! passman agent stop
+//! ```
+//! 
+//! With `agent.autostart = true` in `config.toml`, the agent is started
+//! the first time a vault is unlocked, and `agent.ttl` is used when
+//! `--ttl` isn't given.
+//! 
+//! Clients talk to the agent over a Unix socket in the user's runtime
+//! directory, one JSON request and response per line. There is no
+//! Windows named-pipe transport yet, so on Windows the agent can't be
+//! started and the session cache stays in the OS keyring.
+
+use crate::config::{AgentSettings, CliConfig};
+use passman_backend::{PassManError, Result, crypto::SecureKey};
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+
+/// Agent subcommands
+#[derive(Subcommand)]
+pub enum AgentCommand {
+    /// Start the agent in the background
+    Start {
+        /// Keep keys at most this many minutes, even if a vault's auto-lock timeout is longer
+        #[arg(long, value_name = "MINUTES")]
+        ttl: Option<u32>,
+        
+        /// Stay in the foreground instead of detaching
+        #[arg(long)]
+        foreground: bool,
+    },
+    
+    /// Stop the agent, forgetting every key it holds
+    Stop,
+}
+
+/// A request to the agent
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    /// Look up a vault's key
+    Get { vault: String },
+    
+    /// Hold a vault's key (base64) for some minutes
+    Put { vault: String, key: String, ttl_minutes: u32 },
+    
+    /// Forget a vault's key
+    Forget { vault: String },
+    
+    /// Check that the agent is running
+    Ping,
+    
+    /// Forget every key and exit
+    Stop,
+}
+
+/// The agent's answer to a request
+#[derive(Default, Serialize, Deserialize)]
+struct Response {
+    /// The vault's key (base64), for `Get`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    
+    /// When the key is forgotten, as a Unix timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+    
+    /// Whether a key was forgotten, for `Forget`
+    #[serde(default)]
+    removed: bool,
+    
+    /// Why the request failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Run an agent subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: AgentCommand) -> Result<()> {
+    match command {
+        AgentCommand::Start { ttl, foreground } => {
+            let ttl = match ttl {
+                Some(ttl) => Some(ttl),
+                None => CliConfig::load()?.agent.ttl,
+            };
+            if foreground { imp::serve(ttl) } else { imp::start(ttl) }
+        }
+        AgentCommand::Stop => imp::stop(),
+    }
+}
+
+/// Start the agent in the background if `agent.autostart` is set and it isn't running
+/// 
+/// # Arguments
+/// * `settings` - The `agent` config settings
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if the agent can't be started
+pub fn autostart(settings: &AgentSettings) -> Result<()> {
+    if !settings.autostart || imp::request(&Request::Ping)?.is_some() {
+        return Ok(());
+    }
+    imp::start(settings.ttl)
+}
+
+/// Look up a vault's key in the running agent
+/// 
+/// # Returns
+/// The key and when the agent forgets it, or None if no agent is running
+/// or it holds no key for the vault
+pub fn get(vault_name: &str) -> Option<(SecureKey, DateTime<Utc>)> {
+    let response = imp::request(&Request::Get { vault: vault_name.to_string() }).ok()??;
+    let key = decode_key(&zeroize::Zeroizing::new(response.key?))?;
+    let expires_at = DateTime::from_timestamp(response.expires_at?, 0)?;
+    Some((key, expires_at))
+}
+
+/// Give a vault's key to the running agent
+/// 
+/// # Arguments
+/// * `vault_name` - Vault the key belongs to
+/// * `key` - Derived vault key
+/// * `ttl_minutes` - How long the agent should keep it
+/// 
+/// # Returns
+/// When the agent forgets the key, or None if no agent is running
+/// 
+/// # Errors
+/// Returns an error if the agent refused the key
+pub fn put(vault_name: &str, key: &SecureKey, ttl_minutes: u32) -> Result<Option<DateTime<Utc>>> {
+    let mut request = Request::Put { vault: vault_name.to_string(), key: encode_key(key), ttl_minutes };
+    let response = imp::request(&request);
+    if let Request::Put { key, .. } = &mut request {
+        zeroize::Zeroize::zeroize(key);
+    }
+    let Some(response) = response? else {
+        return Ok(None);
+    };
+    Ok(response.expires_at.and_then(|timestamp| DateTime::from_timestamp(timestamp, 0)))
+}
+
+/// Make the running agent forget a vault's key
+/// 
+/// # Returns
+/// Whether a key was forgotten, or None if no agent is running
+/// 
+/// # Errors
+/// Returns an error if the agent can't be reached properly
+pub fn forget(vault_name: &str) -> Result<Option<bool>> {
+    Ok(imp::request(&Request::Forget { vault: vault_name.to_string() })?.map(|response| response.removed))
+}
+
+fn encode_key(key: &SecureKey) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    STANDARD.encode(key.as_bytes())
+}
+
+fn decode_key(encoded: &str) -> Option<SecureKey> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    let bytes = zeroize::Zeroizing::new(STANDARD.decode(encoded).ok()?);
+    let key: [u8; 32] = bytes.as_slice().try_into().ok()?;
+    Some(SecureKey::new(key))
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use crate::status;
+    use colored::*;
+    use passman_backend::{PassManConfig, screenlock};
+    use std::collections::HashMap;
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use zeroize::{Zeroize, Zeroizing};
+    
+    /// Socket file in the runtime directory
+    const SOCKET_NAME: &str = "agent.sock";
+    /// How often the agent checks for clients and expired keys
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+    /// How long a client or the agent may take to answer
+    const IO_TIMEOUT: Duration = Duration::from_secs(2);
+    /// How long `start` waits for the agent to come up
+    const START_TIMEOUT: Duration = Duration::from_secs(5);
+    
+    /// A key in memory that is locked against swapping and wiped on drop
+    struct LockedKey(Box<[u8; 32]>);
+    
+    impl LockedKey {
+        fn new(key: &SecureKey) -> Self {
+            let mut bytes = Box::new([0u8; 32]);
+            // SAFETY: the pointer and length describe the boxed array, which
+            // stays at the same address until it is unlocked in `drop`.
+            // Failing to lock (e.g. RLIMIT_MEMLOCK) leaves it swappable.
+            unsafe {
+                libc::mlock(bytes.as_ptr().cast(), bytes.len());
+            }
+            bytes.copy_from_slice(key.as_bytes());
+            Self(bytes)
+        }
+    }
+    
+    impl Drop for LockedKey {
+        fn drop(&mut self) {
+            self.0.zeroize();
+            // SAFETY: same region as locked in `new`
+            unsafe {
+                libc::munlock(self.0.as_ptr().cast(), self.0.len());
+            }
+        }
+    }
+    
+    /// A key the agent holds
+    struct Entry {
+        key: LockedKey,
+        expires_at: DateTime<Utc>,
+    }
+    
+    /// Send a request to the running agent
+    /// 
+    /// # Returns
+    /// The response, or None if no agent is running
+    pub(super) fn request(request: &Request) -> Result<Option<Response>> {
+        let Ok(mut stream) = UnixStream::connect(socket_path()?) else {
+            return Ok(None);
+        };
+        // Keys go only to an agent run by the same user
+        crate::socket::check_peer(&stream)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        
+        let mut line = Zeroizing::new(serde_json::to_string(request)?);
+        line.push('\n');
+        stream.write_all(line.as_bytes())?;
+        let mut answer = Zeroizing::new(String::new());
+        BufReader::new(stream).read_line(&mut answer)?;
+        let response: Response = serde_json::from_str(&answer)?;
+        match response.error {
+            Some(error) => Err(PassManError::StorageError(format!("Session agent: {}", error))),
+            None => Ok(Some(response)),
+        }
+    }
+    
+    /// Start the agent as a detached background process
+    pub(super) fn start(ttl: Option<u32>) -> Result<()> {
+        let socket = socket_path()?;
+        if UnixStream::connect(&socket).is_ok() {
+            return Err(PassManError::InvalidInput(format!("The agent is already running on {}", socket.display())));
+        }
+        
+        let mut cmd = Command::new(std::env::current_exe()?);
+        cmd.args(["agent", "start", "--foreground"]);
+        if let Some(ttl) = ttl {
+            cmd.args(["--ttl", &ttl.to_string()]);
+        }
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()
+            .map_err(|e| PassManError::IoError(io::Error::other(format!("Failed to start the agent: {}", e))))?;
+        
+        let started = std::time::Instant::now();
+        while UnixStream::connect(&socket).is_err() {
+            if started.elapsed() > START_TIMEOUT {
+                return Err(PassManError::IoError(io::Error::other("The agent didn't start; run `passman agent start --foreground` to see why")));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        status!("{}", format!("✓ Session agent started on {}", socket.display()).green().bold());
+        Ok(())
+    }
+    
+    /// Stop the running agent
+    pub(super) fn stop() -> Result<()> {
+        match request(&Request::Stop)? {
+            Some(_) => status!("{}", "✓ Session agent stopped".green().bold()),
+            None => status!("{}", "The session agent isn't running.".yellow()),
+        }
+        Ok(())
+    }
+    
+    /// Run the agent in the foreground until it is stopped
+    pub(super) fn serve(ttl_cap: Option<u32>) -> Result<()> {
+        harden_process();
+        let socket = socket_path()?;
+        let listener = crate::socket::bind(&socket)?;
+        status!("{}", format!("✓ Session agent listening on {}", socket.display()).green().bold());
+        
+        let os_locked = Arc::new(AtomicBool::new(false));
+        if !PassManConfig::load()?.keep_unlocked_on_screen_lock {
+            let flag = Arc::clone(&os_locked);
+            if let Err(e) = screenlock::watch(move |_| flag.store(true, Ordering::Relaxed)) {
+                status!("{}", format!("{}; keys are kept when the screen locks.", e).yellow());
+            }
+        }
+        
+        let mut keys: HashMap<String, Entry> = HashMap::new();
+        loop {
+            let now = Utc::now();
+            keys.retain(|_, entry| entry.expires_at > now);
+            if os_locked.swap(false, Ordering::Relaxed) && !keys.is_empty() {
+                keys.clear();
+                status!("{}", "Forgot all keys because the screen was locked.".yellow());
+            }
+            
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            // Only the user's own processes may get or put keys
+            if let Err(e) = crate::socket::check_peer(&stream) {
+                status!("{}", format!("Refused a client: {}", e).yellow());
+                continue;
+            }
+            // A client that stalls only holds up the agent until the timeout
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(IO_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+            if serve_client(stream, &mut keys, ttl_cap) {
+                break;
+            }
+        }
+        
+        keys.clear();
+        let _ = std::fs::remove_file(&socket);
+        status!("Session agent stopped");
+        Ok(())
+    }
+    
+    /// Answer one request
+    /// 
+    /// # Returns
+    /// True if the agent was asked to stop
+    fn serve_client(stream: UnixStream, keys: &mut HashMap<String, Entry>, ttl_cap: Option<u32>) -> bool {
+        let mut reader = BufReader::new(stream);
+        let mut line = Zeroizing::new(String::new());
+        if reader.read_line(&mut line).is_err() {
+            return false;
+        }
+        
+        let mut stop = false;
+        let mut response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Get { vault }) => match keys.get(&vault) {
+                Some(entry) => Response {
+                    key: Some(encode_key(&SecureKey::new(*entry.key.0))),
+                    expires_at: Some(entry.expires_at.timestamp()),
+                    ..Response::default()
+                },
+                None => Response::default(),
+            },
+            Ok(Request::Put { vault, mut key, ttl_minutes }) => {
+                let decoded = decode_key(&key);
+                key.zeroize();
+                match decoded {
+                    Some(decoded) => {
+                        let minutes = ttl_cap.map_or(ttl_minutes, |cap| ttl_minutes.min(cap));
+                        let expires_at = Utc::now() + chrono::Duration::minutes(minutes.into());
+                        keys.insert(vault, Entry { key: LockedKey::new(&decoded), expires_at });
+                        Response { expires_at: Some(expires_at.timestamp()), ..Response::default() }
+                    }
+                    None => Response { error: Some("malformed key".to_string()), ..Response::default() },
+                }
+            }
+            Ok(Request::Forget { vault }) => Response { removed: keys.remove(&vault).is_some(), ..Response::default() },
+            Ok(Request::Ping) => Response::default(),
+            Ok(Request::Stop) => {
+                stop = true;
+                Response::default()
+            }
+            Err(e) => Response { error: Some(format!("bad request: {}", e)), ..Response::default() },
+        };
+        
+        let mut answer = Zeroizing::new(serde_json::to_string(&response).unwrap_or_default());
+        if let Some(key) = response.key.as_mut() {
+            key.zeroize();
+        }
+        answer.push('\n');
+        let _ = reader.get_mut().write_all(answer.as_bytes());
+        stop
+    }
+    
+    /// Keep keys out of core dumps
+    fn harden_process() {
+        let limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: setrlimit only reads the struct passed by reference
+        unsafe {
+            libc::setrlimit(libc::RLIMIT_CORE, &limit);
+        }
+        // SAFETY: PR_SET_DUMPABLE takes a plain integer argument
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::prctl(libc::PR_SET_DUMPABLE, 0);
+        }
+    }
+    
+    fn socket_path() -> Result<PathBuf> {
+        crate::socket::runtime_socket_path(SOCKET_NAME)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::*;
+    
+    fn unsupported() -> PassManError {
+        PassManError::InvalidInput("passman agent needs Unix domain sockets; on Windows the session cache stays in the Credential Manager".to_string())
+    }
+    
+    pub(super) fn request(_request: &Request) -> Result<Option<Response>> {
+        Ok(None)
+    }
+    
+    pub(super) fn start(_ttl: Option<u32>) -> Result<()> {
+        Err(unsupported())
+    }
+    
+    pub(super) fn stop() -> Result<()> {
+        Err(unsupported())
+    }
+    
+    pub(super) fn serve(_ttl_cap: Option<u32>) -> Result<()> {
+        Err(unsupported())
+    }
+}
@@ -0,0 +1,253 @@
+//! # Password Audit Commands
+//! 
+//! This module implements `passman audit`, which reports reused, weak,
+//! stale, breached, and expired or expiring passwords, `passman breaches`, which manages the
+//! offline breach data the audit checks against, and `passman canary`,
+//! which manages canary accounts. Nothing is sent over the network: breach
+//! data comes from a Pwned Passwords dataset the user downloads themselves.
+//! The exception is `passman audit --breach` (with the `breach-online`
+//! feature), which sends 5-digit SHA-1 prefixes to Have I Been Pwned.
+//! 
+//! ```bash
+//! passman breaches install pwned-passwords-sha1-ordered-by-hash-v8.txt
+//! passman audit
+//! passman audit --breach   # online, k-anonymity
+//! passman canary mark "Staging admin"
+//! passman canary list --json > canaries.json   # for external monitoring
+//! ```
+
+use crate::{output, status};
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{
+    PassMan, Result,
+    audit::{AuditEntry, AuditOptions, HealthReport},
+    breach,
+};
+use std::path::PathBuf;
+
+/// Canary account subcommands
+#[derive(Subcommand)]
+pub enum CanaryCommand {
+    /// Mark an account as a canary (fake credentials for a service you control)
+    Mark {
+        /// Account name or ID
+        name: String,
+    },
+    
+    /// Turn a canary back into a normal account
+    Unmark {
+        /// Account name or ID
+        name: String,
+    },
+    
+    /// List canary accounts
+    List {
+        /// Print JSON for external monitoring, with password SHA-1 hashes
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Offline breach data subcommands
+#[derive(Subcommand)]
+pub enum BreachCommand {
+    /// Install breach data from the SHA-1 Pwned Passwords dataset (or a filter built from it)
+    Install {
+        /// The dataset (`HASH:COUNT` lines) or a `.bloom` filter file
+        file: PathBuf,
+        
+        /// Share of safe passwords a filter may report as breached
+        #[arg(long, default_value_t = breach::DEFAULT_FALSE_POSITIVE_RATE)]
+        false_positive_rate: f64,
+    },
+    
+    /// Show whether breach data is installed
+    Status,
+    
+    /// Delete the installed breach data
+    Remove,
+}
+
+/// Audit the passwords of the current vault
+/// 
+/// # Arguments
+/// * `stale_days` - Days after which an unchanged password is stale (0 = never)
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked or the breach data can't be read
+pub fn run(stale_days: u32, online: bool) -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let options = AuditOptions {
+        stale_after_days: stale_days,
+        ..AuditOptions::default()
+    };
+    
+    let report = if online {
+        let is_breached = check_online(&passman)?;
+        passman.health_report(&options, Some(is_breached.as_ref()))?
+    } else {
+        offline_report(&passman, &options)?
+    };
+    
+    if output::is_json() {
+        return output::print_json(&report);
+    }
+    
+    status!("{}", format!("Password health: {}/100 ({} accounts)", report.score, report.total).bold());
+    print_group("Breached", &report.breached);
+    for group in &report.reused {
+        print_group("Reused", group);
+    }
+    print_group("Weak", &report.weak);
+    print_group("Stale", &report.stale);
+    print_group("Expired", &report.expired);
+    print_group("Expiring soon", &report.expiring);
+    print_group("Tripped canaries", &report.tripped_canaries);
+    if report.affected_accounts() == 0 && report.tripped_canaries.is_empty() {
+        println!("No problems found");
+    }
+    if !report.canaries.is_empty() {
+        status!("{} canary account(s) not audited (see `passman canary list`).", report.canaries.len());
+    }
+    if !report.breach_checked {
+        status!("{}", "Breaches not checked: no breach data installed (see `passman breaches install`).".yellow());
+    }
+    Ok(())
+}
+
+/// Build a health report, checking breaches against the installed breach data if any
+/// 
+/// # Errors
+/// Returns an error if the breach data can't be read
+pub fn offline_report(passman: &PassMan, options: &AuditOptions) -> Result<HealthReport> {
+    let filter = breach::installed_filter()?;
+    let is_breached = |password: &str| filter.as_ref().is_some_and(|filter| filter.contains(password).unwrap_or(false));
+    passman.health_report(options, filter.is_some().then_some(&is_breached as &dyn Fn(&str) -> bool))
+}
+
+/// A breach check for `PassMan::health_report`
+type BreachCheck = Box<dyn Fn(&str) -> bool>;
+
+/// Check the vault's passwords against Have I Been Pwned
+/// 
+/// `--breach` on the command line is the user's consent for this run.
+/// 
+/// # Returns
+/// The breach check for the audit
+#[cfg(feature = "breach-online")]
+fn check_online(passman: &passman_backend::PassMan) -> Result<BreachCheck> {
+    let passwords: Vec<&str> = passman.get_all_accounts().into_iter()
+        .map(|account| account.password.as_str())
+        .filter(|password| !password.is_empty())
+        .collect();
+    status!(
+        "Checking {} password(s) with Have I Been Pwned; only the first 5 digits of each SHA-1 hash are sent...",
+        passwords.len(),
+    );
+    let breached = breach::online::RangeClient::new().check_passwords(passwords, breach::online::NetworkConsent::granted())?;
+    Ok(Box::new(move |password| breached.contains(password)))
+}
+
+#[cfg(not(feature = "breach-online"))]
+fn check_online(_passman: &passman_backend::PassMan) -> Result<BreachCheck> {
+    Err(passman_backend::PassManError::InvalidInput(
+        "This passman was built without online breach checks (build it with --features breach-online)".to_string(),
+    ))
+}
+
+/// Run a breach data subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run_breaches(command: BreachCommand) -> Result<()> {
+    match command {
+        BreachCommand::Install { file, false_positive_rate } => {
+            status!("Installing breach data from {}; the full dataset takes several minutes and a few GB of memory...", file.display());
+            let count = breach::install(&file, false_positive_rate)?;
+            status!("{}", format!("✓ Installed {} breached password hashes", count).green().bold());
+        }
+        BreachCommand::Status => match breach::installed_filter()? {
+            Some(filter) => println!("{} breached password hashes ({})", filter.len(), breach::filter_path()?.display()),
+            None => println!("No breach data installed"),
+        },
+        BreachCommand::Remove => {
+            if breach::uninstall()? {
+                status!("{}", "✓ Breach data removed".green().bold());
+            } else {
+                status!("{}", "No breach data installed.".yellow());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run a canary subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run_canaries(command: CanaryCommand) -> Result<()> {
+    match command {
+        CanaryCommand::Mark { name } => set_canary(&name, true)?,
+        CanaryCommand::Unmark { name } => set_canary(&name, false)?,
+        CanaryCommand::List { json } => {
+            let canaries = crate::unlock_vault()?.canaries()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&canaries)?);
+            } else if canaries.is_empty() {
+                println!("No canary accounts");
+            } else {
+                for canary in &canaries {
+                    let target = [canary.username.as_deref(), canary.url.as_deref()]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(" @ ");
+                    println!("{}  {}", canary.name.bold(), target);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_canary(name: &str, canary: bool) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let account = crate::find_account(&passman, name)?.clone();
+    if account.canary == canary {
+        status!("{}", format!("'{}' is already {}a canary.", account.name, if canary { "" } else { "not " }).yellow());
+        return Ok(());
+    }
+    passman.set_canary(account.id, canary)?;
+    if canary {
+        status!("{}", format!("✓ '{}' is now a canary; audits list it separately", account.name).green().bold());
+    } else {
+        status!("{}", format!("✓ '{}' is no longer a canary", account.name).green().bold());
+    }
+    Ok(())
+}
+
+/// Print one finding with its accounts
+fn print_group(label: &str, entries: &[AuditEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let label = match label {
+        "Breached" | "Tripped canaries" => label.red().bold(),
+        "Weak" | "Reused" | "Expired" => label.yellow().bold(),
+        _ => label.normal().bold(),
+    };
+    let names: Vec<String> = entries.iter()
+        .map(|entry| match &entry.username {
+            Some(username) if !username.is_empty() => format!("{} ({})", entry.name, username),
+            _ => entry.name.clone(),
+        })
+        .collect();
+    println!("{}: {}", label, names.join(", "));
+}
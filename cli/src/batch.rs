@@ -0,0 +1,116 @@
+//! # Batch Account Creation
+//! 
+//! This module parses CSV input for `passman add --batch`, turning each
+//! row into an account or a per-row error message.
+//! 
+//! The first row must be a header. Recognized columns (case-insensitive):
+//! `name`, `password`, `username`, `url`, `notes`, `tags` (separated by
+//! `;` or `,`), `type`, `folder`, and `totp`. Unknown columns are ignored.
+
+use passman_backend::{
+    PassManError, Result,
+    models::{Account, AccountType},
+    totp::Totp,
+};
+use std::io::Read;
+
+/// Outcome of parsing one CSV row
+pub struct BatchRow {
+    /// 1-based line number in the input (header is line 1)
+    pub line: usize,
+    
+    /// The parsed account, or why the row was rejected
+    pub result: std::result::Result<Account, String>,
+}
+
+/// Parse CSV input into accounts
+/// 
+/// # Arguments
+/// * `reader` - CSV input with a header row
+/// * `generate` - Password generator used for rows without a password
+/// 
+/// # Returns
+/// One entry per data row
+/// 
+/// # Errors
+/// Returns an error if the header is missing or lacks a `name` column
+pub fn parse_rows<R: Read>(
+    reader: R,
+    mut generate: Option<&mut dyn FnMut() -> Result<String>>,
+) -> Result<Vec<BatchRow>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    
+    let headers: Vec<String> = csv.headers()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to read CSV header: {}", e)))?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+    if !headers.iter().any(|h| h == "name") {
+        return Err(PassManError::InvalidInput("CSV header must include a 'name' column".to_string()));
+    }
+    
+    let mut rows = Vec::new();
+    for (index, record) in csv.records().enumerate() {
+        let line = index + 2;
+        let result = match record {
+            Ok(record) => {
+                let field = |name: &str| {
+                    headers.iter()
+                        .position(|h| h == name)
+                        .and_then(|i| record.get(i))
+                        .filter(|value| !value.is_empty())
+                        .map(str::to_string)
+                };
+                parse_account(&field, &mut generate)
+            }
+            Err(e) => Err(format!("malformed row: {}", e)),
+        };
+        rows.push(BatchRow { line, result });
+    }
+    
+    Ok(rows)
+}
+
+/// Build an account from the columns of one row
+fn parse_account(
+    field: &dyn Fn(&str) -> Option<String>,
+    generate: &mut Option<&mut dyn FnMut() -> Result<String>>,
+) -> std::result::Result<Account, String> {
+    let name = field("name").ok_or("missing name")?;
+    
+    let password = match (field("password"), generate.as_mut()) {
+        (Some(password), _) => password,
+        (None, Some(generate)) => generate().map_err(|e| e.to_string())?,
+        (None, None) => return Err("missing password (use --generate to fill in blanks)".to_string()),
+    };
+    
+    let account_type = match field("type") {
+        Some(value) => value.parse().map_err(|e: PassManError| e.to_string())?,
+        None => AccountType::Personal,
+    };
+    
+    let totp = field("totp");
+    if let Some(ref secret) = totp {
+        Totp::parse(secret).map_err(|e| e.to_string())?;
+    }
+    
+    let mut account = Account::new(name, account_type, password);
+    account.url = field("url");
+    account.username = field("username");
+    account.notes = field("notes");
+    account.folder = field("folder");
+    account.totp = totp;
+    account.tags = field("tags")
+        .map(|tags| {
+            tags.split([';', ','])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    Ok(account)
+}
@@ -0,0 +1,77 @@
+//! # Browser Launcher
+//! 
+//! This module opens account URLs in the user's default browser using
+//! the platform's standard launcher (xdg-open, open, or the URL protocol
+//! handler on Windows). Only http and https URLs are opened.
+
+use passman_backend::{PassManError, Result};
+use std::process::{Command, Stdio};
+
+/// Open a URL in the default browser
+/// 
+/// URLs without a scheme (e.g. "github.com") are opened over https.
+/// 
+/// # Arguments
+/// * `url` - The URL to open
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if the URL isn't an http or https URL, or the
+/// launcher cannot be started or reports failure
+pub fn open_url(url: &str) -> Result<()> {
+    let url = normalize_url(url)?;
+    
+    let status = launcher_command(&url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to launch browser: {}", e)))?;
+    
+    if !status.success() {
+        return Err(PassManError::InvalidInput(format!("Browser launcher failed to open {}", url)));
+    }
+    
+    Ok(())
+}
+
+/// Add an https scheme to URLs that don't have one, refusing other schemes
+fn normalize_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    if url.is_empty() || url.chars().any(char::is_control) {
+        return Err(PassManError::InvalidInput(format!("'{}' is not a web address", url.escape_debug())));
+    }
+    
+    match url.split_once("://") {
+        Some((scheme, _)) if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") => Ok(url.to_string()),
+        Some((scheme, _)) => Err(PassManError::InvalidInput(format!(
+            "Only http and https URLs are opened, not {}:// URLs",
+            scheme,
+        ))),
+        None => Ok(format!("https://{}", url)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn launcher_command(url: &str) -> Command {
+    let mut cmd = Command::new("open");
+    cmd.arg(url);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn launcher_command(url: &str) -> Command {
+    // Not `cmd /C start`: cmd.exe would interpret &, |, ^ and % in the URL
+    let mut cmd = Command::new("rundll32");
+    cmd.args(["url.dll,FileProtocolHandler", url]);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn launcher_command(url: &str) -> Command {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(url);
+    cmd
+}
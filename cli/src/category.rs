@@ -0,0 +1,110 @@
+//! # Category Commands
+//! 
+//! This module implements `passman category`, which manages the current
+//! vault's custom categories. Accounts use them like the built-in types,
+//! and adding an account to a category that doesn't exist yet defines it:
+//! 
+//! ```bash
+//! passman category add Travel
+//! passman add Airline --account-type Travel --generate
+//! passman list --account-type travel
+//! passman category rename Travel Trips
+//! passman category remove Trips   # its accounts become "Other"
+//! ```
+
+use crate::{output, status};
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{Result, models::AccountType};
+use serde::Serialize;
+
+/// Category subcommands
+#[derive(Subcommand)]
+pub enum CategoryCommand {
+    /// List the custom categories and how many accounts each holds
+    List,
+    
+    /// Define a custom category
+    Add {
+        /// Category name
+        name: String,
+    },
+    
+    /// Rename a custom category, moving its accounts along
+    Rename {
+        /// Current name
+        name: String,
+        
+        /// New name
+        new_name: String,
+    },
+    
+    /// Delete a custom category; its accounts become "Other"
+    Remove {
+        /// Category name
+        name: String,
+    },
+}
+
+/// A category, as printed with `--output json`
+#[derive(Serialize)]
+struct CategoryJson<'a> {
+    name: &'a str,
+    accounts: usize,
+}
+
+/// Run a category subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: CategoryCommand) -> Result<()> {
+    match command {
+        CategoryCommand::List => list(),
+        CategoryCommand::Add { name } => {
+            let mut passman = crate::unlock_vault()?;
+            passman.add_category(&name)?;
+            status!("{}", format!("✓ Category '{}' added", name.trim()).green().bold());
+            Ok(())
+        }
+        CategoryCommand::Rename { name, new_name } => {
+            let mut passman = crate::unlock_vault()?;
+            let moved = passman.rename_category(&name, &new_name)?;
+            status!("{}", format!("✓ Category renamed to '{}' ({} account(s))", new_name.trim(), moved).green().bold());
+            Ok(())
+        }
+        CategoryCommand::Remove { name } => {
+            let mut passman = crate::unlock_vault()?;
+            let moved = passman.remove_category(&name)?;
+            status!("{}", format!("✓ Category '{}' removed", name.trim()).green().bold());
+            if moved > 0 {
+                status!("  {} account(s) moved to Other", moved);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn list() -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let categories: Vec<CategoryJson> = passman.get_categories().iter()
+        .map(|name| CategoryJson {
+            name,
+            accounts: passman.get_accounts_by_type(&AccountType::Custom(name.clone())).len(),
+        })
+        .collect();
+    
+    if output::is_json() {
+        return output::print_json(&categories);
+    }
+    if categories.is_empty() {
+        status!("{}", "No custom categories yet; add one with `passman category add <name>`.".yellow());
+        return Ok(());
+    }
+    for category in &categories {
+        println!("{}  {}", category.name.white().bold(), format!("{} account(s)", category.accounts).dimmed());
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! # Clipboard Copies
+//! 
+//! This module puts secrets on the clipboard for the CLI and clears them
+//! after the vault's `clipboard_timeout` (or the `clipboard_timeout` in
+//! `config.toml`, which wins) without keeping the terminal busy. The copy is handed to a detached `passman` helper process: it
+//! owns the clipboard (on X11 copied text disappears as soon as its owner
+//! exits), waits out the timeout, and clears the clipboard if it still
+//! holds the secret. The secret reaches the helper through a pipe, never
+//! its command line.
+
+use crate::config::CliConfig;
+use passman_backend::{PassManError, Result, clipboard::Clipboard, models::VaultSettings};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// Hidden subcommand that runs the helper
+pub const HELPER_COMMAND: &str = "clipboard-helper";
+
+/// Line the helper prints once the text is on the clipboard
+const READY: &str = "ready";
+
+/// Copy text to the clipboard, clearing it in the background if the vault says so
+/// 
+/// # Arguments
+/// * `text` - The text to copy
+/// * `settings` - Settings of the vault the text came from
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if no clipboard is available or the helper can't be
+/// started
+pub fn copy(text: &str, settings: &VaultSettings) -> Result<()> {
+    let Some(timeout) = clear_timeout(settings) else {
+        return Clipboard::new()?.copy(text);
+    };
+    
+    let mut helper = helper_command(timeout)?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| PassManError::IoError(io::Error::other(format!("Failed to start the clipboard helper: {}", e))))?;
+    
+    let (Some(mut stdin), Some(stdout)) = (helper.stdin.take(), helper.stdout.take()) else {
+        return Err(PassManError::IoError(io::Error::other("The clipboard helper has no pipes")));
+    };
+    stdin.write_all(text.as_bytes())?;
+    drop(stdin);
+    
+    // "ready", or why the helper couldn't copy
+    let mut line = String::new();
+    BufReader::new(stdout).read_line(&mut line)?;
+    match line.trim_end() {
+        READY => Ok(()),
+        message => {
+            let _ = helper.wait();
+            let message = if message.is_empty() { "The clipboard helper failed" } else { message };
+            Err(PassManError::IoError(io::Error::other(message)))
+        }
+    }
+}
+
+/// Seconds before a copied secret is cleared
+/// 
+/// # Arguments
+/// * `settings` - Settings of the vault the secret came from
+/// 
+/// # Returns
+/// The timeout, or None if the clipboard isn't cleared
+pub fn clear_timeout(settings: &VaultSettings) -> Option<u32> {
+    match CliConfig::load().ok().and_then(|config| config.clipboard_timeout) {
+        Some(0) => None,
+        Some(timeout) => Some(timeout),
+        None => settings.auto_clear_clipboard.then_some(settings.clipboard_timeout),
+    }
+}
+
+/// Run the helper: copy stdin to the clipboard, then clear it after `timeout` seconds
+/// 
+/// # Arguments
+/// * `timeout` - Seconds before the clipboard is cleared
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if stdin can't be read or the clipboard can't be
+/// used; it is also reported to the process that started the helper
+pub fn run_helper(timeout: u32) -> Result<()> {
+    let mut text = Zeroizing::new(String::new());
+    io::stdin().read_to_string(&mut text)?;
+    
+    let copied = Clipboard::new().and_then(|mut clipboard| clipboard.copy(&text).map(|_| clipboard));
+    let mut clipboard = match copied {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            let message = match &e {
+                PassManError::IoError(inner) => inner.to_string(),
+                e => e.to_string(),
+            };
+            println!("{}", message);
+            return Err(e);
+        }
+    };
+    println!("{}", READY);
+    io::stdout().flush()?;
+    
+    clipboard.clear_after(&text, Duration::from_secs(timeout as u64))?;
+    Ok(())
+}
+
+/// Command that starts the helper detached from the terminal
+fn helper_command(timeout: u32) -> Result<Command> {
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.args([HELPER_COMMAND, "--timeout", &timeout.to_string()]);
+    
+    // Outlive the terminal's process group (e.g. Ctrl+C right after copying)
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    
+    Ok(cmd)
+}
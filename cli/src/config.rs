@@ -0,0 +1,434 @@
+//! # CLI Configuration
+//! 
+//! This module loads the optional `config.toml` from PassMan's config
+//! directory (`~/.config/passman/config.toml` on Linux). Every setting is
+//! optional; a missing file means all defaults.
+//! 
+//! The same file holds the library settings shared with the desktop app
+//! (`PassManConfig`), which are kept when the CLI saves it.
+//! 
+//! `passman config get/set/unset` read and change settings by dotted key
+//! (`generator.length`), and each CLI setting can be overridden for one
+//! run with an environment variable named after its key
+//! (`PASSMAN_GENERATOR_LENGTH`); see `SETTINGS`.
+//! 
+//! ```bash
+//! passman config get
+//! passman config set generator.length 24
+//! passman config unset clipboard_timeout
+//! ```
+
+use crate::output::{self, OutputFormat};
+use crate::status;
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{PassManConfig, PassManError, Result, config, models::PasswordOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// CLI settings that can be overridden by `PASSMAN_<KEY>` environment variables
+pub const SETTINGS: [&str; 16] = [
+    "color",
+    "output",
+    "current_vault",
+    "clipboard_timeout",
+    "generator.length",
+    "generator.uppercase",
+    "generator.lowercase",
+    "generator.numbers",
+    "generator.special",
+    "generator.words",
+    "generator.separator",
+    "generator.capitalize",
+    "generator.digits",
+    "generator.symbols",
+    "agent.ttl",
+    "agent.autostart",
+];
+
+/// Config subcommands
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print a setting, or the whole configuration
+    Get {
+        /// Dotted key, e.g. generator.length
+        key: Option<String>,
+    },
+    
+    /// Change a setting
+    Set {
+        /// Dotted key, e.g. generator.length
+        key: String,
+        
+        /// New value (TOML syntax; plain text is taken as a string)
+        value: String,
+    },
+    
+    /// Reset a setting to its default
+    Unset {
+        /// Dotted key, e.g. generator.length
+        key: String,
+    },
+    
+    /// Print the path of the config file
+    Path,
+}
+
+/// When to emit ANSI colors
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color only when stdout is a terminal and the environment allows it
+    #[default]
+    Auto,
+    
+    /// Always color, even when piped
+    Always,
+    
+    /// Never color
+    Never,
+}
+
+/// Defaults for `passman generate`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GeneratorDefaults {
+    /// Password length
+    pub length: usize,
+    
+    /// Character classes used when none are given on the command line
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub numbers: bool,
+    pub special: bool,
+    
+    /// Words in a passphrase
+    pub words: usize,
+    
+    /// Text placed between passphrase words
+    pub separator: String,
+    
+    /// Capitalize each passphrase word
+    pub capitalize: bool,
+    
+    /// Append a random digit to one passphrase word
+    pub digits: bool,
+    
+    /// Append a random symbol to one passphrase word
+    pub symbols: bool,
+}
+
+impl Default for GeneratorDefaults {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            numbers: true,
+            special: true,
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            digits: false,
+            symbols: false,
+        }
+    }
+}
+
+impl GeneratorDefaults {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Password generator options for these defaults
+    pub fn password_options(&self) -> PasswordOptions {
+        PasswordOptions {
+            length: self.length,
+            include_uppercase: self.uppercase,
+            include_lowercase: self.lowercase,
+            include_numbers: self.numbers,
+            include_special: self.special,
+            ..PasswordOptions::default()
+        }
+    }
+}
+
+/// Settings of the session agent (`passman agent`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AgentSettings {
+    /// Keep keys at most this many minutes (default: each vault's auto-lock timeout)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    
+    /// Start the agent when a vault is unlocked and it isn't running
+    pub autostart: bool,
+}
+
+impl AgentSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Settings read from `config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CliConfig {
+    /// Color preference, overridden by `--color`
+    pub color: ColorChoice,
+    
+    /// Format of command results, overridden by `--output`
+    pub output: OutputFormat,
+    
+    /// Vault used when a command doesn't name one
+    pub current_vault: Option<String>,
+    
+    /// Seconds before copied secrets are cleared, instead of each vault's
+    /// `clipboard_timeout` (0 = never)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clipboard_timeout: Option<u32>,
+    
+    /// Defaults for `passman generate`
+    #[serde(skip_serializing_if = "GeneratorDefaults::is_default")]
+    pub generator: GeneratorDefaults,
+    
+    /// Session agent settings
+    #[serde(skip_serializing_if = "AgentSettings::is_default")]
+    pub agent: AgentSettings,
+    
+    /// Keyfile location for each vault that requires one
+    pub keyfiles: BTreeMap<String, PathBuf>,
+    
+    /// Settings shared with the library and the desktop app
+    #[serde(flatten)]
+    pub library: PassManConfig,
+}
+
+impl CliConfig {
+    /// Load the effective configuration: the config file with environment overrides
+    /// 
+    /// # Returns
+    /// The parsed configuration
+    /// 
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed,
+    /// or an override has an invalid value
+    pub fn load() -> Result<Self> {
+        let mut table = file_table()?;
+        for key in SETTINGS {
+            let name = env_name(key);
+            if let Some(value) = std::env::var(&name).ok().filter(|value| !value.is_empty()) {
+                set_value(&mut table, key, &value).map_err(|e| match e {
+                    PassManError::InvalidInput(message) => PassManError::InvalidInput(format!("{}: {}", name, message)),
+                    e => e,
+                })?;
+            }
+        }
+        from_table(table)
+    }
+    
+    /// Load the config file alone, to change and save it
+    /// 
+    /// # Returns
+    /// The parsed configuration, without environment overrides
+    /// 
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed
+    pub fn load_file() -> Result<Self> {
+        from_table(file_table()?)
+    }
+    
+    /// Write the config file, creating the config directory if needed
+    /// 
+    /// # Returns
+    /// Unit on success
+    /// 
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub fn save(&self) -> Result<()> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| PassManError::StorageError(format!("Failed to serialize config: {}", e)))?;
+        std::fs::write(&path, contents)?;
+        
+        Ok(())
+    }
+}
+
+/// Path of the config file
+pub fn config_path() -> Result<PathBuf> {
+    config::config_path()
+}
+
+/// Run a config subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Get { key } => get(key.as_deref()),
+        ConfigCommand::Set { key, value } => {
+            let mut table = file_table()?;
+            set_value(&mut table, &key, &value)?;
+            from_table(table)?.save()?;
+            status!("{}", format!("✓ Set {}", key).green());
+            Ok(())
+        }
+        ConfigCommand::Unset { key } => {
+            let mut table = file_table()?;
+            let (parent, name) = parent_table(&mut table, &key)?;
+            if parent.remove(name).is_none() {
+                status!("{}", format!("{} is not set", key).yellow());
+                return Ok(());
+            }
+            from_table(table)?.save()?;
+            status!("{}", format!("✓ Reset {} to its default", key).green());
+            Ok(())
+        }
+        ConfigCommand::Path => {
+            println!("{}", config_path()?.display());
+            Ok(())
+        }
+    }
+}
+
+/// Print a setting of the effective configuration, or all of them
+fn get(key: Option<&str>) -> Result<()> {
+    let config = CliConfig::load()?;
+    let all = toml::Value::try_from(&config)
+        .map_err(|e| PassManError::StorageError(format!("Failed to serialize config: {}", e)))?;
+    let Some(key) = key else {
+        if output::is_json() {
+            return output::print_json(&all);
+        }
+        print!("{}", toml::to_string_pretty(&all).unwrap_or_default());
+        return Ok(());
+    };
+    
+    let value = key.split('.')
+        .try_fold(&all, |value, part| value.get(part))
+        .ok_or_else(|| PassManError::InvalidInput(format!("{} is not set", key)))?;
+    match value {
+        _ if output::is_json() => output::print_json(value)?,
+        toml::Value::String(text) => println!("{}", text),
+        toml::Value::Table(table) => print!("{}", toml::to_string_pretty(table).unwrap_or_default()),
+        value => println!("{}", value),
+    }
+    Ok(())
+}
+
+/// The config file's contents as a TOML table (empty if there is no file)
+fn file_table() -> Result<toml::Table> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    
+    let contents = std::fs::read_to_string(&path)?;
+    contents.parse()
+        .map_err(|e| PassManError::InvalidInput(format!("Invalid config file {}: {}", path.display(), e)))
+}
+
+/// Parse settings from a TOML table
+fn from_table(table: toml::Table) -> Result<CliConfig> {
+    parse_table(table)
+        .map_err(|e| PassManError::InvalidInput(format!("Invalid config file {}: {}", config_path().map(|path| path.display().to_string()).unwrap_or_default(), e.message().trim_end())))
+}
+
+fn parse_table(table: toml::Table) -> std::result::Result<CliConfig, toml::de::Error> {
+    toml::Value::Table(table).try_into()
+}
+
+/// Set a dotted key in a config table, checking that it is a known setting
+/// 
+/// The value is read as TOML (`24`, `true`, `["a"]`); if the setting
+/// doesn't accept that, it is taken as a string.
+fn set_value(table: &mut toml::Table, key: &str, raw: &str) -> Result<()> {
+    let parsed = format!("value = {}", raw).parse::<toml::Table>().ok()
+        .and_then(|mut parsed| parsed.remove("value"));
+    let candidates = parsed.into_iter().chain([toml::Value::String(raw.to_string())]);
+    
+    let before = from_table(table.clone())?;
+    let mut last_error = None;
+    for value in candidates {
+        let mut updated = table.clone();
+        let (parent, name) = parent_table(&mut updated, key)?;
+        parent.insert(name.to_string(), value.clone());
+        match parse_table(updated.clone()) {
+            // Keys the config doesn't know are dropped when it is read, so they change nothing
+            Ok(config) if config != before || SETTINGS.contains(&key) || setting(&config, key).as_ref() == Some(&value) => {
+                *table = updated;
+                return Ok(());
+            }
+            Ok(_) => last_error = Some(format!("'{}' is not a setting, or is already {}", key, raw)),
+            Err(e) => last_error = Some(format!("invalid value for {}: {}", key, e.message().trim_end())),
+        }
+    }
+    Err(PassManError::InvalidInput(last_error.unwrap_or_default()))
+}
+
+/// Look up a dotted key in a parsed configuration
+fn setting(config: &CliConfig, key: &str) -> Option<toml::Value> {
+    let value = toml::Value::try_from(config).ok()?;
+    key.split('.').try_fold(value, |value, part| value.get(part).cloned())
+}
+
+/// The table holding a dotted key, created as needed, and the key's last part
+fn parent_table<'a, 'k>(table: &'a mut toml::Table, key: &'k str) -> Result<(&'a mut toml::Table, &'k str)> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let name = parts.pop().filter(|name| !name.is_empty())
+        .ok_or_else(|| PassManError::InvalidInput("The key is empty".to_string()))?;
+    let mut parent = table;
+    for part in parts {
+        parent = parent.entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| PassManError::InvalidInput(format!("'{}' in '{}' is not a section", part, key)))?;
+    }
+    Ok((parent, name))
+}
+
+/// Environment variable overriding a setting, e.g. `PASSMAN_GENERATOR_LENGTH`
+fn env_name(key: &str) -> String {
+    format!("PASSMAN_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Decide whether to color output and apply the decision globally
+/// 
+/// An explicit `--color` wins, then a non-auto `color` config setting.
+/// In auto mode `NO_COLOR` disables color, `CLICOLOR_FORCE` enables it,
+/// and otherwise color is used only when stdout is a terminal.
+/// 
+/// # Arguments
+/// * `flag` - Value of the `--color` flag, if given
+/// * `config` - Value of the `color` config setting
+pub fn apply_color_choice(flag: Option<ColorChoice>, config: ColorChoice) {
+    let choice = flag.unwrap_or(config);
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => auto_color(),
+    };
+    colored::control::set_override(enabled);
+}
+
+/// Resolve auto color mode from the environment and terminal
+fn auto_color() -> bool {
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    if std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0") {
+        return true;
+    }
+    std::io::stdout().is_terminal()
+}
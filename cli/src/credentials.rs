@@ -0,0 +1,135 @@
+//! # Non-Interactive Credentials
+//! 
+//! This module lets scripts unlock a vault without a terminal. The vault
+//! comes from `--vault` or `PASSMAN_VAULT` (falling back to the vault
+//! chosen with `passman use`), and the master password from the first
+//! line of stdin (`--password-stdin`) or of an inherited file descriptor
+//! (`--password-fd N`, Unix only):
+//! 
+//! ```bash
+//! PASSMAN_VAULT=work passman show GitHub --password-stdin < ~/.secrets/work
+//! passman list --password-fd 3 3< <(pass show passman)
+//! ```
+//! 
+//! The password is read once and reused by every prompt of the command.
+//! Without either flag the password is asked for on the terminal, and
+//! commands fail with a clear error when there is none.
+
+use passman_backend::{PassManError, Result};
+use std::io::{self, BufRead};
+use std::sync::{Mutex, OnceLock};
+use zeroize::Zeroizing;
+
+/// Environment variable naming the vault to use
+pub const VAULT_ENV: &str = "PASSMAN_VAULT";
+
+/// Where the master password comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordSource {
+    /// Asked for on the terminal
+    #[default]
+    Terminal,
+    
+    /// The first line of stdin
+    Stdin,
+    
+    /// The first line of an inherited file descriptor
+    Fd(u32),
+}
+
+/// Source chosen on the command line
+static SOURCE: OnceLock<PasswordSource> = OnceLock::new();
+
+/// Vault chosen with `--vault`
+static VAULT: OnceLock<String> = OnceLock::new();
+
+/// Password read from a non-terminal source, kept for later prompts
+static PASSWORD: Mutex<Option<Zeroizing<String>>> = Mutex::new(None);
+
+/// Choose where the master password comes from
+pub fn set_source(source: PasswordSource) {
+    let _ = SOURCE.set(source);
+}
+
+/// Use a vault for this run instead of the current vault
+pub fn set_vault(name: String) {
+    let _ = VAULT.set(name);
+}
+
+/// The vault given for this run with `--vault` or `PASSMAN_VAULT`
+/// 
+/// # Returns
+/// The vault name and where it came from, or None if neither was given
+pub fn given_vault() -> Option<(String, &'static str)> {
+    if let Some(name) = VAULT.get() {
+        return Some((name.clone(), "--vault"));
+    }
+    std::env::var(VAULT_ENV).ok()
+        .filter(|name| !name.is_empty())
+        .map(|name| (name, VAULT_ENV))
+}
+
+/// Check whether the master password is asked for on the terminal
+pub fn is_interactive() -> bool {
+    SOURCE.get().copied().unwrap_or_default() == PasswordSource::Terminal
+}
+
+/// The master password given by `--password-stdin` or `--password-fd`
+/// 
+/// # Returns
+/// The password, or None if it should be asked for on the terminal
+/// 
+/// # Errors
+/// Returns an error if the source can't be read or has no password
+pub fn given_password() -> Result<Option<String>> {
+    let source = SOURCE.get().copied().unwrap_or_default();
+    if source == PasswordSource::Terminal {
+        return Ok(None);
+    }
+    
+    let mut cached = PASSWORD.lock().unwrap_or_else(|e| e.into_inner());
+    if cached.is_none() {
+        *cached = Some(match source {
+            PasswordSource::Stdin => read_first_line(io::stdin().lock(), "stdin")?,
+            PasswordSource::Fd(fd) => read_fd(fd)?,
+            PasswordSource::Terminal => unreachable!("handled above"),
+        });
+    }
+    Ok(cached.as_ref().map(|password| password.to_string()))
+}
+
+/// Error for a command that needs input no one can give
+/// 
+/// # Arguments
+/// * `what` - What was needed, e.g. "the master password of 'main'"
+pub fn no_terminal(what: &str) -> PassManError {
+    PassManError::VaultLocked(format!(
+        "no terminal to ask for {}; pass the master password with --password-stdin or --password-fd",
+        what,
+    ))
+}
+
+#[cfg(unix)]
+fn read_fd(fd: u32) -> Result<Zeroizing<String>> {
+    let name = format!("file descriptor {}", fd);
+    let file = std::fs::File::open(format!("/dev/fd/{}", fd))
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read the master password from {}: {}", name, e)))?;
+    read_first_line(io::BufReader::new(file), &name)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: u32) -> Result<Zeroizing<String>> {
+    Err(PassManError::InvalidInput("--password-fd is only supported on Unix; use --password-stdin".to_string()))
+}
+
+/// Read a password from the first line of `input`
+fn read_first_line(input: impl BufRead, name: &str) -> Result<Zeroizing<String>> {
+    let mut line = Zeroizing::new(String::new());
+    input.take(64 * 1024).read_line(&mut line)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read the master password from {}: {}", name, e)))?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err(PassManError::InvalidInput(format!("No master password on {}", name)));
+    }
+    Ok(Zeroizing::new(password.to_string()))
+}
@@ -0,0 +1,116 @@
+//! # Duplicate Accounts
+//! 
+//! This module implements `passman dedupe`, which walks through accounts
+//! that share a site and username (often left behind by imports) and
+//! merges each group into the account the user picks. Tags, notes, and
+//! custom fields are combined, other passwords are kept as hidden
+//! "Previous password" fields, and the merged-away accounts go to the
+//! trash, so nothing is lost for good:
+//! 
+//! ```bash
+//! passman dedupe
+//! passman dedupe --list     # only show the groups
+//! ```
+
+use crate::status;
+use colored::*;
+use passman_backend::{Result, models::Account};
+use std::io::{self, Write};
+
+/// Find duplicate accounts and merge them group by group
+/// 
+/// # Arguments
+/// * `list` - Only show the groups, without merging anything
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked, input can't be read,
+/// or a merge can't be saved
+pub fn run(list: bool) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let groups: Vec<Vec<(_, String)>> = passman.find_duplicates().iter()
+        .map(|group| group.iter().map(|account| (account.id, describe(account))).collect())
+        .collect();
+    if groups.is_empty() {
+        status!("{}", "No duplicate accounts found.".green());
+        return Ok(());
+    }
+    
+    let mut merged = 0;
+    for (index, group) in groups.iter().enumerate() {
+        println!("{}", format!("Group {} of {}", index + 1, groups.len()).bold());
+        for (number, (_, line)) in group.iter().enumerate() {
+            println!("  {}. {}", number + 1, line);
+        }
+        if list {
+            println!();
+            continue;
+        }
+        
+        match prompt_keep(group.len())? {
+            Choice::Keep(keep) => {
+                let others: Vec<_> = group.iter().map(|(id, _)| *id).filter(|id| *id != group[keep].0).collect();
+                passman.merge_duplicates(group[keep].0, &others)?;
+                merged += others.len();
+                status!("{}", format!("✓ Merged {} account(s) into #{}", others.len(), keep + 1).green());
+            }
+            Choice::Skip => {}
+            Choice::Quit => break,
+        }
+        status!();
+    }
+    
+    if list {
+        status!("{}", format!("{} group(s) of duplicates; run `passman dedupe` to merge them", groups.len()).blue());
+    } else if merged > 0 {
+        status!("{}", format!("✓ Merged {} duplicate(s); restore any with `passman trash restore`", merged).green().bold());
+    } else {
+        status!("Nothing was merged");
+    }
+    Ok(())
+}
+
+/// One line about an account, enough to tell duplicates apart
+fn describe(account: &Account) -> String {
+    let updated_at: chrono::DateTime<chrono::Local> = account.updated_at.into();
+    let mut line = format!(
+        "{}  {}  {}",
+        account.name.white().bold(),
+        account.username.as_deref().unwrap_or("-"),
+        account.url.as_deref().unwrap_or("-").dimmed(),
+    );
+    line.push_str(&format!("  updated {}", updated_at.format("%Y-%m-%d")).dimmed().to_string());
+    if !account.tags.is_empty() {
+        line.push_str(&format!("  [{}]", account.tags.join(", ")));
+    }
+    line
+}
+
+/// What to do with a group of duplicates
+enum Choice {
+    /// Merge the others into the account at this index
+    Keep(usize),
+    Skip,
+    Quit,
+}
+
+/// Ask which account of a group to keep
+fn prompt_keep(count: usize) -> Result<Choice> {
+    loop {
+        eprint!("Keep which account? [1-{}, s to skip, q to quit] (1): ", count);
+        io::stderr().flush()?;
+        
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(Choice::Quit);
+        }
+        match input.trim().to_lowercase().as_str() {
+            "" => return Ok(Choice::Keep(0)),
+            "s" | "skip" => return Ok(Choice::Skip),
+            "q" | "quit" => return Ok(Choice::Quit),
+            answer => match answer.parse::<usize>() {
+                Ok(number) if (1..=count).contains(&number) => return Ok(Choice::Keep(number - 1)),
+                _ => eprintln!("{}", format!("Enter a number from 1 to {}", count).yellow()),
+            },
+        }
+    }
+}
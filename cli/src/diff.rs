@@ -0,0 +1,83 @@
+//! # Backup Diff
+//! 
+//! This module implements `passman diff`, which answers "what changed
+//! since this backup?" by comparing a backup with the current vault.
+//! Secret values are masked:
+//! 
+//! ```bash
+//! passman diff --list
+//! passman diff 20240601_093000
+//! ```
+
+use crate::status;
+use colored::*;
+use passman_backend::{PassManError, Result, diff::VaultDiff};
+
+/// Run the diff command
+/// 
+/// # Arguments
+/// * `backup` - Timestamp of the backup to compare with (default: the newest)
+/// * `list` - List the backups instead of comparing
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked or the backup can't be read
+pub fn run(backup: Option<String>, list: bool) -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let backups = passman.list_backups()?;
+    
+    if list {
+        for backup in &backups {
+            println!(
+                "{}  {}{}",
+                backup.timestamp,
+                backup.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                if backup.signed { "  (signed)" } else { "" },
+            );
+        }
+        if backups.is_empty() {
+            status!("{}", "No backups yet.".yellow());
+        }
+        return Ok(());
+    }
+    
+    let timestamp = match backup {
+        Some(timestamp) => timestamp,
+        None => backups.first()
+            .map(|backup| backup.timestamp.clone())
+            .ok_or_else(|| PassManError::InvalidInput("The vault has no backups to compare with".to_string()))?,
+    };
+    let changes = passman.diff_backup(&timestamp)?;
+    
+    status!("{}", format!("Changes since the backup from {}:", timestamp).bold());
+    print_diff(&changes);
+    Ok(())
+}
+
+/// Print a diff, one line per account and changed field
+fn print_diff(changes: &VaultDiff) {
+    if changes.is_empty() {
+        println!("No changes");
+        return;
+    }
+    
+    for account in &changes.added {
+        println!("{} {}", "+".green().bold(), account.name);
+    }
+    for account in &changes.removed {
+        println!("{} {}", "-".red().bold(), account.name);
+    }
+    for account in &changes.modified {
+        println!("{} {}", "~".yellow().bold(), account.name);
+        for change in &account.changes {
+            // Keep multi-line notes on one line
+            let show = |value: &Option<String>| value.as_deref().map_or_else(|| "(none)".to_string(), |v| v.replace('\n', "\\n"));
+            println!(
+                "    {}: {} {} {}",
+                change.field,
+                show(&change.old).dimmed(),
+                "→".dimmed(),
+                show(&change.new),
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! # External Editor
+//! 
+//! This module lets the user author multi-line text (e.g. notes) in
+//! `$VISUAL`/`$EDITOR`. The text is staged in a private temp file, on a
+//! RAM-backed filesystem where available, and overwritten before removal
+//! so the plaintext doesn't linger on disk.
+
+use passman_backend::{PassManError, Result};
+use rand::Rng;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zeroize::Zeroizing;
+
+/// RAM-backed directory preferred for temp files on Linux
+const RAMDISK_DIR: &str = "/dev/shm";
+
+/// Edit text in the user's editor
+/// 
+/// # Arguments
+/// * `initial` - Text to prefill the editor with
+/// 
+/// # Returns
+/// The edited text
+/// 
+/// # Errors
+/// Returns an error if the temp file can't be written or the editor fails
+pub fn edit_text(initial: &str) -> Result<Zeroizing<String>> {
+    let path = temp_path();
+    let mut file = create_private(&path)?;
+    
+    let result = (|| {
+        file.write_all(initial.as_bytes())?;
+        file.sync_all()?;
+        
+        let editor = editor_command();
+        let status = shell_command(&editor, &path)
+            .status()
+            .map_err(|e| PassManError::InvalidInput(format!("Failed to start editor '{}': {}", editor, e)))?;
+        if !status.success() {
+            return Err(PassManError::InvalidInput(format!("Editor '{}' exited with {}", editor, status)));
+        }
+        
+        Ok(Zeroizing::new(fs::read_to_string(&path)?))
+    })();
+    
+    // Always scrub the file, even if the editor failed
+    let scrubbed = scrub(&path);
+    let text = result?;
+    scrubbed?;
+    
+    Ok(text)
+}
+
+/// Pick the editor from `$VISUAL`, `$EDITOR`, or a platform default
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad".to_string() } else { "vi".to_string() })
+}
+
+/// Choose an unpredictable temp file path, preferring a ramdisk
+fn temp_path() -> PathBuf {
+    let ramdisk = Path::new(RAMDISK_DIR);
+    let dir = if ramdisk.is_dir() { ramdisk.to_path_buf() } else { std::env::temp_dir() };
+    let suffix: u64 = rand::thread_rng().gen();
+    dir.join(format!("passman-{:016x}.txt", suffix))
+}
+
+/// Create a new file readable only by the current user
+fn create_private(path: &Path) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to create temp file {}: {}", path.display(), e)))
+}
+
+/// Overwrite a file with zeros, then delete it
+fn scrub(path: &Path) -> Result<()> {
+    // Editors may replace the file, so reopen it rather than reusing a handle
+    if let Ok(mut file) = OpenOptions::new().write(true).open(path) {
+        let len = file.metadata()?.len() as usize;
+        file.rewind()?;
+        file.write_all(&vec![0u8; len])?;
+        file.sync_all()?;
+    }
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Run the editor through the shell so values like `code --wait` work
+#[cfg(target_os = "windows")]
+fn shell_command(editor: &str, path: &Path) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(format!("{} \"{}\"", editor, path.display()));
+    cmd
+}
+
+/// Run the editor through the shell so values like `code --wait` work
+#[cfg(not(target_os = "windows"))]
+fn shell_command(editor: &str, path: &Path) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(format!("{} \"$1\"", editor)).arg("sh").arg(path);
+    cmd
+}
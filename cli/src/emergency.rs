@@ -0,0 +1,199 @@
+//! # Emergency Access Commands
+//! 
+//! This module implements `passman emergency`. The owner grants a contact
+//! an emergency key; the contact can request access at any time, and
+//! unlocks the vault once the waiting period passes without the owner
+//! denying the request:
+//! 
+//! ```bash
+//! passman emergency grant "Alice" --wait-days 7   # owner, prints the key once
+//! passman emergency request main                 # contact, starts the wait
+//! passman emergency deny                         # owner, cancels the request
+//! passman emergency unlock main                  # contact, after the wait
+//! ```
+//! 
+//! The request is a small file next to the vault file, so it reaches the
+//! owner through whatever copies the vault between devices.
+//! 
+//! The waiting period is advisory. The emergency key alone unlocks the
+//! vault, and the request time isn't authenticated, so a contact who
+//! edits the request file or patches PassMan can skip the wait.
+
+use crate::{status, wizard};
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{PassMan, PassManError, Result};
+use std::io::{self, Write};
+
+/// Emergency access subcommands
+#[derive(Subcommand)]
+pub enum EmergencyCommand {
+    /// Give a contact an emergency key to the current vault (replaces any previous key)
+    /// 
+    /// The waiting period is advisory: the key alone can unlock the vault,
+    /// and a contact who edits the request file can skip the wait. Only
+    /// grant access to someone you would trust with the vault today.
+    Grant {
+        /// Who receives the key (a name or email)
+        contact: String,
+        
+        /// Days between the contact's request and access (advisory, not enforced cryptographically)
+        #[arg(long, default_value_t = 7)]
+        wait_days: u32,
+    },
+    
+    /// Invalidate the emergency key of the current vault
+    Revoke,
+    
+    /// Show who has emergency access to the current vault and any pending request
+    Status,
+    
+    /// Deny the pending emergency access request
+    Deny,
+    
+    /// Request access to a vault with an emergency key
+    Request {
+        /// Vault to request access to
+        vault: String,
+    },
+    
+    /// Unlock a vault with an emergency key after the waiting period, and set a new master password
+    Unlock {
+        /// Vault to unlock
+        vault: String,
+    },
+}
+
+/// Run an emergency access subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: EmergencyCommand) -> Result<()> {
+    match command {
+        EmergencyCommand::Grant { contact, wait_days } => grant(&contact, wait_days),
+        EmergencyCommand::Revoke => revoke(),
+        EmergencyCommand::Status => show_status(),
+        EmergencyCommand::Deny => deny(),
+        EmergencyCommand::Request { vault } => request(&vault),
+        EmergencyCommand::Unlock { vault } => unlock(&vault),
+    }
+}
+
+/// Warn the owner of an unlocked vault about a pending request
+pub fn warn_pending_request(passman: &PassMan) {
+    let Ok(Some(request)) = passman.emergency_request() else {
+        return;
+    };
+    eprintln!(
+        "{} Emergency access to this vault was requested on {}. Run `passman emergency deny` if you didn't expect this.",
+        "Warning:".yellow().bold(),
+        request.requested_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+    );
+}
+
+fn grant(contact: &str, wait_days: u32) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let emergency_key = passman.grant_emergency_access(contact, wait_days)?;
+    
+    status!("{}", format!("✓ Emergency access granted to {}", contact.trim()).green().bold());
+    status!("Give them this key; it is shown only once:");
+    println!("{}", emergency_key);
+    status!(
+        "With it they can unlock '{}' {} day(s) after requesting access, unless you deny the request.",
+        passman.vault_name(),
+        wait_days,
+    );
+    status!(
+        "{}",
+        "The wait is advisory: someone with the key who edits the files beside the vault can skip it.".yellow()
+    );
+    Ok(())
+}
+
+fn revoke() -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    if passman.emergency_access()?.is_none() {
+        status!("{}", "No emergency access to revoke.".yellow());
+        return Ok(());
+    }
+    passman.revoke_emergency_access()?;
+    status!("{}", "✓ Emergency access revoked; the emergency key no longer works".green().bold());
+    Ok(())
+}
+
+fn show_status() -> Result<()> {
+    let passman = PassMan::new(&crate::get_current_vault_name()?)?;
+    let Some(access) = passman.emergency_access()? else {
+        println!("No emergency access");
+        return Ok(());
+    };
+    
+    println!("Contact: {}", access.contact);
+    println!("Waiting period: {} day(s)", access.wait_days);
+    println!("Granted: {}", access.granted_at.with_timezone(&chrono::Local).format("%Y-%m-%d"));
+    match passman.emergency_request()? {
+        Some(request) => {
+            let available_at = request.requested_at + chrono::Duration::days(access.wait_days.into());
+            println!(
+                "Request: {} on {}, access from {}",
+                "pending".red().bold(),
+                request.requested_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                available_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            );
+        }
+        None => println!("Request: none"),
+    }
+    Ok(())
+}
+
+fn deny() -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    if passman.deny_emergency_request()? {
+        status!("{}", "✓ Emergency access request denied".green().bold());
+    } else {
+        status!("{}", "No pending emergency access request.".yellow());
+    }
+    Ok(())
+}
+
+fn request(vault: &str) -> Result<()> {
+    let passman = open_existing(vault)?;
+    let available_at = passman.request_emergency_access(&prompt_emergency_key()?)?;
+    status!(
+        "{}",
+        format!(
+            "✓ Emergency access requested. Unless the owner denies it, run `passman emergency unlock {}` after {}.",
+            vault,
+            available_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        ).green().bold()
+    );
+    Ok(())
+}
+
+fn unlock(vault: &str) -> Result<()> {
+    let mut passman = open_existing(vault)?;
+    passman.open_vault_with_emergency_key(&prompt_emergency_key()?)?;
+    status!("{}", "✓ Emergency key accepted.".green());
+    
+    status!("Choose a new master password for '{}'; the owner's master password will stop working.", vault);
+    let new_password = wizard::prompt_new_master_password()?;
+    passman.change_master_password(&new_password, None)?;
+    status!("{}", format!("✓ Master password of '{}' set. Use `passman use {}` to work with it.", vault, vault).green().bold());
+    Ok(())
+}
+
+fn open_existing(vault: &str) -> Result<PassMan> {
+    if !PassMan::list_vaults()?.iter().any(|name| name == vault) {
+        return Err(PassManError::VaultNotFound(format!("Vault '{}' does not exist", vault)));
+    }
+    PassMan::new(vault)
+}
+
+fn prompt_emergency_key() -> Result<String> {
+    eprint!("Enter emergency key: ");
+    io::stderr().flush()?;
+    Ok(rpassword::read_password()?)
+}
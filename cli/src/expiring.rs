@@ -0,0 +1,96 @@
+//! # Expiring Passwords
+//! 
+//! This module implements `passman expiring`, which lists the accounts
+//! whose password is past its expiry date or rotation interval, or falls
+//! due within the next days. Both are set per account with `passman edit`:
+//! 
+//! ```bash
+//! passman edit Bank --expires 2026-12-31
+//! passman edit GitHub --rotate-days 90
+//! passman expiring --days 60
+//! ```
+
+use crate::{output, status};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use colored::*;
+use passman_backend::Result;
+use serde::Serialize;
+
+/// An expiring account, as printed with `--output json`
+#[derive(Serialize)]
+struct ExpiringAccount<'a> {
+    id: String,
+    name: &'a str,
+    username: Option<&'a str>,
+    expires_at: DateTime<Utc>,
+    expired: bool,
+}
+
+/// List the accounts whose password expires within some days
+/// 
+/// # Arguments
+/// * `days` - How many days ahead to look; expired passwords are always listed
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked
+pub fn run(days: u32) -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let now = Utc::now();
+    let accounts: Vec<_> = passman.get_expiring_accounts(days).into_iter()
+        .filter_map(|account| account.password_expiry().map(|expiry| (account, expiry)))
+        .collect();
+    
+    if output::is_json() {
+        let entries: Vec<ExpiringAccount> = accounts.iter()
+            .map(|(account, expiry)| ExpiringAccount {
+                id: account.id.to_string(),
+                name: &account.name,
+                username: account.username.as_deref(),
+                expires_at: *expiry,
+                expired: *expiry <= now,
+            })
+            .collect();
+        return output::print_json(&entries);
+    }
+    
+    if accounts.is_empty() {
+        status!("{}", format!("No passwords expire within {} days.", days).green());
+        return Ok(());
+    }
+    
+    for (account, expiry) in &accounts {
+        let local: DateTime<Local> = (*expiry).into();
+        let when = describe(*expiry, now);
+        println!(
+            "{}  {}  {}",
+            local.format("%Y-%m-%d"),
+            account.name.white().bold(),
+            if *expiry <= now { when.red() } else { when.yellow() },
+        );
+    }
+    
+    status!();
+    status!("{}", format!("{} password(s) to change; `passman edit <name> --generate` replaces one", accounts.len()).blue());
+    Ok(())
+}
+
+/// Parse an expiry date given as `YYYY-MM-DD` (midnight UTC) or RFC 3339
+pub fn parse_date(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("'{}' is not a date (expected YYYY-MM-DD)", value))
+}
+
+/// How long until, or since, a password expires
+fn describe(expiry: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let days = (expiry - now).num_days();
+    match days {
+        0 if expiry <= now => "expired today".to_string(),
+        0 => "expires today".to_string(),
+        _ if days < 0 => format!("expired {} day(s) ago", -days),
+        _ => format!("expires in {} day(s)", days),
+    }
+}
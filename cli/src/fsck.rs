@@ -0,0 +1,59 @@
+//! # Vault Consistency Check
+//! 
+//! This module implements `passman fsck`, a deep check of the current
+//! vault: its header, every backup, metadata against the accounts, and
+//! files left next to the vault. `--repair` fixes what can be fixed
+//! without losing data; the vault is backed up before repairs are saved.
+//! 
+//! ```bash
+//! passman fsck
+//! passman fsck --repair
+//! ```
+
+use crate::status;
+use colored::*;
+use passman_backend::{PassManError, Result, fsck::Severity};
+
+/// Run the consistency check
+/// 
+/// # Arguments
+/// * `repair` - Fix the issues that can be fixed
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked or checked, or if
+/// issues remain after the check
+pub fn run(repair: bool) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let report = passman.fsck(repair)?;
+    
+    status!("{}", format!(
+        "Checked {} account(s) and {} backup(s) of '{}'",
+        report.accounts_checked,
+        report.backups_checked,
+        passman.vault_name(),
+    ).bold());
+    for issue in &report.issues {
+        let label = match issue.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+        };
+        let note = if issue.repaired {
+            " (repaired)".green().to_string()
+        } else if issue.repairable {
+            " (fixable with --repair)".dimmed().to_string()
+        } else {
+            String::new()
+        };
+        println!("{}: {}{}", label, issue.message, note);
+    }
+    
+    let unresolved = report.unresolved().count();
+    if report.is_clean() {
+        println!("No problems found");
+    } else if unresolved == 0 {
+        status!("{}", format!("✓ Repaired {} issue(s)", report.issues.len()).green().bold());
+    } else {
+        return Err(PassManError::StorageError(format!("{} issue(s) remain", unresolved)));
+    }
+    Ok(())
+}
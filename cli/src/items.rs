@@ -0,0 +1,142 @@
+//! # Cards and Identities
+//! 
+//! Accounts of type `card` and `identity` hold a payment card or a set of
+//! personal details instead of a login. `passman add <name> --account-type card`
+//! asks for the card number (checked with the Luhn algorithm), expiry,
+//! CVV, and cardholder; `--account-type identity` asks for a name, contact
+//! details, an address, and document numbers.
+//! 
+//! `passman show` masks the card number down to its last four digits and
+//! hides the CVV and document numbers unless `--show-password` is given.
+
+use chrono::Utc;
+use colored::*;
+use passman_backend::{PassManError, Result, models::{Identity, PaymentCard}};
+use std::io::{self, Write};
+
+/// Ask for the details of a payment card
+/// 
+/// # Errors
+/// Returns an error if the number fails the Luhn check or the expiry
+/// or CVV is malformed
+pub fn prompt_card() -> Result<PaymentCard> {
+    let number = prompt_secret("Card number")?.unwrap_or_default();
+    let mut card = PaymentCard::new(&number);
+    
+    if let Some(expiry) = prompt_line("Expiry (MM/YY, optional)")? {
+        let (month, year) = PaymentCard::parse_expiry(&expiry)?;
+        card.expiry_month = Some(month);
+        card.expiry_year = Some(year);
+    }
+    card.cvv = prompt_secret("CVV (optional)")?;
+    card.cardholder = prompt_line("Cardholder name (optional)")?;
+    
+    card.validate()?;
+    Ok(card)
+}
+
+/// Ask for the details of an identity; every field is optional
+/// 
+/// # Errors
+/// Returns an error if stdin can't be read or nothing was entered
+pub fn prompt_identity() -> Result<Identity> {
+    let identity = Identity {
+        title: prompt_line("Title (optional)")?,
+        first_name: prompt_line("First name (optional)")?,
+        middle_name: prompt_line("Middle name (optional)")?,
+        last_name: prompt_line("Last name (optional)")?,
+        company: prompt_line("Company (optional)")?,
+        email: prompt_line("Email (optional)")?,
+        phone: prompt_line("Phone (optional)")?,
+        address: prompt_line("Street address (optional)")?,
+        city: prompt_line("City (optional)")?,
+        state: prompt_line("State or region (optional)")?,
+        postal_code: prompt_line("Postal code (optional)")?,
+        country: prompt_line("Country (optional)")?,
+        national_id: prompt_secret("National ID number (optional)")?,
+        passport_number: prompt_secret("Passport number (optional)")?,
+        license_number: prompt_secret("Driver's license number (optional)")?,
+    };
+    
+    if identity.is_empty() {
+        return Err(PassManError::InvalidInput("An identity needs at least one detail".to_string()));
+    }
+    Ok(identity)
+}
+
+/// Print a payment card, masking the number and CVV unless `show_secrets`
+pub fn print_card(card: &PaymentCard, show_secrets: bool) {
+    if let Some(ref cardholder) = card.cardholder {
+        println!("  Cardholder: {}", cardholder);
+    }
+    if let Some(ref brand) = card.brand {
+        println!("  Brand: {}", brand);
+    }
+    if show_secrets {
+        println!("  Number: {}", card.number.red());
+    } else {
+        println!("  Number: {}", card.masked_number().red());
+    }
+    if let Some(expiry) = card.expiry() {
+        if card.is_expired(Utc::now()) {
+            println!("  Expiry: {} {}", expiry, "(expired)".red());
+        } else {
+            println!("  Expiry: {}", expiry);
+        }
+    }
+    if let Some(ref cvv) = card.cvv {
+        println!("  CVV: {}", if show_secrets { cvv.as_str() } else { "•••" }.red());
+    }
+}
+
+/// Print an identity, masking document numbers unless `show_secrets`
+pub fn print_identity(identity: &Identity, show_secrets: bool) {
+    if let Some(name) = identity.full_name() {
+        println!("  Name: {}", name);
+    }
+    for (label, value) in [("Company", &identity.company), ("Email", &identity.email), ("Phone", &identity.phone)] {
+        if let Some(value) = value {
+            println!("  {}: {}", label, value);
+        }
+    }
+    
+    let locality: Vec<&str> = [&identity.postal_code, &identity.city, &identity.state].into_iter()
+        .filter_map(|part| part.as_deref())
+        .collect();
+    let address: Vec<String> = identity.address.iter()
+        .flat_map(|address| address.lines().map(str::to_string))
+        .chain((!locality.is_empty()).then(|| locality.join(" ")))
+        .chain(identity.country.clone())
+        .collect();
+    if let Some((first, rest)) = address.split_first() {
+        println!("  Address: {}", first);
+        for line in rest {
+            println!("           {}", line);
+        }
+    }
+    
+    for (label, value) in identity.documents() {
+        println!("  {}: {}", label, if show_secrets { value } else { "••••••••" }.red());
+    }
+}
+
+/// Read one line from stdin, `None` if it was left empty
+fn prompt_line(label: &str) -> Result<Option<String>> {
+    eprint!("{}: ", label);
+    io::stderr().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let value = input.trim();
+    Ok((!value.is_empty()).then(|| value.to_string()))
+}
+
+/// Read a value without echo, `None` if it was left empty
+fn prompt_secret(label: &str) -> Result<Option<String>> {
+    eprint!("{}: ", label);
+    io::stderr().flush()?;
+    
+    let input = rpassword::read_password().map_err(PassManError::IoError)?;
+    let value = input.trim();
+    Ok((!value.is_empty()).then(|| value.to_string()))
+}
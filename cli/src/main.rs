@@ -3,13 +3,58 @@
 //! Command-line interface for PassMan password manager.
 //! Provides secure password management through the terminal.
 
-use clap::{Parser, Subcommand};
+mod agent;
+mod audit;
+mod batch;
+mod browser;
+mod category;
+mod clip;
+mod config;
+mod credentials;
+mod dedupe;
+mod diff;
+mod editor;
+mod emergency;
+mod expiring;
+mod fsck;
+mod items;
+mod man;
+mod notify;
+mod output;
+mod pair;
+mod pick;
+mod policy;
+mod qr;
+mod serve;
+mod session;
+#[cfg(unix)]
+mod socket;
+mod ssh;
+mod template;
+mod transfer;
+mod trash;
+mod vaults;
+mod wizard;
+
+use clap::{CommandFactory, Parser, Subcommand};
 use passman_backend::{
     PassMan, Result, PassManError,
-    models::{AccountType, PasswordOptions},
+    appearance,
+    generator::PasswordGenerator,
+    plugins,
+    search_cache::SearchCache,
+    models::{Account, AccountPatch, AccountQuery, AccountType, CustomField, DedupePolicy, PassphraseOptions, PassphraseWordList, PasswordOptions, SortOrder, UsernameOptions, VaultSettings},
+    totp::Totp,
 };
 use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
 use colored::*;
+use config::{CliConfig, ColorChoice};
+use credentials::PasswordSource;
+use output::{AccountJson, OutputFormat};
+use template::AccountTemplate;
 
 /// PassMan - A secure local password manager
 #[derive(Parser)]
@@ -17,7 +62,32 @@ use colored::*;
 #[command(about = "A secure, local-first password manager")]
 #[command(version)]
 #[command(long_about = "PassMan is a secure password manager that stores your passwords locally with military-grade encryption. Your data never leaves your device.")]
+#[command(after_help = output::EXIT_CODES_HELP)]
 pub struct Cli {
+    /// Suppress decorative output; only command results are printed
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    
+    /// When to use colors
+    #[arg(long, value_enum, global = true, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+    
+    /// Use this vault instead of the current one (see `passman use`)
+    #[arg(long = "vault", global = true, value_name = "NAME")]
+    pub vault_name: Option<String>,
+    
+    /// Read the master password from the first line of stdin
+    #[arg(long, global = true, conflicts_with = "password_fd")]
+    pub password_stdin: bool,
+    
+    /// Read the master password from the first line of this file descriptor
+    #[arg(long, global = true, value_name = "FD")]
+    pub password_fd: Option<u32>,
+    
+    /// Format of the results of list, show, audit, vaults, and config [default: text]
+    #[arg(long, value_enum, global = true, value_name = "FORMAT")]
+    pub output: Option<OutputFormat>,
+    
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -28,15 +98,29 @@ pub enum Commands {
     Init {
         /// Email address for the vault
         email: String,
+        
+        /// Skip the optional wizard questions and use recommended settings
+        #[arg(long)]
+        defaults: bool,
     },
     
     /// Add a new account
     Add {
         /// Account name
-        name: String,
+        #[arg(required_unless_present_any = ["batch", "from_file"])]
+        name: Option<String>,
+        
+        /// Add many accounts from a CSV file ('-' reads stdin)
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["account_type", "url", "username", "totp", "folder", "ssh_key", "from_file"])]
+        batch: Option<String>,
         
-        /// Account type
-        #[arg(short, long, value_enum)]
+        /// Read the account from a TOML or JSON template (other flags override it)
+        #[arg(long, value_name = "FILE")]
+        from_file: Option<PathBuf>,
+        
+        /// Account type (social, banking, work, personal, email, shopping,
+        /// gaming, card, identity, ssh-key, other) or a custom category
+        #[arg(short, long, value_name = "TYPE")]
         account_type: Option<AccountType>,
         
         /// Website URL
@@ -44,7 +128,7 @@ pub enum Commands {
         url: Option<String>,
         
         /// Username or email
-        #[arg(short, long)]
+        #[arg(short = 'U', long)]
         username: Option<String>,
         
         /// Generate password instead of prompting
@@ -54,18 +138,154 @@ pub enum Commands {
         /// Password length for generation
         #[arg(long, default_value = "16")]
         length: usize,
+        
+        /// TOTP secret (base32 or otpauth:// URI) for two-factor codes
+        #[arg(long)]
+        totp: Option<String>,
+        
+        /// Folder to file the account under
+        #[arg(long)]
+        folder: Option<String>,
+        
+        /// Store this OpenSSH private key with the account (for `passman ssh-agent`)
+        #[arg(long, value_name = "FILE")]
+        ssh_key: Option<PathBuf>,
+    },
+    
+    /// Edit an existing account
+    #[command(group(clap::ArgGroup::new("change").required(true).multiple(true).args(["account_type", "notes", "field", "remove_field", "generate", "expires", "no_expiry", "rotate_days", "no_rotation", "label_color", "remove_label_color", "icon", "icon_from_site", "remove_icon"])))]
+    Edit {
+        /// Account name or ID
+        name: String,
+        
+        /// Change the account type or custom category
+        #[arg(short, long, value_name = "TYPE")]
+        account_type: Option<AccountType>,
+        
+        /// Edit the notes in $EDITOR (multi-line)
+        #[arg(long)]
+        notes: bool,
+        
+        /// Add or replace a custom field; its value is prompted for
+        #[arg(long, value_name = "NAME")]
+        field: Option<String>,
+        
+        /// Mask the value of the `--field` when shown, and don't echo it while typing
+        #[arg(long, requires = "field")]
+        hidden: bool,
+        
+        /// Remove a custom field
+        #[arg(long, value_name = "NAME")]
+        remove_field: Vec<String>,
+        
+        /// Replace the password with a generated one, following the site's
+        /// policy (see `passman policy site`)
+        #[arg(long)]
+        generate: bool,
+        
+        /// Length of the generated password [default: generator.length]
+        #[arg(long, requires = "generate")]
+        length: Option<usize>,
+        
+        /// Date the password expires (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = expiring::parse_date, conflicts_with = "no_expiry")]
+        expires: Option<chrono::DateTime<chrono::Utc>>,
+        
+        /// Remove the expiry date
+        #[arg(long)]
+        no_expiry: bool,
+        
+        /// Rotate the password this many days after each change
+        #[arg(long, value_name = "DAYS", value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "no_rotation")]
+        rotate_days: Option<u32>,
+        
+        /// Remove the rotation interval
+        #[arg(long)]
+        no_rotation: bool,
+        
+        /// Label the account with a color (hex, e.g. #3b82f6)
+        #[arg(long, value_name = "HEX", conflicts_with = "remove_label_color")]
+        label_color: Option<String>,
+        
+        /// Remove the label color
+        #[arg(long)]
+        remove_label_color: bool,
+        
+        /// Set the icon: an emoji, or an image as a data:image/ URL
+        #[arg(long, conflicts_with_all = ["icon_from_site", "remove_icon"])]
+        icon: Option<String>,
+        
+        /// Use the favicon of the account's URL as its icon (needs the favicon feature)
+        #[arg(long, conflicts_with = "remove_icon")]
+        icon_from_site: bool,
+        
+        /// Remove the icon
+        #[arg(long)]
+        remove_icon: bool,
+    },
+    
+    /// Delete an account (it stays in the trash until purged; see `passman trash`)
+    Delete {
+        /// Account name or ID
+        name: String,
+    },
+    
+    /// Mark an account as a favorite, or unmark it (favorites are listed first)
+    Favorite {
+        /// Account name or ID
+        name: String,
+    },
+    
+    /// List, add, rename, or remove custom account categories
+    Category {
+        #[command(subcommand)]
+        command: category::CategoryCommand,
+    },
+    
+    /// List, restore, or purge deleted accounts
+    Trash {
+        #[command(subcommand)]
+        command: trash::TrashCommand,
     },
     
     /// List all accounts
     List {
-        /// Filter by account type
-        #[arg(short, long, value_enum)]
+        /// Filter by account type or custom category
+        #[arg(short, long, value_name = "TYPE")]
         account_type: Option<AccountType>,
         
         /// Search query
         #[arg(short, long)]
         search: Option<String>,
         
+        /// Filter by tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        
+        /// Filter by folder
+        #[arg(long)]
+        folder: Option<String>,
+        
+        /// Only show favorites
+        #[arg(long, alias = "favorite")]
+        favorites: bool,
+        
+        /// Show archived accounts instead of active ones
+        #[arg(long)]
+        archived: bool,
+        
+        /// Sort order
+        #[arg(long, value_enum, default_value = "name")]
+        sort: SortOrder,
+        
+        /// Sort strictly by --sort instead of listing favorites first
+        #[arg(long)]
+        no_favorites_first: bool,
+        
+        /// Maximum number of accounts to show
+        #[arg(short, long)]
+        limit: Option<usize>,
+        
         /// Show passwords (use with caution)
         #[arg(long)]
         show_passwords: bool,
@@ -76,16 +296,70 @@ pub enum Commands {
         /// Account name or ID
         name: String,
         
-        /// Show password
+        /// Show password (asks for the master password again if the vault requires confirmation)
         #[arg(long)]
         show_password: bool,
+        
+        /// Copy the password to the clipboard instead of printing it
+        #[arg(long, conflicts_with = "show_password")]
+        clip: bool,
+    },
+    
+    /// Copy an account field to the clipboard
+    Copy {
+        /// Account name or ID
+        name: String,
+        
+        /// Field to copy: password, username, url, notes, otp, or custom:<label>
+        #[arg(short, long, default_value = "password")]
+        field: CopyField,
+    },
+    
+    /// Copy credentials and open the account's URL in the browser
+    Open {
+        /// Account name or ID
+        name: String,
+        
+        /// Copy only the username
+        #[arg(long, conflicts_with = "password")]
+        username: bool,
+        
+        /// Copy only the password
+        #[arg(long)]
+        password: bool,
+    },
+    
+    /// Print accounts for an external picker (rofi, dmenu, fzf) and resolve the choice
+    Pick {
+        /// Print this field of the selected account: password, username, url, notes, otp, or custom:<label>
+        #[arg(long, value_name = "FIELD")]
+        print_field: Option<CopyField>,
+        
+        /// Picker command that reads candidates on stdin and prints the choice
+        #[arg(long, value_name = "COMMAND", requires = "print_field")]
+        picker: Option<String>,
+    },
+    
+    /// Show a credential as a QR code for scanning with a phone
+    Qr {
+        /// Account name or ID
+        name: String,
+        
+        /// Encode the account's otpauth URI instead of the password
+        #[arg(long)]
+        otp: bool,
     },
     
     /// Generate a password
+    /// 
+    /// Defaults (length, character classes, passphrase options) come from
+    /// the `generator` section of config.toml. Giving any character class
+    /// flag uses only the classes given.
+    #[command(group(clap::ArgGroup::new("words_mode").args(["passphrase", "username"])))]
     Generate {
-        /// Password length
-        #[arg(short, long, default_value = "16")]
-        length: usize,
+        /// Password length [default: 16, or 4 with --pin]
+        #[arg(short, long)]
+        length: Option<usize>,
         
         /// Include special characters
         #[arg(long)]
@@ -103,230 +377,1396 @@ pub enum Commands {
         #[arg(long)]
         lowercase: bool,
         
+        /// Characters never to use
+        #[arg(long, value_name = "CHARS", conflicts_with = "passphrase")]
+        exclude: Option<String>,
+        
+        /// Fewest uppercase letters (includes them)
+        #[arg(long, value_name = "N", conflicts_with = "passphrase")]
+        min_uppercase: Option<usize>,
+        
+        /// Fewest numbers (includes them)
+        #[arg(long, value_name = "N", conflicts_with = "passphrase")]
+        min_numbers: Option<usize>,
+        
+        /// Fewest special characters (includes them)
+        #[arg(long, value_name = "N", conflicts_with = "passphrase")]
+        min_special: Option<usize>,
+        
         /// Copy to clipboard
         #[arg(short, long)]
         copy: bool,
+        
+        /// Generate this many passwords to choose from, with their strength
+        #[arg(long, value_name = "N", conflicts_with_all = ["copy", "passphrase", "pin", "username"])]
+        count: Option<usize>,
+        
+        /// Generate a passphrase of random words instead of a password
+        #[arg(long)]
+        passphrase: bool,
+        
+        /// Generate a numeric PIN instead of a password (no repeats, runs, or common PINs)
+        #[arg(long, conflicts_with_all = ["passphrase", "exclude", "min_uppercase", "min_numbers", "min_special"])]
+        pin: bool,
+        
+        /// Generate a username like quiet_otter_42 instead of a password
+        #[arg(long, conflicts_with_all = ["passphrase", "pin", "exclude", "min_uppercase", "min_numbers", "min_special"])]
+        username: bool,
+        
+        /// Number of words in the passphrase [default: 6]
+        #[arg(long, requires = "passphrase")]
+        words: Option<usize>,
+        
+        /// Text placed between passphrase or username words [default: - for passphrases, _ for usernames]
+        #[arg(long, requires = "words_mode")]
+        separator: Option<String>,
+        
+        /// Word list file (one word per line; diceware lists are accepted)
+        #[arg(long, value_name = "PATH", requires = "passphrase")]
+        wordlist: Option<PathBuf>,
+        
+        /// Pick passphrase words from the EFF long word list (7,776 words)
+        #[arg(long, requires = "passphrase", conflicts_with = "wordlist")]
+        eff: bool,
+        
+        /// Capitalize each passphrase or username word
+        #[arg(long, requires = "words_mode")]
+        capitalize: bool,
+        
+        /// Append a random digit to one passphrase word
+        #[arg(long, requires = "passphrase")]
+        digits: bool,
+        
+        /// Append a random symbol to one passphrase word
+        #[arg(long, requires = "passphrase")]
+        symbols: bool,
+    },
+    
+    /// Manage vaults (create, list, rename, delete, info, switch)
+    Vault {
+        #[command(subcommand)]
+        command: vaults::VaultCommand,
     },
     
-    /// List all vaults
+    /// List all vaults (same as `vault list`)
+    #[command(hide = true)]
     Vaults,
+    
+    /// Hold text read from stdin on the clipboard and clear it later (used by `copy`)
+    #[command(name = clip::HELPER_COMMAND, hide = true)]
+    ClipboardHelper {
+        /// Seconds before the clipboard is cleared
+        #[arg(long)]
+        timeout: u32,
+    },
+    
+    /// Set a new master password using the vault's recovery key
+    Recover {
+        /// Keyfile to require from now on (default: no keyfile)
+        #[arg(long, value_name = "FILE")]
+        keyfile: Option<PathBuf>,
+    },
+    
+    /// Let a trusted contact unlock a vault after a waiting period
+    Emergency {
+        #[command(subcommand)]
+        command: emergency::EmergencyCommand,
+    },
+    
+    /// Lock all vaults by dropping cached session keys
+    Lock,
+    
+    /// Show the current vault and which vaults have an active session
+    Status,
+    
+    /// Make a vault the default for all other commands
+    Use {
+        /// Vault to use
+        name: String,
+    },
+    
+    /// Hold vault keys in a background agent so commands don't ask for the master password
+    /// 
+    /// Linux and macOS only: the agent listens on a Unix socket, and there is
+    /// no Windows named-pipe transport yet.
+    Agent {
+        #[command(subcommand)]
+        command: agent::AgentCommand,
+    },
+    
+    /// Read and change settings in config.toml
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommand,
+    },
+    
+    /// Serve the SSH keys stored in accounts to ssh over an agent socket
+    SshAgent {
+        /// Socket path (default: passman/ssh-agent.sock in the runtime directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+    
+    /// Load stored SSH keys into a running ssh-agent
+    Ssh {
+        #[command(subcommand)]
+        command: ssh::SshCommand,
+    },
+    
+    /// Serve an authenticated local JSON API for editor plugins and the browser extension
+    Serve {
+        /// Loopback address to listen on
+        #[arg(long, value_name = "ADDR", default_value = serve::DEFAULT_LISTEN)]
+        listen: SocketAddr,
+        
+        /// Also write the access token to this file (mode 0600)
+        #[arg(long, value_name = "FILE")]
+        token_file: Option<PathBuf>,
+        
+        /// Start with the vault locked and keep serving while it is locked (unlock with POST /v1/unlock)
+        #[arg(long)]
+        locked: bool,
+        
+        /// Serve HTTPS with this PEM certificate chain (needs --tls-key and the `tls` feature)
+        #[arg(long, value_name = "FILE", requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        
+        /// PEM private key of the --tls-cert certificate
+        #[arg(long, value_name = "FILE", requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+    },
+    
+    /// Import accounts from a file (see --list-formats)
+    Import {
+        /// File to import
+        #[arg(required_unless_present = "list_formats")]
+        file: Option<PathBuf>,
+        
+        /// Format of the file: json, passman, csv, kdbx (KeePass), bitwarden, lastpass, 1pux (1Password), or a plugin's name
+        #[arg(long, default_value = plugins::JSON_FORMAT)]
+        format: String,
+        
+        /// Read an account field from a CSV column, e.g. --map name=Site (repeatable;
+        /// fields: name, username, password, url, notes, tags, type, folder, totp)
+        #[arg(long, value_name = "FIELD=COLUMN")]
+        map: Vec<String>,
+        
+        /// How accounts with the same name and username as an existing one are handled
+        #[arg(long, value_enum, default_value_t = DedupePolicy::Skip)]
+        on_duplicate: DedupePolicy,
+        
+        /// Show what would be imported without changing the vault
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// List the available import and export formats
+        #[arg(long)]
+        list_formats: bool,
+    },
+    
+    /// Export active accounts to an unencrypted file, or the whole vault
+    /// encrypted with --format passman (see --list-formats)
+    Export {
+        /// File to write
+        #[arg(required_unless_present = "list_formats")]
+        file: Option<PathBuf>,
+        
+        /// Format to write: json, csv (browser-compatible; asks for
+        /// confirmation), passman (the whole vault, encrypted with an export
+        /// password), or a plugin's name
+        #[arg(long, default_value = plugins::JSON_FORMAT)]
+        format: String,
+        
+        /// Only export accounts of this type or custom category
+        #[arg(short, long, value_name = "TYPE")]
+        account_type: Option<AccountType>,
+        
+        /// Only export accounts with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        
+        /// Leave out canary accounts
+        #[arg(long)]
+        exclude_canaries: bool,
+        
+        /// List the available import and export formats
+        #[arg(long)]
+        list_formats: bool,
+    },
+    
+    /// Report reused, weak, stale, and breached passwords
+    Audit {
+        /// Days after which an unchanged password is stale (0 = never)
+        #[arg(long, default_value_t = 365)]
+        stale_days: u32,
+        
+        /// Check passwords against Have I Been Pwned online (sends only the
+        /// first 5 hex digits of each SHA-1 hash; needs the breach-online feature)
+        #[arg(long)]
+        breach: bool,
+    },
+    
+    /// List passwords past or near their expiry date or rotation interval
+    Expiring {
+        /// How many days ahead to look
+        #[arg(short, long, default_value_t = 30)]
+        days: u32,
+    },
+    
+    /// Notify about passwords due for a change (for cron; never prompts)
+    Notify {
+        /// Check even if the last check was recent
+        #[arg(long)]
+        force: bool,
+        
+        /// Print the reminders instead of showing a desktop notification
+        #[arg(long)]
+        print: bool,
+    },
+    
+    /// Check the vault, its backups, and the files next to it for damage
+    Fsck {
+        /// Fix the issues that can be fixed without losing data
+        #[arg(long)]
+        repair: bool,
+    },
+    
+    /// Show or change the password policy of the current vault
+    Policy {
+        #[command(subcommand)]
+        command: policy::PolicyCommand,
+    },
+    
+    /// Manage the offline breach data used by `audit`
+    Breaches {
+        #[command(subcommand)]
+        command: audit::BreachCommand,
+    },
+    
+    /// Manage canary accounts watched by external monitoring
+    Canary {
+        #[command(subcommand)]
+        command: audit::CanaryCommand,
+    },
+    
+    /// Find accounts with the same site and username and merge them
+    Dedupe {
+        /// Only list the groups of duplicates
+        #[arg(long)]
+        list: bool,
+    },
+    
+    /// Show what changed in the vault since a backup
+    Diff {
+        /// Timestamp of the backup, as shown by --list (default: the newest backup)
+        #[arg(value_name = "BACKUP")]
+        backup: Option<String>,
+        
+        /// List the vault's backups
+        #[arg(long, conflicts_with = "backup")]
+        list: bool,
+    },
+    
+    /// Copy a vault to or from another device on the local network
+    Pair {
+        #[command(subcommand)]
+        command: pair::PairCommand,
+    },
+    
+    /// Print the man page, or write pages for every subcommand
+    Man {
+        /// Write passman.1 and one page per subcommand into this directory
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
 }
 
+/// Master password prompts offered before a command gives up
+const MAX_PASSWORD_ATTEMPTS: u32 = 3;
+/// Failed attempts after which the vault's password hint is shown
+const HINT_AFTER_FAILURES: u32 = 2;
+/// Digits in generated PINs unless `--length` is given
+const DEFAULT_PIN_LENGTH: usize = 4;
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            // --help and --version are "errors" that print to stdout
+            let code = if e.use_stderr() { output::EXIT_USAGE } else { output::EXIT_SUCCESS };
+            let _ = e.print();
+            std::process::exit(code);
+        }
+    };
+    output::set_quiet(cli.quiet);
+    credentials::set_source(match (cli.password_stdin, cli.password_fd) {
+        (true, _) => PasswordSource::Stdin,
+        (false, Some(fd)) => PasswordSource::Fd(fd),
+        (false, None) => PasswordSource::Terminal,
+    });
+    if let Some(name) = cli.vault_name.clone() {
+        credentials::set_vault(name);
+    }
+    
+    let config = match CliConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            config::apply_color_choice(cli.color, ColorChoice::Auto);
+            output::set_format(cli.output.unwrap_or_default());
+            print_error(&e);
+            std::process::exit(output::exit_code(&e));
+        }
+    };
+    config::apply_color_choice(cli.color, config.color);
+    output::set_format(cli.output.unwrap_or(config.output));
+    
+    if let Err(e) = run_command(cli) {
+        print_error(&e);
+        std::process::exit(output::exit_code(&e));
+    }
+}
+
+/// Print the error that ended the command
+/// 
+/// Policy violations get one line per broken rule instead of a single
+/// run-on sentence.
+fn print_error(error: &PassManError) {
+    match error {
+        PassManError::PolicyViolation(violations) => {
+            eprintln!("{} The password doesn't meet the vault's password policy:", "Error:".red().bold());
+            for violation in violations {
+                eprintln!("  {} {}", "✗".red(), violation);
+            }
+        }
+        _ => eprintln!("{} {}", "Error:".red().bold(), error),
+    }
+}
+
+fn run_command(cli: Cli) -> Result<()> {
+    match cli.command {
+        Commands::Init { email, defaults } => {
+            wizard::run(None, &email, defaults)?;
+        }
+        
+        Commands::Add { name, batch, from_file, account_type, url, username, generate, length, totp, folder, ssh_key } => {
+            match (batch, from_file, name) {
+                (Some(path), _, _) => batch_add(&path, generate, length)?,
+                (None, Some(path), name) => {
+                    let mut template = AccountTemplate::load(&path)?;
+                    template.name = name.or(template.name);
+                    template.account_type = account_type.map(|t| t.display_name().to_string()).or(template.account_type);
+                    template.url = url.or(template.url);
+                    template.username = username.or(template.username);
+                    template.totp = totp.or(template.totp);
+                    template.folder = folder.or(template.folder);
+                    add_from_template(template, generate, length, ssh_key)?;
+                }
+                (None, None, Some(name)) => add_account(&name, account_type, url, username, generate, length, totp, folder, ssh_key)?,
+                (None, None, None) => unreachable!("clap requires a name unless --batch or --from-file is given"),
+            }
+        }
+        
+        Commands::Edit { name, account_type, notes, field, hidden, remove_field, generate, length, expires, no_expiry, rotate_days, no_rotation, label_color, remove_label_color, icon, icon_from_site, remove_icon } => {
+            if let Some(account_type) = account_type {
+                edit_account_type(&name, account_type)?;
+            }
+            if generate {
+                regenerate_password(&name, length)?;
+            }
+            if notes {
+                edit_notes(&name)?;
+            }
+            if field.is_some() || !remove_field.is_empty() {
+                edit_custom_fields(&name, field, hidden, &remove_field)?;
+            }
+            let expires_at = expires.map(Some).or(no_expiry.then_some(None));
+            let rotation_interval_days = rotate_days.map(Some).or(no_rotation.then_some(None));
+            if expires_at.is_some() || rotation_interval_days.is_some() {
+                edit_expiry(&name, expires_at, rotation_interval_days)?;
+            }
+            let color = label_color.map(Some).or(remove_label_color.then_some(None));
+            let icon = icon.map(Some).or(remove_icon.then_some(None));
+            if color.is_some() || icon.is_some() || icon_from_site {
+                edit_appearance(&name, color, icon, icon_from_site)?;
+            }
+        }
+        
+        Commands::Delete { name } => {
+            delete_account(&name)?;
+        }
+        
+        Commands::Favorite { name } => {
+            toggle_favorite(&name)?;
+        }
+        
+        Commands::Category { command } => {
+            category::run(command)?;
+        }
+        
+        Commands::Trash { command } => {
+            trash::run(command)?;
+        }
+        
+        Commands::List { account_type, search, tag, folder, favorites, archived, sort, no_favorites_first, limit, show_passwords } => {
+            let query = AccountQuery {
+                search,
+                account_type,
+                tag,
+                folder,
+                favorites_only: favorites,
+                favorites_first: !no_favorites_first,
+                archived,
+                exclude_canaries: false,
+                sort,
+                offset: 0,
+                limit,
+            };
+            list_accounts(&query, show_passwords)?;
+        }
+        
+        Commands::Show { name, show_password, clip } => {
+            show_account(&name, show_password, clip)?;
+        }
+        
+        Commands::Copy { name, field } => {
+            copy_field(&name, &field)?;
+        }
+        
+        Commands::Open { name, username, password } => {
+            // With neither flag, copy the username first and then the password
+            let copy_username = username || !password;
+            let copy_password = password || !username;
+            open_account(&name, copy_username, copy_password)?;
+        }
+        
+        Commands::Pick { print_field, picker } => {
+            pick::run(print_field, picker)?;
+        }
+        
+        Commands::Qr { name, otp } => {
+            show_qr(&name, otp)?;
+        }
+        
+        Commands::Generate { passphrase: true, words, separator, wordlist, eff, capitalize, digits, symbols, copy, .. } => {
+            let defaults = CliConfig::load()?.generator;
+            let options = PassphraseOptions {
+                word_count: words.unwrap_or(defaults.words),
+                separator: separator.unwrap_or(defaults.separator),
+                capitalize: capitalize || defaults.capitalize,
+                include_number: digits || defaults.digits,
+                include_symbol: symbols || defaults.symbols,
+                word_list: if eff { PassphraseWordList::EffLong } else { PassphraseWordList::Default },
+            };
+            generate_passphrase(&options, wordlist, copy)?;
+        }
+        
+        Commands::Generate { username: true, separator, capitalize, copy, .. } => {
+            let defaults = UsernameOptions::default();
+            let options = UsernameOptions {
+                separator: separator.unwrap_or(defaults.separator),
+                capitalize,
+                ..defaults
+            };
+            generate_username(&options, copy)?;
+        }
+        
+        Commands::Generate { pin: true, length, copy, .. } => {
+            generate_pin(length.unwrap_or(DEFAULT_PIN_LENGTH), copy)?;
+        }
+        
+        Commands::Generate { length, special, numbers, uppercase, lowercase, exclude, min_uppercase, min_numbers, min_special, copy, count, .. } => {
+            let defaults = CliConfig::load()?.generator;
+            let (min_uppercase, min_numbers, min_special) = (min_uppercase.unwrap_or(0), min_numbers.unwrap_or(0), min_special.unwrap_or(0));
+            
+            // Classes that aren't given come from the config only if none are given
+            let given = special || numbers || uppercase || lowercase;
+            let class = |flag: bool, default: bool| if given { flag } else { default };
+            let options = PasswordOptions {
+                length: length.unwrap_or(defaults.length),
+                include_uppercase: class(uppercase, defaults.uppercase) || min_uppercase > 0,
+                include_lowercase: class(lowercase, defaults.lowercase),
+                include_numbers: class(numbers, defaults.numbers) || min_numbers > 0,
+                include_special: class(special, defaults.special) || min_special > 0,
+                exclude_similar: true,
+                exclude_ambiguous: false,
+                exclude_chars: exclude.unwrap_or_default(),
+                min_uppercase,
+                min_numbers,
+                min_special,
+            };
+            match count {
+                Some(count) => generate_passwords(&options, count)?,
+                None => generate_password(&options, copy)?,
+            }
+        }
+        
+        Commands::Vault { command } => {
+            vaults::run(command)?;
+        }
+        
+        Commands::Vaults => {
+            vaults::list_vaults()?;
+        }
+        
+        Commands::ClipboardHelper { timeout } => {
+            clip::run_helper(timeout)?;
+        }
+        
+        Commands::Recover { keyfile } => {
+            recover_vault(keyfile)?;
+        }
+        
+        Commands::Emergency { command } => {
+            emergency::run(command)?;
+        }
+        
+        Commands::Lock => {
+            lock_vaults()?;
+        }
+        
+        Commands::Status => {
+            show_status()?;
+        }
+        
+        Commands::Use { name } => {
+            vaults::switch_vault(&name)?;
+        }
+        
+        Commands::Agent { command } => {
+            agent::run(command)?;
+        }
+        
+        Commands::Config { command } => {
+            config::run(command)?;
+        }
+        
+        Commands::SshAgent { socket } => {
+            ssh::run_agent(socket)?;
+        }
+        
+        Commands::Ssh { command } => {
+            ssh::run(command)?;
+        }
+        
+        Commands::Serve { listen, token_file, locked, tls_cert, tls_key } => {
+            let tls = tls_cert.zip(tls_key).map(|(cert, key)| serve::TlsFiles { cert, key });
+            serve::run(listen, token_file, locked, tls)?;
+        }
+        
+        Commands::Import { list_formats: true, .. } | Commands::Export { list_formats: true, .. } => {
+            transfer::list_formats();
+        }
+        
+        Commands::Import { file, format, map, on_duplicate, dry_run, .. } => {
+            let file = file.expect("clap requires a file unless --list-formats is given");
+            transfer::import(&file, &format, &map, on_duplicate, dry_run)?;
+        }
+        
+        Commands::Export { file, format, account_type, tag, exclude_canaries, .. } => {
+            let file = file.expect("clap requires a file unless --list-formats is given");
+            let query = AccountQuery { account_type, tag, exclude_canaries, ..AccountQuery::default() };
+            transfer::export(&file, &format, &query)?;
+        }
+        
+        Commands::Audit { stale_days, breach } => {
+            audit::run(stale_days, breach)?;
+        }
+        
+        Commands::Expiring { days } => {
+            expiring::run(days)?;
+        }
+        
+        Commands::Notify { force, print } => {
+            notify::run(force, print)?;
+        }
+        
+        Commands::Fsck { repair } => {
+            fsck::run(repair)?;
+        }
+        
+        Commands::Policy { command } => {
+            policy::run(command)?;
+        }
+        
+        Commands::Breaches { command } => {
+            audit::run_breaches(command)?;
+        }
+        
+        Commands::Canary { command } => {
+            audit::run_canaries(command)?;
+        }
+        
+        Commands::Dedupe { list } => {
+            dedupe::run(list)?;
+        }
+        
+        Commands::Diff { backup, list } => {
+            diff::run(backup, list)?;
+        }
+        
+        Commands::Pair { command } => {
+            pair::run(command)?;
+        }
+        
+        Commands::Man { dir } => {
+            write_man_pages(dir)?;
+        }
+    }
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_account(name: &str, account_type: Option<AccountType>, url: Option<String>, username: Option<String>, generate: bool, length: usize, totp: Option<String>, folder: Option<String>, ssh_key: Option<PathBuf>) -> Result<()> {
+    // Reject a bad TOTP secret or SSH key before asking for anything else
+    if let Some(ref secret) = totp {
+        Totp::parse(secret)?;
+    }
+    let ssh_key = ssh_key.as_deref().map(ssh::read_key_file).transpose()?;
+    
+    let mut passman = unlock_vault()?;
+    
+    let account_type = match account_type {
+        Some(account_type) => account_type,
+        None if ssh_key.is_some() => AccountType::SshKey,
+        None => prompt_account_type(passman.get_categories()),
+    };
+    let ssh_key = match ssh_key {
+        None if account_type == AccountType::SshKey => Some(ssh::prompt_key_file()?),
+        ssh_key => ssh_key,
+    };
+    let (card, identity) = match account_type {
+        AccountType::Card => (Some(items::prompt_card()?), None),
+        AccountType::Identity => (None, Some(items::prompt_identity()?)),
+        _ => (None, None),
+    };
+    let (url, username) = if card.is_some() || identity.is_some() {
+        // Cards and identities aren't logins; only ask for their details
+        (url, username)
+    } else {
+        (url.or_else(prompt_url), username.or_else(prompt_username))
+    };
+    
+    let password = if generate {
+        let options = PasswordOptions::strong(length);
+        passman.generate_password(&options)?
+    } else if ssh_key.is_some() || card.is_some() || identity.is_some() {
+        // The key, card, or identity is the credential; a password is optional
+        String::new()
+    } else {
+        prompt_password()?
+    };
+    
+    let notes = prompt_notes();
+    let tags = prompt_tags();
+    let custom_fields = prompt_custom_fields()?;
+    
+    let mut account = Account::new(name.to_string(), account_type, password);
+    account.url = url;
+    account.username = username;
+    account.notes = notes;
+    account.tags = tags;
+    account.totp = totp;
+    account.folder = folder;
+    account.ssh_key = ssh_key;
+    account.card = card;
+    account.identity = identity;
+    account.custom_fields = custom_fields;
+    let new_category = is_new_category(&passman, &account.account_type).then(|| account.account_type.to_string());
+    passman.add_account_entry(account)?;
+    
+    if let Some(category) = new_category {
+        status!("{}", format!("Created category '{}'", category).blue());
+    }
+    status!("{}", "✓ Account added successfully!".green().bold());
+    
+    Ok(())
+}
+
+fn add_from_template(template: AccountTemplate, generate: bool, length: usize, ssh_key: Option<PathBuf>) -> Result<()> {
+    if let Some(ref secret) = template.totp {
+        Totp::parse(secret)?;
+    }
+    let ssh_key = ssh_key.as_deref().map(ssh::read_key_file).transpose()?;
+    let name = template.name.clone()
+        .ok_or_else(|| PassManError::InvalidInput("Account template has no name; pass one on the command line".to_string()))?;
+    let account_type = template.parsed_account_type()?;
+    
+    let mut passman = unlock_vault()?;
+    
+    let account_type = account_type.unwrap_or_else(|| prompt_account_type(passman.get_categories()));
+    let password = match template.password.clone() {
+        Some(password) if !generate => password,
+        _ if generate => {
+            let options = PasswordOptions::strong(length);
+            passman.generate_password(&options)?
+        }
+        _ if ssh_key.is_some() => String::new(),
+        _ => prompt_password()?,
+    };
+    
+    let mut account = template.into_account(name, account_type, password);
+    account.ssh_key = ssh_key;
+    let fields = account.custom_fields.len();
+    let new_category = is_new_category(&passman, &account.account_type).then(|| account.account_type.to_string());
+    passman.add_account_entry(account)?;
+    
+    if let Some(category) = new_category {
+        status!("{}", format!("Created category '{}'", category).blue());
+    }
+    status!("{}", "✓ Account added successfully!".green().bold());
+    if fields > 0 {
+        status!("  with {} custom field(s)", fields);
+    }
+    
+    Ok(())
+}
+
+fn batch_add(path: &str, generate: bool, length: usize) -> Result<()> {
+    // Read the whole input before prompting, so stdin can carry the CSV
+    let input = if path == "-" {
+        let mut input = Vec::new();
+        io::Read::read_to_end(&mut io::stdin(), &mut input)?;
+        input
+    } else {
+        std::fs::read(path)?
+    };
+    
+    let mut passman = unlock_vault()?;
+    
+    let options = PasswordOptions::strong(length);
+    let mut generator = PassMan::new("temp")?;
+    let mut generate_password = || generator.generate_password(&options);
+    let rows = batch::parse_rows(
+        input.as_slice(),
+        if generate { Some(&mut generate_password) } else { None },
+    )?;
+    
+    let mut accounts = Vec::new();
+    let mut failures = 0;
+    for row in rows {
+        match row.result {
+            Ok(account) => {
+                status!("  {} line {}: {}", "✓".green(), row.line, account.name);
+                accounts.push(account);
+            }
+            Err(reason) => {
+                failures += 1;
+                // Failures are reported even in quiet mode
+                eprintln!("  {} line {}: {}", "✗".red(), row.line, reason);
+            }
+        }
+    }
+    
+    let added = accounts.len();
+    if added > 0 {
+        passman.add_accounts(accounts)?;
+    }
+    
+    status!();
+    let summary = format!("Added {} account(s), {} failed", added, failures);
+    if failures == 0 {
+        status!("{}", summary.green().bold());
+    } else {
+        status!("{}", summary.yellow().bold());
+    }
+    
+    Ok(())
+}
+
+fn edit_account_type(name: &str, account_type: AccountType) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    let is_new = is_new_category(&passman, &account_type);
+    passman.patch_account(id, AccountPatch { account_type: Some(account_type), ..AccountPatch::default() })?;
+    
+    let account_type = passman.get_account(id).map(|account| account.account_type.to_string()).unwrap_or_default();
+    if is_new {
+        status!("{}", format!("Created category '{}'", account_type).blue());
+    }
+    status!("{}", format!("✓ '{}' is now of type {}", account_name, account_type).green().bold());
+    Ok(())
+}
+
+/// Whether an account type is a custom category the vault doesn't know yet
+fn is_new_category(passman: &PassMan, account_type: &AccountType) -> bool {
+    account_type.is_custom() && !passman.get_categories().iter().any(|category| AccountType::Custom(category.clone()) == *account_type)
+}
+
+fn edit_notes(name: &str) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?.clone();
+    
+    let current = account.notes.clone().unwrap_or_default();
+    let edited = editor::edit_text(&current)?;
+    let edited = edited.trim_end();
+    
+    if edited == current.trim_end() {
+        status!("{}", "Notes unchanged.".yellow());
+        return Ok(());
+    }
+    
+    let notes = if edited.is_empty() { None } else { Some(edited.to_string()) };
+    let (id, account_name) = (account.id, account.name.clone());
+    passman.patch_account(id, AccountPatch { notes: Some(notes), ..AccountPatch::default() })?;
+    
+    status!("{}", format!("✓ Notes updated for '{}'", account_name).green().bold());
+    
+    Ok(())
+}
+
+fn edit_expiry(name: &str, expires_at: Option<Option<chrono::DateTime<chrono::Utc>>>, rotation_interval_days: Option<Option<u32>>) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    passman.patch_account(id, AccountPatch { expires_at, rotation_interval_days, ..AccountPatch::default() })?;
+    
+    match passman.get_account(id).and_then(|account| account.password_expiry()) {
+        Some(expiry) => {
+            let expiry: chrono::DateTime<chrono::Local> = expiry.into();
+            status!("{}", format!("✓ The password of '{}' is due for a change on {}", account_name, expiry.format("%Y-%m-%d")).green().bold());
+        }
+        None => status!("{}", format!("✓ The password of '{}' no longer expires", account_name).green().bold()),
+    }
+    
+    Ok(())
+}
+
+fn edit_appearance(name: &str, color: Option<Option<String>>, icon: Option<Option<String>>, icon_from_site: bool) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    let icon = if icon_from_site {
+        Some(Some(site_icon(account)?))
+    } else {
+        icon
+    };
+    passman.patch_account(id, AccountPatch { color, icon, ..AccountPatch::default() })?;
+    
+    status!("{}", format!("✓ Updated the color and icon of '{}'", account_name).green().bold());
+    Ok(())
+}
+
+/// Download the favicon of an account's URL, as an icon
+#[cfg(feature = "favicon")]
+fn site_icon(account: &Account) -> Result<String> {
+    let url = account.url.as_deref()
+        .ok_or_else(|| PassManError::InvalidInput(format!("Account '{}' has no URL", account.name)))?;
+    status!("Downloading the favicon of {}...", url);
+    let favicon = passman_backend::appearance::favicon::fetch_favicon(url)?;
+    Ok(favicon.data_url())
+}
+
+#[cfg(not(feature = "favicon"))]
+fn site_icon(_account: &Account) -> Result<String> {
+    Err(PassManError::InvalidInput(
+        "This passman was built without favicon downloads (build it with --features favicon)".to_string(),
+    ))
+}
+
+fn edit_custom_fields(name: &str, field: Option<String>, hidden: bool, remove: &[String]) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    let mut fields = account.custom_fields.clone();
+    
+    for removed in remove {
+        let before = fields.len();
+        fields.retain(|field| !field.name.eq_ignore_ascii_case(removed));
+        if fields.len() == before {
+            return Err(PassManError::InvalidInput(format!("'{}' has no custom field '{}'", account_name, removed)));
+        }
+    }
+    if let Some(field_name) = field {
+        let value = prompt_field_value(&field_name, hidden)?;
+        match fields.iter_mut().find(|field| field.name.eq_ignore_ascii_case(&field_name)) {
+            Some(existing) => {
+                existing.value = value;
+                existing.hidden = hidden;
+            }
+            None => fields.push(CustomField::new(field_name, value, hidden)),
+        }
+    }
+    passman.set_custom_fields(id, fields)?;
+    
+    status!("{}", format!("✓ Custom fields updated for '{}'", account_name).green().bold());
+    
+    Ok(())
+}
+
+fn regenerate_password(name: &str, length: Option<usize>) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    
+    let mut options = CliConfig::load()?.generator.password_options();
+    if let Some(length) = length {
+        options.length = length;
+    }
+    let policy = passman.site_policy(id)?.map(policy::describe_site);
+    let password = passman.generate_account_password(id, &options)?;
+    
+    passman.patch_account(id, AccountPatch { password: Some(password), ..AccountPatch::default() })?;
+    
+    status!("{}", format!("✓ New password set for '{}'", account_name).green().bold());
+    if let Some(policy) = policy {
+        status!("Site policy: {}", policy);
+    }
+    status!("Copy it with: passman copy \"{}\"", account_name);
+    
+    Ok(())
+}
+
+fn delete_account(name: &str) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    passman.delete_account(id)?;
+    
+    status!("{}", format!("✓ Moved '{}' to the trash", account_name).green().bold());
+    status!("Restore it with: passman trash restore \"{}\"", account_name);
+    
+    Ok(())
+}
+
+fn toggle_favorite(name: &str) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
+    let (id, account_name) = (account.id, account.name.clone());
+    
+    if passman.toggle_favorite(id)? {
+        status!("{}", format!("✓ Added '{}' to favorites", account_name).green().bold());
+    } else {
+        status!("{}", format!("✓ Removed '{}' from favorites", account_name).green().bold());
+    }
+    
+    Ok(())
+}
+
+fn list_accounts(query: &AccountQuery, show_passwords: bool) -> Result<()> {
+    // Without passwords, a current search cache saves decrypting the vault
+    if !show_passwords {
+        if let Some(cache) = cached_search()? {
+            return print_account_list(&cache.query_accounts(query), false);
+        }
+    }
+    
+    let passman = unlock_vault()?;
+    // The cache was missing or stale; only a cached session can use a new one
+    let settings = vault_settings(&passman)?;
+    if settings.search_cache_minutes > 0 && settings.auto_lock_timeout > 0 {
+        let _ = passman.refresh_search_cache();
+    }
+    print_account_list(&passman.query_accounts(query), show_passwords)
+}
+
+/// The current vault's search cache, if a cached session can read it and it is current
+fn cached_search() -> Result<Option<SearchCache>> {
+    let vault_name = get_current_vault_name()?;
+    let Some(cached) = session::load(&vault_name) else {
+        return Ok(None);
+    };
+    Ok(PassMan::new(&vault_name)?.load_search_cache(&cached.key))
+}
+
+fn print_account_list(accounts: &[&Account], show_passwords: bool) -> Result<()> {
+    if output::is_json() {
+        let accounts: Vec<AccountJson> = accounts.iter().map(|account| AccountJson::new(account, show_passwords)).collect();
+        return output::print_json(&accounts);
+    }
+    
+    if accounts.is_empty() {
+        status!("{}", "No accounts found.".yellow());
+        return Ok(());
+    }
+    
+    print_account_table(accounts, show_passwords);
+    status!();
+    status!("{}", format!("{} account(s)", accounts.len()).blue());
+    
+    Ok(())
+}
+
+fn show_account(name: &str, show_password: bool, clip: bool) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let id = find_account(&passman, name)?.id;
+    if show_password {
+        confirm_reveal(&mut passman)?;
+    }
+    if show_password || clip {
+        passman.record_access(id)?;
+    }
+    let account = passman.get_account(id)
+        .ok_or_else(|| PassManError::AccountNotFound(format!("Account '{}' not found", name)))?;
+    
+    if output::is_json() {
+        output::print_json(&AccountJson::new(account, show_password))?;
+    } else if output::is_quiet() && show_password {
+        // In quiet mode the password is the whole output, ready for a pipe
+        println!("{}", account.password);
+        return Ok(());
+    } else {
+        print_account_details(account, show_password);
+    }
     
-    if let Err(e) = run_command(cli) {
-        eprintln!("{} {}", "Error:".red().bold(), e);
-        std::process::exit(1);
+    if clip {
+        clip::copy(&account.password, vault_settings(&passman)?)?;
+        status!("{}", format!("✓ Password of '{}' copied to clipboard", account.name).green());
+        announce_clear(vault_settings(&passman)?);
     }
+    
+    Ok(())
 }
 
-fn run_command(cli: Cli) -> Result<()> {
-    match cli.command {
-        Commands::Init { email } => {
-            init_vault(&email)?;
-        }
-        
-        Commands::Add { name, account_type, url, username, generate, length } => {
-            add_account(&name, account_type, url, username, generate, length)?;
-        }
-        
-        Commands::List { account_type, search, show_passwords } => {
-            list_accounts(account_type, search, show_passwords)?;
-        }
-        
-        Commands::Show { name, show_password } => {
-            show_account(&name, show_password)?;
+fn print_account_details(account: &Account, show_password: bool) {
+    match account.icon.as_deref().filter(|icon| !appearance::is_icon_url(icon)) {
+        Some(icon) => println!("{} {}", icon, format!("Account: {}", account.name).white().bold()),
+        None => println!("{}", format!("Account: {}", account.name).white().bold()),
+    }
+    println!("  Type: {}", account.account_type.display_name());
+    if let Some(ref color) = account.color {
+        match color_swatch(color) {
+            Some(swatch) => println!("  Color: {} {}", swatch, color),
+            None => println!("  Color: {}", color),
         }
-        
-        Commands::Generate { length, special, numbers, uppercase, lowercase, copy } => {
-            generate_password(length, special, numbers, uppercase, lowercase, copy)?;
+    }
+    if account.icon.as_deref().is_some_and(appearance::is_icon_url) {
+        // Data URLs are too long to print
+        println!("  Icon: {}", "(image stored in the vault)".dimmed());
+    }
+    if let Some(ref url) = account.url {
+        println!("  URL: {}", url.blue());
+    }
+    if let Some(ref username) = account.username {
+        println!("  Username: {}", username);
+    }
+    // Cards and identities without a password show their details instead
+    if account.uses_password() {
+        if show_password {
+            println!("  Password: {}", account.password.red());
+        } else {
+            println!("  Password: {}", "••••••••".red());
         }
-        
-        Commands::Vaults => {
-            list_vaults()?;
+    }
+    if let Some(ref card) = account.card {
+        items::print_card(card, show_password);
+    }
+    if let Some(ref identity) = account.identity {
+        items::print_identity(identity, show_password);
+    }
+    if let Some(ref ssh_key) = account.ssh_key {
+        println!("  SSH key: {}", ssh_key.public_key.trim());
+    }
+    if !account.tags.is_empty() {
+        println!("  Tags: {}", account.tags.join(", ").cyan());
+    }
+    if let Some(ref notes) = account.notes {
+        println!("  Notes: {}", notes);
+    }
+    for field in &account.custom_fields {
+        if field.hidden && !show_password {
+            println!("  {}: {}", field.name, "••••••••".red());
+        } else {
+            println!("  {}: {}", field.name, field.value);
         }
     }
+    println!("  Created: {}", account.created_at.format("%Y-%m-%d %H:%M:%S"));
+    println!("  Updated: {}", account.updated_at.format("%Y-%m-%d %H:%M:%S"));
+}
+
+/// Ask for the master password again before revealing a secret
+/// 
+/// Only vaults with `require_confirmation` enabled are gated; a cached
+/// session alone is not enough to put a password on screen.
+fn confirm_reveal(passman: &mut PassMan) -> Result<()> {
+    if !vault_settings(passman)?.require_confirmation {
+        return Ok(());
+    }
     
-    Ok(())
+    let keyfile = if passman.requires_keyfile()? {
+        Some(read_keyfile(passman.vault_name())?)
+    } else {
+        None
+    };
+    
+    status!("{}", "This vault requires the master password to reveal secrets.".yellow());
+    let master_password = prompt_master_password()
+        .map_err(|_| credentials::no_terminal("the master password to reveal secrets"))?;
+    passman.verify_master_password(&master_password, keyfile.as_deref())
 }
 
-fn init_vault(email: &str) -> Result<()> {
-    println!("{}", "Initializing new PassMan vault...".green().bold());
+fn open_account(name: &str, copy_username: bool, copy_password: bool) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let id = find_account(&passman, name)?.id;
+    passman.record_access(id)?;
+    let account = passman.get_account(id)
+        .ok_or_else(|| PassManError::AccountNotFound(format!("Account '{}' not found", name)))?;
+    
+    let url = account.url.as_deref()
+        .ok_or_else(|| PassManError::InvalidInput(format!("Account '{}' has no URL", account.name)))?;
     
-    let vault_name = prompt_vault_name()?;
-    let master_password = prompt_master_password()?;
-    let confirm_password = prompt_confirm_password()?;
+    let settings = vault_settings(&passman)?;
+    let mut copied = false;
     
-    if master_password != confirm_password {
-        return Err(PassManError::InvalidInput("Passwords do not match".to_string()));
+    if copy_username {
+        match account.username.as_deref() {
+            Some(username) => {
+                clip::copy(username, settings)?;
+                copied = true;
+                status!("{}", "✓ Username copied to clipboard".green());
+            }
+            None => status!("{}", "Account has no username, skipping.".yellow()),
+        }
     }
     
-    let mut passman = PassMan::new(&vault_name)?;
-    passman.init_vault(email.to_string(), &master_password)?;
+    browser::open_url(url)?;
+    status!("{}", format!("Opened {}", url).blue());
     
-    println!("{}", "✓ Vault created successfully!".green().bold());
-    println!("{}", "You can now add accounts with 'passman add'".blue());
+    if copy_password {
+        if copied {
+            eprint!("Press Enter to copy the password...");
+            io::stderr().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+        }
+        clip::copy(&account.password, settings)?;
+        copied = true;
+        status!("{}", "✓ Password copied to clipboard".green());
+    }
+    
+    if copied {
+        announce_clear(settings);
+    }
     
     Ok(())
 }
 
-fn add_account(name: &str, account_type: Option<AccountType>, url: Option<String>, username: Option<String>, generate: bool, length: usize) -> Result<()> {
-    let vault_name = get_current_vault_name()?;
-    let master_password = prompt_master_password()?;
-    let mut passman = PassMan::new(&vault_name)?;
-    passman.open_vault(&master_password)?;
-    
-    let account_type = account_type.unwrap_or_else(|| prompt_account_type());
-    let url = url.or_else(|| prompt_url());
-    let username = username.or_else(|| prompt_username());
-    
-    let password = if generate {
-        let options = PasswordOptions::strong(length);
-        passman.generate_password(&options)?
-    } else {
-        prompt_password()?
-    };
-    
-    let notes = prompt_notes();
-    let tags = prompt_tags();
+fn copy_field(name: &str, field: &CopyField) -> Result<()> {
+    let mut passman = unlock_vault()?;
+    let id = find_account(&passman, name)?.id;
+    passman.record_access(id)?;
+    let account = passman.get_account(id)
+        .ok_or_else(|| PassManError::AccountNotFound(format!("Account '{}' not found", name)))?;
     
-    passman.add_account(
-        name.to_string(),
-        account_type,
-        password,
-        url,
-        username,
-        notes,
-        tags,
-    )?;
+    let value = field.value(account)?;
+    let settings = vault_settings(&passman)?;
+    clip::copy(&value, settings)?;
+    status!("{}", format!("✓ {} of '{}' copied to clipboard", field, account.name).green());
     
-    println!("{}", "✓ Account added successfully!".green().bold());
+    if let CopyField::Otp = field {
+        if let Some(ref secret) = account.totp {
+            let (_, remaining) = Totp::parse(secret)?.current_code();
+            status!("Code is valid for {} more seconds.", remaining);
+        }
+    }
     
+    announce_clear(settings);
     Ok(())
 }
 
-fn list_accounts(account_type: Option<AccountType>, search: Option<String>, show_passwords: bool) -> Result<()> {
-    let vault_name = get_current_vault_name()?;
-    let master_password = prompt_master_password()?;
-    let mut passman = PassMan::new(&vault_name)?;
-    passman.open_vault(&master_password)?;
+fn show_qr(name: &str, otp: bool) -> Result<()> {
+    let passman = unlock_vault()?;
+    let account = find_account(&passman, name)?;
     
-    let accounts = if let Some(search_query) = search {
-        passman.search_accounts(&search_query)
-    } else if let Some(acc_type) = account_type {
-        passman.get_accounts_by_type(&acc_type)
+    let (what, payload) = if otp {
+        let secret = account.totp.as_deref()
+            .ok_or_else(|| PassManError::InvalidInput(format!("Account '{}' has no TOTP secret", account.name)))?;
+        ("two-factor secret", Totp::parse(secret)?.to_uri(&account.name))
     } else {
-        passman.get_all_accounts()
+        ("password", account.password.clone())
     };
     
-    if accounts.is_empty() {
-        println!("{}", "No accounts found.".yellow());
+    if vault_settings(&passman)?.require_confirmation
+        && !prompt_confirm(&format!("Display the {} of '{}' on screen?", what, account.name))?
+    {
+        status!("{}", "Cancelled.".yellow());
         return Ok(());
     }
     
-    println!("{}", format!("Found {} account(s):", accounts.len()).blue().bold());
-    println!();
+    println!("{}", qr::render(&payload)?);
+    status!("{}", format!("Scan to transfer the {} of '{}'.", what, account.name).blue());
     
-    for account in accounts {
-        println!("{}", format!("Name: {}", account.name).white().bold());
-        println!("  Type: {}", account.account_type.display_name());
-        if let Some(ref url) = account.url {
-            println!("  URL: {}", url.blue());
-        }
-        if let Some(ref username) = account.username {
-            println!("  Username: {}", username);
-        }
-        if show_passwords {
-            println!("  Password: {}", account.password.red());
-        } else {
-            println!("  Password: {}", "••••••••".red());
-        }
-        if !account.tags.is_empty() {
-            println!("  Tags: {}", account.tags.join(", ").cyan());
-        }
-        if let Some(ref notes) = account.notes {
-            println!("  Notes: {}", notes);
-        }
-        println!();
+    Ok(())
+}
+
+fn generate_password(options: &PasswordOptions, copy: bool) -> Result<()> {
+    let mut passman = PassMan::new("temp")?;
+    let password = passman.generate_password(options)?;
+    let estimate = passman.estimate_password_strength(&password);
+    let strength_desc = passman.get_password_strength_description(estimate.score);
+    
+    println!("{}", password.green().bold());
+    status!("{}", format!("Strength: {} ({}, cracked in {})", estimate.score, strength_desc, estimate.crack_time).blue());
+    
+    if copy {
+        let settings = VaultSettings::default();
+        clip::copy(&password, &settings)?;
+        status!("{}", "✓ Password copied to clipboard".green());
+        announce_clear(&settings);
     }
     
     Ok(())
 }
 
-fn show_account(name: &str, show_password: bool) -> Result<()> {
-    let vault_name = get_current_vault_name()?;
-    let master_password = prompt_master_password()?;
-    let mut passman = PassMan::new(&vault_name)?;
-    passman.open_vault(&master_password)?;
+fn generate_passphrase(options: &PassphraseOptions, wordlist: Option<PathBuf>, copy: bool) -> Result<()> {
+    let mut generator = match wordlist {
+        Some(path) => PasswordGenerator::with_wordlist(path)?,
+        None => PasswordGenerator::new(),
+    };
+    let passphrase = generator.generate_passphrase_from_list(options)?;
+    let word_count = generator.word_list_len(options);
+    let entropy = PasswordGenerator::passphrase_entropy(options, word_count);
     
-    let accounts = passman.search_accounts(name);
-    let account = accounts.first()
-        .ok_or_else(|| PassManError::AccountNotFound(format!("Account '{}' not found", name)))?;
+    println!("{}", passphrase.green().bold());
+    status!("{}", format!("Entropy: {:.1} bits ({} words from a list of {})", entropy, options.word_count, word_count).blue());
     
-    println!("{}", format!("Account: {}", account.name).white().bold());
-    println!("  Type: {}", account.account_type.display_name());
-    if let Some(ref url) = account.url {
-        println!("  URL: {}", url.blue());
-    }
-    if let Some(ref username) = account.username {
-        println!("  Username: {}", username);
+    if copy {
+        let settings = VaultSettings::default();
+        clip::copy(&passphrase, &settings)?;
+        status!("{}", "✓ Passphrase copied to clipboard".green());
+        announce_clear(&settings);
     }
-    if show_password {
-        println!("  Password: {}", account.password.red());
-    } else {
-        println!("  Password: {}", "••••••••".red());
+    
+    Ok(())
+}
+
+fn generate_passwords(options: &PasswordOptions, count: usize) -> Result<()> {
+    let mut passman = PassMan::new("temp")?;
+    for candidate in passman.generate_password_batch(options, count)? {
+        let strength = format!("{:>3} ({})", candidate.strength, passman.get_password_strength_description(candidate.strength));
+        println!("{}  {}", candidate.password.green().bold(), strength.blue());
     }
-    if !account.tags.is_empty() {
-        println!("  Tags: {}", account.tags.join(", ").cyan());
+    Ok(())
+}
+
+fn generate_pin(length: usize, copy: bool) -> Result<()> {
+    let pin = PasswordGenerator::new().generate_pin(length)?;
+    println!("{}", pin.green().bold());
+    
+    if copy {
+        let settings = VaultSettings::default();
+        clip::copy(&pin, &settings)?;
+        status!("{}", "✓ PIN copied to clipboard".green());
+        announce_clear(&settings);
     }
-    if let Some(ref notes) = account.notes {
-        println!("  Notes: {}", notes);
+    
+    Ok(())
+}
+
+fn generate_username(options: &UsernameOptions, copy: bool) -> Result<()> {
+    let mut passman = PassMan::new("temp")?;
+    let username = passman.generate_username(options)?;
+    println!("{}", username.green().bold());
+    
+    if copy {
+        let settings = VaultSettings::default();
+        clip::copy(&username, &settings)?;
+        status!("{}", "✓ Username copied to clipboard".green());
+        announce_clear(&settings);
     }
-    println!("  Created: {}", account.created_at.format("%Y-%m-%d %H:%M:%S"));
-    println!("  Updated: {}", account.updated_at.format("%Y-%m-%d %H:%M:%S"));
     
     Ok(())
 }
 
-fn generate_password(length: usize, special: bool, numbers: bool, uppercase: bool, lowercase: bool, copy: bool) -> Result<()> {
-    let options = PasswordOptions {
-        length,
-        include_uppercase: uppercase,
-        include_lowercase: lowercase,
-        include_numbers: numbers,
-        include_special: special,
-        exclude_similar: true,
-        exclude_ambiguous: false,
+fn write_man_pages(output: Option<PathBuf>) -> Result<()> {
+    let cmd = Cli::command();
+    match output {
+        Some(dir) => {
+            let pages = man::render_all(cmd, &dir)?;
+            status!("{}", format!("✓ Wrote {} man page(s) to {}", pages.len(), dir.display()).green());
+        }
+        None => man::render(cmd, &mut io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+fn recover_vault(keyfile: Option<PathBuf>) -> Result<()> {
+    let vault_name = get_current_vault_name()?;
+    let mut passman = PassMan::new(&vault_name)?;
+    
+    eprint!("Enter recovery key: ");
+    io::stderr().flush()?;
+    let recovery_key = rpassword::read_password()?;
+    passman.open_vault_with_recovery_key(&recovery_key)?;
+    status!("{}", "✓ Recovery key accepted.".green());
+    
+    let keyfile = keyfile.map(std::fs::canonicalize).transpose()?;
+    let keyfile_contents = keyfile.as_ref().map(std::fs::read).transpose()?;
+    let new_password = wizard::prompt_new_master_password()?;
+    passman.change_master_password(&new_password, keyfile_contents.as_deref())?;
+    
+    let mut config = CliConfig::load_file()?;
+    let previous = match keyfile {
+        Some(path) => config.keyfiles.insert(vault_name.clone(), path),
+        None => config.keyfiles.remove(&vault_name),
     };
+    if previous.is_some() || keyfile_contents.is_some() {
+        config.save()?;
+    }
     
-    let mut passman = PassMan::new("temp")?;
-    let password = passman.generate_password(&options)?;
-    let strength = passman.calculate_password_strength(&password);
-    let strength_desc = passman.get_password_strength_description(strength);
+    status!("{}", "✓ Master password reset. The recovery key remains valid.".green().bold());
     
-    println!("{}", format!("Generated password: {}", password).green().bold());
-    println!("{}", format!("Strength: {} ({})", strength, strength_desc).blue());
+    Ok(())
+}
+
+fn lock_vaults() -> Result<()> {
+    let mut locked = 0;
+    for vault in PassMan::list_vaults()? {
+        if session::clear(&vault)? {
+            locked += 1;
+        }
+    }
     
-    if copy {
-        // In a real implementation, you'd use the clipboard crate
-        println!("{}", "Password copied to clipboard!".green());
+    if locked == 0 {
+        status!("{}", "No active sessions.".yellow());
+    } else {
+        status!("{}", format!("✓ Locked {} vault session(s)", locked).green().bold());
     }
     
     Ok(())
 }
 
-fn list_vaults() -> Result<()> {
+fn show_status() -> Result<()> {
     let vaults = PassMan::list_vaults()?;
     
     if vaults.is_empty() {
-        println!("{}", "No vaults found.".yellow());
+        status!("{}", "No vaults found.".yellow());
         return Ok(());
     }
     
-    println!("{}", "Available vaults:".blue().bold());
+    let current = match current_vault()? {
+        Some((name, source)) => {
+            let source = source.map(|source| format!(" (from {})", source)).unwrap_or_default();
+            if vaults.contains(&name) {
+                println!("Current vault: {}{}", name.white().bold(), source);
+            } else {
+                println!("Current vault: {}{} {}", name.white().bold(), source, "(missing)".red());
+            }
+            Some(name)
+        }
+        None => {
+            println!("Current vault: {}", "none (run `passman use <name>`)".yellow());
+            None
+        }
+    };
+    
     for vault in vaults {
-        println!("  {}", vault);
+        let marker = if current.as_deref() == Some(vault.as_str()) { "*" } else { " " };
+        match session::load(&vault) {
+            Some(cached) => {
+                let remaining = (cached.expires_at - chrono::Utc::now()).num_minutes();
+                println!(
+                    "{} {}  {} (expires {}, in {} min)",
+                    marker,
+                    vault.white().bold(),
+                    "unlocked".green(),
+                    cached.expires_at.with_timezone(&chrono::Local).format("%H:%M:%S"),
+                    remaining,
+                );
+            }
+            None => println!("{} {}  {}", marker, vault.white().bold(), "locked".red()),
+        }
     }
     
     Ok(())
@@ -335,8 +1775,8 @@ fn list_vaults() -> Result<()> {
 // Helper functions for user input
 
 fn prompt_vault_name() -> Result<String> {
-    print!("Enter vault name: ");
-    io::stdout().flush()?;
+    eprint!("Enter vault name: ");
+    io::stderr().flush()?;
     
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
@@ -349,35 +1789,55 @@ fn prompt_vault_name() -> Result<String> {
     Ok(name)
 }
 
+/// Ask for the master password, unless it was given with `--password-stdin` or `--password-fd`
 fn prompt_master_password() -> Result<String> {
-    print!("Enter master password: ");
-    io::stdout().flush()?;
+    if let Some(password) = credentials::given_password()? {
+        return Ok(password);
+    }
+    eprint!("Enter master password: ");
+    io::stderr().flush()?;
     
     rpassword::read_password()
-        .map_err(|e| PassManError::IoError(e))
+        .map_err(PassManError::IoError)
 }
 
 fn prompt_confirm_password() -> Result<String> {
-    print!("Confirm master password: ");
-    io::stdout().flush()?;
+    eprint!("Confirm master password: ");
+    io::stderr().flush()?;
     
     rpassword::read_password()
-        .map_err(|e| PassManError::IoError(e))
+        .map_err(PassManError::IoError)
 }
 
-fn prompt_account_type() -> AccountType {
-    println!("Select account type:");
-    println!("1. Social");
-    println!("2. Banking");
-    println!("3. Work");
-    println!("4. Personal");
-    println!("5. Email");
-    println!("6. Shopping");
-    println!("7. Gaming");
-    println!("8. Other");
+fn prompt_confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N]: ", question);
+    io::stderr().flush()?;
     
-    print!("Enter choice (1-8): ");
-    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn prompt_account_type(categories: &[String]) -> AccountType {
+    eprintln!("Select account type:");
+    eprintln!("1. Social");
+    eprintln!("2. Banking");
+    eprintln!("3. Work");
+    eprintln!("4. Personal");
+    eprintln!("5. Email");
+    eprintln!("6. Shopping");
+    eprintln!("7. Gaming");
+    eprintln!("8. Other");
+    eprintln!("9. Card");
+    eprintln!("10. Identity");
+    eprintln!("11. SSH key");
+    for (i, category) in categories.iter().enumerate() {
+        eprintln!("{}. {}", 12 + i, category);
+    }
+    
+    eprint!("Enter choice (1-{}) or a new category name: ", 11 + categories.len());
+    io::stderr().flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -391,13 +1851,21 @@ fn prompt_account_type() -> AccountType {
         "6" => AccountType::Shopping,
         "7" => AccountType::Gaming,
         "8" => AccountType::Other,
-        _ => AccountType::Personal,
+        "9" => AccountType::Card,
+        "10" => AccountType::Identity,
+        "11" => AccountType::SshKey,
+        choice => match choice.parse::<usize>() {
+            Ok(number) => number.checked_sub(12)
+                .and_then(|i| categories.get(i))
+                .map_or(AccountType::Personal, |category| AccountType::Custom(category.clone())),
+            Err(_) => choice.parse().unwrap_or(AccountType::Personal),
+        },
     }
 }
 
 fn prompt_url() -> Option<String> {
-    print!("Enter URL (optional): ");
-    io::stdout().flush().unwrap();
+    eprint!("Enter URL (optional): ");
+    io::stderr().flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -407,8 +1875,8 @@ fn prompt_url() -> Option<String> {
 }
 
 fn prompt_username() -> Option<String> {
-    print!("Enter username/email (optional): ");
-    io::stdout().flush().unwrap();
+    eprint!("Enter username/email (optional): ");
+    io::stderr().flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -418,16 +1886,16 @@ fn prompt_username() -> Option<String> {
 }
 
 fn prompt_password() -> Result<String> {
-    print!("Enter password: ");
-    io::stdout().flush()?;
+    eprint!("Enter password: ");
+    io::stderr().flush()?;
     
     rpassword::read_password()
-        .map_err(|e| PassManError::IoError(e))
+        .map_err(PassManError::IoError)
 }
 
 fn prompt_notes() -> Option<String> {
-    print!("Enter notes (optional): ");
-    io::stdout().flush().unwrap();
+    eprint!("Enter notes (optional): ");
+    io::stderr().flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -437,8 +1905,8 @@ fn prompt_notes() -> Option<String> {
 }
 
 fn prompt_tags() -> Vec<String> {
-    print!("Enter tags (comma-separated, optional): ");
-    io::stdout().flush().unwrap();
+    eprint!("Enter tags (comma-separated, optional): ");
+    io::stderr().flush().unwrap();
     
     let mut input = String::new();
     io::stdin().read_line(&mut input).unwrap();
@@ -451,8 +1919,369 @@ fn prompt_tags() -> Vec<String> {
     }
 }
 
+/// Ask for custom fields until an empty name is entered
+fn prompt_custom_fields() -> Result<Vec<CustomField>> {
+    let mut fields = Vec::new();
+    loop {
+        eprint!("Custom field name (optional, empty to finish): ");
+        io::stderr().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let name = input.trim().to_string();
+        if name.is_empty() {
+            return Ok(fields);
+        }
+        
+        let hidden = prompt_confirm("Hide the value?")?;
+        let value = prompt_field_value(&name, hidden)?;
+        fields.push(CustomField::new(name, value, hidden));
+    }
+}
+
+/// Ask for the value of a custom field, without echo if it is hidden
+fn prompt_field_value(name: &str, hidden: bool) -> Result<String> {
+    eprint!("{}: ", name);
+    io::stderr().flush()?;
+    
+    if hidden {
+        return rpassword::read_password().map_err(PassManError::IoError);
+    }
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Maximum width of a single table column
+const MAX_COLUMN_WIDTH: usize = 32;
+
+/// Print accounts as a compact table, one row per account
+fn print_account_table(accounts: &[&Account], show_passwords: bool) {
+    let mut headers = vec!["NAME", "TYPE", "USERNAME", "URL", "TAGS"];
+    if show_passwords {
+        headers.push("PASSWORD");
+    }
+    
+    let rows: Vec<Vec<String>> = accounts.iter()
+        .map(|account| {
+            let mut name = account.name.clone();
+            if account.is_favorite {
+                name.push_str(" ★");
+            }
+            let mut row = vec![
+                name,
+                account.account_type.display_name().to_string(),
+                account.username.clone().unwrap_or_default(),
+                account.url.clone().unwrap_or_default(),
+                account.tags.join(","),
+            ];
+            if show_passwords {
+                row.push(account.password.clone());
+            }
+            row.into_iter().map(|cell| truncate(&cell, MAX_COLUMN_WIDTH)).collect()
+        })
+        .collect();
+    
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(headers[i].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    
+    let header_line: Vec<String> = headers.iter().zip(&widths)
+        .map(|(header, width)| format!("{:width$}", header, width = width))
+        .collect();
+    // Colored accounts get a swatch before their name; the others are indented to match
+    let swatches = accounts.iter().any(|account| account.color.is_some());
+    let indent = if swatches { "  " } else { "" };
+    status!("{}{}", indent, header_line.join("  ").trim_end().bold());
+    
+    for (account, row) in accounts.iter().zip(rows) {
+        let cells: Vec<String> = row.iter().zip(&widths).enumerate()
+            .map(|(i, (cell, width))| {
+                // No padding on the last column so lines don't end in spaces
+                let padded = if i + 1 == widths.len() {
+                    cell.clone()
+                } else {
+                    format!("{:width$}", cell, width = width)
+                };
+                match headers[i] {
+                    "NAME" => match account.color.as_deref().and_then(color_swatch) {
+                        Some(swatch) => format!("{} {}", swatch, padded.white().bold()),
+                        None => format!("{}{}", indent, padded.white().bold()),
+                    },
+                    "URL" => padded.blue().to_string(),
+                    "TAGS" => padded.cyan().to_string(),
+                    "PASSWORD" => padded.red().to_string(),
+                    _ => padded,
+                }
+            })
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+/// Shorten text to at most `max` characters, marking the cut with an ellipsis
+/// A colored dot for a `#rrggbb` label color
+fn color_swatch(color: &str) -> Option<ColoredString> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some("●".truecolor(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Account field that can be copied to the clipboard
+#[derive(Clone, Debug, PartialEq)]
+pub enum CopyField {
+    Password,
+    Username,
+    Url,
+    Notes,
+    Otp,
+    Custom(String),
+}
+
+impl CopyField {
+    /// Extract this field's value from an account
+    fn value(&self, account: &Account) -> Result<String> {
+        let missing = || PassManError::InvalidInput(format!("Account '{}' has no {}", account.name, self));
+        match self {
+            CopyField::Password => Ok(account.password.clone()),
+            CopyField::Username => account.username.clone().ok_or_else(missing),
+            CopyField::Url => account.url.clone().ok_or_else(missing),
+            CopyField::Notes => account.notes.clone().ok_or_else(missing),
+            CopyField::Otp => {
+                let secret = account.totp.as_deref().ok_or_else(missing)?;
+                Ok(Totp::parse(secret)?.current_code().0)
+            }
+            CopyField::Custom(label) => account.custom_field(label)
+                .map(|f| f.value.clone())
+                .ok_or_else(missing),
+        }
+    }
+}
+
+impl FromStr for CopyField {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(label) = s.strip_prefix("custom:") {
+            if label.is_empty() {
+                return Err("custom field label cannot be empty".to_string());
+            }
+            return Ok(CopyField::Custom(label.to_string()));
+        }
+        
+        match s.to_lowercase().as_str() {
+            "password" => Ok(CopyField::Password),
+            "username" => Ok(CopyField::Username),
+            "url" => Ok(CopyField::Url),
+            "notes" => Ok(CopyField::Notes),
+            "otp" | "totp" => Ok(CopyField::Otp),
+            _ => Err(format!("unknown field '{}' (expected password, username, url, notes, otp, or custom:<label>)", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for CopyField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyField::Password => write!(f, "password"),
+            CopyField::Username => write!(f, "username"),
+            CopyField::Url => write!(f, "URL"),
+            CopyField::Notes => write!(f, "notes"),
+            CopyField::Otp => write!(f, "one-time code"),
+            CopyField::Custom(label) => write!(f, "field '{}'", label),
+        }
+    }
+}
+
+/// Get the settings of an open vault
+fn vault_settings(passman: &PassMan) -> Result<&VaultSettings> {
+    passman.get_vault_metadata()
+        .map(|metadata| &metadata.settings)
+        .ok_or_else(|| PassManError::VaultLocked("vault is not open".to_string()))
+}
+
+/// Tell the user when the clipboard will be cleared
+fn announce_clear(settings: &VaultSettings) {
+    if let Some(timeout) = clip::clear_timeout(settings) {
+        status!("Clipboard will be cleared in {} seconds.", timeout);
+    }
+}
+
+/// Open the current vault, reusing a cached session when one is active
+/// 
+/// Falls back to prompting for the master password, and caches the new
+/// session for the vault's auto-lock timeout.
+fn unlock_vault() -> Result<PassMan> {
+    let vault_name = get_current_vault_name()?;
+    let mut passman = PassMan::new(&vault_name)?;
+    
+    if let Some(cached) = session::load(&vault_name) {
+        if passman.open_vault_with_key(cached.key).is_ok() {
+            emergency::warn_pending_request(&passman);
+            return Ok(passman);
+        }
+        // The vault was re-keyed or replaced since the session was cached
+        let _ = session::clear(&vault_name);
+    }
+    
+    open_with_master_password(&mut passman)?;
+    emergency::warn_pending_request(&passman);
+    
+    let timeout = vault_settings(&passman)?.auto_lock_timeout;
+    if timeout > 0 {
+        if let Err(e) = agent::autostart(&CliConfig::load()?.agent) {
+            eprintln!("{} {}", "Warning:".yellow().bold(), e);
+        }
+        if let Some(key) = passman.session_key() {
+            if let Err(e) = session::store(&vault_name, &key, timeout) {
+                eprintln!("{} {}", "Warning:".yellow().bold(), e);
+            }
+        }
+    }
+    
+    Ok(passman)
+}
+
+/// Open a vault by prompting for its master password (and keyfile)
+/// 
+/// Never uses a cached session, so it also serves to re-confirm the
+/// master password before destructive operations.
+fn open_with_master_password(passman: &mut PassMan) -> Result<()> {
+    let vault_name = passman.vault_name().to_string();
+    let keyfile = if passman.requires_keyfile()? {
+        Some(read_keyfile(&vault_name)?)
+    } else {
+        None
+    };
+    
+    // A password given on stdin or a file descriptor can't be retyped
+    let max_attempts = if credentials::is_interactive() { MAX_PASSWORD_ATTEMPTS } else { 1 };
+    for attempt in 1..=max_attempts {
+        let delay = passman.unlock_retry_delay();
+        if !delay.is_zero() {
+            status!("{}", format!("Waiting {} second(s) before the next attempt...", delay.as_secs_f64().ceil()).yellow());
+            std::thread::sleep(delay);
+        }
+        
+        let master_password = prompt_master_password().map_err(|e| match e {
+            PassManError::IoError(_) => credentials::no_terminal(&format!("the master password of '{}' (no active session)", vault_name)),
+            e => e,
+        })?;
+        
+        let failures = passman.failed_unlock_attempts();
+        let result = passman.open_vault_with_keyfile(&master_password, keyfile.as_deref());
+        // Only a wrong password is worth another try
+        let wrong_password = passman.failed_unlock_attempts() > failures;
+        if result.is_ok() || !wrong_password || attempt == max_attempts || passman.is_unlock_locked_out() {
+            return result;
+        }
+        
+        eprintln!("{} Wrong master password ({} of {} attempts)", "✗".red(), attempt, MAX_PASSWORD_ATTEMPTS);
+        if passman.failed_unlock_attempts() == HINT_AFTER_FAILURES {
+            if let Some(hint) = passman.password_hint()? {
+                eprintln!("{} {}", "Hint:".cyan().bold(), hint);
+            }
+        }
+    }
+    
+    unreachable!("the last attempt always returns")
+}
+
+/// Read the keyfile configured for a vault, asking for its path if unknown
+fn read_keyfile(vault_name: &str) -> Result<Vec<u8>> {
+    let path = match CliConfig::load()?.keyfiles.remove(vault_name) {
+        Some(path) => path,
+        None if !credentials::is_interactive() => {
+            return Err(PassManError::InvalidInput(format!(
+                "'{}' needs a keyfile; set its path under [keyfiles] in config.toml to unlock it without a terminal",
+                vault_name,
+            )));
+        }
+        None => {
+            eprint!("Enter keyfile path: ");
+            io::stderr().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            PathBuf::from(input.trim())
+        }
+    };
+    
+    std::fs::read(&path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read keyfile {}: {}", path.display(), e)))
+}
+
+/// Resolve an account by ID, exact name, or unique partial name match
+fn find_account<'a>(passman: &'a PassMan, name: &str) -> Result<&'a Account> {
+    if let Ok(id) = name.parse() {
+        if let Some(account) = passman.get_account(id) {
+            return Ok(account);
+        }
+    }
+    
+    let matches = passman.search_accounts(name);
+    if let Some(account) = matches.iter().find(|a| a.name.eq_ignore_ascii_case(name)) {
+        return Ok(account);
+    }
+    
+    match matches.as_slice() {
+        [account] => Ok(account),
+        [] => Err(PassManError::AccountNotFound(format!("Account '{}' not found", name))),
+        _ => {
+            let names: Vec<&str> = matches.iter().map(|a| a.name.as_str()).collect();
+            Err(PassManError::InvalidInput(format!(
+                "'{}' matches several accounts: {}", name, names.join(", ")
+            )))
+        }
+    }
+}
+
+/// The vault commands use: given with `--vault` or `PASSMAN_VAULT`, or chosen with `passman use`
+/// 
+/// # Returns
+/// The vault name and, if it was given for this run, where it came from;
+/// None if no vault is selected
+pub(crate) fn current_vault() -> Result<Option<(String, Option<&'static str>)>> {
+    if let Some((name, source)) = credentials::given_vault() {
+        return Ok(Some((name, Some(source))));
+    }
+    Ok(CliConfig::load()?.current_vault.map(|name| (name, None)))
+}
+
+/// Get the current vault, or ask for one if none is selected
+/// 
+/// With no current vault and a single vault, that vault is used.
 fn get_current_vault_name() -> Result<String> {
-    // In a real implementation, you'd get this from a session file or environment variable
-    // For now, we'll prompt for it
+    let vaults = PassMan::list_vaults()?;
+    match current_vault()? {
+        Some((name, Some(source))) if !vaults.contains(&name) => {
+            return Err(PassManError::VaultNotFound(format!("Vault '{}' (from {}) does not exist", name, source)));
+        }
+        Some((name, _)) if vaults.contains(&name) => return Ok(name),
+        _ => {}
+    }
+    if let [name] = vaults.as_slice() {
+        return Ok(name.clone());
+    }
+    if !credentials::is_interactive() {
+        return Err(PassManError::InvalidInput(format!(
+            "No current vault; pass --vault, set {}, or run `passman use <name>`",
+            credentials::VAULT_ENV,
+        )));
+    }
     prompt_vault_name()
 }
\ No newline at end of file
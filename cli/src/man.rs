@@ -0,0 +1,66 @@
+//! # Man Pages
+//! 
+//! This module renders roff man pages from the clap command definition, so
+//! the pages always match the binary. `passman man` prints the top-level
+//! page; `passman man --dir DIR` writes one page per subcommand
+//! (`passman.1`, `passman-vault-create.1`, ...) for OS packages to install.
+
+use clap::Command;
+use passman_backend::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write the man page for a command to a writer
+/// 
+/// # Arguments
+/// * `cmd` - The command to document
+/// * `out` - Where to write the roff source
+/// 
+/// # Returns
+/// Unit on success
+pub fn render(cmd: Command, out: &mut dyn Write) -> Result<()> {
+    clap_mangen::Man::new(cmd).render(out)?;
+    Ok(())
+}
+
+/// Write man pages for a command and all of its visible subcommands
+/// 
+/// # Arguments
+/// * `cmd` - The root command
+/// * `dir` - Output directory (created if missing)
+/// 
+/// # Returns
+/// The paths of the pages that were written
+/// 
+/// # Errors
+/// Returns an error if the directory or a page can't be written
+pub fn render_all(mut cmd: Command, dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    // Propagate global arguments and version info into subcommands
+    cmd.build();
+    
+    let mut written = Vec::new();
+    render_tree(&cmd, dir, &mut written)?;
+    Ok(written)
+}
+
+/// Render one page, then recurse into subcommands
+fn render_tree(cmd: &Command, dir: &Path, written: &mut Vec<PathBuf>) -> Result<()> {
+    let page_name = cmd.get_display_name().unwrap_or(cmd.get_name());
+    let path = dir.join(format!("{}.1", page_name));
+    let mut file = std::fs::File::create(&path)?;
+    render(cmd.clone(), &mut file)?;
+    written.push(path);
+    
+    let bin_name = cmd.get_bin_name().unwrap_or(cmd.get_name());
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set() && sub.get_name() != "help") {
+        // Pages are named after the full command path, e.g. passman-vault-create
+        let sub = sub.clone()
+            .display_name(format!("{}-{}", page_name, sub.get_name()))
+            .bin_name(format!("{} {}", bin_name, sub.get_name()))
+            .version(env!("CARGO_PKG_VERSION"));
+        render_tree(&sub, dir, written)?;
+    }
+    
+    Ok(())
+}
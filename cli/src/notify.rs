@@ -0,0 +1,131 @@
+//! # Password Change Notifications
+//! 
+//! This module implements `passman notify`, a cron entry point that shows
+//! a desktop notification when passwords of the current vault are due for
+//! a change (see `ReminderPolicy` in the config file):
+//! 
+//! ```text
+//! 0 * * * *  passman notify
+//! ```
+//! 
+//! It never prompts. With an active session it checks the vault itself;
+//! while the vault is locked it uses the due dates from the last check
+//! with the vault open, and can only say how many passwords need a change.
+//! Notifications go through the platform's standard tool (notify-send,
+//! osascript, or PowerShell).
+
+use crate::session;
+use passman_backend::{
+    PassMan, PassManConfig, PassManError, Result,
+    reminders::{Reminder, ReminderScheduler},
+};
+use std::process::{Command, Stdio};
+
+/// Reminders listed by name in one notification
+const MAX_LISTED: usize = 5;
+
+/// Check for passwords due for a change and notify about them
+/// 
+/// # Arguments
+/// * `force` - Check even if the last check was recent
+/// * `print` - Print the reminders instead of showing a notification
+/// 
+/// # Errors
+/// Returns an error if the scheduler state can't be read or written, or
+/// the notification can't be shown
+pub fn run(force: bool, print: bool) -> Result<()> {
+    let vault_name = crate::get_current_vault_name()?;
+    let config = PassManConfig::load()?;
+    let mut scheduler = ReminderScheduler::load(&vault_name, config.reminders.clone())?;
+    let now = chrono::Utc::now();
+    if !force && !scheduler.is_due(now) {
+        return Ok(());
+    }
+    
+    let mut passman = PassMan::new(&vault_name)?;
+    let unlocked = session::load(&vault_name)
+        .is_some_and(|cached| passman.open_vault_with_key(cached.key).is_ok());
+    let reminders = if unlocked {
+        scheduler.run(Some(&passman.get_all_accounts()), now)
+    } else {
+        scheduler.run(None, now)
+    };
+    scheduler.save()?;
+    
+    if reminders.is_empty() {
+        return Ok(());
+    }
+    let title = format!("PassMan: {} password(s) in '{}' to change", reminders.len(), vault_name);
+    let body = describe(&reminders, now);
+    if print {
+        println!("{}\n{}", title, body);
+        return Ok(());
+    }
+    show_notification(&title, &body)
+}
+
+/// Notification text: names and due dates, or only a count while locked
+fn describe(reminders: &[Reminder], now: chrono::DateTime<chrono::Utc>) -> String {
+    if reminders.iter().all(|reminder| reminder.account_name.is_none()) {
+        return "Unlock the vault and run `passman audit` to see which.".to_string();
+    }
+    let mut lines: Vec<String> = reminders.iter()
+        .take(MAX_LISTED)
+        .map(|reminder| reminder.message(now))
+        .collect();
+    if reminders.len() > MAX_LISTED {
+        lines.push(format!("and {} more", reminders.len() - MAX_LISTED));
+    }
+    lines.join("\n")
+}
+
+/// Show a desktop notification
+fn show_notification(title: &str, body: &str) -> Result<()> {
+    let status = notification_command(title, body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to show notification: {}", e)))?;
+    
+    if !status.success() {
+        return Err(PassManError::InvalidInput("The notification tool reported an error".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Command {
+    // Arguments reach the script through `argv`, so they need no quoting
+    let mut cmd = Command::new("osascript");
+    cmd.args([
+        "-e", "on run argv",
+        "-e", "display notification (item 2 of argv) with title (item 1 of argv)",
+        "-e", "end run",
+        title, body,
+    ]);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn notification_command(title: &str, body: &str) -> Command {
+    // A tray balloon; the text is passed through environment variables, not the script
+    let script = "Add-Type -AssemblyName System.Windows.Forms; \
+        $icon = New-Object System.Windows.Forms.NotifyIcon; \
+        $icon.Icon = [System.Drawing.SystemIcons]::Information; \
+        $icon.Visible = $true; \
+        $icon.ShowBalloonTip(10000, $env:PASSMAN_TITLE, $env:PASSMAN_BODY, 'Info'); \
+        Start-Sleep -Seconds 10; $icon.Dispose()";
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .env("PASSMAN_TITLE", title)
+        .env("PASSMAN_BODY", body);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn notification_command(title: &str, body: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.args(["--app-name=PassMan", "--icon=dialog-password", title, body]);
+    cmd
+}
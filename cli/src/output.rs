@@ -0,0 +1,238 @@
+//! # Output Conventions
+//! 
+//! This module keeps the CLI usable from scripts: command results go to
+//! stdout, while prompts, progress, and other diagnostics go to stderr
+//! (and are dropped entirely with `--quiet`). Failures map to stable
+//! exit codes so callers can tell them apart without parsing messages.
+//! 
+//! With `--output json`, `list`, `show`, `audit`, and `vaults` print their
+//! results as JSON instead of colored text, for `jq` and other tools.
+//! Passwords, hidden field values, card numbers and CVVs, and identity
+//! document numbers are left out unless they were asked for with
+//! `--show-password(s)`.
+
+use chrono::{DateTime, Utc};
+use passman_backend::{PassManError, Result, models::{Account, AccountType, Identity}};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Command completed successfully
+pub const EXIT_SUCCESS: i32 = 0;
+/// Unclassified failure (storage, I/O, crypto)
+pub const EXIT_FAILURE: i32 = 1;
+/// The requested account or vault does not exist
+pub const EXIT_NOT_FOUND: i32 = 2;
+/// The master password was wrong or authentication was refused
+pub const EXIT_AUTH_FAILED: i32 = 3;
+/// The vault is locked and could not be unlocked non-interactively
+pub const EXIT_LOCKED: i32 = 4;
+/// The input was rejected (bad value, ambiguous name, cancelled)
+pub const EXIT_INVALID_INPUT: i32 = 5;
+/// The command line could not be parsed (sysexits EX_USAGE)
+pub const EXIT_USAGE: i32 = 64;
+
+/// Exit code table shown at the end of `passman --help`
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0   success
+  1   general failure
+  2   account or vault not found
+  3   authentication failed
+  4   vault locked
+  5   invalid input
+  64  usage error";
+
+/// Format of command results on stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    
+    /// JSON for scripts
+    Json,
+}
+
+/// Whether decorative output is suppressed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether results are printed as JSON
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable quiet mode
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Check whether quiet mode is enabled
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Set the format of command results
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+/// Check whether results are printed as JSON
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Print a result as pretty-printed JSON on stdout
+/// 
+/// # Arguments
+/// * `value` - The result to print
+/// 
+/// # Returns
+/// Unit on success
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// An account as printed by `list` and `show` with `--output json`
+#[derive(Serialize)]
+pub struct AccountJson<'a> {
+    id: String,
+    name: &'a str,
+    account_type: &'a AccountType,
+    url: Option<&'a str>,
+    username: Option<&'a str>,
+    
+    /// Left out unless passwords were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<&'a str>,
+    
+    notes: Option<&'a str>,
+    tags: &'a [String],
+    folder: Option<&'a str>,
+    favorite: bool,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<&'a str>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<&'a str>,
+    
+    archived: bool,
+    has_totp: bool,
+    custom_fields: Vec<FieldJson<'a>>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card: Option<CardJson<'a>>,
+    
+    /// Document numbers are left out unless passwords were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity: Option<Identity>,
+    
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// A custom field of an `AccountJson`
+#[derive(Serialize)]
+pub struct FieldJson<'a> {
+    name: &'a str,
+    
+    /// Left out for hidden fields unless passwords were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<&'a str>,
+    
+    hidden: bool,
+}
+
+/// The payment card of an `AccountJson`
+#[derive(Serialize)]
+pub struct CardJson<'a> {
+    cardholder: Option<&'a str>,
+    brand: Option<&'a str>,
+    
+    /// The full number if passwords were asked for, else the last four digits
+    number: String,
+    
+    expiry: Option<String>,
+    
+    /// Left out unless passwords were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cvv: Option<&'a str>,
+}
+
+impl<'a> AccountJson<'a> {
+    /// View an account for JSON output
+    /// 
+    /// # Arguments
+    /// * `account` - The account
+    /// * `show_secrets` - Include the password and hidden field values
+    pub fn new(account: &'a Account, show_secrets: bool) -> Self {
+        Self {
+            id: account.id.to_string(),
+            name: &account.name,
+            account_type: &account.account_type,
+            url: account.url.as_deref(),
+            username: account.username.as_deref(),
+            password: show_secrets.then_some(account.password.as_str()),
+            notes: account.notes.as_deref(),
+            tags: &account.tags,
+            folder: account.folder.as_deref(),
+            favorite: account.is_favorite,
+            color: account.color.as_deref(),
+            icon: account.icon.as_deref(),
+            archived: account.archived,
+            has_totp: account.totp.is_some(),
+            custom_fields: account.custom_fields.iter()
+                .map(|field| FieldJson {
+                    name: &field.name,
+                    value: (show_secrets || !field.hidden).then_some(field.value.as_str()),
+                    hidden: field.hidden,
+                })
+                .collect(),
+            card: account.card.as_ref().map(|card| CardJson {
+                cardholder: card.cardholder.as_deref(),
+                brand: card.brand.as_deref(),
+                number: if show_secrets { card.number.clone() } else { card.masked_number() },
+                expiry: card.expiry(),
+                cvv: card.cvv.as_deref().filter(|_| show_secrets),
+            }),
+            identity: account.identity.as_ref().map(|identity| {
+                let mut identity = identity.clone();
+                if !show_secrets {
+                    identity.national_id = None;
+                    identity.passport_number = None;
+                    identity.license_number = None;
+                }
+                identity
+            }),
+            created_at: account.created_at,
+            updated_at: account.updated_at,
+        }
+    }
+}
+
+/// Map an error to the process exit code
+/// 
+/// # Arguments
+/// * `error` - The error that ended the command
+/// 
+/// # Returns
+/// One of the `EXIT_*` codes
+pub fn exit_code(error: &PassManError) -> i32 {
+    match error {
+        PassManError::AccountNotFound(_) | PassManError::VaultNotFound(_) => EXIT_NOT_FOUND,
+        PassManError::AuthenticationFailed(_) | PassManError::LockedOut(_) => EXIT_AUTH_FAILED,
+        PassManError::VaultLocked(_) => EXIT_LOCKED,
+        PassManError::InvalidInput(_) | PassManError::PolicyViolation(_) | PassManError::VaultExists(_) => EXIT_INVALID_INPUT,
+        _ => EXIT_FAILURE,
+    }
+}
+
+/// Print a diagnostic line to stderr unless `--quiet` is set
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
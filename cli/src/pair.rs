@@ -0,0 +1,108 @@
+//! # Device Pairing Commands
+//! 
+//! This module implements `passman pair`, which copies a vault to another
+//! device on the same network. The sending device shows a QR code (and the
+//! same code as text); the receiving device passes the code to `receive`:
+//! 
+//! ```bash
+//! passman pair send
+//! passman pair receive 'passman-pair:192.168.1.20:41873/MZXW6...'
+//! ```
+//! 
+//! The vault arrives still encrypted, and opens with the same master password.
+
+use crate::{qr, status};
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{
+    PassManError, Result,
+    pairing::{self, PairingCode, PairingListener},
+    storage::{self, VaultStorage},
+};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Device pairing subcommands
+#[derive(Subcommand)]
+pub enum PairCommand {
+    /// Show a pairing code and send the current vault to the device that uses it
+    Send {
+        /// Address to listen on (default: this device's address on the local network)
+        #[arg(long)]
+        address: Option<IpAddr>,
+        
+        /// Seconds to wait for the other device
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+    },
+    
+    /// Receive a vault from the device showing a pairing code
+    Receive {
+        /// The pairing code shown by the sending device
+        code: String,
+        
+        /// Name for the vault on this device (default: its name on the sending device)
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+/// Run a pairing subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: PairCommand) -> Result<()> {
+    match command {
+        PairCommand::Send { address, timeout } => send(address, Duration::from_secs(timeout)),
+        PairCommand::Receive { code, name } => receive(&code, name),
+    }
+}
+
+fn send(address: Option<IpAddr>, timeout: Duration) -> Result<()> {
+    // Only someone who can unlock the vault may hand it out
+    let passman = crate::unlock_vault()?;
+    let data = passman.read_vault_file()?;
+    
+    let listener = PairingListener::bind(address)?;
+    let code = listener.code().to_string();
+    println!("{}", qr::render(&code)?);
+    println!("{}", code);
+    status!("{}", format!("Scan the code on the other device, or run: passman pair receive '{}'", code).blue());
+    status!("Waiting up to {} seconds...", timeout.as_secs());
+    
+    let peer = listener.send(passman.vault_name(), &data, timeout)?;
+    status!("{}", format!("✓ Sent vault '{}' to {}", passman.vault_name(), peer.ip()).green().bold());
+    Ok(())
+}
+
+fn receive(code: &str, name: Option<String>) -> Result<()> {
+    let code = PairingCode::parse(code)?;
+    // Refuse a taken name before the other device sends anything
+    if let Some(name) = &name {
+        check_free(name)?;
+    }
+    status!("Connecting to {}...", code.address);
+    let received = pairing::receive(&code)?;
+    
+    let name = name.unwrap_or(received.name);
+    check_free(&name)?;
+    VaultStorage::new(&name)?.install_vault_file(&received.data)?;
+    
+    status!("{}", format!("✓ Received vault '{}'", name).green().bold());
+    status!("It opens with the master password it has on the other device: passman use {}", name);
+    Ok(())
+}
+
+fn check_free(name: &str) -> Result<()> {
+    storage::validate_vault_name(name)?;
+    if VaultStorage::new(name)?.vault_exists() {
+        return Err(PassManError::VaultExists(format!(
+            "'{}'; choose another name with --name",
+            name,
+        )));
+    }
+    Ok(())
+}
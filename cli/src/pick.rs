@@ -0,0 +1,150 @@
+//! # Picker Integration
+//! 
+//! This module implements `passman pick`, a dmenu-style mode for external
+//! pickers such as rofi, dmenu, or fzf. Candidates are printed one per
+//! line; the chosen line is resolved back to an account and the requested
+//! field is printed:
+//! 
+//! ```bash
+//! passman pick | rofi -dmenu | passman pick --print-field password
+//! passman pick --picker "fzf" --print-field otp
+//! ```
+//! 
+//! Candidates are listed most-used first, and picking an account counts
+//! as using it.
+
+use crate::CopyField;
+use passman_backend::{
+    PassMan, PassManError, Result,
+    models::{Account, AccountQuery, SortOrder},
+};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Run the pick command
+/// 
+/// Without `--print-field`, the candidates are printed and nothing else
+/// happens. With it, the selection is read from the picker command when
+/// one is given, and from stdin otherwise.
+/// 
+/// # Arguments
+/// * `field` - Field to print for the selected account
+/// * `picker` - Shell command that reads candidates and prints the choice
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if the picker fails or the selection matches no account
+pub fn run(field: Option<CopyField>, picker: Option<String>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let candidates = candidates(&passman);
+    
+    let field = match field {
+        Some(field) => field,
+        None => {
+            let mut stdout = io::stdout().lock();
+            for (label, _) in &candidates {
+                writeln!(stdout, "{}", label)?;
+            }
+            return Ok(());
+        }
+    };
+    
+    let selection = match picker {
+        Some(command) => run_picker(&command, &candidates)?,
+        None if io::stdin().is_terminal() => {
+            return Err(PassManError::InvalidInput(
+                "No selection: pipe a picker's output into stdin or pass --picker".to_string(),
+            ));
+        }
+        None => {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line
+        }
+    };
+    
+    let selection = selection.trim_end_matches(['\r', '\n']);
+    if selection.is_empty() {
+        return Err(PassManError::InvalidInput("Nothing was selected".to_string()));
+    }
+    
+    let account = candidates.iter()
+        .find(|(label, _)| label == selection)
+        .map(|(_, account)| *account)
+        .ok_or_else(|| PassManError::AccountNotFound(format!("No account matches '{}'", selection)))?;
+    let (id, value) = (account.id, field.value(account)?);
+    
+    passman.record_access(id)?;
+    println!("{}", value);
+    
+    Ok(())
+}
+
+/// Build one unique line per active account, favorites first, then most used first
+/// 
+/// Lines read `Name (username)`; accounts that would print the same line
+/// get a short ID suffix so every line resolves to exactly one account.
+fn candidates(passman: &PassMan) -> Vec<(String, &Account)> {
+    let query = AccountQuery { sort: SortOrder::MostUsed, favorites_first: true, ..AccountQuery::default() };
+    let accounts = passman.query_accounts(&query);
+    let labels: Vec<String> = accounts.iter()
+        .map(|account| match account.username {
+            Some(ref username) => format!("{} ({})", account.name, username),
+            None => account.name.clone(),
+        })
+        .collect();
+    
+    labels.iter()
+        .zip(&accounts)
+        .map(|(label, account)| {
+            let duplicate = labels.iter().filter(|other| *other == label).count() > 1;
+            let label = if duplicate {
+                format!("{} [{}]", label, &account.id.simple().to_string()[..8])
+            } else {
+                label.clone()
+            };
+            (label, *account)
+        })
+        .collect()
+}
+
+/// Feed the candidates to a picker command and read back its choice
+fn run_picker(command: &str, candidates: &[(String, &Account)]) -> Result<String> {
+    let mut child = shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| PassManError::InvalidInput(format!("Failed to start picker '{}': {}", command, e)))?;
+    
+    if let Some(mut stdin) = child.stdin.take() {
+        for (label, _) in candidates {
+            // The picker may exit before reading everything
+            if writeln!(stdin, "{}", label).is_err() {
+                break;
+            }
+        }
+    }
+    
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(PassManError::InvalidInput("Picker was cancelled".to_string()));
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
@@ -0,0 +1,247 @@
+//! # Password Policy Commands
+//! 
+//! This module implements `passman policy`, which shows and changes the
+//! current vault's password policy. Once set, passwords added or changed
+//! with `add`, `edit`, and the other front-ends must meet it:
+//! 
+//! ```bash
+//! passman policy set --min-length 14 --require upper,lower,digit --deny-common
+//! passman policy show
+//! passman policy clear
+//! ```
+//! 
+//! `passman policy site` records what a website accepts instead, for one
+//! account or a whole domain; `passman edit NAME --generate` follows it:
+//! 
+//! ```bash
+//! passman policy site bank.example --domain --max-length 12 --forbid special
+//! passman policy site "Phone PIN" --min-length 6 --max-length 6 --forbid upper,lower,special
+//! passman policy site bank.example --domain --clear
+//! ```
+
+use crate::status;
+use clap::{Subcommand, ValueEnum};
+use colored::*;
+use passman_backend::{PassManError, Result, policy::{PasswordPolicy, SitePolicy}};
+
+/// Password policy subcommands
+#[derive(Subcommand)]
+pub enum PolicyCommand {
+    /// Show the current vault's password policy
+    Show,
+    
+    /// Replace the current vault's password policy
+    Set {
+        /// Minimum length in characters
+        #[arg(long, default_value_t = 0)]
+        min_length: usize,
+        
+        /// Character classes every password must contain
+        #[arg(long, value_enum, value_delimiter = ',')]
+        require: Vec<CharClass>,
+        
+        /// Minimum strength score (0-100)
+        #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+        min_strength: u8,
+        
+        /// Reject well-known common passwords
+        #[arg(long)]
+        deny_common: bool,
+    },
+    
+    /// Remove the current vault's password policy
+    Clear,
+    
+    /// Record what a website accepts, so passwords generated for it fit
+    /// 
+    /// Applies to one account, or with --domain to every account on the
+    /// domain and its subdomains; an account's own policy wins.
+    Site {
+        /// Account name or ID, or a domain with --domain
+        target: String,
+        
+        /// TARGET is a domain
+        #[arg(long)]
+        domain: bool,
+        
+        /// Shortest password the site accepts
+        #[arg(long, default_value_t = 0)]
+        min_length: usize,
+        
+        /// Longest password the site accepts (0 = no limit)
+        #[arg(long, default_value_t = 0)]
+        max_length: usize,
+        
+        /// Character classes the site rejects
+        #[arg(long, value_enum, value_delimiter = ',')]
+        forbid: Vec<CharClass>,
+        
+        /// Character classes the site insists on
+        #[arg(long, value_enum, value_delimiter = ',')]
+        require: Vec<CharClass>,
+        
+        /// Remove the site's policy instead
+        #[arg(long, conflicts_with_all = ["min_length", "max_length", "forbid", "require"])]
+        clear: bool,
+    },
+}
+
+/// Character class required by a policy
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CharClass {
+    Upper,
+    Lower,
+    Digit,
+    Special,
+}
+
+/// Run a password policy subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: PolicyCommand) -> Result<()> {
+    match command {
+        PolicyCommand::Show => show(),
+        PolicyCommand::Set { min_length, require, min_strength, deny_common } => {
+            let policy = PasswordPolicy {
+                min_length,
+                require_uppercase: require.contains(&CharClass::Upper),
+                require_lowercase: require.contains(&CharClass::Lower),
+                require_digit: require.contains(&CharClass::Digit),
+                require_special: require.contains(&CharClass::Special),
+                min_strength,
+                deny_common,
+            };
+            if policy.is_empty() {
+                return Err(PassManError::InvalidInput(
+                    "The policy has no rules; pass at least one option, or use `passman policy clear`".to_string(),
+                ));
+            }
+            update(Some(policy))
+        }
+        PolicyCommand::Clear => update(None),
+        PolicyCommand::Site { target, domain, min_length, max_length, forbid, require, clear } => {
+            let policy = (!clear).then(|| SitePolicy {
+                min_length,
+                max_length,
+                allow_uppercase: !forbid.contains(&CharClass::Upper),
+                allow_lowercase: !forbid.contains(&CharClass::Lower),
+                allow_digits: !forbid.contains(&CharClass::Digit),
+                allow_special: !forbid.contains(&CharClass::Special),
+                require_uppercase: require.contains(&CharClass::Upper),
+                require_lowercase: require.contains(&CharClass::Lower),
+                require_digit: require.contains(&CharClass::Digit),
+                require_special: require.contains(&CharClass::Special),
+            });
+            if policy.as_ref().is_some_and(SitePolicy::is_empty) {
+                return Err(PassManError::InvalidInput(
+                    "The site policy has no rules; pass at least one option, or --clear".to_string(),
+                ));
+            }
+            update_site(&target, domain, policy)
+        }
+    }
+}
+
+/// One-line summary of a site policy, e.g. `6-12 characters; no special; requires digit`
+pub fn describe_site(policy: &SitePolicy) -> String {
+    let mut parts = Vec::new();
+    match (policy.min_length, policy.max_length) {
+        (0, 0) => {}
+        (min, 0) => parts.push(format!("at least {} characters", min)),
+        (0, max) => parts.push(format!("at most {} characters", max)),
+        (min, max) if min == max => parts.push(format!("exactly {} characters", min)),
+        (min, max) => parts.push(format!("{}-{} characters", min, max)),
+    }
+    let classes = [
+        ("uppercase", policy.allow_uppercase, policy.require_uppercase),
+        ("lowercase", policy.allow_lowercase, policy.require_lowercase),
+        ("digit", policy.allow_digits, policy.require_digit),
+        ("special", policy.allow_special, policy.require_special),
+    ];
+    let forbidden: Vec<&str> = classes.iter().filter(|(_, allow, _)| !allow).map(|(name, _, _)| *name).collect();
+    if !forbidden.is_empty() {
+        parts.push(format!("no {}", forbidden.join(", ")));
+    }
+    let required: Vec<&str> = classes.iter().filter(|(_, _, require)| *require).map(|(name, _, _)| *name).collect();
+    if !required.is_empty() {
+        parts.push(format!("requires {}", required.join(", ")));
+    }
+    parts.join("; ")
+}
+
+fn show() -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let settings = crate::vault_settings(&passman)?;
+    if !settings.site_policies.is_empty() {
+        println!("Site policies:");
+        for (domain, policy) in &settings.site_policies {
+            println!("  {}: {}", domain, describe_site(policy));
+        }
+    }
+    let Some(policy) = settings.password_policy.as_ref() else {
+        println!("No password policy");
+        return Ok(());
+    };
+    
+    if policy.min_length > 0 {
+        println!("Minimum length: {}", policy.min_length);
+    }
+    let classes: Vec<&str> = [
+        (policy.require_uppercase, "uppercase"),
+        (policy.require_lowercase, "lowercase"),
+        (policy.require_digit, "digit"),
+        (policy.require_special, "special"),
+    ]
+    .into_iter()
+    .filter_map(|(required, name)| required.then_some(name))
+    .collect();
+    if !classes.is_empty() {
+        println!("Required characters: {}", classes.join(", "));
+    }
+    if policy.min_strength > 0 {
+        println!("Minimum strength: {}", policy.min_strength);
+    }
+    if policy.deny_common {
+        println!("Common passwords: rejected");
+    }
+    Ok(())
+}
+
+fn update(policy: Option<PasswordPolicy>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let mut settings = crate::vault_settings(&passman)?.clone();
+    let cleared = policy.is_none();
+    settings.password_policy = policy;
+    passman.update_settings(settings)?;
+    
+    if cleared {
+        status!("{}", "✓ Password policy removed".green().bold());
+    } else {
+        status!("{}", "✓ Password policy updated; existing passwords are checked when they change".green().bold());
+    }
+    Ok(())
+}
+
+fn update_site(target: &str, domain: bool, policy: Option<SitePolicy>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let cleared = policy.is_none();
+    let name = if domain {
+        passman.set_domain_policy(target, policy)?
+    } else {
+        let account = crate::find_account(&passman, target)?;
+        let (id, name) = (account.id, account.name.clone());
+        passman.set_site_policy(id, policy)?;
+        name
+    };
+    
+    if cleared {
+        status!("{}", format!("✓ Site policy removed for '{}'", name).green().bold());
+    } else {
+        status!("{}", format!("✓ Site policy saved for '{}'; `passman edit NAME --generate` follows it", name).green().bold());
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! # Terminal QR Codes
+//! 
+//! This module renders text as a QR code made of unicode half blocks, so
+//! secrets can be moved to a phone without touching the clipboard.
+
+use passman_backend::{PassManError, Result};
+use qrcode::{render::unicode::Dense1x2, EcLevel, QrCode};
+
+/// Render text as a QR code for the terminal
+/// 
+/// Each character cell holds two modules stacked vertically, which keeps
+/// the code roughly square in most terminal fonts.
+/// 
+/// # Arguments
+/// * `text` - The text to encode
+/// 
+/// # Returns
+/// The QR code as printable lines, including the quiet zone
+/// 
+/// # Errors
+/// Returns an error if the text is too long to fit in a QR code
+pub fn render(text: &str) -> Result<String> {
+    let code = QrCode::with_error_correction_level(text.as_bytes(), EcLevel::M)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot encode QR code: {}", e)))?;
+    
+    Ok(code.render::<Dense1x2>()
+        .quiet_zone(true)
+        .build())
+}
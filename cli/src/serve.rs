@@ -0,0 +1,936 @@
+//! # Local API Server
+//! 
+//! `passman serve` unlocks the current vault once and answers JSON
+//! requests on a loopback address, for local integrations such as editor
+//! plugins and the browser extension:
+//! 
+//! - `GET /v1/status` - vault name, whether it is locked, and the account count
+//! - `POST /v1/unlock` - unlock the vault with `{ "password": "..." }` (see `--locked`)
+//! - `GET /v1/accounts?q=<search>&limit=<n>` - matching accounts, without passwords
+//! - `GET /v1/accounts?url=<page url>` - accounts for a site (or a parent domain of it)
+//! - `GET /v1/accounts/<id>` - one account, including its password
+//! - `POST /v1/accounts` - create an account (the password is generated if omitted;
+//!   passwords breaking the vault's password policy get a 422 listing the `violations`)
+//! - `GET /v1/canaries` - canary accounts for external monitoring (passwords as SHA-1 hashes)
+//! - `GET /v1/audit?stale_days=<n>&expiring_days=<n>` - the password health report (breaches are
+//!   checked against installed breach data only)
+//! - `POST /v1/generate` - a new password (`length`, `uppercase`, `lowercase`,
+//!   `numbers`, `special`) or passphrase (`"passphrase": true`, `words`,
+//!   `separator`); omitted options come from the `generator` config settings.
+//!   Passwords come with their `strength` score and estimated `crack_time`
+//! - `POST /v1/lock` - lock the vault and stop the server
+//! 
+//! Every request needs `Authorization: Bearer <token>`; a new random
+//! token is printed after unlocking and dies with the server. Only
+//! loopback addresses can be bound, requests without a Host header or
+//! naming a non-loopback Host are refused (DNS rebinding), and no CORS
+//! headers are sent. After the
+//! vault's auto-lock timeout passes without an authenticated request, the
+//! vault is locked and the server stops.
+//! 
+//! With `--locked` the server starts without asking for the master
+//! password and keeps running when the vault is locked; clients unlock it
+//! with `POST /v1/unlock`, and every other vault endpoint answers 423
+//! until then. A vault that needs a keyfile uses the one configured under
+//! `[keyfiles]`.
+//! 
+//! The server speaks plain HTTP unless it is given a certificate and key
+//! with `--tls-cert` and `--tls-key` (in builds with the `tls` feature).
+//! Then it accepts only TLS on the address, and relays each connection
+//! to the HTTP server on another loopback port. Either way it never
+//! leaves the loopback interface, and every request needs the token.
+
+use crate::config::CliConfig;
+use crate::status;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use colored::*;
+use passman_backend::{
+    PassMan, PassManError, Result,
+    audit::AuditOptions,
+    generator::{PasswordGenerator, default_word_list},
+    models::{Account, AccountQuery, AccountType, PassphraseOptions, PasswordOptions},
+};
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+use zeroize::Zeroizing;
+
+/// Address used when `--listen` isn't given
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:7656";
+
+/// How often the idle timer is checked while no requests arrive
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Largest request body accepted, in bytes
+const MAX_BODY: u64 = 64 * 1024;
+
+/// Certificate and private key for serving HTTPS
+#[cfg_attr(not(all(feature = "tls", unix)), allow(dead_code))]
+pub struct TlsFiles {
+    /// PEM certificate chain, the server's own certificate first
+    pub cert: PathBuf,
+    
+    /// PEM private key of the certificate
+    pub key: PathBuf,
+}
+
+/// An account in search results (no secrets)
+#[derive(Serialize)]
+struct AccountSummary<'a> {
+    id: String,
+    name: &'a str,
+    account_type: &'a AccountType,
+    username: Option<&'a str>,
+    url: Option<&'a str>,
+}
+
+impl<'a> From<&'a Account> for AccountSummary<'a> {
+    fn from(account: &'a Account) -> Self {
+        Self {
+            id: account.id.to_string(),
+            name: &account.name,
+            account_type: &account.account_type,
+            username: account.username.as_deref(),
+            url: account.url.as_deref(),
+        }
+    }
+}
+
+/// Body of `POST /v1/accounts`
+#[derive(Deserialize)]
+struct NewAccount {
+    name: String,
+    #[serde(default)]
+    account_type: Option<AccountType>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Body of `POST /v1/unlock`
+#[derive(Deserialize)]
+struct Unlock {
+    password: String,
+}
+
+/// Body of `POST /v1/generate`; omitted options come from the config
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Generate {
+    passphrase: bool,
+    length: Option<usize>,
+    uppercase: Option<bool>,
+    lowercase: Option<bool>,
+    numbers: Option<bool>,
+    special: Option<bool>,
+    words: Option<usize>,
+    separator: Option<String>,
+}
+
+/// A response to send back
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+    
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self { status, body: json!({ "error": message.to_string() }) }
+    }
+}
+
+impl From<PassManError> for Reply {
+    /// The body carries the backend's stable error `code` and `kind` next to the message
+    fn from(error: PassManError) -> Self {
+        let status = match error {
+            PassManError::AuthenticationFailed(_) => 401,
+            PassManError::AccountNotFound(_) => 404,
+            PassManError::InvalidInput(_) => 400,
+            PassManError::PolicyViolation(_) => 422,
+            PassManError::VaultLocked(_) => 423,
+            PassManError::LockedOut(_) => 429,
+            _ => 500,
+        };
+        let info = error.info();
+        let mut body = json!({ "error": info.message, "code": info.code, "kind": info.kind });
+        if !info.violations.is_empty() {
+            body["violations"] = json!(info.violations);
+        }
+        Self { status, body }
+    }
+}
+
+/// Run the serve command
+/// 
+/// # Arguments
+/// * `listen` - Loopback address to listen on
+/// * `token_file` - File to also write the access token to
+/// * `locked` - Start without unlocking, and keep serving while locked
+/// * `tls` - Certificate and key to serve HTTPS with
+/// 
+/// # Returns
+/// Unit once the vault was locked
+/// 
+/// # Errors
+/// Returns an error if the address isn't loopback, the vault can't be
+/// unlocked, the certificate can't be used, or the server can't start
+pub fn run(listen: SocketAddr, token_file: Option<PathBuf>, locked: bool, tls: Option<TlsFiles>) -> Result<()> {
+    if !listen.ip().is_loopback() {
+        return Err(PassManError::InvalidInput(format!(
+            "Refusing to listen on {}: only loopback addresses (127.0.0.1, ::1) are allowed",
+            listen
+        )));
+    }
+    
+    let mut passman = if locked {
+        PassMan::new(&crate::get_current_vault_name()?)?
+    } else {
+        crate::unlock_vault()?
+    };
+    let (server, scheme) = match tls {
+        Some(ref files) => (tls::start(listen, files)?, "https"),
+        None => (listen_http(listen)?, "http"),
+    };
+    
+    let mut bytes = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(bytes.as_mut());
+    let token = Zeroizing::new(URL_SAFE_NO_PAD.encode(bytes.as_ref()));
+    if let Some(ref path) = token_file {
+        write_private(path, &token)?;
+    }
+    
+    status!("{}", format!("✓ Serving '{}' on {}://{}", passman.vault_name(), scheme, listen).green().bold());
+    match idle_limit(&passman) {
+        Some(limit) => status!("Locks after {} min without requests. Press Ctrl+C to stop.", limit.as_secs() / 60),
+        None if locked => status!("The vault is locked; unlock it with POST /v1/unlock. Press Ctrl+C to stop."),
+        None => status!("Press Ctrl+C to stop."),
+    }
+    println!("{}", token.as_str());
+    
+    let mut last_request = Instant::now();
+    loop {
+        if idle_limit(&passman).is_some_and(|limit| last_request.elapsed() >= limit) {
+            status!("{}", "Auto-locked after inactivity.".yellow());
+            if !locked {
+                break;
+            }
+            passman.close_vault();
+        }
+        let Some(mut request) = server.recv_timeout(POLL_INTERVAL)? else {
+            continue;
+        };
+        
+        let mut lock = false;
+        let reply = match authorize(header(&request, "Host"), header(&request, "Authorization"), &token) {
+            Ok(()) => {
+                last_request = Instant::now();
+                handle(&mut passman, &mut request, &mut lock)
+            }
+            Err(reply) => reply,
+        };
+        respond(request, reply);
+        
+        if lock {
+            status!("{}", "Locked by API request.".yellow());
+            if !locked {
+                break;
+            }
+            passman.close_vault();
+        }
+    }
+    
+    passman.close_vault();
+    if let Some(path) = token_file {
+        let _ = fs::remove_file(path);
+    }
+    Ok(())
+}
+
+fn listen_http(listen: SocketAddr) -> Result<Server> {
+    Server::http(listen)
+        .map_err(|e| PassManError::StorageError(format!("Cannot listen on {}: {}", listen, e)))
+}
+
+/// How long the open vault may go without requests, or None if it is locked
+/// or never auto-locks
+fn idle_limit(passman: &PassMan) -> Option<Duration> {
+    let timeout = crate::vault_settings(passman).ok()?.auto_lock_timeout;
+    (timeout > 0).then(|| Duration::from_secs(u64::from(timeout) * 60))
+}
+
+/// Check the Host and Authorization headers of a request
+/// 
+/// A missing Host is refused too, so a page that got its own name to
+/// resolve to 127.0.0.1 can't get past the check by leaving it out.
+fn authorize(host: Option<&str>, authorization: Option<&str>, token: &str) -> std::result::Result<(), Reply> {
+    if !host.is_some_and(is_loopback_host) {
+        return Err(Reply::error(403, "Host must be a loopback address"));
+    }
+    
+    let presented = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if constant_time_eq(presented.as_bytes(), token.as_bytes()) {
+        Ok(())
+    } else {
+        Err(Reply::error(401, "Missing or invalid token"))
+    }
+}
+
+/// Route an authorized request
+fn handle(passman: &mut PassMan, request: &mut Request, lock: &mut bool) -> Reply {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    
+    let endpoint = (request.method().clone(), segments.as_slice());
+    let result = match endpoint {
+        (Method::Get, ["v1", "status"]) => Ok(Reply::ok(json!({
+            "vault": passman.vault_name(),
+            "locked": !passman.is_vault_open(),
+            "account_count": passman.is_vault_open().then(|| passman.get_all_accounts().len()),
+        }))),
+        (Method::Post, ["v1", "unlock"]) => request_body(request).and_then(|body| unlock(passman, &body)),
+        (Method::Post, ["v1", "generate"]) => request_body(request).and_then(|body| generate(&body)),
+        (_, ["v1", "lock"]) if !passman.is_vault_open() => Err(Reply::error(409, "The vault is already locked")),
+        (_, ["v1", ..]) if !passman.is_vault_open() => {
+            Err(Reply::error(423, "The vault is locked; unlock it with POST /v1/unlock"))
+        }
+        (Method::Get, ["v1", "accounts"]) => search(passman, query),
+        (Method::Get, ["v1", "accounts", id]) => get(passman, id),
+        (Method::Post, ["v1", "accounts"]) => request_body(request).and_then(|body| create(passman, &body)),
+        (Method::Get, ["v1", "canaries"]) => passman.canaries()
+            .map(|canaries| Reply::ok(json!({ "canaries": canaries })))
+            .map_err(Reply::from),
+        (Method::Get, ["v1", "audit"]) => audit(passman, query),
+        (Method::Post, ["v1", "lock"]) => {
+            *lock = true;
+            Ok(Reply::ok(json!({ "locked": true })))
+        }
+        (_, ["v1", "status" | "unlock" | "generate" | "accounts" | "canaries" | "audit" | "lock"] | ["v1", "accounts", _]) => {
+            Err(Reply::error(405, "Method not allowed"))
+        }
+        _ => Err(Reply::error(404, "No such endpoint")),
+    };
+    result.unwrap_or_else(|reply| reply)
+}
+
+/// `GET /v1/accounts`
+fn search(passman: &PassMan, query: &str) -> std::result::Result<Reply, Reply> {
+    let mut account_query = AccountQuery::default();
+    let mut url = None;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "q" => account_query.search = Some(percent_decode(value)),
+            "url" => url = Some(percent_decode(value)),
+            "limit" => {
+                let limit = value.parse().map_err(|_| Reply::error(400, "limit must be a number"))?;
+                account_query.limit = Some(limit);
+            }
+            _ => {}
+        }
+    }
+    
+    let matches = match url {
+        Some(url) => passman.get_accounts_for_url(&url)
+            .into_iter()
+            .filter(|account| account_query.matches(account))
+            .take(account_query.limit.unwrap_or(usize::MAX))
+            .collect(),
+        None => passman.query_accounts(&account_query),
+    };
+    let accounts: Vec<AccountSummary> = matches.into_iter().map(AccountSummary::from).collect();
+    Ok(Reply::ok(json!({ "accounts": accounts })))
+}
+
+/// `POST /v1/unlock`
+fn unlock(passman: &mut PassMan, body: &str) -> std::result::Result<Reply, Reply> {
+    if passman.is_vault_open() {
+        return Err(Reply::error(409, "The vault is already unlocked"));
+    }
+    let password = serde_json::from_str::<Unlock>(body)
+        .map(|input| Zeroizing::new(input.password))
+        .map_err(|_| Reply::error(400, "Expected { \"password\": \"...\" }"))?;
+    
+    let keyfile = if passman.requires_keyfile()? {
+        let path = CliConfig::load()?.keyfiles.remove(passman.vault_name())
+            .ok_or_else(|| Reply::error(400, "The vault needs a keyfile; set its path under [keyfiles] in config.toml"))?;
+        Some(fs::read(&path).map_err(|e| Reply::error(500, format!("Cannot read keyfile {}: {}", path.display(), e)))?)
+    } else {
+        None
+    };
+    passman.open_vault_with_keyfile(&password, keyfile.as_deref())?;
+    Ok(Reply::ok(json!({ "locked": false })))
+}
+
+/// `POST /v1/generate`
+fn generate(body: &str) -> std::result::Result<Reply, Reply> {
+    let input: Generate = if body.trim().is_empty() {
+        Generate::default()
+    } else {
+        serde_json::from_str(body).map_err(|e| Reply::error(400, format!("Invalid options: {}", e)))?
+    };
+    let defaults = CliConfig::load()?.generator;
+    let mut generator = PasswordGenerator::new();
+    
+    if input.passphrase {
+        let options = PassphraseOptions {
+            word_count: input.words.unwrap_or(defaults.words),
+            separator: input.separator.unwrap_or(defaults.separator),
+            capitalize: defaults.capitalize,
+            include_number: defaults.digits,
+            include_symbol: defaults.symbols,
+            ..Default::default()
+        };
+        let words = default_word_list();
+        let passphrase = Zeroizing::new(generator.generate_passphrase_with(&options, &words)?);
+        let entropy = PasswordGenerator::passphrase_entropy(&options, words.len());
+        return Ok(Reply::ok(json!({ "password": passphrase.as_str(), "entropy_bits": entropy })));
+    }
+    
+    // Classes that aren't given fall back to the config only if none are given
+    let classes = [input.uppercase, input.lowercase, input.numbers, input.special];
+    let given = classes.iter().any(Option::is_some);
+    let class = |value: Option<bool>, default: bool| if given { value.unwrap_or(false) } else { default };
+    let options = PasswordOptions {
+        length: input.length.unwrap_or(defaults.length),
+        include_uppercase: class(input.uppercase, defaults.uppercase),
+        include_lowercase: class(input.lowercase, defaults.lowercase),
+        include_numbers: class(input.numbers, defaults.numbers),
+        include_special: class(input.special, defaults.special),
+        exclude_similar: true,
+        exclude_ambiguous: false,
+        ..PasswordOptions::default()
+    };
+    let password = Zeroizing::new(generator.generate(&options)?);
+    let estimate = generator.estimate_strength(&password);
+    Ok(Reply::ok(json!({
+        "password": password.as_str(),
+        "strength": estimate.score,
+        "crack_time": estimate.crack_time,
+    })))
+}
+
+/// `GET /v1/audit`
+fn audit(passman: &PassMan, query: &str) -> std::result::Result<Reply, Reply> {
+    let mut options = AuditOptions::default();
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "stale_days" => options.stale_after_days = value.parse().map_err(|_| Reply::error(400, "stale_days must be a number"))?,
+            "expiring_days" => options.expiring_within_days = value.parse().map_err(|_| Reply::error(400, "expiring_days must be a number"))?,
+            _ => {}
+        }
+    }
+    let report = crate::audit::offline_report(passman, &options)?;
+    Ok(Reply::ok(json!(report)))
+}
+
+/// `GET /v1/accounts/<id>`
+fn get(passman: &PassMan, id: &str) -> std::result::Result<Reply, Reply> {
+    let id = id.parse().map_err(|_| Reply::error(400, "Invalid account ID"))?;
+    let account = passman.get_account(id)
+        .ok_or_else(|| Reply::error(404, format!("Account with ID {} not found", id)))?;
+    Ok(Reply::ok(json!(account)))
+}
+
+/// `POST /v1/accounts`
+fn create(passman: &mut PassMan, body: &str) -> std::result::Result<Reply, Reply> {
+    let input: NewAccount = serde_json::from_str(body)
+        .map_err(|e| Reply::error(400, format!("Invalid account: {}", e)))?;
+    let password = match input.password {
+        Some(password) => password,
+        None => {
+            let options = crate::vault_settings(passman)?.default_password_options.clone();
+            passman.generate_password(&options)?
+        }
+    };
+    
+    let mut account = Account::new(input.name, input.account_type.unwrap_or(AccountType::Other), password);
+    account.username = input.username;
+    account.url = input.url;
+    account.notes = input.notes;
+    account.tags = input.tags;
+    let id = passman.add_account_entry(account)?;
+    
+    let account = passman.get_account(id)
+        .ok_or_else(|| Reply::error(500, "Account vanished after saving"))?;
+    Ok(Reply { status: 201, body: json!(account) })
+}
+
+/// Read a request body, up to `MAX_BODY` bytes
+fn request_body(request: &mut Request) -> std::result::Result<Zeroizing<String>, Reply> {
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY) {
+        return Err(Reply::error(413, "Request body too large"));
+    }
+    let mut body = Zeroizing::new(String::new());
+    request.as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body)
+        .map_err(|_| Reply::error(400, "Request body must be UTF-8"))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(Reply::error(413, "Request body too large"));
+    }
+    Ok(body)
+}
+
+/// Send a reply, ignoring clients that went away
+fn respond(request: Request, reply: Reply) {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    let body = Zeroizing::new(reply.body.to_string());
+    let response = Response::from_string(body.as_str())
+        .with_status_code(reply.status)
+        .with_header(content_type);
+    let _ = request.respond(response);
+}
+
+/// Get a request header's value
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request.headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Check whether a Host header names a loopback address
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Compare secrets without an early exit on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Decode a `application/x-www-form-urlencoded` query value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Write a file readable only by the current user
+/// 
+/// The contents go to a new temporary file, created with owner-only
+/// permissions, that then replaces `path`; an existing file's
+/// permissions are never reused.
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path.file_name()
+        .ok_or_else(|| PassManError::InvalidInput(format!("{} is not a file name", path.display())))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    
+    // A leftover from an interrupted run; removing it also removes a symlink rather than following it
+    let _ = fs::remove_file(&temp_path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temp_path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to create token file {}: {}", temp_path.display(), e)))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())?;
+    drop(file);
+    fs::rename(&temp_path, path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to write token file {}: {}", path.display(), e)))
+}
+
+/// TLS in front of the HTTP server
+/// 
+/// tiny_http can't use a TLS library of our choosing, so TLS connections
+/// are accepted here and each one is relayed, decrypted, to the HTTP
+/// server. That server listens on a Unix socket in the user's private
+/// runtime directory (see `socket`), not on a TCP port, so other users'
+/// processes can't reach it without going through TLS.
+#[cfg(all(feature = "tls", unix))]
+mod tls {
+    use super::TlsFiles;
+    use passman_backend::{PassManError, Result};
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+    use rustls::{ServerConfig, ServerConnection};
+    use std::io::{self, Read, Write};
+    use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::thread;
+    use tiny_http::Server;
+    
+    /// Size of the buffers data is relayed through
+    const BUFFER_SIZE: usize = 16 * 1024;
+    /// Most TLS connections relayed at once; further ones are closed right away
+    const MAX_CONNECTIONS: usize = 32;
+    
+    /// A relayed connection, counted against `MAX_CONNECTIONS` until dropped
+    struct Slot(Arc<AtomicUsize>);
+    
+    impl Slot {
+        /// Count a new connection, or None if `MAX_CONNECTIONS` are open
+        fn take(active: &Arc<AtomicUsize>) -> Option<Self> {
+            active
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| (count < MAX_CONNECTIONS).then_some(count + 1))
+                .ok()
+                .map(|_| Self(active.clone()))
+        }
+    }
+    
+    impl Drop for Slot {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+    
+    /// Accept TLS connections on `listen` and start the HTTP server behind them
+    /// 
+    /// # Returns
+    /// The HTTP server, to receive requests from as usual
+    /// 
+    /// # Errors
+    /// Returns an error if the certificate or key can't be used, or an
+    /// address can't be bound
+    pub(super) fn start(listen: SocketAddr, files: &TlsFiles) -> Result<Server> {
+        let config = load_config(files)?;
+        let listener = TcpListener::bind(listen)
+            .map_err(|e| PassManError::StorageError(format!("Cannot listen on {}: {}", listen, e)))?;
+        
+        // Named after the port, so a socket left by a killed server is replaced
+        let backend = crate::socket::runtime_socket_path(&format!("serve-{}.sock", listen.port()))?;
+        let inner = crate::socket::bind(&backend)?;
+        // tiny_http blocks in accept and gives up on errors such as WouldBlock
+        inner.set_nonblocking(false)?;
+        let server = Server::from_listener(inner, None)
+            .map_err(|e| PassManError::StorageError(format!("Cannot listen on {}: {}", backend.display(), e)))?;
+        
+        let active = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                // Dropping the client closes it
+                let Some(slot) = Slot::take(&active) else {
+                    continue;
+                };
+                let config = config.clone();
+                let backend = backend.clone();
+                thread::spawn(move || {
+                    let _ = relay(client, &backend, config);
+                    drop(slot);
+                });
+            }
+        });
+        Ok(server)
+    }
+    
+    /// Read the certificate chain and private key
+    fn load_config(files: &TlsFiles) -> Result<Arc<ServerConfig>> {
+        let certs = CertificateDer::pem_file_iter(&files.cert)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .map_err(|e| PassManError::InvalidInput(format!("Can't read the certificate {}: {}", files.cert.display(), e)))?;
+        if certs.is_empty() {
+            return Err(PassManError::InvalidInput(format!("{} has no PEM certificate", files.cert.display())));
+        }
+        let key = PrivateKeyDer::from_pem_file(&files.key)
+            .map_err(|e| PassManError::InvalidInput(format!("Can't read the private key {}: {}", files.key.display(), e)))?;
+        
+        let config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| PassManError::InvalidInput(format!("Can't serve TLS with this certificate and key: {}", e)))?;
+        Ok(Arc::new(config))
+    }
+    
+    /// Relay one TLS connection to the HTTP server until either side closes it
+    fn relay(client: TcpStream, backend: &Path, config: Arc<ServerConfig>) -> io::Result<()> {
+        let connection = Arc::new(Mutex::new(ServerConnection::new(config).map_err(io::Error::other)?));
+        let upstream = UnixStream::connect(backend)?;
+        
+        // Responses: plaintext from the HTTP server, encrypted for the client
+        let responses = {
+            let connection = connection.clone();
+            let mut upstream = upstream.try_clone()?;
+            let mut client = client.try_clone()?;
+            thread::spawn(move || -> io::Result<()> {
+                let mut buffer = [0u8; BUFFER_SIZE];
+                loop {
+                    let read = upstream.read(&mut buffer)?;
+                    let mut connection = lock(&connection);
+                    if read == 0 {
+                        connection.send_close_notify();
+                    } else {
+                        connection.writer().write_all(&buffer[..read])?;
+                    }
+                    while connection.wants_write() {
+                        connection.write_tls(&mut client)?;
+                    }
+                    if read == 0 {
+                        let _ = client.shutdown(Shutdown::Write);
+                        return Ok(());
+                    }
+                }
+            })
+        };
+        
+        // Requests: ciphertext from the client, decrypted for the HTTP server
+        let result = forward_requests(&connection, &client, &upstream);
+        let _ = upstream.shutdown(Shutdown::Write);
+        if result.is_err() {
+            let _ = client.shutdown(Shutdown::Both);
+            let _ = upstream.shutdown(Shutdown::Both);
+        }
+        let _ = responses.join();
+        result
+    }
+    
+    fn forward_requests(connection: &Mutex<ServerConnection>, mut client: &TcpStream, mut upstream: &UnixStream) -> io::Result<()> {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        loop {
+            let read = client.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            
+            let mut plaintext = Vec::new();
+            {
+                let mut connection = lock(connection);
+                let mut received = &buffer[..read];
+                while !received.is_empty() {
+                    connection.read_tls(&mut received)?;
+                    let state = match connection.process_new_packets() {
+                        Ok(state) => state,
+                        Err(e) => {
+                            // Tell the client why before giving up
+                            let _ = connection.write_tls(&mut client);
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+                        }
+                    };
+                    let start = plaintext.len();
+                    plaintext.resize(start + state.plaintext_bytes_to_read(), 0);
+                    connection.reader().read_exact(&mut plaintext[start..])?;
+                }
+                while connection.wants_write() {
+                    connection.write_tls(&mut client)?;
+                }
+            }
+            // Not under the lock, so responses keep flowing while the server reads
+            upstream.write_all(&plaintext)?;
+        }
+    }
+    
+    fn lock(connection: &Mutex<ServerConnection>) -> MutexGuard<'_, ServerConnection> {
+        connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+        use std::path::Path;
+        
+        fn testdata(name: &str) -> std::path::PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/testdata").join(name)
+        }
+        
+        #[test]
+        fn test_tls_relay() {
+            let files = TlsFiles { cert: testdata("cert.pem"), key: testdata("key.pem") };
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let listen = listener.local_addr().unwrap();
+            drop(listener);
+            let server = start(listen, &files).unwrap();
+            // The decrypted side isn't on a TCP port
+            assert!(server.server_addr().to_ip().is_none());
+            let answer = thread::spawn(move || {
+                let request = server.recv().unwrap();
+                let url = request.url().to_string();
+                request.respond(tiny_http::Response::from_string("hello over tls")).unwrap();
+                url
+            });
+            
+            let mut roots = RootCertStore::empty();
+            for cert in CertificateDer::pem_file_iter(testdata("ca.pem")).unwrap() {
+                roots.add(cert.unwrap()).unwrap();
+            }
+            let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let connection = ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap()).unwrap();
+            let mut stream = StreamOwned::new(connection, TcpStream::connect(listen).unwrap());
+            stream.write_all(b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            
+            assert_eq!(answer.join().unwrap(), "/v1/status");
+            assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+            assert!(response.ends_with("hello over tls"), "{}", response);
+            
+            // Plain HTTP on the TLS port gets nowhere
+            let mut plain = TcpStream::connect(listen).unwrap();
+            plain.write_all(b"GET /v1/status HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut reply = Vec::new();
+            let _ = plain.read_to_end(&mut reply);
+            assert!(!reply.starts_with(b"HTTP"));
+        }
+        
+        #[test]
+        fn test_connection_limit() {
+            let files = TlsFiles { cert: testdata("cert.pem"), key: testdata("key.pem") };
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let listen = listener.local_addr().unwrap();
+            drop(listener);
+            let _server = start(listen, &files).unwrap();
+            
+            // Idle clients hold every slot, so the next one is closed at once
+            let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS).map(|_| TcpStream::connect(listen).unwrap()).collect();
+            let mut extra = TcpStream::connect(listen).unwrap();
+            extra.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+            assert_eq!(extra.read(&mut [0u8; 16]).unwrap(), 0);
+            
+            // Closing one frees its slot
+            drop(idle);
+            let started = std::time::Instant::now();
+            loop {
+                let mut client = TcpStream::connect(listen).unwrap();
+                client.set_read_timeout(Some(std::time::Duration::from_millis(200))).unwrap();
+                match client.read(&mut [0u8; 16]) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    _ => assert!(started.elapsed().as_secs() < 5, "no slot was freed"),
+                }
+            }
+        }
+        
+        #[test]
+        fn test_load_config() {
+            let missing = TlsFiles { cert: testdata("missing.pem"), key: testdata("key.pem") };
+            assert!(load_config(&missing).is_err());
+            let swapped = TlsFiles { cert: testdata("key.pem"), key: testdata("cert.pem") };
+            assert!(load_config(&swapped).is_err());
+            assert!(load_config(&TlsFiles { cert: testdata("cert.pem"), key: testdata("key.pem") }).is_ok());
+        }
+    }
+}
+
+#[cfg(not(all(feature = "tls", unix)))]
+mod tls {
+    use super::TlsFiles;
+    use passman_backend::{PassManError, Result};
+    use std::net::SocketAddr;
+    use tiny_http::Server;
+    
+    pub(super) fn start(_listen: SocketAddr, _files: &TlsFiles) -> Result<Server> {
+        let reason = if cfg!(feature = "tls") {
+            // The relay needs a socket only the user can reach; see the Unix `tls` module
+            "HTTPS for passman serve needs Unix domain sockets"
+        } else {
+            "This passman was built without TLS support (build it with --features tls)"
+        };
+        Err(PassManError::InvalidInput(reason.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_is_loopback_host() {
+        for host in ["localhost", "LOCALHOST:7656", "127.0.0.1", "127.0.0.1:7656", "127.1.2.3:80", "[::1]", "[::1]:7656"] {
+            assert!(is_loopback_host(host), "{}", host);
+        }
+        for host in ["", "example.com", "localhost.example.com:7656", "10.0.0.1:7656", "[::2]:7656", "127.0.0.1.nip.io"] {
+            assert!(!is_loopback_host(host), "{}", host);
+        }
+    }
+    
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+        assert!(!constant_time_eq(b"", b"token"));
+    }
+    
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("git+hub"), "git hub");
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode("%FF"), "\u{FFFD}");
+    }
+    
+    #[test]
+    fn test_authorize() {
+        let token = "secret-token";
+        assert!(authorize(Some("127.0.0.1:7656"), Some("Bearer secret-token"), token).is_ok());
+        assert!(authorize(Some("localhost"), Some("Bearer secret-token"), token).is_ok());
+        
+        let status = |host, authorization| authorize(host, authorization, token).unwrap_err().status;
+        assert_eq!(status(None, Some("Bearer secret-token")), 403);
+        assert_eq!(status(Some("evil.example.com"), Some("Bearer secret-token")), 403);
+        assert_eq!(status(Some("127.0.0.1:7656"), None), 401);
+        assert_eq!(status(Some("127.0.0.1:7656"), Some("Bearer wrong-token")), 401);
+        assert_eq!(status(Some("127.0.0.1:7656"), Some("secret-token")), 401);
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_write_private() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = std::env::temp_dir().join(format!("passman-test-serve-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("token");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        
+        write_private(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//! # Session Cache
+//! 
+//! This module caches the derived vault key so consecutive commands don't
+//! ask for the master password until the vault's auto-lock timeout
+//! expires. The key is held by the session agent (`passman agent`) when
+//! it runs, and otherwise stored in the OS keyring (kernel keyring on
+//! Linux, Keychain on macOS, Credential Manager on Windows).
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use passman_backend::{PassManError, Result, crypto::SecureKey};
+use zeroize::Zeroizing;
+use crate::agent;
+
+/// Keyring service name under which sessions are stored
+const SERVICE: &str = "passman";
+/// Format tag of the stored session record
+const RECORD_VERSION: &str = "v1";
+
+/// A cached, unexpired vault session
+pub struct CachedSession {
+    /// Derived vault key
+    pub key: SecureKey,
+    
+    /// When the session stops being valid
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Cache a vault key for the given number of minutes
+/// 
+/// # Arguments
+/// * `vault_name` - Vault the key belongs to
+/// * `key` - Derived vault key
+/// * `ttl_minutes` - How long the session stays valid
+/// 
+/// # Returns
+/// When the cached session expires
+/// 
+/// # Errors
+/// Returns an error if the agent refuses the key or the keyring is unavailable
+pub fn store(vault_name: &str, key: &SecureKey, ttl_minutes: u32) -> Result<DateTime<Utc>> {
+    if let Some(expires_at) = agent::put(vault_name, key, ttl_minutes)? {
+        return Ok(expires_at);
+    }
+    
+    let expires_at = Utc::now() + Duration::minutes(ttl_minutes as i64);
+    let record = Zeroizing::new(format!(
+        "{}:{}:{}",
+        RECORD_VERSION,
+        expires_at.timestamp(),
+        STANDARD.encode(key.as_bytes()),
+    ));
+    
+    entry(vault_name)?
+        .set_password(&record)
+        .map_err(keyring_error)?;
+    
+    Ok(expires_at)
+}
+
+/// Load the cached session for a vault
+/// 
+/// Expired or unreadable sessions are removed from the keyring.
+/// 
+/// # Arguments
+/// * `vault_name` - Vault to look up
+/// 
+/// # Returns
+/// The cached session, or None if there is no valid session
+pub fn load(vault_name: &str) -> Option<CachedSession> {
+    if let Some((key, expires_at)) = agent::get(vault_name) {
+        return Some(CachedSession { key, expires_at });
+    }
+    
+    let entry = entry(vault_name).ok()?;
+    let record = Zeroizing::new(entry.get_password().ok()?);
+    
+    match parse_record(&record) {
+        Some(session) if session.expires_at > Utc::now() => Some(session),
+        _ => {
+            let _ = entry.delete_credential();
+            None
+        }
+    }
+}
+
+/// Remove the cached session for a vault, from the agent and the keyring
+/// 
+/// # Arguments
+/// * `vault_name` - Vault to lock
+/// 
+/// # Returns
+/// True if a session was removed
+/// 
+/// # Errors
+/// Returns an error if the keyring is unavailable
+pub fn clear(vault_name: &str) -> Result<bool> {
+    let forgotten = agent::forget(vault_name)?.unwrap_or(false);
+    match entry(vault_name)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(forgotten),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// Parse a stored `v1:<expiry>:<base64 key>` record
+fn parse_record(record: &str) -> Option<CachedSession> {
+    let mut parts = record.splitn(3, ':');
+    if parts.next()? != RECORD_VERSION {
+        return None;
+    }
+    
+    let expires_at = Utc.timestamp_opt(parts.next()?.parse().ok()?, 0).single()?;
+    let bytes = Zeroizing::new(STANDARD.decode(parts.next()?).ok()?);
+    let key_bytes: [u8; 32] = bytes.as_slice().try_into().ok()?;
+    
+    Some(CachedSession {
+        key: SecureKey::new(key_bytes),
+        expires_at,
+    })
+}
+
+/// Keyring entry holding a vault's session
+fn entry(vault_name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, vault_name).map_err(keyring_error)
+}
+
+/// Convert a keyring error into a PassMan error
+fn keyring_error(e: keyring::Error) -> PassManError {
+    PassManError::StorageError(format!("Keyring unavailable: {}", e))
+}
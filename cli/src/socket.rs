@@ -0,0 +1,116 @@
+//! # Private Sockets
+//! 
+//! Helpers shared by the two agents that listen on Unix sockets, the
+//! session agent (see `agent`) and the SSH agent (see `ssh`): a socket
+//! directory only the user can enter, binding with owner-only
+//! permissions, and refusing peers that run as another user.
+
+use passman_backend::{PassManError, Result};
+use std::fs;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// Path of a socket in a directory private to the user
+/// 
+/// The directory is `passman/` in the runtime directory, or
+/// `passman-<uid>/` in the shared temporary directory when there is no
+/// runtime directory. One that already exists is used only if the user
+/// owns it and nobody else can enter it; otherwise another local user
+/// could have created it to listen in place of the agent.
+/// 
+/// # Errors
+/// Returns an error if the directory can't be created or isn't private
+pub fn runtime_socket_path(file_name: &str) -> Result<PathBuf> {
+    let uid = current_uid();
+    let dir = match dirs::runtime_dir() {
+        Some(runtime) => runtime.join("passman"),
+        None => std::env::temp_dir().join(format!("passman-{}", uid)),
+    };
+    match fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+    
+    // symlink_metadata, so a link to someone else's directory is refused too
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o777 != 0o700 {
+        return Err(PassManError::StorageError(format!(
+            "{} has to be a directory owned by you with mode 0700; remove it and try again",
+            dir.display(),
+        )));
+    }
+    Ok(dir.join(file_name))
+}
+
+/// Check that the other end of a socket runs as the same user
+/// 
+/// # Errors
+/// Returns an error if the peer's user can't be read or is another user
+pub fn check_peer(stream: &UnixStream) -> Result<()> {
+    let uid = peer_uid(stream)?;
+    if uid != current_uid() {
+        return Err(PassManError::AuthenticationFailed(format!("the other end of the socket runs as user {}", uid)));
+    }
+    Ok(())
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and can't fail
+    unsafe { libc::getuid() }
+}
+
+/// User ID of the process at the other end of a socket (SO_PEERCRED)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: the pointer and length describe a writable ucred, which is
+    // what SO_PEERCRED fills in
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut credentials as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(credentials.uid)
+}
+
+/// User ID of the process at the other end of a socket (getpeereid)
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: getpeereid only writes the two IDs passed by reference
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// Bind the socket, replacing a stale one, readable only by the user
+pub fn bind(socket: &Path) -> Result<UnixListener> {
+    if let Ok(metadata) = fs::symlink_metadata(socket) {
+        if !metadata.file_type().is_socket() {
+            return Err(PassManError::InvalidInput(format!("{} exists and is not a socket", socket.display())));
+        }
+        if UnixStream::connect(socket).is_ok() {
+            return Err(PassManError::InvalidInput(format!("Another agent is already listening on {}", socket.display())));
+        }
+        fs::remove_file(socket)?;
+    }
+    
+    let listener = UnixListener::bind(socket)
+        .map_err(|e| PassManError::StorageError(format!("Cannot listen on {}: {}", socket.display(), e)))?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
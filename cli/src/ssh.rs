@@ -0,0 +1,460 @@
+//! # SSH Agent
+//! 
+//! Accounts can hold an SSH key (`passman add <name> --ssh-key <file>`).
+//! `passman ssh-agent` serves those keys to `ssh` and other agent clients
+//! over a Unix socket, printing the `SSH_AUTH_SOCK` line to use. The
+//! socket lives in a directory only the user can enter, and clients
+//! running as other users are refused.
+//! 
+//! The vault stays unlocked while the agent runs. Each signature is
+//! approved according to the vault's `ssh_agent_confirmation` setting,
+//! asking in the agent's terminal, so run it in the foreground of a
+//! terminal of its own. When the vault's auto-lock timeout passes without
+//! a signature, or the screen locks or the system goes to sleep (unless
+//! `keep_unlocked_on_screen_lock` is set), the vault is locked and the
+//! agent exits.
+//! 
+//! `passman ssh add <name>` loads a stored key into an agent that is
+//! already running instead, such as OpenSSH's `ssh-agent` at
+//! `SSH_AUTH_SOCK`, optionally for a limited time:
+//! 
+//! ```bash
+//! passman add "Git server" --account-type ssh-key --ssh-key ~/.ssh/id_ed25519
+//! passman ssh add "Git server" --lifetime 3600 --confirm
+//! passman ssh remove "Git server"
+//! ```
+
+use crate::{credentials, status};
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{PassManError, Result, SshKey, ssh};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Commands for a running ssh-agent
+#[derive(Subcommand)]
+pub enum SshCommand {
+    /// Load an account's SSH key into the agent at SSH_AUTH_SOCK
+    Add {
+        /// Account name or ID
+        name: String,
+        
+        /// Seconds after which the agent forgets the key
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+        lifetime: Option<u32>,
+        
+        /// Have the agent ask before each use of the key
+        #[arg(long)]
+        confirm: bool,
+    },
+    
+    /// Remove an account's SSH key from the agent at SSH_AUTH_SOCK
+    Remove {
+        /// Account name or ID
+        name: String,
+    },
+}
+
+/// Run an `ssh` subcommand
+/// 
+/// # Errors
+/// Returns an error if the vault can't be unlocked, the account holds no
+/// SSH key, or the agent can't be reached or refuses the request
+pub fn run(command: SshCommand) -> Result<()> {
+    match command {
+        SshCommand::Add { name, lifetime, confirm } => client::add(&name, lifetime, confirm),
+        SshCommand::Remove { name } => client::remove(&name),
+    }
+}
+
+/// Read an OpenSSH private key file for storage in an account
+/// 
+/// Asks for the key's passphrase if it has one; the key is stored
+/// without it, protected by the vault's encryption instead.
+/// 
+/// # Errors
+/// Returns an error if the file can't be read or holds no usable key
+pub fn read_key_file(path: &Path) -> Result<SshKey> {
+    let pem = zeroize::Zeroizing::new(std::fs::read_to_string(path)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read SSH key {}: {}", path.display(), e)))?);
+    
+    let passphrase = if ssh::is_encrypted(&pem)? {
+        Some(zeroize::Zeroizing::new(rpassword::prompt_password("Enter SSH key passphrase: ")?))
+    } else {
+        None
+    };
+    let key = ssh::import_private_key(&pem, passphrase.as_deref().map(String::as_str))?;
+    status!("SSH key: {}", key.public_key.trim());
+    Ok(key)
+}
+
+/// Ask for the path of a private key file and read it
+/// 
+/// # Errors
+/// Returns an error if no path was given or the file holds no usable key
+pub fn prompt_key_file() -> Result<SshKey> {
+    eprint!("Private key file: ");
+    io::stderr().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let path = input.trim();
+    if path.is_empty() {
+        return Err(PassManError::InvalidInput("An SSH key account needs a private key file".to_string()));
+    }
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    };
+    read_key_file(&path)
+}
+
+#[cfg(unix)]
+pub use agent::run_agent;
+
+/// Run the SSH agent (needs Unix domain sockets)
+#[cfg(not(unix))]
+pub fn run_agent(_socket: Option<PathBuf>) -> Result<()> {
+    Err(PassManError::InvalidInput("passman ssh-agent needs Unix domain sockets".to_string()))
+}
+
+#[cfg(not(unix))]
+mod client {
+    use super::*;
+    
+    pub fn add(_name: &str, _lifetime: Option<u32>, _confirm: bool) -> Result<()> {
+        Err(PassManError::InvalidInput("passman ssh add needs Unix domain sockets".to_string()))
+    }
+    
+    pub fn remove(_name: &str) -> Result<()> {
+        Err(PassManError::InvalidInput("passman ssh remove needs Unix domain sockets".to_string()))
+    }
+}
+
+#[cfg(unix)]
+mod client {
+    use super::*;
+    use passman_backend::ssh::SshIdentity;
+    use std::os::unix::net::UnixStream;
+    
+    /// Load an account's key into the running agent
+    pub fn add(name: &str, lifetime: Option<u32>, confirm: bool) -> Result<()> {
+        let mut passman = crate::unlock_vault()?;
+        let account = crate::find_account(&passman, name)?;
+        let id = account.id;
+        let identity = identity(account)?;
+        crate::confirm_reveal(&mut passman)?;
+        
+        let reply = request(&identity.add_request(lifetime, confirm)?)?;
+        if !ssh::is_success(&reply) {
+            return Err(PassManError::InvalidInput("The SSH agent refused the key".to_string()));
+        }
+        passman.record_access(id)?;
+        
+        status!("{}", format!("✓ Added {} ({}) to the SSH agent", identity.fingerprint(), identity.name()).green());
+        if let Some(seconds) = lifetime {
+            status!("  The agent forgets it after {} seconds.", seconds);
+        }
+        Ok(())
+    }
+    
+    /// Take an account's key out of the running agent
+    pub fn remove(name: &str) -> Result<()> {
+        let passman = crate::unlock_vault()?;
+        let identity = identity(crate::find_account(&passman, name)?)?;
+        
+        let reply = request(&identity.remove_request())?;
+        if !ssh::is_success(&reply) {
+            return Err(PassManError::AccountNotFound(format!("The SSH agent doesn't hold the key of '{}'", identity.name())));
+        }
+        status!("{}", format!("✓ Removed {} ({}) from the SSH agent", identity.fingerprint(), identity.name()).green());
+        Ok(())
+    }
+    
+    fn identity(account: &passman_backend::models::Account) -> Result<SshIdentity> {
+        SshIdentity::from_account(account)?
+            .ok_or_else(|| PassManError::InvalidInput(format!("Account '{}' holds no SSH key", account.name)))
+    }
+    
+    /// Send one message to the agent at SSH_AUTH_SOCK and read its reply
+    fn request(message: &[u8]) -> Result<Vec<u8>> {
+        let socket = std::env::var_os("SSH_AUTH_SOCK")
+            .ok_or_else(|| PassManError::InvalidInput("SSH_AUTH_SOCK is not set; start an agent with `eval $(ssh-agent)`".to_string()))?;
+        let mut stream = UnixStream::connect(&socket).map_err(|e| PassManError::InvalidInput(format!(
+            "Cannot reach the SSH agent at {}: {}", Path::new(&socket).display(), e
+        )))?;
+        
+        stream.write_all(message)?;
+        ssh::read_message(&mut stream)?
+            .ok_or_else(|| PassManError::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "the SSH agent closed the connection")))
+    }
+}
+
+#[cfg(unix)]
+mod agent {
+    use super::*;
+    use passman_backend::{
+        PassMan, PassManConfig, SshConfirmation,
+        screenlock::{self, LockEvent},
+        ssh::{AgentRequest, AgentResponse, SshIdentity},
+    };
+    use crate::socket::{bind, check_peer, runtime_socket_path};
+    use std::fs;
+    use std::io::{self, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+    use std::time::{Duration, Instant};
+    use zeroize::Zeroizing;
+    
+    /// How often the listener checks for clients and the idle timer
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+    
+    /// The unlocked vault and the keys it serves
+    struct Agent {
+        passman: PassMan,
+        identities: Vec<SshIdentity>,
+        confirmation: SshConfirmation,
+        keyfile: Option<Zeroizing<Vec<u8>>>,
+        last_signature: Instant,
+    }
+    
+    /// Run the SSH agent
+    /// 
+    /// # Arguments
+    /// * `socket` - Socket path; defaults to `passman/ssh-agent.sock` in
+    ///   the runtime directory
+    /// 
+    /// # Returns
+    /// Unit once the vault was locked
+    /// 
+    /// # Errors
+    /// Returns an error if the vault can't be unlocked or the socket can't
+    /// be created
+    pub fn run_agent(socket: Option<PathBuf>) -> Result<()> {
+        let socket = match socket {
+            Some(socket) => socket,
+            None => runtime_socket_path("ssh-agent.sock")?,
+        };
+        
+        let passman = crate::unlock_vault()?;
+        let settings = crate::vault_settings(&passman)?;
+        let confirmation = settings.ssh_agent_confirmation;
+        let timeout = settings.auto_lock_timeout;
+        let keyfile = match confirmation {
+            SshConfirmation::Reauthenticate if passman.requires_keyfile()? => {
+                Some(Zeroizing::new(crate::read_keyfile(passman.vault_name())?))
+            }
+            _ => None,
+        };
+        
+        let mut agent = Agent {
+            passman,
+            identities: Vec::new(),
+            confirmation,
+            keyfile,
+            last_signature: Instant::now(),
+        };
+        agent.load_identities();
+        if agent.identities.is_empty() {
+            status!("{}", "No accounts hold an SSH key yet; add one with `passman add <name> --ssh-key <file>`.".yellow());
+        }
+        
+        let listener = bind(&socket)?;
+        status!("{}", format!("✓ SSH agent for '{}' listening on {}", agent.passman.vault_name(), socket.display()).green().bold());
+        for identity in &agent.identities {
+            status!("  {} {}", identity.fingerprint(), identity.name());
+        }
+        if timeout > 0 {
+            status!("Locks after {} min without signatures. Press Ctrl+C to stop.", timeout);
+        } else {
+            status!("Press Ctrl+C to stop.");
+        }
+        println!("SSH_AUTH_SOCK={}; export SSH_AUTH_SOCK;", socket.display());
+        
+        let idle_limit = (timeout > 0).then(|| Duration::from_secs(u64::from(timeout) * 60));
+        let agent = Arc::new(Mutex::new(agent));
+        let os_lock = watch_screen_lock()?;
+        let result = serve(&listener, &agent, idle_limit, &os_lock);
+        
+        let mut agent = lock(&agent);
+        agent.identities.clear();
+        agent.passman.close_vault();
+        let _ = fs::remove_file(&socket);
+        result
+    }
+    
+    /// Start watching for screen locks, unless the config says not to
+    /// 
+    /// # Returns
+    /// Set to the first screen lock or suspend seen
+    fn watch_screen_lock() -> Result<Arc<OnceLock<LockEvent>>> {
+        let os_lock = Arc::new(OnceLock::new());
+        if PassManConfig::load()?.keep_unlocked_on_screen_lock {
+            return Ok(os_lock);
+        }
+        let seen = Arc::clone(&os_lock);
+        if let Err(e) = screenlock::watch(move |event| {
+            let _ = seen.set(event);
+        }) {
+            status!("{}", format!("{}; the agent stays unlocked when the screen locks.", e).yellow());
+        }
+        Ok(os_lock)
+    }
+    
+    /// Accept clients until the idle limit passes or the screen locks
+    fn serve(
+        listener: &UnixListener,
+        agent: &Arc<Mutex<Agent>>,
+        idle_limit: Option<Duration>,
+        os_lock: &OnceLock<LockEvent>,
+    ) -> Result<()> {
+        loop {
+            if idle_limit.is_some_and(|limit| lock(agent).last_signature.elapsed() >= limit) {
+                status!("{}", "Auto-locked after inactivity.".yellow());
+                return Ok(());
+            }
+            match os_lock.get() {
+                Some(LockEvent::ScreenLocked) => {
+                    status!("{}", "Locked because the screen was locked.".yellow());
+                    return Ok(());
+                }
+                Some(LockEvent::Suspending) => {
+                    status!("{}", "Locked because the system is going to sleep.".yellow());
+                    return Ok(());
+                }
+                None => {}
+            }
+            
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    // Only the user's own processes may use the keys
+                    if let Err(e) = check_peer(&stream) {
+                        status!("{}", format!("Refused a client: {}", e).yellow());
+                        continue;
+                    }
+                    stream.set_nonblocking(false)?;
+                    let agent = Arc::clone(agent);
+                    std::thread::spawn(move || serve_client(&agent, stream));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    
+    /// Answer one client's requests until it disconnects
+    fn serve_client(agent: &Mutex<Agent>, mut stream: UnixStream) {
+        while let Ok(Some(message)) = ssh::read_message(&mut stream) {
+            let response = match AgentRequest::decode(&message) {
+                Ok(AgentRequest::Sign { key_blob, data, flags }) => sign(agent, &key_blob, &data, flags),
+                Ok(request) => lock(agent).handle(request),
+                Err(_) => AgentResponse::Failure,
+            };
+            if stream.write_all(&response.encode()).is_err() {
+                break;
+            }
+        }
+    }
+    
+    /// Sign for a client once the user approves
+    /// 
+    /// The agent isn't locked while the user is asked, so other clients,
+    /// the idle timer, and the screen lock watcher carry on meanwhile.
+    fn sign(agent: &Mutex<Agent>, key_blob: &[u8], data: &[u8], flags: u32) -> AgentResponse {
+        // One question at a time on the terminal
+        static PROMPT: Mutex<()> = Mutex::new(());
+        
+        let (name, confirmation) = {
+            let agent = lock(agent);
+            match agent.identity(key_blob) {
+                Some(identity) => (identity.name().to_string(), agent.confirmation),
+                None => return AgentResponse::Failure,
+            }
+        };
+        
+        let action = match ssh::userauth_user(data) {
+            Some(user) => format!("log in as '{}'", user),
+            None => "sign data".to_string(),
+        };
+        let approved = match confirmation {
+            SshConfirmation::Never => true,
+            SshConfirmation::Confirm => {
+                let _prompt = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+                crate::prompt_confirm(&format!("Allow a client to {} with SSH key '{}'?", action, name))
+                    .unwrap_or(false)
+            }
+            SshConfirmation::Reauthenticate => {
+                let _prompt = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+                status!("A client wants to {} with SSH key '{}'.", action, name);
+                // A password given with --password-stdin isn't a fresh confirmation
+                credentials::is_interactive() && crate::prompt_master_password().is_ok_and(|password| {
+                    let password = Zeroizing::new(password);
+                    let agent = &mut *lock(agent);
+                    agent.passman.verify_master_password(&password, agent.keyfile.as_deref().map(Vec::as_slice)).is_ok()
+                })
+            }
+        };
+        if !approved {
+            status!("{} Refused to sign with '{}'", "✗".red(), name);
+            return AgentResponse::Failure;
+        }
+        
+        // The vault may have locked or reloaded while the user was asked
+        let mut agent = lock(agent);
+        let Some(signed) = agent.identity(key_blob).map(|identity| identity.sign(data, flags)) else {
+            status!("{} '{}' is no longer loaded", "✗".red(), name);
+            return AgentResponse::Failure;
+        };
+        match signed {
+            Ok(signature) => {
+                agent.last_signature = Instant::now();
+                status!("{} Signed with '{}'", "✓".green(), name);
+                AgentResponse::Signature(signature)
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                AgentResponse::Failure
+            }
+        }
+    }
+    
+    impl Agent {
+        fn handle(&mut self, request: AgentRequest) -> AgentResponse {
+            match request {
+                AgentRequest::RequestIdentities => {
+                    // Pick up keys added since the agent started
+                    if self.passman.reload_vault().unwrap_or(false) {
+                        self.load_identities();
+                    }
+                    AgentResponse::Identities(self.identities.iter()
+                        .map(|identity| (identity.public_key_blob().to_vec(), identity.name().to_string()))
+                        .collect())
+                }
+                AgentRequest::Sign { .. } | AgentRequest::Unsupported(_) => AgentResponse::Failure,
+            }
+        }
+        
+        fn identity(&self, key_blob: &[u8]) -> Option<&SshIdentity> {
+            self.identities.iter().find(|identity| identity.public_key_blob() == key_blob)
+        }
+        
+        fn load_identities(&mut self) {
+            self.identities = self.passman.get_all_accounts()
+                .into_iter()
+                .filter(|account| !account.archived)
+                .filter_map(|account| match SshIdentity::from_account(account) {
+                    Ok(identity) => identity,
+                    Err(e) => {
+                        eprintln!("{} Skipping the SSH key of '{}': {}", "Warning:".yellow().bold(), account.name, e);
+                        None
+                    }
+                })
+                .collect();
+        }
+    }
+    
+    fn lock(agent: &Mutex<Agent>) -> MutexGuard<'_, Agent> {
+        agent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
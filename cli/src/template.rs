@@ -0,0 +1,160 @@
+//! # Account Templates
+//! 
+//! This module reads account descriptions for `passman add --from-file`.
+//! Files ending in `.json` are parsed as JSON; anything else as TOML:
+//! 
+//! ```toml
+//! name = "GitHub"
+//! type = "work"
+//! username = "alice"
+//! url = "https://github.com"
+//! tags = ["dev", "code"]
+//! totp = "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP"
+//! 
+//! [[custom_fields]]
+//! name = "Recovery PIN"
+//! value = "1234"
+//! hidden = true
+//! ```
+//! 
+//! Every field except `name` is optional. A template without a `password`
+//! gets one generated or prompted for, so templates can be shared safely.
+
+use passman_backend::{
+    PassManError, Result,
+    models::{Account, AccountType, CustomField},
+    totp::Totp,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// An account description read from a template file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountTemplate {
+    /// Display name for the account
+    pub name: Option<String>,
+    
+    /// Account type (case-insensitive, e.g. "work")
+    #[serde(rename = "type")]
+    pub account_type: Option<String>,
+    
+    /// Website URL
+    pub url: Option<String>,
+    
+    /// Username or email
+    pub username: Option<String>,
+    
+    /// Password; generated or prompted for when absent
+    pub password: Option<String>,
+    
+    /// Free-form notes
+    pub notes: Option<String>,
+    
+    /// Tags for organizing the account
+    #[serde(default)]
+    pub tags: Vec<String>,
+    
+    /// Folder to file the account under
+    pub folder: Option<String>,
+    
+    /// Whether to mark the account as a favorite
+    #[serde(default)]
+    pub favorite: bool,
+    
+    /// TOTP secret (base32 or otpauth URI)
+    pub totp: Option<String>,
+    
+    /// Additional user-defined fields
+    #[serde(default)]
+    pub custom_fields: Vec<TemplateField>,
+}
+
+/// A custom field in a template
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateField {
+    /// Field label
+    pub name: String,
+    
+    /// Field value
+    pub value: String,
+    
+    /// Whether the value is secret
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl AccountTemplate {
+    /// Read a template from a TOML or JSON file
+    /// 
+    /// # Arguments
+    /// * `path` - Template file; `.json` files are parsed as JSON, others as TOML
+    /// 
+    /// # Returns
+    /// The parsed template
+    /// 
+    /// # Errors
+    /// Returns an error if the file can't be read or parsed, or has an
+    /// invalid account type or TOTP secret
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        
+        let template: Self = if is_json {
+            serde_json::from_str(&contents).map_err(|e| template_error(path, e))?
+        } else {
+            toml::from_str(&contents).map_err(|e| template_error(path, e))?
+        };
+        
+        template.parsed_account_type()?;
+        if let Some(ref secret) = template.totp {
+            Totp::parse(secret)?;
+        }
+        
+        Ok(template)
+    }
+    
+    /// The template's account type, if it names one
+    /// 
+    /// Names that aren't a built-in type are custom categories.
+    /// 
+    /// # Errors
+    /// Returns an error if the type is empty
+    pub fn parsed_account_type(&self) -> Result<Option<AccountType>> {
+        self.account_type.as_deref().map(str::parse).transpose()
+    }
+    
+    /// Build an account from the template
+    /// 
+    /// # Arguments
+    /// * `name` - Account name (the template's, unless overridden)
+    /// * `account_type` - Account type (the template's, unless overridden)
+    /// * `password` - The account password
+    /// 
+    /// # Returns
+    /// A new account carrying every field from the template
+    pub fn into_account(self, name: String, account_type: AccountType, password: String) -> Account {
+        let mut account = Account::new(name, account_type, password);
+        account.url = self.url;
+        account.username = self.username;
+        account.notes = self.notes;
+        account.tags = self.tags;
+        account.folder = self.folder;
+        account.is_favorite = self.favorite;
+        account.totp = self.totp;
+        account.custom_fields = self.custom_fields.into_iter()
+            .map(|field| CustomField {
+                name: field.name,
+                value: field.value,
+                hidden: field.hidden,
+            })
+            .collect();
+        account
+    }
+}
+
+/// Describe a template parse failure
+fn template_error(path: &Path, e: impl std::fmt::Display) -> PassManError {
+    PassManError::InvalidInput(format!("Invalid account template {}: {}", path.display(), e))
+}
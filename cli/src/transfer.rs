@@ -0,0 +1,222 @@
+//! # Import and Export Commands
+//! 
+//! This module implements `passman import` and `passman export`. Formats
+//! come from the backend's plugin registry: the built-in `json`, `csv`
+//! (browser exports, with `--map field=column` for other columns), `kdbx`
+//! (KeePass), `bitwarden`, `lastpass`, and `1pux` (1Password) formats, and
+//! any `passman-import-<format>` / `passman-export-<format>` executables in
+//! the `passman/plugins` config directory or on `PATH`. Password-protected
+//! files prompt for their password.
+//! 
+//! The `passman` format is a portable, encrypted export of the whole
+//! vault: exporting asks for a new export password, and importing merges
+//! its accounts into the current vault.
+
+use crate::status;
+use colored::*;
+use passman_backend::{
+    PassManError, Result,
+    models::{Account, AccountQuery, DedupePolicy},
+    importers::{CSV_FORMAT, ColumnMapping, CsvImporter},
+    plugins::{self, ImportPlugin, ImportedAccounts, PluginRegistry},
+    signing::SignatureStatus,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Build the registry of available formats
+pub fn registry() -> PluginRegistry {
+    let mut registry = PluginRegistry::with_builtins();
+    registry.discover(&plugins::plugin_dirs());
+    registry
+}
+
+/// Import accounts from a file into the current vault
+/// 
+/// # Arguments
+/// * `file` - File to import
+/// * `format` - Name of the import format
+/// * `map` - CSV columns for account fields, as `field=column`
+/// * `policy` - How accounts that already exist are handled
+/// * `dry_run` - Report what would change without saving
+/// 
+/// # Errors
+/// Returns an error if the format is unknown, a mapping is invalid or
+/// given for another format than CSV, the file's signature doesn't match,
+/// or the file can't be read
+pub fn import(file: &Path, format: &str, map: &[String], policy: DedupePolicy, dry_run: bool) -> Result<()> {
+    let registry = registry();
+    let mapped_csv;
+    let plugin: &dyn ImportPlugin = if map.is_empty() {
+        registry.importer(format).ok_or_else(|| unknown_format(format, registry.importers().map(|p| p.name())))?
+    } else if format.eq_ignore_ascii_case(CSV_FORMAT) {
+        mapped_csv = CsvImporter::new(ColumnMapping::parse(map)?);
+        &mapped_csv
+    } else {
+        return Err(PassManError::InvalidInput("--map only applies to --format csv".to_string()));
+    };
+    
+    let mut passman = crate::unlock_vault()?;
+    match passman.verify_file(file)? {
+        SignatureStatus::Valid => status!("{} Signature verified", "✓".green()),
+        SignatureStatus::Invalid => {
+            return Err(PassManError::CryptoError(format!(
+                "The signature of {} doesn't match; it may have been tampered with",
+                file.display(),
+            )));
+        }
+        SignatureStatus::Unsigned | SignatureStatus::NoKey => {}
+    }
+    
+    let data = std::fs::read(file)
+        .map_err(|e| PassManError::InvalidInput(format!("Cannot read {}: {}", file.display(), e)))?;
+    let ImportedAccounts { accounts, errors } = if plugin.is_encrypted(&data) {
+        let password = Zeroizing::new(rpassword::prompt_password(format!("Password for {}: ", file.display()))?);
+        plugin.import_encrypted(&mut data.as_slice(), &password)?
+    } else {
+        plugin.import(&mut data.as_slice())?
+    };
+    for error in &errors {
+        // Failures are reported even in quiet mode
+        eprintln!("  {} {}", "✗".red(), error);
+    }
+    
+    let summary = passman.merge_accounts(accounts, policy, dry_run, &mut |_, _| {})?;
+    let report = format!(
+        "{} {} account(s), overwrote {}, merged {}, skipped {} duplicate(s), {} failed",
+        if dry_run { "Would add" } else { "Added" },
+        summary.added,
+        summary.overwritten,
+        summary.merged,
+        summary.skipped,
+        errors.len(),
+    );
+    if errors.is_empty() {
+        status!("{}", report.green().bold());
+    } else {
+        status!("{}", report.yellow().bold());
+    }
+    
+    Ok(())
+}
+
+/// Export the current vault's active accounts to a file
+/// 
+/// The file is signed if the vault has a signing key. The `passman`
+/// format exports the whole vault, encrypted with an export password.
+/// CSV exports are only written once the user types `export`, since
+/// they end up in spreadsheets and other tools as plain text.
+/// 
+/// # Arguments
+/// * `file` - File to write (created readable only by the current user)
+/// * `format` - Name of the export format
+/// * `query` - Which accounts to export (type, tag, canaries)
+/// 
+/// # Errors
+/// Returns an error if the format is unknown, the CSV export isn't
+/// confirmed, or the file can't be written
+pub fn export(file: &Path, format: &str, query: &AccountQuery) -> Result<()> {
+    if format.eq_ignore_ascii_case(plugins::PORTABLE_FORMAT) {
+        return export_portable(file, query);
+    }
+    let registry = registry();
+    let plugin = registry.exporter(format).ok_or_else(|| unknown_format(format, registry.exporters().map(|p| p.name())))?;
+    
+    let passman = crate::unlock_vault()?;
+    let accounts: Vec<Account> = passman.query_accounts(query).into_iter().cloned().collect();
+    if format.eq_ignore_ascii_case(CSV_FORMAT) {
+        confirm_plaintext(file, accounts.len())?;
+    }
+    
+    let mut output = BufWriter::new(create_private(file)?);
+    plugin.export(&accounts, &mut output)?;
+    output.flush()?;
+    drop(output);
+    
+    status!("{}", format!("✓ Exported {} account(s) to {}", accounts.len(), file.display()).green().bold());
+    if let Some(signature) = passman.sign_file(file)? {
+        status!("Signature written to {}", signature.display());
+    }
+    status!("{}", "The file is not encrypted; delete it once you no longer need it.".yellow());
+    
+    Ok(())
+}
+
+/// Make the user type `export` before passwords are written in plain text
+fn confirm_plaintext(file: &Path, count: usize) -> Result<()> {
+    eprintln!(
+        "{}",
+        format!("This writes {} password(s) to {} in plain text; anyone who can read the file can read them.", count, file.display()).yellow(),
+    );
+    eprint!("Type \"export\" to continue: ");
+    std::io::stderr().flush()?;
+    
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim() != "export" {
+        return Err(PassManError::InvalidInput("Export cancelled".to_string()));
+    }
+    Ok(())
+}
+
+/// Export the whole vault, encrypted with a new export password
+fn export_portable(file: &Path, query: &AccountQuery) -> Result<()> {
+    if *query != AccountQuery::default() {
+        return Err(PassManError::InvalidInput("--format passman exports the whole vault; --account-type, --tag, and --exclude-canaries don't apply".to_string()));
+    }
+    let passman = crate::unlock_vault()?;
+    
+    let password = Zeroizing::new(rpassword::prompt_password("Export password: ")?);
+    if *password != rpassword::prompt_password("Confirm export password: ")? {
+        return Err(PassManError::InvalidInput("Passwords do not match".to_string()));
+    }
+    passman.export_portable(file, &password)?;
+    
+    status!("{}", format!("✓ Exported the vault to {}", file.display()).green().bold());
+    status!("Import it with: passman import {} --format {}", file.display(), plugins::PORTABLE_FORMAT);
+    Ok(())
+}
+
+/// List the available import and export formats
+pub fn list_formats() {
+    let registry = registry();
+    println!("{}", "Import formats:".bold());
+    for plugin in registry.importers() {
+        println!("  {:<12} {}", plugin.name(), plugin.description());
+    }
+    println!("{}", "Export formats:".bold());
+    for plugin in registry.exporters() {
+        println!("  {:<12} {}", plugin.name(), plugin.description());
+    }
+    println!("  {:<12} Whole vault, encrypted with an export password", plugins::PORTABLE_FORMAT);
+}
+
+fn unknown_format<'a>(format: &str, available: impl Iterator<Item = &'a str>) -> PassManError {
+    PassManError::InvalidInput(format!(
+        "Unknown format '{}' (available: {})",
+        format,
+        available.collect::<Vec<_>>().join(", "),
+    ))
+}
+
+/// Create (or truncate) a file readable only by the current user
+fn create_private(path: &Path) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)
+        .map_err(|e| PassManError::StorageError(format!("Failed to create {}: {}", path.display(), e)))?;
+    // The mode only applies to new files; tighten an existing one too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
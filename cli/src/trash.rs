@@ -0,0 +1,132 @@
+//! # Trash Commands
+//! 
+//! This module implements `passman trash`. Deleted accounts stay in the
+//! current vault's trash until they are restored, purged by hand, or
+//! older than the vault's `trash_retention_days` setting:
+//! 
+//! ```bash
+//! passman delete GitHub
+//! passman trash list
+//! passman trash restore GitHub
+//! passman trash purge            # empty the whole trash
+//! ```
+
+use crate::status;
+use clap::Subcommand;
+use colored::*;
+use passman_backend::{PassMan, PassManError, Result, models::TrashedAccount};
+
+/// Trash subcommands
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// List deleted accounts, most recent first
+    List,
+    
+    /// Move a deleted account back into the vault
+    Restore {
+        /// Account name or ID
+        name: String,
+    },
+    
+    /// Permanently delete one account from the trash, or all of them
+    Purge {
+        /// Account name or ID (default: the whole trash)
+        name: Option<String>,
+        
+        /// Don't ask before emptying the whole trash
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+/// Run a trash subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: TrashCommand) -> Result<()> {
+    match command {
+        TrashCommand::List => list(),
+        TrashCommand::Restore { name } => restore(&name),
+        TrashCommand::Purge { name, yes } => purge(name.as_deref(), yes),
+    }
+}
+
+fn list() -> Result<()> {
+    let passman = crate::unlock_vault()?;
+    let trashed = passman.list_trash();
+    if trashed.is_empty() {
+        status!("{}", "The trash is empty.".yellow());
+        return Ok(());
+    }
+    
+    for entry in &trashed {
+        let deleted_at: chrono::DateTime<chrono::Local> = entry.deleted_at.into();
+        println!(
+            "{}  {}  {}",
+            deleted_at.format("%Y-%m-%d %H:%M"),
+            entry.account.name.white().bold(),
+            entry.account.id.to_string().dimmed(),
+        );
+    }
+    
+    let days = crate::vault_settings(&passman)?.trash_retention_days;
+    status!();
+    if days > 0 {
+        status!("{}", format!("{} account(s); purged {} days after deletion", trashed.len(), days).blue());
+    } else {
+        status!("{}", format!("{} account(s); kept until purged", trashed.len()).blue());
+    }
+    Ok(())
+}
+
+fn restore(name: &str) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let entry = find_trashed(&passman, name)?;
+    let (id, account_name) = (entry.account.id, entry.account.name.clone());
+    passman.restore_account(id)?;
+    
+    status!("{}", format!("✓ Restored '{}'", account_name).green().bold());
+    Ok(())
+}
+
+fn purge(name: Option<&str>, yes: bool) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let id = match name {
+        Some(name) => Some(find_trashed(&passman, name)?.account.id),
+        None => {
+            let count = passman.list_trash().len();
+            if count == 0 {
+                status!("{}", "The trash is empty.".yellow());
+                return Ok(());
+            }
+            if !yes && !crate::prompt_confirm(&format!("Permanently delete {} account(s) in the trash?", count))? {
+                status!("Nothing was purged");
+                return Ok(());
+            }
+            None
+        }
+    };
+    
+    let purged = passman.purge_trash(id)?;
+    status!("{}", format!("✓ Permanently deleted {} account(s)", purged).green().bold());
+    Ok(())
+}
+
+/// Find an account in the trash by ID or name
+fn find_trashed<'a>(passman: &'a PassMan, name: &str) -> Result<&'a TrashedAccount> {
+    let matches: Vec<&TrashedAccount> = passman.list_trash().into_iter()
+        .filter(|entry| entry.account.id.to_string().eq_ignore_ascii_case(name) || entry.account.name.eq_ignore_ascii_case(name))
+        .collect();
+    
+    match matches.as_slice() {
+        [entry] => Ok(entry),
+        [] => Err(PassManError::AccountNotFound(format!("No account '{}' in the trash", name))),
+        _ => Err(PassManError::InvalidInput(format!(
+            "Several deleted accounts are named '{}'; use the ID from `passman trash list`",
+            name,
+        ))),
+    }
+}
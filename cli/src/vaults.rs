@@ -0,0 +1,515 @@
+//! # Vault Management Commands
+//! 
+//! This module implements `passman vault ...`: creating, listing,
+//! renaming, deleting, inspecting, and switching between vaults,
+//! managing the key that signs its backups and exports, choosing how
+//! accounts are stored, and merging copies of a vault edited on
+//! different devices.
+
+use crate::config::CliConfig;
+use crate::{output, session, status, wizard};
+use clap::{Subcommand, ValueEnum};
+use colored::*;
+use passman_backend::{
+    PassMan, PassManError, Result,
+    crypto::KdfParams,
+    signing::{self, SignatureStatus},
+    storage::KeySlotKind,
+};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Vault lifecycle subcommands
+#[derive(Subcommand)]
+pub enum VaultCommand {
+    /// Create a new vault with the setup wizard
+    Create {
+        /// Name of the new vault
+        name: String,
+        
+        /// Email address for the vault
+        email: String,
+        
+        /// Skip the optional wizard questions and use recommended settings
+        #[arg(long)]
+        defaults: bool,
+    },
+    
+    /// List all vaults
+    List,
+    
+    /// Rename a vault
+    Rename {
+        /// Current vault name
+        old_name: String,
+        
+        /// New vault name
+        new_name: String,
+    },
+    
+    /// Delete a vault and its backups
+    Delete {
+        /// Vault to delete
+        name: String,
+    },
+    
+    /// Show details about a vault (default: the current vault)
+    Info {
+        /// Vault to inspect
+        name: Option<String>,
+    },
+    
+    /// Make a vault the default for all other commands (same as `passman use`)
+    Switch {
+        /// Vault to use
+        name: String,
+    },
+    
+    /// Set the master password hint shown after failed unlock attempts
+    Hint {
+        /// Hint text (stored unencrypted in the vault file)
+        #[arg(required_unless_present = "clear")]
+        hint: Option<String>,
+        
+        /// Remove the current hint
+        #[arg(long, conflicts_with = "hint")]
+        clear: bool,
+    },
+    
+    /// Show or set how long the encrypted search cache stays valid
+    /// 
+    /// With a cache and a cached session, `passman list` doesn't decrypt the vault.
+    SearchCache {
+        /// Minutes after each save (0 turns the cache off and deletes it)
+        minutes: Option<u32>,
+    },
+    
+    /// Show or change how accounts are stored
+    /// 
+    /// With records, each account is encrypted on its own and a save only
+    /// rewrites the accounts that changed; best for vaults with thousands of entries.
+    Storage {
+        /// Layout to switch to
+        layout: Option<StorageLayout>,
+    },
+    
+    /// Show the public key that signs backups and exports
+    SigningKey {
+        /// Create a new signing key, replacing the current one
+        #[arg(long)]
+        generate: bool,
+        
+        /// Stop signing backups and exports
+        #[arg(long, conflicts_with = "generate")]
+        remove: bool,
+    },
+    
+    /// Check the detached signature (`<file>.minisig`) of a backup or export
+    Verify {
+        /// The signed file
+        file: PathBuf,
+        
+        /// Public key to check against instead of the vault's own
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    
+    /// Merge another copy of the current vault, such as a sync tool's conflict copy
+    Merge {
+        /// The other copy of the vault file
+        file: PathBuf,
+        
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// How a vault's accounts are stored
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StorageLayout {
+    /// All accounts in the vault file
+    SingleFile,
+    /// An encrypted record per account, beside the vault file
+    Records,
+}
+
+/// Run a vault subcommand
+/// 
+/// # Arguments
+/// * `command` - The subcommand to run
+/// 
+/// # Returns
+/// Unit on success
+pub fn run(command: VaultCommand) -> Result<()> {
+    match command {
+        VaultCommand::Create { name, email, defaults } => wizard::run(Some(name), &email, defaults),
+        VaultCommand::List => list_vaults(),
+        VaultCommand::Rename { old_name, new_name } => rename_vault(&old_name, &new_name),
+        VaultCommand::Delete { name } => delete_vault(&name),
+        VaultCommand::Info { name } => show_info(name),
+        VaultCommand::Switch { name } => switch_vault(&name),
+        VaultCommand::Hint { hint, .. } => set_hint(hint),
+        VaultCommand::SearchCache { minutes } => search_cache(minutes),
+        VaultCommand::Storage { layout } => storage_layout(layout),
+        VaultCommand::SigningKey { generate, remove } => signing_key(generate, remove),
+        VaultCommand::Verify { file, public_key } => verify_file(&file, public_key.as_deref()),
+        VaultCommand::Merge { file, dry_run } => merge_copy(&file, dry_run),
+    }
+}
+
+/// A vault as printed by `vaults` with `--output json`
+#[derive(serde::Serialize)]
+struct VaultJson<'a> {
+    name: &'a str,
+    current: bool,
+}
+
+/// List vaults, marking the current one
+pub fn list_vaults() -> Result<()> {
+    let vaults = PassMan::list_vaults()?;
+    let current = crate::current_vault()?.map(|(name, _)| name);
+    
+    if output::is_json() {
+        let vaults: Vec<VaultJson> = vaults.iter()
+            .map(|name| VaultJson { name, current: current.as_deref() == Some(name.as_str()) })
+            .collect();
+        return output::print_json(&vaults);
+    }
+    
+    if vaults.is_empty() {
+        status!("{}", "No vaults found.".yellow());
+        return Ok(());
+    }
+    
+    status!("{}", "Available vaults:".blue().bold());
+    for vault in vaults {
+        if output::is_quiet() {
+            println!("{}", vault);
+        } else if current.as_deref() == Some(vault.as_str()) {
+            println!("{} {}", "*".green().bold(), vault.green().bold());
+        } else {
+            println!("  {}", vault);
+        }
+    }
+    
+    Ok(())
+}
+
+fn rename_vault(old_name: &str, new_name: &str) -> Result<()> {
+    PassMan::rename_vault(old_name, new_name)?;
+    
+    // The cached session is keyed by vault name
+    let _ = session::clear(old_name);
+    
+    let mut config = CliConfig::load_file()?;
+    let mut changed = false;
+    if let Some(keyfile) = config.keyfiles.remove(old_name) {
+        config.keyfiles.insert(new_name.to_string(), keyfile);
+        changed = true;
+    }
+    if config.current_vault.as_deref() == Some(old_name) {
+        config.current_vault = Some(new_name.to_string());
+        changed = true;
+    }
+    if changed {
+        config.save()?;
+    }
+    
+    status!("{}", format!("✓ Renamed vault '{}' to '{}'", old_name, new_name).green().bold());
+    
+    Ok(())
+}
+
+fn delete_vault(name: &str) -> Result<()> {
+    if !PassMan::list_vaults()?.iter().any(|vault| vault == name) {
+        return Err(PassManError::VaultNotFound(format!("Vault '{}' does not exist", name)));
+    }
+    
+    status!("{}", format!("This permanently deletes vault '{}' and all of its backups.", name).red().bold());
+    eprint!("Type the vault name to confirm: ");
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim() != name {
+        return Err(PassManError::InvalidInput("Vault name did not match; nothing was deleted".to_string()));
+    }
+    
+    // Require the master password even if a session is cached
+    let mut passman = PassMan::new(name)?;
+    crate::open_with_master_password(&mut passman)?;
+    passman.close_vault();
+    
+    PassMan::delete_vault(name)?;
+    let _ = session::clear(name);
+    
+    let mut config = CliConfig::load_file()?;
+    let had_keyfile = config.keyfiles.remove(name).is_some();
+    let was_current = config.current_vault.as_deref() == Some(name);
+    if was_current {
+        config.current_vault = None;
+    }
+    if had_keyfile || was_current {
+        config.save()?;
+    }
+    
+    status!("{}", format!("✓ Deleted vault '{}'", name).green().bold());
+    
+    Ok(())
+}
+
+fn show_info(name: Option<String>) -> Result<()> {
+    let name = match name {
+        Some(name) => name,
+        None => crate::get_current_vault_name()?,
+    };
+    if !PassMan::list_vaults()?.contains(&name) {
+        return Err(PassManError::VaultNotFound(format!("Vault '{}' does not exist", name)));
+    }
+    
+    let passman = PassMan::new(&name)?;
+    let (size, modified) = passman.get_vault_info()?;
+    let header = passman.vault_header()?;
+    let config = CliConfig::load()?;
+    let is_current = crate::current_vault()?.is_some_and(|(current, _)| current == name);
+    
+    let current = if is_current { " (current)" } else { "" };
+    println!("{}", format!("Vault: {}{}", name, current).white().bold());
+    if let Some(path) = passman.vault_path() {
+        println!("  Path: {}", path.display());
+    }
+    println!("  Size: {} bytes", size);
+    if let Some(modified) = modified {
+        let modified: chrono::DateTime<chrono::Local> = modified.into();
+        println!("  Modified: {}", modified.format("%Y-%m-%d %H:%M:%S"));
+    }
+    
+    let kdf = header.as_ref().map(|h| h.kdf).unwrap_or_default();
+    match header {
+        Some(ref header) => println!("  Format: version {}", header.format_version),
+        None => println!("  Format: legacy (version 1)"),
+    }
+    println!("  Key derivation: {}", describe_kdf(&kdf));
+    if let Some(ref header) = header {
+        println!("  Cipher: {}", header.cipher.as_str());
+    }
+    let records = header.as_ref().is_some_and(|h| h.records);
+    println!("  Storage: {}", if records { "a record per account" } else { "single file" });
+    
+    let keyfile = header.as_ref().is_some_and(|h| h.keyfile);
+    match (keyfile, config.keyfiles.get(&name)) {
+        (true, Some(path)) => println!("  Keyfile: required ({})", path.display()),
+        (true, None) => println!("  Keyfile: required (location not configured)"),
+        (false, _) => println!("  Keyfile: not required"),
+    }
+    let recovery = header.as_ref().is_some_and(|h| h.has_slot(KeySlotKind::Recovery));
+    println!("  Recovery key: {}", if recovery { "configured" } else { "none" });
+    let device = header.as_ref().is_some_and(|h| h.has_slot(KeySlotKind::Device));
+    println!("  Device unlock: {}", if device { "enrolled" } else { "none" });
+    match passman.emergency_access()? {
+        Some(access) => println!("  Emergency access: {} ({}-day wait)", access.contact, access.wait_days),
+        None => println!("  Emergency access: none"),
+    }
+    let hint = header.as_ref().is_some_and(|h| h.hint.is_some());
+    println!("  Password hint: {}", if hint { "set" } else { "none" });
+    
+    match session::load(&name) {
+        Some(cached) => println!(
+            "  Session: {} until {}",
+            "unlocked".green(),
+            cached.expires_at.with_timezone(&chrono::Local).format("%H:%M:%S"),
+        ),
+        None => println!("  Session: {}", "locked".red()),
+    }
+    
+    Ok(())
+}
+
+/// Describe Argon2id parameters for humans
+fn describe_kdf(kdf: &KdfParams) -> String {
+    format!(
+        "Argon2id, {} MiB memory, {} iterations, {} lane(s)",
+        kdf.memory_kib / 1024,
+        kdf.iterations,
+        kdf.parallelism,
+    )
+}
+
+fn set_hint(hint: Option<String>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let cleared = hint.is_none();
+    passman.set_password_hint(hint)?;
+    
+    if cleared {
+        status!("{}", "✓ Password hint removed".green().bold());
+    } else {
+        status!("{}", "✓ Password hint updated".green().bold());
+    }
+    
+    Ok(())
+}
+
+fn search_cache(minutes: Option<u32>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let mut settings = crate::vault_settings(&passman)?.clone();
+    let Some(minutes) = minutes else {
+        match settings.search_cache_minutes {
+            0 => println!("Search cache: off"),
+            minutes => println!("Search cache: {} minute(s) after each save", minutes),
+        }
+        return Ok(());
+    };
+    
+    settings.search_cache_minutes = minutes;
+    passman.update_settings(settings)?;
+    
+    if minutes == 0 {
+        status!("{}", "✓ Search cache turned off".green().bold());
+    } else {
+        status!("{}", format!("✓ Search cache stays valid for {} minute(s) after each save", minutes).green().bold());
+    }
+    
+    Ok(())
+}
+
+fn storage_layout(layout: Option<StorageLayout>) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    let Some(layout) = layout else {
+        let records = passman.vault_header()?.is_some_and(|header| header.records);
+        println!("Storage: {}", if records { "a record per account" } else { "single file" });
+        return Ok(());
+    };
+    
+    let records = layout == StorageLayout::Records;
+    if !passman.set_record_storage(records)? {
+        status!("{}", "✓ The vault already uses this layout".green().bold());
+    } else if records {
+        status!("{}", "✓ Each account is now stored in a record of its own".green().bold());
+    } else {
+        status!("{}", "✓ All accounts are now stored in the vault file".green().bold());
+    }
+    
+    Ok(())
+}
+
+/// Make a vault the default for all other commands
+/// 
+/// # Arguments
+/// * `name` - Vault to use
+/// 
+/// # Errors
+/// Returns an error if the vault doesn't exist or the config can't be saved
+pub fn switch_vault(name: &str) -> Result<()> {
+    if !PassMan::list_vaults()?.iter().any(|vault| vault == name) {
+        return Err(PassManError::VaultNotFound(format!("Vault '{}' does not exist", name)));
+    }
+    
+    let mut config = CliConfig::load_file()?;
+    config.current_vault = Some(name.to_string());
+    config.save()?;
+    
+    status!("{}", format!("✓ Now using vault '{}'", name).green().bold());
+    
+    Ok(())
+}
+
+fn signing_key(generate: bool, remove: bool) -> Result<()> {
+    let mut passman = crate::unlock_vault()?;
+    
+    if remove {
+        if passman.signing_public_key().is_none() {
+            status!("{}", "This vault has no signing key.".yellow());
+            return Ok(());
+        }
+        if !crate::prompt_confirm("Stop signing backups and exports? Existing signatures can no longer be checked.")? {
+            status!("{}", "Cancelled.".yellow());
+            return Ok(());
+        }
+        passman.remove_signing_key()?;
+        status!("{}", "✓ Signing key removed".green().bold());
+        return Ok(());
+    }
+    
+    if generate {
+        if passman.signing_public_key().is_some()
+            && !crate::prompt_confirm("Replace the signing key? Files signed with the current key will no longer verify.")?
+        {
+            status!("{}", "Cancelled.".yellow());
+            return Ok(());
+        }
+        passman.generate_signing_key()?;
+        status!("{}", "✓ Backups and exports will be signed from now on".green().bold());
+    }
+    
+    match passman.signing_public_key() {
+        Some(public_key) => {
+            println!("{}", public_key);
+            status!("Check signatures elsewhere with: minisign -Vm <file> -P {}", public_key);
+        }
+        None => status!("{}", "This vault has no signing key; create one with `passman vault signing-key --generate`.".yellow()),
+    }
+    
+    Ok(())
+}
+
+fn verify_file(file: &Path, public_key: Option<&str>) -> Result<()> {
+    if !file.is_file() {
+        return Err(PassManError::InvalidInput(format!("{} is not a file", file.display())));
+    }
+    let status = match public_key {
+        Some(public_key) => {
+            signing::parse_public_key(public_key)?;
+            signing::check_file(Some(public_key), file)?
+        }
+        None => crate::unlock_vault()?.verify_file(file)?,
+    };
+    
+    match status {
+        SignatureStatus::Valid => {
+            status!("{}", format!("✓ {} is signed and unchanged", file.display()).green().bold());
+            Ok(())
+        }
+        SignatureStatus::Invalid => Err(PassManError::CryptoError(format!(
+            "The signature of {} doesn't match; it may have been tampered with",
+            file.display(),
+        ))),
+        SignatureStatus::Unsigned => Err(PassManError::InvalidInput(format!(
+            "{} has no signature ({} not found)",
+            file.display(),
+            signing::signature_path(file).display(),
+        ))),
+        SignatureStatus::NoKey => Err(PassManError::InvalidInput(
+            "This vault has no signing key; pass the signer's key with --public-key".to_string(),
+        )),
+    }
+}
+
+fn merge_copy(file: &Path, dry_run: bool) -> Result<()> {
+    if !file.is_file() {
+        return Err(PassManError::InvalidInput(format!("{} is not a file", file.display())));
+    }
+    let mut passman = crate::unlock_vault()?;
+    if passman.vault_path().map(Path::canonicalize).transpose()? == Some(file.canonicalize()?) {
+        return Err(PassManError::InvalidInput("That is the vault file itself".to_string()));
+    }
+    
+    let summary = passman.merge_vault_file(file, dry_run)?;
+    if summary.is_empty() {
+        status!("{}", "✓ Nothing to merge: the vault already has every change in the copy".green().bold());
+        return Ok(());
+    }
+    status!(
+        "{}",
+        format!(
+            "✓ {} {} new account(s), {} deletion(s), and changes to {} account(s)",
+            if dry_run { "Would merge" } else { "Merged" },
+            summary.added,
+            summary.deleted,
+            summary.updated,
+        ).green().bold()
+    );
+    if !dry_run {
+        status!("The copy can be deleted now: {}", file.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! # Init Wizard
+//! 
+//! This module walks the user through creating a vault: a master password
+//! with a live strength meter, key derivation calibration, an optional
+//! keyfile and recovery key, and the vault's default settings.
+
+use crate::config::{self, CliConfig};
+use crate::{credentials, status};
+use colored::*;
+use console::{Key, Term};
+use passman_backend::{
+    PassMan, PassManConfig, PassManError, Result, VaultInitOptions,
+    crypto::KdfParams,
+    storage,
+    generator::PasswordGenerator,
+    models::VaultSettings,
+};
+use rand::{RngCore, rngs::OsRng};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Strength score below which the wizard asks before accepting a password
+const WEAK_PASSWORD_SCORE: u8 = 60;
+/// Width of the strength meter bar in characters
+const METER_WIDTH: usize = 20;
+/// Size of generated keyfiles in bytes
+const KEYFILE_SIZE: usize = 64;
+/// Unlock-time presets offered for key derivation calibration (name, milliseconds)
+const UNLOCK_PRESETS: [(&str, u64); 3] = [("fast", 500), ("balanced", 1000), ("paranoid", 3000)];
+/// Preset chosen when the user just presses Enter
+const DEFAULT_PRESET: usize = 2;
+
+/// Run the init wizard and create the vault
+/// 
+/// # Arguments
+/// * `vault_name` - Name of the new vault, or None to ask for it
+/// * `email` - Email address for the vault
+/// * `use_defaults` - Skip the optional questions and use recommended settings
+/// 
+/// # Returns
+/// Unit on success
+/// 
+/// # Errors
+/// Returns an error if input is invalid or the vault cannot be created
+pub fn run(vault_name: Option<String>, email: &str, use_defaults: bool) -> Result<()> {
+    status!("{}", "Initializing new PassMan vault...".green().bold());
+    
+    let vault_name = match vault_name {
+        Some(name) => name,
+        None => crate::prompt_vault_name()?,
+    };
+    storage::validate_vault_name(&vault_name)?;
+    if PassMan::list_vaults()?.contains(&vault_name) {
+        return Err(PassManError::VaultExists(format!("'{}'", vault_name)));
+    }
+    
+    let master_password = prompt_new_master_password()?;
+    
+    let mut options = VaultInitOptions::from_config(&PassManConfig::load()?);
+    let mut keyfile_path = None;
+    if !use_defaults {
+        options.kdf = choose_kdf_params()?;
+        if let Some((path, contents)) = choose_keyfile(&vault_name)? {
+            keyfile_path = Some(path);
+            options.keyfile = Some(contents);
+        }
+        options.recovery_key = confirm("Generate a recovery key in case you forget the master password?", true)?;
+        options.hint = prompt_hint()?;
+        options.settings = choose_settings()?;
+    }
+    
+    let mut passman = PassMan::new(&vault_name)?;
+    let recovery_key = passman.init_vault_with_options(email.to_string(), &master_password, options)?;
+    
+    if let Some(path) = keyfile_path {
+        let mut config = CliConfig::load_file()?;
+        config.keyfiles.insert(vault_name.clone(), path);
+        config.save()?;
+    }
+    
+    status!("{}", "✓ Vault created successfully!".green().bold());
+    
+    if let Some(recovery_key) = recovery_key {
+        status!();
+        status!("{}", "Recovery key (shown only once):".yellow().bold());
+        println!("{}", recovery_key);
+        status!("{}", "Write it down and keep it offline. Anyone with it can open this vault;".yellow());
+        status!("{}", "use 'passman recover' with it to set a new master password.".yellow());
+        status!();
+    }
+    
+    status!("{}", "You can now add accounts with 'passman add'".blue());
+    
+    Ok(())
+}
+
+/// Ask for a new master password twice, showing its strength while typing
+/// 
+/// # Returns
+/// The confirmed password
+/// 
+/// # Errors
+/// Returns an error if the passwords differ or a weak password is declined
+pub fn prompt_new_master_password() -> Result<String> {
+    // Given on stdin or a file descriptor: nothing to confirm
+    if let Some(password) = credentials::given_password()? {
+        if PasswordGenerator::new().calculate_strength(&password) < WEAK_PASSWORD_SCORE {
+            status!("{}", "Warning: this master password is weak.".yellow());
+        }
+        return Ok(password);
+    }
+    
+    let term = Term::stderr();
+    let password = if term.is_term() {
+        read_password_with_meter(&term)?
+    } else {
+        crate::prompt_master_password()?
+    };
+    
+    let score = PasswordGenerator::new().calculate_strength(&password);
+    if score < WEAK_PASSWORD_SCORE
+        && !confirm("This master password is weak. Use it anyway?", false)?
+    {
+        return Err(PassManError::InvalidInput("Choose a stronger master password".to_string()));
+    }
+    
+    if password != crate::prompt_confirm_password()? {
+        return Err(PassManError::InvalidInput("Passwords do not match".to_string()));
+    }
+    
+    Ok(password)
+}
+
+/// Read a password key by key, redrawing a strength meter after each one
+fn read_password_with_meter(term: &Term) -> Result<String> {
+    let generator = PasswordGenerator::new();
+    let mut password = String::new();
+    
+    loop {
+        term.clear_line()?;
+        term.write_str(&format!("Enter master password: {}", strength_meter(&generator, &password)))?;
+        
+        match term.read_key()? {
+            Key::Enter => break,
+            Key::Backspace => {
+                password.pop();
+            }
+            Key::Char(c) if !c.is_control() => password.push(c),
+            Key::CtrlC | Key::Escape => {
+                term.write_line("")?;
+                return Err(PassManError::InvalidInput("Cancelled".to_string()));
+            }
+            _ => {}
+        }
+    }
+    
+    term.write_line("")?;
+    Ok(password)
+}
+
+/// Render a colored strength bar with its description
+fn strength_meter(generator: &PasswordGenerator, password: &str) -> String {
+    let estimate = generator.estimate_strength(password);
+    let score = estimate.score;
+    let filled = score as usize * METER_WIDTH / 100;
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(METER_WIDTH - filled));
+    let bar = match score {
+        0..=40 => bar.red(),
+        41..=60 => bar.yellow(),
+        _ => bar.green(),
+    };
+    format!("{} {} (cracked in {})", bar, generator.get_strength_description(score), estimate.crack_time)
+}
+
+/// Let the user pick an unlock time and calibrate key derivation to it
+fn choose_kdf_params() -> Result<KdfParams> {
+    status!("How long should unlocking take? Slower unlocks are harder to brute-force.");
+    for (i, (name, millis)) in UNLOCK_PRESETS.iter().enumerate() {
+        status!("{}. {} (~{} ms)", i + 1, name, millis);
+    }
+    
+    let choice = prompt_with_default("Enter choice (1-3)", &DEFAULT_PRESET.to_string())?;
+    let (_, millis) = choice.parse::<usize>().ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| UNLOCK_PRESETS.get(i))
+        .ok_or_else(|| PassManError::InvalidInput(format!("Invalid choice: {}", choice)))?;
+    
+    status!("Calibrating key derivation...");
+    let params = KdfParams::calibrate(Duration::from_millis(*millis))?;
+    status!(
+        "Using Argon2id with {} MiB of memory and {} iterations.",
+        params.memory_kib / 1024,
+        params.iterations,
+    );
+    
+    Ok(params)
+}
+
+/// Optionally set up a keyfile, reusing an existing file or generating one
+fn choose_keyfile(vault_name: &str) -> Result<Option<(PathBuf, Vec<u8>)>> {
+    if !confirm("Require a keyfile in addition to the master password?", false)? {
+        return Ok(None);
+    }
+    
+    let default_path = config::config_path()?.with_file_name(format!("{}.key", vault_name));
+    let path = PathBuf::from(prompt_with_default("Keyfile path", &default_path.display().to_string())?);
+    
+    let contents = if path.exists() {
+        status!("Using existing keyfile {}", path.display());
+        std::fs::read(&path)?
+    } else {
+        let contents = generate_keyfile(&path)?;
+        status!("{}", format!("✓ Generated keyfile {}", path.display()).green());
+        contents
+    };
+    status!("{}", "Back up the keyfile: the vault cannot be opened without it.".yellow());
+    
+    Ok(Some((std::fs::canonicalize(&path)?, contents)))
+}
+
+/// Write a new random keyfile readable only by the owner
+fn generate_keyfile(path: &Path) -> Result<Vec<u8>> {
+    let mut contents = vec![0u8; KEYFILE_SIZE];
+    OsRng.fill_bytes(&mut contents);
+    
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, &contents)?;
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    
+    Ok(contents)
+}
+
+/// Offer the recommended vault settings or ask for each one
+fn choose_settings() -> Result<VaultSettings> {
+    let defaults = VaultSettings::default();
+    let summary = format!(
+        "Use recommended settings (auto-lock after {} min, clipboard cleared after {} s)?",
+        defaults.auto_lock_timeout, defaults.clipboard_timeout,
+    );
+    if confirm(&summary, true)? {
+        return Ok(defaults);
+    }
+    
+    let auto_lock_timeout = prompt_number("Auto-lock timeout in minutes (0 disables)", defaults.auto_lock_timeout)?;
+    let clipboard_timeout = prompt_number("Clear clipboard after how many seconds (0 disables)", defaults.clipboard_timeout)?;
+    let require_confirmation = confirm("Ask before showing secrets on screen?", defaults.require_confirmation)?;
+    
+    Ok(VaultSettings {
+        auto_lock_timeout,
+        require_confirmation,
+        auto_clear_clipboard: clipboard_timeout > 0,
+        clipboard_timeout: if clipboard_timeout > 0 { clipboard_timeout } else { defaults.clipboard_timeout },
+        ..defaults
+    })
+}
+
+/// Ask a yes/no question with a default answer
+fn confirm(question: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = prompt_with_default(question, hint)?;
+    
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Ask for a number, accepting the default on empty input
+fn prompt_number(question: &str, default: u32) -> Result<u32> {
+    let answer = prompt_with_default(question, &default.to_string())?;
+    answer.parse()
+        .map_err(|_| PassManError::InvalidInput(format!("Not a number: {}", answer)))
+}
+
+/// Ask a question on stderr, returning `default` if the answer is empty
+/// Ask for an optional master password hint
+fn prompt_hint() -> Result<Option<String>> {
+    eprint!("Password hint (optional, stored unencrypted): ");
+    io::stderr().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    
+    let hint = input.trim();
+    Ok(if hint.is_empty() { None } else { Some(hint.to_string()) })
+}
+
+fn prompt_with_default(question: &str, default: &str) -> Result<String> {
+    eprint!("{} [{}]: ", question, default);
+    io::stderr().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    
+    let answer = input.trim();
+    Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+}
@@ -0,0 +1,184 @@
+//! # Auto-Lock Watcher
+//! 
+//! A background thread locks the shared vault after the vault's
+//! `auto_lock_timeout` of inactivity, and tells the frontend through Tauri
+//! events so the UI returns to the unlock screen:
+//! 
+//! - `vault-lock-warning` with `{ "seconds_remaining": n }` shortly before
+//!   the timeout
+//! - `vault-unlocked` after the vault has been opened
+//! - `vault-locked` with `{ "reason": "timeout" | "sleep" | "screen-lock" | "manual" | "external" | "restore" }`
+//! 
+//! `get_lock_remaining` reports the seconds left without counting as
+//! activity, so the UI can show a countdown.
+//! 
+//! Inactivity is system-wide: time since the last PassMan command or the
+//! last keyboard/mouse input anywhere, whichever is more recent. The OS
+//! idle time comes from `user-idle` (GetLastInputInfo on Windows, IOKit
+//! on macOS, XScreenSaver on X11); where it isn't available (e.g. native
+//! Wayland) only PassMan's own activity counts.
+//! 
+//! The vault also locks as soon as the screen locks or the system is about
+//! to sleep, as reported by the backend's `screenlock` watcher, unless
+//! `keep_unlocked_on_screen_lock` is set in the config file. Where the
+//! watcher isn't available, sleep is still detected from the wall clock
+//! jumping ahead between ticks.
+
+use crate::state::VaultState;
+use crate::tray;
+use passman_backend::{PassMan, PassManConfig, screenlock};
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted after the vault has been locked
+pub const LOCKED_EVENT: &str = "vault-locked";
+/// Event emitted after the vault has been opened
+pub const UNLOCKED_EVENT: &str = "vault-unlocked";
+/// Event emitted shortly before an idle lock
+pub const WARNING_EVENT: &str = "vault-lock-warning";
+
+/// How often the watcher checks the vault
+const TICK: Duration = Duration::from_secs(5);
+/// How long before the idle lock the warning is sent
+const WARNING_LEAD: Duration = Duration::from_secs(60);
+/// Wall-clock gap between ticks treated as a system sleep
+const SLEEP_GAP: Duration = Duration::from_secs(30);
+
+/// Payload of the `vault-locked` event
+#[derive(Clone, Serialize)]
+pub struct LockedPayload {
+    /// Why the vault was locked: "timeout", "sleep", "screen-lock", "manual", "external", or "restore"
+    pub reason: &'static str,
+}
+
+/// Payload of the `vault-lock-warning` event
+#[derive(Clone, Serialize)]
+pub struct WarningPayload {
+    /// Seconds until the vault locks unless it is used
+    pub seconds_remaining: u64,
+}
+
+/// Start the watcher thread
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+pub fn spawn(app: AppHandle) {
+    watch_screen_lock(&app);
+    
+    std::thread::spawn(move || {
+        let mut last_wall = SystemTime::now();
+        let mut warned = false;
+        
+        loop {
+            std::thread::sleep(TICK);
+            let state = app.state::<VaultState>();
+            
+            // Monotonic clocks may pause during suspend; the wall clock doesn't
+            let now_wall = SystemTime::now();
+            let slept = now_wall.duration_since(last_wall).unwrap_or_default() > TICK + SLEEP_GAP;
+            last_wall = now_wall;
+            
+            if !state.is_open() {
+                warned = false;
+                continue;
+            }
+            if slept {
+                lock(&app, "sleep");
+                continue;
+            }
+            
+            let Some(timeout) = idle_timeout(&state) else {
+                continue;
+            };
+            let idle = idle_time(&state);
+            
+            if idle >= timeout {
+                lock(&app, "timeout");
+            } else if timeout - idle > WARNING_LEAD {
+                warned = false;
+            } else if !warned {
+                // Warn once per idle period
+                warned = true;
+                let _ = app.emit(WARNING_EVENT, WarningPayload {
+                    seconds_remaining: (timeout - idle).as_secs(),
+                });
+            }
+        }
+    });
+}
+
+/// Lock the vault when the screen locks or the system goes to sleep
+fn watch_screen_lock(app: &AppHandle) {
+    if PassManConfig::load().is_ok_and(|config| config.keep_unlocked_on_screen_lock) {
+        return;
+    }
+    let app = app.clone();
+    if let Err(e) = screenlock::watch(move |event| lock(&app, event.as_str())) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Seconds until the open vault locks for inactivity
+/// 
+/// # Arguments
+/// * `state` - The shared vault state
+/// 
+/// # Returns
+/// The seconds left, or None if no vault is open or auto-lock is disabled
+pub fn seconds_remaining(state: &VaultState) -> Option<u64> {
+    if !state.is_open() {
+        return None;
+    }
+    let timeout = idle_timeout(state)?;
+    Some(timeout.saturating_sub(idle_time(state)).as_secs())
+}
+
+/// The open vault's auto-lock timeout, if enabled
+fn idle_timeout(state: &VaultState) -> Option<Duration> {
+    state.auto_lock_minutes()
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60))
+}
+
+/// Time since the last PassMan command or system input, whichever is more recent
+fn idle_time(state: &VaultState) -> Duration {
+    let app_idle = state.last_activity().elapsed();
+    system_idle().map_or(app_idle, |system| system.min(app_idle))
+}
+
+/// Time since the user last used any input device, if the OS reports it
+fn system_idle() -> Option<Duration> {
+    user_idle::UserIdle::get_time().ok().map(|idle| idle.duration())
+}
+
+/// Lock the shared vault and notify the frontend
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `reason` - Why the vault is being locked
+pub fn lock(app: &AppHandle, reason: &'static str) {
+    if app.state::<VaultState>().close() {
+        tray::refresh(app);
+        let _ = app.emit(LOCKED_EVENT, LockedPayload { reason });
+    }
+}
+
+/// Keep a freshly opened vault in the shared state and notify the frontend
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `passman` - The opened vault
+pub fn unlock(app: &AppHandle, passman: PassMan) {
+    app.state::<VaultState>().set_open(passman);
+    unlocked(app);
+}
+
+/// Notify the frontend that the shared vault was unlocked in place
+/// 
+/// # Arguments
+/// * `app` - Handle used to refresh the tray and emit events
+pub fn unlocked(app: &AppHandle) {
+    tray::refresh(app);
+    let _ = app.emit(UNLOCKED_EVENT, ());
+}
@@ -0,0 +1,190 @@
+//! # Global-Shortcut Autotype
+//! 
+//! Pressing `SHORTCUT` anywhere types the credentials of the account that
+//! matches the focused window: its title is matched against account URLs
+//! and names (`passman_backend::autotype::match_window`), and the best
+//! match's sequence (`{USERNAME}{TAB}{PASSWORD}{ENTER}` unless the account
+//! has its own) is typed through a virtual keyboard. enigo drives the
+//! keyboard with SendInput on Windows, CGEvent on macOS, and XTest or the
+//! Wayland virtual-keyboard protocol on Linux.
+//! 
+//! Every attempt is reported through the `autotype` event:
+//! 
+//! - `{ "status": "typed", "window_title": "...", "account": "GitHub", "matches": n }`
+//! - `{ "status": "locked" | "no-match" | "failed", "window_title": "...", "error": "..." }`
+//! 
+//! A locked vault brings up the main window to unlock; no match opens
+//! the quick search window instead. Wayland compositors don't expose the
+//! focused window's title, so there every attempt ends in quick search.
+
+use crate::{error::CommandError, state::VaultState, tray};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use passman_backend::autotype::{self, AutotypeAction, AutotypeKey};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{Builder, ShortcutState};
+
+/// Shortcut that triggers autotype
+pub const SHORTCUT: &str = "CommandOrControl+Alt+A";
+/// Event reporting the outcome of an autotype attempt
+pub const AUTOTYPE_EVENT: &str = "autotype";
+
+/// Time for the user to let go of the shortcut's modifier keys, which
+/// would otherwise turn typed letters into shortcuts of their own
+const RELEASE_DELAY: Duration = Duration::from_millis(350);
+/// Pause between actions so slow forms keep up with field changes
+const STEP_DELAY: Duration = Duration::from_millis(40);
+
+/// Payload of the `autotype` event
+#[derive(Clone, Default, Serialize)]
+pub struct AutotypePayload {
+    /// "typed", "locked", "no-match", or "failed"
+    pub status: &'static str,
+    
+    /// Title of the window autotype targeted
+    pub window_title: String,
+    
+    /// Name of the typed account
+    pub account: Option<String>,
+    
+    /// Number of accounts matching the window
+    pub matches: usize,
+    
+    /// Why typing failed
+    pub error: Option<String>,
+}
+
+/// Register the global shortcut plugin and its handler
+/// 
+/// # Arguments
+/// * `app` - Handle of the running app
+/// 
+/// # Errors
+/// Returns an error if the shortcut is taken or can't be registered
+pub fn register(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let plugin = Builder::new()
+        .with_shortcuts([SHORTCUT])?
+        .with_handler(|app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                trigger(app.clone());
+            }
+        })
+        .build();
+    app.plugin(plugin)?;
+    Ok(())
+}
+
+/// Run one autotype attempt on a background thread
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+pub fn trigger(app: AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(RELEASE_DELAY);
+        let payload = run(&app);
+        
+        let fallback = match payload.status {
+            "locked" => tray::show_main_window(&app),
+            "no-match" => tray::open_quick_search(&app),
+            _ => Ok(()),
+        };
+        if let Err(e) = fallback {
+            eprintln!("Autotype fallback failed: {}", e);
+        }
+        let _ = app.emit(AUTOTYPE_EVENT, payload);
+    });
+}
+
+/// Match the focused window and type the best account into it
+fn run(app: &AppHandle) -> AutotypePayload {
+    let window_title = focused_window_title();
+    let mut payload = AutotypePayload {
+        window_title: window_title.clone(),
+        ..AutotypePayload::default()
+    };
+    
+    let state = app.state::<VaultState>();
+    if !state.is_open() {
+        payload.status = "locked";
+        return payload;
+    }
+    
+    let resolved = state.with_vault(|passman| {
+        let accounts = passman.get_all_accounts();
+        let matches = autotype::match_window(&window_title, &accounts);
+        let Some(account) = matches.first() else {
+            return Ok(None);
+        };
+        let actions = autotype::actions(account)?;
+        Ok(Some((account.id, account.name.clone(), matches.len(), actions)))
+    });
+    
+    match resolved {
+        Ok(Some((id, account, matches, actions))) => {
+            payload.account = Some(account);
+            payload.matches = matches;
+            match type_actions(&actions) {
+                Ok(()) => {
+                    payload.status = "typed";
+                    // The text is already typed, so a failed save only loses the usage count
+                    let _ = state.with_vault(|passman| passman.record_access(id).map_err(CommandError::from));
+                }
+                Err(e) => {
+                    payload.status = "failed";
+                    payload.error = Some(e);
+                }
+            }
+        }
+        Ok(None) => payload.status = "no-match",
+        Err(e) => {
+            payload.status = "failed";
+            payload.error = Some(e.to_string());
+        }
+    }
+    payload
+}
+
+/// Title of the window that currently has focus, or "" if unknown
+fn focused_window_title() -> String {
+    active_win_pos_rs::get_active_window()
+        .map(|window| window.title)
+        .unwrap_or_default()
+}
+
+/// Play actions on the virtual keyboard
+fn type_actions(actions: &[AutotypeAction]) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Virtual keyboard unavailable: {}", e))?;
+    
+    for action in actions {
+        match action {
+            AutotypeAction::Text(text) => enigo.text(text),
+            AutotypeAction::Key(key) => enigo.key(to_enigo_key(*key), Direction::Click),
+            AutotypeAction::Delay(ms) => {
+                std::thread::sleep(Duration::from_millis(*ms));
+                Ok(())
+            }
+        }.map_err(|e| format!("Typing failed: {}", e))?;
+        std::thread::sleep(STEP_DELAY);
+    }
+    Ok(())
+}
+
+/// Map an autotype key onto enigo's key codes
+fn to_enigo_key(key: AutotypeKey) -> Key {
+    match key {
+        AutotypeKey::Tab => Key::Tab,
+        AutotypeKey::Enter => Key::Return,
+        AutotypeKey::Space => Key::Space,
+        AutotypeKey::Backspace => Key::Backspace,
+        AutotypeKey::Delete => Key::Delete,
+        AutotypeKey::Escape => Key::Escape,
+        AutotypeKey::Up => Key::UpArrow,
+        AutotypeKey::Down => Key::DownArrow,
+        AutotypeKey::Left => Key::LeftArrow,
+        AutotypeKey::Right => Key::RightArrow,
+        AutotypeKey::Home => Key::Home,
+        AutotypeKey::End => Key::End,
+    }
+}
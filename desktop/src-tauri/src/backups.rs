@@ -0,0 +1,96 @@
+//! # Backup Restore Confirmation
+//! 
+//! Restoring a backup replaces every account in the vault, so it takes
+//! two steps: `request_restore` hands out a confirmation token for one
+//! backup, and `restore_backup` only proceeds with that token. Tokens are
+//! single-use, expire after `TOKEN_TTL`, and a newer request replaces an
+//! older one, so a stray or replayed call can't overwrite the vault.
+
+use passman_backend::{diff::VaultDiff, signing::SignatureStatus, storage::BackupInfo};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a restore confirmation token is valid
+pub const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// A pending restore the user has to confirm
+#[derive(Serialize)]
+pub struct RestoreConfirmation {
+    /// Token to pass to `restore_backup`
+    pub token: String,
+    
+    /// The backup that would be restored
+    pub backup: BackupInfo,
+    
+    /// Its signature, checked against the vault's signing key
+    pub signature: SignatureStatus,
+    
+    /// What changed since the backup, i.e. what restoring it undoes (None
+    /// if the backup can't be opened with the current session)
+    pub changes: Option<VaultDiff>,
+    
+    /// Seconds until the token expires
+    pub expires_in_seconds: u64,
+}
+
+/// The outstanding restore token, if any
+#[derive(Default)]
+pub struct PendingRestore {
+    pending: Mutex<Option<Pending>>,
+}
+
+struct Pending {
+    token: String,
+    timestamp: String,
+    issued: Instant,
+}
+
+impl PendingRestore {
+    /// Issue a confirmation token for restoring a backup
+    /// 
+    /// # Arguments
+    /// * `backup` - The backup to restore
+    /// * `signature` - Status of the backup's signature
+    /// * `changes` - What changed since the backup, if known
+    pub fn issue(&self, backup: BackupInfo, signature: SignatureStatus, changes: Option<VaultDiff>) -> RestoreConfirmation {
+        let token = Uuid::new_v4().simple().to_string();
+        *self.lock() = Some(Pending {
+            token: token.clone(),
+            timestamp: backup.timestamp.clone(),
+            issued: Instant::now(),
+        });
+        RestoreConfirmation {
+            token,
+            backup,
+            signature,
+            changes,
+            expires_in_seconds: TOKEN_TTL.as_secs(),
+        }
+    }
+    
+    /// Use up a confirmation token
+    /// 
+    /// # Arguments
+    /// * `token` - Token from `issue`
+    /// * `timestamp` - Backup the caller wants to restore
+    /// 
+    /// # Errors
+    /// Returns an error if the token is unknown, expired, or for another backup
+    pub fn redeem(&self, token: &str, timestamp: &str) -> Result<(), String> {
+        let pending = self.lock().take()
+            .ok_or("No restore is waiting for confirmation")?;
+        if pending.token != token || pending.timestamp != timestamp {
+            return Err("Invalid restore confirmation".to_string());
+        }
+        if pending.issued.elapsed() > TOKEN_TTL {
+            return Err("Restore confirmation expired; please try again".to_string());
+        }
+        Ok(())
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Pending>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
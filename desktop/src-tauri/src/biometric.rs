@@ -0,0 +1,181 @@
+//! # Biometric Unlock
+//! 
+//! Daily unlocks without typing the master password. Enabling biometric
+//! unlock adds a device key slot to the vault (see
+//! `PassMan::enroll_device_key`) and stores that random key in the OS
+//! keystore; unlocking asks the OS for a biometric check, then reads the
+//! key back and unwraps the vault key with it. The keystore never holds
+//! the vault key itself, and disabling removes the slot so a copied
+//! device key is useless.
+//! 
+//! The OS check uses Touch ID on macOS, Windows Hello on Windows, and
+//! fprintd (`fprintd-verify`) on Linux. The Linux kernel keyring doesn't
+//! outlive the login session, so after a reboot the first unlock uses the
+//! master password, which stores a fresh device key.
+
+use crate::error::CommandError;
+use passman_backend::{PassMan, crypto::SecureKey};
+use zeroize::Zeroizing;
+
+/// Keyring service under which device keys are stored
+const SERVICE: &str = "passman-desktop-device";
+/// Prompt shown by the OS biometric dialog
+const PROMPT: &str = "unlock your PassMan vault";
+
+/// Check whether this platform has a supported biometric check
+pub fn is_supported() -> bool {
+    platform::is_supported()
+}
+
+/// Ask the OS to verify the user biometrically
+/// 
+/// # Returns
+/// True if the user was verified, false if they cancelled or failed
+/// 
+/// # Errors
+/// Returns an error if no biometric check is available
+pub fn verify_user() -> Result<bool, String> {
+    platform::verify_user(PROMPT)
+}
+
+/// Enroll the open vault for biometric unlock on this device
+/// 
+/// # Errors
+/// Returns an error if the vault is locked, legacy, or the keystore fails
+pub fn enable(passman: &mut PassMan) -> Result<(), CommandError> {
+    let device_key = passman.enroll_device_key()?;
+    if let Err(e) = store_key(passman.vault_name(), &device_key) {
+        // Don't leave a slot behind that nothing can unlock
+        let _ = passman.remove_device_key();
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Turn off biometric unlock for the open vault
+/// 
+/// # Errors
+/// Returns an error if the vault is locked or the keystore fails
+pub fn disable(passman: &mut PassMan) -> Result<(), CommandError> {
+    passman.remove_device_key()?;
+    Ok(clear_key(passman.vault_name())?)
+}
+
+/// Store a new device key if biometric unlock is enabled but the keystore lost its key
+/// 
+/// Called after a master password unlock; failures only mean biometric
+/// unlock stays unavailable until the next attempt.
+pub fn refresh(passman: &mut PassMan) {
+    if passman.has_device_key().unwrap_or(false) && load_key(passman.vault_name()).is_none() {
+        let _ = enable(passman);
+    }
+}
+
+/// Check whether this device can unlock the vault biometrically
+pub fn is_enrolled(vault_name: &str) -> bool {
+    load_key(vault_name).is_some()
+}
+
+/// Read the device key of a vault from the keystore
+pub fn load_key(vault_name: &str) -> Option<SecureKey> {
+    let secret = Zeroizing::new(entry(vault_name).ok()?.get_secret().ok()?);
+    let key: [u8; 32] = secret.as_slice().try_into().ok()?;
+    Some(SecureKey::new(key))
+}
+
+/// Remove the device key of a vault from the keystore
+/// 
+/// # Errors
+/// Returns an error if the keystore is unavailable
+pub fn clear_key(vault_name: &str) -> Result<(), String> {
+    match entry(vault_name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// Save a device key in the keystore
+fn store_key(vault_name: &str, key: &SecureKey) -> Result<(), String> {
+    entry(vault_name)?.set_secret(key.as_bytes()).map_err(keyring_error)
+}
+
+/// Keyring entry holding a vault's device key
+fn entry(vault_name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, vault_name).map_err(keyring_error)
+}
+
+/// Describe a keyring error
+fn keyring_error(e: keyring::Error) -> String {
+    format!("Keystore unavailable: {}", e)
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+mod platform {
+    use robius_authentication::{
+        AndroidText, BiometricStrength, Context, Error, PolicyBuilder, Text, WindowsText,
+    };
+    
+    pub fn is_supported() -> bool {
+        true
+    }
+    
+    pub fn verify_user(prompt: &str) -> Result<bool, String> {
+        // Biometrics only: a device password fallback would let anyone
+        // who knows the OS login into the vault
+        let policy = PolicyBuilder::new()
+            .biometrics(Some(BiometricStrength::Strong))
+            .password(false)
+            .watch(false)
+            .build()
+            .ok_or("Biometric unlock is not supported on this device")?;
+        let text = Text {
+            android: AndroidText { title: "PassMan", subtitle: None, description: None },
+            apple: prompt,
+            windows: WindowsText::new_truncated("PassMan", prompt),
+        };
+        
+        match Context::new(()).blocking_authenticate(text, &policy) {
+            Ok(()) => Ok(true),
+            Err(Error::Authentication | Error::UserCanceled | Error::AppCanceled | Error::SystemCanceled) => Ok(false),
+            Err(e) => Err(format!("Biometric check unavailable: {:?}", e)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::{Command, Stdio};
+    
+    pub fn is_supported() -> bool {
+        // fprintd-list succeeds without enrolled fingers too, so check its listing
+        let user = std::env::var("USER").unwrap_or_default();
+        Command::new("fprintd-list")
+            .arg(user)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .is_ok_and(|output| output.status.success()
+                && String::from_utf8_lossy(&output.stdout).contains("Fingerprints for user"))
+    }
+    
+    pub fn verify_user(_prompt: &str) -> Result<bool, String> {
+        let status = Command::new("fprintd-verify")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| format!("Biometric check unavailable (fprintd): {}", e))?;
+        Ok(status.success())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    pub fn is_supported() -> bool {
+        false
+    }
+    
+    pub fn verify_user(_prompt: &str) -> Result<bool, String> {
+        Err("Biometric unlock is not supported on this platform".to_string())
+    }
+}
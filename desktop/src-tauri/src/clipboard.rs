@@ -0,0 +1,96 @@
+//! # Clipboard Copies
+//! 
+//! `copy_password` puts an account's password on the OS clipboard from the
+//! backend, so the secret never passes through the webview. The copy is
+//! marked as sensitive (see the backend's `clipboard` module) and, when the
+//! vault's `auto_clear_clipboard` setting is on, cleared after
+//! `clipboard_timeout` seconds if the clipboard still holds it:
+//! 
+//! - `clipboard-cleared` with `{ "id": "<account id>" }` once it is cleared
+//! 
+//! Each copy gets its own thread, which owns the clipboard contents until
+//! they are cleared (on X11 copied text disappears with its owner).
+
+use crate::error::CommandError;
+use crate::reveal;
+use crate::state::VaultState;
+use passman_backend::clipboard::Clipboard;
+use serde::Serialize;
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// Event emitted after a copied password has been cleared from the clipboard
+pub const CLEARED_EVENT: &str = "clipboard-cleared";
+
+/// Result of a copy
+#[derive(Serialize)]
+pub struct CopiedPassword {
+    /// Account ID
+    pub id: String,
+    
+    /// Seconds until the clipboard is cleared, or None if it isn't
+    pub clears_in_seconds: Option<u64>,
+}
+
+/// Payload of the `clipboard-cleared` event
+#[derive(Clone, Serialize)]
+pub struct ClearedPayload {
+    /// Account whose password was cleared
+    pub id: String,
+}
+
+/// Copy an account's password to the clipboard and schedule the clear
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `id` - Account to copy
+/// * `master_password` - Master password, required if the vault asks for confirmation
+/// 
+/// # Errors
+/// Returns an error if the vault is locked, the account doesn't exist, a
+/// required master password is missing or wrong, or the clipboard can't
+/// be used
+pub fn copy_password(app: &AppHandle, id: Uuid, master_password: Option<&str>) -> Result<CopiedPassword, CommandError> {
+    let (password, timeout) = app.state::<VaultState>().with_vault(|passman| {
+        let password = Zeroizing::new(reveal::account_password(passman, id, master_password)?);
+        let timeout = passman.get_vault_metadata()
+            .map(|metadata| &metadata.settings)
+            .filter(|settings| settings.auto_clear_clipboard)
+            .map(|settings| Duration::from_secs(settings.clipboard_timeout as u64));
+        Ok((password, timeout))
+    })?;
+    
+    // The thread owns the clipboard; it reports whether the copy worked
+    let (copied_tx, copied_rx) = mpsc::channel();
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut clipboard = match Clipboard::new().and_then(|mut clipboard| clipboard.copy(&password).map(|()| clipboard)) {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                let _ = copied_tx.send(Err(e));
+                return;
+            }
+        };
+        let _ = copied_tx.send(Ok(()));
+        
+        let Some(timeout) = timeout else {
+            return;
+        };
+        match clipboard.clear_after(&password, timeout) {
+            Ok(true) => {
+                let _ = app.emit(CLEARED_EVENT, ClearedPayload { id: id.to_string() });
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    });
+    
+    copied_rx.recv().map_err(|e| e.to_string())??;
+    Ok(CopiedPassword {
+        id: id.to_string(),
+        clears_in_seconds: timeout.map(|timeout| timeout.as_secs()),
+    })
+}
@@ -0,0 +1,66 @@
+//! # Command Errors
+//! 
+//! Tauri commands fail with a `CommandError`, which reaches the frontend
+//! as the backend's `ErrorInfo` object (`code`, `kind`, `message`, and
+//! the broken rules of a policy violation) instead of a bare string, so
+//! the UI can tell e.g. a wrong password from a lockout without parsing
+//! messages. Errors that don't come from the backend get kind `other`.
+
+use passman_backend::{ErrorInfo, PassManError};
+use serde::Serialize;
+use std::fmt;
+
+/// Code of errors that don't come from the backend
+pub const OTHER_CODE: u16 = 0;
+
+/// Error returned by Tauri commands
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct CommandError(ErrorInfo);
+
+impl CommandError {
+    /// Stable name of the error kind (see `PassManError::kind`)
+    pub fn kind(&self) -> &str {
+        &self.0.kind
+    }
+}
+
+impl From<PassManError> for CommandError {
+    fn from(error: PassManError) -> Self {
+        Self(error.info())
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        PassManError::from(error).into()
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self(ErrorInfo {
+            code: OTHER_CODE,
+            kind: "other".to_string(),
+            message,
+            violations: Vec::new(),
+        })
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.message)
+    }
+}
+
+/// Error returned while no vault is open
+pub fn vault_locked() -> CommandError {
+    PassManError::VaultLocked("unlock the vault first".to_string()).into()
+}
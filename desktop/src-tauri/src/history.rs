@@ -0,0 +1,95 @@
+//! # Generation History
+//! 
+//! A password that was generated but never saved to an account is easy
+//! to lose. Every password from `generate_password` is kept in memory
+//! (the newest `GENERATION_HISTORY_LIMIT` of them) until the app quits,
+//! and when the vault's `keep_generation_history` setting is on it is
+//! also stored in the encrypted vault so it survives restarts.
+//! 
+//! Passwords can be generated while the vault is locked, but the history
+//! is only handed out while it is unlocked.
+
+use crate::error::CommandError;
+use crate::state::VaultState;
+use chrono::{DateTime, Utc};
+use passman_backend::{GeneratedPassword, vault::GENERATION_HISTORY_LIMIT};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use zeroize::Zeroizing;
+
+/// Passwords generated since the app started
+#[derive(Default)]
+pub struct GenerationHistory {
+    /// Newest first
+    entries: Mutex<VecDeque<Entry>>,
+}
+
+struct Entry {
+    password: Zeroizing<String>,
+    generated_at: DateTime<Utc>,
+}
+
+impl GenerationHistory {
+    /// Remember a generated password
+    /// 
+    /// # Arguments
+    /// * `state` - Shared vault, which also records it if the setting is on
+    /// * `password` - The generated password
+    pub fn record(&self, state: &VaultState, password: &str) {
+        let mut entries = self.lock();
+        entries.push_front(Entry {
+            password: Zeroizing::new(password.to_string()),
+            generated_at: Utc::now(),
+        });
+        entries.truncate(GENERATION_HISTORY_LIMIT);
+        drop(entries);
+        
+        if state.is_open() {
+            if let Err(e) = state.with_vault(|passman| {
+                passman.record_generated_password(password).map_err(CommandError::from)
+            }) {
+                eprintln!("Failed to save generation history: {}", e);
+            }
+        }
+    }
+    
+    /// Get the generation history, merged with the one kept in the vault
+    /// 
+    /// # Returns
+    /// Recently generated passwords, newest first
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is locked
+    pub fn list(&self, state: &VaultState) -> Result<Vec<GeneratedPassword>, CommandError> {
+        let mut history = state.with_vault(|passman| {
+            passman.generation_history().map(<[_]>::to_vec).map_err(CommandError::from)
+        })?;
+        for entry in self.lock().iter() {
+            // Passwords generated while unlocked are in both lists
+            if !history.iter().any(|stored| stored.password == *entry.password) {
+                history.push(GeneratedPassword {
+                    password: entry.password.to_string(),
+                    generated_at: entry.generated_at,
+                });
+            }
+        }
+        
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.generated_at));
+        history.truncate(GENERATION_HISTORY_LIMIT);
+        Ok(history)
+    }
+    
+    /// Forget the generation history, including the one kept in the vault
+    /// 
+    /// # Errors
+    /// Returns an error if the vault is locked or can't be saved
+    pub fn clear(&self, state: &VaultState) -> Result<(), CommandError> {
+        state.with_vault(|passman| passman.clear_generation_history().map_err(CommandError::from))?;
+        self.lock().clear();
+        Ok(())
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
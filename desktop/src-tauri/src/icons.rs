@@ -0,0 +1,80 @@
+//! # Site Icons
+//! 
+//! Favicons make long account lists easier to scan. Fetching one tells
+//! the site (and anyone watching the network) that the user has an
+//! account there, so nothing is fetched unless the vault's `fetch_icons`
+//! setting is on.
+//! 
+//! Icons are downloaded with the backend's `appearance::favicon`, which
+//! only contacts the site itself, and cached in the user's cache directory
+//! for `CACHE_TTL`, misses included. Cache files
+//! are named by a hash of the host so the directory doesn't list the
+//! user's sites in the clear.
+
+use passman_backend::appearance::favicon;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// How long a cached icon (or a cached miss) is used before refetching
+const CACHE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Get the icon of a site, fetching it on a cache miss
+/// 
+/// # Arguments
+/// * `host` - Host of the site (see `Account::host`)
+/// 
+/// # Returns
+/// The icon bytes, or None if the site has no usable icon
+pub fn get_icon(host: &str) -> Option<Vec<u8>> {
+    if !is_valid_host(host) {
+        return None;
+    }
+    let path = cache_path(host);
+    
+    if let Some(ref path) = path {
+        let fresh = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| SystemTime::now().duration_since(modified).unwrap_or_default() < CACHE_TTL);
+        if fresh {
+            // An empty file records a miss
+            return fs::read(path).ok().filter(|bytes| !bytes.is_empty());
+        }
+    }
+    
+    let icon = favicon::fetch_favicon(host).ok().map(|icon| icon.data);
+    if let Some(path) = path {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(path, icon.as_deref().unwrap_or_default());
+    }
+    icon
+}
+
+/// Delete all cached icons (used when icon fetching is turned off)
+pub fn clear_cache() {
+    if let Some(dir) = cache_dir() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+/// Check that a host is a plain domain name or address
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+/// Directory holding cached icons
+fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("passman").join("icons"))
+}
+
+/// Cache file of a host's icon
+fn cache_path(host: &str) -> Option<PathBuf> {
+    let digest = Sha256::digest(host.as_bytes());
+    let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Some(cache_dir()?.join(name))
+}
@@ -1,135 +1,332 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use passman_backend::{PassMan, models::{Account, AccountType, PasswordOptions}};
+mod autolock;
+mod autotype;
+mod backups;
+mod biometric;
+mod clipboard;
+mod error;
+mod history;
+mod reminders;
+mod icons;
+mod reveal;
+mod state;
+mod transfer;
+mod tray;
+mod watcher;
+
+use passman_backend::{AsyncPassMan, PassMan, PassManConfig, PassManError, appearance::favicon, audit::{AuditOptions, HealthReport}, breach, generator::{PasswordCandidate, PasswordGenerator}, policy::SitePolicy, storage::BackupInfo, strength::StrengthEstimate, models::{Account, AccountPatch, AccountQuery, AccountType, CustomField, DedupePolicy, GeneratedPassword, PasswordOptions, SortOrder, UsernameOptions, VaultSettings}};
+use backups::{PendingRestore, RestoreConfirmation};
+use clipboard::CopiedPassword;
+use error::CommandError;
+use history::GenerationHistory;
+use reveal::{RevealedPassword, Reveals};
+use state::{VaultState, with_vault_blocking};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use transfer::{ImportReport, TransferFormat};
+use tray::QuickSearchResult;
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
-async fn greet(name: &str) -> Result<String, String> {
+async fn greet(name: &str) -> Result<String, CommandError> {
     Ok(format!("Hello, {}! You've been greeted from Rust!", name))
 }
 
-// Account management commands
-#[tauri::command]
-async fn create_account(email: String, masterPassword: String) -> Result<(), String> {
-    // Create a simple account file with email and hashed password
-    let account_data = serde_json::json!({
-        "email": email,
-        "password_hash": format!("{:x}", md5::compute(&masterPassword)), // Simple hash for demo
-        "created_at": chrono::Utc::now().to_rfc3339()
-    });
-    
-    let account_path = std::env::var("HOME").unwrap_or_else(|_| ".".to_string()) + "/.passman/account.json";
-    std::fs::create_dir_all(std::path::Path::new(&account_path).parent().unwrap())
-        .map_err(|e| e.to_string())?;
-    
-    std::fs::write(&account_path, serde_json::to_string_pretty(&account_data).unwrap())
-        .map_err(|e| e.to_string())?;
+/// Vault the desktop app keeps the user's accounts in
+const DESKTOP_VAULT: &str = "main";
+
+/// Pre-vault login file written by older desktop builds (email + md5 hash)
+fn legacy_account_path() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+        .join(".passman")
+        .join("account.json")
+}
+
+/// Move a user of the old `account.json` login onto the encrypted vault
+/// 
+/// The md5 hash is only used to check the password once. If the vault is
+/// missing it is created with the account's email; then the file is removed.
+/// 
+/// # Errors
+/// Returns an authentication error if the password is wrong
+fn migrate_legacy_account(passman: &mut PassMan, master_password: &str) -> Result<(), CommandError> {
+    let account_path = legacy_account_path();
+    let account_data: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&account_path)?
+    ).map_err(|e| e.to_string())?;
     
-    // Initialize the vault after creating the account
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    println!("DEBUG: Attempting to initialize vault for email: {}", email);
-    match passman.init_vault(email.clone(), &masterPassword) {
-        Ok(_) => {
-            println!("DEBUG: Vault created successfully");
-        }
-        Err(e) => {
-            println!("DEBUG: Vault init error: {}", e);
-            // If vault already exists, try to open it instead
-            if e.to_string().contains("already exists") {
-                println!("DEBUG: Vault already exists, trying to open it");
-                passman.open_vault(&masterPassword).map_err(|e| {
-                    println!("DEBUG: Failed to open existing vault: {}", e);
-                    e.to_string()
-                })?;
-                println!("DEBUG: Successfully opened existing vault");
-            } else {
-                println!("DEBUG: Vault init failed with error: {}", e);
-                return Err(e.to_string());
-            }
+    if vault_exists()? {
+        // The vault's own Argon2 + AEAD check is authoritative
+        passman.open_vault(master_password)?;
+    } else {
+        let stored_hash = account_data["password_hash"].as_str().unwrap_or("");
+        if stored_hash != format!("{:x}", md5::compute(master_password)) {
+            return Err(PassManError::AuthenticationFailed("Invalid master password".to_string()).into());
         }
+        let email = account_data["email"].as_str().unwrap_or_default().to_string();
+        passman.init_vault(email, master_password)?;
     }
     
+    std::fs::remove_file(&account_path)?;
     Ok(())
 }
 
-#[tauri::command]
-async fn check_account_exists() -> Result<bool, String> {
-    let account_path = std::env::var("HOME").unwrap_or_else(|_| ".".to_string()) + "/.passman/account.json";
-    Ok(std::path::Path::new(&account_path).exists())
+/// Check whether the desktop vault has been created
+fn vault_exists() -> Result<bool, CommandError> {
+    let vaults = PassMan::list_vaults()?;
+    Ok(vaults.iter().any(|name| name == DESKTOP_VAULT))
 }
 
+// Account management commands
 #[tauri::command]
-async fn verify_password(masterPassword: String) -> Result<bool, String> {
-    let account_path = std::env::var("HOME").unwrap_or_else(|_| ".".to_string()) + "/.passman/account.json";
-    
-    if !std::path::Path::new(&account_path).exists() {
-        return Ok(false);
+async fn create_account(app: AppHandle, email: String, masterPassword: String) -> Result<(), CommandError> {
+    // The vault itself is the account: Argon2id-derived key, AEAD payload
+    if vault_exists()? {
+        // Setting up again only checks the existing vault's password, and
+        // counts a wrong one like a failed unlock; the login screen follows
+        tauri::async_runtime::spawn_blocking(move || {
+            app.state::<VaultState>().unlock_with(DESKTOP_VAULT, |passman| {
+                passman.open_vault(&masterPassword)?;
+                passman.close_vault();
+                Ok(())
+            })
+        }).await.map_err(|e| e.to_string())??;
+    } else {
+        let passman = AsyncPassMan::new(DESKTOP_VAULT).await?;
+        passman.init_vault(email, &masterPassword).await?;
     }
     
-    let account_data: serde_json::Value = serde_json::from_str(
-        &std::fs::read_to_string(&account_path).map_err(|e| e.to_string())?
-    ).map_err(|e| e.to_string())?;
-    
-    let stored_hash = account_data["password_hash"].as_str().unwrap_or("");
-    let input_hash = format!("{:x}", md5::compute(masterPassword));
+    // A leftover pre-vault login file is superseded by the vault
+    let account_path = legacy_account_path();
+    if account_path.exists() {
+        std::fs::remove_file(&account_path)?;
+    }
     
-    Ok(stored_hash == input_hash)
+    Ok(())
 }
 
 #[tauri::command]
-async fn reset_passman() -> Result<(), String> {
+async fn check_account_exists() -> Result<bool, CommandError> {
+    Ok(vault_exists()? || legacy_account_path().exists())
+}
+
+/// Start over after a forgotten master password
+/// 
+/// Nothing is deleted: the vault is renamed, with its backups, to
+/// `main-reset-<timestamp>`, where the CLI can still open it if the
+/// password turns up. The login screen is locked, so instead of the
+/// master password the user has to type the vault's name.
+/// 
+/// Returns the name the vault was moved to, or None if there was none.
+#[tauri::command]
+async fn reset_passman(state: State<'_, VaultState>, confirmation: String) -> Result<Option<String>, CommandError> {
+    if confirmation.trim() != DESKTOP_VAULT {
+        return Err(format!("Type '{}' to confirm the reset", DESKTOP_VAULT).into());
+    }
+    
     let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
     let passman_dir = format!("{}/.passman", home_dir);
     
-    // Remove the entire .passman directory
+    // Remove the entire .passman directory (the pre-vault login file)
     if std::path::Path::new(&passman_dir).exists() {
-        std::fs::remove_dir_all(&passman_dir).map_err(|e| e.to_string())?;
+        std::fs::remove_dir_all(&passman_dir)?;
     }
     
-    Ok(())
+    if !vault_exists()? {
+        return Ok(None);
+    }
+    state.discard();
+    let moved_to = format!("{}-reset-{}", DESKTOP_VAULT, chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+    PassMan::rename_vault(DESKTOP_VAULT, &moved_to)?;
+    Ok(Some(moved_to))
 }
 
 // Vault management commands
 #[tauri::command]
-async fn init_vault(email: String, master_password: String) -> Result<(), String> {
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    passman.init_vault(email, &master_password).map_err(|e| e.to_string())?;
+async fn init_vault(app: AppHandle, email: String, master_password: String) -> Result<(), CommandError> {
+    let passman = AsyncPassMan::new(DESKTOP_VAULT).await?;
+    passman.init_vault(email, &master_password).await?;
+    // The new vault is open; keep it for the commands that follow
+    let passman = passman.into_inner().map_err(|_| "Vault is still in use".to_string())?;
+    autolock::unlock(&app, passman);
     Ok(())
 }
 
+/// Unlock the vault with the master password
+/// 
+/// Every attempt goes through the same kept manager (see `VaultState`),
+/// so wrong passwords back off and lock out as the backend intends. A
+/// user of the old `account.json` login is moved onto the vault here.
 #[tauri::command]
-async fn open_vault(masterPassword: String) -> Result<(), String> {
-    println!("DEBUG: Attempting to open vault");
-    let mut passman = PassMan::new("main").map_err(|e| {
-        println!("DEBUG: Failed to create PassMan instance: {}", e);
-        e.to_string()
-    })?;
-    println!("DEBUG: PassMan instance created, attempting to open vault");
-    passman.open_vault(&masterPassword).map_err(|e| {
-        println!("DEBUG: Failed to open vault: {}", e);
-        e.to_string()
-    })?;
-    println!("DEBUG: Vault opened successfully");
+async fn open_vault(app: AppHandle, masterPassword: String) -> Result<(), CommandError> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<VaultState>().unlock_with(DESKTOP_VAULT, |passman| {
+            if legacy_account_path().exists() {
+                migrate_legacy_account(passman, &masterPassword)?;
+            } else {
+                passman.open_vault(&masterPassword)?;
+            }
+            // Talks to the OS keystore, so it stays on the blocking pool
+            biometric::refresh(passman);
+            Ok(())
+        })
+    }).await.map_err(|e| e.to_string())??;
+    autolock::unlocked(&app);
     Ok(())
 }
 
 #[tauri::command]
-async fn close_vault() -> Result<(), String> {
-    // In a real implementation, you'd manage the vault instance globally
+async fn close_vault(app: AppHandle) -> Result<(), CommandError> {
+    autolock::lock(&app, "manual");
     Ok(())
 }
 
 #[tauri::command]
-async fn is_vault_open() -> Result<bool, String> {
-    // In a real implementation, you'd check the global vault state
-    Ok(false)
+async fn is_vault_open(state: State<'_, VaultState>) -> Result<bool, CommandError> {
+    Ok(state.is_open())
+}
+
+/// Whether biometric unlock can be offered and is turned on
+#[derive(serde::Serialize)]
+struct BiometricStatus {
+    /// The OS has a usable biometric check
+    supported: bool,
+    
+    /// The vault has a device key slot
+    enabled: bool,
+    
+    /// This device holds the device key, so unlocking can skip the password
+    available: bool,
+}
+
+#[tauri::command]
+async fn get_biometric_status() -> Result<BiometricStatus, CommandError> {
+    let enabled = vault_exists()? && PassMan::new(DESKTOP_VAULT)
+        .and_then(|passman| passman.has_device_key())
+        ?;
+    let supported = tauri::async_runtime::spawn_blocking(biometric::is_supported)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(BiometricStatus {
+        supported,
+        enabled,
+        available: supported && enabled && biometric::is_enrolled(DESKTOP_VAULT),
+    })
+}
+
+/// Unlock with the OS biometric check instead of the master password
+/// 
+/// Returns false when the frontend should fall back to the master
+/// password: biometrics unavailable, not enrolled, or not verified.
+#[tauri::command]
+async fn unlock_with_biometrics(app: AppHandle) -> Result<bool, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<bool, CommandError> {
+        let Some(device_key) = biometric::load_key(DESKTOP_VAULT) else {
+            return Ok(false);
+        };
+        if !biometric::is_supported() || !biometric::verify_user()? {
+            return Ok(false);
+        }
+        
+        let mut passman = PassMan::new(DESKTOP_VAULT)?;
+        match passman.open_vault_with_device_key(&device_key) {
+            Ok(()) => {}
+            Err(PassManError::AuthenticationFailed(_)) => {
+                // Biometrics were disabled elsewhere; the stored key is stale
+                biometric::clear_key(DESKTOP_VAULT)?;
+                return Ok(false);
+            }
+            Err(e) => return Err(e.into()),
+        }
+        autolock::unlock(&app, passman);
+        Ok(true)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn enable_biometric_unlock(app: AppHandle) -> Result<(), CommandError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<(), CommandError> {
+        // Make sure the check works before relying on it
+        if !biometric::is_supported() || !biometric::verify_user()? {
+            return Err("Biometric verification failed".into());
+        }
+        app.state::<VaultState>().with_vault(biometric::enable)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn disable_biometric_unlock(app: AppHandle) -> Result<(), CommandError> {
+    with_vault_blocking(&app, biometric::disable).await
+}
+
+/// Pick up changes other programs saved to the vault file
+/// 
+/// Returns true if the vault on disk had changed.
+#[tauri::command]
+async fn reload_vault(app: AppHandle) -> Result<bool, CommandError> {
+    with_vault_blocking(&app, |passman| passman.reload_vault().map_err(CommandError::from)).await
+}
+
+/// Postpone auto-lock (e.g. from the lock warning's "stay unlocked" button)
+#[tauri::command]
+async fn keep_alive(state: State<'_, VaultState>) -> Result<(), CommandError> {
+    if !state.is_open() {
+        return Err(error::vault_locked());
+    }
+    state.touch();
+    Ok(())
+}
+
+/// Seconds until the vault locks for inactivity, or None if auto-lock is off
+#[tauri::command]
+async fn get_lock_remaining(state: State<'_, VaultState>) -> Result<Option<u64>, CommandError> {
+    if !state.is_open() {
+        return Err(error::vault_locked());
+    }
+    Ok(autolock::seconds_remaining(&state))
+}
+
+// Settings commands (stored in the encrypted vault metadata)
+#[tauri::command]
+async fn get_settings(state: State<'_, VaultState>) -> Result<VaultSettings, CommandError> {
+    state.with_vault(|passman| {
+        passman.get_vault_metadata()
+            .map(|metadata| metadata.settings.clone())
+            .ok_or_else(error::vault_locked)
+    })
+}
+
+#[tauri::command]
+async fn update_settings(app: AppHandle, settings: VaultSettings) -> Result<(), CommandError> {
+    // The auto-lock watcher reads the timeout from the vault, so this applies immediately
+    let fetch_icons = settings.fetch_icons;
+    with_vault_blocking(&app, |passman| passman.update_settings(settings).map_err(CommandError::from)).await?;
+    if !fetch_icons {
+        icons::clear_cache();
+    }
+    Ok(())
+}
+
+// Library configuration (config.toml, shared with the CLI)
+#[tauri::command]
+async fn get_config() -> Result<PassManConfig, CommandError> {
+    PassManConfig::load().map_err(CommandError::from)
+}
+
+/// Save the library configuration; it applies to vaults opened afterwards
+#[tauri::command]
+async fn update_config(config: PassManConfig) -> Result<(), CommandError> {
+    config.save().map_err(CommandError::from)
 }
 
 // Account management commands
 #[tauri::command]
 async fn add_account(
+    app: AppHandle,
     name: String,
     account_type: AccountType,
     password: String,
@@ -137,79 +334,282 @@ async fn add_account(
     username: Option<String>,
     notes: Option<String>,
     tags: Vec<String>,
-    masterPassword: Option<String>,
-) -> Result<(), String> {
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    
-    // If master password is provided, try to open the vault
-    if let Some(master_pwd) = masterPassword {
-        passman.open_vault(&master_pwd).map_err(|e| e.to_string())?;
-    } else {
-        return Err("Master password is required to add accounts.".to_string());
+    color: Option<String>,
+    icon: Option<String>,
+    autotype: Option<String>,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<(), CommandError> {
+    let mut account = Account::new(name, account_type, password);
+    account.url = url;
+    account.username = username;
+    account.notes = notes;
+    account.tags = tags;
+    account.color = color;
+    account.icon = icon;
+    account.custom_fields = custom_fields.unwrap_or_default();
+    if let Some(sequence) = autotype.filter(|sequence| !sequence.trim().is_empty()) {
+        passman_backend::autotype::validate_sequence(&sequence)?;
+        account.autotype = Some(sequence);
     }
-    
-    passman.add_account(name, account_type, password, url, username, notes, tags).map_err(|e| e.to_string())?;
-    Ok(())
+    with_vault_blocking(&app, |passman| {
+        passman.add_account_entry(account)
+            .map(|_| ())
+            .map_err(CommandError::from)
+    }).await
 }
 
 #[tauri::command]
-async fn list_accounts(masterPassword: String) -> Result<Vec<Account>, String> {
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    
-    // Open the vault with the master password
-    passman.open_vault(&masterPassword).map_err(|e| e.to_string())?;
+async fn list_accounts(state: State<'_, VaultState>) -> Result<Vec<Account>, CommandError> {
+    state.with_vault(|passman| Ok(passman.get_all_accounts().into_iter().cloned().collect()))
+}
+
+#[tauri::command]
+async fn search_accounts(state: State<'_, VaultState>, query: String) -> Result<Vec<Account>, CommandError> {
+    state.with_vault(|passman| Ok(passman.search_accounts(&query).into_iter().cloned().collect()))
+}
+
+#[tauri::command]
+async fn get_account(state: State<'_, VaultState>, id: String) -> Result<Option<Account>, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    state.with_vault(|passman| Ok(passman.get_account(uuid).cloned()))
+}
+
+/// Change some fields of an account; fields left out of the patch are kept
+#[tauri::command]
+async fn update_account(app: AppHandle, id: String, patch: AccountPatch) -> Result<(), CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    with_vault_blocking(&app, move |passman| passman.patch_account(uuid, patch).map_err(CommandError::from)).await
+}
+
+/// Mark an account as a favorite, or unmark it; returns whether it is now one
+#[tauri::command]
+async fn toggle_favorite(app: AppHandle, id: String) -> Result<bool, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    with_vault_blocking(&app, move |passman| passman.toggle_favorite(uuid).map_err(CommandError::from)).await
+}
+
+/// Custom account categories defined in the vault
+#[tauri::command]
+async fn get_categories(state: State<'_, VaultState>) -> Result<Vec<String>, CommandError> {
+    state.with_vault(|passman| Ok(passman.get_categories().to_vec()))
+}
+
+/// Define a custom account category
+#[tauri::command]
+async fn add_category(app: AppHandle, name: String) -> Result<(), CommandError> {
+    with_vault_blocking(&app, move |passman| passman.add_category(&name).map_err(CommandError::from)).await
+}
+
+/// Rename a custom category; returns how many accounts moved along
+#[tauri::command]
+async fn rename_category(app: AppHandle, name: String, new_name: String) -> Result<usize, CommandError> {
+    with_vault_blocking(&app, move |passman| passman.rename_category(&name, &new_name).map_err(CommandError::from)).await
+}
+
+/// Delete a custom category; returns how many accounts became "Other"
+#[tauri::command]
+async fn remove_category(app: AppHandle, name: String) -> Result<usize, CommandError> {
+    with_vault_blocking(&app, move |passman| passman.remove_category(&name).map_err(CommandError::from)).await
+}
+
+/// Copy a password to the clipboard, clearing it after the vault's `clipboard_timeout`
+#[tauri::command]
+async fn copy_password(app: AppHandle, id: String, master_password: Option<String>) -> Result<CopiedPassword, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || clipboard::copy_password(&app, uuid, master_password.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Show an account's password for a limited time (see `reveal`)
+#[tauri::command]
+async fn reveal_password(app: AppHandle, id: String, master_password: Option<String>) -> Result<RevealedPassword, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    // Confirming the master password runs the KDF, so keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || reveal::reveal(&app, uuid, master_password.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_account(app: AppHandle, id: String) -> Result<(), CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    with_vault_blocking(&app, move |passman| passman.delete_account(uuid).map_err(CommandError::from)).await
+}
+
+/// Rules of the vault's password policy that a password breaks, as messages
+#[tauri::command]
+async fn password_policy_violations(state: State<'_, VaultState>, password: String) -> Result<Vec<String>, CommandError> {
+    state.with_vault(|passman| match passman.check_password_policy(&password) {
+        Ok(()) => Ok(Vec::new()),
+        Err(PassManError::PolicyViolation(violations)) => Ok(violations.iter().map(ToString::to_string).collect()),
+        Err(e) => Err(e.into()),
+    })
+}
+
+/// Count a use of an account, e.g. after its password was copied
+#[tauri::command]
+async fn record_access(app: AppHandle, id: String) -> Result<(), CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    with_vault_blocking(&app, move |passman| passman.record_access(uuid).map_err(CommandError::from)).await
+}
+
+/// Site icon of an account as raw image bytes (empty if there is none)
+/// 
+/// Only returns anything when the vault's `fetch_icons` setting is on.
+#[tauri::command]
+async fn get_account_icon(state: State<'_, VaultState>, id: String) -> Result<tauri::ipc::Response, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    let host = state.with_vault(|passman| {
+        let enabled = passman.get_vault_metadata().is_some_and(|metadata| metadata.settings.fetch_icons);
+        let account = passman.get_account(uuid)
+            .ok_or_else(|| format!("Account with ID {} not found", id))?;
+        Ok(account.host().filter(|_| enabled))
+    })?;
     
-    // Get all accounts
-    let accounts = passman.get_all_accounts().into_iter().cloned().collect();
-    Ok(accounts)
+    let icon = match host {
+        Some(host) => tauri::async_runtime::spawn_blocking(move || icons::get_icon(&host))
+            .await
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    Ok(tauri::ipc::Response::new(icon.unwrap_or_default()))
 }
 
+/// Download the favicon of an account's URL and keep it as the account's icon
+/// 
+/// Unlike `get_account_icon`, this runs only when the user asks for it,
+/// and the icon is stored in the vault instead of the cache.
 #[tauri::command]
-async fn search_accounts(query: String) -> Result<Vec<Account>, String> {
-    let passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    // In a real implementation, you'd authenticate first
-    Ok(passman.search_accounts(&query).into_iter().cloned().collect())
+async fn fetch_account_favicon(app: AppHandle, state: State<'_, VaultState>, id: String) -> Result<String, CommandError> {
+    let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+    let url = state.with_vault(|passman| {
+        let account = passman.get_account(uuid)
+            .ok_or_else(|| format!("Account with ID {} not found", id))?;
+        account.url.clone()
+            .ok_or_else(|| format!("Account '{}' has no URL", account.name).into())
+    })?;
+    
+    let favicon = tauri::async_runtime::spawn_blocking(move || favicon::fetch_favicon(&url))
+        .await
+        .map_err(|e| e.to_string())??;
+    let icon = favicon.data_url();
+    let patch = AccountPatch { icon: Some(Some(icon.clone())), ..AccountPatch::default() };
+    with_vault_blocking(&app, move |passman| passman.patch_account(uuid, patch).map_err(CommandError::from)).await?;
+    Ok(icon)
 }
 
+/// Site policy that applies to an account (its own, or its domain's)
 #[tauri::command]
-async fn get_account(id: String) -> Result<Option<Account>, String> {
-    let passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    // In a real implementation, you'd authenticate first
+async fn get_site_policy(state: State<'_, VaultState>, id: String) -> Result<Option<SitePolicy>, CommandError> {
     let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
-    Ok(passman.get_account(uuid).cloned())
+    state.with_vault(|passman| Ok(passman.site_policy(uuid)?.cloned()))
 }
 
+/// Set or clear what an account's website accepts as a password
 #[tauri::command]
-async fn update_account(
-    id: String,
-    name: String,
-    account_type: AccountType,
-    password: String,
-    url: Option<String>,
-    username: Option<String>,
-    notes: Option<String>,
-    tags: Vec<String>,
-) -> Result<(), String> {
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    // In a real implementation, you'd authenticate first
+async fn set_site_policy(app: AppHandle, id: String, policy: Option<SitePolicy>) -> Result<(), CommandError> {
     let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
-    passman.update_account(uuid, name, account_type, password, url, username, notes, tags).map_err(|e| e.to_string())?;
-    Ok(())
+    with_vault_blocking(&app, move |passman| passman.set_site_policy(uuid, policy).map_err(CommandError::from)).await
 }
 
+/// Set or clear a domain's site policy; returns the domain as stored
 #[tauri::command]
-async fn delete_account(id: String) -> Result<(), String> {
-    let mut passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    // In a real implementation, you'd authenticate first
+async fn set_domain_policy(app: AppHandle, domain: String, policy: Option<SitePolicy>) -> Result<String, CommandError> {
+    with_vault_blocking(&app, move |passman| passman.set_domain_policy(&domain, policy).map_err(CommandError::from)).await
+}
+
+// Autotype commands
+#[tauri::command]
+async fn set_autotype_sequence(app: AppHandle, id: String, sequence: Option<String>) -> Result<(), CommandError> {
     let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
-    passman.delete_account(uuid).map_err(|e| e.to_string())?;
-    Ok(())
+    let patch = AccountPatch { autotype: Some(sequence), ..AccountPatch::default() };
+    with_vault_blocking(&app, move |passman| passman.patch_account(uuid, patch).map_err(CommandError::from)).await
+}
+
+#[tauri::command]
+async fn get_autotype_shortcut() -> Result<String, CommandError> {
+    Ok(autotype::SHORTCUT.to_string())
+}
+
+// Tray commands
+#[tauri::command]
+async fn quick_search(state: State<'_, VaultState>, query: String) -> Result<Vec<QuickSearchResult>, CommandError> {
+    let query = AccountQuery {
+        search: Some(query),
+        sort: SortOrder::MostUsed,
+        favorites_first: true,
+        limit: Some(tray::QUICK_SEARCH_LIMIT),
+        ..AccountQuery::default()
+    };
+    state.with_vault(|passman| Ok(passman.query_accounts(&query).into_iter().map(QuickSearchResult::from).collect()))
+}
+
+#[tauri::command]
+async fn open_quick_search(app: AppHandle) -> Result<(), CommandError> {
+    tray::open_quick_search(&app).map_err(CommandError::from)
 }
 
+#[tauri::command]
+async fn hide_quick_search(app: AppHandle) -> Result<(), CommandError> {
+    tray::hide_quick_search(&app).map_err(CommandError::from)
+}
+
+#[tauri::command]
+async fn show_main_window(app: AppHandle) -> Result<(), CommandError> {
+    tray::show_main_window(&app).map_err(CommandError::from)
+}
+
+// Import/export commands (run off the async runtime; large files take a while)
+#[tauri::command]
+async fn import_file(
+    app: AppHandle,
+    path: PathBuf,
+    format: TransferFormat,
+    dry_run: bool,
+    dedupe_policy: DedupePolicy,
+) -> Result<ImportReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        transfer::import_file(&app, &path, format, dry_run, dedupe_policy)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn export_file(
+    app: AppHandle,
+    path: PathBuf,
+    format: TransferFormat,
+    filter: Option<AccountQuery>,
+) -> Result<usize, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        transfer::export_file(&app, &path, format, filter)
+    }).await.map_err(|e| e.to_string())?
+}
+
+// Security overview commands
+#[tauri::command]
+async fn get_health_report(state: State<'_, VaultState>, options: Option<AuditOptions>) -> Result<HealthReport, CommandError> {
+    let options = options.unwrap_or_default();
+    // Offline breach data, if the user installed it (`passman breaches install`)
+    let filter = breach::installed_filter()?;
+    let is_breached = |password: &str| filter.as_ref().is_some_and(|filter| filter.contains(password).unwrap_or(false));
+    state.with_vault(|passman| {
+        passman.health_report(&options, filter.is_some().then_some(&is_breached as &dyn Fn(&str) -> bool))
+            .map_err(CommandError::from)
+    })
+}
+
+/// Accounts whose password expired or expires within `within_days`, soonest first
+#[tauri::command]
+async fn get_expiring_accounts(state: State<'_, VaultState>, within_days: u32) -> Result<Vec<Account>, CommandError> {
+    state.with_vault(|passman| Ok(passman.get_expiring_accounts(within_days).into_iter().cloned().collect()))
+}
 
 // Password generation commands
 #[tauri::command]
 async fn generate_password(
+    state: State<'_, VaultState>,
+    history: State<'_, GenerationHistory>,
     length: usize,
     include_uppercase: bool,
     include_lowercase: bool,
@@ -217,8 +617,20 @@ async fn generate_password(
     include_special: bool,
     exclude_similar: bool,
     exclude_ambiguous: bool,
-) -> Result<String, String> {
-    let mut passman = PassMan::new("temp").map_err(|e| e.to_string())?;
+    exclude_chars: Option<String>,
+    min_uppercase: Option<usize>,
+    min_numbers: Option<usize>,
+    min_special: Option<usize>,
+    account_id: Option<String>,
+) -> Result<String, CommandError> {
+    // A new password for an existing account follows its site's policy
+    let policy = match account_id {
+        Some(id) => {
+            let uuid = id.parse().map_err(|_| "Invalid UUID".to_string())?;
+            state.with_vault(|passman| Ok(passman.site_policy(uuid)?.cloned()))?
+        }
+        None => None,
+    };
     let options = PasswordOptions {
         length,
         include_uppercase,
@@ -227,27 +639,65 @@ async fn generate_password(
         include_special,
         exclude_similar,
         exclude_ambiguous,
+        exclude_chars: exclude_chars.unwrap_or_default(),
+        min_uppercase: min_uppercase.unwrap_or(0),
+        min_numbers: min_numbers.unwrap_or(0),
+        min_special: min_special.unwrap_or(0),
     };
-    passman.generate_password(&options).map_err(|e| e.to_string())
+    let mut generator = PasswordGenerator::new();
+    let password = match policy {
+        Some(policy) => generator.generate_for_site(&options, &policy)?,
+        None => generator.generate(&options)?,
+    };
+    history.record(&state, &password);
+    Ok(password)
+}
+
+#[tauri::command]
+async fn generate_password_suggestions(options: PasswordOptions, count: usize) -> Result<Vec<PasswordCandidate>, CommandError> {
+    Ok(PasswordGenerator::new().generate_batch(&options, count)?)
+}
+
+#[tauri::command]
+async fn generate_username(options: Option<UsernameOptions>) -> Result<String, CommandError> {
+    Ok(PasswordGenerator::new().generate_username(&options.unwrap_or_default())?)
+}
+
+#[tauri::command]
+async fn get_generation_history(
+    state: State<'_, VaultState>,
+    history: State<'_, GenerationHistory>,
+) -> Result<Vec<GeneratedPassword>, CommandError> {
+    history.list(&state)
+}
+
+#[tauri::command]
+async fn clear_generation_history(
+    state: State<'_, VaultState>,
+    history: State<'_, GenerationHistory>,
+) -> Result<(), CommandError> {
+    history.clear(&state)
+}
+
+#[tauri::command]
+async fn calculate_password_strength(password: String) -> Result<u8, CommandError> {
+    Ok(PasswordGenerator::new().calculate_strength(&password))
 }
 
 #[tauri::command]
-async fn calculate_password_strength(password: String) -> Result<u8, String> {
-    let passman = PassMan::new("temp").map_err(|e| e.to_string())?;
-    Ok(passman.calculate_password_strength(&password))
+async fn estimate_password_strength(password: String) -> Result<StrengthEstimate, CommandError> {
+    Ok(PasswordGenerator::new().estimate_strength(&password))
 }
 
 #[tauri::command]
-async fn get_password_strength_description(score: u8) -> Result<String, String> {
-    let passman = PassMan::new("temp").map_err(|e| e.to_string())?;
-    Ok(passman.get_password_strength_description(score).to_string())
+async fn get_password_strength_description(score: u8) -> Result<String, CommandError> {
+    Ok(PasswordGenerator::new().get_strength_description(score).to_string())
 }
 
 // Vault information commands
 #[tauri::command]
-async fn get_vault_info() -> Result<HashMap<String, String>, String> {
-    let passman = PassMan::new("main").map_err(|e| e.to_string())?;
-    let (size, modified) = passman.get_vault_info().map_err(|e| e.to_string())?;
+async fn get_vault_info(state: State<'_, VaultState>) -> Result<HashMap<String, String>, CommandError> {
+    let (size, modified) = state.with_vault(|passman| Ok(passman.get_vault_info()?))?;
     let mut info = HashMap::new();
     info.insert("size".to_string(), size.to_string());
     if let Some(modified_time) = modified {
@@ -256,31 +706,160 @@ async fn get_vault_info() -> Result<HashMap<String, String>, String> {
     Ok(info)
 }
 
+// Backup commands
+#[tauri::command]
+async fn create_backup(app: AppHandle) -> Result<BackupInfo, CommandError> {
+    with_vault_blocking(&app, |passman| passman.create_backup().map_err(CommandError::from)).await
+}
+
+#[tauri::command]
+async fn list_backups(state: State<'_, VaultState>) -> Result<Vec<BackupInfo>, CommandError> {
+    state.with_vault(|passman| passman.list_backups().map_err(CommandError::from))
+}
+
+/// First step of a restore: get a token the frontend confirms with
+#[tauri::command]
+async fn request_restore(
+    state: State<'_, VaultState>,
+    pending: State<'_, PendingRestore>,
+    timestamp: String,
+) -> Result<RestoreConfirmation, CommandError> {
+    let backup = state.with_vault(|passman| {
+        passman.list_backups()
+            ?
+            .into_iter()
+            .find(|backup| backup.timestamp == timestamp)
+            .ok_or_else(|| format!("No backup from {}", timestamp).into())
+    })?;
+    let signature = state.with_vault(|passman| passman.verify_backup(&timestamp).map_err(CommandError::from))?;
+    let changes = state.with_vault(|passman| Ok(passman.diff_backup(&timestamp).ok()))?;
+    Ok(pending.issue(backup, signature, changes))
+}
+
+/// Replace the vault with a backup
+/// 
+/// Returns false if the restored vault needs to be unlocked again (it
+/// predates a master password change); the app is then locked.
+#[tauri::command]
+async fn restore_backup(app: AppHandle, timestamp: String, confirmation_token: String) -> Result<bool, CommandError> {
+    let state = app.state::<VaultState>();
+    if !state.is_open() {
+        return Err(error::vault_locked());
+    }
+    app.state::<PendingRestore>().redeem(&confirmation_token, &timestamp)?;
+    
+    let still_open = with_vault_blocking(&app, move |passman| {
+        passman.restore_backup(&timestamp).map_err(CommandError::from)
+    }).await?;
+    if !still_open {
+        autolock::lock(&app, "restore");
+    }
+    Ok(still_open)
+}
+
+// Signing key commands (backups and exports are signed while one is set)
+#[tauri::command]
+async fn get_signing_key(state: State<'_, VaultState>) -> Result<Option<String>, CommandError> {
+    state.with_vault(|passman| Ok(passman.signing_public_key().map(str::to_string)))
+}
+
 #[tauri::command]
-async fn list_vaults() -> Result<Vec<String>, String> {
-    PassMan::list_vaults().map_err(|e| e.to_string())
+async fn generate_signing_key(app: AppHandle) -> Result<String, CommandError> {
+    with_vault_blocking(&app, |passman| passman.generate_signing_key().map_err(CommandError::from)).await
+}
+
+#[tauri::command]
+async fn remove_signing_key(app: AppHandle) -> Result<(), CommandError> {
+    with_vault_blocking(&app, |passman| passman.remove_signing_key().map_err(CommandError::from)).await
+}
+
+#[tauri::command]
+async fn list_vaults() -> Result<Vec<String>, CommandError> {
+    PassMan::list_vaults().map_err(CommandError::from)
 }
 
 fn main() {
     tauri::Builder::default()
+        .manage(VaultState::new())
+        .manage(Reveals::default())
+        .manage(PendingRestore::default())
+        .manage(GenerationHistory::default())
+        .setup(|app| {
+            autolock::spawn(app.handle().clone());
+            reminders::spawn(app.handle().clone(), DESKTOP_VAULT);
+            tray::build(app.handle())?;
+            autotype::register(app.handle())?;
+            if let Some(vault_path) = PassMan::new(DESKTOP_VAULT)?.vault_path() {
+                if let Err(e) = watcher::spawn(app.handle().clone(), vault_path.to_path_buf()) {
+                    eprintln!("Vault file watcher unavailable: {}", e);
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             create_account,
             check_account_exists,
-            verify_password,
             reset_passman,
             init_vault,
             open_vault,
             close_vault,
             is_vault_open,
+            reload_vault,
+            keep_alive,
+            get_lock_remaining,
+            get_settings,
+            update_settings,
+            get_config,
+            update_config,
+            get_biometric_status,
+            unlock_with_biometrics,
+            enable_biometric_unlock,
+            disable_biometric_unlock,
             add_account,
             list_accounts,
             search_accounts,
             get_account,
             update_account,
+            toggle_favorite,
+            get_categories,
+            add_category,
+            rename_category,
+            remove_category,
             delete_account,
+            record_access,
+            password_policy_violations,
+            get_site_policy,
+            set_site_policy,
+            set_domain_policy,
+            reveal_password,
+            copy_password,
+            get_account_icon,
+            fetch_account_favicon,
+            set_autotype_sequence,
+            get_autotype_shortcut,
+            import_file,
+            export_file,
+            quick_search,
+            open_quick_search,
+            hide_quick_search,
+            show_main_window,
+            get_health_report,
+            get_expiring_accounts,
+            create_backup,
+            list_backups,
+            request_restore,
+            restore_backup,
+            get_signing_key,
+            generate_signing_key,
+            remove_signing_key,
             generate_password,
+            generate_password_suggestions,
+            generate_username,
+            get_generation_history,
+            clear_generation_history,
             calculate_password_strength,
+            estimate_password_strength,
             get_password_strength_description,
             get_vault_info,
             list_vaults
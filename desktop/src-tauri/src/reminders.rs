@@ -0,0 +1,122 @@
+//! # Password Change Reminders
+//! 
+//! A background thread runs the backend's `ReminderScheduler` for the
+//! desktop vault and shows OS notifications for passwords due for a change.
+//! While the vault is unlocked the notifications name the accounts; while
+//! it is locked they only say how many passwords need attention.
+//! 
+//! Clicking a notification (where the platform reports clicks) brings up
+//! the main window and emits `open-account` with `{ "id": ..., "name": ... }`
+//! so the frontend can show the entry.
+
+use crate::state::VaultState;
+use passman_backend::{PassManConfig, reminders::{Reminder, ReminderScheduler}};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Event asking the frontend to show an account
+pub const OPEN_ACCOUNT_EVENT: &str = "open-account";
+
+/// How often the thread asks the scheduler whether a check is due
+const TICK: Duration = Duration::from_secs(10 * 60);
+/// Reminders shown as separate notifications before they are summarized
+const MAX_NOTIFICATIONS: usize = 3;
+
+/// Payload of the `open-account` event
+#[derive(Clone, Serialize)]
+pub struct OpenAccountPayload {
+    pub id: String,
+    pub name: String,
+}
+
+/// Start the reminder thread
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `vault_name` - The vault to check
+pub fn spawn(app: AppHandle, vault_name: &'static str) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = check(&app, vault_name) {
+            eprintln!("Password reminders failed: {}", e);
+        }
+        std::thread::sleep(TICK);
+    });
+}
+
+/// Run the scheduler if a check is due and notify about its reminders
+fn check(app: &AppHandle, vault_name: &str) -> passman_backend::Result<()> {
+    let policy = PassManConfig::load()?.reminders;
+    let mut scheduler = ReminderScheduler::load(vault_name, policy)?;
+    let now = chrono::Utc::now();
+    if !scheduler.is_due(now) {
+        return Ok(());
+    }
+    
+    // Reading the accounts must not postpone auto-lock
+    let reminders = app.state::<VaultState>()
+        .with_vault_idle(|passman| Ok(scheduler.run(Some(&passman.get_all_accounts()), now)))
+        .unwrap_or_else(|_| scheduler.run(None, now));
+    scheduler.save()?;
+    
+    let named = reminders.iter().all(|reminder| reminder.account_name.is_some());
+    if named && reminders.len() <= MAX_NOTIFICATIONS {
+        for reminder in &reminders {
+            notify(app, "PassMan: change this password", &reminder.message(now), Some(reminder));
+        }
+    } else if !reminders.is_empty() {
+        let body = if named {
+            "Open PassMan and run a password health check to see which."
+        } else {
+            "Unlock PassMan and run a password health check to see which."
+        };
+        notify(app, &format!("PassMan: {} passwords to change", reminders.len()), body, None);
+    }
+    Ok(())
+}
+
+/// Show one notification; clicking it opens the reminder's account, if any
+fn notify(app: &AppHandle, summary: &str, body: &str, reminder: Option<&Reminder>) {
+    let mut notification = notify_rust::Notification::new();
+    notification.appname("PassMan")
+        .summary(summary)
+        .body(body)
+        .icon("dialog-password")
+        .action("default", "Open");
+    
+    // Only the freedesktop backend reports clicks
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use tauri::Emitter;
+        
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => {
+                eprintln!("Failed to show notification: {}", e);
+                return;
+            }
+        };
+        let app = app.clone();
+        let payload = reminder.map(|reminder| OpenAccountPayload {
+            id: reminder.account_id.to_string(),
+            name: reminder.account_name.clone().unwrap_or_default(),
+        });
+        std::thread::spawn(move || handle.wait_for_action(|action| {
+            if action != "default" {
+                return;
+            }
+            let _ = crate::tray::show_main_window(&app);
+            if let Some(payload) = payload {
+                let _ = app.emit(OPEN_ACCOUNT_EVENT, payload);
+            }
+        }));
+    }
+    
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        let _ = (app, reminder);
+        if let Err(e) = notification.show() {
+            eprintln!("Failed to show notification: {}", e);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! # Timed Password Reveal
+//! 
+//! Showing a password on screen is temporary: `reveal` hands the secret
+//! to the frontend together with a time-to-live, and once it runs out
+//! the `reveal-expired` event tells the UI to mask it again:
+//! 
+//! - `reveal-expired` with `{ "id": "<account id>" }`
+//! 
+//! Revealing the same account again restarts its timer. When the vault's
+//! `require_confirmation` setting is on, every reveal re-checks the master
+//! password, and failures count towards the unlock lockout.
+
+use crate::error::CommandError;
+use crate::state::VaultState;
+use passman_backend::{PassMan, PassManError};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+/// Event emitted when a revealed password should be masked again
+pub const EXPIRED_EVENT: &str = "reveal-expired";
+
+/// How long a revealed password stays visible
+pub const REVEAL_TTL: Duration = Duration::from_secs(20);
+
+/// A password handed out for display
+#[derive(Serialize)]
+pub struct RevealedPassword {
+    /// Account ID
+    pub id: String,
+    
+    /// The password
+    pub password: String,
+    
+    /// Seconds until `reveal-expired` is emitted for this account
+    pub ttl_seconds: u64,
+}
+
+/// Payload of the `reveal-expired` event
+#[derive(Clone, Serialize)]
+pub struct ExpiredPayload {
+    /// Account whose password should be masked
+    pub id: String,
+}
+
+/// Running reveal timers, so a newer reveal outlives an older timer
+#[derive(Default)]
+pub struct Reveals {
+    /// Latest reveal number per account
+    latest: Mutex<HashMap<Uuid, u64>>,
+}
+
+impl Reveals {
+    /// Record a new reveal of an account
+    fn start(&self, id: Uuid) -> u64 {
+        let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        let generation = latest.get(&id).map_or(0, |generation| generation + 1);
+        latest.insert(id, generation);
+        generation
+    }
+    
+    /// Check whether a reveal is still the latest one, and forget it if so
+    fn finish(&self, id: Uuid, generation: u64) -> bool {
+        let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        if latest.get(&id) == Some(&generation) {
+            latest.remove(&id);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Reveal an account's password for `REVEAL_TTL`
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `id` - Account to reveal
+/// * `master_password` - Master password, required if the vault asks for confirmation
+/// 
+/// # Errors
+/// Returns an error if the vault is locked, the account doesn't exist, or
+/// a required master password is missing or wrong
+pub fn reveal(app: &AppHandle, id: Uuid, master_password: Option<&str>) -> Result<RevealedPassword, CommandError> {
+    let password = app.state::<VaultState>().with_vault(|passman| account_password(passman, id, master_password))?;
+    
+    let generation = app.state::<Reveals>().start(id);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(REVEAL_TTL);
+        if app.state::<Reveals>().finish(id, generation) {
+            let _ = app.emit(EXPIRED_EVENT, ExpiredPayload { id: id.to_string() });
+        }
+    });
+    
+    Ok(RevealedPassword {
+        id: id.to_string(),
+        password,
+        ttl_seconds: REVEAL_TTL.as_secs(),
+    })
+}
+
+/// Get an account's password for display or copying, and mark the account accessed
+/// 
+/// # Arguments
+/// * `passman` - The open vault
+/// * `id` - Account whose password is needed
+/// * `master_password` - Master password, required if the vault asks for confirmation
+/// 
+/// # Errors
+/// Returns an error if the account doesn't exist, or a required master
+/// password is missing or wrong
+pub fn account_password(passman: &mut PassMan, id: Uuid, master_password: Option<&str>) -> Result<String, CommandError> {
+    let require_confirmation = passman.get_vault_metadata()
+        .is_some_and(|metadata| metadata.settings.require_confirmation);
+    if require_confirmation {
+        let master_password = master_password.ok_or("Master password confirmation required")?;
+        passman.verify_master_password(master_password, None)?;
+    }
+    let password = passman.get_account(id)
+        .map(|account| account.password.clone())
+        .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+    passman.record_access(id)?;
+    Ok(password)
+}
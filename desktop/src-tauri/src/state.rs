@@ -0,0 +1,173 @@
+//! # Shared Vault State
+//! 
+//! The desktop app keeps one open vault in Tauri managed state, so
+//! commands operate on the same unlocked `PassMan` instead of re-opening
+//! the vault with the master password on every call. Activity is tracked
+//! here for the auto-lock watcher.
+//! 
+//! Locking keeps the `PassMan` and only closes its vault. Unlock attempts
+//! go through that same manager, so its failed-attempt backoff and lockout
+//! build up across attempts instead of starting over with every command.
+
+use crate::error::{self, CommandError};
+use passman_backend::PassMan;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+/// Vault shared between Tauri commands
+pub struct VaultState {
+    /// The vault manager, open or locked; None until the first unlock
+    vault: Mutex<Option<PassMan>>,
+    
+    /// Last time a command touched the vault
+    last_activity: Mutex<Instant>,
+}
+
+impl VaultState {
+    /// Create an empty (locked) state
+    pub fn new() -> Self {
+        Self {
+            vault: Mutex::new(None),
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+    
+    /// Store a freshly opened vault, replacing the kept manager
+    pub fn set_open(&self, passman: PassMan) {
+        *self.lock_vault() = Some(passman);
+        self.touch();
+    }
+    
+    /// Close the vault, keeping the manager for later unlock attempts
+    /// 
+    /// # Returns
+    /// True if a vault was open
+    pub fn close(&self) -> bool {
+        let mut vault = self.lock_vault();
+        match vault.as_mut().filter(|passman| passman.is_vault_open()) {
+            Some(passman) => {
+                passman.close_vault();
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Close the vault and drop the manager, e.g. when the vault is moved
+    pub fn discard(&self) {
+        if let Some(mut passman) = self.lock_vault().take() {
+            passman.close_vault();
+        }
+    }
+    
+    /// Try to unlock the vault with the kept manager
+    /// 
+    /// Attempts are made one at a time, and failures count against the
+    /// same manager's backoff and lockout.
+    /// 
+    /// # Arguments
+    /// * `vault_name` - Vault to create a manager for if there is none yet
+    /// * `unlock` - Opens the vault, e.g. with the master password
+    /// 
+    /// # Errors
+    /// Returns an error if the vault can't be found or `unlock` fails
+    pub fn unlock_with(
+        &self,
+        vault_name: &str,
+        unlock: impl FnOnce(&mut PassMan) -> Result<(), CommandError>,
+    ) -> Result<(), CommandError> {
+        let mut vault = self.lock_vault();
+        let passman = match vault.take() {
+            Some(passman) => passman,
+            None => PassMan::new(vault_name)?,
+        };
+        let passman = vault.insert(passman);
+        unlock(passman)?;
+        drop(vault);
+        self.touch();
+        Ok(())
+    }
+    
+    /// Check whether a vault is open
+    pub fn is_open(&self) -> bool {
+        self.lock_vault().as_ref().is_some_and(|passman| passman.is_vault_open())
+    }
+    
+    /// Run a closure against the open vault, recording activity
+    /// 
+    /// # Errors
+    /// Returns an error if no vault is open
+    pub fn with_vault<T>(&self, f: impl FnOnce(&mut PassMan) -> Result<T, CommandError>) -> Result<T, CommandError> {
+        let mut vault = self.lock_vault();
+        let passman = vault.as_mut()
+            .filter(|passman| passman.is_vault_open())
+            .ok_or_else(error::vault_locked)?;
+        let result = f(passman);
+        drop(vault);
+        self.touch();
+        result
+    }
+    
+    /// Run a closure against the open vault without counting it as activity
+    /// 
+    /// For background work (e.g. picking up external changes) that must
+    /// not keep an idle vault unlocked.
+    /// 
+    /// # Errors
+    /// Returns an error if no vault is open
+    pub fn with_vault_idle<T>(&self, f: impl FnOnce(&mut PassMan) -> Result<T, CommandError>) -> Result<T, CommandError> {
+        let mut vault = self.lock_vault();
+        let passman = vault.as_mut()
+            .filter(|passman| passman.is_vault_open())
+            .ok_or_else(error::vault_locked)?;
+        f(passman)
+    }
+    
+    /// Record user activity, postponing auto-lock
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+    
+    /// Get when the vault was last used
+    pub fn last_activity(&self) -> Instant {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Get the open vault's auto-lock timeout in minutes (0 = disabled)
+    pub fn auto_lock_minutes(&self) -> Option<u32> {
+        self.lock_vault()
+            .as_ref()
+            .and_then(|passman| passman.get_vault_metadata())
+            .map(|metadata| metadata.settings.auto_lock_timeout)
+    }
+    
+    /// Lock the vault mutex, recovering from a poisoned lock
+    fn lock_vault(&self) -> MutexGuard<'_, Option<PassMan>> {
+        self.vault.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Run a closure against the open vault on the blocking thread pool
+/// 
+/// For commands that save the vault, so the file write doesn't stall the
+/// async runtime. Records activity like `VaultState::with_vault`.
+/// 
+/// # Errors
+/// Returns an error if no vault is open
+pub async fn with_vault_blocking<T, F>(app: &AppHandle, f: F) -> Result<T, CommandError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut PassMan) -> Result<T, CommandError> + Send + 'static,
+{
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || app.state::<VaultState>().with_vault(f))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+impl Default for VaultState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,325 @@
+//! # Import and Export
+//! 
+//! File import/export for the desktop app's migration screens. Accounts
+//! move as plain JSON (the backend's account records) or CSV with the
+//! columns `passman add --batch` reads, so files round-trip with the CLI.
+//! 
+//! Large files report progress through the `transfer-progress` event:
+//! 
+//! - `{ "operation": "import" | "export", "stage": "reading" | "merging" | "writing", "processed": n, "total": n }`
+//! 
+//! `total` is in bytes while reading a CSV file and in accounts otherwise.
+//! 
+//! Exports are signed when the vault has a signing key, and an import
+//! whose signature doesn't match that key is refused.
+
+use crate::error::{self, CommandError};
+use crate::state::VaultState;
+use passman_backend::{
+    PassManError,
+    models::{Account, AccountQuery, AccountType, DedupePolicy, ImportSummary},
+    signing::SignatureStatus,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted while a file is imported or exported
+pub const PROGRESS_EVENT: &str = "transfer-progress";
+
+/// Minimum time between progress events, so big files don't flood the UI
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// CSV columns, in export order
+const CSV_COLUMNS: [&str; 9] = ["name", "username", "password", "url", "notes", "tags", "type", "folder", "totp"];
+
+/// File formats accepted by import and export
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    /// JSON array of account records
+    Json,
+    
+    /// CSV with a header row
+    Csv,
+}
+
+/// Payload of the `transfer-progress` event
+#[derive(Clone, Serialize)]
+pub struct ProgressPayload {
+    /// "import" or "export"
+    pub operation: &'static str,
+    
+    /// Current step of the operation
+    pub stage: &'static str,
+    
+    /// Units done so far
+    pub processed: u64,
+    
+    /// Units in total
+    pub total: u64,
+}
+
+/// Result of an import
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// Counts of added, overwritten, and skipped accounts
+    #[serde(flatten)]
+    pub summary: ImportSummary,
+    
+    /// Rows that couldn't be read, as "line N: reason"
+    pub errors: Vec<String>,
+    
+    /// Whether the vault was left unchanged
+    pub dry_run: bool,
+    
+    /// The file's signature, checked against the vault's signing key
+    pub signature: SignatureStatus,
+}
+
+/// Emits progress events, throttled to `PROGRESS_INTERVAL`
+struct Progress<'a> {
+    app: &'a AppHandle,
+    operation: &'static str,
+    last_sent: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(app: &'a AppHandle, operation: &'static str) -> Self {
+        Self { app, operation, last_sent: None }
+    }
+    
+    /// Report progress; the final update of a stage is always sent
+    fn report(&mut self, stage: &'static str, processed: u64, total: u64) {
+        let due = self.last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL);
+        if !due && processed < total {
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+        let _ = self.app.emit(PROGRESS_EVENT, ProgressPayload {
+            operation: self.operation,
+            stage,
+            processed,
+            total,
+        });
+    }
+}
+
+/// Import accounts from a file into the open vault
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `path` - File to import
+/// * `format` - Format of the file
+/// * `dry_run` - Report what would change without saving
+/// * `policy` - How accounts duplicating existing ones are handled
+/// 
+/// # Errors
+/// Returns an error if the vault is locked, the file can't be read, or
+/// the vault can't be saved
+pub fn import_file(
+    app: &AppHandle,
+    path: &Path,
+    format: TransferFormat,
+    dry_run: bool,
+    policy: DedupePolicy,
+) -> Result<ImportReport, CommandError> {
+    let state = app.state::<VaultState>();
+    if !state.is_open() {
+        return Err(error::vault_locked());
+    }
+    
+    let signature = state.with_vault(|passman| passman.verify_file(path).map_err(CommandError::from))?;
+    if signature == SignatureStatus::Invalid {
+        return Err(format!("The signature of {} doesn't match; it may have been tampered with", path.display()).into());
+    }
+    
+    let mut progress = Progress::new(app, "import");
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let (accounts, errors) = match format {
+        TransferFormat::Json => {
+            let accounts: Vec<Account> = serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| format!("Invalid JSON export: {}", e))?;
+            (accounts, Vec::new())
+        }
+        TransferFormat::Csv => read_csv(file, &mut progress)?,
+    };
+    
+    let summary = state.with_vault(|passman| {
+        passman.merge_accounts(accounts, policy, dry_run, &mut |processed, total| {
+            progress.report("merging", processed as u64, total as u64);
+        }).map_err(CommandError::from)
+    })?;
+    
+    Ok(ImportReport { summary, errors, dry_run, signature })
+}
+
+/// Export accounts from the open vault to a file
+/// 
+/// The file holds plaintext passwords, so it is created readable only
+/// by the current user. It is signed if the vault has a signing key.
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `path` - File to write
+/// * `format` - Format to write
+/// * `filter` - Which accounts to export (all active accounts by default)
+/// 
+/// # Returns
+/// The number of exported accounts
+/// 
+/// # Errors
+/// Returns an error if the vault is locked or the file can't be written
+pub fn export_file(
+    app: &AppHandle,
+    path: &Path,
+    format: TransferFormat,
+    filter: Option<AccountQuery>,
+) -> Result<usize, CommandError> {
+    let filter = filter.unwrap_or_default();
+    let accounts: Vec<Account> = app.state::<VaultState>().with_vault(|passman| {
+        Ok(passman.query_accounts(&filter).into_iter().cloned().collect())
+    })?;
+    
+    let mut progress = Progress::new(app, "export");
+    let file = create_private(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    match format {
+        TransferFormat::Json => {
+            progress.report("writing", 0, accounts.len() as u64);
+            serde_json::to_writer_pretty(&mut writer, &accounts).map_err(|e| e.to_string())?;
+            progress.report("writing", accounts.len() as u64, accounts.len() as u64);
+        }
+        TransferFormat::Csv => write_csv(&mut writer, &accounts, &mut progress)?,
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    drop(writer);
+    
+    app.state::<VaultState>().with_vault(|passman| passman.sign_file(path).map_err(CommandError::from))?;
+    Ok(accounts.len())
+}
+
+/// Read accounts from CSV, collecting per-row errors instead of failing
+fn read_csv(file: File, progress: &mut Progress) -> Result<(Vec<Account>, Vec<String>), String> {
+    let total = file.metadata().map(|m| m.len()).unwrap_or_default();
+    let mut csv = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(BufReader::new(file));
+    
+    let headers: Vec<String> = csv.headers()
+        .map_err(|e| format!("Failed to read CSV header: {}", e))?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+    if !headers.iter().any(|h| h == "name") {
+        return Err("CSV header must include a 'name' column".to_string());
+    }
+    
+    let mut accounts = Vec::new();
+    let mut errors = Vec::new();
+    let mut record = csv::StringRecord::new();
+    let mut line = 1;
+    loop {
+        line += 1;
+        match csv.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => {
+                let field = |name: &str| {
+                    headers.iter()
+                        .position(|h| h == name)
+                        .and_then(|i| record.get(i))
+                        .filter(|value| !value.is_empty())
+                        .map(str::to_string)
+                };
+                match parse_row(&field) {
+                    Ok(account) => accounts.push(account),
+                    Err(reason) => errors.push(format!("line {}: {}", line, reason)),
+                }
+            }
+            Err(e) => errors.push(format!("line {}: malformed row: {}", line, e)),
+        }
+        progress.report("reading", csv.position().byte(), total);
+    }
+    progress.report("reading", total, total);
+    
+    Ok((accounts, errors))
+}
+
+/// Build an account from the columns of one CSV row
+fn parse_row(field: &dyn Fn(&str) -> Option<String>) -> Result<Account, String> {
+    let name = field("name").ok_or("missing name")?;
+    let password = field("password").ok_or("missing password")?;
+    let account_type = match field("type") {
+        Some(value) => value.parse().map_err(|e: PassManError| e.to_string())?,
+        None => AccountType::Personal,
+    };
+    
+    let mut account = Account::new(name, account_type, password);
+    account.username = field("username");
+    account.url = field("url");
+    account.notes = field("notes");
+    account.folder = field("folder");
+    account.totp = field("totp");
+    account.tags = field("tags")
+        .map(|tags| {
+            tags.split([';', ','])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    Ok(account)
+}
+
+/// Write accounts as CSV with a header row
+fn write_csv(writer: &mut dyn Write, accounts: &[Account], progress: &mut Progress) -> Result<(), String> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(CSV_COLUMNS).map_err(|e| e.to_string())?;
+    
+    let total = accounts.len() as u64;
+    for (index, account) in accounts.iter().enumerate() {
+        let tags = account.tags.join(";");
+        let account_type = account.account_type.display_name().to_lowercase();
+        csv.write_record([
+            account.name.as_str(),
+            account.username.as_deref().unwrap_or_default(),
+            account.password.as_str(),
+            account.url.as_deref().unwrap_or_default(),
+            account.notes.as_deref().unwrap_or_default(),
+            tags.as_str(),
+            account_type.as_str(),
+            account.folder.as_deref().unwrap_or_default(),
+            account.totp.as_deref().unwrap_or_default(),
+        ]).map_err(|e| e.to_string())?;
+        progress.report("writing", index as u64 + 1, total);
+    }
+    if accounts.is_empty() {
+        progress.report("writing", 0, 0);
+    }
+    
+    csv.flush().map_err(|e| e.to_string())
+}
+
+/// Create (or truncate) a file readable only by the current user
+fn create_private(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    // The mode only applies to new files; tighten an existing one too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
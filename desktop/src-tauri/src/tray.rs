@@ -0,0 +1,188 @@
+//! # System Tray
+//! 
+//! The tray icon keeps PassMan reachable while the main window is hidden:
+//! 
+//! - **Open PassMan** shows the main window
+//! - **Lock Now** locks the shared vault (emitting `vault-locked`); while
+//!   the vault is locked it reads **Unlock…** and shows the main window's
+//!   unlock screen instead
+//! - **Quick Search** opens a small always-on-top search window
+//! - **Generate Password** brings up the main window on the generator,
+//!   via the `tray-navigate` event with `{ "route": "/generate" }`
+//! - **Quit** exits the app
+
+use crate::autolock;
+use passman_backend::models::Account;
+use serde::Serialize;
+use crate::state::VaultState;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
+
+/// Event asking the main window to show a route
+pub const NAVIGATE_EVENT: &str = "tray-navigate";
+
+/// Label of the app's main window (the first window in tauri.conf.json)
+const MAIN_WINDOW: &str = "main";
+/// Label of the quick search window
+const QUICK_SEARCH_WINDOW: &str = "quick-search";
+/// Most results the quick search window lists
+pub const QUICK_SEARCH_LIMIT: usize = 8;
+
+const OPEN_ID: &str = "open";
+const LOCK_ID: &str = "lock";
+const QUICK_SEARCH_ID: &str = "quick-search";
+const GENERATE_ID: &str = "generate";
+const QUIT_ID: &str = "quit";
+
+/// Payload of the `tray-navigate` event
+#[derive(Clone, Serialize)]
+pub struct NavigatePayload {
+    /// Frontend route to show
+    pub route: &'static str,
+}
+
+/// The lock menu item, kept to follow the vault's state
+struct LockItem(MenuItem<Wry>);
+
+/// One quick search hit; secrets are fetched separately when copied
+#[derive(Clone, Serialize)]
+pub struct QuickSearchResult {
+    pub id: String,
+    pub name: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+}
+
+impl From<&Account> for QuickSearchResult {
+    fn from(account: &Account) -> Self {
+        Self {
+            id: account.id.to_string(),
+            name: account.name.clone(),
+            username: account.username.clone(),
+            url: account.url.clone(),
+        }
+    }
+}
+
+/// Create the tray icon and its menu
+/// 
+/// # Arguments
+/// * `app` - Handle of the running app
+/// 
+/// # Errors
+/// Returns an error if the menu or icon can't be created
+pub fn build(app: &AppHandle) -> tauri::Result<()> {
+    let lock_item = MenuItem::with_id(app, LOCK_ID, lock_text(app), true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[
+        &MenuItem::with_id(app, OPEN_ID, "Open PassMan", true, None::<&str>)?,
+        &lock_item,
+        &MenuItem::with_id(app, QUICK_SEARCH_ID, "Quick Search", true, None::<&str>)?,
+        &MenuItem::with_id(app, GENERATE_ID, "Generate Password", true, None::<&str>)?,
+        &PredefinedMenuItem::separator(app)?,
+        &MenuItem::with_id(app, QUIT_ID, "Quit PassMan", true, None::<&str>)?,
+    ])?;
+    
+    let mut tray = TrayIconBuilder::with_id("passman")
+        .tooltip("PassMan")
+        .menu(&menu)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    app.manage(LockItem(lock_item));
+    
+    Ok(())
+}
+
+/// Update the lock menu item after the vault was locked or unlocked
+/// 
+/// # Arguments
+/// * `app` - Handle of the running app
+pub fn refresh(app: &AppHandle) {
+    if let Some(item) = app.try_state::<LockItem>() {
+        if let Err(e) = item.0.set_text(lock_text(app)) {
+            eprintln!("Tray update failed: {}", e);
+        }
+    }
+}
+
+/// Text of the lock menu item for the vault's current state
+fn lock_text(app: &AppHandle) -> &'static str {
+    if app.state::<VaultState>().is_open() { "Lock Now" } else { "Unlock…" }
+}
+
+/// Dispatch a tray menu click
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let result = match event.id().as_ref() {
+        OPEN_ID => show_main_window(app),
+        LOCK_ID if app.state::<VaultState>().is_open() => {
+            autolock::lock(app, "manual");
+            Ok(())
+        }
+        // Unlocking needs the master password, which the main window asks for
+        LOCK_ID => show_main_window(app),
+        QUICK_SEARCH_ID => open_quick_search(app),
+        GENERATE_ID => show_main_window(app)
+            .and_then(|()| app.emit_to(MAIN_WINDOW, NAVIGATE_EVENT, NavigatePayload { route: "/generate" })),
+        QUIT_ID => {
+            autolock::lock(app, "manual");
+            app.exit(0);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("Tray action failed: {}", e);
+    }
+}
+
+/// Show, unminimize, and focus the main window
+/// 
+/// # Errors
+/// Returns an error if the window can't be shown
+pub fn show_main_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        window.show()?;
+        window.unminimize()?;
+        window.set_focus()?;
+    }
+    Ok(())
+}
+
+/// Open the quick search window, or focus it if it is already open
+/// 
+/// # Errors
+/// Returns an error if the window can't be created
+pub fn open_quick_search(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(QUICK_SEARCH_WINDOW) {
+        window.show()?;
+        window.set_focus()?;
+        return Ok(());
+    }
+    
+    // The query string picks the mini search UI instead of the full app
+    WebviewWindowBuilder::new(app, QUICK_SEARCH_WINDOW, WebviewUrl::App("index.html?window=quick-search".into()))
+        .title("PassMan Quick Search")
+        .inner_size(480.0, 360.0)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build()?;
+    
+    Ok(())
+}
+
+/// Hide the quick search window, keeping it around for next time
+/// 
+/// # Errors
+/// Returns an error if the window can't be hidden
+pub fn hide_quick_search(app: &AppHandle) -> tauri::Result<()> {
+    match app.get_webview_window(QUICK_SEARCH_WINDOW) {
+        Some(window) => window.hide(),
+        None => Ok(()),
+    }
+}
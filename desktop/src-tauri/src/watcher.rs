@@ -0,0 +1,106 @@
+//! # Vault File Watcher
+//! 
+//! The CLI or a sync tool may rewrite the vault file while the desktop
+//! app has it open. A background watcher notices, reloads the shared
+//! vault from disk (so the app's next save doesn't overwrite those
+//! changes), and tells the frontend to refresh:
+//! 
+//! - `vault-changed-externally` with `{ "account_count": n }`
+//! 
+//! Saves replace the vault file by renaming a temporary file over it, so
+//! the watcher observes the vault's directory and filters by file name.
+//! Writes made by the app itself reload to an identical vault and emit
+//! nothing. If the new file no longer opens with the session key (say, a
+//! legacy vault's password was changed), the vault is locked with reason
+//! `external`.
+
+use crate::{autolock, state::VaultState};
+use notify::{Event, RecursiveMode, Watcher};
+use passman_backend::PassManError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted after the vault was reloaded with outside changes
+pub const CHANGED_EVENT: &str = "vault-changed-externally";
+
+/// Quiet period that groups the events of one save (rename, chmod, ...)
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Payload of the `vault-changed-externally` event
+#[derive(Clone, Serialize)]
+pub struct ChangedPayload {
+    /// Number of accounts in the reloaded vault
+    pub account_count: usize,
+}
+
+/// Start watching a vault file
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+/// * `vault_path` - Path of the vault file
+/// 
+/// # Errors
+/// Returns an error if the vault's directory can't be watched
+pub fn spawn(app: AppHandle, vault_path: PathBuf) -> notify::Result<()> {
+    let directory = vault_path.parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| notify::Error::path_not_found().add_path(vault_path.clone()))?;
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    
+    std::thread::spawn(move || {
+        // The watcher stops when dropped, so it lives as long as this thread
+        let _watcher = watcher;
+        let touches_vault = |event: &notify::Result<Event>| {
+            event.as_ref().is_ok_and(|event| {
+                event.paths.iter().any(|path| path.file_name() == vault_path.file_name())
+            })
+        };
+        
+        while let Ok(event) = rx.recv() {
+            if !touches_vault(&event) {
+                continue;
+            }
+            loop {
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            reload(&app);
+        }
+    });
+    
+    Ok(())
+}
+
+/// Reload the shared vault and report outside changes
+/// 
+/// # Arguments
+/// * `app` - Handle used to reach the shared state and emit events
+pub fn reload(app: &AppHandle) {
+    let state = app.state::<VaultState>();
+    // A locked app has nothing in memory to refresh
+    let Ok(result) = state.with_vault_idle(|passman| {
+        Ok(passman.reload_vault().map(|changed| (changed, passman.get_all_accounts().len())))
+    }) else {
+        return;
+    };
+    
+    match result {
+        Ok((true, account_count)) => {
+            let _ = app.emit(CHANGED_EVENT, ChangedPayload { account_count });
+        }
+        Ok((false, _)) => {}
+        Err(PassManError::AuthenticationFailed(_) | PassManError::EncryptionError(_)) => {
+            autolock::lock(app, "external");
+        }
+        // Deleted or moved away; keep what's in memory
+        Err(e) => eprintln!("Failed to reload vault: {}", e),
+    }
+}
@@ -1,8 +1,9 @@
 import { AnimatePresence, motion } from 'framer-motion'
 import { useEffect, useState } from 'react'
 import { QueryClient, QueryClientProvider } from 'react-query'
-import { Route, BrowserRouter as Router, Routes } from 'react-router-dom'
+import { Route, BrowserRouter as Router, Routes, useNavigate } from 'react-router-dom'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 
 // Components
 import Header from './components/Header'
@@ -23,16 +24,55 @@ import { AuthProvider } from './contexts/AuthContext'
 // Create a client
 const queryClient = new QueryClient()
 
+// Follows routes requested from the tray menu (e.g. Generate Password)
+function TrayNavigator() {
+  const navigate = useNavigate()
+
+  useEffect(() => {
+    const unlisten = listen<{ route: string }>('tray-navigate', (event) => navigate(event.payload.route))
+    return () => {
+      unlisten.then((unlisten) => unlisten())
+    }
+  }, [navigate])
+
+  return null
+}
+
 function App() {
   const [isAuthenticated, setIsAuthenticated] = useState(false)
   const [isVaultInitialized, setIsVaultInitialized] = useState(false)
   const [isLoading, setIsLoading] = useState(true)
+  const [lockWarning, setLockWarning] = useState<number | null>(null)
 
   useEffect(() => {
     // Check if vault exists and user is authenticated
     checkVaultStatus()
   }, [])
 
+  useEffect(() => {
+    // The backend locks the vault on idle timeout or system sleep
+    const unlistenLocked = listen<{ reason: string }>('vault-locked', () => {
+      setLockWarning(null)
+      setIsAuthenticated(false)
+    })
+    const unlistenWarning = listen<{ seconds_remaining: number }>('vault-lock-warning', (event) => {
+      setLockWarning(event.payload.seconds_remaining)
+    })
+
+    return () => {
+      unlistenLocked.then((unlisten) => unlisten())
+      unlistenWarning.then((unlisten) => unlisten())
+    }
+  }, [])
+
+  const stayUnlocked = async () => {
+    try {
+      await invoke('keep_alive')
+    } finally {
+      setLockWarning(null)
+    }
+  }
+
   const checkVaultStatus = async () => {
     try {
       // Check if account exists
@@ -91,8 +131,16 @@ function App() {
     <QueryClientProvider client={queryClient}>
       <AuthProvider>
         <Router>
+          <TrayNavigator />
           <div className="min-h-screen bg-background text-white">
-            <Header onLogout={() => setIsAuthenticated(false)} />
+            <Header onLogout={() => { invoke('close_vault'); setIsAuthenticated(false) }} />
+            
+            {lockWarning !== null && (
+              <div className="bg-yellow-500/20 text-yellow-200 px-8 py-2 flex items-center justify-between">
+                <span>Vault will lock in {lockWarning} seconds due to inactivity.</span>
+                <button onClick={stayUnlocked} className="underline">Stay unlocked</button>
+              </div>
+            )}
             
             <div className="flex">
               <Sidebar />
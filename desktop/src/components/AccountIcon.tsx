@@ -0,0 +1,46 @@
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+
+interface AccountIconProps {
+  accountId: string
+  // The account's own icon: an emoji, or an image as a data: URL
+  icon?: string | null
+  className?: string
+}
+
+const isImageUrl = (icon: string) => /^data:image\//i.test(icon)
+
+// Remote icons from older vaults are never loaded: the webview would
+// contact their host on every render, even with site icons turned off
+const isRemoteUrl = (icon: string) => /^[a-z][a-z0-9+.-]*:\/\//i.test(icon)
+
+// The account's own icon if it has one, else its site favicon; renders
+// nothing unless icons are enabled and found
+export default function AccountIcon({ accountId, icon: storedIcon, className = 'w-6 h-6' }: AccountIconProps) {
+  const [src, setSrc] = useState<string | null>(null)
+  const icon = storedIcon && !isRemoteUrl(storedIcon) ? storedIcon : null
+
+  useEffect(() => {
+    setSrc(null)
+    if (icon) return
+    let url: string | null = null
+    invoke<ArrayBuffer>('get_account_icon', { id: accountId })
+      .then((bytes) => {
+        if (bytes.byteLength > 0) {
+          url = URL.createObjectURL(new Blob([bytes]))
+          setSrc(url)
+        }
+      })
+      .catch(() => setSrc(null))
+    return () => {
+      if (url) URL.revokeObjectURL(url)
+    }
+  }, [accountId, icon])
+
+  if (icon) {
+    return isImageUrl(icon)
+      ? <img src={icon} alt="" className={`${className} rounded`} />
+      : <span className={`${className} inline-flex items-center justify-center leading-none`}>{icon}</span>
+  }
+  return src ? <img src={src} alt="" className={`${className} rounded`} /> : null
+}
@@ -0,0 +1,11 @@
+import { CommandError, ErrorKind } from './types'
+
+// Commands reject with a CommandError; Tauri itself may reject with a string
+export const isCommandError = (error: unknown): error is CommandError =>
+  typeof error === 'object' && error !== null && 'kind' in error && 'message' in error
+
+export const errorKind = (error: unknown): ErrorKind | undefined =>
+  isCommandError(error) ? error.kind : undefined
+
+export const errorMessage = (error: unknown): string =>
+  isCommandError(error) ? error.message : String(error)
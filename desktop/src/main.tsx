@@ -1,10 +1,12 @@
 import React from 'react'
 import ReactDOM from 'react-dom/client'
 import App from './App.tsx'
+import QuickSearch from './pages/QuickSearch.tsx'
 import './index.css'
 
 ReactDOM.createRoot(document.getElementById('root') as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {/* The tray's quick search window loads index.html?window=quick-search */}
+    {new URLSearchParams(window.location.search).get('window') === 'quick-search' ? <QuickSearch /> : <App />}
   </React.StrictMode>,
 )
@@ -1,10 +1,11 @@
-import React, { useState } from 'react'
+import React, { useEffect, useState } from 'react'
 import { motion } from 'framer-motion'
-import { Save, Eye, EyeOff, Key, RefreshCw } from 'lucide-react'
-import { AccountFormData, AccountType, PasswordOptions } from '../types'
+import { Save, Eye, EyeOff, Key, RefreshCw, Plus, X } from 'lucide-react'
+import { AccountFormData, AccountType, CustomField, PasswordOptions } from '../types'
 import { invoke } from '@tauri-apps/api/core'
 
 import { useAuth } from '../contexts/AuthContext'
+import { errorMessage } from '../errors'
 import ErrorModal from '../components/ErrorModal'
 
 const AddAccount: React.FC = () => {
@@ -16,9 +17,15 @@ const AddAccount: React.FC = () => {
     username: '',
     password: '',
     notes: '',
-    tags: []
+    tags: [],
+    color: '',
+    icon: '',
+    autotype: '',
+    custom_fields: []
   })
+  const [categories, setCategories] = useState<string[]>([])
   const [showPassword, setShowPassword] = useState(false)
+  const [policyViolations, setPolicyViolations] = useState<string[]>([])
   const [isGenerating, setIsGenerating] = useState(false)
   const [isSaving, setIsSaving] = useState(false)
   const [errorModal, setErrorModal] = useState<{
@@ -42,6 +49,23 @@ const AddAccount: React.FC = () => {
     exclude_ambiguous: false
   })
 
+  useEffect(() => {
+    invoke<string[]>('get_categories')
+      .then(setCategories)
+      .catch(() => {})
+  }, [])
+
+  useEffect(() => {
+    // The backend rejects passwords that break the vault's policy, so show why while typing
+    if (!formData.password) {
+      setPolicyViolations([])
+      return
+    }
+    invoke<string[]>('password_policy_violations', { password: formData.password })
+      .then(setPolicyViolations)
+      .catch(() => setPolicyViolations([]))
+  }, [formData.password])
+
   const generatePassword = async () => {
     setIsGenerating(true)
     try {
@@ -91,6 +115,11 @@ const AddAccount: React.FC = () => {
     return password
   }
 
+  const updateCustomField = (index: number, change: Partial<CustomField>) => {
+    const custom_fields = formData.custom_fields.map((field, i) => i === index ? { ...field, ...change } : field)
+    setFormData({ ...formData, custom_fields })
+  }
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault()
     setIsSaving(true)
@@ -105,6 +134,10 @@ const AddAccount: React.FC = () => {
         username: formData.username || null,
         notes: formData.notes || null,
         tags: formData.tags,
+        color: formData.color || null,
+        icon: formData.icon.trim() || null,
+        autotype: formData.autotype || null,
+        customFields: formData.custom_fields.filter(field => field.name.trim()),
         masterPassword: masterPassword
       })
       
@@ -116,7 +149,11 @@ const AddAccount: React.FC = () => {
         username: '',
         password: '',
         notes: '',
-        tags: []
+        tags: [],
+        color: '',
+        icon: '',
+        autotype: '',
+        custom_fields: []
       })
       
       setErrorModal({
@@ -130,7 +167,7 @@ const AddAccount: React.FC = () => {
       setErrorModal({
         isOpen: true,
         title: 'Error',
-        message: `Failed to add account: ${error}`,
+        message: `Failed to add account: ${errorMessage(error)}`,
         type: 'error'
       })
     } finally {
@@ -180,7 +217,7 @@ const AddAccount: React.FC = () => {
               </label>
               <select
                 value={formData.account_type}
-                onChange={(e) => setFormData({ ...formData, account_type: e.target.value as AccountType })}
+                onChange={(e) => setFormData({ ...formData, account_type: e.target.value })}
                 className="input-field w-full"
               >
                 <option value={AccountType.Personal}>Personal</option>
@@ -188,6 +225,9 @@ const AddAccount: React.FC = () => {
                 <option value={AccountType.Banking}>Banking</option>
                 <option value={AccountType.Social}>Social</option>
                 <option value={AccountType.Other}>Other</option>
+                {categories.map((category) => (
+                  <option key={category} value={category}>{category}</option>
+                ))}
               </select>
             </div>
           </div>
@@ -261,6 +301,13 @@ const AddAccount: React.FC = () => {
                   <span>Generate</span>
                 </motion.button>
               </div>
+              {policyViolations.length > 0 && (
+                <ul className="mt-2 space-y-1 text-sm text-red-400">
+                  {policyViolations.map((violation) => (
+                    <li key={violation}>Password {violation}</li>
+                  ))}
+                </ul>
+              )}
             </div>
           </div>
         </div>
@@ -376,6 +423,111 @@ const AddAccount: React.FC = () => {
                 placeholder="work, important, 2fa (comma-separated)"
               />
             </div>
+
+            <div className="grid grid-cols-2 gap-4">
+              <div>
+                <label className="block text-sm font-medium text-white mb-2">
+                  Label Color
+                </label>
+                <div className="flex items-center space-x-2">
+                  <input
+                    type="color"
+                    value={formData.color || '#3b82f6'}
+                    onChange={(e) => setFormData({ ...formData, color: e.target.value })}
+                    className="h-10 w-14 rounded cursor-pointer bg-transparent"
+                  />
+                  {formData.color ? (
+                    <button
+                      type="button"
+                      onClick={() => setFormData({ ...formData, color: '' })}
+                      className="text-sm text-muted hover:text-white transition-colors"
+                    >
+                      Clear
+                    </button>
+                  ) : (
+                    <span className="text-sm text-muted">None</span>
+                  )}
+                </div>
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-white mb-2">
+                  Icon
+                </label>
+                <input
+                  type="text"
+                  value={formData.icon}
+                  onChange={(e) => setFormData({ ...formData, icon: e.target.value })}
+                  className="input-field w-full"
+                  placeholder="An emoji like 🔑, or a data:image/ URL"
+                />
+              </div>
+            </div>
+
+            <div>
+              <label className="block text-sm font-medium text-white mb-2">
+                Autotype Sequence
+              </label>
+              <input
+                type="text"
+                value={formData.autotype}
+                onChange={(e) => setFormData({ ...formData, autotype: e.target.value })}
+                className="input-field w-full font-mono"
+                placeholder="{USERNAME}{TAB}{PASSWORD}{ENTER}"
+              />
+              <p className="text-xs text-muted mt-1">
+                Typed into the matching window with Ctrl+Alt+A. Leave empty for the default.
+              </p>
+            </div>
+
+            <div>
+              <label className="block text-sm font-medium text-white mb-2">
+                Custom Fields
+              </label>
+              <div className="space-y-2">
+                {formData.custom_fields.map((field, index) => (
+                  <div key={index} className="flex items-center space-x-2">
+                    <input
+                      type="text"
+                      value={field.name}
+                      onChange={(e) => updateCustomField(index, { name: e.target.value })}
+                      className="input-field w-1/3"
+                      placeholder="PIN, security question..."
+                    />
+                    <input
+                      type={field.hidden ? 'password' : 'text'}
+                      value={field.value}
+                      onChange={(e) => updateCustomField(index, { value: e.target.value })}
+                      className="input-field flex-1"
+                      placeholder="Value"
+                    />
+                    <label className="flex items-center space-x-1">
+                      <input
+                        type="checkbox"
+                        checked={field.hidden}
+                        onChange={(e) => updateCustomField(index, { hidden: e.target.checked })}
+                        className="rounded border-muted/20"
+                      />
+                      <span className="text-sm text-white">Hidden</span>
+                    </label>
+                    <button
+                      type="button"
+                      onClick={() => setFormData({ ...formData, custom_fields: formData.custom_fields.filter((_, i) => i !== index) })}
+                      className="p-2 text-muted hover:text-white"
+                    >
+                      <X className="w-4 h-4" />
+                    </button>
+                  </div>
+                ))}
+                <button
+                  type="button"
+                  onClick={() => setFormData({ ...formData, custom_fields: [...formData.custom_fields, { name: '', value: '', hidden: false }] })}
+                  className="flex items-center space-x-1 text-sm text-primary hover:text-white"
+                >
+                  <Plus className="w-4 h-4" />
+                  <span>Add field</span>
+                </button>
+              </div>
+            </div>
           </div>
         </div>
 
@@ -1,17 +1,23 @@
 import React, { useState, useEffect } from 'react'
 import { motion } from 'framer-motion'
-import { Plus, Search, Filter, Copy, Edit, Trash2, Eye, EyeOff, Shield } from 'lucide-react'
-import { Account, AccountType } from '../types'
+import { Plus, Search, Filter, Copy, Edit, Trash2, Eye, EyeOff, Shield, Star } from 'lucide-react'
+import { Account, AccountType, AccountTypeName, RevealedPassword, VaultSettings } from '../types'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import AccountIcon from '../components/AccountIcon'
 import ConfirmationModal from '../components/ConfirmationModal'
 import { useAuth } from '../contexts/AuthContext'
+import { errorMessage } from '../errors'
 
 const Dashboard: React.FC = () => {
   const { masterPassword } = useAuth()
   const [accounts, setAccounts] = useState<Account[]>([])
   const [searchQuery, setSearchQuery] = useState('')
-  const [selectedType, setSelectedType] = useState<AccountType | 'All'>('All')
-  const [showPasswords, setShowPasswords] = useState<{ [key: string]: boolean }>({})
+  const [selectedType, setSelectedType] = useState<AccountTypeName | 'All'>('All')
+  const [categories, setCategories] = useState<string[]>([])
+  const [revealed, setRevealed] = useState<{ [key: string]: string }>({})
+  const [requireConfirmation, setRequireConfirmation] = useState(false)
+  const [confirmReveal, setConfirmReveal] = useState<{ accountId: string; password: string; error: string } | null>(null)
   const [isLoading, setIsLoading] = useState(true)
   const [deleteModal, setDeleteModal] = useState<{
     isOpen: boolean
@@ -29,6 +35,39 @@ const Dashboard: React.FC = () => {
     }
   }, [masterPassword])
 
+  useEffect(() => {
+    invoke<VaultSettings>('get_settings')
+      .then((settings) => setRequireConfirmation(settings.require_confirmation))
+      .catch(() => {})
+  }, [])
+
+  // Revealed passwords are masked again when their time runs out
+  useEffect(() => {
+    const unlisten = listen<{ id: string }>('reveal-expired', (event) => hidePassword(event.payload.id))
+    return () => {
+      unlisten.then((unlisten) => unlisten())
+    }
+  }, [])
+
+  // The backend has already reloaded the vault; just fetch the new list
+  useEffect(() => {
+    const unlisten = listen<{ account_count: number }>('vault-changed-externally', () => loadAccounts())
+    return () => {
+      unlisten.then((unlisten) => unlisten())
+    }
+  }, [masterPassword])
+
+  // A password reminder notification was clicked; narrow the list to that account
+  useEffect(() => {
+    const unlisten = listen<{ id: string; name: string }>('open-account', (event) => {
+      setSelectedType('All')
+      setSearchQuery(event.payload.name)
+    })
+    return () => {
+      unlisten.then((unlisten) => unlisten())
+    }
+  }, [])
+
   const loadAccounts = async () => {
     try {
       if (!masterPassword) {
@@ -39,6 +78,7 @@ const Dashboard: React.FC = () => {
       // Call the Tauri command to load accounts
       const accounts = await invoke<Account[]>('list_accounts', { masterPassword })
       setAccounts(accounts)
+      setCategories(await invoke<string[]>('get_categories'))
     } catch (error) {
       console.error('Error loading accounts:', error)
     } finally {
@@ -50,15 +90,46 @@ const Dashboard: React.FC = () => {
     const matchesSearch = account.name.toLowerCase().includes(searchQuery.toLowerCase()) ||
                          account.username?.toLowerCase().includes(searchQuery.toLowerCase()) ||
                          account.url?.toLowerCase().includes(searchQuery.toLowerCase())
-    const matchesType = selectedType === 'All' || account.account_type === selectedType
+    const matchesType = selectedType === 'All' || account.account_type.toLowerCase() === selectedType.toLowerCase()
     return matchesSearch && matchesType
-  })
+  }).sort((a, b) => Number(!!b.is_favorite) - Number(!!a.is_favorite))
+
+  const hidePassword = (accountId: string) => {
+    setRevealed(prev => {
+      const next = { ...prev }
+      delete next[accountId]
+      return next
+    })
+  }
+
+  const revealPassword = async (accountId: string, confirmation: string | null) => {
+    const secret = await invoke<RevealedPassword>('reveal_password', { id: accountId, masterPassword: confirmation })
+    setRevealed(prev => ({ ...prev, [secret.id]: secret.password }))
+  }
+
+  const togglePasswordVisibility = async (accountId: string) => {
+    if (revealed[accountId] !== undefined) {
+      hidePassword(accountId)
+    } else if (requireConfirmation) {
+      setConfirmReveal({ accountId, password: '', error: '' })
+    } else {
+      try {
+        await revealPassword(accountId, null)
+      } catch (error) {
+        console.error('Failed to reveal password:', error)
+      }
+    }
+  }
 
-  const togglePasswordVisibility = (accountId: string) => {
-    setShowPasswords(prev => ({
-      ...prev,
-      [accountId]: !prev[accountId]
-    }))
+  const handleConfirmReveal = async (e: React.FormEvent) => {
+    e.preventDefault()
+    if (!confirmReveal) return
+    try {
+      await revealPassword(confirmReveal.accountId, confirmReveal.password)
+      setConfirmReveal(null)
+    } catch (error) {
+      setConfirmReveal({ ...confirmReveal, password: '', error: errorMessage(error) })
+    }
   }
 
   const copyToClipboard = async (text: string) => {
@@ -70,6 +141,24 @@ const Dashboard: React.FC = () => {
     }
   }
 
+  const copyPassword = async (account: Account) => {
+    // Copied by the backend, which clears the clipboard after the vault's timeout
+    try {
+      await invoke('copy_password', { id: account.id })
+    } catch (error) {
+      console.error('Failed to copy password:', error)
+    }
+  }
+
+  const toggleFavorite = async (accountId: string) => {
+    try {
+      const isFavorite = await invoke<boolean>('toggle_favorite', { id: accountId })
+      setAccounts(prev => prev.map(account => account.id === accountId ? { ...account, is_favorite: isFavorite } : account))
+    } catch (error) {
+      console.error('Failed to toggle favorite:', error)
+    }
+  }
+
   const handleDeleteClick = (accountId: string, accountName: string) => {
     setDeleteModal({
       isOpen: true,
@@ -105,12 +194,15 @@ const Dashboard: React.FC = () => {
     })
   }
 
-  const getAccountTypeColor = (type: AccountType): string => {
+  const getAccountTypeColor = (type: AccountTypeName): string => {
     switch (type) {
       case AccountType.Social: return 'bg-blue-500/20 text-blue-400 border-blue-500/30'
       case AccountType.Banking: return 'bg-green-500/20 text-green-400 border-green-500/30'
       case AccountType.Work: return 'bg-purple-500/20 text-purple-400 border-purple-500/30'
       case AccountType.Personal: return 'bg-orange-500/20 text-orange-400 border-orange-500/30'
+      case AccountType.Card: return 'bg-red-500/20 text-red-400 border-red-500/30'
+      case AccountType.Identity: return 'bg-teal-500/20 text-teal-400 border-teal-500/30'
+      case AccountType.SshKey: return 'bg-yellow-500/20 text-yellow-400 border-yellow-500/30'
       default: return 'bg-gray-500/20 text-gray-400 border-gray-500/30'
     }
   }
@@ -169,7 +261,7 @@ const Dashboard: React.FC = () => {
           <Filter className="absolute left-3 top-1/2 transform -translate-y-1/2 w-4 h-4 text-muted" />
           <select
             value={selectedType}
-            onChange={(e) => setSelectedType(e.target.value as AccountType | 'All')}
+            onChange={(e) => setSelectedType(e.target.value)}
             className="input-field pl-10 pr-8 appearance-none"
           >
             <option value="All">All Types</option>
@@ -177,7 +269,13 @@ const Dashboard: React.FC = () => {
             <option value={AccountType.Banking}>Banking</option>
             <option value={AccountType.Work}>Work</option>
             <option value={AccountType.Personal}>Personal</option>
+            <option value={AccountType.Card}>Card</option>
+            <option value={AccountType.Identity}>Identity</option>
+            <option value={AccountType.SshKey}>SSH Key</option>
             <option value={AccountType.Other}>Other</option>
+            {categories.map((category) => (
+              <option key={category} value={category}>{category}</option>
+            ))}
           </select>
         </div>
       </motion.div>
@@ -214,21 +312,31 @@ const Dashboard: React.FC = () => {
               animate={{ opacity: 1, y: 0 }}
               transition={{ delay: index * 0.1 }}
               className="card hover:border-primary/30 transition-colors duration-200"
+              style={account.color ? { borderLeftColor: account.color, borderLeftWidth: 4 } : undefined}
             >
               {/* Account Header */}
               <div className="flex items-start justify-between mb-4">
                 <div className="flex-1">
-                  <h3 className="text-lg font-semibold text-white mb-1">{account.name}</h3>
+                  <h3 className="text-lg font-semibold text-white mb-1 flex items-center gap-2">
+                    <AccountIcon accountId={account.id} icon={account.icon} />
+                    {account.name}
+                  </h3>
                   <div className={`inline-flex items-center px-2 py-1 rounded-full text-xs font-medium border ${getAccountTypeColor(account.account_type)}`}>
                     {account.account_type}
                   </div>
                 </div>
                 <div className="flex space-x-1">
+                  <button
+                    onClick={() => toggleFavorite(account.id)}
+                    className={`p-1 transition-colors ${account.is_favorite ? 'text-yellow-400' : 'text-muted hover:text-white'}`}
+                  >
+                    <Star className="w-4 h-4" fill={account.is_favorite ? 'currentColor' : 'none'} />
+                  </button>
                   <button
                     onClick={() => togglePasswordVisibility(account.id)}
                     className="p-1 text-muted hover:text-white transition-colors"
                   >
-                    {showPasswords[account.id] ? <EyeOff className="w-4 h-4" /> : <Eye className="w-4 h-4" />}
+                    {revealed[account.id] !== undefined ? <EyeOff className="w-4 h-4" /> : <Eye className="w-4 h-4" />}
                   </button>
                   <button className="p-1 text-muted hover:text-white transition-colors">
                     <Edit className="w-4 h-4" />
@@ -270,10 +378,10 @@ const Dashboard: React.FC = () => {
                   <label className="text-xs text-muted uppercase tracking-wide">Password</label>
                   <div className="flex items-center space-x-2">
                     <p className="text-sm text-white flex-1 font-mono">
-                      {showPasswords[account.id] ? account.password : '••••••••••••'}
+                      {revealed[account.id] ?? '••••••••••••'}
                     </p>
                     <button
-                      onClick={() => copyToClipboard(account.password)}
+                      onClick={() => copyPassword(account)}
                       className="p-1 text-muted hover:text-white transition-colors"
                     >
                       <Copy className="w-4 h-4" />
@@ -315,11 +423,35 @@ const Dashboard: React.FC = () => {
         onClose={handleDeleteCancel}
         onConfirm={handleDeleteConfirm}
         title="Delete Account"
-        message={`Are you sure you want to delete "${deleteModal.accountName}"? This action cannot be undone and all data for this account will be permanently removed.`}
+        message={`Are you sure you want to delete "${deleteModal.accountName}"? It moves to the trash, where it can be restored with \`passman trash restore\` until the trash is purged.`}
         confirmText="Delete"
         cancelText="Cancel"
         type="danger"
       />
+
+      {/* Master password confirmation before revealing */}
+      {confirmReveal && (
+        <div className="fixed inset-0 bg-black/50 flex items-center justify-center z-50">
+          <form onSubmit={handleConfirmReveal} className="card w-full max-w-sm space-y-4">
+            <h2 className="text-lg font-semibold text-white">Confirm Master Password</h2>
+            <input
+              autoFocus
+              type="password"
+              value={confirmReveal.password}
+              onChange={(e) => setConfirmReveal({ ...confirmReveal, password: e.target.value })}
+              className="input-field w-full"
+              placeholder="Master password"
+            />
+            {confirmReveal.error && <p className="text-sm text-red-400">{confirmReveal.error}</p>}
+            <div className="flex justify-end space-x-2">
+              <button type="button" onClick={() => setConfirmReveal(null)} className="px-4 py-2 text-muted hover:text-white">
+                Cancel
+              </button>
+              <button type="submit" className="btn-primary">Reveal</button>
+            </div>
+          </form>
+        </div>
+      )}
     </div>
   )
 }
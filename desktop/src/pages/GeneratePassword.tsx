@@ -1,6 +1,8 @@
 import { useState, useEffect } from 'react'
 import { motion } from 'framer-motion'
+import { invoke } from '@tauri-apps/api/core'
 import { 
+  History,
   Key, 
   Copy, 
   RefreshCw, 
@@ -9,8 +11,10 @@ import {
   Check, 
   Settings,
   Zap,
-  Shield
+  Shield,
+  Trash2
 } from 'lucide-react'
+import { GeneratedPassword } from '../types'
 
 interface PasswordOptions {
   length: number
@@ -34,6 +38,8 @@ export default function GeneratePassword() {
   const [showPassword, setShowPassword] = useState(false)
   const [copied, setCopied] = useState(false)
   const [strength, setStrength] = useState<PasswordStrength>({ score: 0, description: '', color: '' })
+  const [history, setHistory] = useState<GeneratedPassword[]>([])
+  const [copiedHistory, setCopiedHistory] = useState<string | null>(null)
   
   const [options, setOptions] = useState<PasswordOptions>({
     length: 16,
@@ -55,22 +61,31 @@ export default function GeneratePassword() {
     generatePassword()
   }, [])
 
+  const loadHistory = async () => {
+    try {
+      setHistory(await invoke<GeneratedPassword[]>('get_generation_history'))
+    } catch (error) {
+      // Only available while the vault is unlocked
+      setHistory([])
+    }
+  }
+
   const generatePassword = async () => {
     setIsGenerating(true)
     try {
-      // This would call a Tauri command to generate password
-      // For now, we'll simulate it
-      await new Promise(resolve => setTimeout(resolve, 500))
-      
-      // Generate a simple password for demo
-      const chars = getCharacterSet()
-      let generated = ''
-      for (let i = 0; i < options.length; i++) {
-        generated += chars[Math.floor(Math.random() * chars.length)]
-      }
+      const generated = await invoke<string>('generate_password', {
+        length: options.length,
+        includeUppercase: options.includeUppercase,
+        includeLowercase: options.includeLowercase,
+        includeNumbers: options.includeNumbers,
+        includeSpecial: options.includeSpecial,
+        excludeSimilar: options.excludeSimilar,
+        excludeAmbiguous: options.excludeAmbiguous
+      })
       
       setPassword(generated)
       calculateStrength(generated)
+      loadHistory()
     } catch (error) {
       console.error('Error generating password:', error)
     } finally {
@@ -78,21 +93,23 @@ export default function GeneratePassword() {
     }
   }
 
-  const getCharacterSet = () => {
-    let chars = ''
-    if (options.includeLowercase) chars += 'abcdefghijklmnopqrstuvwxyz'
-    if (options.includeUppercase) chars += 'ABCDEFGHIJKLMNOPQRSTUVWXYZ'
-    if (options.includeNumbers) chars += '0123456789'
-    if (options.includeSpecial) chars += '!@#$%^&*()_+-=[]{}|;:,.<>?'
-    
-    if (options.excludeSimilar) {
-      chars = chars.replace(/[il1Lo0O]/g, '')
+  const clearHistory = async () => {
+    try {
+      await invoke('clear_generation_history')
+      setHistory([])
+    } catch (error) {
+      console.error('Failed to clear generation history:', error)
     }
-    if (options.excludeAmbiguous) {
-      chars = chars.replace(/[{}[\]()\/\\'"~,;.<>]/g, '')
+  }
+
+  const copyFromHistory = async (entry: GeneratedPassword) => {
+    try {
+      await navigator.clipboard.writeText(entry.password)
+      setCopiedHistory(entry.generated_at)
+      setTimeout(() => setCopiedHistory(null), 2000)
+    } catch (error) {
+      console.error('Failed to copy password:', error)
     }
-    
-    return chars
   }
 
   const calculateStrength = (pwd: string) => {
@@ -347,6 +364,52 @@ export default function GeneratePassword() {
           </div>
         </motion.div>
       </div>
+
+      {/* Generation History */}
+      {history.length > 0 && (
+        <motion.div
+          initial={{ opacity: 0, y: 20 }}
+          animate={{ opacity: 1, y: 0 }}
+          transition={{ delay: 0.3 }}
+          className="glass-surface rounded-xl p-6 mt-8"
+        >
+          <div className="flex items-center justify-between mb-6">
+            <div className="flex items-center gap-3">
+              <History className="w-6 h-6 text-primary" />
+              <h2 className="text-xl font-semibold">Recently Generated</h2>
+            </div>
+            <button
+              onClick={clearHistory}
+              className="btn-secondary text-sm py-2 flex items-center gap-2"
+            >
+              <Trash2 className="w-4 h-4" />
+              Clear
+            </button>
+          </div>
+
+          <div className="space-y-2">
+            {history.map((entry) => (
+              <div
+                key={entry.generated_at}
+                className="flex items-center gap-3 p-3 bg-surface border border-border rounded-lg"
+              >
+                <span className="flex-1 font-mono text-sm truncate">
+                  {showPassword ? entry.password : '•'.repeat(Math.min(entry.password.length, 24))}
+                </span>
+                <span className="text-xs text-muted">
+                  {new Date(entry.generated_at).toLocaleString()}
+                </span>
+                <button
+                  onClick={() => copyFromHistory(entry)}
+                  className="p-2 hover:bg-gray-700 rounded-lg transition-colors"
+                >
+                  {copiedHistory === entry.generated_at ? <Check className="w-4 h-4 text-green-500" /> : <Copy className="w-4 h-4" />}
+                </button>
+              </div>
+            ))}
+          </div>
+        </motion.div>
+      )}
     </motion.div>
   )
 }
@@ -1,9 +1,10 @@
-import React, { useState } from 'react'
+import React, { useEffect, useState } from 'react'
 import { motion } from 'framer-motion'
-import { Shield, Eye, EyeOff, Lock, AlertCircle } from 'lucide-react'
+import { Shield, Eye, EyeOff, Lock, AlertCircle, Fingerprint } from 'lucide-react'
 import { invoke } from '@tauri-apps/api/core'
-import { LoginFormData } from '../types'
+import { BiometricStatus, LoginFormData } from '../types'
 import { useAuth } from '../contexts/AuthContext'
+import { errorKind, errorMessage } from '../errors'
 
 interface LoginProps {
   onAuthenticated: () => void
@@ -19,6 +20,33 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
   const [error, setError] = useState('')
   const [showResetModal, setShowResetModal] = useState(false)
   const [isResetting, setIsResetting] = useState(false)
+  const [resetConfirmation, setResetConfirmation] = useState('')
+  const [biometricAvailable, setBiometricAvailable] = useState(false)
+
+  useEffect(() => {
+    invoke<BiometricStatus>('get_biometric_status')
+      .then((status) => setBiometricAvailable(status.available))
+      .catch(() => setBiometricAvailable(false))
+  }, [])
+
+  const handleBiometricUnlock = async () => {
+    setIsLoading(true)
+    setError('')
+    try {
+      // False means fall back to the master password
+      if (await invoke<boolean>('unlock_with_biometrics')) {
+        onAuthenticated()
+      } else {
+        setError('Biometric unlock failed. Enter your master password.')
+      }
+    } catch (error) {
+      console.error('Biometric unlock error:', error)
+      setBiometricAvailable(false)
+      setError('Biometric unlock is unavailable. Enter your master password.')
+    } finally {
+      setIsLoading(false)
+    }
+  }
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault()
@@ -32,21 +60,15 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
     setError('')
     
     try {
-      // Call the Tauri command to verify password
-      const isValid = await invoke<boolean>('verify_password', { masterPassword: formData.master_password })
-      
-      if (isValid) {
-        // Store the master password in context
-        setMasterPassword(formData.master_password)
-        // Open the vault after successful authentication
-        await invoke('open_vault', { masterPassword: formData.master_password })
-        onAuthenticated()
-      } else {
-        setError('Invalid master password. Please try again.')
-      }
+      // One attempt: a wrong password fails here and counts toward the backoff
+      await invoke('open_vault', { masterPassword: formData.master_password })
+      // Store the master password in context
+      setMasterPassword(formData.master_password)
+      onAuthenticated()
     } catch (error) {
       console.error('Authentication error:', error)
-      setError('Invalid master password. Please try again.')
+      // A lockout says how long to wait; anything else is treated as a wrong password
+      setError(errorKind(error) === 'locked_out' ? errorMessage(error) : 'Invalid master password. Please try again.')
     } finally {
       setIsLoading(false)
     }
@@ -55,8 +77,8 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
   const handleReset = async () => {
     setIsResetting(true)
     try {
-      // Call a Tauri command to reset all configuration
-      await invoke('reset_passman')
+      // The vault is moved aside, not deleted, once its name is typed
+      await invoke<string | null>('reset_passman', { confirmation: resetConfirmation })
       setShowResetModal(false)
       setError('')
       // Reload the page to go back to setup
@@ -174,6 +196,21 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
             )}
           </motion.button>
 
+          {/* Biometric Unlock */}
+          {biometricAvailable && (
+            <motion.button
+              whileHover={{ scale: 1.02 }}
+              whileTap={{ scale: 0.98 }}
+              type="button"
+              onClick={handleBiometricUnlock}
+              disabled={isLoading}
+              className="w-full flex items-center justify-center space-x-2 border border-border rounded-lg py-3 text-white hover:border-primary disabled:opacity-50"
+            >
+              <Fingerprint className="w-5 h-5" />
+              <span>Unlock with Biometrics</span>
+            </motion.button>
+          )}
+
           {/* Reset Button */}
           <motion.button
             whileHover={{ scale: 1.02 }}
@@ -217,29 +254,36 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
               </div>
               <div>
                 <h3 className="text-xl font-bold text-white">Reset PassMan</h3>
-                <p className="text-sm text-muted">This will delete all your data</p>
+                <p className="text-sm text-muted">This starts over with a new vault</p>
               </div>
             </div>
 
             <div className="mb-6">
               <p className="text-muted mb-4">
-                This action will permanently delete:
-              </p>
-              <ul className="text-sm text-muted space-y-2 mb-4">
-                <li>• All stored passwords and accounts</li>
-                <li>• Your master password</li>
-                <li>• All vault data and settings</li>
-              </ul>
-              <p className="text-red-400 font-medium">
-                This action cannot be undone!
+                Your current vault and its backups are set aside as <span className="font-mono">main-reset-&lt;date&gt;</span>, not
+                deleted. If you remember the master password later, open it with{' '}
+                <span className="font-mono">passman use</span>.
               </p>
+              <label className="block text-sm font-medium text-white mb-2">
+                Type <span className="font-mono">main</span> to confirm
+              </label>
+              <input
+                type="text"
+                value={resetConfirmation}
+                onChange={(e) => setResetConfirmation(e.target.value)}
+                className="input-field w-full"
+                placeholder="main"
+              />
             </div>
 
             <div className="flex justify-end space-x-4">
               <motion.button
                 whileHover={{ scale: 1.02 }}
                 whileTap={{ scale: 0.98 }}
-                onClick={() => setShowResetModal(false)}
+                onClick={() => {
+                  setShowResetModal(false)
+                  setResetConfirmation('')
+                }}
                 className="px-6 py-3 text-muted hover:text-white transition-colors duration-200 rounded-md"
               >
                 Cancel
@@ -248,7 +292,7 @@ const Login: React.FC<LoginProps> = ({ onAuthenticated }) => {
                 whileHover={{ scale: 1.02 }}
                 whileTap={{ scale: 0.98 }}
                 onClick={handleReset}
-                disabled={isResetting}
+                disabled={isResetting || resetConfirmation.trim() !== 'main'}
                 className="btn-danger flex items-center space-x-2 disabled:opacity-50"
               >
                 {isResetting ? (
@@ -0,0 +1,90 @@
+import { Copy, Lock, Search } from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { Account, QuickSearchResult } from '../types'
+
+// Mini search window opened from the tray
+export default function QuickSearch() {
+  const [query, setQuery] = useState('')
+  const [results, setResults] = useState<QuickSearchResult[]>([])
+  const [locked, setLocked] = useState(false)
+  const [copiedId, setCopiedId] = useState<string | null>(null)
+
+  useEffect(() => {
+    const search = async () => {
+      try {
+        setResults(await invoke<QuickSearchResult[]>('quick_search', { query }))
+        setLocked(false)
+      } catch {
+        setResults([])
+        setLocked(true)
+      }
+    }
+    search()
+  }, [query])
+
+  const copyPassword = async (id: string) => {
+    const account = await invoke<Account | null>('get_account', { id })
+    if (account) {
+      await navigator.clipboard.writeText(account.password)
+      await invoke('record_access', { id })
+      setCopiedId(id)
+      setTimeout(() => invoke('hide_quick_search'), 600)
+    }
+  }
+
+  const unlock = async () => {
+    await invoke('show_main_window')
+    await invoke('hide_quick_search')
+  }
+
+  return (
+    <div
+      className="min-h-screen bg-background text-white p-4"
+      onKeyDown={(e) => e.key === 'Escape' && invoke('hide_quick_search')}
+    >
+      <div className="relative mb-3">
+        <Search className="absolute left-3 top-1/2 transform -translate-y-1/2 w-4 h-4 text-muted" />
+        <input
+          autoFocus
+          type="text"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          onKeyDown={(e) => e.key === 'Enter' && results[0] && copyPassword(results[0].id)}
+          placeholder="Search accounts..."
+          className="w-full pl-10 pr-4 py-2 bg-surface border border-border rounded-lg text-white"
+        />
+      </div>
+
+      {locked ? (
+        <div className="text-center text-muted py-8">
+          <Lock className="w-8 h-8 mx-auto mb-2" />
+          <p className="mb-3">PassMan is locked.</p>
+          <button onClick={unlock} className="text-primary underline">Unlock</button>
+        </div>
+      ) : (
+        <ul className="space-y-1">
+          {results.map((result) => (
+            <li key={result.id}>
+              <button
+                onClick={() => copyPassword(result.id)}
+                className="w-full flex items-center justify-between px-3 py-2 rounded-lg hover:bg-surface text-left"
+              >
+                <span>
+                  <span className="font-medium">{result.name}</span>
+                  {result.username && <span className="text-sm text-muted ml-2">{result.username}</span>}
+                </span>
+                {copiedId === result.id ? (
+                  <span className="text-sm text-primary">Copied</span>
+                ) : (
+                  <Copy className="w-4 h-4 text-muted" />
+                )}
+              </button>
+            </li>
+          ))}
+          {results.length === 0 && <li className="text-center text-muted py-8">No matching accounts</li>}
+        </ul>
+      )}
+    </div>
+  )
+}
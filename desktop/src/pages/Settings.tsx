@@ -4,12 +4,18 @@ import {
     Check,
     Database,
     Download,
+    History,
+    Key,
     Settings as SettingsIcon,
     Shield,
     Trash2,
     X
 } from 'lucide-react'
-import { useState } from 'react'
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { BackupInfo, BiometricStatus, PassManConfig, PasswordPolicy, RestoreConfirmation, SshConfirmation, VaultSettings } from '../types'
+import ConfirmationModal from '../components/ConfirmationModal'
+import { errorMessage } from '../errors'
 
 interface SettingsData {
   autoLock: boolean
@@ -17,11 +23,32 @@ interface SettingsData {
   clearClipboard: boolean
   clearClipboardTime: number
   showPasswords: boolean
+  fetchIcons: boolean
+  keepGenerationHistory: boolean
+  sshAgentConfirmation: SshConfirmation
   darkMode: boolean
   backupEnabled: boolean
   backupLocation: string
 }
 
+// Starting point when a policy is first turned on
+const DEFAULT_POLICY: PasswordPolicy = {
+  min_length: 12,
+  require_uppercase: false,
+  require_lowercase: false,
+  require_digit: false,
+  require_special: false,
+  min_strength: 0,
+  deny_common: true
+}
+
+const POLICY_CLASSES: [keyof PasswordPolicy, string][] = [
+  ['require_uppercase', 'Uppercase'],
+  ['require_lowercase', 'Lowercase'],
+  ['require_digit', 'Digit'],
+  ['require_special', 'Special']
+]
+
 export default function Settings() {
   const [settings, setSettings] = useState<SettingsData>({
     autoLock: true,
@@ -29,19 +56,212 @@ export default function Settings() {
     clearClipboard: true,
     clearClipboardTime: 30,
     showPasswords: false,
+    fetchIcons: false,
+    keepGenerationHistory: false,
+    sshAgentConfirmation: 'Confirm',
     darkMode: true,
-    backupEnabled: false,
+    backupEnabled: true,
     backupLocation: ''
   })
 
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false)
   const [isExporting, setIsExporting] = useState(false)
+  const [biometric, setBiometric] = useState<BiometricStatus | null>(null)
+  const [biometricError, setBiometricError] = useState('')
+  const [vaultSettings, setVaultSettings] = useState<VaultSettings | null>(null)
+  const [config, setConfig] = useState<PassManConfig | null>(null)
+  const [backups, setBackups] = useState<BackupInfo[]>([])
+  const [backupError, setBackupError] = useState('')
+  const [pendingRestore, setPendingRestore] = useState<RestoreConfirmation | null>(null)
+  const [signingKey, setSigningKey] = useState<string | null>(null)
+  const [showReplaceKeyConfirm, setShowReplaceKeyConfirm] = useState(false)
+
+  useEffect(() => {
+    invoke<BiometricStatus>('get_biometric_status').then(setBiometric).catch(() => setBiometric(null))
+  }, [])
+
+  useEffect(() => {
+    // Security preferences live in the vault, so the backend enforces them
+    invoke<VaultSettings>('get_settings')
+      .then((stored) => {
+        setVaultSettings(stored)
+        setSettings(prev => ({
+          ...prev,
+          autoLock: stored.auto_lock_timeout > 0,
+          autoLockTime: stored.auto_lock_timeout || prev.autoLockTime,
+          clearClipboard: stored.auto_clear_clipboard,
+          clearClipboardTime: stored.clipboard_timeout,
+          fetchIcons: stored.fetch_icons,
+          keepGenerationHistory: stored.keep_generation_history,
+          sshAgentConfirmation: stored.ssh_agent_confirmation
+        }))
+      })
+      .catch((error) => console.error('Failed to load settings:', error))
+  }, [])
+
+  useEffect(() => {
+    // Backup policy lives in config.toml, shared with the CLI
+    invoke<PassManConfig>('get_config')
+      .then((stored) => {
+        setConfig(stored)
+        setSettings(prev => ({
+          ...prev,
+          backupEnabled: stored.backups.automatic,
+          backupLocation: stored.backups.dir ?? ''
+        }))
+      })
+      .catch((error) => console.error('Failed to load config:', error))
+  }, [])
+
+  const saveConfig = async (next: SettingsData) => {
+    if (!config) return
+    const updated: PassManConfig = {
+      ...config,
+      backups: { ...config.backups, automatic: next.backupEnabled }
+    }
+    try {
+      await invoke('update_config', { config: updated })
+      setConfig(updated)
+    } catch (error) {
+      console.error('Failed to save config:', error)
+    }
+  }
+
+  const saveVaultSettings = async (next: SettingsData) => {
+    if (!vaultSettings) return
+    const updated: VaultSettings = {
+      ...vaultSettings,
+      auto_lock_timeout: next.autoLock ? next.autoLockTime : 0,
+      auto_clear_clipboard: next.clearClipboard,
+      clipboard_timeout: next.clearClipboardTime,
+      fetch_icons: next.fetchIcons,
+      keep_generation_history: next.keepGenerationHistory,
+      ssh_agent_confirmation: next.sshAgentConfirmation
+    }
+    try {
+      await invoke('update_settings', { settings: updated })
+      setVaultSettings(updated)
+    } catch (error) {
+      console.error('Failed to save settings:', error)
+    }
+  }
+
+  const savePasswordPolicy = async (policy: PasswordPolicy | null) => {
+    if (!vaultSettings) return
+    const updated: VaultSettings = { ...vaultSettings, password_policy: policy }
+    try {
+      await invoke('update_settings', { settings: updated })
+      setVaultSettings(updated)
+    } catch (error) {
+      console.error('Failed to save password policy:', error)
+    }
+  }
+
+  const changePasswordPolicy = (change: Partial<PasswordPolicy>) => {
+    const current = vaultSettings?.password_policy ?? DEFAULT_POLICY
+    savePasswordPolicy({ ...current, ...change })
+  }
+
+  const loadBackups = async () => {
+    try {
+      setBackups(await invoke<BackupInfo[]>('list_backups'))
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    }
+  }
+
+  useEffect(() => {
+    loadBackups()
+    invoke<string | null>('get_signing_key').then(setSigningKey).catch(() => setSigningKey(null))
+  }, [])
+
+  const handleGenerateSigningKey = async () => {
+    setBackupError('')
+    try {
+      setSigningKey(await invoke<string>('generate_signing_key'))
+      await loadBackups()
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    } finally {
+      setShowReplaceKeyConfirm(false)
+    }
+  }
+
+  const handleRemoveSigningKey = async () => {
+    setBackupError('')
+    try {
+      await invoke('remove_signing_key')
+      setSigningKey(null)
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    }
+  }
+
+  const restoreMessage = (pending: RestoreConfirmation) => {
+    const when = new Date(pending.backup.created_at).toLocaleString()
+    const unsigned = pending.signature === 'Unsigned' && signingKey
+      ? ' This backup is not signed, so it can\'t be checked for tampering.'
+      : ''
+    const changes = pending.changes
+      ? ` Since then ${pending.changes.added.length} account(s) were added, ${pending.changes.removed.length} removed, and ${pending.changes.modified.length} changed.`
+      : ''
+    return `Replace the vault with the backup from ${when}? Changes made since then are lost (the current state is backed up first).${changes}${unsigned}`
+  }
+
+  const handleCreateBackup = async () => {
+    setBackupError('')
+    try {
+      await invoke<BackupInfo>('create_backup')
+      await loadBackups()
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    }
+  }
+
+  const handleRestoreRequest = async (timestamp: string) => {
+    setBackupError('')
+    try {
+      setPendingRestore(await invoke<RestoreConfirmation>('request_restore', { timestamp }))
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    }
+  }
+
+  const handleRestoreConfirm = async () => {
+    if (!pendingRestore) return
+    try {
+      // If the backup needs a different password the app locks itself
+      await invoke<boolean>('restore_backup', {
+        timestamp: pendingRestore.backup.timestamp,
+        confirmationToken: pendingRestore.token
+      })
+      await loadBackups()
+    } catch (error) {
+      setBackupError(errorMessage(error))
+    } finally {
+      setPendingRestore(null)
+    }
+  }
+
+  const handleBiometricToggle = async () => {
+    if (!biometric) return
+    setBiometricError('')
+    try {
+      await invoke(biometric.enabled ? 'disable_biometric_unlock' : 'enable_biometric_unlock')
+      setBiometric(await invoke<BiometricStatus>('get_biometric_status'))
+    } catch (error) {
+      setBiometricError(errorMessage(error))
+    }
+  }
 
   const handleSettingChange = (key: keyof SettingsData, value: any) => {
-    setSettings(prev => ({
-      ...prev,
-      [key]: value
-    }))
+    const next = { ...settings, [key]: value }
+    setSettings(next)
+    if (key === 'backupEnabled') {
+      saveConfig(next)
+    } else {
+      saveVaultSettings(next)
+    }
   }
 
   const handleExportVault = async () => {
@@ -140,6 +360,30 @@ export default function Settings() {
               </div>
             </div>
 
+            {/* Biometric Unlock */}
+            {biometric?.supported && (
+              <div className="flex items-center justify-between">
+                <div>
+                  <h3 className="font-medium mb-1">Biometric Unlock</h3>
+                  <p className="text-sm text-muted">
+                    {biometricError || 'Unlock with your fingerprint or face instead of the master password'}
+                  </p>
+                </div>
+                <button
+                  onClick={handleBiometricToggle}
+                  className={`relative w-12 h-6 rounded-full transition-colors ${
+                    biometric.enabled ? 'bg-primary' : 'bg-surface border border-border'
+                  }`}
+                >
+                  <div
+                    className={`absolute w-5 h-5 bg-white rounded-full transition-transform top-0.5 ${
+                      biometric.enabled ? 'translate-x-6' : 'translate-x-0.5'
+                    }`}
+                  />
+                </button>
+              </div>
+            )}
+
             {/* Clear Clipboard */}
             <div className="flex items-center justify-between">
               <div>
@@ -192,6 +436,131 @@ export default function Settings() {
                 />
               </button>
             </div>
+
+            {/* Site Icons */}
+            <div className="flex items-center justify-between">
+              <div>
+                <h3 className="font-medium mb-1">Show Site Icons</h3>
+                <p className="text-sm text-muted">Download favicons from your accounts' websites (reveals which sites you use to them)</p>
+              </div>
+              <button
+                onClick={() => handleSettingChange('fetchIcons', !settings.fetchIcons)}
+                className={`relative w-12 h-6 rounded-full transition-colors ${
+                  settings.fetchIcons ? 'bg-primary' : 'bg-surface border border-border'
+                }`}
+              >
+                <div
+                  className={`absolute w-5 h-5 bg-white rounded-full transition-transform top-0.5 ${
+                    settings.fetchIcons ? 'translate-x-6' : 'translate-x-0.5'
+                  }`}
+                />
+              </button>
+            </div>
+
+            {/* Generation History */}
+            <div className="flex items-center justify-between">
+              <div>
+                <h3 className="font-medium mb-1">Remember Generated Passwords</h3>
+                <p className="text-sm text-muted">Keep the last 20 generated passwords in the vault so unsaved ones can be recovered</p>
+              </div>
+              <button
+                onClick={() => handleSettingChange('keepGenerationHistory', !settings.keepGenerationHistory)}
+                className={`relative w-12 h-6 rounded-full transition-colors ${
+                  settings.keepGenerationHistory ? 'bg-primary' : 'bg-surface border border-border'
+                }`}
+              >
+                <div
+                  className={`absolute w-5 h-5 bg-white rounded-full transition-transform top-0.5 ${
+                    settings.keepGenerationHistory ? 'translate-x-6' : 'translate-x-0.5'
+                  }`}
+                />
+              </button>
+            </div>
+
+            {/* SSH Agent */}
+            <div className="flex items-center justify-between">
+              <div>
+                <h3 className="font-medium mb-1">SSH Key Approval</h3>
+                <p className="text-sm text-muted">What passman ssh-agent asks before signing with a stored SSH key</p>
+              </div>
+              <select
+                value={settings.sshAgentConfirmation}
+                onChange={(e) => handleSettingChange('sshAgentConfirmation', e.target.value)}
+                className="bg-surface border border-border rounded-lg px-3 py-2 text-sm text-white"
+              >
+                <option value="Confirm">Confirm each use</option>
+                <option value="Reauthenticate">Master password each use</option>
+                <option value="Never">Don't ask</option>
+              </select>
+            </div>
+
+            {/* Password Policy */}
+            <div>
+              <div className="flex items-center justify-between">
+                <div>
+                  <h3 className="font-medium mb-1">Password Policy</h3>
+                  <p className="text-sm text-muted">Reject new or changed passwords that break these rules</p>
+                </div>
+                <button
+                  onClick={() => savePasswordPolicy(vaultSettings?.password_policy ? null : DEFAULT_POLICY)}
+                  className={`relative w-12 h-6 rounded-full transition-colors ${
+                    vaultSettings?.password_policy ? 'bg-primary' : 'bg-surface border border-border'
+                  }`}
+                >
+                  <div
+                    className={`absolute w-5 h-5 bg-white rounded-full transition-transform top-0.5 ${
+                      vaultSettings?.password_policy ? 'translate-x-6' : 'translate-x-0.5'
+                    }`}
+                  />
+                </button>
+              </div>
+              {vaultSettings?.password_policy && (
+                <div className="mt-4 grid grid-cols-2 gap-4 text-sm">
+                  <label className="flex items-center justify-between gap-2">
+                    <span>Minimum length</span>
+                    <input
+                      type="number"
+                      min={0}
+                      max={128}
+                      value={vaultSettings.password_policy.min_length}
+                      onChange={(e) => changePasswordPolicy({ min_length: parseInt(e.target.value) || 0 })}
+                      className="w-20 bg-surface border border-border rounded-lg px-3 py-1 text-white"
+                    />
+                  </label>
+                  <label className="flex items-center justify-between gap-2">
+                    <span>Minimum strength (0-100)</span>
+                    <input
+                      type="number"
+                      min={0}
+                      max={100}
+                      value={vaultSettings.password_policy.min_strength}
+                      onChange={(e) => changePasswordPolicy({ min_strength: Math.min(100, parseInt(e.target.value) || 0) })}
+                      className="w-20 bg-surface border border-border rounded-lg px-3 py-1 text-white"
+                    />
+                  </label>
+                  {POLICY_CLASSES.map(([key, label]) => (
+                    <label key={key} className="flex items-center gap-2">
+                      <input
+                        type="checkbox"
+                        checked={Boolean(vaultSettings.password_policy?.[key])}
+                        onChange={(e) => changePasswordPolicy({ [key]: e.target.checked })}
+                        className="rounded border-muted/20"
+                      />
+                      <span>Require {label.toLowerCase()}</span>
+                    </label>
+                  ))}
+                  <label className="flex items-center gap-2">
+                    <input
+                      type="checkbox"
+                      checked={vaultSettings.password_policy.deny_common}
+                      onChange={(e) => changePasswordPolicy({ deny_common: e.target.checked })}
+                      className="rounded border-muted/20"
+                    />
+                    <span>Reject common passwords</span>
+                  </label>
+                </div>
+              )}
+            </div>
           </div>
         </motion.div>
 
@@ -212,7 +581,7 @@ export default function Settings() {
             <div className="flex items-center justify-between">
               <div>
                 <h3 className="font-medium mb-1">Automatic Backup</h3>
-                <p className="text-sm text-muted">Automatically backup your vault</p>
+                <p className="text-sm text-muted">Back up the vault file before every change (applies after the next unlock)</p>
               </div>
               <button
                 onClick={() => handleSettingChange('backupEnabled', !settings.backupEnabled)}
@@ -236,7 +605,7 @@ export default function Settings() {
                   <input
                     type="text"
                     value={settings.backupLocation}
-                    placeholder="Select backup folder..."
+                    placeholder="Next to the vault (default)"
                     readOnly
                     className="flex-1 bg-surface border border-border rounded-lg px-4 py-2 text-sm"
                   />
@@ -250,6 +619,70 @@ export default function Settings() {
               </div>
             )}
 
+            {/* Restore Points */}
+            <div className="pt-4 border-t border-border">
+              <div className="flex items-center justify-between mb-2">
+                <h3 className="font-medium">Restore Points</h3>
+                <button onClick={handleCreateBackup} className="btn-secondary px-4 py-2 text-sm">
+                  Back Up Now
+                </button>
+              </div>
+              <p className="text-sm text-muted mb-4">
+                The last {config?.backups.keep ?? 10} backups are kept. Restoring one replaces all accounts.
+              </p>
+              {backupError && <p className="text-sm text-red-500 mb-2">{backupError}</p>}
+              <ul className="space-y-2">
+                {backups.map((backup) => (
+                  <li key={backup.timestamp} className="flex items-center justify-between bg-surface border border-border rounded-lg px-4 py-2">
+                    <span className="text-sm">
+                      {new Date(backup.created_at).toLocaleString()}
+                      <span className="text-muted ml-2">{(backup.size / 1024).toFixed(1)} KB</span>
+                      {backup.signed && <span className="text-muted ml-2">signed</span>}
+                    </span>
+                    <button
+                      onClick={() => handleRestoreRequest(backup.timestamp)}
+                      className="flex items-center gap-1 text-sm text-primary hover:underline"
+                    >
+                      <History className="w-4 h-4" />
+                      Restore
+                    </button>
+                  </li>
+                ))}
+                {backups.length === 0 && <li className="text-sm text-muted">No backups yet</li>}
+              </ul>
+            </div>
+
+            {/* Signing Key */}
+            <div className="pt-4 border-t border-border">
+              <div className="flex items-center justify-between mb-2">
+                <h3 className="font-medium flex items-center gap-2">
+                  <Key className="w-4 h-4" />
+                  Signing Key
+                </h3>
+                <div className="flex gap-2">
+                  {signingKey && (
+                    <button onClick={handleRemoveSigningKey} className="btn-secondary px-4 py-2 text-sm">
+                      Remove
+                    </button>
+                  )}
+                  <button
+                    onClick={() => signingKey ? setShowReplaceKeyConfirm(true) : handleGenerateSigningKey()}
+                    className="btn-secondary px-4 py-2 text-sm"
+                  >
+                    {signingKey ? 'Replace' : 'Create'}
+                  </button>
+                </div>
+              </div>
+              <p className="text-sm text-muted mb-2">
+                Signs backups and exports so a copy changed on untrusted storage is refused when restored or imported.
+              </p>
+              {signingKey && (
+                <code className="block text-xs break-all bg-surface border border-border rounded-lg px-4 py-2">
+                  {signingKey}
+                </code>
+              )}
+            </div>
+
             {/* Export Vault */}
             <div className="pt-4 border-t border-border">
               <h3 className="font-medium mb-2">Export Vault</h3>
@@ -332,6 +765,26 @@ export default function Settings() {
           </motion.div>
         </div>
       )}
+
+      <ConfirmationModal
+        isOpen={pendingRestore !== null}
+        onClose={() => setPendingRestore(null)}
+        onConfirm={handleRestoreConfirm}
+        title="Restore Backup"
+        message={pendingRestore ? restoreMessage(pendingRestore) : ''}
+        confirmText="Restore"
+        type="warning"
+      />
+
+      <ConfirmationModal
+        isOpen={showReplaceKeyConfirm}
+        onClose={() => setShowReplaceKeyConfirm(false)}
+        onConfirm={handleGenerateSigningKey}
+        title="Replace Signing Key"
+        message="Backups and exports signed with the current key will no longer verify and can't be restored or imported. Continue?"
+        confirmText="Replace"
+        type="warning"
+      />
     </motion.div>
   )
 }
@@ -2,14 +2,95 @@
 export interface Account {
   id: string
   name: string
-  account_type: AccountType
+  account_type: AccountTypeName
   url?: string
   username?: string
   password: string
   notes?: string
   tags: string[]
+  is_favorite?: boolean
+  // Label color as #rrggbb, and an emoji or image URL shown next to the name
+  color?: string | null
+  icon?: string | null
+  autotype?: string
+  custom_fields?: CustomField[]
+  ssh_key?: SshKey | null
+  card?: PaymentCard | null
+  identity?: Identity | null
+  // Fake credentials watched by external monitoring; not audited
+  canary?: boolean
+  // The password has to be changed by this date or this many days after a change
+  expires_at?: string | null
+  rotation_interval_days?: number | null
   created_at: string
   updated_at: string
+  last_accessed?: string | null
+  // Times the password was copied, revealed, or typed
+  access_count?: number
+}
+
+// Fields to change with `update_account`: missing keys are kept, null clears
+export interface AccountPatch {
+  name?: string
+  account_type?: AccountTypeName
+  password?: string
+  url?: string | null
+  username?: string | null
+  notes?: string | null
+  tags?: string[]
+  folder?: string | null
+  is_favorite?: boolean
+  color?: string | null
+  icon?: string | null
+  totp?: string | null
+  custom_fields?: CustomField[]
+  autotype?: string | null
+  expires_at?: string | null
+  rotation_interval_days?: number | null
+  card?: PaymentCard | null
+  identity?: Identity | null
+}
+
+// Extra value such as a PIN or security question; hidden values are masked
+export interface CustomField {
+  name: string
+  value: string
+  hidden: boolean
+}
+
+// Held by accounts of type Card; the number is digits only
+export interface PaymentCard {
+  cardholder?: string | null
+  brand?: string | null
+  number: string
+  expiry_month?: number | null
+  expiry_year?: number | null
+  cvv?: string | null
+}
+
+// Held by accounts of type Identity; the address may span several lines
+export interface Identity {
+  title?: string | null
+  first_name?: string | null
+  middle_name?: string | null
+  last_name?: string | null
+  company?: string | null
+  email?: string | null
+  phone?: string | null
+  address?: string | null
+  city?: string | null
+  state?: string | null
+  postal_code?: string | null
+  country?: string | null
+  national_id?: string | null
+  passport_number?: string | null
+  license_number?: string | null
+}
+
+// Served by `passman ssh-agent`
+export interface SshKey {
+  private_key: string
+  public_key: string
 }
 
 export enum AccountType {
@@ -17,9 +98,15 @@ export enum AccountType {
   Banking = 'Banking',
   Work = 'Work',
   Personal = 'Personal',
+  Card = 'Card',
+  Identity = 'Identity',
+  SshKey = 'SshKey',
   Other = 'Other'
 }
 
+// A built-in type, or the name of a custom category (see `get_categories`)
+export type AccountTypeName = AccountType | string
+
 export interface PasswordOptions {
   length: number
   include_uppercase: boolean
@@ -30,6 +117,141 @@ export interface PasswordOptions {
   exclude_ambiguous: boolean
 }
 
+export interface BackupInfo {
+  timestamp: string
+  created_at: string
+  size: number
+  signed: boolean
+}
+
+// Detached signature of a backup or export, checked against the vault's signing key
+export type SignatureStatus = 'Valid' | 'Invalid' | 'Unsigned' | 'NoKey'
+
+// Changes between two versions of a vault; secret values are masked
+export interface VaultDiff {
+  added: AccountRef[]
+  removed: AccountRef[]
+  modified: AccountDiff[]
+}
+
+export interface AccountRef {
+  id: string
+  name: string
+}
+
+export interface AccountDiff {
+  id: string
+  name: string
+  changes: FieldChange[]
+}
+
+export interface FieldChange {
+  field: string
+  old: string | null
+  new: string | null
+  secret: boolean
+}
+
+export interface RestoreConfirmation {
+  token: string
+  backup: BackupInfo
+  signature: SignatureStatus
+  changes: VaultDiff | null
+  expires_in_seconds: number
+}
+
+export interface RevealedPassword {
+  id: string
+  password: string
+  ttl_seconds: number
+}
+
+export interface GeneratedPassword {
+  password: string
+  generated_at: string
+}
+
+export interface VaultSettings {
+  auto_lock_timeout: number
+  require_confirmation: boolean
+  auto_clear_clipboard: boolean
+  clipboard_timeout: number
+  show_strength_indicators: boolean
+  default_password_options: PasswordOptions
+  fetch_icons: boolean
+  keep_generation_history: boolean
+  ssh_agent_confirmation: SshConfirmation
+  password_policy?: PasswordPolicy | null
+  search_cache_minutes: number
+  trash_retention_days: number
+}
+
+export type SshConfirmation = 'Never' | 'Confirm' | 'Reauthenticate'
+
+// Rules enforced when passwords are added or changed
+export interface PasswordPolicy {
+  min_length: number
+  require_uppercase: boolean
+  require_lowercase: boolean
+  require_digit: boolean
+  require_special: boolean
+  min_strength: number
+  deny_common: boolean
+}
+
+// Error returned by Tauri commands (see src-tauri/src/error.rs)
+export interface CommandError {
+  code: number
+  kind: ErrorKind
+  message: string
+  violations?: PolicyViolation[]
+}
+
+export type ErrorKind =
+  | 'authentication_failed'
+  | 'locked_out'
+  | 'vault_locked'
+  | 'vault_not_found'
+  | 'vault_exists'
+  | 'vault_corrupted'
+  | 'account_not_found'
+  | 'invalid_input'
+  | 'policy_violation'
+  | 'storage_error'
+  | 'io_error'
+  | 'serialization_error'
+  | 'encryption_error'
+  | 'crypto_error'
+  | 'other'
+
+export interface PolicyViolation {
+  rule: string
+  min?: number
+  actual?: number
+  score?: number
+}
+
+// Library settings shared with the CLI (config.toml)
+export interface PassManConfig {
+  vault_dir?: string
+  kdf?: KdfParams
+  backups: BackupPolicy
+  locale?: string
+  telemetry: boolean
+}
+
+export interface KdfParams {
+  memory_kib: number
+  iterations: number
+  parallelism: number
+}
+
+export interface BackupPolicy {
+  automatic: boolean
+  keep: number
+  dir?: string
+}
+
 export interface VaultInfo {
   size: number
   modified?: number
@@ -40,6 +262,66 @@ export interface PasswordStrength {
   description: string
 }
 
+// Import/export (import_file, export_file, and the transfer-progress event)
+export type TransferFormat = 'json' | 'csv'
+
+export type DedupePolicy = 'skip' | 'overwrite' | 'merge' | 'keep_both'
+
+export interface ImportReport {
+  added: number
+  overwritten: number
+  merged: number
+  skipped: number
+  errors: string[]
+  dry_run: boolean
+  signature: SignatureStatus
+}
+
+export interface TransferProgress {
+  operation: 'import' | 'export'
+  stage: 'reading' | 'merging' | 'writing'
+  processed: number
+  total: number
+}
+
+// Tray quick search hit (no secrets; fetch the account to copy its password)
+export interface QuickSearchResult {
+  id: string
+  name: string
+  username?: string
+  url?: string
+}
+
+export interface BiometricStatus {
+  supported: boolean
+  enabled: boolean
+  available: boolean
+}
+
+// Password health audit (get_health_report)
+export interface AuditEntry {
+  id: string
+  name: string
+  username?: string
+  strength: number
+  days_since_change: number
+  expires_at?: string | null
+}
+
+export interface HealthReport {
+  total: number
+  score: number
+  reused: AuditEntry[][]
+  weak: AuditEntry[]
+  stale: AuditEntry[]
+  breached: AuditEntry[]
+  expired: AuditEntry[]
+  expiring: AuditEntry[]
+  breach_checked: boolean
+  canaries: AuditEntry[]
+  tripped_canaries: AuditEntry[]
+}
+
 // UI State types
 export interface AppState {
   isAuthenticated: boolean
@@ -53,12 +335,16 @@ export interface AppState {
 // Form types
 export interface AccountFormData {
   name: string
-  account_type: AccountType
+  account_type: AccountTypeName
   url: string
   username: string
   password: string
   notes: string
   tags: string[]
+  color: string
+  icon: string
+  autotype: string
+  custom_fields: CustomField[]
 }
 
 export interface SetupFormData {
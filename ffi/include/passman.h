@@ -0,0 +1,143 @@
+/*
+ * PassMan C API
+ *
+ * Bindings for the PassMan password manager backend (the `passman-ffi`
+ * crate, built as libpassman). Vault files are shared with the PassMan
+ * CLI and desktop app.
+ *
+ * - Fallible functions return a PassManStatus. After a failure,
+ *   passman_last_error_message() describes the error (per thread).
+ * - An open vault is an opaque PassManHandle, released with
+ *   passman_close_vault(). Don't use one handle from two threads at once.
+ * - Strings written to out parameters are owned by the caller and must be
+ *   released with passman_string_free(), which wipes them first.
+ * - Accounts are exchanged as JSON in the vault's own format.
+ * - The _async variants of open and create run the key derivation on a
+ *   background thread and report through a callback on that thread.
+ */
+
+#ifndef PASSMAN_H
+#define PASSMAN_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum PassManStatus {
+    PASSMAN_STATUS_OK = 0,
+    PASSMAN_STATUS_NULL_POINTER = 1,
+    PASSMAN_STATUS_INVALID_UTF8 = 2,
+    PASSMAN_STATUS_INVALID_JSON = 3,
+    PASSMAN_STATUS_AUTHENTICATION_FAILED = 4,
+    PASSMAN_STATUS_VAULT_NOT_FOUND = 5,
+    PASSMAN_STATUS_VAULT_LOCKED = 6,
+    PASSMAN_STATUS_ACCOUNT_NOT_FOUND = 7,
+    PASSMAN_STATUS_INVALID_INPUT = 8,
+    PASSMAN_STATUS_STORAGE_ERROR = 9,
+    PASSMAN_STATUS_CRYPTO_ERROR = 10,
+    PASSMAN_STATUS_PANIC = 11,
+    PASSMAN_STATUS_LOCKED_OUT = 12,
+    PASSMAN_STATUS_VAULT_EXISTS = 13,
+    PASSMAN_STATUS_VAULT_CORRUPTED = 14,
+    PASSMAN_STATUS_POLICY_VIOLATION = 15
+} PassManStatus;
+
+/* An open vault */
+typedef struct PassManHandle PassManHandle;
+
+/* Library version; static, do not free */
+const char *passman_version(void);
+
+/* Message of the last failed call on this thread, or NULL */
+char *passman_last_error_message(void);
+
+/* Release (and wipe) a string returned by this library; NULL is ignored */
+void passman_string_free(char *s);
+
+/* Create a new vault and open it */
+PassManStatus passman_create_vault(const char *vault_name,
+                                   const char *email,
+                                   const char *master_password,
+                                   PassManHandle **out_handle);
+
+/* Open an existing vault */
+PassManStatus passman_open_vault(const char *vault_name,
+                                 const char *master_password,
+                                 PassManHandle **out_handle);
+
+/*
+ * Result of passman_open_vault_async() or passman_create_vault_async(),
+ * called on a background thread. handle is the open vault if status is
+ * PASSMAN_STATUS_OK and NULL otherwise; message describes the error and
+ * is only valid during the call (NULL on success).
+ */
+typedef void (*PassManOpenCallback)(PassManStatus status,
+                                    PassManHandle *handle,
+                                    const char *message,
+                                    void *user_data);
+
+/*
+ * Create a vault on a background thread. Returns PASSMAN_STATUS_OK if the
+ * work started (callback reports the result) or an argument error (callback
+ * is not called). Strings are copied before this returns.
+ */
+PassManStatus passman_create_vault_async(const char *vault_name,
+                                         const char *email,
+                                         const char *master_password,
+                                         PassManOpenCallback callback,
+                                         void *user_data);
+
+/* Open a vault on a background thread; see passman_create_vault_async() */
+PassManStatus passman_open_vault_async(const char *vault_name,
+                                       const char *master_password,
+                                       PassManOpenCallback callback,
+                                       void *user_data);
+
+/* Close a vault, wipe its keys, and release the handle; NULL is ignored */
+void passman_close_vault(PassManHandle *handle);
+
+/* All accounts as a JSON array */
+PassManStatus passman_list_accounts(PassManHandle *handle, char **out_json);
+
+/* One account as a JSON object */
+PassManStatus passman_get_account(PassManHandle *handle,
+                                  const char *id,
+                                  char **out_json);
+
+/*
+ * Add an account. account_json is an object with "name", "account_type"
+ * and "password", and optionally "url", "username", "notes" and "tags".
+ * The new account's ID is written to out_id.
+ */
+PassManStatus passman_add_account(PassManHandle *handle,
+                                  const char *account_json,
+                                  char **out_id);
+
+/* Replace an account's fields; account_json as for passman_add_account */
+PassManStatus passman_update_account(PassManHandle *handle,
+                                     const char *id,
+                                     const char *account_json);
+
+/*
+ * Change only the fields in patch_json, such as {"password": "..."};
+ * null clears an optional field like "url" or "notes".
+ */
+PassManStatus passman_patch_account(PassManHandle *handle,
+                                    const char *id,
+                                    const char *patch_json);
+
+/* Move an account to the trash */
+PassManStatus passman_delete_account(PassManHandle *handle, const char *id);
+
+/*
+ * Generate a password. options_json holds the generator options
+ * ("length", "include_uppercase", ...) or is NULL for the defaults.
+ */
+PassManStatus passman_generate_password(const char *options_json,
+                                        char **out_password);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PASSMAN_H */
@@ -0,0 +1,682 @@
+//! # PassMan C API
+//! 
+//! A stable C ABI over the backend so apps written in Swift, C++, or any
+//! language with a C FFI can work with the same vault files as the CLI
+//! and desktop app. The matching header is `include/passman.h`.
+//! 
+//! ## Conventions
+//! - Every fallible function returns a `PassManStatus`; on failure,
+//!   `passman_last_error_message` describes the error (per thread).
+//! - An open vault is an opaque `PassManHandle`, released with
+//!   `passman_close_vault`. A handle must not be used from two threads
+//!   at once.
+//! - Results are written through `out_*` pointers. Strings returned this
+//!   way are owned by the caller and must be released with
+//!   `passman_string_free`, which wipes them first.
+//! - Accounts cross the boundary as JSON in the vault's own format.
+//! - Panics never cross the boundary; they are reported as
+//!   `PASSMAN_STATUS_PANIC`.
+//! - Opening and creating a vault run the key derivation, which takes
+//!   about a second. The `_async` variants do it on a background thread
+//!   and report through a callback, so UI threads and event loops (Swift
+//!   concurrency, Kotlin coroutines, Python asyncio) aren't blocked.
+
+use passman_backend::{
+    Account, AccountPatch, AccountType, PassMan, PassManError, PasswordOptions,
+    generator::PasswordGenerator,
+};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use uuid::Uuid;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Result of a C API call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassManStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was NULL
+    NullPointer = 1,
+    /// A string argument wasn't valid UTF-8
+    InvalidUtf8 = 2,
+    /// A JSON argument couldn't be parsed
+    InvalidJson = 3,
+    /// Wrong master password
+    AuthenticationFailed = 4,
+    /// The vault doesn't exist
+    VaultNotFound = 5,
+    /// The vault isn't open
+    VaultLocked = 6,
+    /// No account with the given ID
+    AccountNotFound = 7,
+    /// An argument was rejected (e.g. invalid generator options)
+    InvalidInput = 8,
+    /// Reading or writing the vault file failed
+    StorageError = 9,
+    /// Encryption or decryption failed
+    CryptoError = 10,
+    /// An internal error; the handle should be closed
+    Panic = 11,
+    /// Too many failed unlock attempts; retry later
+    LockedOut = 12,
+    /// A vault with that name already exists
+    VaultExists = 13,
+    /// The vault file is damaged
+    VaultCorrupted = 14,
+    /// The password breaks the vault's password policy
+    PolicyViolation = 15,
+}
+
+/// An open vault
+pub struct PassManHandle {
+    passman: PassMan,
+}
+
+/// Called when `passman_open_vault_async` or `passman_create_vault_async` finishes
+/// 
+/// Runs on a background thread. `handle` is the open vault (owned by the
+/// callee) if `status` is `Ok`, and NULL otherwise; `message` describes the
+/// error and is only valid during the call (NULL on success).
+pub type PassManOpenCallback = extern "C" fn(
+    status: PassManStatus,
+    handle: *mut PassManHandle,
+    message: *const c_char,
+    user_data: *mut c_void,
+);
+
+/// Caller context passed back to a callback on another thread
+struct UserData(*mut c_void);
+
+// SAFETY: the pointer is never dereferenced here, only handed back to the
+// caller, who promised it may be used from the callback's thread
+unsafe impl Send for UserData {}
+
+/// Fields of a new or updated account
+#[derive(Deserialize)]
+struct AccountInput {
+    name: String,
+    account_type: AccountType,
+    password: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A failed call, recorded for `passman_last_error_message`
+struct FfiError {
+    status: PassManStatus,
+    message: String,
+}
+
+impl FfiError {
+    fn new(status: PassManStatus, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<PassManError> for FfiError {
+    fn from(error: PassManError) -> Self {
+        let status = match error {
+            PassManError::AuthenticationFailed(_) => PassManStatus::AuthenticationFailed,
+            PassManError::LockedOut(_) => PassManStatus::LockedOut,
+            PassManError::VaultExists(_) => PassManStatus::VaultExists,
+            PassManError::VaultCorrupted(_) => PassManStatus::VaultCorrupted,
+            PassManError::PolicyViolation(_) => PassManStatus::PolicyViolation,
+            PassManError::VaultNotFound(_) => PassManStatus::VaultNotFound,
+            PassManError::VaultLocked(_) => PassManStatus::VaultLocked,
+            PassManError::AccountNotFound(_) => PassManStatus::AccountNotFound,
+            PassManError::InvalidInput(_) => PassManStatus::InvalidInput,
+            PassManError::StorageError(_) | PassManError::IoError(_) => PassManStatus::StorageError,
+            PassManError::SerializationError(_) => PassManStatus::StorageError,
+            PassManError::EncryptionError(_) | PassManError::CryptoError(_) => PassManStatus::CryptoError,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+thread_local! {
+    /// Message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run the body of an exported function, catching panics and recording errors
+fn call(f: impl FnOnce() -> Result<(), FfiError>) -> PassManStatus {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(FfiError::new(PassManStatus::Panic, "internal error")));
+    match result {
+        Ok(()) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            PassManStatus::Ok
+        }
+        Err(error) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(error.message));
+            error.status
+        }
+    }
+}
+
+/// Borrow a C string argument
+/// 
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string that outlives the call
+unsafe fn read_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::new(PassManStatus::NullPointer, format!("{} is NULL", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::new(PassManStatus::InvalidUtf8, format!("{} is not valid UTF-8", name)))
+}
+
+/// Parse an account ID argument
+/// 
+/// # Safety
+/// Same as `read_str`
+unsafe fn read_id(ptr: *const c_char) -> Result<Uuid, FfiError> {
+    read_str(ptr, "id")?
+        .parse()
+        .map_err(|_| FfiError::new(PassManStatus::InvalidInput, "id is not a valid account ID"))
+}
+
+/// Parse a JSON argument
+/// 
+/// # Safety
+/// Same as `read_str`
+unsafe fn read_json<T: for<'de> Deserialize<'de>>(ptr: *const c_char, name: &str) -> Result<T, FfiError> {
+    serde_json::from_str(read_str(ptr, name)?)
+        .map_err(|e| FfiError::new(PassManStatus::InvalidJson, format!("{}: {}", name, e)))
+}
+
+/// Borrow the vault behind a handle
+/// 
+/// # Safety
+/// `handle` must be NULL or a live handle from this library
+unsafe fn read_handle<'a>(handle: *mut PassManHandle) -> Result<&'a mut PassMan, FfiError> {
+    handle.as_mut()
+        .map(|handle| &mut handle.passman)
+        .ok_or_else(|| FfiError::new(PassManStatus::NullPointer, "handle is NULL"))
+}
+
+/// Store a result in an out pointer
+/// 
+/// # Safety
+/// `out` must be NULL or valid for writes
+unsafe fn write_out<T>(out: *mut T, value: T) -> Result<(), FfiError> {
+    if out.is_null() {
+        return Err(FfiError::new(PassManStatus::NullPointer, "output pointer is NULL"));
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// Hand a string to the caller (released with `passman_string_free`)
+/// 
+/// # Safety
+/// `out` must be NULL or valid for writes
+unsafe fn write_string(out: *mut *mut c_char, value: String) -> Result<(), FfiError> {
+    let value = CString::new(value).map_err(|e| {
+        let mut bytes = e.into_vec();
+        bytes.zeroize();
+        FfiError::new(PassManStatus::InvalidInput, "result contains a NUL byte")
+    })?;
+    let raw = value.into_raw();
+    write_out(out, raw).inspect_err(|_| passman_string_free(raw))
+}
+
+/// Hand a value to the caller as JSON
+/// 
+/// # Safety
+/// `out` must be NULL or valid for writes
+unsafe fn write_json<T: serde::Serialize>(out: *mut *mut c_char, value: &T) -> Result<(), FfiError> {
+    let json = serde_json::to_string(value).map_err(PassManError::from)?;
+    write_string(out, json)
+}
+
+/// Get the library version
+/// 
+/// # Returns
+/// A static NUL-terminated string; do not free it
+#[no_mangle]
+pub extern "C" fn passman_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Get the message of the last failed call on this thread
+/// 
+/// # Returns
+/// A string to release with `passman_string_free`, or NULL if the last
+/// call succeeded
+#[no_mangle]
+pub extern "C" fn passman_last_error_message() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_deref()
+            .and_then(|message| CString::new(message).ok())
+            .map_or(ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// Release a string returned by this library, wiping its contents
+/// 
+/// # Safety
+/// `s` must be NULL or a string from this library that hasn't been freed
+#[no_mangle]
+pub unsafe extern "C" fn passman_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        let mut bytes = CString::from_raw(s).into_bytes();
+        bytes.zeroize();
+    }
+}
+
+/// Create a new vault and open it
+/// 
+/// # Safety
+/// String arguments must be NUL-terminated; `out_handle` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_create_vault(
+    vault_name: *const c_char,
+    email: *const c_char,
+    master_password: *const c_char,
+    out_handle: *mut *mut PassManHandle,
+) -> PassManStatus {
+    call(|| {
+        let vault_name = read_str(vault_name, "vault_name")?;
+        let email = read_str(email, "email")?;
+        let master_password = read_str(master_password, "master_password")?;
+        if out_handle.is_null() {
+            return Err(FfiError::new(PassManStatus::NullPointer, "out_handle is NULL"));
+        }
+        
+        let mut passman = PassMan::new(vault_name)?;
+        passman.init_vault(email.to_string(), master_password)?;
+        write_out(out_handle, Box::into_raw(Box::new(PassManHandle { passman })))
+    })
+}
+
+/// Open an existing vault
+/// 
+/// # Safety
+/// String arguments must be NUL-terminated; `out_handle` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_open_vault(
+    vault_name: *const c_char,
+    master_password: *const c_char,
+    out_handle: *mut *mut PassManHandle,
+) -> PassManStatus {
+    call(|| {
+        let vault_name = read_str(vault_name, "vault_name")?;
+        let master_password = read_str(master_password, "master_password")?;
+        if out_handle.is_null() {
+            return Err(FfiError::new(PassManStatus::NullPointer, "out_handle is NULL"));
+        }
+        
+        let mut passman = PassMan::new(vault_name)?;
+        passman.open_vault(master_password)?;
+        write_out(out_handle, Box::into_raw(Box::new(PassManHandle { passman })))
+    })
+}
+
+/// Create a new vault on a background thread and pass it to `callback`
+/// 
+/// # Returns
+/// `Ok` if the work was started (the callback then reports the result),
+/// or the error of an invalid argument (the callback isn't called)
+/// 
+/// # Safety
+/// String arguments must be NUL-terminated; they are copied before this
+/// returns. `user_data` must be usable from the callback's thread.
+#[no_mangle]
+pub unsafe extern "C" fn passman_create_vault_async(
+    vault_name: *const c_char,
+    email: *const c_char,
+    master_password: *const c_char,
+    callback: Option<PassManOpenCallback>,
+    user_data: *mut c_void,
+) -> PassManStatus {
+    call(|| {
+        let vault_name = read_str(vault_name, "vault_name")?.to_string();
+        let email = read_str(email, "email")?.to_string();
+        let master_password = Zeroizing::new(read_str(master_password, "master_password")?.to_string());
+        let callback = callback.ok_or_else(|| FfiError::new(PassManStatus::NullPointer, "callback is NULL"))?;
+        
+        spawn_open(callback, UserData(user_data), move || {
+            let mut passman = PassMan::new(&vault_name)?;
+            passman.init_vault(email, &master_password)?;
+            Ok(passman)
+        });
+        Ok(())
+    })
+}
+
+/// Open an existing vault on a background thread and pass it to `callback`
+/// 
+/// # Returns
+/// `Ok` if the work was started (the callback then reports the result),
+/// or the error of an invalid argument (the callback isn't called)
+/// 
+/// # Safety
+/// String arguments must be NUL-terminated; they are copied before this
+/// returns. `user_data` must be usable from the callback's thread.
+#[no_mangle]
+pub unsafe extern "C" fn passman_open_vault_async(
+    vault_name: *const c_char,
+    master_password: *const c_char,
+    callback: Option<PassManOpenCallback>,
+    user_data: *mut c_void,
+) -> PassManStatus {
+    call(|| {
+        let vault_name = read_str(vault_name, "vault_name")?.to_string();
+        let master_password = Zeroizing::new(read_str(master_password, "master_password")?.to_string());
+        let callback = callback.ok_or_else(|| FfiError::new(PassManStatus::NullPointer, "callback is NULL"))?;
+        
+        spawn_open(callback, UserData(user_data), move || {
+            let mut passman = PassMan::new(&vault_name)?;
+            passman.open_vault(&master_password)?;
+            Ok(passman)
+        });
+        Ok(())
+    })
+}
+
+/// Open a vault on a new thread and report the result to a callback
+fn spawn_open(
+    callback: PassManOpenCallback,
+    user_data: UserData,
+    open: impl FnOnce() -> Result<PassMan, FfiError> + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        let user_data = user_data;
+        let result = panic::catch_unwind(AssertUnwindSafe(open))
+            .unwrap_or_else(|_| Err(FfiError::new(PassManStatus::Panic, "internal error")));
+        match result {
+            Ok(passman) => {
+                let handle = Box::into_raw(Box::new(PassManHandle { passman }));
+                callback(PassManStatus::Ok, handle, ptr::null(), user_data.0);
+            }
+            Err(error) => {
+                let message = CString::new(error.message).unwrap_or_default();
+                callback(error.status, ptr::null_mut(), message.as_ptr(), user_data.0);
+            }
+        }
+    });
+}
+
+/// Close a vault, wipe its keys from memory, and release the handle
+/// 
+/// # Safety
+/// `handle` must be NULL or a handle from this library that hasn't been closed
+#[no_mangle]
+pub unsafe extern "C" fn passman_close_vault(handle: *mut PassManHandle) {
+    if !handle.is_null() {
+        let mut handle = Box::from_raw(handle);
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| handle.passman.close_vault()));
+    }
+}
+
+/// List all accounts as a JSON array
+/// 
+/// # Safety
+/// `handle` must be a live handle; `out_json` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_list_accounts(handle: *mut PassManHandle, out_json: *mut *mut c_char) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        write_json(out_json, &passman.get_all_accounts())
+    })
+}
+
+/// Get one account as a JSON object
+/// 
+/// # Safety
+/// `handle` must be a live handle; `id` must be NUL-terminated; `out_json`
+/// must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_get_account(
+    handle: *mut PassManHandle,
+    id: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        let id = read_id(id)?;
+        let account = passman.get_account(id)
+            .ok_or_else(|| PassManError::AccountNotFound(format!("Account with ID {} not found", id)))?;
+        write_json(out_json, account)
+    })
+}
+
+/// Add an account and save the vault
+/// 
+/// `account_json` is an object with `name`, `account_type` and `password`,
+/// and optionally `url`, `username`, `notes` and `tags`.
+/// 
+/// # Safety
+/// `handle` must be a live handle; `account_json` must be NUL-terminated;
+/// `out_id` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_add_account(
+    handle: *mut PassManHandle,
+    account_json: *const c_char,
+    out_id: *mut *mut c_char,
+) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        let input: AccountInput = read_json(account_json, "account_json")?;
+        if out_id.is_null() {
+            return Err(FfiError::new(PassManStatus::NullPointer, "out_id is NULL"));
+        }
+        
+        let mut account = Account::new(input.name, input.account_type, input.password);
+        account.url = input.url;
+        account.username = input.username;
+        account.notes = input.notes;
+        account.tags = input.tags;
+        let id = passman.add_account_entry(account)?;
+        write_string(out_id, id.to_string())
+    })
+}
+
+/// Replace an account's fields and save the vault
+/// 
+/// `account_json` has the same shape as for `passman_add_account`.
+/// 
+/// # Safety
+/// `handle` must be a live handle; `id` and `account_json` must be NUL-terminated
+#[no_mangle]
+pub unsafe extern "C" fn passman_update_account(
+    handle: *mut PassManHandle,
+    id: *const c_char,
+    account_json: *const c_char,
+) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        let id = read_id(id)?;
+        let input: AccountInput = read_json(account_json, "account_json")?;
+        passman.update_account(
+            id,
+            input.name,
+            input.account_type,
+            input.password,
+            input.url,
+            input.username,
+            input.notes,
+            input.tags,
+        )?;
+        Ok(())
+    })
+}
+
+/// Change some of an account's fields and save the vault
+/// 
+/// `patch_json` is an object with only the fields to change (see
+/// `AccountPatch`); `null` clears an optional field such as `url`.
+/// 
+/// # Safety
+/// `handle` must be a live handle; `id` and `patch_json` must be NUL-terminated
+#[no_mangle]
+pub unsafe extern "C" fn passman_patch_account(
+    handle: *mut PassManHandle,
+    id: *const c_char,
+    patch_json: *const c_char,
+) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        let id = read_id(id)?;
+        let patch: AccountPatch = read_json(patch_json, "patch_json")?;
+        passman.patch_account(id, patch)?;
+        Ok(())
+    })
+}
+
+/// Move an account to the trash and save the vault
+/// 
+/// # Safety
+/// `handle` must be a live handle; `id` must be NUL-terminated
+#[no_mangle]
+pub unsafe extern "C" fn passman_delete_account(handle: *mut PassManHandle, id: *const c_char) -> PassManStatus {
+    call(|| {
+        let passman = read_handle(handle)?;
+        passman.delete_account(read_id(id)?)?;
+        Ok(())
+    })
+}
+
+/// Generate a password (no vault needed)
+/// 
+/// `options_json` is a generator options object (`length`,
+/// `include_uppercase`, ...), or NULL for the defaults.
+/// 
+/// # Safety
+/// `options_json` must be NULL or NUL-terminated; `out_password` must be
+/// valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn passman_generate_password(
+    options_json: *const c_char,
+    out_password: *mut *mut c_char,
+) -> PassManStatus {
+    call(|| {
+        let options = if options_json.is_null() {
+            PasswordOptions::default()
+        } else {
+            read_json(options_json, "options_json")?
+        };
+        let password = Zeroizing::new(PasswordGenerator::new().generate(&options)?);
+        write_string(out_password, password.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+    
+    unsafe fn take_string(s: *mut c_char) -> String {
+        let value = CStr::from_ptr(s).to_str().unwrap().to_string();
+        passman_string_free(s);
+        value
+    }
+    
+    #[test]
+    fn test_vault_round_trip() {
+        passman_backend::testing::isolate();
+        let vault_name = format!("test_ffi_{}", Uuid::new_v4().simple());
+        let name = c(&vault_name);
+        unsafe {
+            let mut handle = ptr::null_mut();
+            assert_eq!(
+                passman_create_vault(name.as_ptr(), c("test@example.com").as_ptr(), c("master_password").as_ptr(), &mut handle),
+                PassManStatus::Ok
+            );
+            
+            let mut id = ptr::null_mut();
+            let account = c(r#"{"name": "Mail", "account_type": "Email", "password": "pw", "tags": ["work"]}"#);
+            assert_eq!(passman_add_account(handle, account.as_ptr(), &mut id), PassManStatus::Ok);
+            let id = c(&take_string(id));
+            passman_close_vault(handle);
+            
+            let mut handle = ptr::null_mut();
+            assert_eq!(passman_open_vault(name.as_ptr(), c("wrong").as_ptr(), &mut handle), PassManStatus::AuthenticationFailed);
+            assert!(!take_string(passman_last_error_message()).is_empty());
+            assert_eq!(passman_open_vault(name.as_ptr(), c("master_password").as_ptr(), &mut handle), PassManStatus::Ok);
+            assert!(passman_last_error_message().is_null());
+            
+            let update = c(r#"{"name": "Mail", "account_type": "Email", "password": "new"}"#);
+            assert_eq!(passman_update_account(handle, id.as_ptr(), update.as_ptr()), PassManStatus::Ok);
+            let mut json = ptr::null_mut();
+            assert_eq!(passman_get_account(handle, id.as_ptr(), &mut json), PassManStatus::Ok);
+            let account: Account = serde_json::from_str(&take_string(json)).unwrap();
+            assert_eq!(account.password, "new");
+            
+            let patch = c(r#"{"username": "me", "tags": []}"#);
+            assert_eq!(passman_patch_account(handle, id.as_ptr(), patch.as_ptr()), PassManStatus::Ok);
+            assert_eq!(passman_get_account(handle, id.as_ptr(), &mut json), PassManStatus::Ok);
+            let account: Account = serde_json::from_str(&take_string(json)).unwrap();
+            assert_eq!((account.username.as_deref(), account.password.as_str()), (Some("me"), "new"));
+            assert!(account.tags.is_empty());
+            
+            assert_eq!(passman_delete_account(handle, id.as_ptr()), PassManStatus::Ok);
+            assert_eq!(passman_list_accounts(handle, &mut json), PassManStatus::Ok);
+            assert_eq!(take_string(json), "[]");
+            assert_eq!(passman_delete_account(handle, id.as_ptr()), PassManStatus::AccountNotFound);
+            assert_eq!(passman_list_accounts(handle, ptr::null_mut()), PassManStatus::NullPointer);
+            passman_close_vault(handle);
+        }
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_open_vault_async() {
+        use std::sync::mpsc;
+        
+        type Done = (PassManStatus, usize, bool);
+        
+        extern "C" fn done(status: PassManStatus, handle: *mut PassManHandle, message: *const c_char, user_data: *mut c_void) {
+            let sender = unsafe { &*(user_data as *const mpsc::Sender<Done>) };
+            sender.send((status, handle as usize, !message.is_null())).unwrap();
+        }
+        
+        passman_backend::testing::isolate();
+        let vault_name = format!("test_ffi_async_{}", Uuid::new_v4().simple());
+        let name = c(&vault_name);
+        let (sender, receiver) = mpsc::channel::<Done>();
+        let user_data = &sender as *const mpsc::Sender<Done> as *mut c_void;
+        unsafe {
+            let status = passman_create_vault_async(name.as_ptr(), c("test@example.com").as_ptr(), c("master_password").as_ptr(), Some(done), user_data);
+            assert_eq!(status, PassManStatus::Ok);
+            let (status, handle, has_message) = receiver.recv().unwrap();
+            assert_eq!((status, has_message), (PassManStatus::Ok, false));
+            passman_close_vault(handle as *mut PassManHandle);
+            
+            assert_eq!(passman_open_vault_async(name.as_ptr(), c("wrong").as_ptr(), Some(done), user_data), PassManStatus::Ok);
+            assert_eq!(receiver.recv().unwrap(), (PassManStatus::AuthenticationFailed, 0, true));
+            
+            assert_eq!(passman_open_vault_async(name.as_ptr(), ptr::null(), Some(done), user_data), PassManStatus::NullPointer);
+            assert_eq!(passman_open_vault_async(name.as_ptr(), c("master_password").as_ptr(), None, user_data), PassManStatus::NullPointer);
+        }
+        PassMan::delete_vault(&vault_name).unwrap();
+    }
+    
+    #[test]
+    fn test_generate_password() {
+        unsafe {
+            let mut password = ptr::null_mut();
+            assert_eq!(passman_generate_password(ptr::null(), &mut password), PassManStatus::Ok);
+            assert_eq!(take_string(password).len(), PasswordOptions::default().length);
+            
+            let options = c(r#"{"length": 0}"#);
+            assert_eq!(passman_generate_password(options.as_ptr(), &mut password), PassManStatus::InvalidJson);
+        }
+    }
+}